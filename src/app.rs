@@ -1,19 +1,71 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use yaml_rust2::Yaml;
 
+use crate::background::Task;
 use crate::clipboard;
+use crate::diff::{self, DiffAnnotations};
+use crate::history::History;
+use crate::hooks::HookResult;
 use crate::input::{InputAction, InputContext, VimInputHandler};
-use crate::search::{next_match, prev_match};
+use crate::interpolate::Placeholder;
+use crate::journal::Journal;
+use crate::json_patch::{self, PatchOp};
+use crate::jump_list::JumpList;
+use crate::line_diff::DiffLine;
+use crate::lint::LintFinding;
+use crate::lock::{self, Acquire, FileLock, LockInfo};
+use crate::markdown;
+use crate::recent;
+use crate::roundtrip::DocumentReport;
+use crate::schema::Schema;
+use crate::search::{self, matches_row, next_match, prev_match, CaseMode, Query, SearchScope};
+use crate::settings::{self, CliOverrides, Settings};
+use crate::widgets::TextArea;
+use crate::width;
 use crate::yaml_model::{
-    flatten_visible, parse_scalar_input, visible_row_by_path, NodePath, NodeType, TreeNode,
-    VisibleRow, YamlModel,
+    flatten_visible, resolve_dot_path, sibling_row, visible_row_by_path, AliasEdit, NodePath,
+    ParseError, PathSegment, SaveReport, SharedNode, TreeNode, VisibleRow, YamlModel,
 };
 
+mod completion;
+#[cfg(unix)]
+mod control;
+mod edit;
+mod export;
+mod fold;
+mod goto;
+mod hooks;
+mod integrity;
+mod interpolate;
+mod lint;
+mod live_search;
+mod marks;
+mod merge;
+mod picker;
+mod pipe;
+mod raw;
+mod replace;
+mod view;
+
+pub use goto::MAX_SHOWN_CANDIDATES;
+use live_search::SearchStash;
+pub use merge::{side_preview, DiskChange, MergeState, Pick};
+use picker::list_picker_entries;
+pub use picker::{FilePickerState, PickerEntry, PickerSort};
+pub use replace::ReplaceState;
+use view::ViewState;
+
+/// Bytes of a long string the Details pane shows at first and adds per Enter.
+pub const DETAILS_CHUNK_BYTES: usize = 4096;
+/// Columns `zl`/`zh` scroll the tree by.
+const H_SCROLL_STEP: usize = 8;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Mode {
     Normal,
@@ -25,9 +77,73 @@ pub enum Mode {
     ConfirmQuit,
     ConfirmOpenAnother,
     ConfirmRawDeleteLine,
+    /// Renaming the `<<` merge key would stop the merge; ask first.
+    ConfirmRenameMerge,
     SearchInput,
     /// Editing a line in raw view (parse error).
     RawEditLine,
+    /// Key for a yanked node being pasted into a mapping.
+    PasteKey,
+    /// Saving would make the collapse of duplicate keys permanent; ask first.
+    ConfirmSaveDuplicates,
+    /// Overlay listing duplicate keys found on load.
+    DuplicateList,
+    /// Overlay listing effective settings and where each came from.
+    SettingsOverlay,
+    /// Another live instance holds the file's lock: open read-only, open anyway, or quit.
+    ConfirmLocked,
+    /// Popup text area for editing a string across several lines.
+    EditMultiline,
+    /// The selected value is shared through an anchor: edit it everywhere or break the alias.
+    ConfirmAlias,
+    /// Overlay listing this session's edits, newest first.
+    JournalOverlay,
+    /// Overlay listing what the last post-save hook reported.
+    HookFindings,
+    /// First edit of a file that saving would alter beyond the edits; ask first.
+    ConfirmLossy,
+    /// Path to write the document to (Save As).
+    SaveAsPath,
+    /// Save As target already exists; ask before replacing it.
+    ConfirmOverwrite,
+    /// Overlay resolving conflicts between changes on disk and unsaved edits.
+    MergeConflicts,
+    /// Custom value for the selected merge conflict.
+    MergeValue,
+    /// Name of a file to create from the file picker.
+    NewFileName,
+    /// Path to write a JSON or TOML export to.
+    ExportPath,
+    /// Export target already exists; ask before replacing it.
+    ConfirmExportOverwrite,
+    /// Field a sequence of mappings is keyed by, or a mapping's keys are put back under.
+    RekeyField,
+    /// New name for every key named like the selected one.
+    RenameKeyEverywhere,
+    /// The file changed on disk while there are unsaved edits: merge, reload, keep or diff.
+    ConfirmDiskChange,
+    /// Overlay listing where the file on disk differs from the buffer.
+    DiskDiff,
+    /// Overlay listing lint findings (tabs, trailing whitespace, long lines, ...).
+    LintFindings,
+    /// Overlay listing the `${VAR}` placeholders in the document and whether each is set.
+    Placeholders,
+    /// Overlay listing the marks set with `m` + letter.
+    MarkList,
+    /// Dot path of a node to jump to.
+    GotoPath,
+    /// Overlay summarizing the document's size: nodes, leaves, depth, types.
+    StatsOverlay,
+    /// Every key binding by category (`?`), scrolled with j/k.
+    HelpOverlay,
+    /// Unified line diff of what saving would write against the file on disk.
+    BufferDiff,
+    /// Find-and-replace command (`%s/old/new/flags`).
+    ReplaceCommand,
+    /// Replace the highlighted match? Asked per match with the `c` flag.
+    ConfirmReplace,
+    /// Shell command the selected subtree is piped through.
+    PipeCommand,
 }
 
 #[derive(Clone, Debug)]
@@ -50,35 +166,42 @@ impl InputLine {
     }
 
     pub fn insert_char(&mut self, ch: char) {
+        self.snap_cursor();
         self.text.insert(self.cursor, ch);
         self.cursor += ch.len_utf8();
     }
 
     pub fn backspace(&mut self) {
+        self.snap_cursor();
         if self.cursor == 0 {
             return;
         }
-        self.cursor -= 1;
-        self.text.remove(self.cursor);
+        let prev = width::prev_grapheme_boundary(&self.text, self.cursor);
+        self.text.replace_range(prev..self.cursor, "");
+        self.cursor = prev;
     }
 
     pub fn delete(&mut self) {
+        self.snap_cursor();
         if self.cursor >= self.text.len() {
             return;
         }
-        self.text.remove(self.cursor);
+        let next = width::next_grapheme_boundary(&self.text, self.cursor);
+        self.text.replace_range(self.cursor..next, "");
+    }
+
+    /// Move a cursor set inside a character or grapheme back to its start, so edits at it
+    /// never split one.
+    fn snap_cursor(&mut self) {
+        self.cursor = width::floor_grapheme_boundary(&self.text, self.cursor);
     }
 
     pub fn move_left(&mut self) {
-        if self.cursor > 0 {
-            self.cursor -= 1;
-        }
+        self.cursor = width::prev_grapheme_boundary(&self.text, self.cursor);
     }
 
     pub fn move_right(&mut self) {
-        if self.cursor < self.text.len() {
-            self.cursor += 1;
-        }
+        self.cursor = width::next_grapheme_boundary(&self.text, self.cursor);
     }
 
     pub fn move_home(&mut self) {
@@ -96,6 +219,8 @@ pub struct Toast {
     pub expires_at: Instant,
 }
 
+/// Screen position of a rendered row; `key_x_start..=key_x_end` are the columns
+/// covered by the row's key (or label), measured in terminal cells.
 #[derive(Clone, Debug)]
 pub struct RowHit {
     pub row_index: usize,
@@ -104,17 +229,28 @@ pub struct RowHit {
     pub key_x_end: u16,
 }
 
-#[derive(Clone, Debug)]
-pub enum PickerEntry {
-    Parent,
-    Dir(PathBuf),
-    File(PathBuf),
+impl RowHit {
+    /// Whether screen column `x` falls on the row's key.
+    pub fn on_key(&self, x: u16) -> bool {
+        (self.key_x_start..=self.key_x_end).contains(&x)
+    }
 }
 
+/// Node copied with `yy`, independent of the document it came from.
 #[derive(Clone, Debug)]
-pub struct FilePickerState {
-    pub current_dir: PathBuf,
-    pub entries: Vec<PickerEntry>,
+pub struct YankRegister {
+    /// Mapping key the node was yanked from, used as the default key when pasting into a map.
+    pub key: Option<String>,
+    pub value: Yaml,
+}
+
+/// Reference document loaded with `--baseline`; never edited or saved.
+pub struct Baseline {
+    pub path: PathBuf,
+    pub doc: Yaml,
+    pub annotations: DiffAnnotations,
+    /// Whether the Details pane expands the "missing vs baseline" list.
+    pub show_missing: bool,
 }
 
 pub struct App {
@@ -122,55 +258,165 @@ pub struct App {
     pub mode: Mode,
     pub selection: usize,
     pub scroll: usize,
+    /// Columns the tree rows are scrolled to the left by (`zl`/`zh`); `0` scrolls back.
+    pub h_scroll: usize,
+    /// Whether sequence items show their `[n]` index before the label; starts from
+    /// `tree.show_indices`, toggled with `#`.
+    pub show_indices: bool,
     pub expanded: HashSet<String>,
+    /// `expanded` from before `zf` folded everything outside the selected subtree.
+    pub focus_stash: Option<HashSet<String>>,
     pub visible: Vec<VisibleRow>,
     pub tree_root: TreeNode,
     pub hit_map: Vec<RowHit>,
     pub dirty: bool,
     pub toast: Option<Toast>,
     pub input: InputLine,
+    /// Buffer of the multi-line popup editor.
+    pub textarea: TextArea,
     pub pending_key: Option<String>,
-    pub search_query: Option<String>,
+    /// Container and sibling an added key or item goes after (`o`); `None` adds a child.
+    pub add_after: Option<(NodePath, PathSegment)>,
+    /// Schema suggestion highlighted while typing a new key.
+    pub completion: Option<String>,
+    /// Path and current text of a value waiting to be opened in `$EDITOR` (Ctrl+e).
+    pub external_edit: Option<(NodePath, String)>,
+    pub search_query: Option<Query>,
+    /// Whether the search looks at keys, values or both; cycled with Ctrl+k or set by a
+    /// `k:`/`v:` prefix.
+    pub search_scope: SearchScope,
+    /// Case handling for the next query; starts from `search.case`, cycled with Alt+c.
+    pub search_case: CaseMode,
+    /// The search was started with `?`: Enter selects the last match and `n`/`N` swap.
+    pub search_backward: bool,
+    /// Committed queries, recalled with Up/Down in the search prompt.
+    pub search_history: History,
+    /// View from before the search being typed, restored by Esc.
+    pub search_stash: Option<SearchStash>,
+    /// When to rerun the search being typed in a large document; `None` when up to date.
+    pub search_due: Option<Instant>,
+    /// Nodes remembered with `m` + letter, by path so they survive rebuilding the tree.
+    pub marks: HashMap<char, NodePath>,
+    /// Where long moves started, for `Ctrl+o`/`Ctrl+i`.
+    pub jumps: JumpList,
     pub matches: Vec<usize>,
     pub vim: VimInputHandler,
+    /// Internal register filled by `yy` and consumed by `p` (separate from the OS clipboard).
+    pub register: Option<YankRegister>,
+    pub baseline: Option<Baseline>,
     pub file_picker: Option<FilePickerState>,
     /// After right-click, ignore 'a'/'r' for a short time (terminal often pastes on right-click).
     pub right_click_ignore_until: Option<Instant>,
     /// Row index under mouse (for hover highlight).
     pub hover_row: Option<usize>,
     /// Parse error when YAML is invalid (file still opened with empty doc).
-    pub parse_error: Option<String>,
+    pub parse_error: Option<ParseError>,
     /// Raw file content when parse failed (so user can edit and fix).
     pub raw_content: Option<String>,
     /// File mtime when loaded (for external change detection).
     pub last_modified: Option<std::time::SystemTime>,
     /// Last time we checked file on disk (for throttling).
     pub last_file_check: Option<Instant>,
+    /// Highlighted entry in list overlays (e.g. duplicate keys).
+    pub list_selection: usize,
+    /// User agreed to save even though duplicate keys were collapsed on load.
+    pub duplicates_acknowledged: bool,
+    /// Command-line settings, reapplied whenever settings are rediscovered for another file.
+    pub cli: CliOverrides,
+    pub settings: Settings,
+    pub schema: Option<Schema>,
+    /// Why the file is read-only (e.g. the readonly glob it matched); edits and saves are refused.
+    pub read_only: Option<String>,
+    /// Advisory lock on the open file; released when dropped.
+    pub lock: Option<FileLock>,
+    /// Owner of the lock that stopped us from taking it.
+    pub lock_holder: Option<LockInfo>,
+    /// Edit waiting on the shared/break choice for an anchored value.
+    pub pending_shared_edit: Option<(InputAction, SharedNode)>,
+    /// Choice made for the edit in progress on an anchored value.
+    pub shared_edit: Option<(SharedNode, AliasEdit)>,
+    /// Edits made to the open file this session; also the undo stack.
+    pub journal: Journal,
+    /// Post-save hook still running.
+    pub hook_task: Option<Task<HookResult>>,
+    /// Outcome of the last post-save hook for the open file.
+    pub hook_result: Option<HookResult>,
+    /// Bytes of a long value loaded into the Details pane, for the node it was loaded for.
+    pub details_loaded: Option<(NodePath, usize)>,
+    /// Startup check that saving would reproduce the loaded file, still running.
+    pub integrity_task: Option<Task<Option<DocumentReport>>>,
+    /// What saving would change beyond the user's edits, if anything.
+    pub lossy: Option<DocumentReport>,
+    /// User agreed to edit although saving is lossy.
+    pub lossy_acknowledged: bool,
+    /// Edit waiting on the lossy-save confirmation.
+    pub pending_lossy_edit: Option<InputAction>,
+    /// Existing file Save As will replace once confirmed.
+    pub pending_save_as: Option<PathBuf>,
+    /// Merge of changes on disk waiting on conflict resolution.
+    pub merge: Option<MergeState>,
+    /// Changes on disk waiting on `Mode::ConfirmDiskChange`.
+    pub disk_change: Option<DiskChange>,
+    /// Lines of the `Mode::BufferDiff` overlay.
+    pub buffer_diff: Vec<DiffLine>,
+    /// Lint findings for the file as last loaded or saved.
+    pub lint: Vec<LintFinding>,
+    /// Show the selected value with `${VAR}` references resolved (`$`).
+    pub env_preview: bool,
+    /// Rows of the `Mode::Placeholders` overlay.
+    pub placeholders: Vec<Placeholder>,
+    /// Find and replace stepping through its matches.
+    pub replace_state: Option<ReplaceState>,
+    /// Exported JSON or TOML, waiting on a destination path.
+    pub export: Option<String>,
+    /// Existing file an export will replace once confirmed.
+    pub pending_export: Option<PathBuf>,
 }
 
 impl App {
-    pub fn new(path: &Path) -> Result<Self> {
+    pub fn new(path: &Path, cli: CliOverrides) -> Result<Self> {
         let (model, parse_error, raw_content) = YamlModel::load_with_error(path)?;
         let mut expanded = HashSet::new();
         expanded.insert(String::new());
         let tree_root = model.build_tree();
         let visible = flatten_visible(&tree_root, &expanded, None);
-        Ok(Self {
+        let mut app = Self {
             model,
             mode: Mode::Normal,
             selection: 0,
             scroll: 0,
+            h_scroll: 0,
+            show_indices: false,
             expanded,
+            focus_stash: None,
             visible,
             tree_root,
             hit_map: Vec::new(),
             dirty: false,
             toast: None,
             input: InputLine::new(),
+            textarea: TextArea::new(""),
             pending_key: None,
+            add_after: None,
+            completion: None,
+            external_edit: None,
             search_query: None,
+            search_scope: SearchScope::default(),
+            search_backward: false,
+            search_case: CaseMode::default(),
+            search_history: cli
+                .search_history
+                .as_deref()
+                .map(History::load)
+                .unwrap_or_default(),
+            search_stash: None,
+            search_due: None,
+            marks: HashMap::new(),
+            jumps: JumpList::default(),
             matches: Vec::new(),
             vim: VimInputHandler::new(),
+            register: None,
+            baseline: None,
             file_picker: None,
             right_click_ignore_until: None,
             hover_row: None,
@@ -178,37 +424,102 @@ impl App {
             raw_content,
             last_modified: std::fs::metadata(path).and_then(|m| m.modified()).ok(),
             last_file_check: None,
-        })
+            list_selection: 0,
+            duplicates_acknowledged: false,
+            settings: Settings::defaults(Path::new(".")),
+            cli,
+            schema: None,
+            read_only: None,
+            lock: None,
+            lock_holder: None,
+            pending_shared_edit: None,
+            shared_edit: None,
+            journal: Journal::default(),
+            hook_task: None,
+            hook_result: None,
+            details_loaded: None,
+            integrity_task: None,
+            lossy: None,
+            lossy_acknowledged: false,
+            pending_lossy_edit: None,
+            pending_save_as: None,
+            merge: None,
+            disk_change: None,
+            buffer_diff: Vec::new(),
+            lint: Vec::new(),
+            env_preview: false,
+            placeholders: Vec::new(),
+            replace_state: None,
+            export: None,
+            pending_export: None,
+        };
+        app.apply_settings(path);
+        app.acquire_lock(path);
+        app.announce_duplicates();
+        app.select_parse_error();
+        app.start_integrity_check();
+        app.refresh_lint();
+        app.remember_recent(path);
+        if let Some(depth) = app.cli.depth {
+            app.expand_to_depth(depth);
+        }
+        app.restore_position(path);
+        Ok(app)
     }
 
     /// Create app in file picker mode (no file loaded). Lists current dir with .., subdirs, .yaml/.yml.
-    pub fn new_for_picker() -> Result<Self> {
+    pub fn new_for_picker(cli: CliOverrides) -> Result<Self> {
         let model = YamlModel::empty();
         let mut expanded = HashSet::new();
         expanded.insert(String::new());
         let tree_root = model.build_tree();
         let visible = flatten_visible(&tree_root, &expanded, None);
         let current_dir = std::env::current_dir()?;
-        let entries = list_picker_entries(&current_dir)?;
-        Ok(Self {
+        let (settings, settings_error) = load_settings(&current_dir, &cli);
+        let recent = cli.recent.as_deref().map(recent::load).unwrap_or_default();
+        let entries = list_picker_entries(&current_dir, &settings, PickerSort::default(), &recent)?;
+        let mut app = Self {
             model,
             mode: Mode::Normal,
             selection: 0,
             scroll: 0,
+            h_scroll: 0,
+            show_indices: settings.show_indices.value,
             expanded,
+            focus_stash: None,
             visible,
             tree_root,
             hit_map: Vec::new(),
             dirty: false,
             toast: None,
             input: InputLine::new(),
+            textarea: TextArea::new(""),
             pending_key: None,
+            add_after: None,
+            completion: None,
+            external_edit: None,
             search_query: None,
+            search_scope: SearchScope::default(),
+            search_backward: false,
+            search_case: settings.search_case.value,
+            search_history: cli
+                .search_history
+                .as_deref()
+                .map(History::load)
+                .unwrap_or_default(),
+            search_stash: None,
+            search_due: None,
+            marks: HashMap::new(),
+            jumps: JumpList::default(),
             matches: Vec::new(),
             vim: VimInputHandler::new(),
+            register: None,
+            baseline: None,
             file_picker: Some(FilePickerState {
                 current_dir,
                 entries,
+                sort: PickerSort::default(),
+                recent,
             }),
             right_click_ignore_until: None,
             hover_row: None,
@@ -216,84 +527,121 @@ impl App {
             raw_content: None,
             last_modified: None,
             last_file_check: None,
-        })
+            list_selection: 0,
+            duplicates_acknowledged: false,
+            cli,
+            settings,
+            schema: None,
+            read_only: None,
+            lock: None,
+            lock_holder: None,
+            pending_shared_edit: None,
+            shared_edit: None,
+            journal: Journal::default(),
+            hook_task: None,
+            hook_result: None,
+            details_loaded: None,
+            integrity_task: None,
+            lossy: None,
+            lossy_acknowledged: false,
+            pending_lossy_edit: None,
+            pending_save_as: None,
+            merge: None,
+            disk_change: None,
+            buffer_diff: Vec::new(),
+            lint: Vec::new(),
+            env_preview: false,
+            placeholders: Vec::new(),
+            replace_state: None,
+            export: None,
+            pending_export: None,
+        };
+        if let Some(err) = settings_error {
+            app.set_toast(err);
+        }
+        Ok(app)
     }
 
-    /// In file picker: enter selected item (change dir or open file). Returns true if dir was changed (refresh UI).
-    pub fn picker_enter_selected(&mut self) -> Result<bool> {
-        let picker = match &self.file_picker {
-            Some(p) => p.clone(),
-            None => return Ok(false),
-        };
-        let entry = match picker.entries.get(self.selection) {
-            Some(e) => e.clone(),
-            None => return Ok(false),
-        };
-        match entry {
-            PickerEntry::Parent => {
-                if let Some(parent) = picker.current_dir.parent() {
-                    let parent = parent.to_path_buf();
-                    std::env::set_current_dir(&parent)?;
-                    let entries = list_picker_entries(&parent)?;
-                    if let Some(ref mut fp) = self.file_picker {
-                        fp.current_dir = parent;
-                        fp.entries = entries;
-                    }
-                    self.selection = 0;
-                    return Ok(true);
-                }
+    /// Discover the settings for `path`, then apply layout, schema and read-only rules.
+    fn apply_settings(&mut self, path: &Path) {
+        let file = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let dir = file
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        let (settings, settings_error) = load_settings(&dir, &self.cli);
+        self.settings = settings;
+        self.search_case = self.settings.search_case.value;
+        self.model.configure(&self.settings);
+        self.read_only = self
+            .settings
+            .readonly_match(&file)
+            .map(|glob| format!("matches readonly '{glob}'"));
+        self.schema = None;
+        let mut problems: Vec<String> = settings_error.into_iter().collect();
+        if let Some(schema_path) = self.settings.schema.value.clone() {
+            match Schema::load(&schema_path) {
+                Ok(schema) => self.schema = Some(schema),
+                Err(err) => problems.push(format!("Schema: {err}")),
             }
-            PickerEntry::Dir(path) => {
-                if path.is_dir() {
-                    std::env::set_current_dir(&path)?;
-                    let entries = list_picker_entries(&path)?;
-                    if let Some(ref mut fp) = self.file_picker {
-                        fp.current_dir = path;
-                        fp.entries = entries;
-                    }
-                    self.selection = 0;
-                    return Ok(true);
+        }
+        self.rebuild_visible();
+        if !problems.is_empty() {
+            self.set_toast(problems.join("; "));
+        } else if let Some(reason) = &self.read_only {
+            self.set_toast(format!("Read-only: {reason}"));
+        }
+    }
+
+    /// Take the advisory lock for `path`. If another live instance holds it, default to
+    /// read-only and ask what to do.
+    fn acquire_lock(&mut self, path: &Path) {
+        self.lock = None;
+        self.lock_holder = None;
+        if self.read_only.is_some() {
+            return;
+        }
+        match lock::acquire(path) {
+            Ok(Acquire::Locked { lock, reclaimed }) => {
+                self.lock = Some(lock);
+                if let Some(stale) = reclaimed {
+                    self.set_toast(format!(
+                        "Reclaimed stale lock of pid {} (since {})",
+                        stale.pid,
+                        stale.since_label()
+                    ));
                 }
             }
-            PickerEntry::File(path) => {
-                if let Err(e) = self.open_file(path) {
-                    self.set_toast(e.to_string());
-                }
+            Ok(Acquire::Held(holder)) => {
+                self.read_only = Some(format!(
+                    "also being edited by pid {} on {} since {}",
+                    holder.pid,
+                    holder.host,
+                    holder.since_label()
+                ));
+                self.lock_holder = Some(holder);
+                self.mode = Mode::ConfirmLocked;
             }
+            // The lock is advisory; an unwritable directory must not stop editing.
+            Err(_) => {}
         }
-        Ok(false)
     }
 
-    /// Refresh file picker entries (e.g. after changing directory).
-    pub fn picker_refresh(&mut self) -> Result<()> {
-        if let Some(ref mut fp) = self.file_picker {
-            fp.entries = list_picker_entries(&fp.current_dir)?;
-            if self.selection >= fp.entries.len() {
-                self.selection = fp.entries.len().saturating_sub(1);
+    fn apply_lock_prompt_action(&mut self, action: InputAction) -> bool {
+        match action {
+            InputAction::Quit => return true,
+            InputAction::ConfirmYes => {
+                self.read_only = None;
+                self.mode = Mode::Normal;
+                if let Some(holder) = &self.lock_holder {
+                    let message = format!("Editing anyway; saves may clobber pid {}", holder.pid);
+                    self.set_toast(message);
+                }
             }
+            InputAction::ConfirmNo => self.mode = Mode::Normal,
+            _ => {}
         }
-        Ok(())
-    }
-
-    /// Switch from editor back to file picker (current file's directory).
-    pub fn switch_to_file_picker(&mut self) -> Result<()> {
-        let current_dir = if self.model.file_path().is_empty() {
-            std::env::current_dir()?
-        } else {
-            PathBuf::from(self.model.file_path())
-                .parent()
-                .map(PathBuf::from)
-                .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
-        };
-        let _ = std::env::set_current_dir(&current_dir);
-        let entries = list_picker_entries(&current_dir)?;
-        self.file_picker = Some(FilePickerState {
-            current_dir,
-            entries,
-        });
-        self.selection = 0;
-        self.mode = Mode::Normal;
-        Ok(())
+        false
     }
 
     /// Load a file and switch from file picker to editor.
@@ -309,6 +657,7 @@ impl App {
         self.expanded = expanded;
         self.selection = 0;
         self.scroll = 0;
+        self.h_scroll = 0;
         self.file_picker = None;
         self.hit_map = Vec::new();
         self.dirty = false;
@@ -316,84 +665,81 @@ impl App {
         self.toast = None;
         self.input.set(String::new());
         self.pending_key = None;
+        self.add_after = None;
         self.search_query = None;
+        self.search_backward = false;
+        self.search_stash = None;
+        self.search_due = None;
         self.matches = Vec::new();
+        self.lossy_acknowledged = false;
+        self.pending_lossy_edit = None;
         self.right_click_ignore_until = None;
         self.hover_row = None;
         self.parse_error = parse_error;
         self.raw_content = raw_content;
         self.last_modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
         self.last_file_check = None;
+        self.journal.clear();
+        self.hook_task = None;
+        self.hook_result = None;
+        self.details_loaded = None;
+        self.merge = None;
+        self.disk_change = None;
+        self.replace_state = None;
+        self.marks.clear();
+        self.jumps.clear();
+        self.focus_stash = None;
+        self.select_parse_error();
+        self.apply_settings(&path);
+        self.acquire_lock(&path);
+        self.refresh_baseline_diff();
+        self.announce_duplicates();
+        self.start_integrity_check();
+        self.refresh_lint();
+        self.remember_recent(&path);
+        if let Some(depth) = self.cli.depth {
+            self.expand_to_depth(depth);
+        }
+        self.restore_position(&path);
         Ok(())
     }
 
-    /// When parse failed, lines of the file for raw edit view.
-    pub fn raw_lines(&self) -> Option<Vec<String>> {
-        self.raw_content
-            .as_ref()
-            .map(|s| s.lines().map(String::from).collect::<Vec<_>>())
-    }
-
-    /// Replace line at index in raw_content (for raw edit).
-    pub fn raw_replace_line(&mut self, line_index: usize, new_line: &str) {
-        if let Some(ref mut raw) = self.raw_content {
-            let mut lines: Vec<String> = raw.lines().map(String::from).collect();
-            if line_index < lines.len() {
-                lines[line_index] = new_line.lines().next().unwrap_or("").to_string();
-                *raw = lines.join("\n");
-            }
-        }
+    /// Load a reference document and annotate the tree against it.
+    pub fn set_baseline(&mut self, path: &Path) -> Result<()> {
+        let doc = YamlModel::load(path)?.root().clone();
+        self.baseline = Some(Baseline {
+            path: path.to_path_buf(),
+            doc,
+            annotations: DiffAnnotations::default(),
+            show_missing: false,
+        });
+        self.refresh_baseline_diff();
+        Ok(())
     }
 
-    /// Remove line at index from raw_content (raw view: d or Shift+Del).
-    pub fn raw_delete_line(&mut self, line_index: usize) {
-        if let Some(ref mut raw) = self.raw_content {
-            let mut lines: Vec<String> = raw.lines().map(String::from).collect();
-            if line_index < lines.len() {
-                lines.remove(line_index);
-                *raw = lines.join("\n");
-                self.dirty = true;
-                if self.selection >= lines.len() && !lines.is_empty() {
-                    self.selection = lines.len() - 1;
-                } else if lines.is_empty() {
-                    self.selection = 0;
-                }
-            }
+    fn refresh_baseline_diff(&mut self) {
+        if let Some(baseline) = self.baseline.as_mut() {
+            baseline.annotations = diff::annotate(self.model.root(), &baseline.doc);
         }
     }
 
-    /// Save raw content to file and re-parse; clear parse_error if successful.
-    pub fn save_raw_and_reparse(&mut self) -> Result<()> {
-        let raw = match &self.raw_content {
-            Some(r) => r.clone(),
-            None => return Ok(()),
-        };
-        let path = PathBuf::from(self.model.file_path());
-        std::fs::write(&path, &raw)?;
-        let (model, parse_error, raw_content) = YamlModel::load_with_error(&path)?;
-        self.model = model;
-        self.parse_error = parse_error.clone();
-        self.raw_content = raw_content;
-        self.dirty = false;
-        if parse_error.is_none() {
-            let mut expanded = HashSet::new();
-            expanded.insert(String::new());
-            self.tree_root = self.model.build_tree();
-            self.visible = flatten_visible(&self.tree_root, &expanded, None);
-            self.selection = 0;
-            self.scroll = 0;
-            self.set_toast("Saved and parsed successfully".to_string());
-        } else {
-            self.set_toast("Saved; parse still has errors".to_string());
+    /// After loading, warn about keys the parser collapsed and require acknowledgement to save.
+    fn announce_duplicates(&mut self) {
+        self.duplicates_acknowledged = false;
+        self.list_selection = 0;
+        let count = self.model.duplicates().len();
+        if count > 0 {
+            self.set_toast(format!(
+                "{count} duplicate key(s) collapsed (last value kept). D: list"
+            ));
         }
-        Ok(())
     }
 
     pub fn is_file_picker(&self) -> bool {
         self.file_picker.is_some()
     }
 
-    /// If file was modified externally and we have no unsaved changes, reload from disk.
+    /// If file was modified externally, reload it from disk, or merge it into unsaved changes.
     pub fn check_and_reload_if_changed(&mut self) -> Result<()> {
         if self.file_picker.is_some() {
             return Ok(());
@@ -402,7 +748,8 @@ impl App {
         if path_str.is_empty() {
             return Ok(());
         }
-        if self.dirty {
+        // A merge takes over the screen; wait until nothing else is in progress.
+        if self.dirty && (self.mode != Mode::Normal || self.raw_content.is_some()) {
             return Ok(());
         }
         let now = Instant::now();
@@ -428,50 +775,71 @@ impl App {
             }
         }
         self.last_modified = Some(modified);
-        let (model, parse_error, raw_content) = YamlModel::load_with_error(&path)?;
+        if self.dirty {
+            return self.ask_disk_change(&path);
+        }
+        self.reload_from_disk(&path)
+    }
+
+    /// Replace the buffer with the file on disk, keeping folds and the selection where the
+    /// new document still has them.
+    fn reload_from_disk(&mut self, path: &Path) -> Result<()> {
+        let (model, parse_error, raw_content) = YamlModel::load_with_error(path)?;
+        let saved_in_session = self.model.saved_in_session();
         self.model = model;
+        self.model.set_saved_in_session(saved_in_session);
+        self.dirty = false;
+        self.journal.clear();
+        self.model.configure(&self.settings);
         self.parse_error = parse_error;
         self.raw_content = raw_content;
-        let mut expanded = HashSet::new();
-        expanded.insert(String::new());
-        self.tree_root = self.model.build_tree();
-        self.visible = flatten_visible(&self.tree_root, &expanded, None);
-        if self.raw_content.is_some() {
-            let len = self.raw_lines().map(|l| l.len()).unwrap_or(0);
-            if len > 0 && self.selection >= len {
-                self.selection = len - 1;
-            }
-        } else if self.selection >= self.visible.len() {
-            self.selection = self.visible.len().saturating_sub(1);
+        if self.parse_error.is_none() {
+            // Keep the fold state, minus what the new document no longer has.
+            let root = self.model.root();
+            self.expanded
+                .retain(|dot| resolve_dot_path(root, dot).is_some());
+        }
+        // The rows are still the old ones here, so the selection is kept by path.
+        self.rebuild_visible();
+        if self.parse_error.is_some() {
+            self.select_parse_error();
         }
         self.set_toast("File changed on disk, reloaded".to_string());
+        if !self.model.duplicates().is_empty() {
+            self.announce_duplicates();
+        }
+        self.start_integrity_check();
+        self.refresh_lint();
         Ok(())
     }
 
     pub fn rebuild_visible(&mut self) {
         let selected_path = self.save_selection_path();
         self.tree_root = self.model.build_tree();
+        self.refresh_baseline_diff();
         self.visible = flatten_visible(
             &self.tree_root,
             &self.expanded,
-            self.search_query.as_deref(),
+            self.search_query
+                .as_ref()
+                .map(|query| (query, self.search_scope)),
         );
         if let Some(query) = &self.search_query {
-            let lower = query.to_lowercase();
             self.matches = self
                 .visible
                 .iter()
                 .enumerate()
-                .filter_map(|(idx, row)| {
-                    if row.path.dot_path().to_lowercase().contains(&lower)
-                        || row.display_key.to_lowercase().contains(&lower)
-                    {
-                        Some(idx)
-                    } else {
-                        None
-                    }
-                })
+                .filter(|(_, row)| matches_row(row, query, self.search_scope))
+                .map(|(idx, _)| idx)
                 .collect();
+            if query.is_fuzzy() {
+                // Best match first; ties stay in document order.
+                let visible = &self.visible;
+                let scope = self.search_scope;
+                self.matches.sort_by_cached_key(|&idx| {
+                    std::cmp::Reverse(search::score_row(&visible[idx], query, scope))
+                });
+            }
         }
         if let Some(path) = selected_path {
             self.restore_selection(Some(path));
@@ -485,6 +853,13 @@ impl App {
         self.visible.get(self.selection)
     }
 
+    /// Pick up the results of finished background tasks; called once per frame.
+    pub fn poll_tasks(&mut self) {
+        self.poll_integrity_check();
+        self.poll_hook();
+        self.poll_live_search();
+    }
+
     pub fn update_hit_map(&mut self, hits: Vec<RowHit>) {
         self.hit_map = hits;
     }
@@ -503,27 +878,18 @@ impl App {
             }
         }
         self.right_click_ignore_until = None;
-        if let Some(ref picker) = self.file_picker {
-            match key.code {
-                KeyCode::Enter => {
-                    let _ = self.picker_enter_selected();
-                }
-                KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
-                KeyCode::Char('j') | KeyCode::Down => {
-                    let max_idx = picker.entries.len().saturating_sub(1);
-                    self.selection = (self.selection + 1).min(max_idx);
-                }
-                KeyCode::Char('k') | KeyCode::Up => {
-                    self.selection = self.selection.saturating_sub(1);
-                }
-                _ => {}
-            }
-            return Ok(false);
+        if self.file_picker.is_some() {
+            return Ok(self.picker_handle_key(key, area_height));
         }
         if let Some(action) = self.vim.handle_key(InputContext {
             mode: &self.mode,
             key,
         }) {
+            if action.takes_count() {
+                for _ in 1..self.vim.repeat() {
+                    self.apply_action(action.clone(), area_height)?;
+                }
+            }
             return self.apply_action(action, area_height);
         }
         Ok(false)
@@ -548,44 +914,23 @@ impl App {
                 Some(Instant::now() + Duration::from_millis(200));
             return Ok(false);
         }
-        if let Some(ref picker) = self.file_picker {
-            match mouse.kind {
-                MouseEventKind::ScrollUp => {
-                    self.selection = self.selection.saturating_sub(1);
-                }
-                MouseEventKind::ScrollDown => {
-                    let max_idx = picker.entries.len().saturating_sub(1);
-                    self.selection = (self.selection + 1).min(max_idx);
-                }
-                MouseEventKind::Down(MouseButton::Left) => {
-                    if let Some(hit) = self.hit_map.iter().find(|hit| hit.y == mouse.row) {
-                        if hit.row_index < picker.entries.len() {
-                            self.selection = hit.row_index;
-                            let _ = self.picker_enter_selected();
-                        }
-                    }
-                }
-                _ => {}
-            }
+        if self.file_picker.is_some() {
+            self.picker_handle_mouse(mouse);
             return Ok(false);
         }
         match mouse.kind {
-            MouseEventKind::ScrollUp => {
-                self.scroll = self.scroll.saturating_sub(1);
-                let max_scroll = self.visible_len().saturating_sub(area_height);
-                self.scroll = self.scroll.min(max_scroll);
-                self.clamp_selection(area_height);
-            }
-            MouseEventKind::ScrollDown => {
-                self.scroll = self.scroll.saturating_add(1);
-                let max_scroll = self.visible_len().saturating_sub(area_height);
-                self.scroll = self.scroll.min(max_scroll);
-                self.clamp_selection(area_height);
-            }
+            MouseEventKind::ScrollUp => self.update_view(area_height, |view| view.scroll_by(-1)),
+            MouseEventKind::ScrollDown => self.update_view(area_height, |view| view.scroll_by(1)),
             MouseEventKind::Down(MouseButton::Left) => {
                 if let Some(hit) = self.hit_map.iter().find(|hit| hit.y == mouse.row) {
-                    self.selection = hit.row_index;
-                    if self.raw_content.is_none() {
+                    let row_index = hit.row_index;
+                    let on_key = hit.on_key(mouse.column);
+                    if row_index.abs_diff(self.selection) > area_height / 2 {
+                        self.record_jump();
+                    }
+                    self.selection = row_index;
+                    // Only a click on the key folds; the rest of the row just selects.
+                    if on_key && self.raw_content.is_none() {
                         let row_data = self.current_row().map(|r| (r.is_container, r.path.dot_path()));
                         if let Some((is_container, dot_path)) = row_data {
                             if is_container {
@@ -606,67 +951,185 @@ impl App {
     }
 
     pub fn apply_action(&mut self, action: InputAction, area_height: usize) -> Result<bool> {
-        let in_raw_mode = self.raw_content.is_some();
+        if self.mode == Mode::DuplicateList {
+            self.apply_duplicate_list_action(action, area_height);
+            return Ok(false);
+        }
+        if self.mode == Mode::EditMultiline {
+            self.apply_multiline_action(action)?;
+            return Ok(false);
+        }
+        if self.mode == Mode::ConfirmLocked {
+            return Ok(self.apply_lock_prompt_action(action));
+        }
+        if self.mode == Mode::ConfirmAlias {
+            return self.apply_alias_prompt_action(action, area_height);
+        }
+        if self.mode == Mode::ConfirmLossy {
+            return self.apply_lossy_prompt_action(action, area_height);
+        }
+        if self.mode == Mode::MergeConflicts {
+            self.apply_merge_action(action);
+            return Ok(false);
+        }
+        if self.mode == Mode::ConfirmReplace {
+            self.apply_replace_action(action);
+            return Ok(false);
+        }
+        if self.mode == Mode::BufferDiff {
+            self.apply_buffer_diff_action(action);
+            return Ok(false);
+        }
+        if self.mode == Mode::LintFindings {
+            self.apply_lint_action(action, area_height);
+            return Ok(false);
+        }
+        if self.mode == Mode::Placeholders {
+            self.apply_placeholders_action(action, area_height);
+            return Ok(false);
+        }
+        if self.mode == Mode::MarkList {
+            self.apply_marks_action(action, area_height);
+            return Ok(false);
+        }
+        if matches!(self.mode, Mode::ConfirmDiskChange | Mode::DiskDiff) {
+            self.apply_disk_change_action(action)?;
+            return Ok(false);
+        }
+        if self.mode == Mode::JournalOverlay {
+            self.apply_journal_action(action, area_height);
+            return Ok(false);
+        }
+        if self.mode == Mode::HookFindings {
+            self.apply_findings_action(action, area_height);
+            return Ok(false);
+        }
+        if self.mode == Mode::HelpOverlay {
+            self.apply_help_action(action, area_height);
+            return Ok(false);
+        }
+        if matches!(self.mode, Mode::SettingsOverlay | Mode::StatsOverlay) {
+            if matches!(action, InputAction::Cancel | InputAction::InputCommit) {
+                self.mode = Mode::Normal;
+            }
+            return Ok(false);
+        }
+        if action.is_edit() {
+            if let Some(reason) = &self.read_only {
+                self.set_toast(format!("Read-only: {reason}"));
+                return Ok(false);
+            }
+            if self.prompt_for_lossy_edit(&action) {
+                return Ok(false);
+            }
+        }
+        if self.raw_content.is_some() {
+            if self.apply_raw_action(&action)? {
+                self.ensure_visible(area_height);
+                return Ok(false);
+            }
+        } else if self.prompt_for_shared_edit(&action) {
+            return Ok(false);
+        }
+        let search_text_changed = self.changes_search_text(&action);
         match action {
             InputAction::Quit => return self.request_quit(),
             InputAction::Save => {
-                if in_raw_mode {
-                    self.save_raw_and_reparse()?;
+                if !self.model.duplicates().is_empty() && !self.duplicates_acknowledged {
+                    self.mode = Mode::ConfirmSaveDuplicates;
                 } else {
                     self.save()?;
                 }
             }
-            InputAction::MoveUp => self.move_selection(area_height, -1),
-            InputAction::MoveDown => self.move_selection(area_height, 1),
-            InputAction::JumpTop => self.jump_top(area_height),
-            InputAction::JumpBottom => self.jump_bottom(area_height),
-            InputAction::PageUp => self.page_scroll(area_height, -(area_height as isize / 2)),
-            InputAction::PageDown => self.page_scroll(area_height, area_height as isize / 2),
-            InputAction::JumpLeft => self.scroll = 0,
-            InputAction::Collapse => self.collapse_selected(),
-            InputAction::Expand => self.expand_selected(),
-            InputAction::ToggleExpand => self.toggle_expand(),
-            InputAction::EditValue => {
-                if in_raw_mode {
-                    self.start_raw_edit_line()?;
-                } else {
-                    self.start_edit_value()?;
-                }
+            InputAction::SaveAs => self.start_save_as(),
+            InputAction::MoveUp => self.update_view(area_height, |view| view.move_by(-1)),
+            InputAction::MoveDown => self.update_view(area_height, |view| view.move_by(1)),
+            InputAction::JumpTop => {
+                self.record_jump();
+                self.update_view(area_height, ViewState::top);
             }
-            InputAction::RenameKey => {
-                if self.raw_content.is_some() {
-                    self.set_toast("Key rename: fix parse errors or save to use tree view".to_string());
-                } else {
-                    self.start_rename_key()?;
-                }
+            InputAction::JumpBottom => {
+                self.record_jump();
+                self.update_view(area_height, ViewState::bottom);
             }
-            InputAction::AddChild => {
-                if self.raw_content.is_some() {
-                    self.set_toast("Add child: fix parse errors or save to use tree view".to_string());
-                } else {
-                    self.start_add_child()?;
-                }
+            InputAction::JumpBack => self.walk_jumps(false, area_height),
+            InputAction::JumpForward => self.walk_jumps(true, area_height),
+            InputAction::PageUp => {
+                self.update_view(area_height, |view| {
+                    view.page_by(-(area_height as isize / 2))
+                });
             }
-            InputAction::AddMapToSequence => {
-                if self.raw_content.is_some() {
-                    self.set_toast("Add object: fix parse errors or save to use tree view".to_string());
-                } else {
-                    self.start_add_map_to_sequence()?;
-                }
+            InputAction::PageDown => {
+                self.update_view(area_height, |view| view.page_by(area_height as isize / 2));
             }
-            InputAction::DeleteNode => {
-                if in_raw_mode {
-                    self.mode = Mode::ConfirmRawDeleteLine;
-                } else {
-                    self.start_delete_node()?;
-                }
+            InputAction::FullPageUp => self.update_view(area_height, |view| view.full_page(false)),
+            InputAction::FullPageDown => self.update_view(area_height, |view| view.full_page(true)),
+            InputAction::JumpLeft => {
+                self.scroll = 0;
+                self.h_scroll = 0;
             }
-            InputAction::DeleteLine => {
-                if in_raw_mode {
-                    self.mode = Mode::ConfirmRawDeleteLine;
-                }
+            InputAction::NextSibling => self.jump_sibling(true),
+            InputAction::PrevSibling => self.jump_sibling(false),
+            InputAction::ExpandAll => self.expand_all(),
+            InputAction::CollapseAll => self.fold_to_depth(1, area_height),
+            InputAction::ExpandToDepth(depth) => self.fold_to_depth(depth, area_height),
+            InputAction::ToggleFocus => self.toggle_focus(area_height),
+            InputAction::ScreenTop => self.update_view(area_height, ViewState::screen_top),
+            InputAction::ScreenMiddle => self.update_view(area_height, ViewState::screen_middle),
+            InputAction::ScreenBottom => self.update_view(area_height, ViewState::screen_bottom),
+            InputAction::CenterSelection => self.update_view(area_height, ViewState::center),
+            InputAction::SelectionToTop => {
+                self.update_view(area_height, ViewState::selection_to_top)
             }
+            InputAction::SelectionToBottom => {
+                self.update_view(area_height, ViewState::selection_to_bottom)
+            }
+            InputAction::ExpandSubtree => self.fold_subtree(true),
+            InputAction::CollapseSubtree => self.fold_subtree(false),
+            InputAction::ScrollLeft => self.h_scroll = self.h_scroll.saturating_sub(H_SCROLL_STEP),
+            InputAction::ScrollRight => self.h_scroll += H_SCROLL_STEP,
+            InputAction::Collapse => self.collapse_selected(),
+            InputAction::Expand => self.expand_selected(),
+            InputAction::ToggleExpand => self.toggle_expand(),
+            InputAction::EditValue => self.start_edit_value()?,
+            InputAction::EditMultiline => {
+                self.start_edit_multiline(true);
+            }
+            InputAction::EditInEditor => self.start_external_edit(),
+            InputAction::CastType => self.cast_selected_type(),
+            InputAction::ToggleFlowStyle => self.toggle_flow_style(),
+            InputAction::SortChildren => self.sort_selected(),
+            InputAction::AddSibling => self.start_add_sibling(),
+            InputAction::OpenLineAbove => {
+                self.set_toast("O opens lines in the raw view only".to_string())
+            }
+            InputAction::IndentLine(_) | InputAction::DedentLine(_) => {
+                self.set_toast("> and < indent lines in the raw view only".to_string())
+            }
+            InputAction::ToggleWrap => self.toggle_wrap(),
+            InputAction::Rekey => self.start_rekey(),
+            InputAction::ToggleBool => self.toggle_bool(),
+            InputAction::RenameKey => self.start_rename_key()?,
+            InputAction::RenameKeyEverywhere => self.start_rename_everywhere(),
+            InputAction::StartReplace => self.start_replace(),
+            InputAction::StartPipe => self.start_pipe(),
+            InputAction::GotoPath => self.start_goto(),
+            InputAction::AddChild => self.start_add_child()?,
+            InputAction::AddMapToSequence => self.start_add_map_to_sequence()?,
+            InputAction::DeleteNode => self.start_delete_node()?,
+            // Lines exist only in the raw view.
+            InputAction::DeleteLine => {}
             InputAction::CopyPath => self.copy_current_path(),
+            InputAction::CopyFencedYaml => self.copy_fenced_yaml(),
+            InputAction::CopyMarkdownTable => self.copy_markdown_table(),
+            InputAction::CopyPointer => self.copy_pointer(),
+            InputAction::CopyPatchOp => self.copy_patch_op(),
+            InputAction::ExportJson => self.start_export_json(true),
+            InputAction::ExportDocumentJson => self.start_export_json(false),
+            InputAction::CopyJson => self.copy_json(),
+            InputAction::ExportToml => self.start_export_toml(),
+            InputAction::YankNode => self.yank_node(),
+            InputAction::Paste => self.start_paste()?,
             InputAction::ConfirmYes => {
                 if self.confirm_yes()? {
                     return Ok(true);
@@ -680,9 +1143,74 @@ impl App {
                     self.switch_to_file_picker()?;
                 }
             }
-            InputAction::StartSearch => self.start_search(),
-            InputAction::SearchNext => self.search_next(),
-            InputAction::SearchPrev => self.search_prev(),
+            InputAction::ShowBufferDiff => self.show_buffer_diff(),
+            InputAction::ShowLint => self.show_lint(),
+            InputAction::ToggleEnvPreview => self.toggle_env_preview(),
+            InputAction::ToggleIndices => {
+                self.show_indices = !self.show_indices;
+                let state = if self.show_indices { "on" } else { "off" };
+                self.set_toast(format!("Index column {state}"));
+            }
+            InputAction::ShowPlaceholders => self.show_placeholders(),
+            InputAction::ShowDuplicates => {
+                if self.model.duplicates().is_empty() {
+                    self.set_toast("No duplicate keys".to_string());
+                } else {
+                    self.list_selection = 0;
+                    self.mode = Mode::DuplicateList;
+                }
+            }
+            InputAction::ShowSettings => self.mode = Mode::SettingsOverlay,
+            InputAction::ShowStats => self.mode = Mode::StatsOverlay,
+            InputAction::ShowHelp => {
+                self.list_selection = 0;
+                self.mode = Mode::HelpOverlay;
+            }
+            InputAction::Undo => self.undo(1),
+            InputAction::ShowJournal => {
+                if self.journal.is_empty() {
+                    self.set_toast("No edits yet".to_string());
+                } else {
+                    self.list_selection = 0;
+                    self.mode = Mode::JournalOverlay;
+                }
+            }
+            InputAction::CopyJournal => self.copy_journal(),
+            InputAction::ShowFindings => self.show_findings(),
+            // Only meaningful in the merge overlay.
+            InputAction::MergeTake(_) | InputAction::DiskChange(_) | InputAction::ReplaceAll => {}
+            InputAction::ToggleBaselineMissing => match self.baseline.as_mut() {
+                Some(baseline) => baseline.show_missing = !baseline.show_missing,
+                None => self.set_toast("No baseline loaded (use --baseline FILE)".to_string()),
+            },
+            InputAction::JumpToParseError => self.jump_to_parse_error(area_height),
+            InputAction::StartSearch => self.start_search(false),
+            InputAction::StartSearchBackward => self.start_search(true),
+            InputAction::SearchNext => {
+                self.record_jump();
+                if self.search_backward {
+                    self.search_prev();
+                } else {
+                    self.search_next();
+                }
+            }
+            InputAction::SearchPrev => {
+                self.record_jump();
+                if self.search_backward {
+                    self.search_next();
+                } else {
+                    self.search_prev();
+                }
+            }
+            InputAction::SetMark(letter) => self.set_mark(letter),
+            InputAction::JumpToMark(letter) => {
+                self.record_jump();
+                self.jump_to_mark(letter);
+                self.ensure_visible(area_height);
+            }
+            InputAction::ShowMarks => self.show_marks(),
+            InputAction::CycleSearchScope => self.search_scope = self.search_scope.next(),
+            InputAction::CycleSearchCase => self.search_case = self.search_case.next(),
             InputAction::Cancel => self.cancel_mode(),
             InputAction::InputChar(ch) => self.input.insert_char(ch),
             InputAction::InputBackspace => self.input.backspace(),
@@ -691,82 +1219,167 @@ impl App {
             InputAction::InputRight => self.input.move_right(),
             InputAction::InputHome => self.input.move_home(),
             InputAction::InputEnd => self.input.move_end(),
+            InputAction::InputUp if self.mode == Mode::SearchInput => self.recall_search(false),
+            InputAction::InputDown if self.mode == Mode::SearchInput => self.recall_search(true),
+            InputAction::InputUp => self.move_completion(false),
+            InputAction::InputDown => self.move_completion(true),
+            InputAction::CompleteKey if self.mode == Mode::GotoPath => self.complete_path(),
+            InputAction::CompleteKey => self.accept_completion(),
+            InputAction::InputNewline => {}
             InputAction::InputCommit => self.commit_input()?,
         }
+        if search_text_changed {
+            self.search_typed();
+        }
         self.ensure_visible(area_height);
         Ok(false)
     }
 
-    fn start_raw_edit_line(&mut self) -> Result<()> {
-        let lines = match self.raw_lines() {
-            Some(l) => l,
-            None => return Ok(()),
-        };
-        if self.selection < lines.len() {
-            self.mode = Mode::RawEditLine;
-            self.input.set(lines[self.selection].clone());
+    /// j/k and Ctrl+d/Ctrl+u scroll the help; Enter, Esc, F1 or `?` close it.
+    fn apply_help_action(&mut self, action: InputAction, area_height: usize) {
+        let len: usize = self
+            .vim
+            .keymap()
+            .help()
+            .iter()
+            .map(|(_, rows)| rows.len() + 2)
+            .sum();
+        // Stop once the last line is at the bottom of the screen.
+        let last = len.saturating_sub(area_height);
+        match action {
+            InputAction::MoveUp => self.list_selection = self.list_selection.saturating_sub(1),
+            InputAction::MoveDown => self.list_selection = (self.list_selection + 1).min(last),
+            InputAction::PageUp => {
+                self.list_selection = self.list_selection.saturating_sub(area_height / 2)
+            }
+            InputAction::PageDown => {
+                self.list_selection = (self.list_selection + area_height / 2).min(last)
+            }
+            InputAction::InputCommit | InputAction::Cancel => self.mode = Mode::Normal,
+            _ => {}
         }
-        Ok(())
     }
 
-    fn visible_len(&self) -> usize {
-        if self.raw_content.is_some() {
-            self.raw_lines().map(|l| l.len()).unwrap_or(0)
-        } else {
-            self.visible.len()
+    fn apply_journal_action(&mut self, action: InputAction, area_height: usize) {
+        let len = self.journal.len();
+        match action {
+            InputAction::MoveUp => self.list_selection = self.list_selection.saturating_sub(1),
+            InputAction::MoveDown => {
+                self.list_selection = (self.list_selection + 1).min(len.saturating_sub(1));
+            }
+            InputAction::InputCommit => {
+                let target = self
+                    .journal
+                    .newest_first()
+                    .nth(self.list_selection)
+                    .map(|e| e.path.clone());
+                self.mode = Mode::Normal;
+                if let Some(path) = target {
+                    if !self.reveal_path(&path) {
+                        self.set_toast(format!("{}: node no longer exists", path.dot_path()));
+                    }
+                    self.ensure_visible(area_height);
+                }
+            }
+            InputAction::Undo => {
+                self.undo(self.list_selection + 1);
+                self.list_selection = 0;
+                if self.journal.is_empty() {
+                    self.mode = Mode::Normal;
+                }
+            }
+            InputAction::CopyJournal => self.copy_journal(),
+            InputAction::Cancel => self.mode = Mode::Normal,
+            _ => {}
         }
     }
 
-    fn ensure_visible(&mut self, area_height: usize) {
-        let len = self.visible_len();
-        if len == 0 {
-            return;
-        }
-        if self.selection < self.scroll {
-            self.scroll = self.selection;
-        } else if self.selection >= self.scroll + area_height {
-            self.scroll = self.selection.saturating_sub(area_height.saturating_sub(1));
+    fn copy_journal(&mut self) {
+        if self.journal.is_empty() {
+            self.set_toast("No edits yet".to_string());
+        } else if clipboard::copy_to_clipboard(&self.journal.to_text()).is_ok() {
+            self.set_toast(format!("Copied journal ({} edits)", self.journal.len()));
+        } else {
+            self.set_toast("Failed to copy journal".to_string());
         }
     }
 
-    fn clamp_selection(&mut self, area_height: usize) {
-        let len = self.visible_len();
-        if self.selection < self.scroll {
-            self.selection = self.scroll;
-        } else if self.selection >= self.scroll + area_height {
-            self.selection = self.scroll + area_height.saturating_sub(1);
-            if self.selection >= len {
-                self.selection = len.saturating_sub(1);
+    fn apply_duplicate_list_action(&mut self, action: InputAction, area_height: usize) {
+        let len = self.model.duplicates().len();
+        match action {
+            InputAction::MoveUp => self.list_selection = self.list_selection.saturating_sub(1),
+            InputAction::MoveDown => {
+                self.list_selection = (self.list_selection + 1).min(len.saturating_sub(1));
+            }
+            InputAction::InputCommit => {
+                let target = self.model.duplicates().get(self.list_selection).cloned();
+                self.mode = Mode::Normal;
+                if let Some(dup) = target {
+                    if self.reveal_path(&dup.path) {
+                        self.set_toast(format!(
+                            "Duplicate '{}' (line {})",
+                            dup.path.dot_path(),
+                            dup.line
+                        ));
+                    } else {
+                        self.set_toast(format!("Line {}: node not in tree", dup.line));
+                    }
+                    self.ensure_visible(area_height);
+                }
             }
+            InputAction::Cancel => self.mode = Mode::Normal,
+            _ => {}
         }
     }
 
-    fn move_selection(&mut self, area_height: usize, delta: isize) {
-        let len = self.visible_len();
-        if len == 0 {
-            return;
+    /// Expand every ancestor of `path` and select it. Returns false if the node is not visible.
+    pub fn reveal_path(&mut self, path: &NodePath) -> bool {
+        for depth in 1..path.0.len() {
+            self.expanded
+                .insert(NodePath(path.0[..depth].to_vec()).dot_path());
+        }
+        self.rebuild_visible();
+        match visible_row_by_path(&self.visible, path) {
+            Some(index) => {
+                self.selection = index;
+                true
+            }
+            None => false,
         }
-        let next = self.selection as isize + delta;
-        self.selection = next.clamp(0, len.saturating_sub(1) as isize) as usize;
-        self.ensure_visible(area_height);
     }
 
-    fn jump_top(&mut self, _area_height: usize) {
-        self.selection = 0;
+    /// `}`/`{`: select the next or previous key or item of the same parent.
+    fn jump_sibling(&mut self, forward: bool) {
+        match sibling_row(&self.visible, self.selection, forward) {
+            Some(index) => self.selection = index,
+            None if forward => self.set_toast("Last sibling".to_string()),
+            None => self.set_toast("First sibling".to_string()),
+        }
     }
 
-    fn jump_bottom(&mut self, _area_height: usize) {
-        let len = self.visible_len();
-        if len > 0 {
-            self.selection = len - 1;
+    fn visible_len(&self) -> usize {
+        if self.raw_content.is_some() {
+            self.raw_lines().map(|l| l.len()).unwrap_or(0)
+        } else {
+            self.visible.len()
         }
     }
 
-    fn page_scroll(&mut self, area_height: usize, delta: isize) {
-        let len = self.visible_len();
-        let new = (self.selection as isize + delta).max(0);
-        self.selection = new.min(len.saturating_sub(1) as isize) as usize;
-        self.ensure_visible(area_height);
+    /// Run `update` on the selection and scroll of the current view, `area_height` rows tall.
+    fn update_view(&mut self, area_height: usize, update: impl FnOnce(&mut ViewState)) {
+        let mut view = ViewState {
+            selection: self.selection,
+            scroll: self.scroll,
+            len: self.visible_len(),
+            height: area_height,
+        };
+        update(&mut view);
+        self.selection = view.selection;
+        self.scroll = view.scroll;
+    }
+
+    fn ensure_visible(&mut self, area_height: usize) {
+        self.update_view(area_height, ViewState::ensure_visible);
     }
 
     fn expand_selected(&mut self) {
@@ -797,122 +1410,156 @@ impl App {
                     self.expanded.insert(dot);
                 }
                 self.rebuild_visible();
-            } else {
+            } else if !self.load_more_details() {
                 self.start_edit_value().ok();
             }
         }
     }
 
-    fn start_edit_value(&mut self) -> Result<()> {
-        let row_data = self
-            .current_row()
-            .map(|r| (r.is_container, r.display_value_preview.clone()));
-        if let Some((is_container, display_value)) = row_data {
-            if is_container {
-                return Ok(());
-            }
-            self.mode = Mode::EditValue;
-            self.input.set(display_value);
+    /// Bytes of the value at `path` the Details pane shows.
+    pub fn details_limit(&self, path: &NodePath) -> usize {
+        match &self.details_loaded {
+            Some((loaded_path, bytes)) if loaded_path == path => *bytes,
+            _ => DETAILS_CHUNK_BYTES,
         }
-        Ok(())
     }
 
-    fn start_rename_key(&mut self) -> Result<()> {
-        let row_data = self.current_row().map(|r| {
-            let is_key = r
-                .path
-                .0
-                .last()
-                .map(|seg| matches!(seg, crate::yaml_model::PathSegment::Key(_)))
-                == Some(true);
-            let is_root = r.path.0.is_empty();
-            (is_key, is_root, r.display_key.clone())
-        });
-        if let Some((is_key, is_root, display_key)) = row_data {
-            if is_key {
-                self.mode = Mode::RenameKey;
-                self.input.set(display_key);
-            } else if is_root {
-                self.set_toast("Root has no key to rename".to_string());
+    /// Show the next chunk of a long value in the Details pane. Returns false once it is whole.
+    fn load_more_details(&mut self) -> bool {
+        let Some((path, size)) = self.current_row().map(|r| (r.path.clone(), r.value_size)) else {
+            return false;
+        };
+        let limit = self.details_limit(&path);
+        if size <= limit {
+            return false;
+        }
+        self.details_loaded = Some((path, limit + DETAILS_CHUNK_BYTES));
+        true
+    }
+
+    fn copy_current_path(&mut self) {
+        if let Some(row) = self.current_row() {
+            let path = row.path.dot_path();
+            if clipboard::copy_to_clipboard(&path).is_ok() {
+                self.set_toast(format!("Copied: {path}"));
             } else {
-                self.set_toast("Cannot rename sequence item".to_string());
+                self.set_toast("Failed to copy path".to_string());
             }
         }
-        Ok(())
     }
 
-    fn start_add_child(&mut self) -> Result<()> {
-        let row_data = self.current_row().map(|r| {
-            let is_mapping_key = r
-                .path
-                .0
-                .last()
-                .map(|seg| matches!(seg, crate::yaml_model::PathSegment::Key(_)))
-                == Some(true);
-            (r.path.clone(), r.node_type.clone(), is_mapping_key)
-        });
-        if let Some((path, node_type, is_mapping_key)) = row_data {
-            if node_type == NodeType::Map {
-                self.mode = Mode::AddKey;
-                self.input.set(String::new());
-            } else if node_type == NodeType::Seq {
-                self.mode = Mode::AddValue;
-                self.input.set(String::new());
-            } else if is_mapping_key {
-                if let Err(e) = self.model.convert_to_empty_map(&path) {
-                    self.set_toast(e.to_string());
-                } else {
-                    self.dirty = true;
-                    self.rebuild_visible();
-                    self.mode = Mode::AddKey;
-                    self.input.set(String::new());
-                }
-            } else {
-                self.set_toast("Cannot add child to scalar".to_string());
+    fn copy_fenced_yaml(&mut self) {
+        let Some(path) = self.current_row().map(|r| r.path.clone()) else {
+            return;
+        };
+        let text = match self
+            .model
+            .node_at(&path)
+            .and_then(|node| markdown::fenced_yaml(&path, node))
+        {
+            Ok(text) => text,
+            Err(e) => {
+                self.set_toast(e.to_string());
+                return;
             }
+        };
+        match clipboard::copy_to_clipboard(&text) {
+            Ok(()) => self.set_toast(format!("Copied YAML block: {} lines", text.lines().count())),
+            Err(e) => self.set_toast(e.to_string()),
         }
-        Ok(())
     }
 
-    /// Add an empty map to the current sequence, then start AddKey on the new element.
-    /// Use Shift+A on a sequence (list) to add a new object and type its first key.
-    fn start_add_map_to_sequence(&mut self) -> Result<()> {
-        let path = self.current_row().map(|r| (r.path.clone(), r.node_type.clone()));
-        if let Some((path, node_type)) = path {
-            if node_type != NodeType::Seq {
-                self.set_toast("Shift+A: only on a sequence (list). Use 'a' to add a value.".to_string());
-                return Ok(());
+    fn copy_markdown_table(&mut self) {
+        let Some(path) = self.current_row().map(|r| r.path.clone()) else {
+            return;
+        };
+        let table = match self.model.node_at(&path) {
+            Ok(node) => markdown::markdown_table(node, markdown::DEFAULT_CELL_WIDTH),
+            Err(e) => {
+                self.set_toast(e.to_string());
+                return;
             }
-            match self.model.add_sequence_empty_map(&path) {
-                Ok(new_path) => {
-                    self.dirty = true;
-                    self.expanded.insert(path.dot_path());
-                    self.rebuild_visible();
-                    self.restore_selection(Some(new_path));
-                    self.mode = Mode::AddKey;
-                    self.input.set(String::new());
-                }
+        };
+        let Some(text) = table else {
+            self.set_toast("Table copy needs a sequence of maps".to_string());
+            return;
+        };
+        // Header and separator lines are not data rows.
+        let rows = text.lines().count().saturating_sub(2);
+        match clipboard::copy_to_clipboard(&text) {
+            Ok(()) => self.set_toast(format!("Copied Markdown table: {rows} rows")),
+            Err(e) => self.set_toast(e.to_string()),
+        }
+    }
+
+    fn copy_pointer(&mut self) {
+        if let Some(row) = self.current_row() {
+            let pointer = json_patch::pointer(&row.path);
+            match clipboard::copy_to_clipboard(&pointer) {
+                Ok(()) => self.set_toast(format!("Copied pointer: {pointer}")),
                 Err(e) => self.set_toast(e.to_string()),
             }
         }
-        Ok(())
     }
 
-    fn start_delete_node(&mut self) -> Result<()> {
-        if self.current_row().is_some() {
-            self.mode = Mode::ConfirmDelete;
+    /// Copy a JSON Patch op for the selected node: `add` if it was created this session,
+    /// `replace` otherwise, and `remove` when chosen from the delete confirmation.
+    fn copy_patch_op(&mut self) {
+        let Some(path) = self.current_row().map(|r| r.path.clone()) else {
+            return;
+        };
+        let op = if self.mode == Mode::ConfirmDelete {
+            self.mode = Mode::Normal;
+            PatchOp::Remove
+        } else if self.journal.created(&path) {
+            PatchOp::Add
+        } else {
+            PatchOp::Replace
+        };
+        let patch = match self.model.node_at(&path) {
+            Ok(node) => json_patch::patch_op(op, &path, Some(node)),
+            Err(e) => Err(e),
+        };
+        let text = match patch {
+            Ok(text) => text,
+            Err(e) => {
+                self.set_toast(e.to_string());
+                return;
+            }
+        };
+        match clipboard::copy_to_clipboard(&text) {
+            Ok(()) => self.set_toast(format!(
+                "Copied {} op: {}",
+                op.name(),
+                json_patch::pointer(&path)
+            )),
+            Err(e) => self.set_toast(e.to_string()),
         }
-        Ok(())
     }
 
-    fn copy_current_path(&mut self) {
-        if let Some(row) = self.current_row() {
-            let path = row.path.dot_path();
-            if clipboard::copy_to_clipboard(&path).is_ok() {
-                self.set_toast(format!("Copied: {path}"));
-            } else {
-                self.set_toast("Failed to copy path".to_string());
+    fn yank_node(&mut self) {
+        let path = match self.current_row() {
+            Some(row) => row.path.clone(),
+            None => return,
+        };
+        match self.model.node_at(&path) {
+            Ok(node) => {
+                let key = match path.0.last() {
+                    Some(crate::yaml_model::PathSegment::Key(key)) => Some(key.clone()),
+                    _ => None,
+                };
+                self.register = Some(YankRegister {
+                    key,
+                    value: node.clone(),
+                });
+                let label = if path.0.is_empty() {
+                    "(root)".to_string()
+                } else {
+                    path.dot_path()
+                };
+                self.set_toast(format!("Yanked: {label}"));
             }
+            Err(e) => self.set_toast(e.to_string()),
         }
     }
 
@@ -924,13 +1571,7 @@ impl App {
     fn confirm_yes(&mut self) -> Result<bool> {
         match self.mode {
             Mode::ConfirmDelete => {
-                let path = self.current_row().map(|r| r.path.clone());
-                if let Some(path) = path {
-                    self.model.delete_node(&path)?;
-                    self.dirty = true;
-                    self.rebuild_visible();
-                }
-                self.mode = Mode::Normal;
+                self.delete_selected()?;
                 Ok(false)
             }
             Mode::ConfirmQuit => Ok(true),
@@ -944,17 +1585,62 @@ impl App {
                 self.mode = Mode::Normal;
                 Ok(false)
             }
+            Mode::ConfirmSaveDuplicates => {
+                self.duplicates_acknowledged = true;
+                self.mode = Mode::Normal;
+                self.save()?;
+                Ok(false)
+            }
+            Mode::ConfirmRenameMerge => {
+                self.mode = Mode::RenameKey;
+                self.input.set(crate::source_map::MERGE_KEY.to_string());
+                Ok(false)
+            }
+            Mode::ConfirmOverwrite => {
+                self.mode = Mode::Normal;
+                if let Some(path) = self.pending_save_as.take() {
+                    self.save_as(path)?;
+                }
+                Ok(false)
+            }
+            Mode::ConfirmExportOverwrite => {
+                self.mode = Mode::Normal;
+                if let Some(path) = self.pending_export.take() {
+                    self.write_export(&path);
+                }
+                Ok(false)
+            }
             _ => Ok(false),
         }
     }
 
     fn confirm_no(&mut self) {
         self.mode = Mode::Normal;
+        self.pending_save_as = None;
+        self.pending_export = None;
+        self.export = None;
     }
 
-    fn start_search(&mut self) {
+    fn start_search(&mut self, backward: bool) {
         self.mode = Mode::SearchInput;
         self.input.set(String::new());
+        self.search_history.reset();
+        self.stash_search();
+        self.search_backward = backward;
+    }
+
+    /// Up/Down in the search prompt: an older or newer committed query, then back to the text
+    /// typed before.
+    fn recall_search(&mut self, newer: bool) {
+        let recalled = if newer {
+            self.search_history.newer()
+        } else {
+            self.search_history.older(&self.input.text)
+        };
+        if let Some(text) = recalled {
+            let text = text.to_string();
+            self.input.set(text);
+        }
     }
 
     fn search_next(&mut self) {
@@ -963,147 +1649,222 @@ impl App {
         }
     }
 
+    fn set_mark(&mut self, letter: char) {
+        let Some(path) = self.current_row().map(|row| row.path.clone()) else {
+            return;
+        };
+        let label = if path.0.is_empty() {
+            "(root)".to_string()
+        } else {
+            path.dot_path()
+        };
+        self.marks.insert(letter, path);
+        self.set_toast(format!("Mark '{letter}' set at {label}"));
+    }
+
+    /// Remember the selected node before a long move, for `Ctrl+o`.
+    pub(super) fn record_jump(&mut self) {
+        if self.raw_content.is_some() {
+            return;
+        }
+        if let Some(path) = self.current_row().map(|row| row.path.clone()) {
+            self.jumps.push(path);
+        }
+    }
+
+    /// `Ctrl+o`/`Ctrl+i`: go back (or forward) along the jump list to a node that still exists,
+    /// expanding its ancestors.
+    fn walk_jumps(&mut self, forward: bool, area_height: usize) {
+        let Some(current) = self.current_row().map(|row| row.path.clone()) else {
+            return;
+        };
+        let model = &self.model;
+        let exists = |path: &NodePath| model.node_at(path).is_ok();
+        let target = if forward {
+            self.jumps.forward(exists)
+        } else {
+            self.jumps.back(&current, exists)
+        };
+        let Some(path) = target else {
+            self.set_toast(
+                if forward {
+                    "At the newest jump"
+                } else {
+                    "At the oldest jump"
+                }
+                .to_string(),
+            );
+            return;
+        };
+        if self.reveal_path(&path) {
+            let (index, total) = self.jumps.label();
+            self.set_toast(format!("Jump {index}/{total}"));
+        } else {
+            self.set_toast(format!("{} is hidden by the search", path.dot_path()));
+        }
+        self.ensure_visible(area_height);
+    }
+
+    /// Expand the ancestors of the node marked `letter` and select it.
+    pub(super) fn jump_to_mark(&mut self, letter: char) {
+        let Some(path) = self.marks.get(&letter).cloned() else {
+            self.set_toast(format!("Mark '{letter}' is not set"));
+            return;
+        };
+        if self.model.node_at(&path).is_err() {
+            self.set_toast(format!(
+                "Mark '{letter}': target {} no longer exists",
+                path.dot_path()
+            ));
+        } else if !self.reveal_path(&path) {
+            self.set_toast(format!(
+                "Mark '{letter}': {} is hidden by the search",
+                path.dot_path()
+            ));
+        }
+    }
+
     fn search_prev(&mut self) {
         if let Some(prev) = prev_match(&self.matches, self.selection) {
             self.selection = prev;
         }
     }
 
+    /// Apply the query typed in `Mode::SearchInput` and jump to the first match (the last for `?`).
+    /// An invalid regex keeps the prompt open and the previous search in place.
+    fn commit_search(&mut self) {
+        let (query, scope) = search::parse_query(&self.input.text, self.search_scope);
+        let parsed = match Query::parse(&query, self.search_case) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                self.set_toast(err.to_string());
+                return;
+            }
+        };
+        self.search_scope = scope;
+        self.search_query = if query.is_empty() { None } else { Some(parsed) };
+        if let Some(from) = self.selection_before_search() {
+            self.jumps.push(from);
+        }
+        self.search_stash = None;
+        self.search_due = None;
+        if !query.is_empty() {
+            self.remember_search();
+        }
+        self.mode = Mode::Normal;
+        if self.search_query.is_none() {
+            self.matches.clear();
+        }
+        self.rebuild_visible();
+        if !query.is_empty() && self.matches.is_empty() {
+            self.set_toast("No matches found".to_string());
+        } else if let Some(&first) = if self.search_backward {
+            self.matches.last()
+        } else {
+            self.matches.first()
+        } {
+            self.selection = first;
+        }
+    }
+
+    /// Put the query as typed, prefixes included, first in the search history. Failing to
+    /// write the state file is not worth interrupting the search for.
+    fn remember_search(&mut self) {
+        self.search_history.remember(self.input.text.trim());
+        if let Some(state) = &self.cli.search_history {
+            let _ = self.search_history.save(state);
+        }
+    }
+
     fn cancel_mode(&mut self) {
+        if self.mode == Mode::MergeValue {
+            self.mode = Mode::MergeConflicts;
+            self.input.set(String::new());
+            return;
+        }
         if self.mode == Mode::SearchInput {
-            self.search_query = None;
-            self.matches.clear();
-            self.rebuild_visible();
+            self.restore_search_stash();
         }
         self.mode = Mode::Normal;
         self.input.set(String::new());
         self.pending_key = None;
+        self.add_after = None;
+        self.shared_edit = None;
+        self.export = None;
     }
 
-    fn commit_input(&mut self) -> Result<()> {
-        match self.mode {
-            Mode::EditValue => {
-                let path = self.current_row().map(|r| r.path.clone());
-                if let Some(path) = path {
-                    let parsed = parse_scalar_input(&self.input.text)?;
-                    self.model.edit_value(&path, parsed)?;
-                    self.dirty = true;
-                }
-                self.mode = Mode::Normal;
-                self.rebuild_visible();
-            }
-            Mode::RenameKey => {
-                let path = self.current_row().map(|r| r.path.clone());
-                if let Some(path) = path {
-                    let key_trimmed = self.input.text.trim();
-                    if key_trimmed.is_empty() {
-                        self.set_toast("Key cannot be empty".to_string());
-                    } else if let Err(e) = self.model.rename_key(&path, key_trimmed) {
-                        self.set_toast(e.to_string());
-                    } else {
-                        self.dirty = true;
-                        self.mode = Mode::Normal;
-                        self.rebuild_visible();
-                    }
-                } else {
-                    self.mode = Mode::Normal;
-                }
-            }
-            Mode::AddKey => {
-                let key_trimmed = self.input.text.trim().to_string();
-                if key_trimmed.is_empty() {
-                    self.set_toast("Key cannot be empty".to_string());
-                } else {
-                    self.pending_key = Some(key_trimmed);
-                    self.mode = Mode::AddValue;
-                    self.input.set(String::new());
-                }
-            }
-            Mode::AddValue => {
-                let row_data = self
-                    .current_row()
-                    .map(|r| (r.path.clone(), r.node_type.clone()));
-                if let Some((path, node_type)) = row_data {
-                    match parse_scalar_input(self.input.text.trim()) {
-                        Ok(parsed) => {
-                            if node_type == NodeType::Map {
-                                if let Some(key) = self.pending_key.take() {
-                                    if let Err(e) =
-                                        self.model.add_mapping_child(&path, key.trim(), parsed)
-                                    {
-                                        self.set_toast(e.to_string());
-                                    } else {
-                                        self.dirty = true;
-                                        self.mode = Mode::Normal;
-                                        self.rebuild_visible();
-                                    }
-                                } else {
-                                    self.mode = Mode::Normal;
-                                }
-                            } else if node_type == NodeType::Seq {
-                                if let Err(e) = self.model.add_sequence_value(&path, parsed) {
-                                    self.set_toast(e.to_string());
-                                } else {
-                                    self.dirty = true;
-                                    self.mode = Mode::Normal;
-                                    self.rebuild_visible();
-                                }
-                            } else {
-                                self.mode = Mode::Normal;
-                            }
-                        }
-                        Err(e) => self.set_toast(e.to_string()),
-                    }
-                } else {
-                    self.mode = Mode::Normal;
-                }
-            }
-            Mode::SearchInput => {
-                let query = self.input.text.trim().to_string();
-                self.search_query = if query.is_empty() { None } else { Some(query.clone()) };
-                self.mode = Mode::Normal;
-                self.rebuild_visible();
-                self.matches = self
-                    .visible
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(idx, row)| {
-                        self.search_query.as_ref().and_then(|q| {
-                            let lower = q.to_lowercase();
-                            if row.path.dot_path().to_lowercase().contains(&lower)
-                                || row.display_key.to_lowercase().contains(&lower)
-                            {
-                                Some(idx)
-                            } else {
-                                None
-                            }
-                        })
-                    })
-                    .collect();
-                if !query.is_empty() && self.matches.is_empty() {
-                    self.set_toast("No matches found".to_string());
-                } else if !self.matches.is_empty() {
-                    self.selection = self.matches[0];
-                }
-            }
-            Mode::RawEditLine => {
-                let text = self.input.text.clone();
-                self.raw_replace_line(self.selection, &text);
-                self.mode = Mode::Normal;
-                self.dirty = true;
-            }
-            _ => {}
-        }
+    pub fn save(&mut self) -> Result<()> {
+        let report = self.model.save()?;
+        self.dirty = false;
+        self.announce_save("Saved".to_string(), report);
+        self.start_post_save_hook();
+        // What a save alters is now on disk; check the file as saved.
+        self.start_integrity_check();
+        self.refresh_lint();
         Ok(())
     }
 
-    pub fn save(&mut self) -> Result<()> {
-        self.model.save()?;
+    fn start_save_as(&mut self) {
+        self.mode = Mode::SaveAsPath;
+        self.input.set(self.model.file_path().to_string());
+    }
+
+    /// Check the path typed in `Mode::SaveAsPath`, asking before replacing another file.
+    fn commit_save_as(&mut self) -> Result<()> {
+        let typed = self.input.text.trim();
+        if typed.is_empty() {
+            self.set_toast("File name cannot be empty".to_string());
+            return Ok(());
+        }
+        let path = PathBuf::from(typed);
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        if !dir.is_dir() {
+            self.set_toast(format!("No such directory: {}", dir.display()));
+            return Ok(());
+        }
+        self.mode = Mode::Normal;
+        if path.exists() && Path::new(self.model.file_path()) != path {
+            self.pending_save_as = Some(path);
+            self.mode = Mode::ConfirmOverwrite;
+            return Ok(());
+        }
+        self.save_as(path)
+    }
+
+    /// Write the document to `path` and keep editing it there.
+    fn save_as(&mut self, path: PathBuf) -> Result<()> {
+        if Path::new(self.model.file_path()) != path {
+            self.model.set_saved_in_session(false);
+        }
+        let report = self.model.save_to(&path)?;
         self.dirty = false;
-        self.set_toast("Saved".to_string());
+        self.last_modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        self.read_only = None;
+        self.apply_settings(&path);
+        self.acquire_lock(&path);
+        self.announce_save(format!("Saved as {}", path.display()), report);
+        self.start_post_save_hook();
+        self.start_integrity_check();
+        self.refresh_lint();
+        self.remember_recent(&path);
         Ok(())
     }
 
+    /// Toast `saved`, explaining a whole-file rewrite and warning about a failed backup.
+    fn announce_save(&mut self, saved: String, report: SaveReport) {
+        let mut message = match report.fallback {
+            Some(reason) => format!("{saved} by rewriting the whole file: {reason}"),
+            None => saved,
+        };
+        if let Some(err) = report.backup_error {
+            message = format!("{message}; backup failed: {err}");
+        }
+        self.set_toast(message);
+    }
+
     pub fn set_toast(&mut self, message: String) {
         self.toast = Some(Toast {
             message,
@@ -1158,28 +1919,661 @@ impl App {
     }
 }
 
-fn list_picker_entries(dir: &Path) -> Result<Vec<PickerEntry>> {
-    let mut entries = Vec::new();
-    if dir.parent().is_some() {
-        entries.push(PickerEntry::Parent);
-    }
-    let mut dirs: Vec<PathBuf> = Vec::new();
-    let mut files: Vec<PathBuf> = Vec::new();
-    for e in fs::read_dir(dir)? {
-        let e = e?;
-        let p = e.path();
-        if p.is_dir() {
-            dirs.push(p);
-        } else if p.is_file() {
-            let ext = p.extension().and_then(|e| e.to_str());
-            if ext.map(|e| e.eq_ignore_ascii_case("yaml") || e.eq_ignore_ascii_case("yml")) == Some(true) {
-                files.push(p);
-            }
-        }
-    }
-    dirs.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
-    files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
-    entries.extend(dirs.into_iter().map(PickerEntry::Dir));
-    entries.extend(files.into_iter().map(PickerEntry::File));
-    Ok(entries)
+/// Settings for `dir`, or defaults plus an error message when a settings file is invalid.
+fn load_settings(dir: &Path, cli: &CliOverrides) -> (Settings, Option<String>) {
+    let user_config = settings::user_config_path();
+    match settings::load(dir, user_config.as_deref(), cli) {
+        Ok(settings) => (settings, None),
+        Err(err) => (Settings::defaults(dir), Some(format!("Settings: {err}"))),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// An app on a temporary file holding `yaml`; the file is gone once it is loaded.
+    pub(crate) fn app_with(name: &str, yaml: &str) -> App {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("yed_app_{}_{}.yaml", name, std::process::id()));
+        std::fs::write(&path, yaml).unwrap();
+        let app = App::new(&path, CliOverrides::default()).unwrap();
+        std::fs::remove_file(&path).ok();
+        app
+    }
+
+    #[test]
+    fn opening_another_file_drops_search_and_lossy_state() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let dir = std::env::temp_dir().join(format!("yed_app_reopen_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let other = dir.join("other.yaml");
+        // The explicit `!!float` tag is dropped on save, so this file is lossy too.
+        std::fs::write(&other, "ratio: !!float 1\n").unwrap();
+        let finish = |app: &mut App| {
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while app.integrity_task.is_some() && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(20));
+                app.poll_tasks();
+            }
+        };
+
+        // A backward search still waiting for a pause in typing.
+        let big: String = (0..3000).map(|i| format!("k{i}:\n  v: {i}\n")).collect();
+        let mut app = app_with("reopen_search", &big);
+        for ch in ['?', 'k'] {
+            app.handle_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE), 10)
+                .unwrap();
+        }
+        assert!(app.search_backward);
+        assert!(app.search_stash.is_some());
+        assert!(app.search_due.is_some());
+        app.open_file(other.clone()).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(!app.search_backward);
+        assert!(app.search_stash.is_none());
+        assert_eq!(app.search_due, None);
+
+        // Going ahead with a lossy edit in one file does not carry over to the next.
+        let mut app = app_with("reopen_lossy", "ratio: !!float 1\nname: api\n");
+        finish(&mut app);
+        app.selection = 2;
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        assert_eq!(app.mode, Mode::ConfirmLossy);
+        app.apply_action(InputAction::ConfirmYes, 10).unwrap();
+        assert!(app.lossy_acknowledged);
+        app.open_file(other).unwrap();
+        assert!(!app.lossy_acknowledged);
+        finish(&mut app);
+        app.selection = 1;
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        assert_eq!(app.mode, Mode::ConfirmLossy);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn save_as_writes_a_new_file_and_confirms_overwrite() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let dir = std::env::temp_dir().join(format!("yed_app_save_as_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let original = dir.join("values.yaml");
+        std::fs::write(&original, "a: 1\n").unwrap();
+        let mut app = App::new(&original, CliOverrides::default()).unwrap();
+        app.dirty = true;
+        let save_as = |app: &mut App, target: &std::path::Path| {
+            app.apply_action(InputAction::SaveAs, 10).unwrap();
+            assert_eq!(app.mode, Mode::SaveAsPath);
+            app.input.set(target.display().to_string());
+            app.apply_action(InputAction::InputCommit, 10).unwrap();
+        };
+
+        save_as(&mut app, &dir.join("missing/copy.yaml"));
+        assert_eq!(app.mode, Mode::SaveAsPath);
+        assert!(app
+            .toast
+            .as_ref()
+            .unwrap()
+            .message
+            .starts_with("No such directory"));
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+
+        let copy = dir.join("copy.yaml");
+        save_as(&mut app, &copy);
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(!app.dirty);
+        assert_eq!(app.model.file_path(), copy.display().to_string());
+        assert_eq!(std::fs::read_to_string(&copy).unwrap(), "---\na: 1\n");
+        assert_eq!(std::fs::read_to_string(&original).unwrap(), "a: 1\n");
+
+        save_as(&mut app, &original);
+        assert_eq!(app.mode, Mode::ConfirmOverwrite);
+        app.apply_action(InputAction::ConfirmNo, 10).unwrap();
+        assert_eq!(std::fs::read_to_string(&original).unwrap(), "a: 1\n");
+        app.handle_key(KeyEvent::new(KeyCode::Char('W'), KeyModifiers::SHIFT), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::SaveAsPath);
+        assert_eq!(app.input.text, copy.display().to_string());
+        app.input.set(original.display().to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        app.apply_action(InputAction::ConfirmYes, 10).unwrap();
+        assert_eq!(std::fs::read_to_string(&original).unwrap(), "---\na: 1\n");
+        assert_eq!(app.model.file_path(), original.display().to_string());
+        drop(app);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn locked_file_opens_read_only_until_confirmed() {
+        let dir = std::env::temp_dir().join(format!("yed_app_locked_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("values.yaml");
+        std::fs::write(&file, "a: 1\n").unwrap();
+        // pid 1 always exists, so the lock looks live.
+        let host = crate::lock::LockInfo::current().host;
+        std::fs::write(
+            crate::lock::lock_path(&file),
+            format!("pid=1\nhost={host}\nsince=0\n"),
+        )
+        .unwrap();
+
+        let mut app = App::new(&file, CliOverrides::default()).unwrap();
+        assert_eq!(app.mode, Mode::ConfirmLocked);
+        assert!(app.read_only.is_some());
+        assert!(app.lock.is_none());
+        app.apply_action(InputAction::ConfirmYes, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.read_only, None);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn search_scope_picks_keys_values_or_both() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let yaml = "server:\n  port: 8080\n  env: production\nports:\n  - 8080\n";
+        let mut app = app_with("search_scope", yaml);
+        let search = |app: &mut App, query: &str| {
+            app.apply_action(InputAction::StartSearch, 10).unwrap();
+            app.input.set(query.to_string());
+            app.apply_action(InputAction::InputCommit, 10).unwrap();
+            app.matches
+                .iter()
+                .map(|&idx| app.visible[idx].path.dot_path())
+                .collect::<Vec<_>>()
+        };
+
+        // Values are found even with their parents collapsed, which are shown as ancestors.
+        assert_eq!(search(&mut app, "8080"), vec!["server.port", "ports.0"]);
+        assert_eq!(app.visible[app.selection].path.dot_path(), "server.port");
+        assert!(app
+            .visible
+            .iter()
+            .any(|row| row.path.dot_path() == "server"));
+        assert!(app
+            .visible
+            .iter()
+            .all(|row| row.path.dot_path() != "server.env"));
+        assert_eq!(
+            search(&mut app, "port"),
+            vec!["server.port", "ports", "ports.0"]
+        );
+
+        // Ctrl+k while typing cycles to keys only.
+        app.apply_action(InputAction::StartSearch, 10).unwrap();
+        let ctrl_k = KeyEvent::new(KeyCode::Char('k'), KeyModifiers::CONTROL);
+        app.handle_key(ctrl_k, 10).unwrap();
+        assert_eq!(app.search_scope, SearchScope::Keys);
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+        assert!(search(&mut app, "production").is_empty());
+
+        // A prefix sets the scope for this and later searches.
+        assert_eq!(search(&mut app, "v:prod"), vec!["server.env"]);
+        assert_eq!(app.search_scope, SearchScope::Values);
+        assert!(search(&mut app, "port").is_empty());
+        assert_eq!(
+            search(&mut app, "k:port"),
+            vec!["server.port", "ports", "ports.0"]
+        );
+    }
+
+    #[test]
+    fn question_mark_searches_upwards() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = app_with("search_up", "a: x1\nb: x2\nc: x3\nd: y\n");
+        let key = |app: &mut App, code: KeyCode| {
+            app.handle_key(KeyEvent::new(code, KeyModifiers::NONE), 10)
+                .unwrap();
+        };
+        let selected = |app: &App| app.visible[app.selection].path.dot_path();
+        key(&mut app, KeyCode::Char('?'));
+        assert_eq!(app.mode, Mode::SearchInput);
+        key(&mut app, KeyCode::Char('x'));
+        key(&mut app, KeyCode::Enter);
+        assert_eq!(selected(&app), "c");
+        key(&mut app, KeyCode::Char('n'));
+        assert_eq!(selected(&app), "b");
+        app.handle_key(KeyEvent::new(KeyCode::Char('N'), KeyModifiers::SHIFT), 10)
+            .unwrap();
+        assert_eq!(selected(&app), "c");
+
+        // A cancelled `/` keeps the direction; a committed one turns it back down.
+        key(&mut app, KeyCode::Char('/'));
+        key(&mut app, KeyCode::Esc);
+        assert!(app.search_backward);
+        key(&mut app, KeyCode::Char('/'));
+        key(&mut app, KeyCode::Char('x'));
+        key(&mut app, KeyCode::Enter);
+        assert_eq!(selected(&app), "a");
+        key(&mut app, KeyCode::Char('n'));
+        assert_eq!(selected(&app), "b");
+    }
+
+    #[test]
+    fn regex_search_filters_and_keeps_previous_search_on_error() {
+        let yaml = "items:\n  - name: web\n    image: nginx\n  - name: db\n    image: postgres\nname: top\n";
+        let mut app = app_with("regex_search", yaml);
+        let search = |app: &mut App, query: &str| {
+            app.apply_action(InputAction::StartSearch, 10).unwrap();
+            app.input.set(query.to_string());
+            app.apply_action(InputAction::InputCommit, 10).unwrap();
+            app.matches
+                .iter()
+                .map(|&idx| app.visible[idx].path.dot_path())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            search(&mut app, r"k:re:^items\.\d+\.name$"),
+            vec!["items.0.name", "items.1.name"]
+        );
+        assert!(app.search_query.as_ref().unwrap().is_regex());
+        assert!(app.visible.iter().all(|row| row.path.dot_path() != "name"));
+
+        // A broken regex reports the error and leaves the last search applied.
+        assert_eq!(
+            search(&mut app, "re:(items"),
+            vec!["items.0.name", "items.1.name"]
+        );
+        assert_eq!(app.mode, Mode::SearchInput);
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Invalid regex: unclosed group"
+        );
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+
+        // Plain text stays the default: regex syntax matches literally.
+        assert!(search(&mut app, "v:ngin.").is_empty());
+        assert_eq!(
+            search(&mut app, "v:re:nginx|postgres"),
+            vec!["items.0.image", "items.1.image"]
+        );
+        // Replace does not start from a regex.
+        app.apply_action(InputAction::StartReplace, 10).unwrap();
+        assert_eq!(app.input.text, "%s//");
+    }
+
+    #[test]
+    fn fuzzy_search_walks_matches_best_first() {
+        let yaml = "services:\n  tls:\n    listen: x\nserver:\n  tls:\n    enabled: true\n";
+        let mut app = app_with("fuzzy_search", yaml);
+        app.apply_action(InputAction::StartSearch, 10).unwrap();
+        app.input.set("k:fz:srvtlsen".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert!(app.search_query.as_ref().unwrap().is_fuzzy());
+        assert_eq!(
+            app.visible[app.selection].path.dot_path(),
+            "server.tls.enabled"
+        );
+        let ranked: Vec<_> = app
+            .matches
+            .iter()
+            .map(|&idx| app.visible[idx].path.dot_path())
+            .collect();
+        assert_eq!(ranked[0], "server.tls.enabled");
+        assert!(ranked.len() > 1, "{ranked:?}");
+        app.apply_action(InputAction::SearchNext, 10).unwrap();
+        assert_eq!(app.visible[app.selection].path.dot_path(), ranked[1]);
+
+        // Without the prefix the same text is a plain substring and matches nothing.
+        app.apply_action(InputAction::StartSearch, 10).unwrap();
+        app.input.set("srvtlsen".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert!(app.matches.is_empty());
+    }
+
+    #[test]
+    fn search_history_recalls_queries_with_up_and_down() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = app_with("search_history", "name: api\nport: 80\n");
+        let key = |app: &mut App, code: KeyCode| {
+            app.handle_key(KeyEvent::new(code, KeyModifiers::NONE), 10)
+                .unwrap();
+        };
+        for query in ["port", "k:name", "port"] {
+            app.apply_action(InputAction::StartSearch, 10).unwrap();
+            app.input.set(query.to_string());
+            app.apply_action(InputAction::InputCommit, 10).unwrap();
+        }
+        app.apply_action(InputAction::StartSearch, 10).unwrap();
+        app.input.set("na".to_string());
+        key(&mut app, KeyCode::Up);
+        assert_eq!(app.input.text, "port");
+        key(&mut app, KeyCode::Up);
+        assert_eq!(app.input.text, "k:name");
+        // Only two distinct queries were committed.
+        key(&mut app, KeyCode::Up);
+        assert_eq!(app.input.text, "k:name");
+        key(&mut app, KeyCode::Down);
+        key(&mut app, KeyCode::Down);
+        assert_eq!(app.input.text, "na");
+        key(&mut app, KeyCode::Up);
+        key(&mut app, KeyCode::Up);
+        key(&mut app, KeyCode::Enter);
+        assert_eq!(app.search_scope, SearchScope::Keys);
+        assert_eq!(app.visible[app.selection].path.dot_path(), "name");
+    }
+
+    #[test]
+    fn keymap_overrides_and_unbinds_built_in_keys() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = app_with("keymap", "name: api\nport: 80\n");
+        let (keymap, warnings) = crate::keymap::parse(
+            "\"ctrl+e\" = \"edit_value\"\ndown = \"none\"\nj = \"jump_bottom\"\n",
+        );
+        assert!(warnings.is_empty());
+        app.vim.set_keymap(keymap);
+
+        app.handle_key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE), 10)
+            .unwrap();
+        assert_eq!(app.selection, 0);
+        app.handle_key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE), 10)
+            .unwrap();
+        assert_eq!(app.selection, 2);
+        app.handle_key(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::EditValue);
+        // Prompts keep their own keys.
+        app.handle_key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE), 10)
+            .unwrap();
+        assert_eq!(app.input.text, "80j");
+    }
+
+    #[test]
+    fn ctrl_o_and_tab_walk_the_jump_list() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = app_with("jumps", "a: 1\nb: 2\nc: 3\nd: 4\n");
+        let key = |app: &mut App, code: KeyCode, modifiers: KeyModifiers| {
+            app.handle_key(KeyEvent::new(code, modifiers), 3).unwrap();
+        };
+        let selected = |app: &App| app.visible[app.selection].path.dot_path();
+        let toast = |app: &App| app.toast.as_ref().unwrap().message.clone();
+        app.selection = app
+            .visible
+            .iter()
+            .position(|row| row.path.dot_path() == "b")
+            .unwrap();
+        key(&mut app, KeyCode::Char('G'), KeyModifiers::SHIFT);
+        key(&mut app, KeyCode::Char('g'), KeyModifiers::NONE);
+        key(&mut app, KeyCode::Char('g'), KeyModifiers::NONE);
+        assert_eq!(selected(&app), "");
+        key(&mut app, KeyCode::Char('o'), KeyModifiers::CONTROL);
+        assert_eq!(
+            (selected(&app), toast(&app)),
+            ("d".to_string(), "Jump 2/3".to_string())
+        );
+        key(&mut app, KeyCode::Char('o'), KeyModifiers::CONTROL);
+        assert_eq!(
+            (selected(&app), toast(&app)),
+            ("b".to_string(), "Jump 1/3".to_string())
+        );
+        key(&mut app, KeyCode::Char('o'), KeyModifiers::CONTROL);
+        assert_eq!(
+            (selected(&app), toast(&app)),
+            ("b".to_string(), "At the oldest jump".to_string())
+        );
+        key(&mut app, KeyCode::Tab, KeyModifiers::NONE);
+        key(&mut app, KeyCode::Char('i'), KeyModifiers::CONTROL);
+        assert_eq!(
+            (selected(&app), toast(&app)),
+            ("".to_string(), "Jump 3/3".to_string())
+        );
+    }
+
+    #[test]
+    fn zz_zt_zb_scroll_the_selection_to_the_middle_top_and_bottom() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let yaml: String = (0..30).map(|i| format!("k{i}: {i}\n")).collect();
+        let mut app = app_with("center", &yaml);
+        app.selection = 20;
+        let keys = |app: &mut App, keys: &str| {
+            for ch in keys.chars() {
+                app.handle_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE), 10)
+                    .unwrap();
+            }
+        };
+        keys(&mut app, "zz");
+        assert_eq!((app.selection, app.scroll), (20, 15));
+        keys(&mut app, "zt");
+        assert_eq!((app.selection, app.scroll), (20, 20));
+        keys(&mut app, "zb");
+        assert_eq!((app.selection, app.scroll), (20, 11));
+
+        // The raw view scrolls over its lines the same way.
+        let raw = format!(
+            "a: [\n{}",
+            (0..29).map(|i| format!("k{i}: {i}\n")).collect::<String>()
+        );
+        let mut app = app_with("center_raw", &raw);
+        assert!(app.raw_content.is_some());
+        app.selection = 20;
+        keys(&mut app, "zz");
+        assert_eq!((app.selection, app.scroll), (20, 15));
+        keys(&mut app, "zt");
+        assert_eq!((app.selection, app.scroll), (20, 20));
+    }
+
+    #[test]
+    fn h_m_l_select_the_top_middle_and_bottom_on_screen() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let yaml: String = (0..30).map(|i| format!("k{i}: {i}\n")).collect();
+        let mut app = app_with("screen", &yaml);
+        app.scroll = 10;
+        app.selection = 12;
+        let key = |app: &mut App, ch: char| {
+            app.handle_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::SHIFT), 10)
+                .unwrap();
+        };
+        key(&mut app, 'L');
+        assert_eq!((app.selection, app.scroll), (19, 10));
+        key(&mut app, 'H');
+        assert_eq!((app.selection, app.scroll), (10, 10));
+        key(&mut app, 'M');
+        assert_eq!((app.selection, app.scroll), (14, 10));
+        app.scroll = 25;
+        key(&mut app, 'L');
+        assert_eq!((app.selection, app.scroll), (30, 25));
+
+        // The raw view moves over its lines.
+        let mut raw = app_with("screen_raw", "a: [\nb: 1\nc: 2\n");
+        assert!(raw.raw_content.is_some());
+        key(&mut raw, 'L');
+        assert_eq!(raw.selection, 2);
+        key(&mut raw, 'M');
+        assert_eq!(raw.selection, 1);
+    }
+
+    #[test]
+    fn braces_jump_between_siblings_over_their_children() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let yaml =
+            "items:\n  - name: a\n    port: 1\n  - name: b\n    port: 2\n  - name: c\nother: x\n";
+        let mut app = app_with("siblings", yaml);
+        for dot in ["items", "items.0", "items.1"] {
+            app.expanded.insert(dot.to_string());
+        }
+        app.rebuild_visible();
+        let key = |app: &mut App, ch: char| {
+            app.handle_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE), 3)
+                .unwrap();
+        };
+        let selected = |app: &App| app.visible[app.selection].path.dot_path();
+        app.selection = app
+            .visible
+            .iter()
+            .position(|row| row.path.dot_path() == "items.0")
+            .unwrap();
+        key(&mut app, '}');
+        assert_eq!(selected(&app), "items.1");
+        key(&mut app, '}');
+        assert_eq!(selected(&app), "items.2");
+        // The view follows the selection.
+        assert!(app.scroll + 3 > app.selection);
+        key(&mut app, '}');
+        assert_eq!(selected(&app), "items.2");
+        assert_eq!(app.toast.as_ref().unwrap().message, "Last sibling");
+        key(&mut app, '{');
+        key(&mut app, '{');
+        assert_eq!(selected(&app), "items.0");
+        assert_eq!(app.scroll, app.selection);
+
+        // The children of one item stop at its last key.
+        key(&mut app, 'j');
+        assert_eq!(selected(&app), "items.0.name");
+        key(&mut app, '}');
+        assert_eq!(selected(&app), "items.0.port");
+        app.toast = None;
+        key(&mut app, '}');
+        assert_eq!(selected(&app), "items.0.port");
+        assert_eq!(app.toast.as_ref().unwrap().message, "Last sibling");
+        app.selection = app
+            .visible
+            .iter()
+            .position(|row| row.path.dot_path() == "other")
+            .unwrap();
+        key(&mut app, '{');
+        assert_eq!(selected(&app), "items");
+    }
+
+    #[test]
+    fn journal_records_edits_and_undoes_to_an_entry() {
+        let mut app = app_with("journal", "name: api\nport: 80\nflag: false\n");
+        app.selection = 2;
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        app.input.set("8080".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        app.selection = 3;
+        app.apply_action(InputAction::ToggleBool, 10).unwrap();
+        app.selection = 1;
+        app.apply_action(InputAction::RenameKey, 10).unwrap();
+        app.input.set("service".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+
+        let entries: Vec<(String, String, String, String)> = app
+            .journal
+            .newest_first()
+            .map(|e| {
+                (
+                    e.kind.to_string(),
+                    e.path.dot_path(),
+                    e.before.clone(),
+                    e.after.clone(),
+                )
+            })
+            .collect();
+        let entry = |kind: &str, path: &str, before: &str, after: &str| {
+            (
+                kind.to_string(),
+                path.to_string(),
+                before.to_string(),
+                after.to_string(),
+            )
+        };
+        assert_eq!(
+            entries,
+            vec![
+                entry("rename", "service", "name", "service"),
+                entry("toggle", "flag", "false", "true"),
+                entry("edit", "port", "80", "8080"),
+            ]
+        );
+
+        // Undo back to the toggle: the rename and the toggle are reverted, the edit stays.
+        app.apply_action(InputAction::ShowJournal, 10).unwrap();
+        assert_eq!(app.mode, Mode::JournalOverlay);
+        app.apply_action(InputAction::MoveDown, 10).unwrap();
+        app.apply_action(InputAction::Undo, 10).unwrap();
+        let key = |k: &str| NodePath(vec![PathSegment::Key(k.into())]);
+        assert_eq!(app.journal.len(), 1);
+        assert_eq!(
+            app.model.node_at(&key("name")).unwrap().as_str(),
+            Some("api")
+        );
+        assert_eq!(
+            app.model.node_at(&key("flag")).unwrap(),
+            &Yaml::Boolean(false)
+        );
+        assert_eq!(
+            app.model.node_at(&key("port")).unwrap(),
+            &Yaml::Integer(8080)
+        );
+
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+        app.apply_action(InputAction::Undo, 10).unwrap();
+        assert_eq!(app.model.node_at(&key("port")).unwrap(), &Yaml::Integer(80));
+        assert!(app.journal.is_empty());
+    }
+
+    #[test]
+    fn project_settings_take_effect() {
+        let root = std::env::temp_dir().join(format!("yed_app_project_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("secrets")).unwrap();
+        std::fs::write(
+            root.join(".yed.toml"),
+            "indent_width = 4\nschema = \"schema.json\"\nreadonly = [\"secrets/**\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("schema.json"),
+            r#"{"properties": {"server": {"properties": {"port": {"type": "integer", "description": "Listen port"}}}}}"#,
+        )
+        .unwrap();
+        let values = root.join("values.yaml");
+        std::fs::write(&values, "server:\n  port: 80\n").unwrap();
+        std::fs::write(root.join("secrets/db.yaml"), "password: x\n").unwrap();
+
+        let mut app = App::new(&values, CliOverrides::default()).unwrap();
+        assert_eq!(app.read_only, None);
+        app.expanded.insert("server".to_string());
+        app.rebuild_visible();
+        let port = &app.visible[2].path;
+        assert_eq!(
+            app.schema.as_ref().and_then(|s| s.describe(port)),
+            Some("integer — Listen port".to_string())
+        );
+        app.dirty = true;
+        app.apply_action(InputAction::Save, 10).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&values).unwrap(),
+            "---\nserver:\n    port: 80\n"
+        );
+
+        let mut secret = App::new(&root.join("secrets/db.yaml"), CliOverrides::default()).unwrap();
+        assert!(secret.read_only.is_some());
+        secret.selection = 1;
+        secret.apply_action(InputAction::DeleteNode, 10).unwrap();
+        assert_eq!(secret.mode, Mode::Normal);
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn reload_keeps_folds_and_selection() {
+        let path = std::env::temp_dir().join(format!("yed_app_reload_{}.yaml", std::process::id()));
+        std::fs::write(&path, "a:\n  x: 1\nb:\n  y: 2\n  z: 3\nc:\n  w: 4\n").unwrap();
+        let mut app = App::new(&path, CliOverrides::default()).unwrap();
+        let z = NodePath(vec![
+            PathSegment::Key("b".into()),
+            PathSegment::Key("z".into()),
+        ]);
+        assert!(app.reveal_path(&NodePath(vec![
+            PathSegment::Key("c".into()),
+            PathSegment::Key("w".into())
+        ])));
+        assert!(app.reveal_path(&z));
+
+        // A formatter sorts the keys and drops `c`.
+        std::fs::write(&path, "new: 0\na: {x: 1}\nb: {y: 2, z: 3}\n").unwrap();
+        app.last_modified = Some(std::time::UNIX_EPOCH);
+        app.last_file_check = None;
+        app.check_and_reload_if_changed().unwrap();
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "File changed on disk, reloaded"
+        );
+        assert_eq!(app.current_row().unwrap().path, z);
+        assert!(app.expanded.contains("b"));
+        assert!(!app.expanded.contains("c"));
+        std::fs::remove_file(&path).ok();
+    }
 }
@@ -0,0 +1,130 @@
+// Key suggestions from the schema, or for Kubernetes manifests, while typing a new key
+// (`Mode::AddKey`).
+
+use yaml_rust2::Yaml;
+
+use super::{App, Mode};
+use crate::k8s;
+use crate::yaml_model::NodePath;
+
+impl App {
+    /// Mapping the key being typed will be added to.
+    fn add_key_target(&self) -> Option<NodePath> {
+        match &self.add_after {
+            Some((parent, _)) => Some(parent.clone()),
+            None => self.current_row().map(|row| row.path.clone()),
+        }
+    }
+
+    /// Schema property names (or, without a schema, the fields a Kubernetes manifest expects)
+    /// for the target mapping that it does not have yet and that start with what was typed.
+    /// Empty when neither says anything there.
+    pub fn key_completions(&self) -> Vec<String> {
+        if self.mode != Mode::AddKey {
+            return Vec::new();
+        }
+        let Some(target) = self.add_key_target() else {
+            return Vec::new();
+        };
+        let candidates = match &self.schema {
+            Some(schema) => schema.property_names(&target),
+            None => k8s::expected_keys(self.model.root(), &target).unwrap_or_default(),
+        };
+        let existing = self
+            .model
+            .node_at(&target)
+            .ok()
+            .and_then(|node| node.as_hash());
+        let typed = self.input.text.trim().to_lowercase();
+        candidates
+            .into_iter()
+            .filter(|name| name.to_lowercase().starts_with(&typed))
+            .filter(|name| {
+                existing.map_or(true, |hash| !hash.contains_key(&Yaml::String(name.clone())))
+            })
+            .collect()
+    }
+
+    /// Index of the highlighted suggestion in `completions`; the first when the highlighted
+    /// one was filtered out.
+    pub fn completion_index(&self, completions: &[String]) -> usize {
+        self.completion
+            .as_ref()
+            .and_then(|name| completions.iter().position(|candidate| candidate == name))
+            .unwrap_or(0)
+    }
+
+    /// Up/Down in the suggestion list.
+    pub(super) fn move_completion(&mut self, down: bool) {
+        let completions = self.key_completions();
+        if completions.is_empty() {
+            return;
+        }
+        let index = self.completion_index(&completions);
+        let index = if down {
+            (index + 1).min(completions.len() - 1)
+        } else {
+            index.saturating_sub(1)
+        };
+        self.completion = Some(completions[index].clone());
+    }
+
+    /// Tab: replace the typed text with the highlighted suggestion.
+    pub(super) fn accept_completion(&mut self) {
+        let completions = self.key_completions();
+        if let Some(name) = completions.get(self.completion_index(&completions)) {
+            self.input.set(name.clone());
+            self.completion = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::app_with;
+    use crate::input::InputAction;
+    use crate::yaml_model::PathSegment;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn add_key_suggests_missing_schema_properties() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = app_with("complete", "replicas: 1\nports:\n  - name: http\n");
+        app.schema = Some(
+            crate::schema::Schema::parse(
+                r#"{"properties": {"replicas": {}, "image": {}, "imagePullPolicy": {}, "env": {},
+                    "ports": {"items": {"properties": {"name": {}, "port": {}}}}}}"#,
+            )
+            .unwrap(),
+        );
+        let press = |app: &mut App, code: KeyCode| {
+            app.handle_key(KeyEvent::new(code, KeyModifiers::NONE), 10)
+                .unwrap();
+        };
+        app.selection = 0;
+        app.apply_action(InputAction::AddChild, 10).unwrap();
+        assert_eq!(app.key_completions(), ["image", "imagePullPolicy", "env"]);
+        press(&mut app, KeyCode::Char('i'));
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input.text, "imagePullPolicy");
+        assert!(app.key_completions().iter().eq(["imagePullPolicy"]));
+
+        // Scoped to the item's schema, and empty when there is none.
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+        assert!(app.reveal_path(&NodePath(vec![
+            PathSegment::Key("ports".into()),
+            PathSegment::Index(0)
+        ])));
+        app.apply_action(InputAction::AddChild, 10).unwrap();
+        assert_eq!(app.key_completions(), ["port"]);
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+        app.schema = None;
+        app.selection = 0;
+        app.apply_action(InputAction::AddChild, 10).unwrap();
+        assert!(app.key_completions().is_empty());
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input.text, "");
+    }
+}
@@ -0,0 +1,83 @@
+// Commands received on the control socket, carried out on the event loop.
+
+use anyhow::{anyhow, bail, Result};
+
+use super::{App, Mode};
+use crate::control::Command;
+use crate::input::InputAction;
+use crate::json_patch;
+use crate::yaml_model::{resolve_dot_path, NodePath};
+
+/// Actions a client may run by name: navigation, folding, saving and undo. Anything that
+/// opens a prompt or quits stays with the person at the keyboard.
+fn remote_action(name: &str) -> Option<InputAction> {
+    let action = match name {
+        "Save" => InputAction::Save,
+        "Undo" => InputAction::Undo,
+        "MoveUp" => InputAction::MoveUp,
+        "MoveDown" => InputAction::MoveDown,
+        "JumpTop" => InputAction::JumpTop,
+        "JumpBottom" => InputAction::JumpBottom,
+        "PageUp" => InputAction::PageUp,
+        "PageDown" => InputAction::PageDown,
+        "Expand" => InputAction::Expand,
+        "Collapse" => InputAction::Collapse,
+        "SearchNext" => InputAction::SearchNext,
+        "SearchPrev" => InputAction::SearchPrev,
+        "CopyPath" => InputAction::CopyPath,
+        "CopyPointer" => InputAction::CopyPointer,
+        _ => return None,
+    };
+    Some(action)
+}
+
+impl App {
+    /// Carry out a control command. Returns the JSON value to answer with, if any.
+    pub fn handle_control(
+        &mut self,
+        command: &Command,
+        area_height: usize,
+    ) -> Result<Option<String>> {
+        if self.is_file_picker() {
+            bail!("no file open");
+        }
+        // Prompts and overlays would take the command as their input.
+        if self.mode != Mode::Normal {
+            bail!("busy: a prompt is open");
+        }
+        match command {
+            Command::Goto(dot) => {
+                let path = self.control_path(dot)?;
+                if !self.reveal_path(&path) {
+                    bail!("{dot} is not shown in the tree");
+                }
+                self.ensure_visible(area_height);
+                Ok(None)
+            }
+            Command::Get(dot) => {
+                let path = self.control_path(dot)?;
+                Ok(Some(json_patch::json_value(self.model.node_at(&path)?)?))
+            }
+            Command::Reload => {
+                self.last_modified = None;
+                self.last_file_check = None;
+                self.check_and_reload_if_changed()?;
+                Ok(None)
+            }
+            Command::Action(name) => {
+                let action = remote_action(name).ok_or_else(|| {
+                    anyhow!("action '{name}' is not allowed over the control socket")
+                })?;
+                self.apply_action(action, area_height)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn control_path(&self, dot: &str) -> Result<NodePath> {
+        if self.raw_content.is_some() {
+            bail!("the file does not parse");
+        }
+        resolve_dot_path(self.model.root(), dot).ok_or_else(|| anyhow!("no node at {dot}"))
+    }
+}
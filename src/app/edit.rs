@@ -0,0 +1,1414 @@
+// Tree edits: starting each editor or prompt, committing it, and journaling the result.
+
+use anyhow::Result;
+use yaml_rust2::Yaml;
+
+use super::{App, Mode};
+use crate::input::InputAction;
+use crate::journal::EditKind;
+use crate::widgets::TextArea;
+use crate::yaml_model::{
+    node_preview, parse_edit_input, parse_scalar_input, yaml_node_type, AliasEdit, NodeOrigin,
+    NodePath, NodeType, PathSegment, ScalarValue, Snapshot,
+};
+
+impl App {
+    /// Ask how to apply a scalar edit to a value shared through an anchor. Returns true if the
+    /// prompt was opened and the action deferred.
+    pub(super) fn prompt_for_shared_edit(&mut self, action: &InputAction) -> bool {
+        let scalar_edit = matches!(
+            action,
+            InputAction::EditValue
+                | InputAction::EditMultiline
+                | InputAction::EditInEditor
+                | InputAction::ToggleBool
+                | InputAction::CastType
+        );
+        if !scalar_edit || self.shared_edit.is_some() {
+            return false;
+        }
+        let Some(row) = self.current_row() else {
+            return false;
+        };
+        if row.is_container
+            || matches!(
+                row.origin,
+                NodeOrigin::Inherited(_) | NodeOrigin::MergeKey(_)
+            )
+        {
+            return false;
+        }
+        let Some(shared) = self.model.shared_node(&row.path) else {
+            return false;
+        };
+        self.pending_shared_edit = Some((action.clone(), shared));
+        self.mode = Mode::ConfirmAlias;
+        true
+    }
+
+    pub(super) fn apply_alias_prompt_action(
+        &mut self,
+        action: InputAction,
+        area_height: usize,
+    ) -> Result<bool> {
+        let choice = match action {
+            InputAction::ConfirmYes => AliasEdit::Shared,
+            InputAction::ConfirmNo => AliasEdit::Break,
+            InputAction::Cancel => {
+                self.pending_shared_edit = None;
+                self.mode = Mode::Normal;
+                return Ok(false);
+            }
+            _ => return Ok(false),
+        };
+        self.mode = Mode::Normal;
+        let Some((deferred, shared)) = self.pending_shared_edit.take() else {
+            return Ok(false);
+        };
+        self.shared_edit = Some((shared, choice));
+        let result = self.apply_action(deferred, area_height);
+        // Toggle and cast finish at once; the editors keep the choice until they commit.
+        if !matches!(self.mode, Mode::EditValue | Mode::EditMultiline)
+            && self.external_edit.is_none()
+        {
+            self.shared_edit = None;
+        }
+        result
+    }
+
+    /// Path a scalar edit of the selected row changes (the anchor, for a shared edit).
+    fn edit_path(&self) -> Option<NodePath> {
+        let path = self.current_row()?.path.clone();
+        Some(match &self.shared_edit {
+            Some((shared, choice)) => self.model.shared_edit_path(&path, shared, *choice),
+            None => path,
+        })
+    }
+
+    /// Complete the shared/break choice after a successful scalar edit.
+    fn finish_shared_edit(&mut self) {
+        if let Some((shared, choice)) = self.shared_edit.take() {
+            self.model.finish_shared_edit(&shared, choice);
+        }
+    }
+
+    /// Document state and preview of the node at `path`, taken before an edit.
+    pub(super) fn begin_edit(&self, path: &NodePath) -> (Snapshot, String) {
+        (self.model.snapshot(), self.preview_at(path))
+    }
+
+    fn preview_at(&self, path: &NodePath) -> String {
+        self.model
+            .node_at(path)
+            .map(node_preview)
+            .unwrap_or_default()
+    }
+
+    /// Journal a completed edit of the node now at `path`.
+    pub(super) fn record_edit(
+        &mut self,
+        before: (Snapshot, String),
+        kind: EditKind,
+        path: NodePath,
+    ) {
+        let after = match kind {
+            EditKind::Delete => "(deleted)".to_string(),
+            EditKind::Style => style_label(self.model.is_flow(&path)).to_string(),
+            EditKind::Rename => match path.0.last() {
+                Some(PathSegment::Key(key)) => key.clone(),
+                _ => String::new(),
+            },
+            _ => self.preview_at(&path),
+        };
+        let (snapshot, before) = before;
+        self.journal.record(kind, path, before, after, snapshot);
+    }
+
+    /// Undo the newest `count` journal entries.
+    pub(super) fn undo(&mut self, count: usize) {
+        let Some((removed, snapshot)) = self.journal.undo(count) else {
+            self.set_toast("Nothing to undo".to_string());
+            return;
+        };
+        self.model.restore(snapshot);
+        self.dirty = true;
+        self.rebuild_visible();
+        if let Some(oldest) = removed.last() {
+            let mut path = oldest.path.clone();
+            while !self.reveal_path(&path) && path.0.pop().is_some() {}
+            if removed.len() == 1 {
+                self.set_toast(format!("Undid {} {}", oldest.kind, oldest.path.dot_path()));
+            } else {
+                self.set_toast(format!("Undid {} edits", removed.len()));
+            }
+        }
+    }
+
+    pub(super) fn apply_multiline_action(&mut self, action: InputAction) -> Result<()> {
+        let area = &mut self.textarea;
+        match action {
+            InputAction::InputChar(ch) => area.insert_char(ch),
+            InputAction::InputNewline => area.newline(),
+            InputAction::InputBackspace => area.backspace(),
+            InputAction::InputDelete => area.delete(),
+            InputAction::InputLeft => area.move_left(),
+            InputAction::InputRight => area.move_right(),
+            InputAction::InputUp => area.move_up(),
+            InputAction::InputDown => area.move_down(),
+            InputAction::InputHome => area.move_home(),
+            InputAction::InputEnd => area.move_end(),
+            InputAction::InputCommit => {
+                if let Some(path) = self.edit_path() {
+                    let text = self.textarea.text();
+                    let before = self.begin_edit(&path);
+                    self.model.edit_value(&path, ScalarValue::String(text))?;
+                    self.finish_shared_edit();
+                    self.record_edit(before, EditKind::Edit, path);
+                    self.dirty = true;
+                }
+                self.mode = Mode::Normal;
+                self.rebuild_visible();
+            }
+            InputAction::Cancel => {
+                self.shared_edit = None;
+                self.mode = Mode::Normal;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Rows reached through a merge key are read-only; returns true (with a toast) if blocked.
+    pub(super) fn block_merged_edit(&mut self) -> bool {
+        let message = match self.current_row().map(|r| &r.origin) {
+            Some(NodeOrigin::Inherited(label)) => {
+                format!("Inherited from {label}; edit the anchor instead")
+            }
+            _ => return false,
+        };
+        self.set_toast(message);
+        true
+    }
+
+    fn current_merge_label(&self) -> Option<String> {
+        match self.current_row().map(|r| &r.origin) {
+            Some(NodeOrigin::MergeKey(label)) => Some(label.clone()),
+            _ => None,
+        }
+    }
+
+    pub(super) fn start_edit_value(&mut self) -> Result<()> {
+        if self.block_merged_edit() {
+            return Ok(());
+        }
+        if let Some(label) = self.current_merge_label() {
+            self.set_toast(format!("Merge key: edit {label} at its anchor instead"));
+            return Ok(());
+        }
+        if self.start_edit_multiline(false) {
+            return Ok(());
+        }
+        // The tree preview is truncated; edit the whole value.
+        let value = self
+            .current_row()
+            .filter(|r| !r.is_container)
+            .and_then(|r| self.model.full_value(&r.path));
+        if let Some(value) = value {
+            self.mode = Mode::EditValue;
+            self.input.set(value);
+        }
+        Ok(())
+    }
+
+    /// Open the popup editor on the selected string. Without `force`, only strings that already
+    /// span several lines qualify. Returns whether the popup was opened.
+    pub(super) fn start_edit_multiline(&mut self, force: bool) -> bool {
+        if force && (self.block_merged_edit() || self.current_merge_label().is_some()) {
+            return false;
+        }
+        let Some(path) = self.current_row().map(|r| r.path.clone()) else {
+            return false;
+        };
+        let text = match self.model.node_at(&path) {
+            Ok(Yaml::String(value)) if force || value.contains('\n') => value.clone(),
+            Ok(Yaml::String(_)) => return false,
+            _ => {
+                if force {
+                    self.set_toast("Multi-line edit: not a string".to_string());
+                }
+                return false;
+            }
+        };
+        self.textarea = TextArea::new(&text);
+        self.mode = Mode::EditMultiline;
+        true
+    }
+
+    /// Ctrl+e: queue the selected scalar for `$EDITOR`; the main loop hands the terminal over and
+    /// calls [`App::finish_external_edit`] with the result.
+    pub(super) fn start_external_edit(&mut self) {
+        if self.block_merged_edit() {
+            return;
+        }
+        if let Some(label) = self.current_merge_label() {
+            self.set_toast(format!("Merge key: edit {label} at its anchor instead"));
+            return;
+        }
+        let value = self
+            .current_row()
+            .filter(|r| !r.is_container)
+            .and_then(|r| self.model.full_value(&r.path));
+        match (self.edit_path(), value) {
+            (Some(path), Some(value)) => self.external_edit = Some((path, value)),
+            _ => {
+                self.shared_edit = None;
+                self.set_toast("External editor: select a scalar value".to_string());
+            }
+        }
+    }
+
+    /// Store what the external editor returned for `path`. Multi-line text is kept as a string
+    /// (saved as a block scalar); a single line is read like typed input.
+    pub fn finish_external_edit(&mut self, path: NodePath, original: &str, edited: Result<String>) {
+        let edited = match edited {
+            Ok(text) => text,
+            Err(err) => {
+                self.shared_edit = None;
+                self.set_toast(format!("{err:#}"));
+                return;
+            }
+        };
+        // Editors end the file with a newline the value did not have.
+        let text = match edited.strip_suffix('\n') {
+            Some(stripped) if !original.ends_with('\n') => {
+                stripped.strip_suffix('\r').unwrap_or(stripped)
+            }
+            _ => edited.as_str(),
+        };
+        if text == original {
+            self.shared_edit = None;
+            self.set_toast("Value unchanged".to_string());
+            return;
+        }
+        let result = self.model.node_at(&path).cloned().and_then(|previous| {
+            let parsed = if text.contains('\n') {
+                ScalarValue::String(text.to_string())
+            } else {
+                parse_edit_input(text, &previous)?
+            };
+            let before = self.begin_edit(&path);
+            self.model.edit_value(&path, parsed)?;
+            Ok(before)
+        });
+        match result {
+            Ok(before) => {
+                self.finish_shared_edit();
+                self.record_edit(before, EditKind::Edit, path);
+                self.dirty = true;
+                self.rebuild_visible();
+            }
+            Err(err) => {
+                self.shared_edit = None;
+                self.set_toast(err.to_string());
+            }
+        }
+    }
+
+    /// Flip the selected boolean in place.
+    pub(super) fn toggle_bool(&mut self) {
+        if self.block_merged_edit() {
+            return;
+        }
+        let Some(path) = self.edit_path() else {
+            return;
+        };
+        let current = match self.model.node_at(&path) {
+            Ok(Yaml::Boolean(value)) => *value,
+            Ok(_) => {
+                self.set_toast("Not a boolean".to_string());
+                return;
+            }
+            Err(e) => {
+                self.set_toast(e.to_string());
+                return;
+            }
+        };
+        let before = self.begin_edit(&path);
+        match self.model.edit_value(&path, ScalarValue::Bool(!current)) {
+            Ok(()) => {
+                self.finish_shared_edit();
+                self.record_edit(before, EditKind::Toggle, path);
+                self.dirty = true;
+                self.rebuild_visible();
+            }
+            Err(e) => self.set_toast(e.to_string()),
+        }
+    }
+
+    /// Switch the selected map or sequence between flow and block style.
+    pub(super) fn toggle_flow_style(&mut self) {
+        if self.block_merged_edit() {
+            return;
+        }
+        let Some(path) = self.current_row().map(|r| r.path.clone()) else {
+            return;
+        };
+        let before = (
+            self.model.snapshot(),
+            style_label(self.model.is_flow(&path)).to_string(),
+        );
+        match self.model.toggle_flow_style(&path) {
+            Ok(flow) => {
+                self.record_edit(before, EditKind::Style, path.clone());
+                self.dirty = true;
+                let label = if path.0.is_empty() {
+                    "(root)".to_string()
+                } else {
+                    path.dot_path()
+                };
+                self.set_toast(format!("{label}: {} style", style_label(flow)));
+            }
+            Err(e) => self.set_toast(e.to_string()),
+        }
+    }
+
+    /// Sort the keys or items of the selected collection; a collection already in ascending
+    /// order is sorted descending instead.
+    pub(super) fn sort_selected(&mut self) {
+        if self.block_merged_edit() {
+            return;
+        }
+        let Some(path) = self.current_row().map(|r| r.path.clone()) else {
+            return;
+        };
+        let before = self.begin_edit(&path);
+        let mut descending = false;
+        let sorted = self.model.sort_children(&path, false).and_then(|changed| {
+            if changed {
+                return Ok(true);
+            }
+            descending = true;
+            self.model.sort_children(&path, true)
+        });
+        match sorted {
+            Ok(true) => {
+                self.record_edit(before, EditKind::Sort, path.clone());
+                self.dirty = true;
+                self.rebuild_visible();
+                let label = if path.0.is_empty() {
+                    "(root)".to_string()
+                } else {
+                    path.dot_path()
+                };
+                let order = if descending {
+                    "descending"
+                } else {
+                    "ascending"
+                };
+                self.set_toast(format!("{label}: sorted {order}"));
+            }
+            Ok(false) => self.set_toast("Nothing to sort".to_string()),
+            Err(e) => self.set_toast(e.to_string()),
+        }
+    }
+
+    /// Wrap the selected scalar in a one-item sequence (keeping it selected, now at index 0), or
+    /// unwrap a sequence holding a single scalar.
+    pub(super) fn toggle_wrap(&mut self) {
+        if self.block_merged_edit() {
+            return;
+        }
+        let Some(path) = self.current_row().map(|r| r.path.clone()) else {
+            return;
+        };
+        let wrap = match self.model.node_at(&path) {
+            Ok(Yaml::Array(_)) => false,
+            Ok(Yaml::Hash(_)) => {
+                self.set_toast("Only scalars can be wrapped in a sequence".to_string());
+                return;
+            }
+            Ok(_) => true,
+            Err(e) => {
+                self.set_toast(e.to_string());
+                return;
+            }
+        };
+        let before = self.begin_edit(&path);
+        let result = if wrap {
+            self.model.wrap_in_sequence(&path)
+        } else {
+            self.model.unwrap_sequence(&path)
+        };
+        if let Err(e) = result {
+            self.set_toast(e.to_string());
+            return;
+        }
+        self.record_edit(before, EditKind::Wrap, path.clone());
+        self.dirty = true;
+        if wrap {
+            self.expanded.insert(path.dot_path());
+            self.rebuild_visible();
+            self.restore_selection(Some(path.child_index(0)));
+        } else {
+            self.rebuild_visible();
+            self.restore_selection(Some(path));
+        }
+    }
+
+    /// Ask for the field to key the selected sequence of mappings by, or to put the selected
+    /// mapping's keys back under. Suggests the first item's first key, or `name`.
+    pub(super) fn start_rekey(&mut self) {
+        if self.block_merged_edit() {
+            return;
+        }
+        let Some(path) = self.current_row().map(|r| r.path.clone()) else {
+            return;
+        };
+        let suggestion = match self.model.node_at(&path) {
+            Ok(Yaml::Array(seq)) => seq
+                .first()
+                .and_then(Yaml::as_hash)
+                .and_then(|map| map.keys().next())
+                .and_then(Yaml::as_str)
+                .unwrap_or("name")
+                .to_string(),
+            Ok(Yaml::Hash(_)) => "name".to_string(),
+            _ => {
+                self.set_toast(
+                    "Only sequences of maps and maps of maps can be rekeyed".to_string(),
+                );
+                return;
+            }
+        };
+        self.input.set(suggestion);
+        self.mode = Mode::RekeyField;
+    }
+
+    /// Convert the selected collection using the field typed in `Mode::RekeyField`.
+    fn commit_rekey(&mut self) {
+        let field = self.input.text.trim().to_string();
+        if field.is_empty() {
+            self.set_toast("Field cannot be empty".to_string());
+            return;
+        }
+        let Some(path) = self.current_row().map(|r| r.path.clone()) else {
+            self.mode = Mode::Normal;
+            return;
+        };
+        let before = self.begin_edit(&path);
+        let to_map = matches!(self.model.node_at(&path), Ok(Yaml::Array(_)));
+        let result = if to_map {
+            self.model.sequence_to_map(&path, &field)
+        } else {
+            self.model.map_to_sequence(&path, &field)
+        };
+        self.mode = Mode::Normal;
+        self.input.set(String::new());
+        match result {
+            Ok(()) => {
+                self.record_edit(before, EditKind::Rekey, path.clone());
+                self.dirty = true;
+                self.rebuild_visible();
+                self.restore_selection(Some(path));
+                let done = if to_map {
+                    format!("keyed by {field}")
+                } else {
+                    format!("keys moved under {field}")
+                };
+                self.set_toast(format!("Converted: {done}"));
+            }
+            Err(e) => self.set_toast(e.to_string()),
+        }
+    }
+
+    /// Cycle the selected scalar to the next type (string → number → bool → null) that its
+    /// value can be converted to.
+    pub(super) fn cast_selected_type(&mut self) {
+        if self.block_merged_edit() {
+            return;
+        }
+        if let Some(label) = self.current_merge_label() {
+            self.set_toast(format!("Merge key: edit {label} at its anchor instead"));
+            return;
+        }
+        let Some(path) = self.edit_path() else {
+            return;
+        };
+        let current = match self.model.node_at(&path) {
+            Ok(Yaml::Hash(_) | Yaml::Array(_)) => {
+                self.set_toast("Cannot cast a container".to_string());
+                return;
+            }
+            // A timestamp is a string as far as casting goes.
+            Ok(node) => match yaml_node_type(node) {
+                NodeType::Timestamp => NodeType::String,
+                node_type => node_type,
+            },
+            Err(e) => {
+                self.set_toast(e.to_string());
+                return;
+            }
+        };
+        const CYCLE: [NodeType; 4] = [
+            NodeType::String,
+            NodeType::Number,
+            NodeType::Bool,
+            NodeType::Null,
+        ];
+        let start = CYCLE
+            .iter()
+            .position(|t| *t == current)
+            .unwrap_or(CYCLE.len() - 1);
+        let before = self.begin_edit(&path);
+        for step in 1..CYCLE.len() {
+            let target = CYCLE[(start + step) % CYCLE.len()].clone();
+            if self.model.cast_scalar(&path, target.clone()).is_ok() {
+                self.finish_shared_edit();
+                self.record_edit(before, EditKind::Cast, path);
+                self.dirty = true;
+                self.rebuild_visible();
+                self.set_toast(format!("Type: {target}"));
+                return;
+            }
+        }
+    }
+
+    pub(super) fn start_rename_key(&mut self) -> Result<()> {
+        if self.block_merged_edit() {
+            return Ok(());
+        }
+        if self.current_merge_label().is_some() {
+            self.mode = Mode::ConfirmRenameMerge;
+            return Ok(());
+        }
+        let row_data = self.current_row().map(|r| {
+            let is_key = r
+                .path
+                .0
+                .last()
+                .map(|seg| matches!(seg, crate::yaml_model::PathSegment::Key(_)))
+                == Some(true);
+            let is_root = r.path.0.is_empty();
+            (is_key, is_root, r.display_key.clone())
+        });
+        if let Some((is_key, is_root, display_key)) = row_data {
+            if is_key {
+                self.mode = Mode::RenameKey;
+                self.input.set(display_key);
+            } else if is_root {
+                self.set_toast("Root has no key to rename".to_string());
+            } else {
+                self.set_toast("Cannot rename sequence item".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Ask for the name every key named like the selected one should get.
+    pub(super) fn start_rename_everywhere(&mut self) {
+        if self.block_merged_edit() {
+            return;
+        }
+        match self.current_row().map(|r| r.path.0.last().cloned()) {
+            Some(Some(PathSegment::Key(key))) => {
+                self.input.set(key);
+                self.mode = Mode::RenameKeyEverywhere;
+            }
+            Some(_) => self.set_toast("Select a key to rename everywhere".to_string()),
+            None => {}
+        }
+    }
+
+    fn commit_rename_everywhere(&mut self) {
+        let Some(path) = self.current_row().map(|r| r.path.clone()) else {
+            self.mode = Mode::Normal;
+            return;
+        };
+        let Some(PathSegment::Key(old)) = path.0.last().cloned() else {
+            self.mode = Mode::Normal;
+            return;
+        };
+        let new = self.input.text.trim().to_string();
+        let before = (self.model.snapshot(), old.clone());
+        match self.model.rename_key_everywhere(&old, &new) {
+            Ok((renamed, skipped)) => {
+                self.mode = Mode::Normal;
+                self.input.set(String::new());
+                if renamed > 0 {
+                    let mut selected = path.clone();
+                    selected.0.pop();
+                    let selected = selected.child_key(&new);
+                    self.record_edit(before, EditKind::Rename, selected.clone());
+                    self.dirty = true;
+                    self.rebuild_visible();
+                    self.restore_selection(Some(selected));
+                }
+                let occurrences = if renamed == 1 {
+                    "occurrence"
+                } else {
+                    "occurrences"
+                };
+                let skips = if skipped > 0 {
+                    format!(", skipped {skipped} where {new} exists")
+                } else {
+                    String::new()
+                };
+                self.set_toast(format!("Renamed {renamed} {occurrences}{skips}"));
+            }
+            Err(e) => self.set_toast(e.to_string()),
+        }
+    }
+
+    pub(super) fn start_add_child(&mut self) -> Result<()> {
+        if self.block_merged_edit() {
+            return Ok(());
+        }
+        if let Some(label) = self.current_merge_label() {
+            self.set_toast(format!(
+                "Merge key: add keys to {label} at its anchor instead"
+            ));
+            return Ok(());
+        }
+        let row_data = self.current_row().map(|r| {
+            let is_mapping_key = r
+                .path
+                .0
+                .last()
+                .map(|seg| matches!(seg, crate::yaml_model::PathSegment::Key(_)))
+                == Some(true);
+            (r.path.clone(), r.node_type.clone(), is_mapping_key)
+        });
+        if let Some((path, node_type, is_mapping_key)) = row_data {
+            if node_type == NodeType::Map {
+                self.mode = Mode::AddKey;
+                self.input.set(String::new());
+            } else if node_type == NodeType::Seq {
+                self.mode = Mode::AddValue;
+                self.input.set(String::new());
+            } else if is_mapping_key {
+                let before = self.begin_edit(&path);
+                if let Err(e) = self.model.convert_to_empty_map(&path) {
+                    self.set_toast(e.to_string());
+                } else {
+                    self.record_edit(before, EditKind::Edit, path);
+                    self.dirty = true;
+                    self.rebuild_visible();
+                    self.mode = Mode::AddKey;
+                    self.input.set(String::new());
+                }
+            } else {
+                self.set_toast("Cannot add child to scalar".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a key (in a mapping) or item (in a sequence) right after the selected one.
+    pub(super) fn start_add_sibling(&mut self) {
+        if self.block_merged_edit() {
+            return;
+        }
+        if let Some(label) = self.current_merge_label() {
+            self.set_toast(format!(
+                "Merge key: add keys to {label} at its anchor instead"
+            ));
+            return;
+        }
+        let Some(path) = self.current_row().map(|r| r.path.clone()) else {
+            return;
+        };
+        let mut parent = path.clone();
+        let Some(last) = parent.0.pop() else {
+            self.set_toast("The root has no siblings; use 'a' to add a child".to_string());
+            return;
+        };
+        self.mode = match last {
+            PathSegment::Key(_) => Mode::AddKey,
+            PathSegment::Index(_) => Mode::AddValue,
+        };
+        self.add_after = Some((parent, last));
+        self.input.set(String::new());
+    }
+
+    /// Insert the value typed in `Mode::AddValue` after the sibling chosen by `o`. Returns the
+    /// new node's path.
+    fn add_sibling(
+        &mut self,
+        parent: &NodePath,
+        anchor: &PathSegment,
+        value: ScalarValue,
+    ) -> Result<NodePath> {
+        match anchor {
+            PathSegment::Key(anchor_key) => {
+                let key = self.pending_key.clone().unwrap_or_default();
+                self.model
+                    .add_mapping_child_after(parent, anchor_key, &key, value)?;
+                self.pending_key = None;
+                Ok(parent.child_key(&key))
+            }
+            PathSegment::Index(index) => self
+                .model
+                .insert_sequence_value_after(parent, *index, value),
+        }
+    }
+
+    /// Add an empty map to the current sequence, then start AddKey on the new element.
+    /// Use Shift+A on a sequence (list) to add a new object and type its first key.
+    pub(super) fn start_add_map_to_sequence(&mut self) -> Result<()> {
+        if self.block_merged_edit() {
+            return Ok(());
+        }
+        let path = self
+            .current_row()
+            .map(|r| (r.path.clone(), r.node_type.clone()));
+        if let Some((path, node_type)) = path {
+            if node_type != NodeType::Seq {
+                self.set_toast(
+                    "Shift+A: only on a sequence (list). Use 'a' to add a value.".to_string(),
+                );
+                return Ok(());
+            }
+            let before = (self.model.snapshot(), "(none)".to_string());
+            match self.model.add_sequence_empty_map(&path) {
+                Ok(new_path) => {
+                    self.record_edit(before, EditKind::Add, new_path.clone());
+                    self.dirty = true;
+                    self.expanded.insert(path.dot_path());
+                    self.rebuild_visible();
+                    self.restore_selection(Some(new_path));
+                    self.mode = Mode::AddKey;
+                    self.input.set(String::new());
+                }
+                Err(e) => self.set_toast(e.to_string()),
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn start_delete_node(&mut self) -> Result<()> {
+        if self.block_merged_edit() {
+            return Ok(());
+        }
+        if self.current_row().is_some() {
+            self.mode = Mode::ConfirmDelete;
+        }
+        Ok(())
+    }
+
+    /// Delete the selected node once `Mode::ConfirmDelete` is confirmed.
+    pub(super) fn delete_selected(&mut self) -> Result<()> {
+        let path = self.current_row().map(|r| r.path.clone());
+        if let Some(path) = path {
+            let before = self.begin_edit(&path);
+            self.model.delete_node(&path)?;
+            self.record_edit(before, EditKind::Delete, path);
+            self.dirty = true;
+            self.rebuild_visible();
+        }
+        self.mode = Mode::Normal;
+        Ok(())
+    }
+
+    /// Paste the register into the selected container. Mappings need a key: the yanked key is
+    /// used when free, otherwise the user is prompted.
+    pub(super) fn start_paste(&mut self) -> Result<()> {
+        if self.block_merged_edit() {
+            return Ok(());
+        }
+        let register = match &self.register {
+            Some(r) => r.clone(),
+            None => {
+                self.set_toast("Nothing yanked (use yy)".to_string());
+                return Ok(());
+            }
+        };
+        let row_data = self
+            .current_row()
+            .map(|r| (r.path.clone(), r.node_type.clone(), r.is_container));
+        let Some((path, node_type, is_container)) = row_data else {
+            return Ok(());
+        };
+        if !is_container {
+            self.set_toast("Cannot paste into a scalar".to_string());
+            return Ok(());
+        }
+        if node_type == NodeType::Map {
+            let key_free = register
+                .key
+                .as_ref()
+                .map(|key| self.model.node_at(&path.child_key(key)).is_err());
+            if key_free != Some(true) {
+                self.mode = Mode::PasteKey;
+                self.input.set(register.key.unwrap_or_default());
+                return Ok(());
+            }
+        }
+        self.paste_into(&path, register.key.as_deref(), &register.value);
+        Ok(())
+    }
+
+    fn paste_into(&mut self, path: &NodePath, key: Option<&str>, value: &Yaml) {
+        let before = (self.model.snapshot(), "(none)".to_string());
+        match self.model.paste_node(path, key, value) {
+            Ok(new_path) => {
+                self.record_edit(before, EditKind::Paste, new_path.clone());
+                self.dirty = true;
+                self.mode = Mode::Normal;
+                self.expanded.insert(path.dot_path());
+                self.rebuild_visible();
+                self.restore_selection(Some(new_path));
+                self.set_toast("Pasted".to_string());
+            }
+            Err(e) => self.set_toast(e.to_string()),
+        }
+    }
+
+    pub(super) fn commit_input(&mut self) -> Result<()> {
+        match self.mode {
+            Mode::EditValue => {
+                if let Some(path) = self.edit_path() {
+                    let previous = self.model.node_at(&path)?;
+                    let was_timestamp = yaml_node_type(previous) == NodeType::Timestamp;
+                    let parsed = parse_edit_input(&self.input.text, previous)?;
+                    let before = self.begin_edit(&path);
+                    self.model.edit_value(&path, parsed)?;
+                    self.finish_shared_edit();
+                    // Saved either way; the warning only flags a likely typo in a date field.
+                    if was_timestamp
+                        && yaml_node_type(self.model.node_at(&path)?) != NodeType::Timestamp
+                    {
+                        self.set_toast(format!(
+                            "Warning: {} is not a valid timestamp",
+                            self.input.text.trim()
+                        ));
+                    }
+                    self.record_edit(before, EditKind::Edit, path);
+                    self.dirty = true;
+                }
+                self.mode = Mode::Normal;
+                self.rebuild_visible();
+            }
+            Mode::RenameKey => {
+                let path = self.current_row().map(|r| r.path.clone());
+                if let Some(path) = path {
+                    let key_trimmed = self.input.text.trim().to_string();
+                    let old_key = match path.0.last() {
+                        Some(PathSegment::Key(key)) => key.clone(),
+                        _ => String::new(),
+                    };
+                    let before = (self.model.snapshot(), old_key);
+                    if key_trimmed.is_empty() {
+                        self.set_toast("Key cannot be empty".to_string());
+                    } else if let Err(e) = self.model.rename_key(&path, &key_trimmed) {
+                        self.set_toast(e.to_string());
+                    } else {
+                        let mut renamed = path.clone();
+                        renamed.0.pop();
+                        self.record_edit(before, EditKind::Rename, renamed.child_key(&key_trimmed));
+                        self.dirty = true;
+                        self.mode = Mode::Normal;
+                        self.rebuild_visible();
+                    }
+                } else {
+                    self.mode = Mode::Normal;
+                }
+            }
+            Mode::AddKey => {
+                let key_trimmed = self.input.text.trim().to_string();
+                if key_trimmed.is_empty() {
+                    self.set_toast("Key cannot be empty".to_string());
+                } else {
+                    self.pending_key = Some(key_trimmed);
+                    self.mode = Mode::AddValue;
+                    self.input.set(String::new());
+                }
+            }
+            Mode::AddValue if self.add_after.is_some() => {
+                let Some((parent, anchor)) = self.add_after.clone() else {
+                    return Ok(());
+                };
+                let added = parse_scalar_input(self.input.text.trim()).and_then(|parsed| {
+                    let before = (self.model.snapshot(), "(none)".to_string());
+                    let path = self.add_sibling(&parent, &anchor, parsed)?;
+                    Ok((before, path))
+                });
+                match added {
+                    Ok((before, path)) => {
+                        self.record_edit(before, EditKind::Add, path.clone());
+                        self.add_after = None;
+                        self.dirty = true;
+                        self.mode = Mode::Normal;
+                        self.rebuild_visible();
+                        self.restore_selection(Some(path));
+                    }
+                    Err(e) => self.set_toast(e.to_string()),
+                }
+            }
+            Mode::AddValue => {
+                let row_data = self
+                    .current_row()
+                    .map(|r| (r.path.clone(), r.node_type.clone()));
+                if let Some((path, node_type)) = row_data {
+                    match parse_scalar_input(self.input.text.trim()) {
+                        Ok(parsed) => {
+                            if node_type == NodeType::Map {
+                                if let Some(key) = self.pending_key.take() {
+                                    let before = (self.model.snapshot(), "(none)".to_string());
+                                    if let Err(e) =
+                                        self.model.add_mapping_child(&path, key.trim(), parsed)
+                                    {
+                                        self.set_toast(e.to_string());
+                                    } else {
+                                        self.record_edit(
+                                            before,
+                                            EditKind::Add,
+                                            path.child_key(key.trim()),
+                                        );
+                                        self.dirty = true;
+                                        self.mode = Mode::Normal;
+                                        self.rebuild_visible();
+                                    }
+                                } else {
+                                    self.mode = Mode::Normal;
+                                }
+                            } else if node_type == NodeType::Seq {
+                                let before = (self.model.snapshot(), "(none)".to_string());
+                                if let Err(e) = self.model.add_sequence_value(&path, parsed) {
+                                    self.set_toast(e.to_string());
+                                } else {
+                                    let len = self
+                                        .model
+                                        .node_at(&path)
+                                        .ok()
+                                        .and_then(Yaml::as_vec)
+                                        .map_or(0, Vec::len);
+                                    self.record_edit(
+                                        before,
+                                        EditKind::Add,
+                                        path.child_index(len.saturating_sub(1)),
+                                    );
+                                    self.dirty = true;
+                                    self.mode = Mode::Normal;
+                                    self.rebuild_visible();
+                                }
+                            } else {
+                                self.mode = Mode::Normal;
+                            }
+                        }
+                        Err(e) => self.set_toast(e.to_string()),
+                    }
+                } else {
+                    self.mode = Mode::Normal;
+                }
+            }
+            Mode::SearchInput => self.commit_search(),
+            Mode::PasteKey => {
+                let key_trimmed = self.input.text.trim().to_string();
+                let path = self.current_row().map(|r| r.path.clone());
+                let value = self.register.as_ref().map(|r| r.value.clone());
+                if key_trimmed.is_empty() {
+                    self.set_toast("Key cannot be empty".to_string());
+                } else if let (Some(path), Some(value)) = (path, value) {
+                    self.paste_into(&path, Some(&key_trimmed), &value);
+                } else {
+                    self.mode = Mode::Normal;
+                }
+            }
+            Mode::RawEditLine => self.commit_raw_line(),
+            Mode::SaveAsPath => self.commit_save_as()?,
+            Mode::ExportPath => self.commit_export(),
+            Mode::RekeyField => self.commit_rekey(),
+            Mode::RenameKeyEverywhere => self.commit_rename_everywhere(),
+            Mode::ReplaceCommand => self.commit_replace(),
+            Mode::PipeCommand => self.commit_pipe(),
+            Mode::GotoPath => self.commit_goto(),
+            Mode::MergeValue => self.commit_merge_value()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn style_label(flow: bool) -> &'static str {
+    if flow {
+        "flow"
+    } else {
+        "block"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::app_with;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn toggle_flips_booleans_and_refuses_other_values() {
+        let mut app = app_with("toggle", "enabled: true\nname: web\n");
+        app.apply_action(InputAction::MoveDown, 10).unwrap();
+        app.apply_action(InputAction::ToggleBool, 10).unwrap();
+        assert_eq!(app.model.root()["enabled"], Yaml::Boolean(false));
+        assert!(app.dirty);
+        app.apply_action(InputAction::ToggleBool, 10).unwrap();
+        assert_eq!(app.model.root()["enabled"], Yaml::Boolean(true));
+
+        app.dirty = false;
+        app.apply_action(InputAction::MoveDown, 10).unwrap();
+        app.apply_action(InputAction::ToggleBool, 10).unwrap();
+        assert_eq!(app.toast.as_ref().unwrap().message, "Not a boolean");
+        assert_eq!(app.model.root()["name"], Yaml::String("web".into()));
+        assert!(!app.dirty);
+    }
+
+    #[test]
+    fn sort_toggles_direction_and_undoes() {
+        let mut app = app_with("sort", "env:\n  b: 1\n  C: 2\n  a: 3\n");
+        let keys = |app: &App| -> Vec<String> {
+            app.model.root()["env"]
+                .as_hash()
+                .unwrap()
+                .keys()
+                .map(|k| k.as_str().unwrap().to_string())
+                .collect()
+        };
+        app.apply_action(InputAction::MoveDown, 10).unwrap();
+        app.apply_action(InputAction::SortChildren, 10).unwrap();
+        assert_eq!(keys(&app), ["a", "b", "C"]);
+        assert_eq!(app.toast.as_ref().unwrap().message, "env: sorted ascending");
+        assert!(app.dirty);
+        app.apply_action(InputAction::SortChildren, 10).unwrap();
+        assert_eq!(keys(&app), ["C", "b", "a"]);
+        assert_eq!(
+            app.journal.newest_first().next().unwrap().kind,
+            crate::journal::EditKind::Sort
+        );
+        app.apply_action(InputAction::Undo, 10).unwrap();
+        app.apply_action(InputAction::Undo, 10).unwrap();
+        assert_eq!(keys(&app), ["b", "C", "a"]);
+
+        app.apply_action(InputAction::Expand, 10).unwrap();
+        app.apply_action(InputAction::MoveDown, 10).unwrap();
+        app.apply_action(InputAction::SortChildren, 10).unwrap();
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Only maps and sequences can be sorted"
+        );
+        assert_eq!(keys(&app), ["b", "C", "a"]);
+    }
+
+    #[test]
+    fn external_editor_results_are_stored_like_edits() {
+        let mut app = app_with(
+            "external",
+            "port: 80
+note: short
+list: [1]
+",
+        );
+        let path = |key: &str| NodePath(vec![PathSegment::Key(key.into())]);
+        app.selection = 1;
+        app.apply_action(InputAction::EditInEditor, 10).unwrap();
+        let (queued, text) = app.external_edit.take().unwrap();
+        assert_eq!((queued.dot_path().as_str(), text.as_str()), ("port", "80"));
+        // The newline the editor adds is dropped and the number stays a number.
+        app.finish_external_edit(queued, &text, Ok("8080\n".to_string()));
+        assert_eq!(
+            app.model.node_at(&path("port")).unwrap(),
+            &Yaml::Integer(8080)
+        );
+        assert!(app.dirty);
+
+        app.finish_external_edit(
+            path("note"),
+            "short",
+            Ok("line one\nline two\n".to_string()),
+        );
+        assert_eq!(
+            app.model.node_at(&path("note")).unwrap(),
+            &Yaml::String("line one\nline two".to_string())
+        );
+        app.finish_external_edit(
+            path("note"),
+            "line one\nline two",
+            Ok("line one\nline two\n".to_string()),
+        );
+        assert_eq!(app.toast.as_ref().unwrap().message, "Value unchanged");
+        app.finish_external_edit(
+            path("note"),
+            "x",
+            Err(anyhow::anyhow!("vi exited with status 1; value unchanged")),
+        );
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "vi exited with status 1; value unchanged"
+        );
+        assert_eq!(app.journal.len(), 2);
+
+        app.selection = 3;
+        app.apply_action(InputAction::EditInEditor, 10).unwrap();
+        assert!(app.external_edit.is_none());
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "External editor: select a scalar value"
+        );
+    }
+
+    #[test]
+    fn timestamps_are_typed_and_checked_on_edit() {
+        let mut app = app_with("timestamp", "created: 2024-01-01T00:00:00Z\nname: api\n");
+        assert_eq!(app.visible[1].node_type, NodeType::Timestamp);
+        assert_eq!(app.visible[2].node_type, NodeType::String);
+
+        app.selection = 1;
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        app.input.set("2024-02-30".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        // Kept as typed, with a warning rather than an error.
+        assert_eq!(
+            app.model.node_at(&app.visible[1].path).unwrap(),
+            &Yaml::String("2024-02-30".to_string())
+        );
+        assert_eq!(app.visible[1].node_type, NodeType::String);
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Warning: 2024-02-30 is not a valid timestamp"
+        );
+
+        app.toast = None;
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        app.input.set("2024-02-29".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.visible[1].node_type, NodeType::Timestamp);
+        assert!(app.toast.is_none());
+    }
+
+    #[test]
+    fn multiline_strings_open_popup_and_commit_raw_text() {
+        let mut app = app_with("multiline", "note: \"one\\ntwo\"\nplain: x\n");
+        app.selection = 1;
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        assert_eq!(app.mode, Mode::EditMultiline);
+        assert_eq!(app.textarea.lines, vec!["one", "two"]);
+        for action in [
+            InputAction::InputUp,
+            InputAction::InputEnd,
+            InputAction::InputNewline,
+            InputAction::InputChar('!'),
+            InputAction::InputCommit,
+        ] {
+            app.apply_action(action, 10).unwrap();
+        }
+        assert_eq!(app.mode, Mode::Normal);
+        let note = app.model.node_at(&app.visible[1].path).unwrap();
+        assert_eq!(note.as_str(), Some("one\n!\ntwo"));
+
+        app.selection = 2;
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        assert_eq!(app.mode, Mode::EditValue);
+    }
+
+    #[test]
+    fn editing_a_shared_value_asks_shared_or_break() {
+        let mut app = app_with("alias", "base: &b 1\nuse: *b\nother: *b\n");
+        assert_eq!(app.visible[2].origin, NodeOrigin::Alias("*b".into()));
+        let value =
+            |app: &App, row: usize| app.model.node_at(&app.visible[row].path).unwrap().clone();
+
+        app.selection = 1;
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        assert_eq!(app.mode, Mode::ConfirmAlias);
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+
+        // Shared: editing through an alias changes the anchor and every alias.
+        app.selection = 2;
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        app.apply_action(InputAction::ConfirmYes, 10).unwrap();
+        assert_eq!(app.mode, Mode::EditValue);
+        app.input.set("5".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        for row in 1..=3 {
+            assert_eq!(value(&app, row), Yaml::Integer(5));
+        }
+
+        // Break: only this alias becomes its own value.
+        app.selection = 3;
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        app.apply_action(InputAction::ConfirmNo, 10).unwrap();
+        app.input.set("9".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(
+            (value(&app, 1), value(&app, 3)),
+            (Yaml::Integer(5), Yaml::Integer(9))
+        );
+        assert_eq!(app.visible[2].origin, NodeOrigin::Alias("*b".into()));
+        assert_eq!(app.visible[3].origin, NodeOrigin::Own);
+    }
+
+    #[test]
+    fn add_sibling_inserts_after_the_selection() {
+        let mut app = app_with("sibling", "first: 1\nlast: 2\nports: [80, 443]\n");
+        let commit = |app: &mut App, text: &str| {
+            app.input.set(text.to_string());
+            app.apply_action(InputAction::InputCommit, 10).unwrap();
+        };
+        app.apply_action(InputAction::MoveDown, 10).unwrap();
+        app.apply_action(InputAction::AddSibling, 10).unwrap();
+        assert_eq!(app.mode, Mode::AddKey);
+        commit(&mut app, "second");
+        assert_eq!(app.mode, Mode::AddValue);
+        commit(&mut app, "true");
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "second");
+        let keys: Vec<&str> = app
+            .model
+            .root()
+            .as_hash()
+            .unwrap()
+            .keys()
+            .filter_map(Yaml::as_str)
+            .collect();
+        assert_eq!(keys, ["first", "second", "last", "ports"]);
+
+        app.reveal_path(&NodePath(vec![
+            PathSegment::Key("ports".into()),
+            PathSegment::Index(0),
+        ]));
+        app.apply_action(InputAction::AddSibling, 10).unwrap();
+        assert_eq!(app.mode, Mode::AddValue);
+        commit(&mut app, "8080");
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "ports.1");
+        assert_eq!(app.model.root()["ports"][1], Yaml::Integer(8080));
+        app.apply_action(InputAction::Undo, 10).unwrap();
+        assert_eq!(app.model.root()["ports"][1], Yaml::Integer(443));
+
+        app.apply_action(InputAction::JumpTop, 10).unwrap();
+        app.apply_action(InputAction::AddSibling, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        // Cancelling a sibling add leaves `a` adding children again.
+        app.apply_action(InputAction::MoveDown, 10).unwrap();
+        app.apply_action(InputAction::AddSibling, 10).unwrap();
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+        assert!(app.add_after.is_none());
+    }
+
+    #[test]
+    fn rename_everywhere_reports_and_undoes() {
+        let mut app = app_with(
+            "rename_all",
+            "a:\n  hostname: x\nb:\n  hostname: y\n  host: z\nc:\n  - hostname: w\n",
+        );
+        assert!(app.reveal_path(&NodePath(vec![
+            PathSegment::Key("a".into()),
+            PathSegment::Key("hostname".into())
+        ])));
+        app.apply_action(InputAction::RenameKeyEverywhere, 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::RenameKeyEverywhere);
+        assert_eq!(app.input.text, "hostname");
+        app.input.set("host".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Renamed 2 occurrences, skipped 1 where host exists"
+        );
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "a.host");
+        assert_eq!(app.model.root()["c"][0]["host"], Yaml::String("w".into()));
+        assert!(app.dirty);
+
+        app.apply_action(InputAction::Undo, 10).unwrap();
+        assert_eq!(app.model.root()["a"]["hostname"], Yaml::String("x".into()));
+        app.apply_action(InputAction::JumpTop, 10).unwrap();
+        app.apply_action(InputAction::RenameKeyEverywhere, 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Select a key to rename everywhere"
+        );
+    }
+
+    #[test]
+    fn wrap_selects_the_item_and_unwraps_back() {
+        let mut app = app_with(
+            "wrap",
+            "name: web
+env:
+  a: 1
+",
+        );
+        app.apply_action(InputAction::MoveDown, 10).unwrap();
+        app.apply_action(InputAction::ToggleWrap, 10).unwrap();
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "name.0");
+        assert_eq!(
+            app.model.root()["name"],
+            Yaml::Array(vec![Yaml::String("web".into())])
+        );
+        assert!(app.dirty);
+        assert_eq!(
+            app.journal.newest_first().next().unwrap().kind,
+            crate::journal::EditKind::Wrap
+        );
+
+        app.apply_action(InputAction::MoveUp, 10).unwrap();
+        app.apply_action(InputAction::ToggleWrap, 10).unwrap();
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "name");
+        assert_eq!(app.model.root()["name"], Yaml::String("web".into()));
+
+        app.apply_action(InputAction::ToggleWrap, 10).unwrap();
+        app.apply_action(InputAction::Undo, 10).unwrap();
+        assert_eq!(app.model.root()["name"], Yaml::String("web".into()));
+
+        app.reveal_path(&NodePath(vec![PathSegment::Key("env".into())]));
+        app.apply_action(InputAction::ToggleWrap, 10).unwrap();
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Only scalars can be wrapped in a sequence"
+        );
+    }
+
+    #[test]
+    fn rekey_prompts_for_the_field_and_undoes() {
+        let mut app = app_with(
+            "rekey",
+            "users:\n  - name: ann\n    admin: true\n  - name: bob\n",
+        );
+        app.apply_action(InputAction::MoveDown, 10).unwrap();
+        app.apply_action(InputAction::Rekey, 10).unwrap();
+        assert_eq!(app.mode, Mode::RekeyField);
+        assert_eq!(app.input.text, "name");
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.dirty);
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "users");
+        let keys: Vec<&str> = app.model.root()["users"]
+            .as_hash()
+            .unwrap()
+            .keys()
+            .filter_map(Yaml::as_str)
+            .collect();
+        assert_eq!(keys, ["ann", "bob"]);
+        assert_eq!(
+            app.model.root()["users"]["ann"]["admin"],
+            Yaml::Boolean(true)
+        );
+
+        app.apply_action(InputAction::Rekey, 10).unwrap();
+        app.input.set("admin".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.toast.as_ref().unwrap().message, "ann already has admin");
+        assert!(app.model.root()["users"].as_hash().is_some());
+
+        app.apply_action(InputAction::Undo, 10).unwrap();
+        assert_eq!(
+            app.model.root()["users"][1]["name"],
+            Yaml::String("bob".into())
+        );
+        app.apply_action(InputAction::Rekey, 10).unwrap();
+        app.input.set("admin".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.toast.as_ref().unwrap().message, "Item 1 has no admin");
+    }
+}
@@ -0,0 +1,214 @@
+// Exports of the document or the selected subtree: JSON (`x`, `X`, Ctrl+x) and TOML (Ctrl+t).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{App, Mode};
+use crate::clipboard;
+use crate::toml_convert;
+use crate::yaml_model::NodePath;
+
+impl App {
+    /// JSON for the selected subtree, or for the document when `subtree` is false. Failures are
+    /// toasted, so nothing partial is ever written.
+    fn export_text(&mut self, subtree: bool) -> Option<(String, Option<NodePath>)> {
+        if self.raw_content.is_some() {
+            self.set_toast("The file does not parse; fix it before exporting".to_string());
+            return None;
+        }
+        // The root row stands for the whole document.
+        let path = if subtree {
+            Some(self.current_row()?.path.clone()).filter(|path| !path.0.is_empty())
+        } else {
+            None
+        };
+        match self.model.to_json(path.as_ref()) {
+            Ok(json) => Some((json, path)),
+            Err(err) => {
+                self.set_toast(format!("Cannot export to JSON: {err}"));
+                None
+            }
+        }
+    }
+
+    /// Ask where to write the JSON, suggesting `name.json` (or `name.spec.ports.json` for a
+    /// subtree) next to the file.
+    pub(super) fn start_export_json(&mut self, subtree: bool) {
+        let Some((json, path)) = self.export_text(subtree) else {
+            return;
+        };
+        let suffix = match path {
+            Some(path) => format!(".{}.json", path.dot_path()),
+            None => ".json".to_string(),
+        };
+        self.ask_export_path(json, &suffix);
+    }
+
+    /// Ask where to write the document as TOML, suggesting `name.toml` next to the file.
+    pub(super) fn start_export_toml(&mut self) {
+        if self.raw_content.is_some() {
+            self.set_toast("The file does not parse; fix it before exporting".to_string());
+            return;
+        }
+        match toml_convert::to_toml_string(self.model.root()) {
+            Ok(text) => self.ask_export_path(text, ".toml"),
+            Err(err) => self.set_toast(format!("Cannot export to TOML: {err}")),
+        }
+    }
+
+    /// Hold `text` and prompt for a path, prefilled with the file's stem plus `suffix`.
+    fn ask_export_path(&mut self, text: String, suffix: &str) {
+        let file = Path::new(self.model.file_path());
+        let stem = file
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = format!("{stem}{suffix}");
+        let target = file
+            .parent()
+            .map(|dir| dir.join(&name))
+            .unwrap_or_else(|| PathBuf::from(&name));
+        self.export = Some(text);
+        self.input.set(target.display().to_string());
+        self.mode = Mode::ExportPath;
+    }
+
+    /// Check the path typed in `Mode::ExportPath`, asking before replacing a file.
+    pub(super) fn commit_export(&mut self) {
+        let typed = self.input.text.trim();
+        if typed.is_empty() {
+            self.set_toast("File name cannot be empty".to_string());
+            return;
+        }
+        let path = PathBuf::from(typed);
+        if path == Path::new(self.model.file_path()) {
+            self.set_toast("Cannot export over the file being edited".to_string());
+            return;
+        }
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        if !dir.is_dir() {
+            self.set_toast(format!("No such directory: {}", dir.display()));
+            return;
+        }
+        self.input.set(String::new());
+        if path.exists() {
+            self.pending_export = Some(path);
+            self.mode = Mode::ConfirmExportOverwrite;
+            return;
+        }
+        self.mode = Mode::Normal;
+        self.write_export(&path);
+    }
+
+    pub(super) fn write_export(&mut self, path: &Path) {
+        let Some(mut text) = self.export.take() else {
+            return;
+        };
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        match fs::write(path, text) {
+            Ok(()) => self.set_toast(format!("Exported to {}", path.display())),
+            Err(err) => self.set_toast(format!("Writing {}: {err}", path.display())),
+        }
+    }
+
+    pub(super) fn copy_json(&mut self) {
+        let Some((json, _)) = self.export_text(true) else {
+            return;
+        };
+        match clipboard::copy_to_clipboard(&json) {
+            Ok(()) => self.set_toast(format!("Copied JSON: {} lines", json.lines().count())),
+            Err(err) => self.set_toast(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputAction;
+    use crate::settings::CliOverrides;
+    use pretty_assertions::assert_eq;
+    use yaml_rust2::Yaml;
+
+    #[test]
+    fn export_writes_json_for_the_subtree_or_document() {
+        let dir = std::env::temp_dir().join(format!("yed_app_export_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("values.yaml");
+        std::fs::write(&file, "ports: [80]\nbad: .nan\n").unwrap();
+        let mut app = App::new(&file, CliOverrides::default()).unwrap();
+
+        app.apply_action(InputAction::MoveDown, 10).unwrap();
+        app.apply_action(InputAction::ExportJson, 10).unwrap();
+        assert_eq!(app.mode, Mode::ExportPath);
+        let subtree = dir.join("values.ports.json");
+        assert_eq!(app.input.text, subtree.display().to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(std::fs::read_to_string(&subtree).unwrap(), "[\n  80\n]\n");
+
+        app.apply_action(InputAction::ExportDocumentJson, 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Cannot export to JSON: bad has no JSON form"
+        );
+        assert!(!dir.join("values.json").exists());
+
+        app.apply_action(InputAction::MoveDown, 10).unwrap();
+        app.apply_action(InputAction::DeleteNode, 10).unwrap();
+        app.apply_action(InputAction::ConfirmYes, 10).unwrap();
+        app.apply_action(InputAction::ExportDocumentJson, 10)
+            .unwrap();
+        app.input.set(subtree.display().to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.mode, Mode::ConfirmExportOverwrite);
+        app.apply_action(InputAction::ConfirmYes, 10).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&subtree).unwrap(),
+            "{\n  \"ports\": [\n    80\n  ]\n}\n"
+        );
+        assert!(app.dirty);
+        drop(app);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn toml_export_lists_what_toml_cannot_hold() {
+        let dir = std::env::temp_dir().join(format!("yed_app_toml_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("values.yaml");
+        std::fs::write(&file, "name: web\nreplicas: ~\n").unwrap();
+        let mut app = App::new(&file, CliOverrides::default()).unwrap();
+
+        app.apply_action(InputAction::ExportToml, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Cannot export to TOML: TOML cannot represent replicas (null)"
+        );
+
+        app.model.root_mut()["replicas"] = Yaml::Integer(2);
+        app.apply_action(InputAction::ExportToml, 10).unwrap();
+        assert_eq!(app.mode, Mode::ExportPath);
+        assert_eq!(
+            app.input.text,
+            dir.join("values.toml").display().to_string()
+        );
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("values.toml")).unwrap(),
+            "name = \"web\"\nreplicas = 2\n"
+        );
+        drop(app);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
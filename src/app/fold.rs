@@ -0,0 +1,235 @@
+// Folding many nodes at once: `zR`/`zM` for the whole tree, `zO`/`zC` for the selected subtree,
+// `z1`..`z9` to a depth and `zf` for everything but the selected subtree.
+
+use std::collections::HashSet;
+
+use super::App;
+use crate::yaml_model::{container_dot_paths, find_tree_node, visible_row_by_path, NodePath};
+
+impl App {
+    /// `zR`: expand every mapping and list, collected in one walk of the tree.
+    pub(super) fn expand_all(&mut self) {
+        let mut paths = Vec::new();
+        container_dot_paths(&self.tree_root, usize::MAX, &mut paths);
+        self.expanded.extend(paths);
+        self.focus_stash = None;
+        self.rebuild_visible();
+    }
+
+    /// `zM` (depth 1) and `z1`..`z9`: [`Self::expand_to_depth`], keeping the view from
+    /// scrolling past the end of a tree that got shorter.
+    pub(super) fn fold_to_depth(&mut self, depth: usize, area_height: usize) {
+        self.expand_to_depth(depth);
+        self.scroll = self
+            .scroll
+            .min(self.visible.len().saturating_sub(area_height));
+        self.ensure_visible(area_height);
+    }
+
+    /// Show exactly `depth` levels below the root (`--depth`). The selection moves up to the
+    /// closest ancestor still shown.
+    pub(super) fn expand_to_depth(&mut self, depth: usize) {
+        let selected = self.current_row().map(|row| row.path.clone());
+        let mut paths = Vec::new();
+        container_dot_paths(&self.tree_root, depth, &mut paths);
+        self.expanded = paths.into_iter().collect();
+        self.expanded.insert(String::new());
+        self.focus_stash = None;
+        self.rebuild_visible();
+        if let Some(path) = selected {
+            let ancestor = (0..=path.0.len()).rev().find_map(|depth| {
+                visible_row_by_path(&self.visible, &NodePath(path.0[..depth].to_vec()))
+            });
+            self.selection = ancestor.unwrap_or(0);
+        }
+    }
+
+    /// `zf`: fold everything outside the selected node's subtree and its ancestors, with the
+    /// selection near the top of the view; again to bring back what was open before.
+    pub(super) fn toggle_focus(&mut self, area_height: usize) {
+        if let Some(stash) = self.focus_stash.take() {
+            self.expanded = stash;
+            self.rebuild_visible();
+            self.ensure_visible(area_height);
+            self.set_toast("Unfolded the rest".to_string());
+            return;
+        }
+        let Some(path) = self.current_row().map(|row| row.path.clone()) else {
+            return;
+        };
+        let mut keep = Vec::new();
+        if let Some(node) = find_tree_node(&self.tree_root, &path) {
+            container_dot_paths(node, usize::MAX, &mut keep);
+        }
+        let mut keep: HashSet<String> = keep.into_iter().collect();
+        keep.extend((0..path.0.len()).map(|depth| NodePath(path.0[..depth].to_vec()).dot_path()));
+        let focused = self
+            .expanded
+            .iter()
+            .filter(|dot| keep.contains(*dot))
+            .cloned()
+            .collect();
+        self.focus_stash = Some(std::mem::replace(&mut self.expanded, focused));
+        self.rebuild_visible();
+        // One row above: the parent, now directly above its only open child.
+        self.scroll = self
+            .selection
+            .saturating_sub(1)
+            .min(self.visible.len().saturating_sub(area_height));
+        let label = if path.0.is_empty() {
+            "(root)".to_string()
+        } else {
+            path.dot_path()
+        };
+        self.set_toast(format!("Focused on {label}; zf to unfold the rest"));
+    }
+
+    /// `zO`/`zC`: expand or collapse the selected node and every mapping and list below it.
+    pub(super) fn fold_subtree(&mut self, expand: bool) {
+        let Some(path) = self.current_row().map(|row| row.path.clone()) else {
+            return;
+        };
+        let mut paths = Vec::new();
+        if let Some(node) = find_tree_node(&self.tree_root, &path) {
+            container_dot_paths(node, usize::MAX, &mut paths);
+        }
+        if expand {
+            self.expanded.extend(paths);
+        } else {
+            for dot in &paths {
+                self.expanded.remove(dot);
+            }
+        }
+        self.rebuild_visible();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::app_with;
+    use crate::settings::CliOverrides;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn fold_all_and_fold_subtree() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let yaml = "a:\n  b:\n    c: [1, 2]\n  d: {e: 1}\nf:\n  g: 1\n";
+        let mut app = app_with("fold", yaml);
+        let keys = |app: &mut App, keys: &str| {
+            for ch in keys.chars() {
+                app.handle_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE), 3)
+                    .unwrap();
+            }
+        };
+        let shown = |app: &App| {
+            app.visible
+                .iter()
+                .map(|row| row.path.dot_path())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        keys(&mut app, "zR");
+        assert_eq!(shown(&app), " a a.b a.b.c a.b.c.0 a.b.c.1 a.d a.d.e f f.g");
+        app.selection = app
+            .visible
+            .iter()
+            .position(|row| row.path.dot_path() == "a.b.c.1")
+            .unwrap();
+        app.scroll = 3;
+        keys(&mut app, "zM");
+        assert_eq!(shown(&app), " a f");
+        assert_eq!(app.visible[app.selection].path.dot_path(), "a");
+        assert_eq!(app.scroll, 0);
+
+        keys(&mut app, "zO");
+        assert_eq!(shown(&app), " a a.b a.b.c a.b.c.0 a.b.c.1 a.d a.d.e f");
+        keys(&mut app, "j");
+        keys(&mut app, "zC");
+        assert_eq!(shown(&app), " a a.b a.d a.d.e f");
+        // Collapsed with its parent, `a.b.c` opens closed again.
+        keys(&mut app, "l");
+        assert_eq!(shown(&app), " a a.b a.b.c a.d a.d.e f");
+    }
+
+    #[test]
+    fn z_digit_and_depth_flag_show_that_many_levels() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let yaml = "a:\n  b:\n    c: [1, 2]\n  d: 1\nf:\n  g: 1\n";
+        let path = std::env::temp_dir().join(format!("yed_app_depth_{}.yaml", std::process::id()));
+        std::fs::write(&path, yaml).unwrap();
+        let cli = CliOverrides {
+            depth: Some(2),
+            ..CliOverrides::default()
+        };
+        let mut app = App::new(&path, cli).unwrap();
+        std::fs::remove_file(&path).ok();
+        let shown = |app: &App| {
+            app.visible
+                .iter()
+                .map(|row| row.path.dot_path())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        assert_eq!(shown(&app), " a a.b a.d f f.g");
+        let keys = |app: &mut App, keys: &str| {
+            for ch in keys.chars() {
+                app.handle_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE), 10)
+                    .unwrap();
+            }
+        };
+        keys(&mut app, "z3");
+        assert_eq!(shown(&app), " a a.b a.b.c a.d f f.g");
+        app.selection = app
+            .visible
+            .iter()
+            .position(|row| row.path.dot_path() == "a.b.c")
+            .unwrap();
+        // Fewer levels also close what was open deeper down.
+        keys(&mut app, "zRz1");
+        assert_eq!(shown(&app), " a f");
+        assert_eq!(app.visible[app.selection].path.dot_path(), "a");
+        keys(&mut app, "z2");
+        assert_eq!(shown(&app), " a a.b a.d f f.g");
+    }
+
+    #[test]
+    fn focus_folds_everything_but_the_selected_subtree() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let yaml = "pre: {x: 1}\nitems:\n  - name: a\n    port: 1\n  - name: b\n    tags: [t]\n  - name: c\n";
+        let mut app = app_with("focus", yaml);
+        let keys = |app: &mut App, keys: &str| {
+            for ch in keys.chars() {
+                app.handle_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE), 3)
+                    .unwrap();
+            }
+        };
+        let shown = |app: &App| {
+            app.visible
+                .iter()
+                .map(|row| row.path.dot_path())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        keys(&mut app, "zR");
+        app.expanded.remove("items.1.tags");
+        app.rebuild_visible();
+        let before = app.expanded.clone();
+        app.selection = app
+            .visible
+            .iter()
+            .position(|row| row.path.dot_path() == "items.1")
+            .unwrap();
+        keys(&mut app, "zf");
+        assert_eq!(
+            shown(&app),
+            " pre items items.0 items.1 items.1.name items.1.tags items.2"
+        );
+        assert_eq!(app.visible[app.selection].path.dot_path(), "items.1");
+        assert_eq!(app.scroll, app.selection - 1);
+        keys(&mut app, "zf");
+        assert_eq!(app.expanded, before);
+        assert_eq!(app.visible[app.selection].path.dot_path(), "items.1");
+        assert_eq!(app.toast.as_ref().unwrap().message, "Unfolded the rest");
+    }
+}
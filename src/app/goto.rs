@@ -0,0 +1,161 @@
+// Jump to a node by typing its dot path (`gp`), with completion of each segment against the
+// document.
+
+use yaml_rust2::Yaml;
+
+use super::{App, Mode};
+use crate::yaml_model::{self, resolve_dot_path};
+
+/// Candidates listed under the prompt; Tab still completes against all of them.
+pub const MAX_SHOWN_CANDIDATES: usize = 8;
+
+impl App {
+    /// `gp`: prompt for a dot path such as `spec.containers.0.image`.
+    pub(super) fn start_goto(&mut self) {
+        self.input.set(String::new());
+        self.mode = Mode::GotoPath;
+    }
+
+    /// Where the segment being typed starts and the keys (or indices) of the collection before
+    /// it that start with what was typed. Empty outside `Mode::GotoPath`.
+    fn goto_candidates(&self) -> (usize, Vec<String>) {
+        if self.mode != Mode::GotoPath {
+            return (0, Vec::new());
+        }
+        let text = &self.input.text;
+        let starts = std::iter::once(0).chain(text.match_indices('.').map(|(idx, _)| idx + 1));
+        // Keys may contain dots: take the last split whose head names a collection.
+        for start in starts.collect::<Vec<_>>().into_iter().rev() {
+            let head = text[..start].strip_suffix('.').unwrap_or("");
+            let node = resolve_dot_path(self.model.root(), head)
+                .and_then(|path| self.model.node_at(&path).ok());
+            let Some(node) = node else {
+                continue;
+            };
+            let partial = &text[start..];
+            let names: Vec<String> = match node {
+                Yaml::Hash(map) => map
+                    .keys()
+                    .filter_map(yaml_model::yaml_key_to_string)
+                    .collect(),
+                Yaml::Array(seq) => (0..seq.len()).map(|index| index.to_string()).collect(),
+                _ => continue,
+            };
+            let names = names
+                .into_iter()
+                .filter(|name| name.starts_with(partial))
+                .collect();
+            return (start, names);
+        }
+        (0, Vec::new())
+    }
+
+    /// Segments the path being typed could continue with.
+    pub fn path_completions(&self) -> Vec<String> {
+        self.goto_candidates().1
+    }
+
+    /// Tab: finish the segment when one candidate is left, or extend it to the prefix all
+    /// candidates share.
+    pub(super) fn complete_path(&mut self) {
+        let (start, candidates) = self.goto_candidates();
+        let Some(first) = candidates.first() else {
+            return;
+        };
+        let mut text = self.input.text[..start].to_string();
+        if candidates.len() == 1 {
+            text.push_str(first);
+            let path = resolve_dot_path(self.model.root(), &text);
+            let node = path.and_then(|path| self.model.node_at(&path).ok());
+            if matches!(node, Some(Yaml::Hash(_) | Yaml::Array(_))) {
+                text.push('.');
+            }
+        } else {
+            text.push_str(&common_prefix(&candidates));
+        }
+        self.input.set(text);
+    }
+
+    /// Expand the ancestors of the path typed in `Mode::GotoPath` and select it. An unknown
+    /// path leaves the prompt open to be corrected.
+    pub(super) fn commit_goto(&mut self) {
+        let dot = self.input.text.trim().trim_end_matches('.').to_string();
+        let Some(path) = resolve_dot_path(self.model.root(), &dot) else {
+            self.set_toast(format!("No node at {dot}"));
+            return;
+        };
+        self.mode = Mode::Normal;
+        self.record_jump();
+        if path.0.is_empty() {
+            self.selection = 0;
+        } else if !self.reveal_path(&path) {
+            self.set_toast(format!("{dot} is hidden by the search"));
+        }
+    }
+}
+
+/// Longest prefix of every name, cut on a character boundary.
+fn common_prefix(names: &[String]) -> String {
+    let mut prefix = names[0].as_str();
+    for name in &names[1..] {
+        let shared: usize = prefix
+            .chars()
+            .zip(name.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .sum();
+        prefix = &prefix[..shared];
+    }
+    prefix.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::app_with;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn goto_path_completes_segments_and_reveals_the_node() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let yaml = "spec:\n  template:\n    containers:\n      - image: nginx\n        name: web\nstatus: ok\n";
+        let mut app = app_with("goto_path", yaml);
+        let key = |app: &mut App, code: KeyCode| {
+            app.handle_key(KeyEvent::new(code, KeyModifiers::NONE), 10)
+                .unwrap();
+        };
+        key(&mut app, KeyCode::Char('g'));
+        key(&mut app, KeyCode::Char('p'));
+        assert_eq!(app.mode, Mode::GotoPath);
+        key(&mut app, KeyCode::Char('s'));
+        key(&mut app, KeyCode::Tab);
+        assert_eq!(app.input.text, "s");
+        assert_eq!(app.path_completions(), ["spec", "status"]);
+        key(&mut app, KeyCode::Char('p'));
+        for _ in 0..4 {
+            key(&mut app, KeyCode::Tab);
+        }
+        assert_eq!(app.input.text, "spec.template.containers.0.");
+        assert_eq!(app.path_completions(), ["image", "name"]);
+        key(&mut app, KeyCode::Char('i'));
+        key(&mut app, KeyCode::Tab);
+        assert_eq!(app.input.text, "spec.template.containers.0.image");
+        key(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(
+            app.visible[app.selection].path.dot_path(),
+            "spec.template.containers.0.image"
+        );
+
+        // A path that does not exist keeps the prompt open.
+        key(&mut app, KeyCode::Char('g'));
+        key(&mut app, KeyCode::Char('p'));
+        app.input.set("spec.template.containers.1".to_string());
+        key(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, Mode::GotoPath);
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "No node at spec.template.containers.1"
+        );
+    }
+}
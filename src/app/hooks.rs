@@ -0,0 +1,94 @@
+// Post-save hook: started in the background after each save, collected by the event loop,
+// and its findings listed in an overlay that jumps to the reported line.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::{App, Mode};
+use crate::background::{self, Task};
+use crate::hooks;
+use crate::input::InputAction;
+
+impl App {
+    /// Run the configured `hooks.post_save` command for the file just saved.
+    pub(super) fn start_post_save_hook(&mut self) {
+        let Some(template) = self.settings.post_save_hook.value.clone() else {
+            return;
+        };
+        let file = PathBuf::from(self.model.file_path());
+        let command = hooks::command_line(&template, &file);
+        let timeout = Duration::from_secs(self.settings.hook_timeout_secs.value);
+        // A hook still running for an earlier save is superseded; its result is dropped.
+        self.hook_task = Some(background::spawn(move || {
+            hooks::run(&command, &file, timeout)
+        }));
+    }
+
+    /// Pick up the result of a finished hook.
+    pub(super) fn poll_hook(&mut self) {
+        let Some(result) = self.hook_task.as_ref().and_then(Task::poll) else {
+            return;
+        };
+        self.hook_task = None;
+        if result.has_problems() {
+            self.set_toast(format!("{} (F: list)", result.summary()));
+        } else {
+            self.set_toast(result.summary());
+        }
+        self.list_selection = 0;
+        self.hook_result = Some(result);
+    }
+
+    pub fn hook_running(&self) -> bool {
+        self.hook_task.is_some()
+    }
+
+    /// Findings of the last hook, for the red status badge.
+    pub fn hook_finding_count(&self) -> usize {
+        self.hook_result
+            .as_ref()
+            .map_or(0, |result| result.findings.len())
+    }
+
+    pub(super) fn show_findings(&mut self) {
+        if self.hook_result.is_some() {
+            self.list_selection = 0;
+            self.mode = Mode::HookFindings;
+        } else if self.hook_running() {
+            self.set_toast("post_save hook still running".to_string());
+        } else if self.settings.post_save_hook.value.is_none() {
+            self.set_toast("No post_save hook configured (hooks.post_save)".to_string());
+        } else {
+            self.set_toast("No hook results yet; save to run post_save".to_string());
+        }
+    }
+
+    pub(super) fn apply_findings_action(&mut self, action: InputAction, area_height: usize) {
+        let len = self.hook_finding_count();
+        match action {
+            InputAction::MoveUp => self.list_selection = self.list_selection.saturating_sub(1),
+            InputAction::MoveDown => {
+                self.list_selection = (self.list_selection + 1).min(len.saturating_sub(1));
+            }
+            InputAction::InputCommit => {
+                let target = self
+                    .hook_result
+                    .as_ref()
+                    .and_then(|result| result.findings.get(self.list_selection))
+                    .map(|finding| (finding.line, finding.path.clone()));
+                self.mode = Mode::Normal;
+                let Some((line, path)) = target else {
+                    return;
+                };
+                if let Some(lines) = self.raw_lines() {
+                    self.selection = line.saturating_sub(1).min(lines.len().saturating_sub(1));
+                } else if !path.is_some_and(|path| self.reveal_path(&path)) {
+                    self.set_toast(format!("Line {line}: node not in tree"));
+                }
+                self.ensure_visible(area_height);
+            }
+            InputAction::Cancel => self.mode = Mode::Normal,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// Startup integrity check: emit and reload the loaded document in the background, flag files a
+// save would alter beyond the user's edits, and ask before the first edit of such a file.
+
+use super::{App, Mode};
+use crate::background;
+use crate::input::InputAction;
+use crate::roundtrip::{self, DocumentReport};
+use crate::settings::SaveMode;
+
+impl App {
+    /// Check, off the UI thread, whether saving the loaded document would change it.
+    pub(super) fn start_integrity_check(&mut self) {
+        self.lossy = None;
+        self.lossy_acknowledged = false;
+        self.integrity_task = None;
+        // Minimal-diff saves leave nodes the user did not edit alone.
+        if !self.settings.integrity_check.value
+            || self.settings.save_mode.value == SaveMode::Minimal
+        {
+            return;
+        }
+        let Some(text) = self.model.loaded_text().map(str::to_string) else {
+            return;
+        };
+        let options = self.model.emit_options().clone();
+        self.integrity_task = Some(background::spawn(move || {
+            // Only the first document is edited and saved.
+            let report = match roundtrip::check_text(&text, &options) {
+                Ok(reports) => reports.into_iter().next()?,
+                Err(err) => DocumentReport {
+                    error: Some(err.to_string()),
+                    ..DocumentReport::default()
+                },
+            };
+            report.failed().then_some(report)
+        }));
+    }
+
+    pub(super) fn poll_integrity_check(&mut self) {
+        let Some(result) = self.integrity_task.as_ref().and_then(|task| task.poll()) else {
+            return;
+        };
+        self.integrity_task = None;
+        if let Some(report) = &result {
+            self.set_toast(format!("Lossy save: {}", lossy_summary(report)));
+        }
+        self.lossy = result;
+    }
+
+    /// Paths (or the error) behind the "lossy save" badge.
+    pub fn lossy_label(&self) -> Option<String> {
+        self.lossy.as_ref().map(lossy_summary)
+    }
+
+    /// Hold back the first edit of a file that would not save faithfully. Returns true if the
+    /// confirmation was opened.
+    pub(super) fn prompt_for_lossy_edit(&mut self, action: &InputAction) -> bool {
+        if self.lossy.is_none() || self.lossy_acknowledged || self.raw_content.is_some() {
+            return false;
+        }
+        self.pending_lossy_edit = Some(action.clone());
+        self.mode = Mode::ConfirmLossy;
+        true
+    }
+
+    pub(super) fn apply_lossy_prompt_action(
+        &mut self,
+        action: InputAction,
+        area_height: usize,
+    ) -> anyhow::Result<bool> {
+        match action {
+            InputAction::ConfirmYes => {
+                self.mode = Mode::Normal;
+                self.lossy_acknowledged = true;
+                match self.pending_lossy_edit.take() {
+                    Some(deferred) => self.apply_action(deferred, area_height),
+                    None => Ok(false),
+                }
+            }
+            InputAction::ConfirmNo => {
+                self.mode = Mode::Normal;
+                self.pending_lossy_edit = None;
+                Ok(false)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Text of the confirmation shown before the first edit.
+    pub fn lossy_prompt(&self) -> Option<String> {
+        let report = self.lossy.as_ref()?;
+        Some(match &report.error {
+            Some(err) => {
+                format!("Saving this file will not reproduce it ({err}) — continue? (y/n)")
+            }
+            None => format!(
+                "Saving this file will alter {} node(s) unrelated to your edits — continue? (y/n)",
+                report.semantic.len()
+            ),
+        })
+    }
+}
+
+fn lossy_summary(report: &DocumentReport) -> String {
+    const SHOWN: usize = 3;
+    if let Some(err) = &report.error {
+        return err.clone();
+    }
+    let mut label = report
+        .semantic
+        .iter()
+        .take(SHOWN)
+        .map(|difference| difference.path.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    if report.semantic.len() > SHOWN {
+        label.push_str(&format!(" +{}", report.semantic.len() - SHOWN));
+    }
+    label
+}
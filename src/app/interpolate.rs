@@ -0,0 +1,50 @@
+// Environment preview: `$` shows the selected value with its `${VAR}` references resolved,
+// and `V` lists every placeholder in the document and whether it is set. Display only.
+
+use super::{App, Mode};
+use crate::input::InputAction;
+use crate::interpolate;
+
+impl App {
+    pub(super) fn toggle_env_preview(&mut self) {
+        self.env_preview = !self.env_preview;
+        let state = if self.env_preview { "on" } else { "off" };
+        self.set_toast(format!("Environment preview {state}"));
+    }
+
+    pub(super) fn show_placeholders(&mut self) {
+        self.placeholders = interpolate::placeholders(self.model.root());
+        if self.placeholders.is_empty() {
+            self.set_toast("No ${VAR} placeholders".to_string());
+        } else {
+            self.list_selection = 0;
+            self.mode = Mode::Placeholders;
+        }
+    }
+
+    pub(super) fn apply_placeholders_action(&mut self, action: InputAction, area_height: usize) {
+        match action {
+            InputAction::MoveUp => self.list_selection = self.list_selection.saturating_sub(1),
+            InputAction::MoveDown => {
+                self.list_selection =
+                    (self.list_selection + 1).min(self.placeholders.len().saturating_sub(1));
+            }
+            InputAction::InputCommit => {
+                self.mode = Mode::Normal;
+                let Some(path) = self
+                    .placeholders
+                    .get(self.list_selection)
+                    .map(|p| p.path.clone())
+                else {
+                    return;
+                };
+                if !self.reveal_path(&path) {
+                    self.set_toast(format!("{}: node not in tree", path.dot_path()));
+                }
+                self.ensure_visible(area_height);
+            }
+            InputAction::Cancel => self.mode = Mode::Normal,
+            _ => {}
+        }
+    }
+}
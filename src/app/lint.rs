@@ -0,0 +1,92 @@
+// Lint findings for the file as loaded or saved, listed in an overlay that jumps to each one.
+
+use super::{App, Mode};
+use crate::input::InputAction;
+use crate::lint;
+use crate::source_map::SourceMap;
+
+impl App {
+    /// Lint the text on disk: the raw text in the raw view, otherwise the file as last loaded
+    /// or saved.
+    pub(super) fn refresh_lint(&mut self) {
+        let text = self
+            .raw_content
+            .as_deref()
+            .or(self.model.loaded_text())
+            .unwrap_or_default();
+        self.lint = lint::lint(
+            text,
+            &SourceMap::scan(text),
+            self.settings.lint_max_line_length.value,
+        );
+        self.list_selection = self.list_selection.min(self.lint.len().saturating_sub(1));
+    }
+
+    pub(super) fn show_lint(&mut self) {
+        if self.lint.is_empty() {
+            self.set_toast("No lint findings".to_string());
+        } else {
+            self.list_selection = 0;
+            self.mode = Mode::LintFindings;
+        }
+    }
+
+    pub(super) fn apply_lint_action(&mut self, action: InputAction, area_height: usize) {
+        match action {
+            InputAction::MoveUp => self.list_selection = self.list_selection.saturating_sub(1),
+            InputAction::MoveDown => {
+                self.list_selection =
+                    (self.list_selection + 1).min(self.lint.len().saturating_sub(1));
+            }
+            InputAction::InputCommit => {
+                self.mode = Mode::Normal;
+                let Some(finding) = self.lint.get(self.list_selection).cloned() else {
+                    return;
+                };
+                if let Some(lines) = self.raw_lines() {
+                    self.selection = finding
+                        .line
+                        .saturating_sub(1)
+                        .min(lines.len().saturating_sub(1));
+                } else if !finding.path.is_some_and(|path| self.reveal_path(&path)) {
+                    self.set_toast(format!("Line {}: node not in tree", finding.line));
+                }
+                self.ensure_visible(area_height);
+            }
+            InputAction::Cancel => self.mode = Mode::Normal,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::app_with;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn lint_panel_jumps_by_path_or_raw_line() {
+        let mut app = app_with("lint", "name: api \nserver:\n  port: 80\n  host: a\n");
+        assert_eq!(app.lint.len(), 1);
+        app.apply_action(InputAction::ShowLint, 10).unwrap();
+        assert_eq!(app.mode, Mode::LintFindings);
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "name");
+
+        let mut app = app_with("lint_clean", "name: api\n");
+        app.apply_action(InputAction::ShowLint, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.toast.as_ref().unwrap().message, "No lint findings");
+
+        // A file that does not parse is linted as text and jumps by line.
+        let mut app = app_with("lint_raw", "a: 1\nb: [\n\tc: 2\n");
+        assert!(app.raw_content.is_some());
+        app.apply_action(InputAction::ShowLint, 10).unwrap();
+        assert_eq!(app.lint[0].rule, crate::lint::Rule::Tab);
+        app.selection = 0;
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.selection, 2);
+    }
+}
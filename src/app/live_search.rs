@@ -0,0 +1,178 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use super::{App, Mode};
+use crate::input::InputAction;
+use crate::search::{self, Query};
+use crate::yaml_model::{NodePath, TreeNode};
+
+/// Documents with more nodes than this wait for a pause in typing before the search reruns.
+const LARGE_DOCUMENT_NODES: usize = 5_000;
+/// Pause in typing that reruns the search on a large document.
+const TYPING_PAUSE: Duration = Duration::from_millis(150);
+
+/// The view as it was when `/` or `?` was pressed, put back by Esc. The scope and case picked while
+/// typing stay, like the preferences they are.
+#[derive(Clone, Debug)]
+pub struct SearchStash {
+    query: Option<Query>,
+    expanded: HashSet<String>,
+    selection: Option<NodePath>,
+    /// Direction of the search before, which a cancelled `?` or `/` does not change.
+    backward: bool,
+    /// Whether the document is big enough to wait for a pause in typing.
+    large: bool,
+}
+
+impl App {
+    /// `/` or `?`: remember the current view so Esc can restore it.
+    pub(super) fn stash_search(&mut self) {
+        self.search_stash = Some(SearchStash {
+            query: self.search_query.clone(),
+            expanded: self.expanded.clone(),
+            selection: self.current_row().map(|row| row.path.clone()),
+            backward: self.search_backward,
+            large: node_count(&self.tree_root) > LARGE_DOCUMENT_NODES,
+        });
+        self.search_due = None;
+    }
+
+    /// Node selected when `/` was pressed, for the jump list.
+    pub(super) fn selection_before_search(&self) -> Option<NodePath> {
+        self.search_stash.as_ref()?.selection.clone()
+    }
+
+    /// Whether `action` changes the query being typed, so the search should rerun.
+    pub(super) fn changes_search_text(&self, action: &InputAction) -> bool {
+        self.mode == Mode::SearchInput
+            && matches!(
+                action,
+                InputAction::InputChar(_)
+                    | InputAction::InputBackspace
+                    | InputAction::InputDelete
+                    | InputAction::InputUp
+                    | InputAction::InputDown
+                    | InputAction::CycleSearchScope
+                    | InputAction::CycleSearchCase
+            )
+    }
+
+    /// The query changed: rerun it now, or after a pause in typing on a large document.
+    pub(super) fn search_typed(&mut self) {
+        if self.search_stash.as_ref().is_some_and(|stash| stash.large) {
+            self.search_due = Some(Instant::now() + TYPING_PAUSE);
+        } else {
+            self.preview_search();
+        }
+    }
+
+    /// Rerun a search whose typing pause has passed.
+    pub(super) fn poll_live_search(&mut self) {
+        if self.search_due.is_some_and(|due| Instant::now() >= due) {
+            self.preview_search();
+        }
+    }
+
+    /// Filter the tree by the text typed so far and select the first match. An unfinished
+    /// regex leaves the last preview in place; Enter reports it.
+    fn preview_search(&mut self) {
+        self.search_due = None;
+        if self.mode != Mode::SearchInput {
+            return;
+        }
+        let scope = self.search_scope;
+        let (text, typed_scope) = search::parse_query(&self.input.text, scope);
+        let Ok(query) = Query::parse(&text, self.search_case) else {
+            return;
+        };
+        self.search_query = (!text.is_empty()).then_some(query);
+        if self.search_query.is_none() {
+            self.matches.clear();
+        }
+        // A `k:`/`v:` prefix only applies once committed; deleting it goes back to `scope`.
+        self.search_scope = typed_scope;
+        self.rebuild_visible();
+        self.search_scope = scope;
+        if let Some(&first) = self.matches.first() {
+            self.selection = first;
+        }
+    }
+
+    /// Esc while typing a search: put back the query, folds and selection from before `/`.
+    pub(super) fn restore_search_stash(&mut self) {
+        self.search_due = None;
+        let Some(stash) = self.search_stash.take() else {
+            return;
+        };
+        self.search_query = stash.query;
+        self.search_backward = stash.backward;
+        self.expanded = stash.expanded;
+        if self.search_query.is_none() {
+            self.matches.clear();
+        }
+        self.rebuild_visible();
+        self.restore_selection(stash.selection);
+    }
+}
+
+fn node_count(node: &TreeNode) -> usize {
+    1 + node.children.iter().map(node_count).sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::app_with;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn search_narrows_while_typing_and_esc_restores_the_view() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let yaml = "server:\n  port: 8080\n  host: local\nname: api\nports:\n  - 80\n";
+        let mut app = app_with("live_search", yaml);
+        let key = |app: &mut App, code: KeyCode| {
+            app.handle_key(KeyEvent::new(code, KeyModifiers::NONE), 10)
+                .unwrap();
+        };
+        app.selection = app
+            .visible
+            .iter()
+            .position(|row| row.path.dot_path() == "name")
+            .unwrap();
+        key(&mut app, KeyCode::Char('/'));
+        key(&mut app, KeyCode::Char('h'));
+        key(&mut app, KeyCode::Char('o'));
+        assert_eq!(app.mode, Mode::SearchInput);
+        assert_eq!(app.visible[app.selection].path.dot_path(), "server.host");
+        assert!(app.visible.iter().all(|row| row.path.dot_path() != "name"));
+        key(&mut app, KeyCode::Backspace);
+        key(&mut app, KeyCode::Backspace);
+        assert!(app.search_query.is_none());
+        assert!(app.visible.iter().any(|row| row.path.dot_path() == "name"));
+
+        // An unfinished regex keeps the last preview until it compiles.
+        for ch in "re:(p".chars() {
+            key(&mut app, KeyCode::Char(ch));
+        }
+        assert_eq!(app.search_query.as_ref().unwrap().text(), "");
+        key(&mut app, KeyCode::Char(')'));
+        assert_eq!(app.search_query.as_ref().unwrap().text(), "(p)");
+
+        key(&mut app, KeyCode::Esc);
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.search_query.is_none());
+        assert_eq!(app.visible[app.selection].path.dot_path(), "name");
+
+        // A large document waits for a pause in typing.
+        let big: String = (0..3000).map(|i| format!("k{i}:\n  v: {i}\n")).collect();
+        let mut app = app_with("live_search_big", &big);
+        key(&mut app, KeyCode::Char('/'));
+        key(&mut app, KeyCode::Char('v'));
+        assert!(app.search_query.is_none());
+        assert!(app.search_due.is_some());
+        app.search_due = Some(std::time::Instant::now());
+        app.poll_tasks();
+        assert!(app.search_query.is_some());
+        assert_eq!(app.search_due, None);
+    }
+}
@@ -0,0 +1,50 @@
+// The marks set with `m` + letter, listed by `'?` so they can be found again and jumped to.
+
+use super::{App, Mode};
+use crate::input::InputAction;
+use crate::yaml_model::NodePath;
+
+impl App {
+    /// Marks by letter, with whether the marked node still exists.
+    pub fn mark_list(&self) -> Vec<(char, NodePath, bool)> {
+        let mut marks: Vec<_> = self
+            .marks
+            .iter()
+            .map(|(letter, path)| (*letter, path.clone(), self.model.node_at(path).is_ok()))
+            .collect();
+        marks.sort_by_key(|(letter, _, _)| *letter);
+        marks
+    }
+
+    /// `'?`: list the marks.
+    pub(super) fn show_marks(&mut self) {
+        if self.marks.is_empty() {
+            self.set_toast("No marks; m + letter sets one".to_string());
+        } else {
+            self.list_selection = 0;
+            self.mode = Mode::MarkList;
+        }
+    }
+
+    pub(super) fn apply_marks_action(&mut self, action: InputAction, area_height: usize) {
+        match action {
+            InputAction::MoveUp => self.list_selection = self.list_selection.saturating_sub(1),
+            InputAction::MoveDown => {
+                self.list_selection =
+                    (self.list_selection + 1).min(self.marks.len().saturating_sub(1));
+            }
+            InputAction::InputCommit => {
+                self.mode = Mode::Normal;
+                let Some((letter, _, _)) = self.mark_list().into_iter().nth(self.list_selection)
+                else {
+                    return;
+                };
+                self.record_jump();
+                self.jump_to_mark(letter);
+                self.ensure_visible(area_height);
+            }
+            InputAction::Cancel => self.mode = Mode::Normal,
+            _ => {}
+        }
+    }
+}
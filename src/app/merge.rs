@@ -0,0 +1,494 @@
+// Merging changes made on disk into a buffer with unsaved edits: non-overlapping changes are
+// combined at once, conflicts are resolved one by one in an overlay.
+
+use std::path::Path;
+
+use anyhow::Result;
+use yaml_rust2::{Yaml, YamlLoader};
+
+use super::{App, Mode};
+use crate::input::{DiskChoice, InputAction};
+use crate::journal::EditKind;
+use crate::line_diff;
+use crate::merge::{self, Conflict, Side};
+use crate::yaml_model::{
+    get_node, node_preview, parse_scalar_input, scalar_preview, scalar_to_yaml, NodePath,
+};
+
+/// How a conflict was resolved.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pick {
+    Take(Side),
+    /// Value typed by the user.
+    Custom(Yaml),
+}
+
+/// A merge waiting on conflict resolution.
+pub struct MergeState {
+    /// Text and document on disk; the new baseline once the merge is finished.
+    disk_text: String,
+    disk: Yaml,
+    /// The buffer with the disk's non-conflicting changes applied.
+    merged: Yaml,
+    /// Disk changes already applied to `merged`.
+    applied: usize,
+    pub conflicts: Vec<Conflict>,
+    /// Resolution per conflict, `None` while open.
+    pub picks: Vec<Option<Pick>>,
+}
+
+impl MergeState {
+    pub fn unresolved(&self) -> usize {
+        self.picks.iter().filter(|pick| pick.is_none()).count()
+    }
+
+    /// Value a pick keeps at the conflict's path; `None` removes the node.
+    fn picked_value(&self, index: usize) -> Option<Option<Yaml>> {
+        let conflict = &self.conflicts[index];
+        match self.picks[index].as_ref()? {
+            Pick::Take(side) => Some(conflict.value(*side).cloned()),
+            Pick::Custom(value) => Some(Some(value.clone())),
+        }
+    }
+}
+
+/// The file as found on disk while the buffer had unsaved edits.
+pub struct DiskChange {
+    text: String,
+    /// The parsed document, or why it does not parse.
+    disk: Result<Yaml, String>,
+}
+
+/// One-line preview of a conflict value, `(absent)` where the node does not exist.
+pub fn side_preview(value: Option<&Yaml>) -> String {
+    value
+        .map(node_preview)
+        .unwrap_or_else(|| "(absent)".to_string())
+}
+
+impl App {
+    /// The file changed on disk while the buffer has unsaved edits: ask whether to merge,
+    /// reload (dropping the edits), keep the buffer, or see the differences first.
+    pub(super) fn ask_disk_change(&mut self, path: &Path) -> Result<()> {
+        let text = std::fs::read_to_string(path)?;
+        let disk = match YamlLoader::load_from_str(&text) {
+            Ok(mut docs) => Ok(if docs.is_empty() {
+                Yaml::Null
+            } else {
+                docs.remove(0)
+            }),
+            Err(err) => Err(err.to_string()),
+        };
+        self.disk_change = Some(DiskChange { text, disk });
+        self.mode = Mode::ConfirmDiskChange;
+        Ok(())
+    }
+
+    pub(super) fn apply_disk_change_action(&mut self, action: InputAction) -> Result<()> {
+        if self.mode == Mode::DiskDiff {
+            match action {
+                InputAction::MoveUp => self.list_selection = self.list_selection.saturating_sub(1),
+                InputAction::MoveDown => {
+                    let len = self.disk_diff_lines().len();
+                    self.list_selection = (self.list_selection + 1).min(len.saturating_sub(1));
+                }
+                InputAction::InputCommit | InputAction::Cancel => {
+                    self.mode = Mode::ConfirmDiskChange
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+        if matches!(action, InputAction::ShowBufferDiff) {
+            self.show_buffer_diff();
+            return Ok(());
+        }
+        let InputAction::DiskChange(choice) = action else {
+            return Ok(());
+        };
+        let Some(change) = self.disk_change.take() else {
+            self.mode = Mode::Normal;
+            return Ok(());
+        };
+        match choice {
+            DiskChoice::Merge => match change.disk {
+                Ok(disk) => {
+                    self.mode = Mode::Normal;
+                    self.start_merge(change.text, disk);
+                }
+                Err(err) => {
+                    self.set_toast(format!(
+                        "The file on disk does not parse ({err}); reload or keep yours"
+                    ));
+                    self.disk_change = Some(DiskChange {
+                        text: change.text,
+                        disk: Err(err),
+                    });
+                }
+            },
+            DiskChoice::Reload => {
+                self.mode = Mode::Normal;
+                let path = std::path::PathBuf::from(self.model.file_path());
+                self.reload_from_disk(&path)?;
+            }
+            DiskChoice::Keep => {
+                self.mode = Mode::Normal;
+                self.set_toast(
+                    "Kept your edits; saving will overwrite the changes on disk".to_string(),
+                );
+            }
+            DiskChoice::Diff => {
+                let unparsed = change.disk.as_ref().err().cloned();
+                self.disk_change = Some(change);
+                match unparsed {
+                    Some(err) => self.set_toast(format!("The file on disk does not parse: {err}")),
+                    None => {
+                        self.list_selection = 0;
+                        self.mode = Mode::DiskDiff;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// One line per path where the file on disk differs from the buffer: `~` changed,
+    /// `+` only on disk, `-` only in the buffer.
+    pub fn disk_diff_lines(&self) -> Vec<String> {
+        let Some(DiskChange { disk: Ok(disk), .. }) = &self.disk_change else {
+            return Vec::new();
+        };
+        let buffer = self.model.root();
+        merge::changes(buffer, disk)
+            .into_iter()
+            .map(|change| {
+                let label = if change.path.0.is_empty() {
+                    "(root)".to_string()
+                } else {
+                    change.path.dot_path()
+                };
+                let ours = get_node(buffer, &change.path).ok();
+                match (ours, &change.value) {
+                    (Some(ours), Some(theirs)) => {
+                        format!(
+                            "~ {label}: {} → {}",
+                            node_preview(ours),
+                            node_preview(theirs)
+                        )
+                    }
+                    (None, Some(theirs)) => format!("+ {label}: {}", node_preview(theirs)),
+                    (ours, None) => format!("- {label}: {}", side_preview(ours)),
+                }
+            })
+            .collect()
+    }
+
+    /// `=`: unified diff of the file on disk against what saving would write (the raw text in
+    /// the raw view). While a disk change is pending, the diff is against the changed file.
+    pub(super) fn show_buffer_diff(&mut self) {
+        let disk = match &self.disk_change {
+            Some(change) => change.text.clone(),
+            None => match std::fs::read_to_string(self.model.file_path()) {
+                Ok(text) => text,
+                Err(err) => {
+                    self.set_toast(format!("Cannot read {}: {err}", self.model.file_path()));
+                    return;
+                }
+            },
+        };
+        let buffer = match &self.raw_content {
+            Some(raw) => raw.clone(),
+            None => match self.model.render() {
+                Ok((text, _)) => text,
+                Err(err) => {
+                    self.set_toast(format!("Cannot render the buffer: {err}"));
+                    return;
+                }
+            },
+        };
+        let lines = line_diff::unified(&disk, &buffer, 3);
+        if lines.is_empty() {
+            self.set_toast("No differences from the file on disk".to_string());
+            return;
+        }
+        self.buffer_diff = lines;
+        self.list_selection = 0;
+        self.mode = Mode::BufferDiff;
+    }
+
+    /// j/k scroll the line diff; Enter or Esc close it, back to the disk change prompt if one
+    /// is pending.
+    pub(super) fn apply_buffer_diff_action(&mut self, action: InputAction) {
+        match action {
+            InputAction::MoveUp => self.list_selection = self.list_selection.saturating_sub(1),
+            InputAction::MoveDown => {
+                self.list_selection =
+                    (self.list_selection + 1).min(self.buffer_diff.len().saturating_sub(1));
+            }
+            InputAction::InputCommit | InputAction::Cancel => {
+                self.buffer_diff.clear();
+                self.mode = if self.disk_change.is_some() {
+                    Mode::ConfirmDiskChange
+                } else {
+                    Mode::Normal
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Merge the file on disk into the buffer against the baseline, asking about conflicts.
+    fn start_merge(&mut self, disk_text: String, disk: Yaml) {
+        let Some(baseline) = self.model.baseline() else {
+            self.set_toast("File changed on disk; saving will overwrite it".to_string());
+            return;
+        };
+        let result = merge::merge(baseline, &disk, self.model.root());
+        let picks = vec![None; result.conflicts.len()];
+        let state = MergeState {
+            disk_text,
+            disk,
+            merged: result.doc,
+            applied: result.applied,
+            conflicts: result.conflicts,
+            picks,
+        };
+        if state.conflicts.is_empty() {
+            self.finish_merge(state);
+        } else {
+            self.merge = Some(state);
+            self.list_selection = 0;
+            self.mode = Mode::MergeConflicts;
+        }
+    }
+
+    pub(super) fn apply_merge_action(&mut self, action: InputAction) {
+        let Some(state) = self.merge.as_mut() else {
+            self.mode = Mode::Normal;
+            return;
+        };
+        let len = state.conflicts.len();
+        match action {
+            InputAction::MoveUp => self.list_selection = self.list_selection.saturating_sub(1),
+            InputAction::MoveDown => {
+                self.list_selection = (self.list_selection + 1).min(len.saturating_sub(1));
+            }
+            InputAction::MergeTake(side) => {
+                state.picks[self.list_selection] = Some(Pick::Take(side));
+                self.select_next_unresolved();
+            }
+            InputAction::EditValue => {
+                let conflict = &state.conflicts[self.list_selection];
+                let current = conflict.buffer.as_ref().or(conflict.disk.as_ref());
+                match current {
+                    Some(Yaml::Hash(_) | Yaml::Array(_)) => {
+                        self.set_toast(
+                            "Custom values must be scalars; pick a side instead".to_string(),
+                        );
+                    }
+                    value => {
+                        self.input
+                            .set(value.map(scalar_preview).unwrap_or_default());
+                        self.mode = Mode::MergeValue;
+                    }
+                }
+            }
+            InputAction::InputCommit => {
+                let unresolved = state.unresolved();
+                if unresolved > 0 {
+                    self.set_toast(format!(
+                        "{unresolved} conflict(s) left: d disk, b buffer, o baseline, e edit"
+                    ));
+                } else if let Some(state) = self.merge.take() {
+                    self.finish_merge(state);
+                }
+            }
+            InputAction::Cancel => {
+                self.merge = None;
+                self.mode = Mode::Normal;
+                self.set_toast(
+                    "Merge cancelled; saving will overwrite the changes on disk".to_string(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Resolve the selected conflict with the value typed in `Mode::MergeValue`.
+    pub(super) fn commit_merge_value(&mut self) -> Result<()> {
+        let value = scalar_to_yaml(parse_scalar_input(&self.input.text)?);
+        if let Some(state) = self.merge.as_mut() {
+            state.picks[self.list_selection] = Some(Pick::Custom(value));
+        }
+        self.input.set(String::new());
+        self.mode = Mode::MergeConflicts;
+        self.select_next_unresolved();
+        Ok(())
+    }
+
+    fn select_next_unresolved(&mut self) {
+        let Some(state) = &self.merge else {
+            return;
+        };
+        let len = state.picks.len();
+        if let Some(next) = (1..=len)
+            .map(|step| (self.list_selection + step) % len)
+            .find(|idx| state.picks[*idx].is_none())
+        {
+            self.list_selection = next;
+        }
+    }
+
+    /// Apply the picks and adopt the merged document, leaving it dirty; undo restores the buffer.
+    fn finish_merge(&mut self, state: MergeState) {
+        let mut merged = state.merged.clone();
+        let mut failed = Vec::new();
+        for (idx, conflict) in state.conflicts.iter().enumerate() {
+            if let Some(value) = state.picked_value(idx) {
+                if merge::resolve(&mut merged, &conflict.path, value).is_err() {
+                    failed.push(conflict.path.dot_path());
+                }
+            }
+        }
+        let snapshot = self.model.snapshot();
+        let summary = format!(
+            "{} from disk, {} resolved",
+            state.applied,
+            state.conflicts.len()
+        );
+        self.model.adopt_merge(state.disk_text, state.disk, merged);
+        self.journal.record(
+            EditKind::Merge,
+            NodePath(Vec::new()),
+            "(buffer)".to_string(),
+            summary,
+            snapshot,
+        );
+        self.dirty = true;
+        self.mode = Mode::Normal;
+        self.rebuild_visible();
+        if failed.is_empty() {
+            self.set_toast(format!(
+                "Merged {} change(s) from disk, {} conflict(s) resolved",
+                state.applied,
+                state.conflicts.len()
+            ));
+        } else {
+            self.set_toast(format!("Merged; could not apply {}", failed.join(", ")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_diff::DiffLine;
+    use crate::settings::CliOverrides;
+    use crate::yaml_model::PathSegment;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn buffer_diff_shows_unsaved_lines_against_disk() {
+        let path =
+            std::env::temp_dir().join(format!("yed_app_buffer_diff_{}.yaml", std::process::id()));
+        std::fs::write(&path, "---\nname: api\nport: 80\n").unwrap();
+        let mut app = App::new(&path, CliOverrides::default()).unwrap();
+        app.apply_action(InputAction::ShowBufferDiff, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "No differences from the file on disk"
+        );
+
+        assert!(app.reveal_path(&NodePath(vec![PathSegment::Key("port".into())])));
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        app.input.set("8080".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        app.apply_action(InputAction::ShowBufferDiff, 10).unwrap();
+        assert_eq!(app.mode, Mode::BufferDiff);
+        let lines: Vec<String> = app.buffer_diff.iter().map(DiffLine::text).collect();
+        assert_eq!(
+            lines,
+            [
+                "@@ -1,3 +1,3 @@",
+                " ---",
+                " name: api",
+                "-port: 80",
+                "+port: 8080"
+            ]
+        );
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+
+        // From the disk change prompt the diff is against the changed file, and closes back to it.
+        std::fs::write(&path, "---\nname: web\nport: 80\n").unwrap();
+        app.last_modified = Some(std::time::UNIX_EPOCH);
+        app.last_file_check = None;
+        app.check_and_reload_if_changed().unwrap();
+        assert_eq!(app.mode, Mode::ConfirmDiskChange);
+        app.apply_action(InputAction::ShowBufferDiff, 10).unwrap();
+        assert_eq!(app.buffer_diff.len(), 6);
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+        assert_eq!(app.mode, Mode::ConfirmDiskChange);
+        drop(app);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn disk_change_prompt_diffs_keeps_or_reloads() {
+        let path = std::env::temp_dir().join(format!("yed_app_disk_{}.yaml", std::process::id()));
+        std::fs::write(&path, "name: api\nport: 80\n").unwrap();
+        let mut app = App::new(&path, CliOverrides::default()).unwrap();
+        let changed_on_disk = |app: &mut App, text: &str| {
+            std::fs::write(&path, text).unwrap();
+            app.last_modified = Some(std::time::UNIX_EPOCH);
+            app.last_file_check = None;
+            app.check_and_reload_if_changed().unwrap();
+        };
+        assert!(app.reveal_path(&NodePath(vec![PathSegment::Key("port".into())])));
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        app.input.set("8080".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+
+        changed_on_disk(&mut app, "name: web\nport: 80\nextra: 1\n");
+        assert_eq!(app.mode, Mode::ConfirmDiskChange);
+        app.apply_action(InputAction::DiskChange(DiskChoice::Diff), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::DiskDiff);
+        assert_eq!(
+            app.disk_diff_lines(),
+            [
+                "~ name: \"api\" → \"web\"",
+                "~ port: 8080 → 80",
+                "+ extra: 1"
+            ]
+        );
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+        assert_eq!(app.mode, Mode::ConfirmDiskChange);
+        app.apply_action(InputAction::DiskChange(DiskChoice::Keep), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.dirty);
+        assert_eq!(app.model.root()["port"], Yaml::Integer(8080));
+
+        changed_on_disk(&mut app, "name: [\n");
+        app.apply_action(InputAction::DiskChange(DiskChoice::Merge), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::ConfirmDiskChange);
+        assert!(app
+            .toast
+            .as_ref()
+            .unwrap()
+            .message
+            .starts_with("The file on disk does not parse"));
+
+        changed_on_disk(&mut app, "name: db\n");
+        app.apply_action(InputAction::DiskChange(DiskChoice::Reload), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(!app.dirty);
+        assert_eq!(app.model.root()["name"], Yaml::String("db".into()));
+        drop(app);
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,565 @@
+// File picker, shown when no file was given and after `o`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use yaml_rust2::yaml::Hash;
+use yaml_rust2::Yaml;
+
+use super::{load_settings, App, Mode};
+use crate::input::{InputAction, InputContext};
+use crate::positions;
+use crate::recent;
+use crate::settings::Settings;
+use crate::yaml_model::resolve_dot_path;
+
+#[derive(Clone, Debug)]
+pub enum PickerEntry {
+    /// Recently opened file, listed above the directory.
+    Recent(PathBuf),
+    Parent,
+    Dir(PathBuf),
+    File(PathBuf, FileMeta),
+}
+
+/// Size and modification time of a listed file, read once when the directory is listed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileMeta {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// Order of the files in the picker; folders always come first, by name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PickerSort {
+    #[default]
+    Name,
+    /// Most recently modified first.
+    Modified,
+}
+
+impl PickerSort {
+    pub fn label(self) -> &'static str {
+        match self {
+            PickerSort::Name => "name",
+            PickerSort::Modified => "modified",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FilePickerState {
+    pub current_dir: PathBuf,
+    pub entries: Vec<PickerEntry>,
+    pub sort: PickerSort,
+    /// Recently opened files, most recent first.
+    pub recent: Vec<PathBuf>,
+}
+
+impl App {
+    /// Settings for files in `dir`, keeping the current ones when discovery fails.
+    fn reload_picker_settings(&mut self, dir: &Path) {
+        let (settings, settings_error) = load_settings(dir, &self.cli);
+        self.settings = settings;
+        self.search_case = self.settings.search_case.value;
+        if let Some(err) = settings_error {
+            self.set_toast(err);
+        }
+    }
+
+    /// Remember the selected node of the open file for the next time it is opened.
+    pub fn remember_position(&self) {
+        let Some(state) = &self.cli.positions else {
+            return;
+        };
+        if self.is_file_picker() || self.raw_content.is_some() || self.model.file_path().is_empty()
+        {
+            return;
+        }
+        if let Some(row) = self.current_row() {
+            let _ = positions::record(
+                state,
+                Path::new(self.model.file_path()),
+                &row.path.dot_path(),
+            );
+        }
+    }
+
+    /// Select the node remembered for `path`; the top when it is gone.
+    pub(super) fn restore_position(&mut self, path: &Path) {
+        if self.raw_content.is_some() {
+            return;
+        }
+        let Some(dot) = self
+            .cli
+            .positions
+            .as_deref()
+            .and_then(|state| positions::lookup(state, path))
+        else {
+            return;
+        };
+        match resolve_dot_path(self.model.root(), &dot) {
+            Some(node) if self.reveal_path(&node) => self.scroll = self.selection,
+            _ => self.selection = 0,
+        }
+    }
+
+    /// Put `path` at the top of the recent-files list. Failing to write the list is not worth
+    /// interrupting the user over.
+    pub(super) fn remember_recent(&self, path: &Path) {
+        if let Some(state) = &self.cli.recent {
+            let _ = recent::record(state, path);
+        }
+    }
+
+    /// In file picker: enter selected item (change dir or open file). Returns true if dir was changed (refresh UI).
+    pub fn picker_enter_selected(&mut self) -> Result<bool> {
+        let picker = match &self.file_picker {
+            Some(p) => p.clone(),
+            None => return Ok(false),
+        };
+        let entry = match picker.entries.get(self.selection) {
+            Some(e) => e.clone(),
+            None => return Ok(false),
+        };
+        match entry {
+            PickerEntry::Parent => {
+                if let Some(parent) = picker.current_dir.parent() {
+                    let parent = parent.to_path_buf();
+                    std::env::set_current_dir(&parent)?;
+                    self.reload_picker_settings(&parent);
+                    let entries =
+                        list_picker_entries(&parent, &self.settings, picker.sort, &picker.recent)?;
+                    if let Some(ref mut fp) = self.file_picker {
+                        fp.current_dir = parent;
+                        fp.entries = entries;
+                    }
+                    self.selection = 0;
+                    return Ok(true);
+                }
+            }
+            PickerEntry::Dir(path) => {
+                if path.is_dir() {
+                    std::env::set_current_dir(&path)?;
+                    self.reload_picker_settings(&path);
+                    let entries =
+                        list_picker_entries(&path, &self.settings, picker.sort, &picker.recent)?;
+                    if let Some(ref mut fp) = self.file_picker {
+                        fp.current_dir = path;
+                        fp.entries = entries;
+                    }
+                    self.selection = 0;
+                    return Ok(true);
+                }
+            }
+            PickerEntry::Recent(path) | PickerEntry::File(path, _) => {
+                if let Err(e) = self.open_file(path) {
+                    self.set_toast(e.to_string());
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Refresh file picker entries (e.g. after changing directory).
+    pub fn picker_refresh(&mut self) -> Result<()> {
+        if let Some(ref mut fp) = self.file_picker {
+            fp.entries = list_picker_entries(&fp.current_dir, &self.settings, fp.sort, &fp.recent)?;
+            if self.selection >= fp.entries.len() {
+                self.selection = fp.entries.len().saturating_sub(1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Switch between sorting files by name and by modification time, keeping the selection on
+    /// the same entry.
+    fn picker_toggle_sort(&mut self) -> Result<()> {
+        let Some(picker) = self.file_picker.as_mut() else {
+            return Ok(());
+        };
+        picker.sort = match picker.sort {
+            PickerSort::Name => PickerSort::Modified,
+            PickerSort::Modified => PickerSort::Name,
+        };
+        let selected = picker
+            .entries
+            .get(self.selection)
+            .and_then(entry_path)
+            .map(Path::to_path_buf);
+        picker.entries = list_picker_entries(
+            &picker.current_dir,
+            &self.settings,
+            picker.sort,
+            &picker.recent,
+        )?;
+        if let Some(index) = picker.entries.iter().position(|entry| {
+            entry_path(entry).is_some() && entry_path(entry) == selected.as_deref()
+        }) {
+            self.selection = index;
+        }
+        let label = picker.sort.label();
+        self.set_toast(format!("Sorted by {label}"));
+        Ok(())
+    }
+
+    /// Switch from editor back to file picker (current file's directory).
+    pub fn switch_to_file_picker(&mut self) -> Result<()> {
+        self.remember_position();
+        let current_dir = if self.model.file_path().is_empty() {
+            std::env::current_dir()?
+        } else {
+            PathBuf::from(self.model.file_path())
+                .parent()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
+        };
+        let _ = std::env::set_current_dir(&current_dir);
+        self.lock = None;
+        self.lock_holder = None;
+        self.journal.clear();
+        self.hook_task = None;
+        self.hook_result = None;
+        self.reload_picker_settings(&current_dir);
+        let recent = self
+            .cli
+            .recent
+            .as_deref()
+            .map(recent::load)
+            .unwrap_or_default();
+        let entries =
+            list_picker_entries(&current_dir, &self.settings, PickerSort::default(), &recent)?;
+        self.file_picker = Some(FilePickerState {
+            current_dir,
+            entries,
+            sort: PickerSort::default(),
+            recent,
+        });
+        self.selection = 0;
+        self.mode = Mode::Normal;
+        Ok(())
+    }
+
+    /// Keys in the file picker. Returns true to quit.
+    pub(super) fn picker_handle_key(&mut self, key: KeyEvent, area_height: usize) -> bool {
+        if self.mode == Mode::NewFileName {
+            self.picker_name_key(key);
+            return false;
+        }
+        let len = self.file_picker.as_ref().map_or(0, |p| p.entries.len());
+        let page = match (key.code, key.modifiers) {
+            (KeyCode::PageDown, _) | (KeyCode::Char('f'), KeyModifiers::CONTROL) => {
+                area_height as isize
+            }
+            (KeyCode::PageUp, _) | (KeyCode::Char('b'), KeyModifiers::CONTROL) => {
+                -(area_height as isize)
+            }
+            _ => 0,
+        };
+        if page != 0 {
+            self.selection = self
+                .selection
+                .saturating_add_signed(page)
+                .min(len.saturating_sub(1));
+            return false;
+        }
+        match key.code {
+            KeyCode::Char('N') => {
+                self.mode = Mode::NewFileName;
+                self.input.set(String::new());
+            }
+            KeyCode::Char('m') => {
+                if let Err(err) = self.picker_toggle_sort() {
+                    self.set_toast(err.to_string());
+                }
+            }
+            KeyCode::Enter => {
+                let _ = self.picker_enter_selected();
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.selection = (self.selection + 1).min(len.saturating_sub(1));
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.selection = self.selection.saturating_sub(1);
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// Keys while typing the name of a new file.
+    fn picker_name_key(&mut self, key: KeyEvent) {
+        let Some(action) = self.vim.handle_key(InputContext {
+            mode: &self.mode,
+            key,
+        }) else {
+            return;
+        };
+        match action {
+            InputAction::Cancel => self.mode = Mode::Normal,
+            InputAction::InputCommit => {
+                if let Err(err) = self.create_new_file() {
+                    self.set_toast(err.to_string());
+                }
+            }
+            InputAction::InputChar(ch) => self.input.insert_char(ch),
+            InputAction::InputBackspace => self.input.backspace(),
+            InputAction::InputDelete => self.input.delete(),
+            InputAction::InputLeft => self.input.move_left(),
+            InputAction::InputRight => self.input.move_right(),
+            InputAction::InputHome => self.input.move_home(),
+            InputAction::InputEnd => self.input.move_end(),
+            _ => {}
+        }
+    }
+
+    /// Create the file named in `Mode::NewFileName` in the picker's directory and open it as an
+    /// empty mapping. `.yaml` is appended to names without a YAML extension.
+    fn create_new_file(&mut self) -> Result<()> {
+        let Some(dir) = self.file_picker.as_ref().map(|p| p.current_dir.clone()) else {
+            return Ok(());
+        };
+        let mut name = self.input.text.trim().to_string();
+        if name.is_empty() {
+            self.set_toast("File name cannot be empty".to_string());
+            return Ok(());
+        }
+        if name.contains(['/', '\\']) {
+            self.set_toast("Name only; the file is created in this folder".to_string());
+            return Ok(());
+        }
+        if !is_yaml_file(Path::new(&name)) {
+            name.push_str(".yaml");
+        }
+        let path = dir.join(&name);
+        if path.exists() {
+            self.set_toast(format!("{name} already exists"));
+            return Ok(());
+        }
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        self.picker_refresh()?;
+        self.open_file(path)?;
+        // An empty file loads as null; start from a mapping so `a` adds top-level keys.
+        *self.model.root_mut() = Yaml::Hash(Hash::new());
+        self.rebuild_visible();
+        self.set_toast(format!("Created {name}"));
+        Ok(())
+    }
+
+    /// Scroll moves the picker selection; a left click opens the entry under the pointer.
+    pub(super) fn picker_handle_mouse(&mut self, mouse: MouseEvent) {
+        let len = self.file_picker.as_ref().map_or(0, |p| p.entries.len());
+        match mouse.kind {
+            MouseEventKind::ScrollUp => {
+                self.selection = self.selection.saturating_sub(1);
+            }
+            MouseEventKind::ScrollDown => {
+                self.selection = (self.selection + 1).min(len.saturating_sub(1));
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                let clicked = self
+                    .hit_map
+                    .iter()
+                    .find(|hit| hit.y == mouse.row)
+                    .map(|hit| hit.row_index);
+                if let Some(index) = clicked.filter(|&index| index < len) {
+                    self.selection = index;
+                    let _ = self.picker_enter_selected();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn entry_path(entry: &PickerEntry) -> Option<&Path> {
+    match entry {
+        PickerEntry::Parent => None,
+        PickerEntry::Recent(path) | PickerEntry::Dir(path) | PickerEntry::File(path, _) => {
+            Some(path)
+        }
+    }
+}
+
+/// Entries for `dir`, after the `recent` files.
+pub(super) fn list_picker_entries(
+    dir: &Path,
+    settings: &Settings,
+    sort: PickerSort,
+    recent: &[PathBuf],
+) -> Result<Vec<PickerEntry>> {
+    let mut entries: Vec<PickerEntry> = recent.iter().cloned().map(PickerEntry::Recent).collect();
+    if dir.parent().is_some() {
+        entries.push(PickerEntry::Parent);
+    }
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    let mut files: Vec<(PathBuf, FileMeta)> = Vec::new();
+    for e in fs::read_dir(dir)? {
+        let e = e?;
+        let p = e.path();
+        if settings.is_ignored(&e.file_name().to_string_lossy()) {
+            continue;
+        }
+        // Follows symlinks, like opening the entry would.
+        let Ok(metadata) = fs::metadata(&p) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            dirs.push(p);
+        } else if metadata.is_file() && is_yaml_file(&p) {
+            let meta = FileMeta {
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+            };
+            files.push((p, meta));
+        }
+    }
+    dirs.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    match sort {
+        PickerSort::Name => files.sort_by(|a, b| a.0.file_name().cmp(&b.0.file_name())),
+        PickerSort::Modified => files.sort_by(|a, b| {
+            b.1.modified
+                .cmp(&a.1.modified)
+                .then_with(|| a.0.file_name().cmp(&b.0.file_name()))
+        }),
+    }
+    entries.extend(dirs.into_iter().map(PickerEntry::Dir));
+    entries.extend(
+        files
+            .into_iter()
+            .map(|(path, meta)| PickerEntry::File(path, meta)),
+    );
+    Ok(entries)
+}
+
+fn is_yaml_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("yaml") || e.eq_ignore_ascii_case("yml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::CliOverrides;
+    use crate::yaml_model::{NodePath, PathSegment};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn picker_lists_recent_files_first() {
+        use crate::app::PickerEntry;
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let dir = std::env::temp_dir().join(format!("yed_app_recent_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("empty")).unwrap();
+        let file = dir.join("values.yaml");
+        std::fs::write(&file, "a: 1\n").unwrap();
+        let cli = CliOverrides {
+            recent: Some(dir.join("recent")),
+            ..CliOverrides::default()
+        };
+        drop(App::new(&file, cli.clone()).unwrap());
+
+        let mut app = App::new_for_picker(cli).unwrap();
+        app.file_picker.as_mut().unwrap().current_dir = dir.join("empty");
+        app.picker_refresh().unwrap();
+        let entries = &app.file_picker.as_ref().unwrap().entries;
+        assert!(matches!(&entries[0], PickerEntry::Recent(path) if path.ends_with("values.yaml")));
+        assert!(matches!(entries[1], PickerEntry::Parent));
+
+        app.selection = 0;
+        app.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), 10)
+            .unwrap();
+        assert!(!app.is_file_picker());
+        assert!(app.model.file_path().ends_with("values.yaml"));
+        drop(app);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn picker_creates_a_new_file_and_opens_it_empty() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let dir = std::env::temp_dir().join(format!("yed_app_new_file_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("taken.yaml"), "a: 1\n").unwrap();
+        let mut app = App::new_for_picker(CliOverrides::default()).unwrap();
+        app.file_picker.as_mut().unwrap().current_dir = dir.clone();
+        let type_name = |app: &mut App, name: &str| {
+            app.handle_key(KeyEvent::new(KeyCode::Char('N'), KeyModifiers::SHIFT), 10)
+                .unwrap();
+            assert_eq!(app.mode, Mode::NewFileName);
+            for ch in name.chars() {
+                app.handle_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE), 10)
+                    .unwrap();
+            }
+            app.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), 10)
+                .unwrap();
+        };
+
+        type_name(&mut app, "taken");
+        assert!(app.is_file_picker());
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "taken.yaml already exists"
+        );
+        app.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+
+        type_name(&mut app, "service");
+        assert!(!app.is_file_picker());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("service.yaml")).unwrap(),
+            ""
+        );
+        assert_eq!(app.model.root(), &Yaml::Hash(Default::default()));
+        app.apply_action(InputAction::AddChild, 10).unwrap();
+        assert_eq!(app.mode, Mode::AddKey);
+        drop(app);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn reopening_a_file_selects_the_node_it_was_left_on() {
+        let dir = std::env::temp_dir().join(format!("yed_app_positions_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("values.yaml");
+        std::fs::write(
+            &file,
+            "spec:\n  containers:\n    - image: nginx\n      name: web\nname: api\n",
+        )
+        .unwrap();
+        let cli = CliOverrides {
+            positions: Some(dir.join("positions")),
+            ..CliOverrides::default()
+        };
+        let image = NodePath(vec![
+            PathSegment::Key("spec".into()),
+            PathSegment::Key("containers".into()),
+            PathSegment::Index(0),
+            PathSegment::Key("image".into()),
+        ]);
+        let mut app = App::new(&file, cli.clone()).unwrap();
+        assert!(app.reveal_path(&image));
+        app.remember_position();
+
+        let app = App::new(&file, cli.clone()).unwrap();
+        assert_eq!(app.current_row().unwrap().path, image);
+        assert!(app.expanded.contains("spec.containers.0"));
+        assert_eq!(app.scroll, app.selection);
+
+        // The node is gone: back to the top.
+        std::fs::write(&file, "name: api\n").unwrap();
+        let app = App::new(&file, cli).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!((app.selection, app.scroll), (0, 0));
+    }
+}
@@ -0,0 +1,119 @@
+// Filter the selected subtree through a shell command (`|`): its YAML goes to the command's
+// stdin and the YAML the command prints replaces it.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use yaml_rust2::YamlLoader;
+
+use super::{App, Mode};
+use crate::emitter::{self, EmitOptions};
+use crate::hooks;
+use crate::journal::EditKind;
+use crate::source_map::SourceMap;
+use crate::yaml_model::NodePath;
+
+impl App {
+    /// `|`: prompt for the command to pipe the selected node through.
+    pub(super) fn start_pipe(&mut self) {
+        if self.block_merged_edit() || self.current_row().is_none() {
+            return;
+        }
+        self.input.set(String::new());
+        self.mode = Mode::PipeCommand;
+    }
+
+    /// Run the command typed in `Mode::PipeCommand`. On failure the document is left as it was
+    /// and the command's stderr is shown.
+    pub(super) fn commit_pipe(&mut self) {
+        let command = self.input.text.trim().to_string();
+        if command.is_empty() {
+            self.set_toast("Command cannot be empty".to_string());
+            return;
+        }
+        self.input.set(String::new());
+        self.mode = Mode::Normal;
+        let Some(path) = self.current_row().map(|r| r.path.clone()) else {
+            return;
+        };
+        let before = self.begin_edit(&path);
+        match self.pipe_node(&path, &command) {
+            Ok(()) => {
+                self.record_edit(before, EditKind::Pipe, path.clone());
+                self.dirty = true;
+                self.rebuild_visible();
+                self.restore_selection(Some(path));
+                self.set_toast(format!("Piped through {command}"));
+            }
+            Err(err) => self.set_toast(format!("{err:#}")),
+        }
+    }
+
+    fn pipe_node(&mut self, path: &NodePath, command: &str) -> Result<()> {
+        let options = EmitOptions {
+            indent: self.settings.indent_width.value,
+            document_start: false,
+            ..EmitOptions::default()
+        };
+        let text = emitter::emit(self.model.node_at(path)?, &SourceMap::default(), &options)?;
+        let timeout = Duration::from_secs(self.settings.hook_timeout_secs.value);
+        let output = hooks::filter(command, &text, timeout)?;
+        let value = YamlLoader::load_from_str(&output)
+            .map_err(|err| anyhow!("{command}: output is not YAML: {err}"))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("{command}: printed nothing"))?;
+        self.model.replace_subtree(path, value)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::app::tests::app_with;
+    use crate::input::InputAction;
+    use pretty_assertions::assert_eq;
+    use yaml_rust2::Yaml;
+
+    #[test]
+    fn pipe_replaces_subtree_with_command_output() {
+        let mut app = app_with("pipe", "env:\n  name: api\n  tier: web\nport: 80\n");
+        let run = |app: &mut App, command: &str| {
+            app.apply_action(InputAction::StartPipe, 10).unwrap();
+            assert_eq!(app.mode, Mode::PipeCommand);
+            app.input.set(command.to_string());
+            app.apply_action(InputAction::InputCommit, 10).unwrap();
+        };
+        run(&mut app, "tr a-z A-Z");
+        assert_eq!(app.model.root()["ENV"]["NAME"], Yaml::String("API".into()));
+        assert!(app.model.root()["env"].is_badvalue());
+        assert!(app.dirty);
+        app.apply_action(InputAction::Undo, 10).unwrap();
+        assert_eq!(app.model.root()["env"]["name"], Yaml::String("api".into()));
+
+        app.selection = 1;
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "env");
+        run(&mut app, "grep -v tier");
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "env");
+        assert!(app.model.root()["env"]["tier"].is_badvalue());
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Piped through grep -v tier"
+        );
+
+        let before = app.model.root().clone();
+        run(&mut app, "echo 'no such field' >&2; exit 1");
+        assert_eq!(app.toast.as_ref().unwrap().message, "no such field");
+        run(&mut app, "echo '[unclosed'");
+        assert!(app
+            .toast
+            .as_ref()
+            .unwrap()
+            .message
+            .starts_with("echo '[unclosed': output is not YAML"));
+        run(&mut app, "true");
+        assert_eq!(app.toast.as_ref().unwrap().message, "true: printed nothing");
+        assert_eq!(*app.model.root(), before);
+        assert_eq!(app.mode, Mode::Normal);
+    }
+}
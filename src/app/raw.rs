@@ -0,0 +1,347 @@
+// Raw line view, shown instead of the tree while the file does not parse.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::{App, Mode};
+use crate::atomic_write;
+use crate::backup::{self, BackupOptions};
+use crate::input::InputAction;
+use crate::yaml_model::{flatten_visible, YamlModel};
+
+/// Lines shown above the parse-error line when a broken file is opened.
+const ERROR_CONTEXT_LINES: usize = 3;
+
+/// Spaces one `>` or `<` adds or removes.
+const INDENT_STEP: usize = 2;
+
+impl App {
+    /// When parse failed, lines of the file for raw edit view.
+    pub fn raw_lines(&self) -> Option<Vec<String>> {
+        self.raw_content
+            .as_ref()
+            .map(|s| s.lines().map(String::from).collect::<Vec<_>>())
+    }
+
+    /// Raw line index (0-based) the parse error points at, clamped to the file.
+    pub fn parse_error_line(&self) -> Option<usize> {
+        let error = self.parse_error.as_ref()?;
+        let len = self.raw_lines()?.len();
+        Some(error.line.saturating_sub(1).min(len.saturating_sub(1)))
+    }
+
+    /// Select the parse-error line after a load, with a few lines of context above it.
+    pub(super) fn select_parse_error(&mut self) {
+        if let Some(line) = self.parse_error_line() {
+            self.selection = line;
+            self.scroll = line.saturating_sub(ERROR_CONTEXT_LINES);
+        }
+    }
+
+    /// `!`: back to the parse-error line after scrolling away.
+    pub(super) fn jump_to_parse_error(&mut self, area_height: usize) {
+        match self.parse_error_line() {
+            Some(line) => {
+                self.selection = line;
+                self.ensure_visible(area_height);
+            }
+            None => self.set_toast("No parse error".to_string()),
+        }
+    }
+
+    /// Replace line at index in raw_content (for raw edit).
+    pub fn raw_replace_line(&mut self, line_index: usize, new_line: &str) {
+        if let Some(ref mut raw) = self.raw_content {
+            let mut lines: Vec<String> = raw.lines().map(String::from).collect();
+            if line_index < lines.len() {
+                lines[line_index] = new_line.lines().next().unwrap_or("").to_string();
+                *raw = join_lines(&lines, raw.ends_with('\n'));
+            }
+        }
+    }
+
+    /// Insert `text` as a new line at `line_index` (the line count appends) and select it.
+    pub fn raw_insert_line(&mut self, line_index: usize, text: &str) {
+        if let Some(ref mut raw) = self.raw_content {
+            let mut lines: Vec<String> = raw.lines().map(String::from).collect();
+            let line_index = line_index.min(lines.len());
+            lines.insert(line_index, text.lines().next().unwrap_or("").to_string());
+            *raw = join_lines(&lines, raw.ends_with('\n'));
+            self.dirty = true;
+            self.selection = line_index;
+        }
+    }
+
+    /// Add `steps` indent steps of leading spaces to the line at `line_index`.
+    pub fn raw_indent_line(&mut self, line_index: usize, steps: usize) {
+        let Some(line) = self
+            .raw_lines()
+            .and_then(|lines| lines.get(line_index).cloned())
+        else {
+            return;
+        };
+        self.raw_replace_line(
+            line_index,
+            &format!("{}{line}", " ".repeat(steps * INDENT_STEP)),
+        );
+        self.dirty = true;
+    }
+
+    /// Remove up to `steps` indent steps of leading spaces from the line at `line_index`.
+    /// Returns false if the line had none.
+    pub fn raw_dedent_line(&mut self, line_index: usize, steps: usize) -> bool {
+        let Some(line) = self
+            .raw_lines()
+            .and_then(|lines| lines.get(line_index).cloned())
+        else {
+            return false;
+        };
+        let leading = line.len() - line.trim_start_matches(' ').len();
+        let removed = leading.min(steps * INDENT_STEP);
+        if removed == 0 {
+            return false;
+        }
+        self.raw_replace_line(line_index, &line[removed..]);
+        self.dirty = true;
+        true
+    }
+
+    /// Remove line at index from raw_content (raw view: d or Shift+Del).
+    pub fn raw_delete_line(&mut self, line_index: usize) {
+        if let Some(ref mut raw) = self.raw_content {
+            let mut lines: Vec<String> = raw.lines().map(String::from).collect();
+            if line_index < lines.len() {
+                lines.remove(line_index);
+                *raw = join_lines(&lines, raw.ends_with('\n'));
+                self.dirty = true;
+                if self.selection >= lines.len() && !lines.is_empty() {
+                    self.selection = lines.len() - 1;
+                } else if lines.is_empty() {
+                    self.selection = 0;
+                }
+            }
+        }
+    }
+
+    /// Save raw content to file and re-parse; clear parse_error if successful.
+    pub fn save_raw_and_reparse(&mut self) -> Result<()> {
+        let raw = match &self.raw_content {
+            Some(r) => r.clone(),
+            None => return Ok(()),
+        };
+        let path = PathBuf::from(self.model.file_path());
+        let backup_error = BackupOptions::from_settings(&self.settings)
+            .filter(|options| !(options.once && self.model.saved_in_session()))
+            .and_then(|options| backup::backup(&path, &options).err())
+            .map(|err| format!("; backup failed: {err:#}"))
+            .unwrap_or_default();
+        atomic_write::write(&path, raw.as_bytes())?;
+        let (model, parse_error, raw_content) = YamlModel::load_with_error(&path)?;
+        self.model = model;
+        self.model.set_saved_in_session(true);
+        self.journal.clear();
+        self.model.configure(&self.settings);
+        self.parse_error = parse_error.clone();
+        self.raw_content = raw_content;
+        self.dirty = false;
+        if parse_error.is_none() {
+            let mut expanded = HashSet::new();
+            expanded.insert(String::new());
+            self.tree_root = self.model.build_tree();
+            self.visible = flatten_visible(&self.tree_root, &expanded, None);
+            self.selection = 0;
+            self.scroll = 0;
+            self.set_toast(format!("Saved and parsed successfully{backup_error}"));
+            self.refresh_baseline_diff();
+            self.announce_duplicates();
+            self.start_integrity_check();
+        } else {
+            self.select_parse_error();
+            self.set_toast(format!("Saved; parse still has errors{backup_error}"));
+        }
+        self.start_post_save_hook();
+        self.refresh_lint();
+        Ok(())
+    }
+
+    /// Handle the actions that differ in the raw view: saving reparses, `e` and `d` work on
+    /// lines, and tree-only actions explain why they are unavailable. Returns false for actions
+    /// that behave as in the tree view (movement, search, prompts).
+    pub(super) fn apply_raw_action(&mut self, action: &InputAction) -> Result<bool> {
+        let unavailable = match action {
+            InputAction::Save => {
+                self.save_raw_and_reparse()?;
+                return Ok(true);
+            }
+            InputAction::EditValue => {
+                self.start_raw_edit_line()?;
+                return Ok(true);
+            }
+            InputAction::DeleteNode | InputAction::DeleteLine => {
+                self.mode = Mode::ConfirmRawDeleteLine;
+                return Ok(true);
+            }
+            InputAction::IndentLine(steps) => {
+                self.raw_indent_line(self.selection, *steps);
+                return Ok(true);
+            }
+            InputAction::DedentLine(steps) => {
+                if !self.raw_dedent_line(self.selection, *steps) {
+                    self.set_toast("Line is not indented".to_string());
+                }
+                return Ok(true);
+            }
+            InputAction::AddSibling | InputAction::OpenLineAbove => {
+                self.open_raw_line(matches!(action, InputAction::AddSibling));
+                return Ok(true);
+            }
+            InputAction::SaveAs => "Save as",
+            InputAction::EditMultiline | InputAction::EditInEditor => "Edit",
+            InputAction::CastType => "Cast",
+            InputAction::ToggleFlowStyle => "Style toggle",
+            InputAction::ToggleBool => "Toggle",
+            InputAction::RenameKey | InputAction::RenameKeyEverywhere => "Key rename",
+            InputAction::AddChild => "Add child",
+            InputAction::AddMapToSequence => "Add object",
+            InputAction::CopyFencedYaml
+            | InputAction::CopyMarkdownTable
+            | InputAction::CopyPointer
+            | InputAction::CopyPatchOp => "Copy",
+            InputAction::YankNode => "Yank",
+            InputAction::Paste => "Paste",
+            InputAction::Undo => "Undo",
+            InputAction::StartReplace => "Replace",
+            InputAction::StartPipe => "Pipe",
+            InputAction::ToggleEnvPreview | InputAction::ShowPlaceholders => "Placeholders",
+            InputAction::SetMark(_) | InputAction::JumpToMark(_) | InputAction::ShowMarks => {
+                "Marks"
+            }
+            InputAction::ScrollLeft | InputAction::ScrollRight => "Sideways scroll",
+            InputAction::ExpandAll
+            | InputAction::CollapseAll
+            | InputAction::ExpandSubtree
+            | InputAction::CollapseSubtree
+            | InputAction::ExpandToDepth(_)
+            | InputAction::ToggleFocus => "Folding",
+            InputAction::GotoPath => "Go to path",
+            InputAction::JumpBack | InputAction::JumpForward => "Jump list",
+            InputAction::ToggleIndices => "Index column",
+            InputAction::ShowStats => "Stats",
+            InputAction::NextSibling | InputAction::PrevSibling => "Sibling jump",
+            _ => return Ok(false),
+        };
+        self.set_toast(format!(
+            "{unavailable}: fix parse errors or save to use tree view"
+        ));
+        Ok(true)
+    }
+
+    fn start_raw_edit_line(&mut self) -> Result<()> {
+        let lines = match self.raw_lines() {
+            Some(l) => l,
+            None => return Ok(()),
+        };
+        if self.selection < lines.len() {
+            self.mode = Mode::RawEditLine;
+            self.input.set(lines[self.selection].clone());
+        }
+        Ok(())
+    }
+
+    /// `o`/`O`: insert a blank line below or above the selected one and start editing it.
+    fn open_raw_line(&mut self, below: bool) {
+        let Some(len) = self.raw_lines().map(|lines| lines.len()) else {
+            return;
+        };
+        let index = if below && len > 0 {
+            self.selection + 1
+        } else {
+            self.selection
+        };
+        self.raw_insert_line(index.min(len), "");
+        self.input.set(String::new());
+        self.mode = Mode::RawEditLine;
+    }
+
+    /// Store the line edited in `Mode::RawEditLine`.
+    pub(super) fn commit_raw_line(&mut self) {
+        let text = self.input.text.clone();
+        self.raw_replace_line(self.selection, &text);
+        self.mode = Mode::Normal;
+        self.dirty = true;
+    }
+}
+
+/// Raw lines back into file text. A trailing newline is kept, and added when the last line is
+/// blank, since `str::lines` would otherwise drop that line.
+fn join_lines(lines: &[String], trailing_newline: bool) -> String {
+    let mut text = lines.join("\n");
+    if !lines.is_empty() && (trailing_newline || lines.last().is_some_and(String::is_empty)) {
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::app_with;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn raw_view_opens_lines_below_and_above() {
+        let mut app = app_with("raw_open", "a: [\nb: 1\n");
+        app.selection = 0;
+        app.apply_action(InputAction::AddSibling, 10).unwrap();
+        assert_eq!(app.mode, Mode::RawEditLine);
+        assert_eq!(app.selection, 1);
+        app.input.set("c: 2".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.raw_lines().unwrap(), ["a: [", "c: 2", "b: 1"]);
+        assert!(app.dirty);
+
+        app.apply_action(InputAction::OpenLineAbove, 10).unwrap();
+        assert_eq!(app.selection, 1);
+        app.input.set("]".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.raw_lines().unwrap(), ["a: [", "]", "c: 2", "b: 1"]);
+
+        app.apply_action(InputAction::JumpBottom, 10).unwrap();
+        app.apply_action(InputAction::AddSibling, 10).unwrap();
+        assert_eq!(app.selection, 4);
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+        assert_eq!(app.raw_lines().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn raw_view_indents_with_a_count() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = app_with("raw_indent", "a:\nb: [\n   c: 1\n");
+        let press = |app: &mut App, ch: char| {
+            app.handle_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE), 10)
+                .unwrap();
+        };
+        app.selection = 1;
+        press(&mut app, '>');
+        assert_eq!(app.raw_lines().unwrap()[1], "  b: [");
+        assert!(app.dirty);
+        press(&mut app, '1');
+        press(&mut app, '0');
+        press(&mut app, '<');
+        assert_eq!(app.raw_lines().unwrap()[1], "b: [");
+        press(&mut app, '<');
+        assert_eq!(app.toast.as_ref().unwrap().message, "Line is not indented");
+
+        app.selection = 2;
+        press(&mut app, '3');
+        press(&mut app, '>');
+        assert_eq!(app.raw_lines().unwrap()[2], "         c: 1");
+        // A count only applies to the next key.
+        press(&mut app, '2');
+        press(&mut app, 'j');
+        press(&mut app, '<');
+        assert_eq!(app.raw_lines().unwrap()[2], "       c: 1");
+    }
+}
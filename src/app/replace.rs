@@ -0,0 +1,374 @@
+// Find and replace in scalar values or keys (`:%s/old/new/`), optionally confirming each match.
+
+use super::{App, Mode};
+use crate::input::InputAction;
+use crate::journal::EditKind;
+use crate::replace::ReplaceCommand;
+use crate::yaml_model::{NodePath, Snapshot};
+
+/// A replacement walking its matches one by one (the `c` flag).
+pub struct ReplaceState {
+    command: ReplaceCommand,
+    targets: Vec<NodePath>,
+    /// Index of the match being asked about.
+    next: usize,
+    replaced: usize,
+    /// Matches left alone because they are shared through an anchor.
+    shared: usize,
+    /// Keys left alone because their mapping already has the new name.
+    taken: usize,
+    /// Why each of the other matches that could not be replaced failed.
+    failed: Vec<String>,
+    /// The document before the first replacement, for the journal.
+    snapshot: Snapshot,
+}
+
+impl App {
+    /// `:`: prompt for a replace command, starting from the active search query if any.
+    pub(super) fn start_replace(&mut self) {
+        let find = match &self.search_query {
+            Some(query) if !query.is_regex() && !query.is_fuzzy() => {
+                query.text().replace('/', "\\/")
+            }
+            _ => String::new(),
+        };
+        self.input.set(format!("%s/{find}/"));
+        self.mode = Mode::ReplaceCommand;
+    }
+
+    /// Run the command typed in `Mode::ReplaceCommand`.
+    pub(super) fn commit_replace(&mut self) {
+        let command = match ReplaceCommand::parse(&self.input.text) {
+            Ok(command) => command,
+            Err(err) => {
+                self.set_toast(err.to_string());
+                return;
+            }
+        };
+        self.input.set(String::new());
+        self.mode = Mode::Normal;
+        let (targets, shared) = if command.keys {
+            self.model.key_replace_targets(&command.find)
+        } else {
+            self.model.replace_targets(&command.find, command.coerce)
+        };
+        if targets.is_empty() {
+            let what = if command.keys { "keys" } else { "values" };
+            self.set_toast(format!(
+                "No {what} contain \"{}\"{}",
+                command.find,
+                shared_note(shared)
+            ));
+            return;
+        }
+        let mut state = ReplaceState {
+            command,
+            targets,
+            next: 0,
+            replaced: 0,
+            shared,
+            taken: 0,
+            failed: Vec::new(),
+            snapshot: self.model.snapshot(),
+        };
+        if state.command.confirm {
+            let first = state.targets[0].clone();
+            self.replace_state = Some(state);
+            self.mode = Mode::ConfirmReplace;
+            self.reveal_path(&first);
+        } else {
+            self.replace_from(&mut state, 0);
+            self.finish_replace(state);
+        }
+    }
+
+    /// `y` replace and move on, `n` skip, `a` replace this and the rest, `q`/Esc stop.
+    pub(super) fn apply_replace_action(&mut self, action: InputAction) {
+        let Some(mut state) = self.replace_state.take() else {
+            self.mode = Mode::Normal;
+            return;
+        };
+        match action {
+            InputAction::ConfirmYes => {
+                let end = state.next + 1;
+                self.replace_range(&mut state, end);
+                state.next = end;
+            }
+            InputAction::ConfirmNo => state.next += 1,
+            InputAction::ReplaceAll => {
+                let start = state.next;
+                self.replace_from(&mut state, start);
+                state.next = state.targets.len();
+            }
+            InputAction::Cancel => state.next = state.targets.len(),
+            _ => {
+                self.replace_state = Some(state);
+                return;
+            }
+        }
+        match state.targets.get(state.next).cloned() {
+            Some(path) => {
+                self.replace_state = Some(state);
+                self.reveal_path(&path);
+            }
+            None => {
+                self.mode = Mode::Normal;
+                self.finish_replace(state);
+            }
+        }
+    }
+
+    /// Confirm prompt for the match being asked about: the value (or key) now and after
+    /// replacing.
+    pub fn replace_prompt(&self) -> Option<String> {
+        let state = self.replace_state.as_ref()?;
+        let path = state.targets.get(state.next)?;
+        let command = &state.command;
+        let (old, new) = if command.keys {
+            self.model
+                .key_replace_preview(path, &command.find, &command.with)?
+        } else {
+            self.model
+                .replace_preview(path, &command.find, &command.with)?
+        };
+        Some(format!(
+            "{}: \"{old}\" → \"{new}\"  ({}/{}; y: replace, n: skip, a: all, q: stop)",
+            path.dot_path(),
+            state.next + 1,
+            state.targets.len()
+        ))
+    }
+
+    fn replace_from(&mut self, state: &mut ReplaceState, start: usize) {
+        state.next = start;
+        let end = state.targets.len();
+        self.replace_range(state, end);
+    }
+
+    /// Replace in the matches from `state.next` up to `end`.
+    fn replace_range(&mut self, state: &mut ReplaceState, end: usize) {
+        let (find, with) = (&state.command.find, &state.command.with);
+        for index in state.next..end {
+            let path = state.targets[index].clone();
+            if !state.command.keys {
+                match self.model.replace_in_scalar(&path, find, with) {
+                    Ok(()) => state.replaced += 1,
+                    Err(err) => state.failed.push(format!("{}: {err}", path.dot_path())),
+                }
+                continue;
+            }
+            let renamed = match self.model.replace_in_key(&path, find, with) {
+                Ok(Some(renamed)) => renamed,
+                Ok(None) => {
+                    state.taken += 1;
+                    continue;
+                }
+                Err(err) => {
+                    state.failed.push(format!("{}: {err}", path.dot_path()));
+                    continue;
+                }
+            };
+            state.replaced += 1;
+            // Keys further on below the renamed one now live under its new name.
+            for target in &mut state.targets[index + 1..] {
+                if target.0.starts_with(&path.0) {
+                    target.0.splice(..path.0.len(), renamed.0.iter().cloned());
+                }
+            }
+        }
+    }
+
+    fn finish_replace(&mut self, state: ReplaceState) {
+        if state.replaced > 0 {
+            self.journal.record(
+                EditKind::Replace,
+                NodePath(Vec::new()),
+                state.command.find.clone(),
+                format!("{} ({}×)", state.command.with, state.replaced),
+                state.snapshot,
+            );
+            self.dirty = true;
+            self.rebuild_visible();
+        }
+        let values = match (state.command.keys, state.replaced) {
+            (true, 1) => "key",
+            (true, _) => "keys",
+            (false, 1) => "value",
+            (false, _) => "values",
+        };
+        // Matches declined with `n` or left when stopping with `q`.
+        let skipped = state.targets.len() - state.replaced - state.taken - state.failed.len();
+        let skips = if state.command.confirm && skipped > 0 {
+            format!(", skipped {skipped}")
+        } else {
+            String::new()
+        };
+        let taken = if state.taken > 0 {
+            format!("; kept {} whose new name was already taken", state.taken)
+        } else {
+            String::new()
+        };
+        let failed = match state.failed.first() {
+            Some(first) => format!("; {} failed ({first})", state.failed.len()),
+            None => String::new(),
+        };
+        self.set_toast(format!(
+            "Replaced {} {values}{skips}{taken}{failed}{}",
+            state.replaced,
+            shared_note(state.shared)
+        ));
+    }
+}
+
+fn shared_note(shared: usize) -> String {
+    if shared > 0 {
+        format!("; skipped {shared} shared through an anchor")
+    } else {
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::app_with;
+    use crate::search::Query;
+    use pretty_assertions::assert_eq;
+    use yaml_rust2::Yaml;
+
+    #[test]
+    fn replace_all_or_confirm_each_match() {
+        let mut app = app_with(
+            "replace",
+            "a: staging-db\nb: staging-web\nc: prod\nport: 8080\n",
+        );
+        let run = |app: &mut App, command: &str| {
+            app.apply_action(InputAction::StartReplace, 10).unwrap();
+            assert_eq!(app.mode, Mode::ReplaceCommand);
+            app.input.set(command.to_string());
+            app.apply_action(InputAction::InputCommit, 10).unwrap();
+        };
+        run(&mut app, "%s/staging/prod/");
+        assert_eq!(app.toast.as_ref().unwrap().message, "Replaced 2 values");
+        assert_eq!(app.model.root()["b"], Yaml::String("prod-web".into()));
+        assert!(app.dirty);
+        app.apply_action(InputAction::Undo, 10).unwrap();
+        assert_eq!(app.model.root()["a"], Yaml::String("staging-db".into()));
+
+        run(&mut app, "%s/staging/qa/c");
+        assert_eq!(app.mode, Mode::ConfirmReplace);
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "a");
+        assert_eq!(
+            app.replace_prompt().unwrap(),
+            "a: \"staging-db\" → \"qa-db\"  (1/2; y: replace, n: skip, a: all, q: stop)"
+        );
+        app.apply_action(InputAction::ConfirmNo, 10).unwrap();
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "b");
+        app.apply_action(InputAction::ConfirmYes, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Replaced 1 value, skipped 1"
+        );
+        assert_eq!(app.model.root()["a"], Yaml::String("staging-db".into()));
+        assert_eq!(app.model.root()["b"], Yaml::String("qa-web".into()));
+
+        run(&mut app, "%s/8080/9090/");
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "No values contain \"8080\""
+        );
+        run(&mut app, "%s/8080/9090/t");
+        assert_eq!(app.model.root()["port"], Yaml::Integer(9090));
+
+        // A search query seeds the command.
+        app.search_query = Some(Query::plain("prod"));
+        app.apply_action(InputAction::StartReplace, 10).unwrap();
+        assert_eq!(app.input.text, "%s/prod/");
+        app.input.set("%s/prod".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.mode, Mode::ReplaceCommand);
+        assert_eq!(app.toast.as_ref().unwrap().message, "Expected %s/old/new/");
+    }
+
+    #[test]
+    fn key_replace_tells_taken_names_from_failures() {
+        let mut app = app_with("replace_keys", "x-a: 1\nx-b: 2\nb: 3\nx-: 4\n");
+        app.apply_action(InputAction::StartReplace, 10).unwrap();
+        app.input.set("%s/x-//k".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Replaced 1 key; kept 1 whose new name was already taken; 1 failed (x-: Key cannot be empty)"
+        );
+        let keys: Vec<&str> = app
+            .model
+            .root()
+            .as_hash()
+            .unwrap()
+            .keys()
+            .map(|k| k.as_str().unwrap())
+            .collect();
+        assert_eq!(keys, ["a", "x-b", "b", "x-"]);
+    }
+
+    #[test]
+    fn replace_in_keys_keeps_their_place_and_undoes_at_once() {
+        let yaml = "old-host:\n  old-port: 80\n  name: old-api\nkeep: 1\nold-name: x\nname: y\n";
+        let mut app = app_with("replace_keys", yaml);
+        let run = |app: &mut App, command: &str| {
+            app.apply_action(InputAction::StartReplace, 10).unwrap();
+            app.input.set(command.to_string());
+            app.apply_action(InputAction::InputCommit, 10).unwrap();
+        };
+        let keys = |at: &Yaml| -> Vec<String> {
+            at.as_hash()
+                .unwrap()
+                .keys()
+                .map(|key| key.as_str().unwrap().to_string())
+                .collect()
+        };
+        run(&mut app, "%s/old-//k");
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Replaced 2 keys; kept 1 whose new name was already taken"
+        );
+        assert_eq!(keys(app.model.root()), ["host", "keep", "old-name", "name"]);
+        assert_eq!(keys(&app.model.root()["host"]), ["port", "name"]);
+        // Values are left alone.
+        assert_eq!(
+            app.model.root()["host"]["name"],
+            Yaml::String("old-api".into())
+        );
+        app.apply_action(InputAction::Undo, 10).unwrap();
+        assert_eq!(
+            keys(app.model.root()),
+            ["old-host", "keep", "old-name", "name"]
+        );
+        assert_eq!(keys(&app.model.root()["old-host"]), ["old-port", "name"]);
+
+        // Confirming each: the nested key is asked about under its parent's new name.
+        run(&mut app, "%s/old/new/kc");
+        assert_eq!(
+            app.replace_prompt().unwrap(),
+            "old-host: \"old-host\" → \"new-host\"  (1/3; y: replace, n: skip, a: all, q: stop)"
+        );
+        app.apply_action(InputAction::ConfirmYes, 10).unwrap();
+        assert_eq!(
+            app.current_row().unwrap().path.dot_path(),
+            "new-host.old-port"
+        );
+        app.apply_action(InputAction::ConfirmYes, 10).unwrap();
+        app.apply_action(InputAction::ConfirmNo, 10).unwrap();
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Replaced 2 keys, skipped 1"
+        );
+        assert_eq!(app.model.root()["new-host"]["new-port"], Yaml::Integer(80));
+        run(&mut app, "%s/missing/x/k");
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "No keys contain \"missing\""
+        );
+    }
+}
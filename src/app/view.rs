@@ -0,0 +1,315 @@
+// Selection and scroll arithmetic for the tree and raw views, independent of the rest of `App`.
+
+/// Selected row and first shown row of a list of `len` rows, `height` rows of which fit on screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ViewState {
+    pub selection: usize,
+    pub scroll: usize,
+    pub len: usize,
+    pub height: usize,
+}
+
+impl ViewState {
+    /// Scroll just far enough for the selection to be on screen.
+    pub fn ensure_visible(&mut self) {
+        if self.len == 0 {
+            return;
+        }
+        if self.selection < self.scroll {
+            self.scroll = self.selection;
+        } else if self.selection >= self.scroll + self.height {
+            self.scroll = self.selection.saturating_sub(self.height.saturating_sub(1));
+        }
+    }
+
+    /// Pull the selection back on screen after the view scrolled away from it.
+    pub fn clamp_selection(&mut self) {
+        if self.selection < self.scroll {
+            self.selection = self.scroll;
+        } else if self.selection >= self.scroll + self.height {
+            self.selection = self.scroll + self.height.saturating_sub(1);
+            if self.selection >= self.len {
+                self.selection = self.len.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Move the selection `delta` rows, stopping at either end.
+    pub fn move_by(&mut self, delta: isize) {
+        if self.len == 0 {
+            return;
+        }
+        self.page_by(delta);
+    }
+
+    /// Like `move_by`, but also resets the selection of an empty list.
+    pub fn page_by(&mut self, delta: isize) {
+        let next = (self.selection as isize + delta).max(0);
+        self.selection = next.min(self.len.saturating_sub(1) as isize) as usize;
+        self.ensure_visible();
+    }
+
+    /// `Ctrl+f`/`Ctrl+b`: move the view and the selection a whole screen, stopping at either
+    /// end.
+    pub fn full_page(&mut self, forward: bool) {
+        let delta = if forward {
+            self.height as isize
+        } else {
+            -(self.height as isize)
+        };
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(delta)
+            .min(self.len.saturating_sub(self.height));
+        self.page_by(delta);
+    }
+
+    pub fn top(&mut self) {
+        self.selection = 0;
+    }
+
+    pub fn bottom(&mut self) {
+        if self.len > 0 {
+            self.selection = self.len - 1;
+        }
+    }
+
+    /// `H`: select the first row on screen, leaving the scroll alone.
+    pub fn screen_top(&mut self) {
+        self.select_on_screen(0);
+    }
+
+    /// `M`: select the middle of the rows on screen.
+    pub fn screen_middle(&mut self) {
+        let shown = self.height.min(self.len.saturating_sub(self.scroll));
+        self.select_on_screen(shown.saturating_sub(1) / 2);
+    }
+
+    /// `L`: select the last row on screen.
+    pub fn screen_bottom(&mut self) {
+        self.select_on_screen(self.height.saturating_sub(1));
+    }
+
+    /// Select the row `offset` rows below the top of the screen, or the last row.
+    fn select_on_screen(&mut self, offset: usize) {
+        if self.len > 0 {
+            self.selection = (self.scroll + offset).min(self.len - 1);
+        }
+    }
+
+    /// `zz`: scroll so the selection sits in the middle of the screen, as far as the rows allow.
+    pub fn center(&mut self) {
+        self.scroll_to(self.selection.saturating_sub(self.height / 2));
+    }
+
+    /// `zt`: scroll so the selection is the first row shown.
+    pub fn selection_to_top(&mut self) {
+        self.scroll_to(self.selection);
+    }
+
+    /// `zb`: scroll so the selection is the last row shown.
+    pub fn selection_to_bottom(&mut self) {
+        self.scroll_to(self.selection.saturating_sub(self.height.saturating_sub(1)));
+    }
+
+    fn scroll_to(&mut self, scroll: usize) {
+        self.scroll = scroll.min(self.len.saturating_sub(self.height));
+    }
+
+    /// Scroll the view `delta` rows (mouse wheel), keeping the selection on screen.
+    pub fn scroll_by(&mut self, delta: isize) {
+        let max_scroll = self.len.saturating_sub(self.height);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max_scroll);
+        self.clamp_selection();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::app_with;
+    use crate::app::{App, PickerEntry};
+    use crate::settings::CliOverrides;
+
+    fn view(selection: usize, scroll: usize, len: usize, height: usize) -> ViewState {
+        ViewState {
+            selection,
+            scroll,
+            len,
+            height,
+        }
+    }
+
+    #[test]
+    fn moving_scrolls_only_when_leaving_the_screen() {
+        let mut v = view(0, 0, 20, 5);
+        v.move_by(4);
+        assert_eq!((v.selection, v.scroll), (4, 0));
+        v.move_by(1);
+        assert_eq!((v.selection, v.scroll), (5, 1));
+        v.move_by(100);
+        assert_eq!((v.selection, v.scroll), (19, 15));
+        v.move_by(-17);
+        assert_eq!((v.selection, v.scroll), (2, 2));
+        v.move_by(-5);
+        assert_eq!((v.selection, v.scroll), (0, 0));
+
+        let mut empty = view(3, 0, 0, 5);
+        empty.move_by(1);
+        assert_eq!(empty.selection, 3);
+        empty.page_by(1);
+        assert_eq!(empty.selection, 0);
+    }
+
+    #[test]
+    fn full_pages_keep_the_selection_on_screen_and_stop_at_the_ends() {
+        let mut v = view(2, 0, 23, 10);
+        v.full_page(true);
+        assert_eq!((v.selection, v.scroll), (12, 10));
+        v.full_page(true);
+        assert_eq!((v.selection, v.scroll), (22, 13));
+        v.full_page(true);
+        assert_eq!((v.selection, v.scroll), (22, 13));
+        v.full_page(false);
+        assert_eq!((v.selection, v.scroll), (12, 3));
+        v.full_page(false);
+        assert_eq!((v.selection, v.scroll), (2, 0));
+    }
+
+    #[test]
+    fn wheel_scroll_stops_at_the_end_and_drags_the_selection() {
+        let mut v = view(0, 0, 8, 5);
+        v.scroll_by(1);
+        assert_eq!((v.selection, v.scroll), (1, 1));
+        for _ in 0..10 {
+            v.scroll_by(1);
+        }
+        assert_eq!((v.selection, v.scroll), (3, 3));
+        v.selection = 7;
+        v.scroll_by(-3);
+        assert_eq!((v.selection, v.scroll), (4, 0));
+
+        // A list shorter than the screen never scrolls.
+        let mut short = view(2, 0, 3, 5);
+        short.scroll_by(1);
+        assert_eq!((short.selection, short.scroll), (2, 0));
+    }
+
+    #[test]
+    fn h_m_l_select_on_screen_without_scrolling() {
+        let mut v = view(12, 10, 30, 6);
+        v.screen_top();
+        assert_eq!((v.selection, v.scroll), (10, 10));
+        v.screen_middle();
+        assert_eq!((v.selection, v.scroll), (12, 10));
+        v.screen_bottom();
+        assert_eq!((v.selection, v.scroll), (15, 10));
+
+        // Fewer rows than the screen holds: the middle and last of those shown.
+        let mut short = view(0, 0, 5, 10);
+        short.screen_middle();
+        assert_eq!(short.selection, 2);
+        short.screen_bottom();
+        assert_eq!(short.selection, 4);
+    }
+
+    #[test]
+    fn center_top_and_bottom_stop_at_the_ends() {
+        let mut v = view(20, 18, 30, 10);
+        v.center();
+        assert_eq!(v.scroll, 15);
+        v.selection_to_top();
+        assert_eq!(v.scroll, 20);
+        v.selection_to_bottom();
+        assert_eq!(v.scroll, 11);
+
+        // Near the ends there are no rows to scroll past.
+        v.selection = 27;
+        v.selection_to_top();
+        assert_eq!(v.scroll, 20);
+        v.selection = 2;
+        v.center();
+        assert_eq!(v.scroll, 0);
+        v.selection_to_bottom();
+        assert_eq!(v.scroll, 0);
+    }
+
+    #[test]
+    fn ensure_visible_after_jumps() {
+        let mut v = view(10, 0, 30, 6);
+        v.bottom();
+        v.ensure_visible();
+        assert_eq!((v.selection, v.scroll), (29, 24));
+        v.top();
+        v.ensure_visible();
+        assert_eq!((v.selection, v.scroll), (0, 0));
+
+        let mut zero_height = view(4, 0, 10, 0);
+        zero_height.ensure_visible();
+        assert_eq!(zero_height.scroll, 4);
+    }
+
+    #[test]
+    fn ctrl_f_and_ctrl_b_move_a_whole_screen() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let yaml: String = (0..30).map(|i| format!("k{i}: {i}\n")).collect();
+        let mut app = app_with("full_page", &yaml);
+        let key = |app: &mut App, code: KeyCode, modifiers: KeyModifiers| {
+            app.handle_key(KeyEvent::new(code, modifiers), 10).unwrap();
+        };
+        key(&mut app, KeyCode::Char('f'), KeyModifiers::CONTROL);
+        assert_eq!((app.selection, app.scroll), (10, 10));
+        key(&mut app, KeyCode::PageDown, KeyModifiers::NONE);
+        key(&mut app, KeyCode::PageDown, KeyModifiers::NONE);
+        assert_eq!((app.selection, app.scroll), (30, 21));
+        key(&mut app, KeyCode::Char('b'), KeyModifiers::CONTROL);
+        assert_eq!((app.selection, app.scroll), (20, 11));
+
+        // The file picker pages through its entries.
+        let mut app = App::new_for_picker(CliOverrides::default()).unwrap();
+        app.file_picker.as_mut().unwrap().entries = (0..25)
+            .map(|i| PickerEntry::Recent(format!("f{i}.yaml").into()))
+            .collect();
+        app.selection = 0;
+        key(&mut app, KeyCode::PageDown, KeyModifiers::NONE);
+        assert_eq!(app.selection, 10);
+        key(&mut app, KeyCode::Char('f'), KeyModifiers::CONTROL);
+        key(&mut app, KeyCode::Char('f'), KeyModifiers::CONTROL);
+        assert_eq!(app.selection, 24);
+        key(&mut app, KeyCode::PageUp, KeyModifiers::NONE);
+        assert_eq!(app.selection, 14);
+    }
+
+    #[test]
+    fn counts_repeat_motions() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let yaml: String = (0..30).map(|i| format!("k{i}: {i}\n")).collect();
+        let mut app = app_with("counts", &yaml);
+        let keys = |app: &mut App, keys: &str| {
+            for ch in keys.chars() {
+                let code = if ch == '\x1b' {
+                    KeyCode::Esc
+                } else {
+                    KeyCode::Char(ch)
+                };
+                app.handle_key(KeyEvent::new(code, KeyModifiers::NONE), 10)
+                    .unwrap();
+            }
+        };
+        keys(&mut app, "5j");
+        assert_eq!(app.selection, 5);
+        keys(&mut app, "12j");
+        assert_eq!(app.selection, 17);
+        keys(&mut app, "3k");
+        assert_eq!(app.selection, 14);
+        keys(&mut app, "2}");
+        assert_eq!(app.selection, 16);
+        // `0` after a count is a digit; Esc drops a pending count.
+        keys(&mut app, "10k");
+        assert_eq!(app.selection, 6);
+        keys(&mut app, "4\x1bj");
+        assert_eq!(app.selection, 7);
+        keys(&mut app, "99j");
+        assert_eq!(app.selection, 30);
+    }
+}
@@ -0,0 +1,103 @@
+// Saving without ever leaving a half-written file: the new contents go to a temporary file next
+// to the target, which is then renamed over it. A failure at any point leaves the original as it
+// was.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+/// Replace the file at `path` with `contents`. A symlink is followed so the link itself stays,
+/// and an existing file's permissions carry over to the new one. The error names the file and
+/// the cause, ready to show as is.
+pub fn write(path: &Path, contents: &[u8]) -> Result<()> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let temp = temp_path(&target)?;
+    let written = write_temp(&temp, &target, contents).and_then(|()| {
+        fs::rename(&temp, &target).with_context(|| format!("replacing {}", target.display()))
+    });
+    written.map_err(|err| {
+        let _ = fs::remove_file(&temp);
+        anyhow!(
+            "Could not save {}, the file is unchanged: {err:#}",
+            path.display()
+        )
+    })
+}
+
+/// `.<name>.yed-save` next to `target`, so the rename stays on one file system.
+fn temp_path(target: &Path) -> Result<PathBuf> {
+    let name = target
+        .file_name()
+        .ok_or_else(|| anyhow!("{} has no file name", target.display()))?;
+    Ok(target.with_file_name(format!(".{}.yed-save", name.to_string_lossy())))
+}
+
+fn write_temp(temp: &Path, target: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = File::create(temp).with_context(|| format!("creating {}", temp.display()))?;
+    file.write_all(contents)
+        .with_context(|| format!("writing {}", temp.display()))?;
+    file.sync_all()
+        .with_context(|| format!("flushing {}", temp.display()))?;
+    if let Ok(meta) = fs::metadata(target) {
+        fs::set_permissions(temp, meta.permissions())
+            .with_context(|| format!("copying the permissions of {}", target.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("yed_atomic_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn replaces_the_file_and_leaves_no_temp_behind() {
+        let dir = temp_dir("replace");
+        let file = dir.join("values.yaml");
+        fs::write(&file, "a: 1\n").unwrap();
+        write(&file, b"a: 2\n").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "a: 2\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        // A directory in the way: the rename fails and nothing else changes.
+        let blocked = dir.join("blocked");
+        fs::create_dir(&blocked).unwrap();
+        fs::write(blocked.join("inside"), "kept").unwrap();
+        assert!(write(&blocked, b"a: 3\n").is_err());
+        assert_eq!(fs::read_to_string(blocked.join("inside")).unwrap(), "kept");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_symlinks_and_permissions() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+        let dir = temp_dir("link");
+        let real = dir.join("real.yaml");
+        let link = dir.join("link.yaml");
+        fs::write(&real, "a: 1\n").unwrap();
+        fs::set_permissions(&real, fs::Permissions::from_mode(0o640)).unwrap();
+        symlink(&real, &link).unwrap();
+        write(&link, b"a: 2\n").unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&real).unwrap(), "a: 2\n");
+        assert_eq!(
+            fs::metadata(&real).unwrap().permissions().mode() & 0o777,
+            0o640
+        );
+        fs::remove_dir_all(&dir).ok();
+    }
+}
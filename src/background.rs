@@ -0,0 +1,26 @@
+// Work that runs off the UI thread. The event loop polls tasks once per frame; dropping a task
+// detaches its thread, so quitting never waits for one.
+
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+pub struct Task<T> {
+    receiver: Receiver<T>,
+}
+
+/// Run `work` on its own thread.
+pub fn spawn<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Task<T> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // The receiver is gone if the task was dropped; nobody wants the result then.
+        let _ = sender.send(work());
+    });
+    Task { receiver }
+}
+
+impl<T> Task<T> {
+    /// The result, once the work has finished. A task whose work panicked never finishes.
+    pub fn poll(&self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+}
@@ -0,0 +1,134 @@
+// Copies of a file's previous contents, taken just before a save overwrites it.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::settings::Settings;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackupOptions {
+    /// Appended to the file name, e.g. `.bak`.
+    pub suffix: String,
+    /// Where backups go; next to the file when unset.
+    pub dir: Option<PathBuf>,
+    /// Keep every backup as `name.bak.1`, `name.bak.2`, … instead of replacing one.
+    pub numbered: bool,
+    /// Only back up before the first save of a session, so the backup holds the file as opened.
+    pub once: bool,
+}
+
+impl BackupOptions {
+    /// The backup settings, or `None` when backups are off.
+    pub fn from_settings(settings: &Settings) -> Option<Self> {
+        settings.backup.value.then(|| Self {
+            suffix: settings.backup_suffix.value.clone(),
+            dir: settings.backup_dir.value.clone(),
+            numbered: settings.backup_numbered.value,
+            once: settings.backup_once.value,
+        })
+    }
+}
+
+/// Where the next backup of `path` goes.
+pub fn backup_path(path: &Path, options: &BackupOptions) -> Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} has no file name", path.display()))?;
+    let dir = match &options.dir {
+        Some(dir) => dir.clone(),
+        None => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+    };
+    let base = format!("{}{}", name.to_string_lossy(), options.suffix);
+    if !options.numbered {
+        return Ok(dir.join(base));
+    }
+    let prefix = format!("{base}.");
+    let highest = std::fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| {
+                    let name = entry.ok()?.file_name();
+                    name.to_str()?.strip_prefix(&prefix)?.parse::<u32>().ok()
+                })
+                .max()
+                .unwrap_or(0)
+        })
+        .unwrap_or(0);
+    Ok(dir.join(format!("{prefix}{}", highest + 1)))
+}
+
+/// Copy the file at `path` aside before it is overwritten. Returns the backup's path, or
+/// `None` when there is no file yet.
+pub fn backup(path: &Path, options: &BackupOptions) -> Result<Option<PathBuf>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    if let Some(dir) = &options.dir {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    let target = backup_path(path, options)?;
+    std::fs::copy(path, &target).with_context(|| format!("writing {}", target.display()))?;
+    Ok(Some(target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("yed_backup_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn single_backup_is_replaced_each_time() {
+        let dir = temp_dir("single");
+        let file = dir.join("values.yaml");
+        let options = BackupOptions {
+            suffix: ".bak".to_string(),
+            dir: None,
+            numbered: false,
+            once: false,
+        };
+        assert_eq!(backup(&file, &options).unwrap(), None);
+        std::fs::write(&file, "a: 1\n").unwrap();
+        assert_eq!(
+            backup(&file, &options).unwrap(),
+            Some(dir.join("values.yaml.bak"))
+        );
+        std::fs::write(&file, "a: 2\n").unwrap();
+        backup(&file, &options).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("values.yaml.bak")).unwrap(),
+            "a: 2\n"
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn numbered_backups_go_to_the_backup_dir() {
+        let dir = temp_dir("numbered");
+        let file = dir.join("values.yaml");
+        std::fs::write(&file, "a: 1\n").unwrap();
+        let options = BackupOptions {
+            suffix: "~".to_string(),
+            dir: Some(dir.join("backups")),
+            numbered: true,
+            once: false,
+        };
+        assert_eq!(
+            backup(&file, &options).unwrap(),
+            Some(dir.join("backups/values.yaml~.1"))
+        );
+        std::fs::write(dir.join("backups/values.yaml~.7"), "").unwrap();
+        assert_eq!(
+            backup(&file, &options).unwrap(),
+            Some(dir.join("backups/values.yaml~.8"))
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
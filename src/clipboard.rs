@@ -4,7 +4,17 @@ use std::process::{Command, Stdio};
 use anyhow::{anyhow, Result};
 use base64::{engine::general_purpose, Engine as _};
 
+/// Largest payload we attempt to copy; terminals commonly truncate larger OSC 52 sequences.
+pub const MAX_COPY_BYTES: usize = 1 << 20;
+
 pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    if text.len() > MAX_COPY_BYTES {
+        return Err(anyhow!(
+            "Too large to copy ({} KB, limit {} KB)",
+            text.len() / 1024,
+            MAX_COPY_BYTES / 1024
+        ));
+    }
     if osc52_copy(text).is_ok() {
         return Ok(());
     }
@@ -0,0 +1,43 @@
+// Wall-clock helpers for labels shown to the user (lock owners, journal entries).
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds since the Unix epoch.
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// `just now`, `45s ago`, `3m ago`, `2h ago`, `5d ago` for something `secs` seconds old.
+pub fn ago_label(secs: u64) -> String {
+    match secs {
+        0..=4 => "just now".to_string(),
+        5..=59 => format!("{secs}s ago"),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+/// Local (hour, minute, second) of a Unix timestamp, falling back to UTC.
+#[cfg(unix)]
+pub fn local_time(secs: u64) -> (u32, u32, u32) {
+    let time = secs as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return utc_time(secs);
+    }
+    (tm.tm_hour as u32, tm.tm_min as u32, tm.tm_sec as u32)
+}
+
+#[cfg(not(unix))]
+pub fn local_time(secs: u64) -> (u32, u32, u32) {
+    utc_time(secs)
+}
+
+fn utc_time(secs: u64) -> (u32, u32, u32) {
+    let day_secs = (secs % 86_400) as u32;
+    (day_secs / 3600, day_secs % 3600 / 60, day_secs % 60)
+}
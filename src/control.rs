@@ -0,0 +1,438 @@
+// Control socket (`--listen PATH`): scripts and editors send one JSON object per line and get
+// one JSON object back per line. Connections are served on their own threads; commands are
+// handed to the event loop, which carries them out between frames.
+//
+// Requests:  {"cmd":"hello"}                         -> {"ok":true,"version":1}
+//            {"cmd":"goto","path":"spec.replicas"}   -> {"ok":true}
+//            {"cmd":"get","path":"spec.replicas"}    -> {"ok":true,"value":3}
+//            {"cmd":"reload"}                        -> {"ok":true}
+//            {"cmd":"action","name":"Save"}          -> {"ok":true}
+// Any request may carry "version"; one this build does not speak is refused. Failures answer
+// {"ok":false,"error":"..."}.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::json_patch::json_string;
+
+pub const PROTOCOL_VERSION: u64 = 1;
+
+/// Socket currently bound by this process, removed by the panic hook and signal handlers.
+static ACTIVE_SOCKET: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Select the node at a dot path, expanding its ancestors.
+    Goto(String),
+    /// The value at a dot path, as JSON.
+    Get(String),
+    /// Re-read the file from disk (merging if there are unsaved edits).
+    Reload,
+    /// Run an editor action by its name, e.g. `Save` or `Undo`.
+    Action(String),
+}
+
+/// A command waiting for the event loop, with the connection to answer on.
+pub struct Request {
+    pub command: Command,
+    reply: Sender<String>,
+}
+
+impl Request {
+    /// Answer with a JSON `value`, with a bare success, or with the error.
+    pub fn respond(self, result: Result<Option<String>>) {
+        let line = match result {
+            Ok(Some(value)) => format!("{{\"ok\":true,\"value\":{value}}}"),
+            Ok(None) => "{\"ok\":true}".to_string(),
+            Err(err) => error_line(&err.to_string()),
+        };
+        // The client may have hung up; there is nobody to tell then.
+        let _ = self.reply.send(line);
+    }
+}
+
+fn error_line(message: &str) -> String {
+    format!("{{\"ok\":false,\"error\":{}}}", json_string(message))
+}
+
+/// Listening control socket; the socket file is removed when this is dropped.
+pub struct ControlServer {
+    path: PathBuf,
+    requests: Receiver<Request>,
+}
+
+impl ControlServer {
+    /// Listen on `path`, readable and writable by the current user only. A socket file left by
+    /// an instance that is no longer running is replaced; a live one is an error.
+    pub fn bind(path: &Path) -> Result<Self> {
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                bail!("{} is in use by another instance", path.display());
+            }
+            fs::remove_file(path).with_context(|| format!("removing stale {}", path.display()))?;
+        }
+        let listener =
+            UnixListener::bind(path).with_context(|| format!("listening on {}", path.display()))?;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        set_active(Some(path.to_path_buf()));
+        let (sender, requests) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let sender = sender.clone();
+                thread::spawn(move || serve(stream, sender));
+            }
+        });
+        Ok(Self {
+            path: path.to_path_buf(),
+            requests,
+        })
+    }
+
+    /// Commands received since the last call, oldest first.
+    pub fn pending(&self) -> Vec<Request> {
+        self.requests.try_iter().collect()
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        set_active(None);
+    }
+}
+
+fn serve(stream: UnixStream, requests: Sender<Request>) {
+    let Ok(reader) = stream.try_clone() else {
+        return;
+    };
+    let mut writer = stream;
+    if !same_user(&writer) {
+        let _ = writeln!(writer, "{}", error_line("not authorized"));
+        return;
+    }
+    for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match parse_request(&line) {
+            Ok(None) => format!("{{\"ok\":true,\"version\":{PROTOCOL_VERSION}}}"),
+            Ok(Some(command)) => {
+                let (reply, answer) = mpsc::channel();
+                if requests.send(Request { command, reply }).is_err() {
+                    return;
+                }
+                match answer.recv() {
+                    Ok(response) => response,
+                    Err(_) => return,
+                }
+            }
+            Err(err) => error_line(&err.to_string()),
+        };
+        if writeln!(writer, "{response}").is_err() {
+            return;
+        }
+    }
+}
+
+/// Whether the peer runs as the same user. The socket's permissions already keep others out;
+/// this also covers sockets placed in shared directories.
+#[cfg(target_os = "linux")]
+fn same_user(stream: &UnixStream) -> bool {
+    use std::os::unix::io::AsRawFd;
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let found = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    } == 0;
+    found && cred.uid == unsafe { libc::geteuid() }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn same_user(_stream: &UnixStream) -> bool {
+    true
+}
+
+fn set_active(path: Option<PathBuf>) {
+    if let Ok(mut active) = ACTIVE_SOCKET.lock() {
+        *active = path;
+    }
+}
+
+/// Remove the bound socket file; called when the process panics.
+pub fn remove_active() {
+    // Never block here: the socket may be held by the code that panicked.
+    if let Ok(mut active) = ACTIVE_SOCKET.try_lock() {
+        if let Some(path) = active.take() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Parse one request line. `Ok(None)` is a `hello`, answered without the event loop.
+pub fn parse_request(line: &str) -> Result<Option<Command>> {
+    let fields = parse_object(line)?;
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    };
+    if let Some(version) = field("version") {
+        match version {
+            Scalar::Number(n) if *n == PROTOCOL_VERSION => {}
+            _ => bail!("unsupported protocol version (this is {PROTOCOL_VERSION})"),
+        }
+    }
+    let text = |name: &str| match field(name) {
+        Some(Scalar::String(value)) => Ok(value.clone()),
+        _ => Err(anyhow!("missing \"{name}\"")),
+    };
+    let command = match text("cmd")?.as_str() {
+        "hello" => return Ok(None),
+        "goto" => Command::Goto(text("path")?),
+        "get" => Command::Get(text("path")?),
+        "reload" => Command::Reload,
+        "action" => Command::Action(text("name")?),
+        other => bail!("unknown command '{other}'"),
+    };
+    Ok(Some(command))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Scalar {
+    String(String),
+    Number(u64),
+    Bool(bool),
+    Null,
+}
+
+/// A flat JSON object: keys with string, non-negative integer, boolean or null values.
+fn parse_object(text: &str) -> Result<Vec<(String, Scalar)>> {
+    let mut chars = text.trim().chars().peekable();
+    let mut fields = Vec::new();
+    let skip_ws = |chars: &mut std::iter::Peekable<std::str::Chars<'_>>| {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+    };
+    if chars.next() != Some('{') {
+        bail!("expected a JSON object");
+    }
+    skip_ws(&mut chars);
+    if chars.peek() == Some(&'}') {
+        chars.next();
+    } else {
+        loop {
+            skip_ws(&mut chars);
+            let key = parse_string(&mut chars)?;
+            skip_ws(&mut chars);
+            if chars.next() != Some(':') {
+                bail!("expected ':' after \"{key}\"");
+            }
+            skip_ws(&mut chars);
+            let value = match chars.peek() {
+                Some('"') => Scalar::String(parse_string(&mut chars)?),
+                Some(c) if c.is_ascii_digit() => {
+                    let mut digits = String::new();
+                    while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                        digits.push(*c);
+                        chars.next();
+                    }
+                    Scalar::Number(digits.parse().map_err(|_| anyhow!("number out of range"))?)
+                }
+                _ => {
+                    let word: String =
+                        std::iter::from_fn(|| chars.next_if(|c| c.is_ascii_alphabetic())).collect();
+                    match word.as_str() {
+                        "true" => Scalar::Bool(true),
+                        "false" => Scalar::Bool(false),
+                        "null" => Scalar::Null,
+                        _ => bail!("unsupported value for \"{key}\""),
+                    }
+                }
+            };
+            fields.push((key, value));
+            skip_ws(&mut chars);
+            match chars.next() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => bail!("expected ',' or '}}'"),
+            }
+        }
+    }
+    skip_ws(&mut chars);
+    if chars.next().is_some() {
+        bail!("trailing characters after the object");
+    }
+    Ok(fields)
+}
+
+fn parse_string(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Result<String> {
+    if chars.next() != Some('"') {
+        bail!("expected a string");
+    }
+    let mut out = String::new();
+    loop {
+        match chars.next().ok_or_else(|| anyhow!("unterminated string"))? {
+            '"' => return Ok(out),
+            '\\' => match chars.next().ok_or_else(|| anyhow!("unterminated string"))? {
+                '"' => out.push('"'),
+                '\\' => out.push('\\'),
+                '/' => out.push('/'),
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                'b' => out.push('\u{8}'),
+                'f' => out.push('\u{c}'),
+                'u' => {
+                    let hex: String = (0..4).filter_map(|_| chars.next()).collect();
+                    let code =
+                        u32::from_str_radix(&hex, 16).map_err(|_| anyhow!("bad \\u escape"))?;
+                    out.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                }
+                other => bail!("bad escape '\\{other}'"),
+            },
+            ch => out.push(ch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::App;
+    use crate::settings::CliOverrides;
+    use pretty_assertions::assert_eq;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn requests_parse_and_versions_are_checked() {
+        assert_eq!(parse_request(r#"{"cmd":"hello"}"#).unwrap(), None);
+        assert_eq!(
+            parse_request(r#" { "cmd" : "goto", "path" : "spec.\"x\"A", "version": 1 } "#).unwrap(),
+            Some(Command::Goto("spec.\"x\"A".to_string()))
+        );
+        assert_eq!(
+            parse_request(r#"{"cmd":"reload"}"#).unwrap(),
+            Some(Command::Reload)
+        );
+        assert!(parse_request(r#"{"cmd":"goto"}"#).is_err());
+        assert!(parse_request(r#"{"cmd":"rm","path":"a"}"#).is_err());
+        assert!(parse_request(r#"{"cmd":"hello","version":2}"#).is_err());
+        assert!(parse_request(r#"{"cmd":"get","path":{"a":1}}"#).is_err());
+        assert!(parse_request("cmd=hello").is_err());
+    }
+
+    /// Send `line` and answer requests on this thread, as the event loop would, until the
+    /// client has its response.
+    fn exchange(server: &ControlServer, app: &mut App, path: &Path, line: &str) -> String {
+        let line = line.to_string();
+        let path = path.to_path_buf();
+        let client = thread::spawn(move || {
+            let mut stream = UnixStream::connect(path).unwrap();
+            writeln!(stream, "{line}").unwrap();
+            let mut response = String::new();
+            BufReader::new(stream).read_line(&mut response).unwrap();
+            response.trim_end().to_string()
+        });
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !client.is_finished() && Instant::now() < deadline {
+            for request in server.pending() {
+                let result = app.handle_control(&request.command, 10);
+                request.respond(result);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        client.join().unwrap()
+    }
+
+    #[test]
+    fn socket_serves_goto_and_get_against_a_loaded_file() {
+        let dir = std::env::temp_dir().join(format!("yed_control_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("deploy.yaml");
+        std::fs::write(
+            &file,
+            "spec:\n  replicas: 3\n  containers:\n    - image: nginx\n",
+        )
+        .unwrap();
+        let mut app = App::new(&file, CliOverrides::default()).unwrap();
+        let socket = dir.join("yed.sock");
+        let server = ControlServer::bind(&socket).unwrap();
+
+        assert_eq!(
+            exchange(&server, &mut app, &socket, r#"{"cmd":"hello"}"#),
+            r#"{"ok":true,"version":1}"#
+        );
+        assert_eq!(
+            exchange(
+                &server,
+                &mut app,
+                &socket,
+                r#"{"cmd":"get","path":"spec.replicas"}"#
+            ),
+            r#"{"ok":true,"value":3}"#
+        );
+        assert_eq!(
+            exchange(
+                &server,
+                &mut app,
+                &socket,
+                r#"{"cmd":"goto","path":"spec.containers.0.image"}"#
+            ),
+            r#"{"ok":true}"#
+        );
+        assert_eq!(
+            app.current_row().unwrap().path.dot_path(),
+            "spec.containers.0.image"
+        );
+        assert_eq!(
+            exchange(
+                &server,
+                &mut app,
+                &socket,
+                r#"{"cmd":"get","path":"spec.missing"}"#
+            ),
+            r#"{"ok":false,"error":"no node at spec.missing"}"#
+        );
+        assert_eq!(
+            exchange(
+                &server,
+                &mut app,
+                &socket,
+                r#"{"cmd":"action","name":"Quit"}"#
+            ),
+            r#"{"ok":false,"error":"action 'Quit' is not allowed over the control socket"}"#
+        );
+        assert!(ControlServer::bind(&socket).is_err());
+        drop(server);
+        assert!(!socket.exists());
+        drop(app);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
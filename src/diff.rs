@@ -0,0 +1,163 @@
+// Path-keyed structural diff between the edited document and a reference (baseline) document.
+
+use std::collections::{HashMap, HashSet};
+
+use yaml_rust2::Yaml;
+
+use crate::yaml_model::{node_preview, NodePath};
+
+/// How a node of the primary document compares to the baseline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffMark {
+    /// Present in both with a different value; holds the baseline value preview.
+    Changed(String),
+    /// Not present in the baseline.
+    Added,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiffAnnotations {
+    pub marks: HashMap<NodePath, DiffMark>,
+    /// For each container, children that exist only in the baseline: (key or `[index]`, preview).
+    pub missing: HashMap<NodePath, Vec<(String, String)>>,
+}
+
+impl DiffAnnotations {
+    pub fn mark(&self, path: &NodePath) -> Option<&DiffMark> {
+        self.marks.get(path)
+    }
+
+    pub fn missing_under(&self, path: &NodePath) -> &[(String, String)] {
+        self.missing.get(path).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// Compare `primary` against `baseline` and classify every differing path.
+pub fn annotate(primary: &Yaml, baseline: &Yaml) -> DiffAnnotations {
+    let mut out = DiffAnnotations::default();
+    diff_node(&NodePath(Vec::new()), primary, baseline, &mut out);
+    out
+}
+
+fn diff_node(path: &NodePath, primary: &Yaml, baseline: &Yaml, out: &mut DiffAnnotations) {
+    match (primary, baseline) {
+        (Yaml::Hash(ours), Yaml::Hash(theirs)) => {
+            let mut seen = HashSet::new();
+            for (k, v) in ours.iter() {
+                let Some(key) = k.as_str() else {
+                    continue;
+                };
+                seen.insert(key.to_string());
+                let child = path.child_key(key);
+                match theirs.get(k) {
+                    Some(base) => diff_node(&child, v, base, out),
+                    None => {
+                        out.marks.insert(child, DiffMark::Added);
+                    }
+                }
+            }
+            let missing: Vec<(String, String)> = theirs
+                .iter()
+                .filter_map(|(k, v)| {
+                    let key = k.as_str()?;
+                    (!seen.contains(key)).then(|| (key.to_string(), node_preview(v)))
+                })
+                .collect();
+            if !missing.is_empty() {
+                out.missing.insert(path.clone(), missing);
+            }
+        }
+        (Yaml::Array(ours), Yaml::Array(theirs)) => {
+            for (idx, item) in ours.iter().enumerate() {
+                let child = path.child_index(idx);
+                match theirs.get(idx) {
+                    Some(base) => diff_node(&child, item, base, out),
+                    None => {
+                        out.marks.insert(child, DiffMark::Added);
+                    }
+                }
+            }
+            if theirs.len() > ours.len() {
+                let missing = theirs[ours.len()..]
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, v)| (format!("[{}]", ours.len() + offset), node_preview(v)))
+                    .collect();
+                out.missing.insert(path.clone(), missing);
+            }
+        }
+        _ => {
+            if primary != baseline && !path.0.is_empty() {
+                out.marks
+                    .insert(path.clone(), DiffMark::Changed(node_preview(baseline)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml_model::PathSegment;
+    use pretty_assertions::assert_eq;
+    use yaml_rust2::YamlLoader;
+
+    fn load(src: &str) -> Yaml {
+        YamlLoader::load_from_str(src).unwrap().remove(0)
+    }
+
+    fn path(dot: &str) -> NodePath {
+        NodePath(
+            dot.split('.')
+                .map(|seg| match seg.parse::<usize>() {
+                    Ok(idx) => PathSegment::Index(idx),
+                    Err(_) => PathSegment::Key(seg.to_string()),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn classifies_changed_added_and_missing() {
+        let primary = load("server:\n  port: 8080\n  debug: true\nname: api\n");
+        let baseline = load("server:\n  port: 80\n  tls: false\nname: api\nextra: 1\n");
+        let diff = annotate(&primary, &baseline);
+        assert_eq!(
+            diff.mark(&path("server.port")),
+            Some(&DiffMark::Changed("80".into()))
+        );
+        assert_eq!(diff.mark(&path("server.debug")), Some(&DiffMark::Added));
+        assert_eq!(diff.mark(&path("name")), None);
+        assert_eq!(diff.mark(&path("server")), None);
+        assert_eq!(
+            diff.missing_under(&path("server")),
+            &[("tls".to_string(), "false".to_string())]
+        );
+        assert_eq!(
+            diff.missing_under(&NodePath(Vec::new())),
+            &[("extra".to_string(), "1".to_string())]
+        );
+    }
+
+    #[test]
+    fn sequences_compare_by_index_and_types_by_shape() {
+        let primary = load("items:\n  - a\n  - b\n  - c\nmode: {x: 1}\n");
+        let baseline = load("items:\n  - a\n  - z\nmode: plain\n");
+        let diff = annotate(&primary, &baseline);
+        assert_eq!(
+            diff.mark(&path("items.1")),
+            Some(&DiffMark::Changed("\"z\"".into()))
+        );
+        assert_eq!(diff.mark(&path("items.2")), Some(&DiffMark::Added));
+        assert_eq!(
+            diff.mark(&path("mode")),
+            Some(&DiffMark::Changed("\"plain\"".into()))
+        );
+
+        let shorter = annotate(&baseline, &primary);
+        assert_eq!(
+            shorter.missing_under(&path("items")),
+            &[("[2]".to_string(), "\"c\"".to_string())]
+        );
+    }
+}
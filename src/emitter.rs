@@ -0,0 +1,596 @@
+// Block-style YAML writer. Produces the same layout as `yaml_rust2::YamlEmitter`
+// but can also write back anchors, aliases and flow collections recorded in a `SourceMap`.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+use anyhow::Result;
+use yaml_rust2::yaml::Hash;
+use yaml_rust2::Yaml;
+
+use crate::source_map::{Quoting, SourceMap, MERGE_KEY};
+use crate::yaml_model::{self, NodePath};
+use yaml_rust2::scanner::TScalarStyle;
+
+/// Layout choices for saved documents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmitOptions {
+    /// Spaces per nesting level.
+    pub indent: usize,
+    /// Start the output with `---`.
+    pub document_start: bool,
+    /// Write block collections of at most this many one-line scalars in flow style; 0 never.
+    pub flow_max_items: usize,
+}
+
+impl Default for EmitOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            document_start: true,
+            flow_max_items: 0,
+        }
+    }
+}
+
+/// Serialize `doc`, re-emitting anchors, aliases and flow style from `source`.
+pub fn emit(doc: &Yaml, source: &SourceMap, options: &EmitOptions) -> Result<String> {
+    let mut emitter = Emitter {
+        out: String::new(),
+        level: -1,
+        indent: options.indent.max(1),
+        source,
+        emitted_anchors: HashSet::new(),
+        flow_depth: 0,
+        flow_max_items: options.flow_max_items,
+    };
+    if options.document_start {
+        writeln!(emitter.out, "---")?;
+    }
+    emitter.emit_node(&NodePath(Vec::new()), doc)?;
+    Ok(emitter.out)
+}
+
+/// Text for a lone scalar written at nesting `level`; multi-line strings become a literal block
+/// whose lines sit one level deeper. `in_flow` keeps it on one line.
+pub fn emit_scalar(
+    value: &Yaml,
+    options: &EmitOptions,
+    level: usize,
+    in_flow: bool,
+) -> Result<String> {
+    let source = SourceMap::default();
+    let mut emitter = Emitter {
+        out: String::new(),
+        level: level as isize,
+        indent: options.indent.max(1),
+        source: &source,
+        emitted_anchors: HashSet::new(),
+        flow_depth: usize::from(in_flow),
+        flow_max_items: options.flow_max_items,
+    };
+    emitter.emit_node(&NodePath(Vec::new()), value)?;
+    Ok(emitter.out)
+}
+
+struct Emitter<'a> {
+    out: String,
+    level: isize,
+    indent: usize,
+    source: &'a SourceMap,
+    /// Anchors already written; an alias is only emitted once its anchor precedes it.
+    emitted_anchors: HashSet<String>,
+    /// Number of enclosing flow collections; everything inside one is flow too.
+    flow_depth: usize,
+    /// See [`EmitOptions::flow_max_items`].
+    flow_max_items: usize,
+}
+
+impl Emitter<'_> {
+    fn write_indent(&mut self) {
+        if self.level <= 0 {
+            return;
+        }
+        for _ in 0..self.level as usize * self.indent {
+            self.out.push(' ');
+        }
+    }
+
+    fn is_flow(&self, path: &NodePath, node: &Yaml) -> bool {
+        self.flow_depth > 0 || self.source.is_flow(path) || self.is_short(path, node)
+    }
+
+    /// A collection below the root holding no more than `flow_max_items` scalars, none of them
+    /// spanning lines.
+    fn is_short(&self, path: &NodePath, node: &Yaml) -> bool {
+        let one_line = |item: &Yaml| match item {
+            Yaml::Array(_) | Yaml::Hash(_) => false,
+            Yaml::String(value) => !value.contains('\n'),
+            _ => true,
+        };
+        !path.0.is_empty()
+            && match node {
+                Yaml::Array(seq) => seq.len() <= self.flow_max_items && seq.iter().all(one_line),
+                Yaml::Hash(map) => {
+                    map.len() <= self.flow_max_items
+                        && map
+                            .iter()
+                            .all(|(key, value)| one_line(key) && one_line(value))
+                        && !map.contains_key(&Yaml::String(MERGE_KEY.to_string()))
+                }
+                _ => false,
+            }
+    }
+
+    fn emit_node(&mut self, path: &NodePath, node: &Yaml) -> Result<()> {
+        match node {
+            Yaml::Array(seq) if !seq.is_empty() && self.is_flow(path, node) => {
+                self.emit_flow_array(path, seq)?
+            }
+            Yaml::Hash(map) if !map.is_empty() && self.is_flow(path, node) => {
+                self.emit_flow_hash(path, map)?
+            }
+            Yaml::Array(seq) => self.emit_array(path, seq)?,
+            Yaml::Hash(map) => self.emit_hash(path, map)?,
+            Yaml::String(value) => {
+                if self.emit_as_written(path, value) {
+                    return Ok(());
+                }
+                if self.level >= 0 && self.flow_depth == 0 && is_literal_block_candidate(value) {
+                    self.emit_literal_block(value);
+                } else if need_quotes(value) {
+                    escape_str(&mut self.out, value);
+                } else {
+                    self.out.push_str(value);
+                }
+            }
+            Yaml::Boolean(value) => self.out.push_str(if *value { "true" } else { "false" }),
+            Yaml::Integer(value) => write!(self.out, "{value}")?,
+            Yaml::Real(value) => self.out.push_str(value),
+            Yaml::Null | Yaml::BadValue => self.out.push('~'),
+            Yaml::Alias(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Write a string value the way it was loaded (plain, `'single'` or `"double"` quoted) if it
+    /// has not changed since and that style can still hold it here. Returns whether it did.
+    fn emit_as_written(&mut self, path: &NodePath, value: &str) -> bool {
+        self.emit_quoted_as(self.source.quoting.get(path), value)
+    }
+
+    fn emit_quoted_as(&mut self, quoting: Option<&Quoting>, value: &str) -> bool {
+        let Some(quoting) = quoting.filter(|quoting| quoting.value == value) else {
+            return false;
+        };
+        match quoting.style {
+            TScalarStyle::DoubleQuoted => escape_str(&mut self.out, value),
+            TScalarStyle::SingleQuoted if !value.contains(|c: char| c.is_control()) => {
+                self.out.push('\'');
+                self.out.push_str(&value.replace('\'', "''"));
+                self.out.push('\'');
+            }
+            // Flow indicators end a plain scalar inside `[]` or `{}`.
+            TScalarStyle::Plain
+                if !value.contains('\n')
+                    && (self.flow_depth == 0 || !value.contains([',', '[', ']', '{', '}'])) =>
+            {
+                self.out.push_str(value);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// A mapping key, quoted as it was loaded or else when it needs to be. Keys are never
+    /// literal blocks.
+    fn emit_key(&mut self, path: &NodePath, key: &Yaml) -> Result<()> {
+        match key {
+            Yaml::String(key) if self.emit_quoted_as(self.source.key_quoting.get(path), key) => {}
+            Yaml::String(key) if need_quotes(key) => escape_str(&mut self.out, key),
+            Yaml::String(key) => self.out.push_str(key),
+            _ => self.emit_node(path, key)?,
+        }
+        Ok(())
+    }
+
+    /// Write a multi-line string as a `|` block scalar, choosing the chomping indicator that
+    /// reproduces its trailing newlines exactly.
+    fn emit_literal_block(&mut self, value: &str) {
+        let content = value.trim_end_matches('\n');
+        let trailing = value.len() - content.len();
+        self.out.push('|');
+        let first_line = content
+            .split('\n')
+            .find(|line| !line.is_empty())
+            .unwrap_or("");
+        if first_line.starts_with(' ') {
+            // Leading spaces would otherwise be taken as the block's indentation.
+            let _ = write!(self.out, "{}", self.indent);
+        }
+        match trailing {
+            0 => self.out.push('-'),
+            1 => {}
+            _ => self.out.push('+'),
+        }
+        self.level += 1;
+        for line in content.split('\n') {
+            self.out.push('\n');
+            if !line.is_empty() {
+                self.write_indent();
+                self.out.push_str(line);
+            }
+        }
+        for _ in 1..trailing {
+            self.out.push('\n');
+        }
+        self.level -= 1;
+    }
+
+    fn emit_array(&mut self, path: &NodePath, seq: &[Yaml]) -> Result<()> {
+        if seq.is_empty() {
+            self.out.push_str("[]");
+            return Ok(());
+        }
+        self.level += 1;
+        for (idx, item) in seq.iter().enumerate() {
+            if idx > 0 {
+                self.out.push('\n');
+                self.write_indent();
+            }
+            self.out.push('-');
+            self.emit_val(true, &path.child_index(idx), item)?;
+        }
+        self.level -= 1;
+        Ok(())
+    }
+
+    fn emit_hash(&mut self, path: &NodePath, map: &Hash) -> Result<()> {
+        if map.is_empty() {
+            self.out.push_str("{}");
+            return Ok(());
+        }
+        self.level += 1;
+        for (idx, (key, value)) in map.iter().enumerate() {
+            if idx > 0 {
+                self.out.push('\n');
+                self.write_indent();
+            }
+            let child = path.child_key(&key_path_segment(key));
+            if matches!(key, Yaml::Hash(_) | Yaml::Array(_)) {
+                self.out.push('?');
+                self.emit_val(true, &child, key)?;
+                self.out.push('\n');
+                self.write_indent();
+                self.out.push(':');
+                self.emit_val(true, &child, value)?;
+            } else {
+                if matches!(key, Yaml::String(k) if k == MERGE_KEY) {
+                    // A quoted "<<" is a literal key; the merge key must stay plain.
+                    self.out.push_str(MERGE_KEY);
+                } else {
+                    self.emit_key(&child, key)?;
+                }
+                self.out.push(':');
+                self.emit_val(false, &child, value)?;
+            }
+        }
+        self.level -= 1;
+        Ok(())
+    }
+
+    /// `[a, b]`, one line however long.
+    fn emit_flow_array(&mut self, path: &NodePath, seq: &[Yaml]) -> Result<()> {
+        self.flow_depth += 1;
+        self.out.push('[');
+        for (idx, item) in seq.iter().enumerate() {
+            if idx > 0 {
+                self.out.push_str(", ");
+            }
+            self.emit_flow_item(&path.child_index(idx), item)?;
+        }
+        self.out.push(']');
+        self.flow_depth -= 1;
+        Ok(())
+    }
+
+    /// `{k: v, other: w}`, one line however long.
+    fn emit_flow_hash(&mut self, path: &NodePath, map: &Hash) -> Result<()> {
+        self.flow_depth += 1;
+        self.out.push('{');
+        for (idx, (key, value)) in map.iter().enumerate() {
+            if idx > 0 {
+                self.out.push_str(", ");
+            }
+            let child = path.child_key(&key_path_segment(key));
+            if matches!(key, Yaml::String(k) if k == MERGE_KEY) {
+                self.out.push_str(MERGE_KEY);
+            } else {
+                if matches!(key, Yaml::Hash(_) | Yaml::Array(_)) {
+                    self.out.push_str("? ");
+                }
+                self.emit_key(&child, key)?;
+            }
+            self.out.push_str(": ");
+            self.emit_flow_item(&child, value)?;
+        }
+        self.out.push('}');
+        self.flow_depth -= 1;
+        Ok(())
+    }
+
+    /// Emit a value inside a flow collection, with its anchor or as an alias.
+    fn emit_flow_item(&mut self, path: &NodePath, val: &Yaml) -> Result<()> {
+        if let Some(name) = self.source.alias_at(path) {
+            if self.emitted_anchors.contains(name) {
+                write!(self.out, "*{name}")?;
+                return Ok(());
+            }
+        }
+        if let Some(name) = self.source.anchor_at(path) {
+            write!(self.out, "&{name} ")?;
+            self.emitted_anchors.insert(name.to_string());
+        }
+        self.emit_node(path, val)
+    }
+
+    /// Emit a value following `:` or `-`. `inline` means the compact form (`- a: 1`) is allowed.
+    fn emit_val(&mut self, inline: bool, path: &NodePath, val: &Yaml) -> Result<()> {
+        if let Some(name) = self.source.alias_at(path) {
+            if self.emitted_anchors.contains(name) {
+                write!(self.out, " *{name}")?;
+                return Ok(());
+            }
+        }
+        let anchor = self.source.anchor_at(path).map(str::to_string);
+        if let Some(name) = &anchor {
+            write!(self.out, " &{name}")?;
+            self.emitted_anchors.insert(name.clone());
+        }
+        let is_empty = match val {
+            Yaml::Array(seq) => seq.is_empty(),
+            Yaml::Hash(map) => map.is_empty(),
+            _ => {
+                self.out.push(' ');
+                return self.emit_node(path, val);
+            }
+        };
+        // Properties cannot precede compact inline content, so anchored blocks start on a new line.
+        if is_empty || self.is_flow(path, val) {
+            self.out.push(' ');
+        } else if inline && anchor.is_none() {
+            // Pad past the `-` or `?` so compact content lines up with the next indent level.
+            for _ in 0..self.indent.saturating_sub(1).max(1) {
+                self.out.push(' ');
+            }
+        } else {
+            self.out.push('\n');
+            self.level += 1;
+            self.write_indent();
+            self.level -= 1;
+        }
+        self.emit_node(path, val)
+    }
+}
+
+/// Path segment used for a mapping key, matching how the tree names it.
+fn key_path_segment(key: &Yaml) -> String {
+    yaml_model::yaml_key_to_string(key).unwrap_or_else(|| "<non-string>".to_string())
+}
+
+/// Multi-line text that a literal block can hold verbatim (printable characters, tabs and
+/// newlines only, with some visible content).
+fn is_literal_block_candidate(value: &str) -> bool {
+    value.contains('\n')
+        && !value.trim().is_empty()
+        && value.chars().all(|c| {
+            matches!(c, '\t' | '\n' | '\x20'..='\x7e' | '\u{85}' | '\u{a0}'..='\u{d7ff}' | '\u{e000}'..='\u{fffd}' | '\u{10000}'..)
+        })
+}
+
+fn escape_str(out: &mut String, value: &str) {
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\x08' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\x0c' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            '\x00'..='\x1f' | '\x7f' => {
+                let _ = write!(out, "\\u{:04x}", ch as u32);
+            }
+            _ => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+/// Same quoting rules as `yaml_rust2`'s emitter so unchanged documents round-trip identically.
+fn need_quotes(value: &str) -> bool {
+    value.is_empty()
+        || value.starts_with(' ')
+        || value.ends_with(' ')
+        || value.starts_with(|c: char| {
+            matches!(
+                c,
+                '&' | '*' | '?' | '|' | '-' | '<' | '>' | '=' | '!' | '%' | '@'
+            )
+        })
+        || value.contains(|c: char| {
+            matches!(
+                c,
+                ':' | '{'
+                    | '}'
+                    | '['
+                    | ']'
+                    | ','
+                    | '#'
+                    | '`'
+                    | '"'
+                    | '\''
+                    | '\\'
+                    | '\0'..='\x06'
+                    | '\t'
+                    | '\n'
+                    | '\r'
+                    | '\x0e'..='\x1a'
+                    | '\x1c'..='\x1f'
+            )
+        })
+        || [
+            "yes", "Yes", "YES", "no", "No", "NO", "True", "TRUE", "true", "False", "FALSE",
+            "false", "on", "On", "ON", "off", "Off", "OFF", "null", "Null", "NULL", "~",
+        ]
+        .contains(&value)
+        || value.starts_with('.')
+        || value.starts_with("0x")
+        || value.parse::<i64>().is_ok()
+        || value.parse::<f64>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use yaml_rust2::{YamlEmitter, YamlLoader};
+
+    fn roundtrip(src: &str) -> String {
+        let doc = YamlLoader::load_from_str(src).unwrap().remove(0);
+        emit(&doc, &SourceMap::scan(src), &EmitOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn matches_yaml_rust2_without_anchors() {
+        let src = "server:\n  host: localhost\n  port: 8080\n  empty: {}\nitems:\n  - tags:\n      - blue\n      - \"a: b\"\n  - name: gadget\n    price: 29.5\n  - []\n  - ~\nnested:\n  - - 1\n    - 2\n";
+        let doc = YamlLoader::load_from_str(src).unwrap().remove(0);
+        let mut expected = String::new();
+        YamlEmitter::new(&mut expected).dump(&doc).unwrap();
+        assert_eq!(
+            emit(&doc, &SourceMap::default(), &EmitOptions::default()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn multiline_strings_use_literal_blocks() {
+        let src = "description: |\n  first line\n\n  third: line\nstrip: |-\n  a\n  b\nkeep: |+\n  a\n\n\nlist:\n  - |2\n      indented\n    text\n  - plain\n";
+        let out = roundtrip(src);
+        assert_eq!(
+            out,
+            "---\ndescription: |\n  first line\n\n  third: line\nstrip: |-\n  a\n  b\nkeep: |+\n  a\n\n\nlist:\n  - |2\n      indented\n    text\n  - plain"
+        );
+        let doc = YamlLoader::load_from_str(src).unwrap().remove(0);
+        assert_eq!(YamlLoader::load_from_str(&out).unwrap().remove(0), doc);
+    }
+
+    #[test]
+    fn wider_indent_keeps_compact_items_aligned() {
+        let src = "items:\n  - name: a\n    tags:\n      - x\n  - - 1\n    - 2\nmap:\n  k: v\n";
+        let doc = YamlLoader::load_from_str(src).unwrap().remove(0);
+        let options = EmitOptions {
+            indent: 4,
+            document_start: false,
+            ..EmitOptions::default()
+        };
+        let out = emit(&doc, &SourceMap::default(), &options).unwrap();
+        assert_eq!(
+            out,
+            "items:\n    -   name: a\n        tags:\n            - x\n    -   - 1\n        - 2\nmap:\n    k: v"
+        );
+        assert_eq!(YamlLoader::load_from_str(&out).unwrap().remove(0), doc);
+    }
+
+    #[test]
+    fn reemits_merge_key_alias() {
+        let src = "defaults: &defaults\n  a: 1\nsvc:\n  <<: *defaults\n  c: 3\n";
+        assert_eq!(
+            roundtrip(src),
+            "---\ndefaults: &defaults\n  a: 1\nsvc:\n  <<: *defaults\n  c: 3"
+        );
+    }
+
+    #[test]
+    fn anchored_sequence_item_starts_on_new_line() {
+        let src = "items:\n  - &first\n    a: 1\n  - b: 2\n    <<: *first\n";
+        assert_eq!(
+            roundtrip(src),
+            "---\nitems:\n  - &first\n    a: 1\n  - b: 2\n    <<: *first"
+        );
+    }
+
+    #[test]
+    fn flow_collections_stay_flow() {
+        let src = "tags: [a, \"b, c\", {k: v}]\nempty: []\nmeta: {name: api, ports: [80, 443]}\nlist:\n  - [1, 2]\n  - &pair {x: 1}\n  - *pair\nnested:\n  deep: [\"multi\\nline\"]\n";
+        let out = roundtrip(src);
+        assert_eq!(
+            out,
+            "---\ntags: [a, \"b, c\", {k: v}]\nempty: []\nmeta: {name: api, ports: [80, 443]}\nlist:\n  - [1, 2]\n  - &pair {x: 1}\n  - *pair\nnested:\n  deep: [\"multi\\nline\"]"
+        );
+        let doc = YamlLoader::load_from_str(src).unwrap().remove(0);
+        assert_eq!(YamlLoader::load_from_str(&out).unwrap().remove(0), doc);
+    }
+
+    #[test]
+    fn toggled_style_is_saved() {
+        let src = "tags: [a, b]\nserver:\n  host: localhost\n  port: 80\n";
+        let doc = YamlLoader::load_from_str(src).unwrap().remove(0);
+        let mut source = SourceMap::scan(src);
+        let path = |key: &str| NodePath(Vec::new()).child_key(key);
+        assert!(!source.toggle_flow(&path("tags")));
+        assert!(source.toggle_flow(&path("server")));
+        let out = emit(&doc, &source, &EmitOptions::default()).unwrap();
+        assert_eq!(
+            out,
+            "---\ntags:\n  - a\n  - b\nserver: {host: localhost, port: 80}"
+        );
+        assert_eq!(YamlLoader::load_from_str(&out).unwrap().remove(0), doc);
+
+        let root = YamlLoader::load_from_str("[1, 2]").unwrap().remove(0);
+        assert_eq!(
+            emit(&root, &SourceMap::scan("[1, 2]"), &EmitOptions::default()).unwrap(),
+            "---\n[1, 2]"
+        );
+    }
+
+    #[test]
+    fn strings_keep_the_quotes_they_were_written_with() {
+        let src = "plain: http://example.com/a,b\nsingle: 'it''s'\ndouble: \"bob\"\nnumber: '123'\nmulti: \"two\\nlines\"\nflow: [a b, 'x', \"y\"]\n\"quoted key\": v\n";
+        assert_eq!(
+            roundtrip(src),
+            "---\nplain: http://example.com/a,b\nsingle: 'it''s'\ndouble: \"bob\"\nnumber: '123'\nmulti: \"two\\nlines\"\nflow: [a b, 'x', \"y\"]\n\"quoted key\": v"
+        );
+
+        // Edited values are quoted only when they need it; the items after a removed one keep theirs.
+        let src = "plain: bob\nsingle: 'x'\nlist: ['a', \"b\", c]\n";
+        let mut doc = YamlLoader::load_from_str(src).unwrap().remove(0);
+        let mut source = SourceMap::scan(src);
+        let hash = doc.as_mut_hash().unwrap();
+        *hash.get_mut(&Yaml::String("plain".into())).unwrap() = Yaml::String("yes".into());
+        *hash.get_mut(&Yaml::String("single".into())).unwrap() = Yaml::String("y".into());
+        if let Some(Yaml::Array(items)) = hash.get_mut(&Yaml::String("list".into())) {
+            items.remove(0);
+        }
+        source.remove_prefix(&NodePath(Vec::new()).child_key("list").child_index(0));
+        let out = emit(&doc, &source, &EmitOptions::default()).unwrap();
+        assert_eq!(out, "---\nplain: \"yes\"\nsingle: y\nlist: [\"b\", c]");
+    }
+
+    #[test]
+    fn short_collections_are_written_in_flow_style() {
+        let src = "ports:\n  - 1\n  - 2\n  - 3\nhosts:\n  - a\n  - b\n  - c\n  - d\nlimits:\n  cpu: 1\n  mem: 2Gi\nnested:\n  - [1]\ntext:\n  - \"two\\nlines\"\n";
+        let doc = YamlLoader::load_from_str(src).unwrap().remove(0);
+        let options = EmitOptions {
+            flow_max_items: 3,
+            ..EmitOptions::default()
+        };
+        let out = emit(&doc, &SourceMap::default(), &options).unwrap();
+        assert_eq!(
+            out,
+            "---\nports: [1, 2, 3]\nhosts:\n  - a\n  - b\n  - c\n  - d\nlimits: {cpu: 1, mem: 2Gi}\nnested:\n  - [1]\ntext:\n  - |-\n    two\n    lines"
+        );
+        assert_eq!(YamlLoader::load_from_str(&out).unwrap().remove(0), doc);
+    }
+}
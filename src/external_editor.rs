@@ -0,0 +1,53 @@
+// Editing one value in the user's `$VISUAL` or `$EDITOR`, through a temporary file.
+
+use std::fs;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::hooks::{self, FILE_PLACEHOLDER};
+
+/// `$VISUAL`, then `$EDITOR`, then the platform's stock editor.
+pub fn editor() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string())
+}
+
+/// Let `editor` (a shell command, arguments allowed) edit `text` and return the result. The
+/// terminal must already be handed over to it. An editor that exits non-zero (like vim's
+/// `:cq`) abandons the edit.
+pub fn edit(editor: &str, text: &str) -> Result<String> {
+    let path = std::env::temp_dir().join(format!("yed-value-{}.txt", std::process::id()));
+    fs::write(&path, text)?;
+    let command = hooks::command_line(&format!("{editor} {FILE_PLACEHOLDER}"), &path);
+    let status = hooks::shell(&command).status();
+    let edited = fs::read_to_string(&path);
+    fs::remove_file(&path).ok();
+    let status = status.map_err(|err| anyhow!("{editor}: {err}"))?;
+    if !status.success() {
+        match status.code() {
+            Some(code) => bail!("{editor} exited with status {code}; value unchanged"),
+            None => bail!("{editor} was interrupted; value unchanged"),
+        }
+    }
+    Ok(edited?)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn returns_what_the_editor_wrote() {
+        assert_eq!(
+            edit("printf 'one\\ntwo\\n' >", "old").unwrap(),
+            "one\ntwo\n"
+        );
+        assert_eq!(edit("true", "kept").unwrap(), "kept");
+        let err = edit("exit 3;", "old").unwrap_err().to_string();
+        assert_eq!(err, "exit 3; exited with status 3; value unchanged");
+    }
+}
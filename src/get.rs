@@ -0,0 +1,47 @@
+// `yed get FILE PATH`: print one value for shell scripts, without opening the TUI.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use yaml_rust2::Yaml;
+
+use crate::emitter::{self, EmitOptions};
+use crate::json_patch;
+use crate::settings;
+use crate::source_map::SourceMap;
+use crate::yaml_model::{get_node, resolve_merged_dot_path, YamlModel};
+
+/// The value at dot path `dot` in `file`, keys inherited through `<<` included: a scalar as its
+/// raw text (strings unquoted), a mapping or sequence as YAML, or anything as JSON with `json`.
+/// Ends with a newline.
+pub fn value(file: &Path, dot: &str, json: bool) -> Result<String> {
+    let canonical = fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+    let settings = settings::for_file(&canonical)?;
+    let model = YamlModel::load(file).map_err(|err| anyhow!("{}: {err}", file.display()))?;
+    let path =
+        resolve_merged_dot_path(model.root(), dot).ok_or_else(|| anyhow!("No node at {dot}"))?;
+    let node = get_node(model.root(), &path)?;
+    if json {
+        return Ok(format!("{}\n", json_patch::json_pretty(node, &path)?));
+    }
+    Ok(match node {
+        Yaml::String(value) | Yaml::Real(value) => format!("{value}\n"),
+        Yaml::Integer(value) => format!("{value}\n"),
+        Yaml::Boolean(value) => format!("{value}\n"),
+        Yaml::Null => "null\n".to_string(),
+        node => {
+            let options = EmitOptions {
+                indent: settings.indent_width.value,
+                document_start: false,
+                flow_max_items: settings.flow_max_items.value,
+            };
+            let text = emitter::emit(node, &SourceMap::default(), &options)?;
+            if text.ends_with('\n') {
+                text
+            } else {
+                format!("{text}\n")
+            }
+        }
+    })
+}
@@ -0,0 +1,138 @@
+// Committed search queries, recalled with Up/Down while typing a search and remembered
+// between runs.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::settings;
+
+/// Queries kept, in memory and in the state file.
+const MAX_QUERIES: usize = 50;
+
+/// `search_history` next to the user config file (`~/.config/yed/search_history`).
+pub fn state_path() -> Option<PathBuf> {
+    Some(settings::user_config_path()?.with_file_name("search_history"))
+}
+
+/// Past queries, newest first, and where Up/Down currently is among them.
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    entries: Vec<String>,
+    /// Entry shown in the prompt; `None` while editing fresh text.
+    position: Option<usize>,
+    /// Text typed before the first Up, given back when Down passes the newest entry.
+    draft: String,
+}
+
+impl History {
+    /// Queries remembered in `state`; a missing or unreadable file is an empty history.
+    pub fn load(state: &Path) -> Self {
+        let text = fs::read_to_string(state).unwrap_or_default();
+        Self {
+            entries: text
+                .lines()
+                .filter(|line| !line.is_empty())
+                .take(MAX_QUERIES)
+                .map(str::to_string)
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Start a new prompt at fresh text.
+    pub fn reset(&mut self) {
+        self.position = None;
+        self.draft.clear();
+    }
+
+    /// Up: the next older query, keeping `current` as the draft when leaving fresh text.
+    /// `None` at the oldest entry.
+    pub fn older(&mut self, current: &str) -> Option<&str> {
+        let next = self.position.map_or(0, |position| position + 1);
+        if next >= self.entries.len() {
+            return None;
+        }
+        if self.position.is_none() {
+            self.draft = current.to_string();
+        }
+        self.position = Some(next);
+        Some(&self.entries[next])
+    }
+
+    /// Down: the next newer query, or the draft after the newest. `None` when already on it.
+    pub fn newer(&mut self) -> Option<&str> {
+        match self.position? {
+            0 => {
+                self.position = None;
+                Some(&self.draft)
+            }
+            position => {
+                self.position = Some(position - 1);
+                Some(&self.entries[position - 1])
+            }
+        }
+    }
+
+    /// Put `query` first, dropping an earlier copy and the oldest beyond the cap.
+    pub fn remember(&mut self, query: &str) {
+        self.entries.retain(|entry| entry != query);
+        self.entries.insert(0, query.to_string());
+        self.entries.truncate(MAX_QUERIES);
+        self.reset();
+    }
+
+    /// Write the queries to `state`, one per line.
+    pub fn save(&self, state: &Path) -> Result<()> {
+        if let Some(dir) = state.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut text = String::new();
+        for entry in &self.entries {
+            text.push_str(entry);
+            text.push('\n');
+        }
+        fs::write(state, text)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn up_down_walk_entries_and_restore_the_draft() {
+        let mut history = History::default();
+        for query in ["port", "name", "port"] {
+            history.remember(query);
+        }
+        assert_eq!(history.entries, ["port", "name"]);
+        assert_eq!(history.newer(), None);
+        assert_eq!(history.older("po"), Some("port"));
+        assert_eq!(history.older("port"), Some("name"));
+        assert_eq!(history.older("name"), None);
+        assert_eq!(history.newer(), Some("port"));
+        assert_eq!(history.newer(), Some("po"));
+        assert_eq!(history.newer(), None);
+    }
+
+    #[test]
+    fn saved_history_loads_back_capped() {
+        let state = std::env::temp_dir()
+            .join(format!("yed_history_{}", std::process::id()))
+            .join("search_history");
+        let mut history = History::default();
+        for i in 0..MAX_QUERIES + 5 {
+            history.remember(&format!("q{i}"));
+        }
+        history.save(&state).unwrap();
+        let loaded = History::load(&state);
+        fs::remove_dir_all(state.parent().unwrap()).ok();
+        assert_eq!(loaded.entries.len(), MAX_QUERIES);
+        assert_eq!(loaded.entries[0], format!("q{}", MAX_QUERIES + 4));
+        assert!(History::load(&state).entries.is_empty());
+    }
+}
@@ -0,0 +1,355 @@
+// User commands run after a save (`hooks.post_save`), e.g. a linter. Their output is scanned
+// for `file:line[:col]: message` findings that the editor can list and jump to. Also runs the
+// filters a subtree is piped through (`|`).
+
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::bail;
+
+use crate::source_map::SourceMap;
+use crate::yaml_model::NodePath;
+
+/// Placeholder in a hook command for the saved file's path.
+pub const FILE_PLACEHOLDER: &str = "{file}";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub file: String,
+    /// 1-based line.
+    pub line: usize,
+    pub column: Option<usize>,
+    pub message: String,
+    /// Node starting on or before `line` in the saved file, if it parses.
+    pub path: Option<NodePath>,
+}
+
+impl Finding {
+    /// `line:col  message`, for the findings overlay.
+    pub fn summary(&self) -> String {
+        match self.column {
+            Some(column) => format!("{}:{}  {}", self.line, column, self.message),
+            None => format!("{}  {}", self.line, self.message),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HookStatus {
+    /// Exit code, or None when killed by a signal.
+    Exited(Option<i32>),
+    TimedOut(Duration),
+    /// The command could not be started.
+    Failed(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct HookResult {
+    pub status: HookStatus,
+    /// Standard output followed by standard error.
+    pub output: String,
+    pub findings: Vec<Finding>,
+}
+
+impl HookResult {
+    /// One line for the toast and the overlay title.
+    pub fn summary(&self) -> String {
+        let status = match &self.status {
+            HookStatus::Exited(Some(0)) if self.findings.is_empty() => {
+                return "post_save: ok".to_string()
+            }
+            HookStatus::Exited(Some(code)) => format!("exit {code}"),
+            HookStatus::Exited(None) => "killed".to_string(),
+            HookStatus::TimedOut(limit) => format!("timed out after {}s", limit.as_secs_f64()),
+            HookStatus::Failed(err) => return format!("post_save: could not run: {err}"),
+        };
+        match self.findings.len() {
+            0 => format!("post_save: {status}"),
+            count => format!("post_save: {count} finding(s), {status}"),
+        }
+    }
+
+    /// Whether the hook reported a problem (findings, a failing exit code or a timeout).
+    pub fn has_problems(&self) -> bool {
+        !self.findings.is_empty() || self.status != HookStatus::Exited(Some(0))
+    }
+}
+
+/// `template` with every `{file}` replaced by `file`, quoted for the shell.
+pub fn command_line(template: &str, file: &Path) -> String {
+    template.replace(FILE_PLACEHOLDER, &shell_quote(&file.to_string_lossy()))
+}
+
+#[cfg(unix)]
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+#[cfg(not(unix))]
+fn shell_quote(text: &str) -> String {
+    format!("\"{text}\"")
+}
+
+#[cfg(unix)]
+pub fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+pub fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+/// Run `command` through the shell for `file`, killing it after `timeout`. Findings are located
+/// in the tree by re-reading `file`, which the hook may have rewritten.
+pub fn run(command: &str, file: &Path, timeout: Duration) -> HookResult {
+    let (status, output) = match execute(command, timeout) {
+        Ok(done) => done,
+        Err(err) => (HookStatus::Failed(err.to_string()), String::new()),
+    };
+    let mut findings = parse_findings(&output);
+    if let Ok(text) = std::fs::read_to_string(file) {
+        let source = SourceMap::scan(&text);
+        for finding in &mut findings {
+            finding.path = source.path_at_line(finding.line).cloned();
+        }
+    }
+    HookResult {
+        status,
+        output,
+        findings,
+    }
+}
+
+fn execute(command: &str, timeout: Duration) -> std::io::Result<(HookStatus, String)> {
+    let mut child = shell(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break HookStatus::Exited(status.code());
+        }
+        if started.elapsed() >= timeout {
+            kill(&mut child);
+            break HookStatus::TimedOut(timeout);
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    // A timed-out command may have left children holding the pipes open; don't wait for them.
+    let collect = |reader: thread::JoinHandle<String>| {
+        if matches!(status, HookStatus::TimedOut(_)) && !reader.is_finished() {
+            String::new()
+        } else {
+            reader.join().unwrap_or_default()
+        }
+    };
+    let mut output = collect(stdout);
+    output.push_str(&collect(stderr));
+    Ok((status, output))
+}
+
+/// Run `command` through the shell with `input` on stdin and return its stdout. Fails with its
+/// stderr (or exit status) when it exits non-zero, and kills it after `timeout`.
+pub fn filter(command: &str, input: &str, timeout: Duration) -> anyhow::Result<String> {
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Written from a thread so a command that prints before reading all of stdin can't deadlock.
+    let stdin = child.stdin.take();
+    let input = input.to_string();
+    thread::spawn(move || {
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(input.as_bytes());
+        }
+    });
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            kill(&mut child);
+            bail!("{command}: timed out after {}s", timeout.as_secs());
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        let detail = stderr.trim();
+        match status.code() {
+            _ if !detail.is_empty() => bail!("{detail}"),
+            Some(code) => bail!("{command}: exited with status {code}"),
+            None => bail!("{command}: killed by a signal"),
+        }
+    }
+    Ok(stdout)
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Lines of the form `file:line:col: message` or `file:line: message` (yamllint's
+/// `parsable` format, most compilers and linters). Other lines are ignored.
+pub fn parse_findings(output: &str) -> Vec<Finding> {
+    output.lines().filter_map(parse_finding).collect()
+}
+
+fn parse_finding(line: &str) -> Option<Finding> {
+    let parts: Vec<&str> = line.split(':').collect();
+    let is_number = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    // The first numeric field after a non-empty file name; file names may contain ':' (C:\...).
+    let at = (1..parts.len().saturating_sub(1)).find(|&i| is_number(parts[i]))?;
+    let file = parts[..at].join(":");
+    if file.trim().is_empty() {
+        return None;
+    }
+    let line_number = parts[at].parse().ok().filter(|&n| n > 0)?;
+    let (column, rest) = match parts.get(at + 1) {
+        Some(part) if is_number(part) && at + 2 < parts.len() => (part.parse().ok(), at + 2),
+        _ => (None, at + 1),
+    };
+    let message = parts[rest..].join(":").trim().to_string();
+    if message.is_empty() {
+        return None;
+    }
+    Some(Finding {
+        file,
+        line: line_number,
+        column,
+        message,
+        path: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn stub_script() -> String {
+        let script = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/hooks/lint.sh");
+        format!("sh {} {FILE_PLACEHOLDER}", script.display())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn filters_stdin_and_reports_stderr() {
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            filter("tr a-z A-Z", "port: 80\n", timeout).unwrap(),
+            "PORT: 80\n"
+        );
+        let err = filter("echo 'bad input' >&2; exit 2", "x", timeout).unwrap_err();
+        assert_eq!(err.to_string(), "bad input");
+        let err = filter("exit 4", "x", timeout).unwrap_err();
+        assert_eq!(err.to_string(), "exit 4: exited with status 4");
+        let err = filter("sleep 5", "x", Duration::from_millis(100)).unwrap_err();
+        assert_eq!(err.to_string(), "sleep 5: timed out after 0s");
+    }
+
+    #[test]
+    fn parses_linter_lines() {
+        let output = "values.yaml:3:1: [error] wrong indentation (indentation)\n\
+                      C:\\work\\values.yaml:7: trailing spaces\n\
+                      values.yaml:12:5:\n\
+                      summary: 2 problems\n\
+                      :4:2: no file\n";
+        let found: Vec<(String, usize, Option<usize>, String)> = parse_findings(output)
+            .into_iter()
+            .map(|f| (f.file, f.line, f.column, f.message))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "values.yaml".to_string(),
+                    3,
+                    Some(1),
+                    "[error] wrong indentation (indentation)".to_string()
+                ),
+                (
+                    "C:\\work\\values.yaml".to_string(),
+                    7,
+                    None,
+                    "trailing spaces".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stub_hook_findings_are_located_in_the_saved_file() {
+        let file = std::env::temp_dir().join(format!("yed_hook's_{}.yaml", std::process::id()));
+        std::fs::write(&file, "name: api\nserver:\n  port: 80\n").unwrap();
+        let command = command_line(&stub_script(), &file);
+        let result = run(&command, &file, Duration::from_secs(10));
+        std::fs::remove_file(&file).ok();
+
+        assert_eq!(result.status, HookStatus::Exited(Some(1)));
+        let located: Vec<(usize, String)> = result
+            .findings
+            .iter()
+            .map(|f| {
+                (
+                    f.line,
+                    f.path.as_ref().map(NodePath::dot_path).unwrap_or_default(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            located,
+            vec![(1, "name".to_string()), (3, "server.port".to_string())]
+        );
+        assert_eq!(result.findings[0].file, file.display().to_string());
+        assert!(result.output.contains("2 problem(s)"), "{}", result.output);
+        assert_eq!(result.summary(), "post_save: 2 finding(s), exit 1");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn slow_hooks_time_out() {
+        let started = Instant::now();
+        let result = run(
+            "sleep 5",
+            Path::new("/nonexistent.yaml"),
+            Duration::from_millis(200),
+        );
+        assert!(started.elapsed() < Duration::from_secs(3));
+        assert_eq!(
+            result.status,
+            HookStatus::TimedOut(Duration::from_millis(200))
+        );
+        assert!(result.has_problems());
+        assert_eq!(result.summary(), "post_save: timed out after 0.2s");
+    }
+}
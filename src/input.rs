@@ -1,34 +1,166 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use crate::app::Mode;
+use crate::keymap::Keymap;
+use crate::merge::Side;
+
+/// Answer to changes on disk while the buffer has unsaved edits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiskChoice {
+    Merge,
+    Reload,
+    Keep,
+    Diff,
+}
 
 #[derive(Clone, Debug)]
 pub enum InputAction {
     Quit,
     Save,
+    SaveAs,
+    /// Write the selected subtree as JSON to a prompted path.
+    ExportJson,
+    /// Write the whole document as JSON to a prompted path.
+    ExportDocumentJson,
+    /// Copy the selected subtree as JSON.
+    CopyJson,
+    /// Sort the keys or items of the selected collection.
+    SortChildren,
+    /// Add a key or item next to the selected one.
+    AddSibling,
+    /// Wrap the selected scalar in a sequence, or unwrap a sequence of one scalar.
+    ToggleWrap,
+    /// Turn a sequence of mappings into a mapping keyed by a field, or back.
+    Rekey,
+    /// Rename every key in the document named like the selected one.
+    RenameKeyEverywhere,
+    /// Prompt for a `%s/old/new/` find-and-replace over the document's values.
+    StartReplace,
+    /// Prompt for a shell command to pipe the selected subtree through.
+    StartPipe,
+    /// Replace this match and all the rest without asking.
+    ReplaceAll,
+    /// Raw view: insert a blank line above the selected one and edit it.
+    OpenLineAbove,
+    /// Raw view: indent the selected line this many steps (`>`, `3>`).
+    IndentLine(usize),
+    /// Raw view: dedent the selected line this many steps (`<`, `3<`).
+    DedentLine(usize),
+    /// Write the whole document as TOML to a prompted path.
+    ExportToml,
     MoveUp,
     MoveDown,
     JumpTop,
     JumpBottom,
     PageUp,
     PageDown,
+    /// `Ctrl+b`/PageUp and `Ctrl+f`/PageDown: a whole screen rather than half.
+    FullPageUp,
+    FullPageDown,
     JumpLeft,
+    /// `}`/`{`: the next or previous row with the same parent, over the rows below it.
+    NextSibling,
+    PrevSibling,
+    /// `zR`: expand every mapping and list.
+    ExpandAll,
+    /// `zM`: collapse everything back to the top-level keys.
+    CollapseAll,
+    /// `zO`: expand the selected node and everything below it.
+    ExpandSubtree,
+    /// `zC`: collapse the selected node and everything below it.
+    CollapseSubtree,
+    /// `zf`: fold everything outside the selected subtree, or unfold it again.
+    ToggleFocus,
+    /// `z1`..`z9`: show exactly that many levels of the tree.
+    ExpandToDepth(usize),
+    /// Scroll the tree rows sideways (`zh`/`zl`, Shift+Left/Right).
+    ScrollLeft,
+    ScrollRight,
     Collapse,
     Expand,
     ToggleExpand,
     EditValue,
+    EditMultiline,
+    /// Edit the selected value in `$EDITOR`.
+    EditInEditor,
+    ToggleBool,
+    CastType,
+    ToggleFlowStyle,
     RenameKey,
     AddChild,
     AddMapToSequence,
     DeleteNode,
     DeleteLine,
     CopyPath,
+    CopyFencedYaml,
+    CopyMarkdownTable,
+    CopyPointer,
+    CopyPatchOp,
+    YankNode,
+    Paste,
+    ShowDuplicates,
+    /// Unified diff of the buffer against the file on disk.
+    ShowBufferDiff,
+    /// List lint findings for the file.
+    ShowLint,
+    /// Show the selected value with `${VAR}` references resolved from the environment.
+    ToggleEnvPreview,
+    /// List the `${VAR}` placeholders in the document and whether each is set.
+    ShowPlaceholders,
+    /// Show or hide the `[n]` index of sequence items in the tree.
+    ToggleIndices,
+    /// `m` + letter: remember the selected node under that letter.
+    SetMark(char),
+    /// `'` + letter: jump to the node remembered under that letter.
+    JumpToMark(char),
+    /// `'?`: list the marks.
+    ShowMarks,
+    ShowSettings,
+    /// Summarize the document: node count, leaves, depth, count per type.
+    ShowStats,
+    /// List every key binding, as the keymap has them now.
+    ShowHelp,
+    Undo,
+    ShowJournal,
+    CopyJournal,
+    ShowFindings,
+    ToggleBaselineMissing,
+    /// Resolve the selected merge conflict with one side's value.
+    MergeTake(Side),
+    /// Answer the prompt about changes on disk.
+    DiskChange(DiskChoice),
     ConfirmYes,
     ConfirmNo,
     OpenAnother,
+    JumpToParseError,
     StartSearch,
+    /// `?`: search upwards; `n` then goes to the match above.
+    StartSearchBackward,
     SearchNext,
     SearchPrev,
+    /// `gp`: prompt for a dot path to jump to.
+    GotoPath,
+    /// `H`/`M`/`L`: select the top, middle or bottom row on screen.
+    ScreenTop,
+    ScreenMiddle,
+    ScreenBottom,
+    /// `zz`: scroll the selection to the middle of the screen.
+    CenterSelection,
+    /// `zt`: scroll the selection to the top of the screen.
+    SelectionToTop,
+    /// `zb`: scroll the selection to the bottom of the screen.
+    SelectionToBottom,
+    /// `Ctrl+o`: back to where the last long move started.
+    JumpBack,
+    /// `Ctrl+i`/Tab: forward again after `Ctrl+o`.
+    JumpForward,
+    /// Ctrl+k while typing a search: keys and values, keys only, values only.
+    CycleSearchScope,
+    /// Alt+c while typing a search: ignore case, match case, smart case.
+    CycleSearchCase,
+    /// Tab while typing a new key: take the highlighted schema suggestion. Tab while typing a
+    /// path to jump to: complete the segment.
+    CompleteKey,
     Cancel,
     InputChar(char),
     InputBackspace,
@@ -37,9 +169,60 @@ pub enum InputAction {
     InputRight,
     InputHome,
     InputEnd,
+    InputUp,
+    InputDown,
+    InputNewline,
     InputCommit,
 }
 
+impl InputAction {
+    /// Actions that change the document or write it to disk.
+    pub fn is_edit(&self) -> bool {
+        matches!(
+            self,
+            InputAction::Save
+                | InputAction::EditValue
+                | InputAction::EditMultiline
+                | InputAction::EditInEditor
+                | InputAction::ToggleBool
+                | InputAction::CastType
+                | InputAction::ToggleFlowStyle
+                | InputAction::SortChildren
+                | InputAction::ToggleWrap
+                | InputAction::Rekey
+                | InputAction::RenameKey
+                | InputAction::RenameKeyEverywhere
+                | InputAction::StartReplace
+                | InputAction::StartPipe
+                | InputAction::AddChild
+                | InputAction::AddSibling
+                | InputAction::OpenLineAbove
+                | InputAction::IndentLine(_)
+                | InputAction::DedentLine(_)
+                | InputAction::AddMapToSequence
+                | InputAction::DeleteNode
+                | InputAction::DeleteLine
+                | InputAction::Paste
+                | InputAction::Undo
+        )
+    }
+
+    /// Motions a count repeats, as in `5j` or `3}`.
+    pub fn takes_count(&self) -> bool {
+        matches!(
+            self,
+            InputAction::MoveUp
+                | InputAction::MoveDown
+                | InputAction::PageUp
+                | InputAction::PageDown
+                | InputAction::FullPageUp
+                | InputAction::FullPageDown
+                | InputAction::NextSibling
+                | InputAction::PrevSibling
+        )
+    }
+}
+
 pub struct InputContext<'a> {
     pub mode: &'a Mode,
     pub key: KeyEvent,
@@ -47,33 +230,186 @@ pub struct InputContext<'a> {
 
 pub struct VimInputHandler {
     pending_g: bool,
+    /// Set after `y` (which copies the path); a second `y` yanks the node.
+    pending_y: bool,
+    /// Count being typed before a motion or `>`/`<`, as in `5j` or `3>`.
+    count: Option<usize>,
+    /// Count that came with the last action, for the caller to repeat a motion by.
+    repeat: usize,
+    /// `m` (set) or `'` (jump) waiting for the mark letter.
+    pending_mark: Option<char>,
+    /// Set after `z`, which starts a scroll command.
+    pending_z: bool,
+    /// User bindings, tried before the built-in keys of the tree view.
+    keymap: Keymap,
 }
 
 impl VimInputHandler {
     pub fn new() -> Self {
-        Self { pending_g: false }
+        Self {
+            pending_g: false,
+            pending_y: false,
+            count: None,
+            repeat: 1,
+            pending_mark: None,
+            pending_z: false,
+            keymap: Keymap::default(),
+        }
+    }
+
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    /// How many times to repeat the action just returned: the count typed before it, or 1.
+    pub fn repeat(&self) -> usize {
+        self.repeat
     }
 
     pub fn handle_key(&mut self, ctx: InputContext<'_>) -> Option<InputAction> {
         let key = ctx.key;
+        self.repeat = 1;
         match ctx.mode {
             Mode::EditValue
             | Mode::RenameKey
-            | Mode::AddKey
             | Mode::AddValue
-            | Mode::SearchInput
-            | Mode::RawEditLine => return self.handle_input_mode(key),
-            Mode::ConfirmDelete
-            | Mode::ConfirmQuit
+            | Mode::RawEditLine
+            | Mode::PasteKey
+            | Mode::SaveAsPath
+            | Mode::MergeValue
+            | Mode::NewFileName
+            | Mode::ExportPath
+            | Mode::RekeyField
+            | Mode::RenameKeyEverywhere
+            | Mode::ReplaceCommand
+            | Mode::PipeCommand => return self.handle_input_mode(key),
+            Mode::AddKey => {
+                return match key.code {
+                    KeyCode::Up => Some(InputAction::InputUp),
+                    KeyCode::Down => Some(InputAction::InputDown),
+                    KeyCode::Tab => Some(InputAction::CompleteKey),
+                    _ => self.handle_input_mode(key),
+                };
+            }
+            Mode::GotoPath => {
+                return match key.code {
+                    KeyCode::Tab => Some(InputAction::CompleteKey),
+                    _ => self.handle_input_mode(key),
+                };
+            }
+            Mode::SearchInput => {
+                if key.code == KeyCode::Char('k') && key.modifiers == KeyModifiers::CONTROL {
+                    return Some(InputAction::CycleSearchScope);
+                }
+                if key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::ALT {
+                    return Some(InputAction::CycleSearchCase);
+                }
+                return match key.code {
+                    KeyCode::Up => Some(InputAction::InputUp),
+                    KeyCode::Down => Some(InputAction::InputDown),
+                    _ => self.handle_input_mode(key),
+                };
+            }
+            Mode::ConfirmReplace => return self.handle_replace_confirm(key),
+            Mode::ConfirmDelete => return self.handle_delete_confirm(key),
+            Mode::ConfirmQuit
             | Mode::ConfirmOpenAnother
-            | Mode::ConfirmRawDeleteLine => return self.handle_confirm(key),
+            | Mode::ConfirmRawDeleteLine
+            | Mode::ConfirmRenameMerge
+            | Mode::ConfirmSaveDuplicates
+            | Mode::ConfirmLossy
+            | Mode::ConfirmOverwrite
+            | Mode::ConfirmExportOverwrite => return self.handle_confirm(key),
+            Mode::EditMultiline => return self.handle_multiline(key),
+            Mode::DuplicateList
+            | Mode::SettingsOverlay
+            | Mode::StatsOverlay
+            | Mode::HookFindings => return self.handle_list(key),
+            Mode::HelpOverlay => return self.handle_help(key),
+            Mode::ConfirmLocked => return self.handle_lock_prompt(key),
+            Mode::ConfirmAlias => return self.handle_alias_prompt(key),
+            Mode::JournalOverlay => return self.handle_journal(key),
+            Mode::MergeConflicts => return self.handle_merge(key),
+            Mode::ConfirmDiskChange => return self.handle_disk_change(key),
+            Mode::DiskDiff
+            | Mode::BufferDiff
+            | Mode::LintFindings
+            | Mode::Placeholders
+            | Mode::MarkList => return self.handle_list(key),
             Mode::Normal => {}
         }
 
+        let pending_y = std::mem::take(&mut self.pending_y);
+        let pending_g = std::mem::take(&mut self.pending_g);
+        let count = self.count.take();
+        self.repeat = count.unwrap_or(1);
+        if let Some(prefix) = self.pending_mark.take() {
+            return match key.code {
+                KeyCode::Char('?') => Some(InputAction::ShowMarks),
+                KeyCode::Char(letter) if letter.is_ascii_alphabetic() => Some(if prefix == 'm' {
+                    InputAction::SetMark(letter)
+                } else {
+                    InputAction::JumpToMark(letter)
+                }),
+                _ => None,
+            };
+        }
+        if std::mem::take(&mut self.pending_z) {
+            return match key.code {
+                KeyCode::Char('h') => Some(InputAction::ScrollLeft),
+                KeyCode::Char('l') => Some(InputAction::ScrollRight),
+                KeyCode::Char('R') => Some(InputAction::ExpandAll),
+                KeyCode::Char('M') => Some(InputAction::CollapseAll),
+                KeyCode::Char('O') => Some(InputAction::ExpandSubtree),
+                KeyCode::Char('C') => Some(InputAction::CollapseSubtree),
+                KeyCode::Char('f') => Some(InputAction::ToggleFocus),
+                KeyCode::Char('z') => Some(InputAction::CenterSelection),
+                KeyCode::Char('t') => Some(InputAction::SelectionToTop),
+                KeyCode::Char('b') => Some(InputAction::SelectionToBottom),
+                KeyCode::Char(digit @ '1'..='9') => {
+                    Some(InputAction::ExpandToDepth(digit as usize - '0' as usize))
+                }
+                _ => None,
+            };
+        }
+        if let Some(binding) = self.keymap.lookup(key) {
+            return binding;
+        }
         match (key.code, key.modifiers) {
+            (KeyCode::Char(digit @ '0'..='9'), KeyModifiers::NONE)
+                if digit != '0' || count.is_some() =>
+            {
+                let digit = digit.to_digit(10).unwrap_or(0) as usize;
+                self.count = Some((count.unwrap_or(0) * 10 + digit).min(999));
+                None
+            }
+            (KeyCode::Char('>'), _) => Some(InputAction::IndentLine(count.unwrap_or(1))),
+            (KeyCode::Char('<'), _) => Some(InputAction::DedentLine(count.unwrap_or(1))),
             (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InputAction::Quit),
+            (
+                KeyCode::Char(prefix @ ('m' | '\'' | '`')),
+                KeyModifiers::NONE | KeyModifiers::SHIFT,
+            ) => {
+                self.pending_mark = Some(prefix);
+                None
+            }
             (KeyCode::Char('s'), KeyModifiers::CONTROL) => Some(InputAction::Save),
-            (KeyCode::Char('o'), KeyModifiers::CONTROL) => Some(InputAction::OpenAnother),
+            (KeyCode::Char('s' | 'S'), m) if m == KeyModifiers::CONTROL | KeyModifiers::SHIFT => {
+                Some(InputAction::SaveAs)
+            }
+            (KeyCode::Char('p'), KeyModifiers::CONTROL) => Some(InputAction::OpenAnother),
+            (KeyCode::Char('o'), KeyModifiers::CONTROL) => Some(InputAction::JumpBack),
+            (KeyCode::Tab, KeyModifiers::NONE) | (KeyCode::Char('i'), KeyModifiers::CONTROL) => {
+                Some(InputAction::JumpForward)
+            }
+            (KeyCode::Char('x'), KeyModifiers::CONTROL) => Some(InputAction::CopyJson),
+            (KeyCode::Char('e'), KeyModifiers::CONTROL) => Some(InputAction::EditInEditor),
+            (KeyCode::Char('t'), KeyModifiers::CONTROL) => Some(InputAction::ExportToml),
+            (KeyCode::Char('g'), KeyModifiers::CONTROL) => Some(InputAction::ShowStats),
             (KeyCode::Char('j'), KeyModifiers::NONE) | (KeyCode::Down, _) => {
                 Some(InputAction::MoveDown)
             }
@@ -81,8 +417,7 @@ impl VimInputHandler {
                 Some(InputAction::MoveUp)
             }
             (KeyCode::Char('g'), KeyModifiers::NONE) => {
-                if self.pending_g {
-                    self.pending_g = false;
+                if pending_g {
                     Some(InputAction::JumpTop)
                 } else {
                     self.pending_g = true;
@@ -92,6 +427,15 @@ impl VimInputHandler {
             (KeyCode::Char('G'), KeyModifiers::SHIFT) | (KeyCode::Char('G'), KeyModifiers::NONE) => {
                 Some(InputAction::JumpBottom)
             }
+            (KeyCode::Char('H'), KeyModifiers::NONE | KeyModifiers::SHIFT) => Some(InputAction::ScreenTop),
+            (KeyCode::Char('M'), KeyModifiers::NONE | KeyModifiers::SHIFT) => Some(InputAction::ScreenMiddle),
+            (KeyCode::Char('L'), KeyModifiers::NONE | KeyModifiers::SHIFT) => Some(InputAction::ScreenBottom),
+            (KeyCode::Char('z'), KeyModifiers::NONE) => {
+                self.pending_z = true;
+                None
+            }
+            (KeyCode::Left, KeyModifiers::SHIFT) => Some(InputAction::ScrollLeft),
+            (KeyCode::Right, KeyModifiers::SHIFT) => Some(InputAction::ScrollRight),
             (KeyCode::Char('h'), KeyModifiers::NONE) | (KeyCode::Left, _) => {
                 Some(InputAction::Collapse)
             }
@@ -100,29 +444,95 @@ impl VimInputHandler {
             }
             (KeyCode::Enter, _) => Some(InputAction::ToggleExpand),
             (KeyCode::Char('e'), KeyModifiers::NONE) => Some(InputAction::EditValue),
+            (KeyCode::Char('E'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('E'), KeyModifiers::NONE) => Some(InputAction::EditMultiline),
+            (KeyCode::Char('t'), KeyModifiers::NONE) | (KeyCode::Char(' '), KeyModifiers::NONE) => {
+                Some(InputAction::ToggleBool)
+            }
+            (KeyCode::Char('c'), KeyModifiers::NONE) => Some(InputAction::CastType),
+            (KeyCode::Char('f'), KeyModifiers::NONE) => Some(InputAction::ToggleFlowStyle),
+            (KeyCode::Char('s'), KeyModifiers::NONE) => Some(InputAction::SortChildren),
+            (KeyCode::Char('w'), KeyModifiers::NONE) => Some(InputAction::ToggleWrap),
+            (KeyCode::Char('K'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('K'), KeyModifiers::NONE) => Some(InputAction::Rekey),
             (KeyCode::Char('r'), KeyModifiers::NONE) => Some(InputAction::RenameKey),
+            (KeyCode::Char('R'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('R'), KeyModifiers::NONE) => Some(InputAction::RenameKeyEverywhere),
             (KeyCode::Char('a'), KeyModifiers::NONE) => Some(InputAction::AddChild),
+            (KeyCode::Char('o'), KeyModifiers::NONE) => Some(InputAction::AddSibling),
+            (KeyCode::Char('O'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('O'), KeyModifiers::NONE) => Some(InputAction::OpenLineAbove),
             (KeyCode::Char('A'), KeyModifiers::SHIFT) => Some(InputAction::AddMapToSequence),
             (KeyCode::Char('d'), KeyModifiers::NONE) => Some(InputAction::DeleteNode),
             (KeyCode::Delete, KeyModifiers::SHIFT) => Some(InputAction::DeleteLine),
-            (KeyCode::Char('y'), KeyModifiers::NONE) => Some(InputAction::CopyPath),
-            (KeyCode::Char('n'), KeyModifiers::NONE) => Some(InputAction::SearchNext),
-            (KeyCode::Char('N'), KeyModifiers::SHIFT) | (KeyCode::Char('N'), KeyModifiers::NONE) => {
-                Some(InputAction::SearchPrev)
+            (KeyCode::Char('y'), KeyModifiers::NONE) => {
+                if pending_y {
+                    Some(InputAction::YankNode)
+                } else {
+                    self.pending_y = true;
+                    Some(InputAction::CopyPath)
+                }
             }
+            (KeyCode::Char('p'), KeyModifiers::NONE) if pending_g => Some(InputAction::GotoPath),
+            (KeyCode::Char('p'), KeyModifiers::NONE) => Some(InputAction::Paste),
+            (KeyCode::Char('x'), KeyModifiers::NONE) => Some(InputAction::ExportJson),
+            (KeyCode::Char('X'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('X'), KeyModifiers::NONE) => Some(InputAction::ExportDocumentJson),
+            (KeyCode::Char('b'), KeyModifiers::NONE) => Some(InputAction::ToggleBaselineMissing),
+            (KeyCode::Char('Y'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('Y'), KeyModifiers::NONE) => Some(InputAction::CopyFencedYaml),
+            (KeyCode::Char('T'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('T'), KeyModifiers::NONE) => Some(InputAction::CopyMarkdownTable),
+            (KeyCode::Char('J'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('J'), KeyModifiers::NONE) => Some(InputAction::CopyPointer),
+            (KeyCode::Char('P'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('P'), KeyModifiers::NONE) => Some(InputAction::CopyPatchOp),
+            (KeyCode::Char('D'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('D'), KeyModifiers::NONE) => Some(InputAction::ShowDuplicates),
+            (KeyCode::Char('S'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('S'), KeyModifiers::NONE) => Some(InputAction::ShowSettings),
+            // Most terminals send Ctrl+Shift+s as plain Ctrl+s, so Save As needs a key of its own.
+            (KeyCode::Char('W'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('W'), KeyModifiers::NONE) => Some(InputAction::SaveAs),
+            (KeyCode::Char('u'), KeyModifiers::NONE) => Some(InputAction::Undo),
+            (KeyCode::Char('U'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('U'), KeyModifiers::NONE) => Some(InputAction::ShowJournal),
+            (KeyCode::Char('F'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('F'), KeyModifiers::NONE) => Some(InputAction::ShowFindings),
+            (KeyCode::Char('I'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('I'), KeyModifiers::NONE) => Some(InputAction::ShowLint),
+            (KeyCode::Char('V'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('V'), KeyModifiers::NONE) => Some(InputAction::ShowPlaceholders),
+            (KeyCode::Char('$'), _) => Some(InputAction::ToggleEnvPreview),
+            (KeyCode::Char('#'), _) => Some(InputAction::ToggleIndices),
+            (KeyCode::Char('n'), KeyModifiers::NONE) => Some(InputAction::SearchNext),
+            (KeyCode::Char('N'), KeyModifiers::SHIFT)
+            | (KeyCode::Char('N'), KeyModifiers::NONE) => Some(InputAction::SearchPrev),
             (KeyCode::Char('/'), KeyModifiers::NONE) => Some(InputAction::StartSearch),
+            (KeyCode::Char('?'), _) => Some(InputAction::StartSearchBackward),
+            (KeyCode::F(1), _) => Some(InputAction::ShowHelp),
+            (KeyCode::Char(':'), _) => Some(InputAction::StartReplace),
+            (KeyCode::Char('|'), _) => Some(InputAction::StartPipe),
+            (KeyCode::Char('='), _) => Some(InputAction::ShowBufferDiff),
+            (KeyCode::Char('!'), _) => Some(InputAction::JumpToParseError),
             (KeyCode::Char('0'), KeyModifiers::NONE) => Some(InputAction::JumpLeft),
+            (KeyCode::Char('}'), _) => Some(InputAction::NextSibling),
+            (KeyCode::Char('{'), _) => Some(InputAction::PrevSibling),
             (KeyCode::Char('u'), KeyModifiers::CONTROL) => Some(InputAction::PageUp),
             (KeyCode::Char('d'), KeyModifiers::CONTROL) => Some(InputAction::PageDown),
-            _ => {
-                self.pending_g = false;
-                None
+            (KeyCode::Char('b'), KeyModifiers::CONTROL) | (KeyCode::PageUp, _) => {
+                Some(InputAction::FullPageUp)
+            }
+            (KeyCode::Char('f'), KeyModifiers::CONTROL) | (KeyCode::PageDown, _) => {
+                Some(InputAction::FullPageDown)
             }
+            _ => None,
         }
     }
 
     fn handle_input_mode(&mut self, key: KeyEvent) -> Option<InputAction> {
         self.pending_g = false;
+        self.pending_y = false;
         match (key.code, key.modifiers) {
             (KeyCode::Esc, _) => Some(InputAction::Cancel),
             (KeyCode::Enter, _) => Some(InputAction::InputCommit),
@@ -138,8 +548,154 @@ impl VimInputHandler {
         }
     }
 
+    /// Multi-line popup: Enter inserts a newline, Ctrl+s commits, Esc cancels.
+    fn handle_multiline(&mut self, key: KeyEvent) -> Option<InputAction> {
+        self.pending_g = false;
+        self.pending_y = false;
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) => Some(InputAction::Cancel),
+            (KeyCode::Char('s'), KeyModifiers::CONTROL) => Some(InputAction::InputCommit),
+            (KeyCode::Enter, _) => Some(InputAction::InputNewline),
+            (KeyCode::Up, _) => Some(InputAction::InputUp),
+            (KeyCode::Down, _) => Some(InputAction::InputDown),
+            (KeyCode::Left, _) => Some(InputAction::InputLeft),
+            (KeyCode::Right, _) => Some(InputAction::InputRight),
+            (KeyCode::Home, _) => Some(InputAction::InputHome),
+            (KeyCode::End, _) => Some(InputAction::InputEnd),
+            (KeyCode::Backspace, _) => Some(InputAction::InputBackspace),
+            (KeyCode::Delete, _) => Some(InputAction::InputDelete),
+            (KeyCode::Char(ch), KeyModifiers::NONE) => Some(InputAction::InputChar(ch)),
+            (KeyCode::Char(ch), KeyModifiers::SHIFT) => Some(InputAction::InputChar(ch)),
+            _ => None,
+        }
+    }
+
+    /// Overlay lists: move with j/k, Enter to pick, Esc to close.
+    fn handle_list(&mut self, key: KeyEvent) -> Option<InputAction> {
+        self.pending_g = false;
+        self.pending_y = false;
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('j'), KeyModifiers::NONE) | (KeyCode::Down, _) => {
+                Some(InputAction::MoveDown)
+            }
+            (KeyCode::Char('k'), KeyModifiers::NONE) | (KeyCode::Up, _) => {
+                Some(InputAction::MoveUp)
+            }
+            (KeyCode::Enter, _) => Some(InputAction::InputCommit),
+            (KeyCode::Esc, _) | (KeyCode::Char('q'), KeyModifiers::NONE) => {
+                Some(InputAction::Cancel)
+            }
+            _ => None,
+        }
+    }
+
+    /// Help overlay: list keys plus Ctrl+d/Ctrl+u to page, and F1 to close it again.
+    fn handle_help(&mut self, key: KeyEvent) -> Option<InputAction> {
+        match (key.code, key.modifiers) {
+            (KeyCode::F(1), _) | (KeyCode::Char('?'), _) => Some(InputAction::Cancel),
+            (KeyCode::Char('d'), KeyModifiers::CONTROL) | (KeyCode::PageDown, _) => {
+                Some(InputAction::PageDown)
+            }
+            (KeyCode::Char('u'), KeyModifiers::CONTROL) | (KeyCode::PageUp, _) => {
+                Some(InputAction::PageUp)
+            }
+            _ => self.handle_list(key),
+        }
+    }
+
+    /// Journal overlay: list keys plus u (undo back to the entry) and y (copy journal).
+    fn handle_journal(&mut self, key: KeyEvent) -> Option<InputAction> {
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('u'), KeyModifiers::NONE) => Some(InputAction::Undo),
+            (KeyCode::Char('y'), KeyModifiers::NONE) => Some(InputAction::CopyJournal),
+            _ => self.handle_list(key),
+        }
+    }
+
+    /// Merge conflicts: list keys plus d (disk), b (buffer), o (baseline), e (custom value).
+    fn handle_merge(&mut self, key: KeyEvent) -> Option<InputAction> {
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('d'), KeyModifiers::NONE) => Some(InputAction::MergeTake(Side::Disk)),
+            (KeyCode::Char('b'), KeyModifiers::NONE) => Some(InputAction::MergeTake(Side::Buffer)),
+            (KeyCode::Char('o'), KeyModifiers::NONE) => {
+                Some(InputAction::MergeTake(Side::Baseline))
+            }
+            (KeyCode::Char('e'), KeyModifiers::NONE) => Some(InputAction::EditValue),
+            _ => self.handle_list(key),
+        }
+    }
+
+    /// Replace this match? y yes, n no, a all the rest, q or Esc stop.
+    fn handle_replace_confirm(&mut self, key: KeyEvent) -> Option<InputAction> {
+        self.pending_g = false;
+        self.pending_y = false;
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('y'), KeyModifiers::NONE) => Some(InputAction::ConfirmYes),
+            (KeyCode::Char('n'), KeyModifiers::NONE) => Some(InputAction::ConfirmNo),
+            (KeyCode::Char('a'), KeyModifiers::NONE) => Some(InputAction::ReplaceAll),
+            (KeyCode::Char('q'), KeyModifiers::NONE) | (KeyCode::Esc, _) => {
+                Some(InputAction::Cancel)
+            }
+            _ => None,
+        }
+    }
+
+    /// File changed on disk: m merge, r reload, k (or Esc) keep the buffer, d diff by path,
+    /// = diff by line.
+    fn handle_disk_change(&mut self, key: KeyEvent) -> Option<InputAction> {
+        self.pending_g = false;
+        self.pending_y = false;
+        let choice = match (key.code, key.modifiers) {
+            (KeyCode::Char('='), _) => return Some(InputAction::ShowBufferDiff),
+            (KeyCode::Char('m'), KeyModifiers::NONE) | (KeyCode::Enter, _) => DiskChoice::Merge,
+            (KeyCode::Char('r'), KeyModifiers::NONE) => DiskChoice::Reload,
+            (KeyCode::Char('k'), KeyModifiers::NONE) | (KeyCode::Esc, _) => DiskChoice::Keep,
+            (KeyCode::Char('d'), KeyModifiers::NONE) => DiskChoice::Diff,
+            _ => return None,
+        };
+        Some(InputAction::DiskChange(choice))
+    }
+
+    /// File locked by another instance: r (default) read-only, o open anyway, q quit.
+    fn handle_lock_prompt(&mut self, key: KeyEvent) -> Option<InputAction> {
+        self.pending_g = false;
+        self.pending_y = false;
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('r'), KeyModifiers::NONE) | (KeyCode::Enter, _) | (KeyCode::Esc, _) => {
+                Some(InputAction::ConfirmNo)
+            }
+            (KeyCode::Char('o'), KeyModifiers::NONE) => Some(InputAction::ConfirmYes),
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InputAction::Quit),
+            _ => None,
+        }
+    }
+
+    fn handle_alias_prompt(&mut self, key: KeyEvent) -> Option<InputAction> {
+        self.pending_g = false;
+        self.pending_y = false;
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('s'), KeyModifiers::NONE) | (KeyCode::Enter, _) => {
+                Some(InputAction::ConfirmYes)
+            }
+            (KeyCode::Char('b'), KeyModifiers::NONE) => Some(InputAction::ConfirmNo),
+            (KeyCode::Esc, _) | (KeyCode::Char('q'), KeyModifiers::NONE) => {
+                Some(InputAction::Cancel)
+            }
+            _ => None,
+        }
+    }
+
+    /// Delete confirmation: y/n, or p to copy a JSON Patch `remove` op instead of deleting.
+    fn handle_delete_confirm(&mut self, key: KeyEvent) -> Option<InputAction> {
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('p'), KeyModifiers::NONE) => Some(InputAction::CopyPatchOp),
+            _ => self.handle_confirm(key),
+        }
+    }
+
     fn handle_confirm(&mut self, key: KeyEvent) -> Option<InputAction> {
         self.pending_g = false;
+        self.pending_y = false;
         match (key.code, key.modifiers) {
             (KeyCode::Char('y'), KeyModifiers::NONE) => Some(InputAction::ConfirmYes),
             (KeyCode::Char('n'), KeyModifiers::NONE) => Some(InputAction::ConfirmNo),
@@ -148,3 +704,28 @@ impl VimInputHandler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(vim: &mut VimInputHandler, ch: char) -> Option<InputAction> {
+        let key = KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE);
+        vim.handle_key(InputContext {
+            mode: &Mode::Normal,
+            key,
+        })
+    }
+
+    #[test]
+    fn a_g_prefix_only_reaches_the_next_key() {
+        let mut vim = VimInputHandler::new();
+        assert!(press(&mut vim, 'g').is_none());
+        assert!(matches!(press(&mut vim, 'p'), Some(InputAction::GotoPath)));
+        assert!(press(&mut vim, 'g').is_none());
+        assert!(matches!(press(&mut vim, 'j'), Some(InputAction::MoveDown)));
+        assert!(matches!(press(&mut vim, 'p'), Some(InputAction::Paste)));
+        assert!(press(&mut vim, 'g').is_none());
+        assert!(matches!(press(&mut vim, 'g'), Some(InputAction::JumpTop)));
+    }
+}
@@ -0,0 +1,181 @@
+// `${VAR}` and `$VAR` placeholders in string values, resolved against the environment for
+// display only. The stored values keep their placeholder text.
+
+use yaml_rust2::Yaml;
+
+use crate::yaml_model::NodePath;
+
+/// Part of a string value: literal text, or a variable reference as written.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Piece<'a> {
+    Text(&'a str),
+    Var { reference: &'a str, name: &'a str },
+}
+
+/// A variable used somewhere in the document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Placeholder {
+    pub name: String,
+    /// First value that uses it, in document order.
+    pub path: NodePath,
+    pub uses: usize,
+}
+
+/// `text` split into literal runs and references. A `$` not followed by a name or by a closed
+/// `{name}` is literal.
+pub fn pieces(text: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut literal_start = 0;
+    let mut i = 0;
+    while let Some(offset) = text[i..].find('$') {
+        let start = i + offset;
+        let rest = &text[start + 1..];
+        let (name, end) = match rest.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(close) if is_name(&braced[..close]) => (&braced[..close], start + close + 3),
+                _ => ("", start + 1),
+            },
+            None => {
+                let len = name_len(rest);
+                (&rest[..len], start + 1 + len)
+            }
+        };
+        if name.is_empty() {
+            i = start + 1;
+            continue;
+        }
+        if literal_start < start {
+            pieces.push(Piece::Text(&text[literal_start..start]));
+        }
+        pieces.push(Piece::Var {
+            reference: &text[start..end],
+            name,
+        });
+        literal_start = end;
+        i = end;
+    }
+    if literal_start < text.len() {
+        pieces.push(Piece::Text(&text[literal_start..]));
+    }
+    pieces
+}
+
+/// Whether `text` references any variable.
+pub fn has_references(text: &str) -> bool {
+    pieces(text)
+        .iter()
+        .any(|piece| matches!(piece, Piece::Var { .. }))
+}
+
+/// Every variable referenced by a string value under `root`, in order of first use.
+pub fn placeholders(root: &Yaml) -> Vec<Placeholder> {
+    let mut found: Vec<Placeholder> = Vec::new();
+    collect(root, &NodePath(Vec::new()), &mut found);
+    found
+}
+
+fn collect(node: &Yaml, path: &NodePath, found: &mut Vec<Placeholder>) {
+    match node {
+        Yaml::String(text) => {
+            for piece in pieces(text) {
+                let Piece::Var { name, .. } = piece else {
+                    continue;
+                };
+                match found.iter_mut().find(|p| p.name == name) {
+                    Some(placeholder) => placeholder.uses += 1,
+                    None => found.push(Placeholder {
+                        name: name.to_string(),
+                        path: path.clone(),
+                        uses: 1,
+                    }),
+                }
+            }
+        }
+        Yaml::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                collect(item, &path.child_index(index), found);
+            }
+        }
+        Yaml::Hash(map) => {
+            for (key, value) in map {
+                if let Some(key) = key.as_str() {
+                    collect(value, &path.child_key(key), found);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn name_len(text: &str) -> usize {
+    let mut len = 0;
+    for (index, ch) in text.char_indices() {
+        let ok = ch == '_' || ch.is_ascii_alphabetic() || (index > 0 && ch.is_ascii_digit());
+        if !ok {
+            break;
+        }
+        len = index + ch.len_utf8();
+    }
+    len
+}
+
+fn is_name(text: &str) -> bool {
+    !text.is_empty() && name_len(text) == text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use yaml_rust2::YamlLoader;
+
+    #[test]
+    fn splits_braced_and_bare_references() {
+        assert_eq!(
+            pieces("postgres://${DB_USER}@$DB_HOST:5432/db"),
+            [
+                Piece::Text("postgres://"),
+                Piece::Var {
+                    reference: "${DB_USER}",
+                    name: "DB_USER"
+                },
+                Piece::Text("@"),
+                Piece::Var {
+                    reference: "$DB_HOST",
+                    name: "DB_HOST"
+                },
+                Piece::Text(":5432/db"),
+            ]
+        );
+        // Prices, unclosed braces and names starting with a digit stay literal.
+        assert_eq!(
+            pieces("$5 and ${OPEN and ${1X}"),
+            [Piece::Text("$5 and ${OPEN and ${1X}")]
+        );
+        assert_eq!(pieces("$"), [Piece::Text("$")]);
+        assert!(!has_references("no vars"));
+        assert!(has_references("$HOME"));
+    }
+
+    #[test]
+    fn lists_placeholders_with_first_use() {
+        let root = YamlLoader::load_from_str(
+            "db: ${DB_URL}\nhosts:\n  - $HOST\n  - ${HOST}:$PORT\nport: 80\n",
+        )
+        .unwrap()
+        .remove(0);
+        let found = placeholders(&root);
+        let summary: Vec<_> = found
+            .iter()
+            .map(|p| (p.name.as_str(), p.path.dot_path(), p.uses))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("DB_URL", "db".to_string(), 1),
+                ("HOST", "hosts.0".to_string(), 2),
+                ("PORT", "hosts.1".to_string(), 1),
+            ]
+        );
+    }
+}
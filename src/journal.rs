@@ -0,0 +1,222 @@
+// Session journal of tree edits. Each entry keeps the document as it was before the edit, so
+// the journal doubles as the undo stack and the two can never disagree.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::clock;
+use crate::yaml_model::{NodePath, Snapshot};
+
+/// Oldest entries are dropped beyond this many.
+pub const JOURNAL_CAP: usize = 200;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditKind {
+    Edit,
+    Toggle,
+    Cast,
+    Rename,
+    Add,
+    Delete,
+    Paste,
+    /// Flow/block style switch of a collection.
+    Style,
+    /// Changes on disk merged into the buffer.
+    Merge,
+    /// Keys or items of a collection sorted.
+    Sort,
+    /// Scalar wrapped in a one-item sequence, or unwrapped from one.
+    Wrap,
+    /// Sequence of mappings turned into a mapping keyed by a field, or back.
+    Rekey,
+    /// Find and replace across the document's values.
+    Replace,
+    /// Subtree replaced by the output of a shell command.
+    Pipe,
+}
+
+impl fmt::Display for EditKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            EditKind::Edit => "edit",
+            EditKind::Toggle => "toggle",
+            EditKind::Cast => "cast",
+            EditKind::Rename => "rename",
+            EditKind::Add => "add",
+            EditKind::Delete => "delete",
+            EditKind::Paste => "paste",
+            EditKind::Style => "style",
+            EditKind::Merge => "merge",
+            EditKind::Sort => "sort",
+            EditKind::Wrap => "wrap",
+            EditKind::Rekey => "rekey",
+            EditKind::Replace => "replace",
+            EditKind::Pipe => "pipe",
+        };
+        f.pad(label)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct JournalEntry {
+    /// Seconds since the Unix epoch.
+    pub at: u64,
+    pub kind: EditKind,
+    pub path: NodePath,
+    pub before: String,
+    pub after: String,
+    snapshot: Snapshot,
+}
+
+impl JournalEntry {
+    /// One line: `HH:MM:SS kind path: before → after`.
+    pub fn summary(&self) -> String {
+        let (hour, minute, second) = clock::local_time(self.at);
+        let path = if self.path.0.is_empty() {
+            "(root)".to_string()
+        } else {
+            self.path.dot_path()
+        };
+        format!(
+            "{hour:02}:{minute:02}:{second:02} {:<6} {path}: {} → {}",
+            self.kind, self.before, self.after
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Journal {
+    /// Oldest first.
+    entries: VecDeque<JournalEntry>,
+}
+
+impl Journal {
+    /// Record an edit; `snapshot` is the document from before it.
+    pub fn record(
+        &mut self,
+        kind: EditKind,
+        path: NodePath,
+        before: String,
+        after: String,
+        snapshot: Snapshot,
+    ) {
+        if self.entries.len() == JOURNAL_CAP {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(JournalEntry {
+            at: clock::now_secs(),
+            kind,
+            path,
+            before,
+            after,
+            snapshot,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Whether the node at `path` was added or pasted this session, itself or with an ancestor.
+    pub fn created(&self, path: &NodePath) -> bool {
+        self.entries.iter().any(|entry| {
+            matches!(entry.kind, EditKind::Add | EditKind::Paste)
+                && path.0.starts_with(&entry.path.0)
+        })
+    }
+
+    /// Entries newest first, the order the overlay lists them in.
+    pub fn newest_first(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.entries.iter().rev()
+    }
+
+    /// Undo the newest `count` entries. Returns the removed entries (newest first) and the
+    /// document to restore, from before the oldest of them.
+    pub fn undo(&mut self, count: usize) -> Option<(Vec<JournalEntry>, Snapshot)> {
+        if count == 0 || count > self.entries.len() {
+            return None;
+        }
+        let removed: Vec<JournalEntry> =
+            (0..count).filter_map(|_| self.entries.pop_back()).collect();
+        let snapshot = removed.last()?.snapshot.clone();
+        Some((removed, snapshot))
+    }
+
+    /// Plain-text journal, oldest first, for pasting into a change ticket.
+    pub fn to_text(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| format!("{}\n", entry.summary()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml_model::YamlModel;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn capped_and_undone_newest_first() {
+        let snapshot = YamlModel::empty().snapshot();
+        let mut journal = Journal::default();
+        for idx in 0..JOURNAL_CAP + 5 {
+            let path = NodePath(Vec::new()).child_index(idx);
+            journal.record(
+                EditKind::Edit,
+                path,
+                idx.to_string(),
+                String::new(),
+                snapshot.clone(),
+            );
+        }
+        assert_eq!(journal.len(), JOURNAL_CAP);
+        let (removed, _) = journal.undo(2).unwrap();
+        let undone: Vec<&str> = removed.iter().map(|e| e.before.as_str()).collect();
+        assert_eq!(undone, vec!["204", "203"]);
+        assert_eq!(journal.newest_first().next().unwrap().before, "202");
+        assert!(journal.undo(JOURNAL_CAP).is_none());
+        assert!(journal
+            .to_text()
+            .lines()
+            .next()
+            .unwrap()
+            .ends_with("edit   5: 5 → "));
+    }
+
+    #[test]
+    fn created_covers_added_nodes_and_their_children() {
+        let snapshot = YamlModel::empty().snapshot();
+        let root = NodePath(Vec::new());
+        let mut journal = Journal::default();
+        journal.record(
+            EditKind::Edit,
+            root.child_key("name"),
+            String::new(),
+            String::new(),
+            snapshot.clone(),
+        );
+        journal.record(
+            EditKind::Add,
+            root.child_key("tls"),
+            String::new(),
+            String::new(),
+            snapshot,
+        );
+        assert!(journal.created(&root.child_key("tls")));
+        assert!(journal.created(&root.child_key("tls").child_key("cert")));
+        assert!(!journal.created(&root.child_key("name")));
+        assert!(!journal.created(&root));
+        journal.undo(1);
+        assert!(!journal.created(&root.child_key("tls")));
+    }
+}
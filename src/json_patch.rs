@@ -0,0 +1,321 @@
+// JSON Pointer (RFC 6901) paths and JSON Patch (RFC 6902) operations for a node, for pasting
+// into patch files written against the document, and JSON exports of the document.
+
+use anyhow::{anyhow, bail, Result};
+use yaml_rust2::Yaml;
+
+use crate::yaml_model::{merged_entries, NodePath, PathSegment};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatchOp {
+    Add,
+    Replace,
+    Remove,
+}
+
+impl PatchOp {
+    pub fn name(self) -> &'static str {
+        match self {
+            PatchOp::Add => "add",
+            PatchOp::Replace => "replace",
+            PatchOp::Remove => "remove",
+        }
+    }
+}
+
+/// `/spec/containers/0/image`; the root is the empty pointer.
+pub fn pointer(path: &NodePath) -> String {
+    path.0
+        .iter()
+        .map(|segment| match segment {
+            PathSegment::Key(key) => format!("/{}", escape_token(key)),
+            PathSegment::Index(index) => format!("/{index}"),
+        })
+        .collect()
+}
+
+/// `~` becomes `~0` and `/` becomes `~1`, in that order.
+fn escape_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// One patch object, e.g. `{"op":"replace","path":"/a","value":1}`. `value` is ignored for
+/// `remove` and required otherwise.
+pub fn patch_op(op: PatchOp, path: &NodePath, value: Option<&Yaml>) -> Result<String> {
+    let head = format!(
+        "{{\"op\":\"{}\",\"path\":{}",
+        op.name(),
+        json_string(&pointer(path))
+    );
+    if op == PatchOp::Remove {
+        return Ok(format!("{head}}}"));
+    }
+    let Some(value) = value else {
+        bail!("{} needs a value", op.name());
+    };
+    Ok(format!("{head},\"value\":{}}}", json_value(value)?))
+}
+
+/// Compact JSON for a node. Fails for values JSON cannot hold (non-finite numbers, collection keys).
+pub fn json_value(node: &Yaml) -> Result<String> {
+    Ok(match node {
+        Yaml::String(value) => json_string(value),
+        Yaml::Integer(value) => value.to_string(),
+        Yaml::Real(text) => json_number(text)?,
+        Yaml::Boolean(value) => value.to_string(),
+        Yaml::Null => "null".to_string(),
+        Yaml::Array(seq) => {
+            let items: Result<Vec<String>> = seq.iter().map(json_value).collect();
+            format!("[{}]", items?.join(","))
+        }
+        Yaml::Hash(map) => {
+            let entries: Result<Vec<String>> = map
+                .iter()
+                .map(|(key, value)| {
+                    Ok(format!(
+                        "{}:{}",
+                        json_string(&json_key(key)?),
+                        json_value(value)?
+                    ))
+                })
+                .collect();
+            format!("{{{}}}", entries?.join(","))
+        }
+        Yaml::Alias(_) | Yaml::BadValue => bail!("Value has no JSON form"),
+    })
+}
+
+/// Pretty-printed JSON for a node, indented by two spaces. Unlike [`json_value`], collection
+/// keys are written as strings of their compact JSON, and `<<` merge keys are applied rather
+/// than written out. `at` is the node's path, used to say where a value without a JSON form is.
+pub fn json_pretty(node: &Yaml, at: &NodePath) -> Result<String> {
+    let mut out = String::new();
+    write_pretty(node, at, 0, &mut out)?;
+    Ok(out)
+}
+
+fn write_pretty(node: &Yaml, at: &NodePath, depth: usize, out: &mut String) -> Result<()> {
+    let pad = |out: &mut String, depth: usize| out.push_str(&"  ".repeat(depth));
+    match node {
+        Yaml::Array(seq) if !seq.is_empty() => {
+            out.push_str("[\n");
+            for (index, item) in seq.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(",\n");
+                }
+                pad(out, depth + 1);
+                write_pretty(item, &at.child_index(index), depth + 1, out)?;
+            }
+            out.push('\n');
+            pad(out, depth);
+            out.push(']');
+        }
+        Yaml::Hash(map) if !map.is_empty() => {
+            let entries = merged_entries(map);
+            if entries.is_empty() {
+                out.push_str("{}");
+                return Ok(());
+            }
+            out.push_str("{\n");
+            for (index, (key, value)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    out.push_str(",\n");
+                }
+                let key = match key {
+                    Yaml::Array(_) | Yaml::Hash(_) => json_value(key),
+                    _ => json_key(key),
+                }
+                .map_err(|_| anyhow!("A key in {} has no JSON form", location(at)))?;
+                pad(out, depth + 1);
+                out.push_str(&json_string(&key));
+                out.push_str(": ");
+                write_pretty(value, &at.child_key(&key), depth + 1, out)?;
+            }
+            out.push('\n');
+            pad(out, depth);
+            out.push('}');
+        }
+        _ => {
+            let value =
+                json_value(node).map_err(|_| anyhow!("{} has no JSON form", location(at)))?;
+            out.push_str(&value);
+        }
+    }
+    Ok(())
+}
+
+fn location(at: &NodePath) -> String {
+    if at.0.is_empty() {
+        "The document".to_string()
+    } else {
+        at.dot_path()
+    }
+}
+
+fn json_number(text: &str) -> Result<String> {
+    let number = match Yaml::Real(text.to_string()).as_f64() {
+        Some(number) if number.is_finite() => number,
+        _ => bail!("{text} has no JSON form"),
+    };
+    let text = number.to_string();
+    // Keep reals recognizable as such: `1.0`, not `1`.
+    Ok(if text.contains('.') {
+        text
+    } else {
+        format!("{text}.0")
+    })
+}
+
+/// JSON object keys are strings; scalar keys are spelled out.
+fn json_key(key: &Yaml) -> Result<String> {
+    Ok(match key {
+        Yaml::String(value) | Yaml::Real(value) => value.clone(),
+        Yaml::Integer(value) => value.to_string(),
+        Yaml::Boolean(value) => value.to_string(),
+        Yaml::Null => "null".to_string(),
+        _ => bail!("Mapping with a collection key has no JSON form"),
+    })
+}
+
+pub fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use yaml_rust2::YamlLoader;
+
+    fn load(src: &str) -> Yaml {
+        YamlLoader::load_from_str(src).unwrap().remove(0)
+    }
+
+    fn path(segments: &[&str]) -> NodePath {
+        NodePath(
+            segments
+                .iter()
+                .map(|seg| match seg.parse() {
+                    Ok(index) => PathSegment::Index(index),
+                    Err(_) => PathSegment::Key(seg.to_string()),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn pointers_escape_tilde_and_slash() {
+        assert_eq!(
+            pointer(&path(&["spec", "containers", "0", "image"])),
+            "/spec/containers/0/image"
+        );
+        assert_eq!(pointer(&path(&["a/b", "m~n", "~1"])), "/a~1b/m~0n/~01");
+        assert_eq!(pointer(&path(&["", "x"])), "//x");
+        assert_eq!(pointer(&NodePath(Vec::new())), "");
+    }
+
+    #[test]
+    fn values_are_json_encoded() {
+        let doc = load(
+            "s: \"say \\\"hi\\\"\\n\\ttab\\u0001\"\nn: 80\nr: 1e3\nb: true\nz: null\n\
+             list: [1, two]\nmap: {1: a, true: b}\n",
+        );
+        assert_eq!(
+            json_value(&doc["s"]).unwrap(),
+            "\"say \\\"hi\\\"\\n\\ttab\\u0001\""
+        );
+        assert_eq!(json_value(&doc["n"]).unwrap(), "80");
+        assert_eq!(json_value(&doc["r"]).unwrap(), "1000.0");
+        assert_eq!(json_value(&doc["b"]).unwrap(), "true");
+        assert_eq!(json_value(&doc["z"]).unwrap(), "null");
+        assert_eq!(json_value(&doc["list"]).unwrap(), "[1,\"two\"]");
+        assert_eq!(
+            json_value(&doc["map"]).unwrap(),
+            "{\"1\":\"a\",\"true\":\"b\"}"
+        );
+        assert!(json_value(&load("x: .inf")["x"]).is_err());
+        assert!(json_value(&load("? [a]\n: 1\n")).is_err());
+    }
+
+    #[test]
+    fn each_op_kind() {
+        let doc = load("spec:\n  image: nginx:1.25\n  ports: [80]\n");
+        let image = path(&["spec", "image"]);
+        assert_eq!(
+            patch_op(PatchOp::Replace, &image, Some(&doc["spec"]["image"])).unwrap(),
+            "{\"op\":\"replace\",\"path\":\"/spec/image\",\"value\":\"nginx:1.25\"}"
+        );
+        assert_eq!(
+            patch_op(
+                PatchOp::Add,
+                &path(&["spec", "ports"]),
+                Some(&doc["spec"]["ports"])
+            )
+            .unwrap(),
+            "{\"op\":\"add\",\"path\":\"/spec/ports\",\"value\":[80]}"
+        );
+        assert_eq!(
+            patch_op(PatchOp::Remove, &path(&["spec", "a/b"]), None).unwrap(),
+            "{\"op\":\"remove\",\"path\":\"/spec/a~1b\"}"
+        );
+        assert!(patch_op(PatchOp::Add, &image, None).is_err());
+    }
+
+    #[test]
+    fn pretty_json_indents_and_stringifies_keys() {
+        let doc = load("name: web\nports: [80, 443]\nempty: {}\nnone: []\n1: one\n? [a, b]\n: pair\nratio: 0.5\nz: ~\n");
+        assert_eq!(
+            json_pretty(&doc, &NodePath(Vec::new())).unwrap(),
+            "{\n  \"name\": \"web\",\n  \"ports\": [\n    80,\n    443\n  ],\n  \"empty\": {},\n  \"none\": [],\n  \
+             \"1\": \"one\",\n  \"[\\\"a\\\",\\\"b\\\"]\": \"pair\",\n  \"ratio\": 0.5,\n  \"z\": null\n}"
+        );
+        assert_eq!(json_pretty(&load("7"), &NodePath(Vec::new())).unwrap(), "7");
+    }
+
+    #[test]
+    fn pretty_json_applies_merge_keys() {
+        let doc = load(
+            "base: &base\n  host: a\n  port: 1\nextra: &extra\n  port: 2\n  tls: true\n\
+             dev:\n  <<: [*base, *extra]\n  host: b\n",
+        );
+        assert_eq!(
+            json_pretty(&doc["dev"], &path(&["dev"])).unwrap(),
+            "{\n  \"host\": \"b\",\n  \"port\": 1,\n  \"tls\": true\n}"
+        );
+        assert_eq!(
+            json_pretty(&load("a:\n  <<: {}\n"), &path(&[])).unwrap(),
+            "{\n  \"a\": {}\n}"
+        );
+    }
+
+    #[test]
+    fn pretty_json_names_the_value_it_cannot_encode() {
+        let doc = load("spec:\n  sizes: [1, .nan]\n  count: !!int many\n");
+        assert_eq!(
+            json_pretty(&doc, &NodePath(Vec::new()))
+                .unwrap_err()
+                .to_string(),
+            "spec.sizes.1 has no JSON form"
+        );
+        assert_eq!(
+            json_pretty(&doc["spec"]["count"], &path(&["spec", "count"]))
+                .unwrap_err()
+                .to_string(),
+            "spec.count has no JSON form"
+        );
+    }
+}
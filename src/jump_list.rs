@@ -0,0 +1,114 @@
+// Where the selection was before each long move (search, `n`/`N`, `gg`/`G`, go to path, marks,
+// far mouse clicks), walked back with `Ctrl+o` and forward again with `Ctrl+i`.
+
+use crate::yaml_model::NodePath;
+
+/// Entries kept; the oldest are forgotten first.
+const MAX_JUMPS: usize = 100;
+
+/// Jump positions, oldest first, and which one `Ctrl+o`/`Ctrl+i` is visiting.
+#[derive(Clone, Debug, Default)]
+pub struct JumpList {
+    entries: Vec<NodePath>,
+    /// Entry being visited; `entries.len()` when not walking the list.
+    position: usize,
+}
+
+impl JumpList {
+    /// Remember `from` before a long move. Entries walked back over are forgotten, and an
+    /// earlier entry for the same node moves to the end.
+    pub fn push(&mut self, from: NodePath) {
+        self.entries.truncate(self.position + 1);
+        self.entries.retain(|path| *path != from);
+        self.entries.push(from);
+        if self.entries.len() > MAX_JUMPS {
+            self.entries.remove(0);
+        }
+        self.position = self.entries.len();
+    }
+
+    /// Step back from `current` to the newest older entry that still `exists`, dropping the
+    /// ones that do not. The first step also remembers `current`, so `forward` can return to it.
+    pub fn back(
+        &mut self,
+        current: &NodePath,
+        exists: impl Fn(&NodePath) -> bool,
+    ) -> Option<NodePath> {
+        if self.position >= self.entries.len() {
+            if self.entries.last() != Some(current) {
+                self.entries.push(current.clone());
+            }
+            self.position = self.entries.len() - 1;
+        }
+        while self.position > 0 {
+            self.position -= 1;
+            if exists(&self.entries[self.position]) {
+                return Some(self.entries[self.position].clone());
+            }
+            self.entries.remove(self.position);
+        }
+        None
+    }
+
+    /// Step forward to the next newer entry that still `exists`, dropping the ones that do not.
+    pub fn forward(&mut self, exists: impl Fn(&NodePath) -> bool) -> Option<NodePath> {
+        while self.position + 1 < self.entries.len() {
+            self.position += 1;
+            if exists(&self.entries[self.position]) {
+                return Some(self.entries[self.position].clone());
+            }
+            self.entries.remove(self.position);
+            self.position -= 1;
+        }
+        None
+    }
+
+    /// `(n, total)` for the entry being visited, 1-based.
+    pub fn label(&self) -> (usize, usize) {
+        (
+            self.position.min(self.entries.len().saturating_sub(1)) + 1,
+            self.entries.len(),
+        )
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn path(dot: &str) -> NodePath {
+        NodePath(Vec::new()).child_key(dot)
+    }
+
+    #[test]
+    fn walks_back_and_forward_skipping_removed_nodes() {
+        let mut jumps = JumpList::default();
+        for dot in ["a", "b", "gone", "c"] {
+            jumps.push(path(dot));
+        }
+        let exists = |p: &NodePath| *p != path("gone");
+        assert_eq!(jumps.back(&path("d"), exists), Some(path("c")));
+        assert_eq!(jumps.label(), (4, 5));
+        assert_eq!(jumps.back(&path("c"), exists), Some(path("b")));
+        assert_eq!(jumps.label(), (2, 4));
+        assert_eq!(jumps.back(&path("b"), exists), Some(path("a")));
+        assert_eq!(jumps.back(&path("a"), exists), None);
+        assert_eq!(jumps.forward(exists), Some(path("b")));
+        assert_eq!(jumps.forward(exists), Some(path("c")));
+        assert_eq!(jumps.forward(exists), Some(path("d")));
+        assert_eq!(jumps.forward(exists), None);
+
+        // A new jump from the middle forgets what was ahead.
+        jumps.back(&path("d"), exists);
+        jumps.back(&path("c"), exists);
+        jumps.push(path("b"));
+        assert_eq!(jumps.label(), (2, 2));
+        assert_eq!(jumps.back(&path("e"), exists), Some(path("b")));
+        assert_eq!(jumps.back(&path("b"), exists), Some(path("a")));
+    }
+}
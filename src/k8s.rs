@@ -0,0 +1,468 @@
+// Kubernetes manifests: the kind and apiVersion of the document, and a small table of the
+// fields a handful of core kinds expect, used to flag misplaced keys and suggest new ones.
+
+use std::sync::OnceLock;
+
+use yaml_rust2::{Yaml, YamlLoader};
+
+use crate::yaml_model::{NodePath, PathSegment};
+
+/// Expected keys per kind. A mapping lists every key allowed there; `~` allows anything below;
+/// a one-item sequence describes each item. Shared parts are written once as anchors.
+const KINDS: &str = r#"
+Pod:
+  apiVersion:
+  kind:
+  metadata: &metadata
+    name:
+    generateName:
+    namespace:
+    labels:
+    annotations:
+    uid:
+    resourceVersion:
+    generation:
+    creationTimestamp:
+    deletionTimestamp:
+    deletionGracePeriodSeconds:
+    ownerReferences:
+    finalizers:
+    managedFields:
+  spec: &podspec
+    containers: &containers
+      - name:
+        image:
+        imagePullPolicy:
+        command:
+        args:
+        workingDir:
+        ports:
+          - name:
+            containerPort:
+            hostPort:
+            hostIP:
+            protocol:
+        env:
+          - name:
+            value:
+            valueFrom:
+        envFrom:
+        resources:
+          limits:
+          requests:
+          claims:
+        volumeMounts:
+          - name:
+            mountPath:
+            subPath:
+            subPathExpr:
+            readOnly:
+            mountPropagation:
+            recursiveReadOnly:
+        volumeDevices:
+        livenessProbe:
+        readinessProbe:
+        startupProbe:
+        lifecycle:
+        terminationMessagePath:
+        terminationMessagePolicy:
+        securityContext:
+        stdin:
+        stdinOnce:
+        tty:
+        resizePolicy:
+        restartPolicy:
+    initContainers: *containers
+    ephemeralContainers:
+    volumes:
+    restartPolicy:
+    terminationGracePeriodSeconds:
+    activeDeadlineSeconds:
+    dnsPolicy:
+    dnsConfig:
+    nodeSelector:
+    nodeName:
+    serviceAccountName:
+    serviceAccount:
+    automountServiceAccountToken:
+    hostNetwork:
+    hostPID:
+    hostIPC:
+    hostUsers:
+    shareProcessNamespace:
+    securityContext:
+    imagePullSecrets:
+    hostname:
+    subdomain:
+    setHostnameAsFQDN:
+    affinity:
+    schedulerName:
+    tolerations:
+    hostAliases:
+    priorityClassName:
+    priority:
+    preemptionPolicy:
+    runtimeClassName:
+    enableServiceLinks:
+    readinessGates:
+    topologySpreadConstraints:
+    overhead:
+    os:
+    schedulingGates:
+    resourceClaims:
+    resources:
+  status:
+Deployment:
+  apiVersion:
+  kind:
+  metadata: *metadata
+  spec:
+    replicas:
+    selector:
+    template: &template
+      metadata: *metadata
+      spec: *podspec
+    strategy:
+    minReadySeconds:
+    revisionHistoryLimit:
+    progressDeadlineSeconds:
+    paused:
+  status:
+StatefulSet:
+  apiVersion:
+  kind:
+  metadata: *metadata
+  spec:
+    replicas:
+    selector:
+    template: *template
+    serviceName:
+    volumeClaimTemplates:
+    podManagementPolicy:
+    updateStrategy:
+    revisionHistoryLimit:
+    minReadySeconds:
+    persistentVolumeClaimRetentionPolicy:
+    ordinals:
+  status:
+DaemonSet:
+  apiVersion:
+  kind:
+  metadata: *metadata
+  spec:
+    selector:
+    template: *template
+    updateStrategy:
+    minReadySeconds:
+    revisionHistoryLimit:
+  status:
+Job:
+  apiVersion:
+  kind:
+  metadata: *metadata
+  spec: &jobspec
+    template: *template
+    parallelism:
+    completions:
+    completionMode:
+    activeDeadlineSeconds:
+    backoffLimit:
+    backoffLimitPerIndex:
+    maxFailedIndexes:
+    selector:
+    manualSelector:
+    ttlSecondsAfterFinished:
+    suspend:
+    podFailurePolicy:
+    successPolicy:
+    podReplacementPolicy:
+    managedBy:
+  status:
+CronJob:
+  apiVersion:
+  kind:
+  metadata: *metadata
+  spec:
+    schedule:
+    timeZone:
+    startingDeadlineSeconds:
+    concurrencyPolicy:
+    suspend:
+    jobTemplate:
+      metadata: *metadata
+      spec: *jobspec
+    successfulJobsHistoryLimit:
+    failedJobsHistoryLimit:
+  status:
+Service:
+  apiVersion:
+  kind:
+  metadata: *metadata
+  spec:
+    type:
+    selector:
+    ports:
+      - name:
+        protocol:
+        appProtocol:
+        port:
+        targetPort:
+        nodePort:
+    clusterIP:
+    clusterIPs:
+    externalIPs:
+    externalName:
+    externalTrafficPolicy:
+    internalTrafficPolicy:
+    healthCheckNodePort:
+    sessionAffinity:
+    sessionAffinityConfig:
+    loadBalancerIP:
+    loadBalancerSourceRanges:
+    loadBalancerClass:
+    publishNotReadyAddresses:
+    ipFamilies:
+    ipFamilyPolicy:
+    allocateLoadBalancerNodePorts:
+    trafficDistribution:
+  status:
+ConfigMap:
+  apiVersion:
+  kind:
+  metadata: *metadata
+  data:
+  binaryData:
+  immutable:
+Secret:
+  apiVersion:
+  kind:
+  metadata: *metadata
+  type:
+  data:
+  stringData:
+  immutable:
+Ingress:
+  apiVersion:
+  kind:
+  metadata: *metadata
+  spec:
+    ingressClassName:
+    defaultBackend:
+    tls:
+    rules:
+      - host:
+        http:
+  status:
+"#;
+
+fn kinds() -> &'static Yaml {
+    static KIND_TABLE: OnceLock<Yaml> = OnceLock::new();
+    KIND_TABLE.get_or_init(|| {
+        YamlLoader::load_from_str(KINDS)
+            .ok()
+            .and_then(|docs| docs.into_iter().next())
+            .unwrap_or(Yaml::Null)
+    })
+}
+
+/// `Deployment apps/v1` for a document with `kind` and `apiVersion` at its root.
+pub fn label(root: &Yaml) -> Option<String> {
+    let kind = root["kind"].as_str()?;
+    let api_version = root["apiVersion"].as_str()?;
+    Some(format!("{kind} {api_version}"))
+}
+
+/// Expected-key tree for the document's kind, when the document is a manifest of a known kind.
+fn kind_tree(root: &Yaml) -> Option<(&str, &'static Yaml)> {
+    root["apiVersion"].as_str()?;
+    let kind = root["kind"].as_str()?;
+    let tree = &kinds()[kind];
+    (!tree.is_badvalue()).then_some((kind, tree))
+}
+
+/// Entry of `tree` for `path`; `None` once the path leaves what the table describes.
+fn entry<'a>(tree: &'a Yaml, path: &NodePath) -> Option<&'a Yaml> {
+    let mut node = tree;
+    for segment in &path.0 {
+        node = match (segment, node) {
+            (PathSegment::Key(key), Yaml::Hash(hash)) => hash.get(&Yaml::String(key.clone()))?,
+            (PathSegment::Index(_), Yaml::Array(items)) => items.first()?,
+            _ => return None,
+        };
+    }
+    Some(node)
+}
+
+/// Keys the mapping at `path` is expected to have, for a manifest of a known kind.
+pub fn expected_keys(root: &Yaml, path: &NodePath) -> Option<Vec<String>> {
+    let (_, tree) = kind_tree(root)?;
+    let keys = entry(tree, path)?
+        .as_hash()?
+        .keys()
+        .filter_map(Yaml::as_str)
+        .map(String::from);
+    Some(keys.collect())
+}
+
+/// Why the key at `path` looks misplaced: its mapping lists the keys it expects and this is not
+/// one of them. Names where the key is expected instead, if anywhere.
+pub fn misplaced(root: &Yaml, path: &NodePath) -> Option<String> {
+    let Some(PathSegment::Key(key)) = path.0.last() else {
+        return None;
+    };
+    let (kind, tree) = kind_tree(root)?;
+    let parent = NodePath(path.0[..path.0.len() - 1].to_vec());
+    let expected = entry(tree, &parent)?.as_hash()?;
+    if expected.contains_key(&Yaml::String(key.clone())) {
+        return None;
+    }
+    Some(match home_of(tree, key) {
+        Some(home) if home.is_empty() => {
+            format!("{key} is not a {kind} field here; it goes at the top level")
+        }
+        Some(home) => format!("{key} is not a {kind} field here; it goes under {home}"),
+        None => format!("{key} is not a known {kind} field"),
+    })
+}
+
+/// Shallowest place in `tree` expecting `key`, as a dot path with `[]` for sequence items.
+fn home_of(tree: &Yaml, key: &str) -> Option<String> {
+    let mut level = vec![(String::new(), tree)];
+    while !level.is_empty() {
+        let mut next = Vec::new();
+        for (at, node) in level {
+            match node {
+                Yaml::Hash(hash) => {
+                    if hash.contains_key(&Yaml::String(key.to_string())) {
+                        return Some(at);
+                    }
+                    for (child_key, child) in hash {
+                        let name = child_key.as_str().unwrap_or_default();
+                        let child_at = if at.is_empty() {
+                            name.to_string()
+                        } else {
+                            format!("{at}.{name}")
+                        };
+                        next.push((child_at, child));
+                    }
+                }
+                Yaml::Array(items) => {
+                    next.extend(items.first().map(|item| (format!("{at}[]"), item)))
+                }
+                _ => {}
+            }
+        }
+        level = next;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn doc(text: &str) -> Yaml {
+        YamlLoader::load_from_str(text).unwrap().remove(0)
+    }
+
+    fn path(keys: &[&str]) -> NodePath {
+        NodePath(
+            keys.iter()
+                .map(|key| match key.parse() {
+                    Ok(index) => PathSegment::Index(index),
+                    Err(_) => PathSegment::Key(key.to_string()),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn table_parses_with_every_kind() {
+        let kinds: Vec<_> = kinds()
+            .as_hash()
+            .unwrap()
+            .keys()
+            .filter_map(Yaml::as_str)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "Pod",
+                "Deployment",
+                "StatefulSet",
+                "DaemonSet",
+                "Job",
+                "CronJob",
+                "Service",
+                "ConfigMap",
+                "Secret",
+                "Ingress"
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_misplaced_keys_and_names_their_home() {
+        let root = doc("apiVersion: apps/v1\nkind: Deployment\nspec:\n  containers: []\n  template:\n    spec:\n      containers:\n        - name: app\n          imagePull: Always\n    labels: {}\n");
+        assert_eq!(label(&root).as_deref(), Some("Deployment apps/v1"));
+        assert_eq!(
+            misplaced(&root, &path(&["spec", "containers"])).as_deref(),
+            Some("containers is not a Deployment field here; it goes under spec.template.spec")
+        );
+        assert_eq!(
+            misplaced(
+                &root,
+                &path(&["spec", "template", "spec", "containers", "0", "imagePull"])
+            )
+            .as_deref(),
+            Some("imagePull is not a known Deployment field")
+        );
+        assert_eq!(
+            misplaced(&root, &path(&["spec", "template", "labels"])).as_deref(),
+            Some("labels is not a Deployment field here; it goes under metadata")
+        );
+        assert_eq!(
+            misplaced(
+                &root,
+                &path(&["spec", "template", "spec", "containers", "0", "name"])
+            ),
+            None
+        );
+        // Free-form maps such as labels take any key.
+        assert_eq!(
+            misplaced(&root, &path(&["metadata", "labels", "app"])),
+            None
+        );
+
+        let unknown = doc("apiVersion: example.com/v1\nkind: Widget\nspec:\n  anything: 1\n");
+        assert_eq!(label(&unknown).as_deref(), Some("Widget example.com/v1"));
+        assert_eq!(misplaced(&unknown, &path(&["spec", "anything"])), None);
+    }
+
+    #[test]
+    fn expected_keys_follow_items_and_anchors() {
+        let root = doc("apiVersion: batch/v1\nkind: CronJob\n");
+        let keys = expected_keys(
+            &root,
+            &path(&[
+                "spec",
+                "jobTemplate",
+                "spec",
+                "template",
+                "spec",
+                "containers",
+                "0",
+                "ports",
+                "0",
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            keys,
+            ["name", "containerPort", "hostPort", "hostIP", "protocol"]
+        );
+        assert_eq!(expected_keys(&root, &path(&["metadata", "labels"])), None);
+        assert_eq!(expected_keys(&doc("a: 1\n"), &path(&[])), None);
+    }
+}
@@ -0,0 +1,613 @@
+// User key bindings from `~/.config/yed/keys.toml`, consulted before the built-in keys of the
+// tree view.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::input::InputAction;
+use crate::settings;
+
+/// Action names usable in the keymap, and `none`, which unbinds a built-in key.
+const ACTIONS: &[(&str, InputAction)] = &[
+    ("quit", InputAction::Quit),
+    ("save", InputAction::Save),
+    ("save_as", InputAction::SaveAs),
+    ("open_another", InputAction::OpenAnother),
+    ("move_up", InputAction::MoveUp),
+    ("move_down", InputAction::MoveDown),
+    ("jump_top", InputAction::JumpTop),
+    ("jump_bottom", InputAction::JumpBottom),
+    ("screen_top", InputAction::ScreenTop),
+    ("screen_middle", InputAction::ScreenMiddle),
+    ("screen_bottom", InputAction::ScreenBottom),
+    ("page_up", InputAction::PageUp),
+    ("page_down", InputAction::PageDown),
+    ("full_page_up", InputAction::FullPageUp),
+    ("full_page_down", InputAction::FullPageDown),
+    ("jump_left", InputAction::JumpLeft),
+    ("next_sibling", InputAction::NextSibling),
+    ("prev_sibling", InputAction::PrevSibling),
+    ("expand_all", InputAction::ExpandAll),
+    ("collapse_all", InputAction::CollapseAll),
+    ("expand_subtree", InputAction::ExpandSubtree),
+    ("collapse_subtree", InputAction::CollapseSubtree),
+    ("toggle_focus", InputAction::ToggleFocus),
+    ("center_selection", InputAction::CenterSelection),
+    ("selection_to_top", InputAction::SelectionToTop),
+    ("selection_to_bottom", InputAction::SelectionToBottom),
+    ("scroll_left", InputAction::ScrollLeft),
+    ("scroll_right", InputAction::ScrollRight),
+    ("collapse", InputAction::Collapse),
+    ("expand", InputAction::Expand),
+    ("toggle_expand", InputAction::ToggleExpand),
+    ("edit_value", InputAction::EditValue),
+    ("edit_multiline", InputAction::EditMultiline),
+    ("edit_in_editor", InputAction::EditInEditor),
+    ("toggle_bool", InputAction::ToggleBool),
+    ("cast_type", InputAction::CastType),
+    ("toggle_flow_style", InputAction::ToggleFlowStyle),
+    ("sort_children", InputAction::SortChildren),
+    ("toggle_wrap", InputAction::ToggleWrap),
+    ("rekey", InputAction::Rekey),
+    ("rename_key", InputAction::RenameKey),
+    ("rename_key_everywhere", InputAction::RenameKeyEverywhere),
+    ("add_child", InputAction::AddChild),
+    ("add_sibling", InputAction::AddSibling),
+    ("open_line_above", InputAction::OpenLineAbove),
+    ("add_map_to_sequence", InputAction::AddMapToSequence),
+    ("delete_node", InputAction::DeleteNode),
+    ("delete_line", InputAction::DeleteLine),
+    ("indent_line", InputAction::IndentLine(1)),
+    ("dedent_line", InputAction::DedentLine(1)),
+    ("copy_path", InputAction::CopyPath),
+    ("yank_node", InputAction::YankNode),
+    ("paste", InputAction::Paste),
+    ("copy_json", InputAction::CopyJson),
+    ("copy_fenced_yaml", InputAction::CopyFencedYaml),
+    ("copy_markdown_table", InputAction::CopyMarkdownTable),
+    ("copy_pointer", InputAction::CopyPointer),
+    ("copy_patch_op", InputAction::CopyPatchOp),
+    ("export_json", InputAction::ExportJson),
+    ("export_document_json", InputAction::ExportDocumentJson),
+    ("export_toml", InputAction::ExportToml),
+    ("undo", InputAction::Undo),
+    ("show_journal", InputAction::ShowJournal),
+    ("show_duplicates", InputAction::ShowDuplicates),
+    ("show_settings", InputAction::ShowSettings),
+    ("show_stats", InputAction::ShowStats),
+    ("show_findings", InputAction::ShowFindings),
+    ("show_lint", InputAction::ShowLint),
+    ("show_placeholders", InputAction::ShowPlaceholders),
+    ("show_marks", InputAction::ShowMarks),
+    ("toggle_env_preview", InputAction::ToggleEnvPreview),
+    ("toggle_indices", InputAction::ToggleIndices),
+    ("show_buffer_diff", InputAction::ShowBufferDiff),
+    (
+        "toggle_baseline_missing",
+        InputAction::ToggleBaselineMissing,
+    ),
+    ("start_search", InputAction::StartSearch),
+    ("start_search_backward", InputAction::StartSearchBackward),
+    ("search_next", InputAction::SearchNext),
+    ("search_prev", InputAction::SearchPrev),
+    ("goto_path", InputAction::GotoPath),
+    ("jump_back", InputAction::JumpBack),
+    ("jump_forward", InputAction::JumpForward),
+    ("start_replace", InputAction::StartReplace),
+    ("pipe_subtree", InputAction::StartPipe),
+    ("jump_to_parse_error", InputAction::JumpToParseError),
+    ("show_help", InputAction::ShowHelp),
+];
+
+/// A row of the `?` overlay: the action's name in [`ACTIONS`] (empty for key sequences that
+/// cannot be rebound), its built-in keys in `keys.toml` syntax and what it does.
+type HelpRow = (&'static str, &'static [&'static str], &'static str);
+
+/// Sections of the `?` overlay.
+const HELP: &[(&str, &[HelpRow])] = &[
+    (
+        "Navigation",
+        &[
+            ("move_down", &["j", "down"], "Move down"),
+            ("move_up", &["k", "up"], "Move up"),
+            (
+                "next_sibling",
+                &["}"],
+                "Next key or item of the same parent",
+            ),
+            (
+                "prev_sibling",
+                &["{"],
+                "Previous key or item of the same parent",
+            ),
+            (
+                "",
+                &["<count><motion>"],
+                "Repeat j/k, ctrl+d/u or }/{, as in 5j",
+            ),
+            ("jump_top", &["gg"], "Jump to top"),
+            ("jump_bottom", &["G"], "Jump to bottom"),
+            ("screen_top", &["H"], "Top row on screen"),
+            ("screen_middle", &["M"], "Middle row on screen"),
+            ("screen_bottom", &["L"], "Bottom row on screen"),
+            ("page_up", &["ctrl+u"], "Page up"),
+            ("page_down", &["ctrl+d"], "Page down"),
+            ("full_page_up", &["ctrl+b", "pageup"], "A whole screen up"),
+            (
+                "full_page_down",
+                &["ctrl+f", "pagedown"],
+                "A whole screen down",
+            ),
+            ("goto_path", &["gp"], "Go to a dot path (Tab completes)"),
+            (
+                "jump_back",
+                &["ctrl+o"],
+                "Back to where the last jump started",
+            ),
+            (
+                "jump_forward",
+                &["tab", "ctrl+i"],
+                "Forward again along the jumps",
+            ),
+            ("", &["m<letter>"], "Mark the selected node"),
+            ("", &["'<letter>"], "Jump to a mark"),
+            ("show_marks", &["'?"], "List the marks"),
+            (
+                "jump_to_parse_error",
+                &["!"],
+                "Jump to the parse error (raw view)",
+            ),
+        ],
+    ),
+    (
+        "View",
+        &[
+            ("collapse", &["h", "left"], "Collapse node"),
+            ("expand", &["l", "right"], "Expand node"),
+            ("toggle_expand", &["enter"], "Toggle expand/collapse"),
+            ("expand_all", &["zR"], "Expand everything"),
+            ("collapse_all", &["zM"], "Collapse everything"),
+            (
+                "expand_subtree",
+                &["zO"],
+                "Expand the node and everything below it",
+            ),
+            (
+                "collapse_subtree",
+                &["zC"],
+                "Collapse the node and everything below it",
+            ),
+            ("", &["z1..z9"], "Show that many levels"),
+            (
+                "toggle_focus",
+                &["zf"],
+                "Fold all but the selected subtree, or unfold",
+            ),
+            (
+                "center_selection",
+                &["zz"],
+                "Scroll the selection to the middle of the screen",
+            ),
+            (
+                "selection_to_top",
+                &["zt"],
+                "Scroll the selection to the top of the screen",
+            ),
+            (
+                "selection_to_bottom",
+                &["zb"],
+                "Scroll the selection to the bottom of the screen",
+            ),
+            ("scroll_left", &["zh", "shift+left"], "Scroll the rows left"),
+            (
+                "scroll_right",
+                &["zl", "shift+right"],
+                "Scroll the rows right",
+            ),
+            ("jump_left", &["0"], "Scroll back to the start of the rows"),
+            ("toggle_indices", &["#"], "Show or hide list item indices"),
+            (
+                "toggle_env_preview",
+                &["$"],
+                "Preview ${VAR} values resolved",
+            ),
+            (
+                "toggle_baseline_missing",
+                &["b"],
+                "Show keys missing vs the baseline",
+            ),
+        ],
+    ),
+    (
+        "Editing",
+        &[
+            ("edit_value", &["e"], "Edit value"),
+            (
+                "edit_multiline",
+                &["E"],
+                "Edit a string across several lines",
+            ),
+            ("edit_in_editor", &["ctrl+e"], "Edit the value in $EDITOR"),
+            ("toggle_bool", &["t", "space"], "Toggle a boolean"),
+            ("cast_type", &["c"], "Cast a scalar to the next type"),
+            (
+                "toggle_flow_style",
+                &["f"],
+                "Switch between flow and block style",
+            ),
+            ("sort_children", &["s"], "Sort keys or items"),
+            ("toggle_wrap", &["w"], "Wrap a scalar in a list, or unwrap"),
+            ("rekey", &["K"], "List of maps ⇄ map keyed by a field"),
+            ("rename_key", &["r"], "Rename key"),
+            ("rename_key_everywhere", &["R"], "Rename the key everywhere"),
+            ("add_child", &["a"], "Add child"),
+            (
+                "add_sibling",
+                &["o"],
+                "Add sibling (raw view: open line below)",
+            ),
+            ("open_line_above", &["O"], "Open line above (raw view)"),
+            ("add_map_to_sequence", &["A"], "Add an object to a list"),
+            ("delete_node", &["d"], "Delete node"),
+            ("delete_line", &["shift+delete"], "Delete line (raw view)"),
+            ("indent_line", &[">"], "Indent line (raw view)"),
+            ("dedent_line", &["<"], "Dedent line (raw view)"),
+            ("paste", &["p"], "Paste the yanked node"),
+            ("undo", &["u"], "Undo"),
+            ("pipe_subtree", &["|"], "Pipe the subtree through a command"),
+        ],
+    ),
+    (
+        "Copy and export",
+        &[
+            ("copy_path", &["y"], "Copy the dot path"),
+            ("yank_node", &["yy"], "Yank the node"),
+            ("copy_fenced_yaml", &["Y"], "Copy as a fenced yaml block"),
+            (
+                "copy_markdown_table",
+                &["T"],
+                "Copy a list of maps as a Markdown table",
+            ),
+            ("copy_pointer", &["J"], "Copy as a JSON Pointer"),
+            ("copy_patch_op", &["P"], "Copy a JSON Patch op"),
+            ("copy_json", &["ctrl+x"], "Copy as JSON"),
+            ("export_json", &["x"], "Export the subtree as JSON"),
+            (
+                "export_document_json",
+                &["X"],
+                "Export the document as JSON",
+            ),
+            ("export_toml", &["ctrl+t"], "Export the document as TOML"),
+        ],
+    ),
+    (
+        "Search and replace",
+        &[
+            ("start_search", &["/"], "Search (k:, v:, re:, fz: prefixes)"),
+            (
+                "start_search_backward",
+                &["?"],
+                "Search upwards: Enter picks the last match, n/N swap",
+            ),
+            ("search_next", &["n"], "Next match (previous after ?)"),
+            ("search_prev", &["N"], "Previous match (next after ?)"),
+            (
+                "start_replace",
+                &[":"],
+                "Replace: %s/old/new/ (flags c, t, k)",
+            ),
+        ],
+    ),
+    (
+        "Panels",
+        &[
+            ("show_journal", &["U"], "Edit journal"),
+            ("show_buffer_diff", &["="], "Diff against the file on disk"),
+            ("show_duplicates", &["D"], "Duplicate keys"),
+            ("show_lint", &["I"], "Lint findings"),
+            ("show_findings", &["F"], "Post-save hook findings"),
+            ("show_placeholders", &["V"], "${VAR} placeholders"),
+            ("show_settings", &["S"], "Effective settings"),
+            ("show_stats", &["ctrl+g"], "Document stats"),
+            ("show_help", &["f1"], "This help"),
+        ],
+    ),
+    (
+        "Files",
+        &[
+            ("save", &["ctrl+s"], "Save"),
+            ("save_as", &["W", "ctrl+shift+s"], "Save as"),
+            (
+                "open_another",
+                &["ctrl+p"],
+                "Open another file (Ctrl+o before it became jump back)",
+            ),
+            ("quit", &["q"], "Quit"),
+        ],
+    ),
+    (
+        "Prompts",
+        &[
+            ("", &["enter"], "Confirm"),
+            ("", &["esc"], "Cancel"),
+            ("", &["tab"], "Complete a key or path"),
+            ("", &["up", "down"], "Search history, suggestions"),
+        ],
+    ),
+];
+
+/// Key combos mapped to an action, or to `None` when the built-in binding is switched off.
+#[derive(Clone, Debug, Default)]
+pub struct Keymap {
+    bindings: HashMap<(KeyCode, KeyModifiers), Option<(&'static str, InputAction)>>,
+}
+
+impl Keymap {
+    /// `Some(binding)` when the user bound this key; the binding is `None` if it was unbound.
+    pub fn lookup(&self, key: KeyEvent) -> Option<Option<InputAction>> {
+        let binding = self.bindings.get(&normalize(key.code, key.modifiers))?;
+        Some(binding.as_ref().map(|(_, action)| action.clone()))
+    }
+
+    /// Sections of the help overlay with each action's keys as bound now: built-in keys the
+    /// user rebound or unbound are left out, keys the user bound are added.
+    pub fn help(&self) -> Vec<(&'static str, Vec<(String, &'static str)>)> {
+        HELP.iter()
+            .map(|(section, rows)| {
+                let rows = rows
+                    .iter()
+                    .map(|(action, builtin, about)| {
+                        let mut keys: Vec<String> = builtin
+                            .iter()
+                            .filter(|combo| {
+                                parse_key(combo)
+                                    .map_or(true, |key| !self.bindings.contains_key(&key))
+                            })
+                            .map(|combo| combo.to_string())
+                            .collect();
+                        let mut bound: Vec<String> = self
+                            .bindings
+                            .iter()
+                            .filter(|(_, binding)| {
+                                !action.is_empty()
+                                    && binding.as_ref().is_some_and(|(name, _)| name == action)
+                            })
+                            .map(|(key, _)| key_label(*key))
+                            .collect();
+                        bound.sort();
+                        keys.extend(bound);
+                        (keys.join(" "), *about)
+                    })
+                    .collect();
+                (*section, rows)
+            })
+            .collect()
+    }
+}
+
+/// `keys.toml` next to the user config file.
+pub fn path() -> Option<PathBuf> {
+    Some(settings::user_config_path()?.with_file_name("keys.toml"))
+}
+
+/// The keymap in `path`, plus a warning for each entry that was skipped. A missing file is an
+/// empty keymap.
+pub fn load(path: &Path) -> (Keymap, Vec<String>) {
+    match fs::read_to_string(path) {
+        Ok(text) => {
+            let (keymap, warnings) = parse(&text);
+            let warnings = warnings
+                .into_iter()
+                .map(|warning| format!("{}: {warning}", path.display()))
+                .collect();
+            (keymap, warnings)
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => (Keymap::default(), Vec::new()),
+        Err(err) => (
+            Keymap::default(),
+            vec![format!("{}: {err}", path.display())],
+        ),
+    }
+}
+
+/// `"ctrl+e" = "edit_value"` lines; invalid entries are skipped with a warning.
+pub fn parse(text: &str) -> (Keymap, Vec<String>) {
+    let mut keymap = Keymap::default();
+    let mut warnings = Vec::new();
+    let table: toml::Table = match toml::from_str(text) {
+        Ok(table) => table,
+        Err(err) => return (keymap, vec![err.message().to_string()]),
+    };
+    for (combo, value) in table {
+        let binding = value
+            .as_str()
+            .ok_or_else(|| anyhow!("'{combo}' must name an action"))
+            .and_then(action_by_name);
+        match (parse_key(&combo), binding) {
+            (Ok(key), Ok(binding)) => {
+                keymap.bindings.insert(key, binding);
+            }
+            (Err(err), _) | (_, Err(err)) => warnings.push(format!("{err}; ignored")),
+        }
+    }
+    (keymap, warnings)
+}
+
+fn action_by_name(name: &str) -> Result<Option<(&'static str, InputAction)>> {
+    if name == "none" {
+        return Ok(None);
+    }
+    ACTIONS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(known, action)| Some((*known, action.clone())))
+        .ok_or_else(|| anyhow!("unknown action '{name}'"))
+}
+
+/// `ctrl+shift+s`, `alt+x`, `G`, `pagedown`, `f5`: modifiers, then one character or key name.
+fn parse_key(combo: &str) -> Result<(KeyCode, KeyModifiers)> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut parts: Vec<&str> = combo.split('+').collect();
+    // `+` itself, alone or after modifiers (`ctrl++`), leaves two empty parts.
+    if combo.ends_with("++") || combo == "+" {
+        parts.truncate(parts.len() - 2);
+        parts.push("+");
+    }
+    let Some((key, mods)) = parts.split_last() else {
+        bail!("empty key '{combo}'");
+    };
+    for modifier in mods {
+        modifiers |= match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" | "meta" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => bail!("unknown modifier '{modifier}' in '{combo}'"),
+        };
+    }
+    let mut chars = key.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(ch), None) => KeyCode::Char(ch),
+        _ => match key.to_ascii_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n @ 1..=12) => KeyCode::F(n),
+                _ => bail!("unknown key '{key}' in '{combo}'"),
+            },
+        },
+    };
+    Ok(normalize(code, modifiers))
+}
+
+/// `ctrl+e`, `G`, `pagedown`: a key as [`parse_key`] reads it.
+fn key_label((code, modifiers): (KeyCode, KeyModifiers)) -> String {
+    let mut label = String::new();
+    for (modifier, name) in [
+        (KeyModifiers::CONTROL, "ctrl+"),
+        (KeyModifiers::ALT, "alt+"),
+        (KeyModifiers::SHIFT, "shift+"),
+    ] {
+        if modifiers.contains(modifier) {
+            label.push_str(name);
+        }
+    }
+    match code {
+        KeyCode::Char(' ') => label.push_str("space"),
+        KeyCode::Char(ch) => label.push(ch),
+        KeyCode::F(n) => label.push_str(&format!("f{n}")),
+        other => label.push_str(&format!("{other:?}").to_ascii_lowercase()),
+    }
+    label
+}
+
+/// Terminals differ in whether a typed `G` also reports Shift; the character already says so.
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
+    match code {
+        KeyCode::Char(_) => (code, modifiers - KeyModifiers::SHIFT),
+        _ => (code, modifiers),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_combos_and_names() {
+        assert_eq!(
+            parse_key("ctrl+e").unwrap(),
+            (KeyCode::Char('e'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            parse_key("G").unwrap(),
+            (KeyCode::Char('G'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            parse_key("shift+G").unwrap(),
+            (KeyCode::Char('G'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            parse_key("Alt+PageDown").unwrap(),
+            (KeyCode::PageDown, KeyModifiers::ALT)
+        );
+        assert_eq!(
+            parse_key("f5").unwrap(),
+            (KeyCode::F(5), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            parse_key("ctrl++").unwrap(),
+            (KeyCode::Char('+'), KeyModifiers::CONTROL)
+        );
+        assert!(parse_key("hyper+x").is_err());
+        assert!(parse_key("f13").is_err());
+    }
+
+    #[test]
+    fn invalid_entries_warn_and_the_rest_load() {
+        let (keymap, warnings) = parse("\"ctrl+e\" = \"edit_value\"\ndown = \"none\"\nx = \"explode\"\n\"bogus+q\" = \"quit\"\nz = 3\n");
+        assert_eq!(
+            warnings,
+            [
+                "unknown action 'explode'; ignored",
+                "unknown modifier 'bogus' in 'bogus+q'; ignored",
+                "'z' must name an action; ignored",
+            ]
+        );
+        let key = |code, modifiers| keymap.lookup(KeyEvent::new(code, modifiers));
+        assert!(matches!(
+            key(KeyCode::Char('e'), KeyModifiers::CONTROL),
+            Some(Some(InputAction::EditValue))
+        ));
+        assert!(matches!(key(KeyCode::Down, KeyModifiers::NONE), Some(None)));
+        assert!(key(KeyCode::Char('x'), KeyModifiers::NONE).is_none());
+
+        let (keymap, warnings) = parse("not toml [");
+        assert!(keymap.bindings.is_empty());
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn help_follows_the_users_bindings() {
+        for (_, rows) in HELP {
+            for (action, _, _) in rows.iter() {
+                assert!(
+                    action.is_empty() || action_by_name(action).is_ok(),
+                    "{action}"
+                );
+            }
+        }
+        let (keymap, _) = parse("\"ctrl+e\" = \"edit_value\"\nj = \"quit\"\nf6 = \"quit\"\n");
+        let help = keymap.help();
+        let row = |about: &str| {
+            help.iter()
+                .flat_map(|(_, rows)| rows)
+                .find(|(_, text)| *text == about)
+                .unwrap()
+                .0
+                .clone()
+        };
+        assert_eq!(row("Move down"), "down");
+        assert_eq!(row("Edit value"), "e ctrl+e");
+        assert_eq!(row("Edit the value in $EDITOR"), "");
+        assert_eq!(row("Quit"), "q f6 j");
+        assert_eq!(row("Jump to top"), "gg");
+    }
+}
@@ -0,0 +1,214 @@
+// Unified line diff of two texts, for reviewing the buffer against the file on disk.
+
+/// One line of a unified diff.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffLine {
+    /// `@@ -start,count +start,count @@`
+    Hunk(String),
+    Context(String),
+    Added(String),
+    Removed(String),
+}
+
+impl DiffLine {
+    /// The line as it reads in a unified diff.
+    pub fn text(&self) -> String {
+        match self {
+            DiffLine::Hunk(header) => header.clone(),
+            DiffLine::Context(line) => format!(" {line}"),
+            DiffLine::Added(line) => format!("+{line}"),
+            DiffLine::Removed(line) => format!("-{line}"),
+        }
+    }
+}
+
+/// Above this many cells in the comparison table, the differing middle of the two texts is
+/// shown as removed and re-added instead of being aligned line by line.
+const MAX_TABLE_CELLS: usize = 4_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Same,
+    Removed,
+    Added,
+}
+
+/// Diff `old` against `new` line by line, keeping `context` unchanged lines around each change.
+/// Empty when the texts have the same lines.
+pub fn unified(old: &str, new: &str, context: usize) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let ops = line_ops(&old, &new);
+
+    // Old and new line index before each op.
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut at_old, mut at_new) = (0, 0);
+    for op in &ops {
+        positions.push((at_old, at_new));
+        match op {
+            Op::Same => {
+                at_old += 1;
+                at_new += 1;
+            }
+            Op::Removed => at_old += 1,
+            Op::Added => at_new += 1,
+        }
+    }
+    positions.push((at_old, at_new));
+
+    let mut out = Vec::new();
+    let mut idx = 0;
+    while let Some(first) = (idx..ops.len()).find(|&i| ops[i] != Op::Same) {
+        let start = first.saturating_sub(context).max(idx);
+        // Extend the hunk while the next change is close enough for the contexts to touch.
+        let mut end = first;
+        loop {
+            while end < ops.len() && ops[end] != Op::Same {
+                end += 1;
+            }
+            let next = (end..ops.len()).find(|&i| ops[i] != Op::Same);
+            match next {
+                Some(next) if next - end <= 2 * context => end = next,
+                _ => break,
+            }
+        }
+        let end = (end + context).min(ops.len());
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        out.push(DiffLine::Hunk(format!(
+            "@@ -{} +{} @@",
+            hunk_range(old_start, old_end - old_start),
+            hunk_range(new_start, new_end - new_start)
+        )));
+        let (mut o, mut n) = (old_start, new_start);
+        for op in &ops[start..end] {
+            match op {
+                Op::Same => {
+                    out.push(DiffLine::Context(old[o].to_string()));
+                    o += 1;
+                    n += 1;
+                }
+                Op::Removed => {
+                    out.push(DiffLine::Removed(old[o].to_string()));
+                    o += 1;
+                }
+                Op::Added => {
+                    out.push(DiffLine::Added(new[n].to_string()));
+                    n += 1;
+                }
+            }
+        }
+        idx = end;
+    }
+    out
+}
+
+/// `start,count` with a 1-based start, as in `diff -u` (an empty range names the line before).
+fn hunk_range(start: usize, count: usize) -> String {
+    if count == 0 {
+        format!("{start},0")
+    } else {
+        format!("{},{count}", start + 1)
+    }
+}
+
+/// Edit script turning `old` into `new`: the common prefix and suffix, and a longest common
+/// subsequence of what lies between.
+fn line_ops(old: &[&str], new: &[&str]) -> Vec<Op> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ops = vec![Op::Same; prefix];
+    if old_mid.len().saturating_mul(new_mid.len()) > MAX_TABLE_CELLS {
+        ops.extend(std::iter::repeat(Op::Removed).take(old_mid.len()));
+        ops.extend(std::iter::repeat(Op::Added).take(new_mid.len()));
+    } else {
+        // lcs[i][j]: length of the longest common subsequence of old_mid[i..] and new_mid[j..].
+        let width = new_mid.len() + 1;
+        let mut lcs = vec![0u32; (old_mid.len() + 1) * width];
+        for i in (0..old_mid.len()).rev() {
+            for j in (0..new_mid.len()).rev() {
+                lcs[i * width + j] = if old_mid[i] == new_mid[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old_mid.len() || j < new_mid.len() {
+            if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+                ops.push(Op::Same);
+                i += 1;
+                j += 1;
+            } else if j == new_mid.len()
+                || (i < old_mid.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                ops.push(Op::Removed);
+                i += 1;
+            } else {
+                ops.push(Op::Added);
+                j += 1;
+            }
+        }
+    }
+    ops.extend(std::iter::repeat(Op::Same).take(suffix));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn render(old: &str, new: &str, context: usize) -> Vec<String> {
+        unified(old, new, context)
+            .iter()
+            .map(DiffLine::text)
+            .collect()
+    }
+
+    #[test]
+    fn hunks_carry_context_and_ranges() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\n";
+        assert_eq!(
+            render(old, new, 1),
+            vec![
+                "@@ -1,3 +1,3 @@",
+                " a",
+                "-b",
+                "+B",
+                " c",
+                "@@ -8,1 +8,2 @@",
+                " h",
+                "+i"
+            ]
+        );
+        // With more context the two changes share one hunk.
+        assert_eq!(render(old, new, 3).len(), 11);
+        assert_eq!(render(old, new, 3)[0], "@@ -1,8 +1,9 @@");
+    }
+
+    #[test]
+    fn identical_and_empty_texts() {
+        assert!(unified("a\nb\n", "a\nb", 3).is_empty());
+        assert_eq!(render("", "x\n", 3), vec!["@@ -0,0 +1,1 @@", "+x"]);
+        assert_eq!(render("x\n", "", 3), vec!["@@ -1,1 +0,0 @@", "-x"]);
+    }
+
+    #[test]
+    fn aligns_moved_lines() {
+        assert_eq!(
+            render("a\nb\nc\n", "b\nc\na\n", 0),
+            vec!["@@ -1,1 +0,0 @@", "-a", "@@ -3,0 +3,1 @@", "+a"]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+// Textual hygiene checks on the file as written (tabs, trailing whitespace, duplicate keys,
+// long lines, control characters), as opposed to schema validation of its values.
+
+use crate::source_map::SourceMap;
+use crate::width;
+use crate::yaml_model::NodePath;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// Tab in a line's indentation, which YAML forbids.
+    Tab,
+    TrailingSpace,
+    DuplicateKey,
+    LongLine,
+    /// Control character other than tab, e.g. a stray escape or NUL.
+    ControlChar,
+}
+
+impl Rule {
+    pub fn label(self) -> &'static str {
+        match self {
+            Rule::Tab => "tab-indent",
+            Rule::TrailingSpace => "trailing-space",
+            Rule::DuplicateKey => "duplicate-key",
+            Rule::LongLine => "line-length",
+            Rule::ControlChar => "control-char",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LintFinding {
+    /// 1-based line.
+    pub line: usize,
+    /// 1-based column, in characters.
+    pub column: usize,
+    pub rule: Rule,
+    pub message: String,
+    /// Node starting on or before `line`, if the text parses that far.
+    pub path: Option<NodePath>,
+}
+
+impl LintFinding {
+    /// `line:col  rule  message  (path)`, for the lint overlay.
+    pub fn summary(&self) -> String {
+        let mut text = format!(
+            "{}:{}  {}  {}",
+            self.line,
+            self.column,
+            self.rule.label(),
+            self.message
+        );
+        if let Some(path) = self.path.as_ref().filter(|path| !path.0.is_empty()) {
+            text.push_str(&format!("  ({})", path.dot_path()));
+        }
+        text
+    }
+}
+
+/// Findings for `text` in line order; `source` is its scan, used to name duplicate keys and the
+/// node each line belongs to.
+pub fn lint(text: &str, source: &SourceMap, max_line_length: usize) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+    let mut push = |line: usize, column: usize, rule: Rule, message: String| {
+        findings.push(LintFinding {
+            line,
+            column,
+            rule,
+            message,
+            path: source.path_at_line(line).cloned(),
+        });
+    };
+    for (idx, raw) in text.lines().enumerate() {
+        let line = idx + 1;
+        let content = raw.strip_suffix('\r').unwrap_or(raw);
+        let indent = content.len() - content.trim_start_matches([' ', '\t']).len();
+        if let Some(offset) = content[..indent].find('\t') {
+            push(
+                line,
+                offset + 1,
+                Rule::Tab,
+                "tab in indentation".to_string(),
+            );
+        }
+        let trimmed = content.trim_end_matches([' ', '\t']);
+        if trimmed.len() < content.len() {
+            let column = trimmed.chars().count() + 1;
+            push(
+                line,
+                column,
+                Rule::TrailingSpace,
+                "trailing whitespace".to_string(),
+            );
+        }
+        if let Some((column, ch)) = content
+            .chars()
+            .enumerate()
+            .find(|&(_, ch)| ch.is_control() && ch != '\t')
+        {
+            push(
+                line,
+                column + 1,
+                Rule::ControlChar,
+                format!("control character U+{:04X}", ch as u32),
+            );
+        }
+        let line_width = width::display_width(content);
+        if line_width > max_line_length {
+            push(
+                line,
+                max_line_length + 1,
+                Rule::LongLine,
+                format!("line is {line_width} columns (max {max_line_length})"),
+            );
+        }
+    }
+    for duplicate in &source.duplicates {
+        findings.push(LintFinding {
+            line: duplicate.line,
+            column: 1,
+            rule: Rule::DuplicateKey,
+            message: format!("duplicate key {}", duplicate.path.dot_path()),
+            path: Some(duplicate.path.clone()),
+        });
+    }
+    findings.sort_by_key(|finding| (finding.line, finding.column));
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn rules(text: &str, max: usize) -> Vec<(usize, usize, Rule)> {
+        lint(text, &SourceMap::scan(text), max)
+            .into_iter()
+            .map(|finding| (finding.line, finding.column, finding.rule))
+            .collect()
+    }
+
+    #[test]
+    fn reports_each_rule_by_line_and_column() {
+        let text = "a: 1 \nb:\n\t- x\nc: \"\u{1b}[0m\"\nlong: abcdefghij\n";
+        assert_eq!(
+            rules(text, 12),
+            vec![
+                (1, 5, Rule::TrailingSpace),
+                (3, 1, Rule::Tab),
+                (4, 5, Rule::ControlChar),
+                (5, 13, Rule::LongLine),
+            ]
+        );
+        // Whitespace-only lines count as trailing whitespace, CRLF endings don't.
+        assert_eq!(
+            rules("a: 1\r\n  \r\nb: 2\r\n", 80),
+            vec![(2, 1, Rule::TrailingSpace)]
+        );
+        assert_eq!(
+            rules("a: 1\nb: 2\na: 3\n", 80),
+            vec![(3, 1, Rule::DuplicateKey)]
+        );
+    }
+
+    #[test]
+    fn findings_name_their_node() {
+        let text = "server:\n  port: 80 \n";
+        let findings = lint(text, &SourceMap::scan(text), 80);
+        assert_eq!(
+            findings[0].summary(),
+            "2:11  trailing-space  trailing whitespace  (server.port)"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+// Advisory lock so two editors on the same file notice each other. The lock is a sidecar
+// file (`.<name>.yed-lock`) next to the target recording the owner's pid, host and start time.
+
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Mutex;
+
+use anyhow::Result;
+
+use crate::clock;
+
+/// Lock currently held by this process, removed by the panic hook.
+static ACTIVE_LOCK: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Terminating signal caught by `on_signal` (0 while none), acted on by the event loop.
+#[cfg(unix)]
+static SIGNALLED: AtomicI32 = AtomicI32::new(0);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockInfo {
+    pub pid: u32,
+    pub host: String,
+    /// Seconds since the Unix epoch when the lock was taken.
+    pub since: u64,
+}
+
+impl LockInfo {
+    pub fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            host: hostname(),
+            since: clock::now_secs(),
+        }
+    }
+
+    fn render(&self) -> String {
+        format!(
+            "pid={}\nhost={}\nsince={}\n",
+            self.pid, self.host, self.since
+        )
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut pid = None;
+        let mut host = None;
+        let mut since = None;
+        for line in text.lines() {
+            match line.split_once('=') {
+                Some(("pid", value)) => pid = value.trim().parse().ok(),
+                Some(("host", value)) => host = Some(value.trim().to_string()),
+                Some(("since", value)) => since = value.trim().parse().ok(),
+                _ => {}
+            }
+        }
+        Some(Self {
+            pid: pid?,
+            host: host?,
+            since: since?,
+        })
+    }
+
+    /// Local wall-clock time the lock was taken, as `HH:MM`.
+    pub fn since_label(&self) -> String {
+        let (hour, minute, _) = clock::local_time(self.since);
+        format!("{hour:02}:{minute:02}")
+    }
+}
+
+/// A held lock; dropping it removes the lock file if it still belongs to us.
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+    owner: LockInfo,
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let ours = fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|text| LockInfo::parse(&text))
+            .is_some_and(|info| info == self.owner);
+        if ours {
+            let _ = fs::remove_file(&self.path);
+        }
+        set_active(None);
+    }
+}
+
+#[derive(Debug)]
+pub enum Acquire {
+    /// We own the lock. `reclaimed` is the stale owner whose lock we replaced, if any.
+    Locked {
+        lock: FileLock,
+        reclaimed: Option<LockInfo>,
+    },
+    /// Another live process holds the lock.
+    Held(LockInfo),
+}
+
+pub fn lock_path(target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    target.with_file_name(format!(".{name}.yed-lock"))
+}
+
+pub fn acquire(target: &Path) -> Result<Acquire> {
+    acquire_as(target, LockInfo::current(), pid_alive)
+}
+
+/// Take the lock for `target` as `me`. A lock left by a dead process on this host (or one
+/// that cannot be read) is stale and gets reclaimed.
+pub fn acquire_as(target: &Path, me: LockInfo, is_alive: impl Fn(u32) -> bool) -> Result<Acquire> {
+    let path = lock_path(target);
+    let mut reclaimed = None;
+    // The second attempt follows removing a stale lock; losing that race means someone else won.
+    for _ in 0..2 {
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(me.render().as_bytes())?;
+                set_active(Some(path.clone()));
+                return Ok(Acquire::Locked {
+                    lock: FileLock { path, owner: me },
+                    reclaimed,
+                });
+            }
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                let existing = fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|text| LockInfo::parse(&text));
+                if let Some(info) = &existing {
+                    let same_host = info.host == me.host;
+                    let is_us = same_host && info.pid == me.pid;
+                    if !is_us && (!same_host || is_alive(info.pid)) {
+                        return Ok(Acquire::Held(info.clone()));
+                    }
+                    if !is_us {
+                        reclaimed = existing.clone();
+                    }
+                }
+                fs::remove_file(&path)?;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+    let info = fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| LockInfo::parse(&text))
+        .unwrap_or(me);
+    Ok(Acquire::Held(info))
+}
+
+/// Remove the active lock file when the process panics. SIGTERM, SIGHUP and SIGINT are only
+/// recorded; the event loop sees them through `signalled`, shuts down normally and `reraise`s.
+pub fn install_cleanup_handlers() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        remove_active();
+        #[cfg(unix)]
+        crate::control::remove_active();
+        previous(info);
+    }));
+    #[cfg(unix)]
+    unsafe {
+        for signal in [libc::SIGTERM, libc::SIGHUP, libc::SIGINT] {
+            let handler: extern "C" fn(libc::c_int) = on_signal;
+            libc::signal(signal, handler as *const () as libc::sighandler_t);
+        }
+    }
+}
+
+/// Only an atomic store: locks, allocation and file system calls are not async-signal-safe.
+#[cfg(unix)]
+extern "C" fn on_signal(signal: libc::c_int) {
+    SIGNALLED.store(signal, Ordering::SeqCst);
+}
+
+/// The terminating signal caught so far, if any.
+#[cfg(unix)]
+pub fn signalled() -> Option<i32> {
+    match SIGNALLED.load(Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal),
+    }
+}
+
+/// Die of `signal` with its default action, once the lock and socket have been cleaned up, so
+/// the parent sees the real cause.
+#[cfg(unix)]
+pub fn reraise(signal: i32) {
+    unsafe {
+        libc::signal(signal, libc::SIG_DFL);
+        libc::raise(signal);
+    }
+}
+
+fn set_active(path: Option<PathBuf>) {
+    if let Ok(mut active) = ACTIVE_LOCK.lock() {
+        *active = path;
+    }
+}
+
+fn remove_active() {
+    // Never block here: the lock may be held by the code that panicked.
+    if let Ok(mut active) = ACTIVE_LOCK.try_lock() {
+        if let Some(path) = active.take() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn pid_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 only checks whether the process exists; EPERM means it exists but is not ours.
+    let found = unsafe { libc::kill(pid, 0) } == 0;
+    found || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn pid_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(unix)]
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    let ok = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } == 0;
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    if ok && end > 0 {
+        String::from_utf8_lossy(&buf[..end]).into_owned()
+    } else {
+        "localhost".to_string()
+    }
+}
+
+#[cfg(not(unix))]
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| "localhost".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn target(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("yed_lock_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("values.yaml")
+    }
+
+    fn owner(pid: u32, host: &str) -> LockInfo {
+        LockInfo {
+            pid,
+            host: host.to_string(),
+            since: 0,
+        }
+    }
+
+    #[test]
+    fn acquire_and_release() {
+        let file = target("release");
+        let lock = match acquire_as(&file, owner(10, "box"), |_| true).unwrap() {
+            Acquire::Locked { lock, reclaimed } => {
+                assert_eq!(reclaimed, None);
+                lock
+            }
+            Acquire::Held(info) => panic!("unexpectedly held by {info:?}"),
+        };
+        assert_eq!(
+            lock_path(&file),
+            file.with_file_name(".values.yaml.yed-lock")
+        );
+        assert!(lock_path(&file).exists());
+        match acquire_as(&file, owner(11, "box"), |_| true).unwrap() {
+            Acquire::Held(info) => assert_eq!(info, owner(10, "box")),
+            Acquire::Locked { .. } => panic!("second owner got the lock"),
+        }
+        drop(lock);
+        assert!(!lock_path(&file).exists());
+        fs::remove_dir_all(file.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn stale_locks_on_same_host_are_reclaimed() {
+        let file = target("stale");
+        fs::write(lock_path(&file), owner(10, "box").render()).unwrap();
+        match acquire_as(&file, owner(11, "box"), |pid| pid != 10).unwrap() {
+            Acquire::Locked { reclaimed, .. } => assert_eq!(reclaimed, Some(owner(10, "box"))),
+            Acquire::Held(info) => panic!("stale lock not reclaimed: {info:?}"),
+        }
+        // A dead-looking pid on another host cannot be checked, so it still counts as held.
+        fs::write(lock_path(&file), owner(10, "other").render()).unwrap();
+        assert!(matches!(
+            acquire_as(&file, owner(11, "box"), |_| false).unwrap(),
+            Acquire::Held(_)
+        ));
+        fs::remove_dir_all(file.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn release_keeps_a_lock_taken_over_by_someone_else() {
+        let file = target("takeover");
+        let Acquire::Locked { lock, .. } = acquire_as(&file, owner(10, "box"), |_| true).unwrap()
+        else {
+            panic!("lock not acquired");
+        };
+        fs::write(lock_path(&file), owner(12, "box").render()).unwrap();
+        drop(lock);
+        assert!(lock_path(&file).exists());
+        fs::remove_dir_all(file.parent().unwrap()).ok();
+    }
+}
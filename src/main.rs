@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use std::time::Duration;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use crossterm::event::{self, Event, KeyEventKind};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
 use crossterm::execute;
@@ -11,33 +11,227 @@ use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 
 mod app;
+mod atomic_write;
+mod background;
+mod backup;
 mod clipboard;
+mod clock;
+#[cfg(unix)]
+mod control;
+mod diff;
+mod emitter;
+mod external_editor;
+mod get;
+mod history;
+mod hooks;
 mod input;
+mod interpolate;
+mod journal;
+mod json_patch;
+mod jump_list;
+mod k8s;
+mod keymap;
+mod line_diff;
+mod lint;
+mod lock;
+mod markdown;
+mod merge;
+mod positions;
+mod recent;
+mod replace;
+mod roundtrip;
+mod schema;
 mod search;
+mod set;
+mod settings;
+mod source_map;
+mod splice;
+mod stats;
+mod timestamp;
+mod toml_convert;
 mod ui;
 mod widgets;
+mod width;
 mod yaml_model;
 
 use crate::app::App;
+use crate::keymap::Keymap;
+use crate::settings::CliOverrides;
 
 #[derive(Parser)]
-#[command(name = "yed", version, about = "YAML TUI editor")]
+#[command(
+    name = "yed",
+    version,
+    about = "YAML TUI editor",
+    args_conflicts_with_subcommands = true
+)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// YAML file to open. If omitted, TUI opens with a file list to select from (current directory).
     path: Option<PathBuf>,
+    /// Reference YAML file; the tree marks values that differ from it.
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+    /// Spaces per indentation level when saving (overrides .yed.toml and user config).
+    #[arg(long, value_name = "N")]
+    indent: Option<usize>,
+    /// Open with N levels of the tree expanded.
+    #[arg(long, value_name = "N")]
+    depth: Option<usize>,
+    /// Save collections of at most N scalars in flow style, `[a, b]` (overrides .yed.toml and user config).
+    #[arg(long, value_name = "N")]
+    flow_max_items: Option<usize>,
+    /// Schema used to describe keys in the Details pane (overrides .yed.toml and user config).
+    #[arg(long, value_name = "FILE")]
+    schema: Option<PathBuf>,
+    /// Open the file read-only.
+    #[arg(long)]
+    read_only: bool,
+    /// Don't color tree values by type (also honours a non-empty NO_COLOR).
+    #[arg(long)]
+    no_color: bool,
+    /// Skip the background check that saving would reproduce the file (for huge files).
+    #[arg(long)]
+    no_integrity_check: bool,
+    /// Copy the file to `file.yaml.bak` before the first save of the session (see `backup` in .yed.toml).
+    #[arg(long)]
+    backup: bool,
+    /// Accept commands on a Unix socket at PATH (one JSON object per line; see README).
+    #[arg(long, value_name = "PATH")]
+    listen: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Load, emit and reload YAML files, reporting what a save would change.
+    /// Exits non-zero if any value, type or structure changes.
+    Roundtrip {
+        /// File, or directory searched recursively (honouring `ignore` settings).
+        path: PathBuf,
+        #[arg(long, value_enum, default_value_t)]
+        format: roundtrip::Format,
+    },
+    /// Print the value at a dot path (e.g. `server.tls.enabled`): scalars as plain text,
+    /// mappings and sequences as YAML. Exits non-zero if there is no such path.
+    Get {
+        file: PathBuf,
+        path: String,
+        /// Print the value as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Set the scalar at a dot path (e.g. `server.port`) and save, without opening the editor.
+    /// The value is read like one typed in the editor: `8080` is a number, `"8080"` a string.
+    Set {
+        file: PathBuf,
+        path: String,
+        value: String,
+        /// Add missing keys along the path as mappings.
+        #[arg(long)]
+        create: bool,
+        /// Print the resulting document instead of writing the file.
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Roundtrip { path, format }) => {
+            let clean = roundtrip::run(&path, format)?;
+            std::process::exit(if clean { 0 } else { 1 });
+        }
+        Some(Command::Get { file, path, json }) => {
+            exit_on_error(get::value(&file, &path, json).map(|value| print!("{value}")));
+        }
+        Some(Command::Set {
+            file,
+            path,
+            value,
+            create,
+            dry_run,
+        }) => {
+            exit_on_error(set::run(&file, &path, &value, create, dry_run));
+        }
+        None => {}
+    }
+    lock::install_cleanup_handlers();
+    let control = listen(cli.listen.as_deref())?;
+    let (keymap, warnings) = keymap::path()
+        .map(|path| keymap::load(&path))
+        .unwrap_or_default();
+    for warning in warnings {
+        eprintln!("warning: {warning}");
+    }
     let mut terminal = init_terminal()?;
-    let result = run_app(&mut terminal, cli.path);
+    let overrides = CliOverrides {
+        indent_width: cli.indent,
+        flow_max_items: cli.flow_max_items,
+        schema: cli.schema,
+        read_only: cli.read_only,
+        no_color: cli.no_color || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()),
+        no_integrity_check: cli.no_integrity_check,
+        backup: cli.backup,
+        recent: recent::state_path(),
+        search_history: history::state_path(),
+        positions: positions::state_path(),
+        depth: cli.depth,
+    };
+    let result = run_app(
+        &mut terminal,
+        cli.path,
+        cli.baseline,
+        overrides,
+        keymap,
+        control.as_ref(),
+    );
     restore_terminal(&mut terminal)?;
     if let Err(err) = result {
         eprintln!("{err}");
     }
+    #[cfg(unix)]
+    if let Some(signal) = lock::signalled() {
+        drop(control);
+        lock::reraise(signal);
+    }
     Ok(())
 }
 
+/// End a scripted subcommand: status 0, or the error on one line and status 1.
+fn exit_on_error(result: Result<()>) -> ! {
+    match result {
+        Ok(()) => {
+            std::io::Write::flush(&mut stdout()).ok();
+            std::process::exit(0)
+        }
+        Err(err) => {
+            eprintln!("{err:#}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(unix)]
+type ControlServer = control::ControlServer;
+#[cfg(not(unix))]
+type ControlServer = ();
+
+/// Bind the control socket before the terminal is taken over, so errors reach the shell.
+#[cfg(unix)]
+fn listen(path: Option<&std::path::Path>) -> Result<Option<ControlServer>> {
+    path.map(ControlServer::bind).transpose()
+}
+
+#[cfg(not(unix))]
+fn listen(path: Option<&std::path::Path>) -> Result<Option<ControlServer>> {
+    match path {
+        Some(_) => anyhow::bail!("--listen needs Unix domain sockets"),
+        None => Ok(None),
+    }
+}
+
 fn init_terminal() -> Result<Terminal<CrosstermBackend<std::io::Stdout>>> {
     enable_raw_mode()?;
     execute!(stdout(), EnterAlternateScreen, event::EnableMouseCapture)?;
@@ -57,19 +251,36 @@ fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>)
     Ok(())
 }
 
+/// Hand the terminal to `run` (an external program), then take it back, whatever `run` returned.
+fn suspend_terminal<T>(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    run: impl FnOnce() -> T,
+) -> Result<T> {
+    restore_terminal(terminal)?;
+    let result = run();
+    enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen, event::EnableMouseCapture)?;
+    terminal.clear()?;
+    Ok(result)
+}
+
 fn run_app(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     path: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+    overrides: CliOverrides,
+    keymap: Keymap,
+    control: Option<&ControlServer>,
 ) -> Result<()> {
     let mut app = match path {
-        Some(ref p) => match App::new(p) {
+        Some(ref p) => match App::new(p, overrides) {
             Ok(a) => a,
             Err(err) => {
                 show_fatal_error(terminal, &err.to_string())?;
                 return Ok(());
             }
         },
-        None => match App::new_for_picker() {
+        None => match App::new_for_picker(overrides) {
             Ok(a) => a,
             Err(err) => {
                 show_fatal_error(terminal, &err.to_string())?;
@@ -77,11 +288,33 @@ fn run_app(
             }
         },
     };
+    app.vim.set_keymap(keymap);
+    if let Some(ref b) = baseline {
+        if let Err(err) = app.set_baseline(b) {
+            app.set_toast(format!("Baseline: {err}"));
+        }
+    }
     loop {
+        // A terminating signal: leave through the normal teardown so the lock and socket go.
+        #[cfg(unix)]
+        if lock::signalled().is_some() {
+            break;
+        }
         app.update_toast();
+        app.poll_tasks();
         if let Err(err) = app.check_and_reload_if_changed() {
             app.set_toast(err.to_string());
         }
+        #[cfg(unix)]
+        if let Some(server) = control {
+            let area_height = terminal.size()?.height.saturating_sub(4) as usize;
+            for request in server.pending() {
+                let result = app.handle_control(&request.command, area_height);
+                request.respond(result);
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = control;
         terminal.draw(|frame| {
             let hits = ui::draw(frame, &mut app);
             app.update_hit_map(hits);
@@ -104,6 +337,12 @@ fn run_app(
                     if should_quit {
                         break;
                     }
+                    if let Some((path, text)) = app.external_edit.take() {
+                        let edited = suspend_terminal(terminal, || {
+                            external_editor::edit(&external_editor::editor(), &text)
+                        })?;
+                        app.finish_external_edit(path, &text, edited);
+                    }
                 }
                 Event::Mouse(mouse) => {
                     let area_height = terminal.size()?.height.saturating_sub(4) as usize;
@@ -116,6 +355,7 @@ fn run_app(
             }
         }
     }
+    app.remember_position();
     Ok(())
 }
 
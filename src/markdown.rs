@@ -0,0 +1,144 @@
+// Markdown renderings of a subtree for pasting into docs and PR descriptions.
+
+use anyhow::Result;
+use yaml_rust2::Yaml;
+
+use crate::emitter::{self, EmitOptions};
+use crate::source_map::SourceMap;
+use crate::width;
+use crate::yaml_model::{scalar_preview, NodePath};
+
+/// Default maximum display width of a table cell before it is truncated with `…`.
+pub const DEFAULT_CELL_WIDTH: usize = 40;
+
+/// Subtree serialized as YAML inside a ```yaml fence, headed by its dot path as a comment.
+pub fn fenced_yaml(path: &NodePath, node: &Yaml) -> Result<String> {
+    let body = emitter::emit(node, &SourceMap::default(), &EmitOptions::default())?;
+    let body = body.strip_prefix("---\n").unwrap_or(&body);
+    let label = if path.0.is_empty() {
+        "(root)".to_string()
+    } else {
+        path.dot_path()
+    };
+    Ok(format!("```yaml\n# {label}\n{body}\n```\n"))
+}
+
+/// Column names for a table view of `node`: the union of keys (in first-seen order) when it is a
+/// non-empty sequence whose elements are all mappings. `None` if it is not table-shaped.
+pub fn table_columns(node: &Yaml) -> Option<Vec<String>> {
+    let seq = match node {
+        Yaml::Array(seq) if !seq.is_empty() => seq,
+        _ => return None,
+    };
+    let mut columns: Vec<String> = Vec::new();
+    for item in seq {
+        let Yaml::Hash(map) = item else {
+            return None;
+        };
+        for key in map.keys() {
+            let name = cell_text(key);
+            if !columns.contains(&name) {
+                columns.push(name);
+            }
+        }
+    }
+    Some(columns)
+}
+
+/// Markdown table with one row per element; missing cells are left empty.
+pub fn markdown_table(node: &Yaml, cell_width: usize) -> Option<String> {
+    let columns = table_columns(node)?;
+    let Yaml::Array(seq) = node else {
+        return None;
+    };
+    let mut out = String::new();
+    let header: Vec<String> = columns.iter().map(|c| table_cell(c, cell_width)).collect();
+    out.push_str(&format!("| {} |\n", header.join(" | ")));
+    out.push_str(&format!("|{}\n", " --- |".repeat(columns.len())));
+    for item in seq {
+        let Yaml::Hash(map) = item else {
+            continue;
+        };
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| {
+                map.iter()
+                    .find(|(k, _)| cell_text(k) == *column)
+                    .map(|(_, v)| table_cell(&cell_text(v), cell_width))
+                    .unwrap_or_default()
+            })
+            .collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    Some(out)
+}
+
+/// Plain text for a key or value: strings unquoted, nested collections summarized.
+fn cell_text(node: &Yaml) -> String {
+    match node {
+        Yaml::String(value) => value.clone(),
+        Yaml::Hash(map) => format!("{{{} keys}}", map.len()),
+        Yaml::Array(seq) => format!("[{} items]", seq.len()),
+        _ => scalar_preview(node),
+    }
+}
+
+/// Escape pipes and line breaks, then truncate to `cell_width` columns.
+fn table_cell(text: &str, cell_width: usize) -> String {
+    let escaped = text.replace('|', "\\|").replace('\n', "<br>");
+    if width::display_width(&escaped) <= cell_width {
+        return escaped;
+    }
+    let mut cut = width::byte_at_column(&escaped, cell_width.saturating_sub(1));
+    // Never split an escape sequence.
+    if escaped[..cut].ends_with('\\') {
+        cut -= 1;
+    }
+    format!("{}…", &escaped[..cut])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use yaml_rust2::YamlLoader;
+
+    fn load(src: &str) -> Yaml {
+        YamlLoader::load_from_str(src).unwrap().remove(0)
+    }
+
+    #[test]
+    fn fenced_block_has_path_header() {
+        let doc = load("a: 1\nb:\n  - x\n");
+        let path = NodePath(vec![crate::yaml_model::PathSegment::Key("b".into())]);
+        assert_eq!(
+            fenced_yaml(&path, &doc["b"]).unwrap(),
+            "```yaml\n# b\n- x\n```\n"
+        );
+    }
+
+    #[test]
+    fn ragged_elements_leave_cells_empty() {
+        let doc = load("- name: a\n  port: 1\n- name: b\n  tls: true\n");
+        assert_eq!(
+            markdown_table(&doc, DEFAULT_CELL_WIDTH).unwrap(),
+            "| name | port | tls |\n| --- | --- | --- |\n| a | 1 |  |\n| b |  | true |\n"
+        );
+    }
+
+    #[test]
+    fn pipes_are_escaped_and_long_cells_truncated() {
+        let doc = load("- cmd: \"a | b\"\n  note: abcdefghij\n");
+        assert_eq!(
+            markdown_table(&doc, 6).unwrap(),
+            "| cmd | note |\n| --- | --- |\n| a \\| b | abcde… |\n"
+        );
+    }
+
+    #[test]
+    fn non_tabular_nodes_are_rejected() {
+        assert_eq!(table_columns(&load("- 1\n- a: 2\n")), None);
+        assert_eq!(table_columns(&load("[]")), None);
+        assert_eq!(table_columns(&load("a: 1")), None);
+    }
+}
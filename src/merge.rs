@@ -0,0 +1,341 @@
+// Three-way merge of the file on disk into a buffer with unsaved edits. Both documents are
+// diffed, path by path, against the baseline they share (the text last loaded or saved);
+// changes to unrelated paths combine, and overlapping ones are left for the user to resolve.
+
+use anyhow::{anyhow, Result};
+use yaml_rust2::Yaml;
+
+use crate::yaml_model::{get_node, get_node_mut, map_key, NodePath, PathSegment};
+
+/// One side's change to the baseline: the new value at `path`, or `None` where it was removed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub path: NodePath,
+    pub value: Option<Yaml>,
+}
+
+/// Overlapping changes under `path`; each value is the whole node there, `None` where absent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    pub path: NodePath,
+    pub baseline: Option<Yaml>,
+    pub disk: Option<Yaml>,
+    pub buffer: Option<Yaml>,
+}
+
+/// Which version of a conflicting node to keep.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Disk,
+    Buffer,
+    Baseline,
+}
+
+impl Conflict {
+    pub fn value(&self, side: Side) -> Option<&Yaml> {
+        match side {
+            Side::Disk => self.disk.as_ref(),
+            Side::Buffer => self.buffer.as_ref(),
+            Side::Baseline => self.baseline.as_ref(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Merge {
+    /// The buffer with every non-conflicting disk change applied; conflicts keep the buffer's value.
+    pub doc: Yaml,
+    /// Disk changes applied.
+    pub applied: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Paths where `other` differs from `base`, outermost first within each subtree. Sequences are
+/// compared item by item only while their length is unchanged; otherwise the whole sequence
+/// counts as one change, so insertions on both sides conflict rather than interleave.
+pub fn changes(base: &Yaml, other: &Yaml) -> Vec<Change> {
+    let mut out = Vec::new();
+    diff_node(&NodePath(Vec::new()), base, other, &mut out);
+    out
+}
+
+fn diff_node(path: &NodePath, base: &Yaml, other: &Yaml, out: &mut Vec<Change>) {
+    match (base, other) {
+        (Yaml::Hash(old), Yaml::Hash(new))
+            if old.keys().chain(new.keys()).all(|k| k.as_str().is_some()) =>
+        {
+            for (key, old_value) in old.iter() {
+                let child = path.child_key(key.as_str().unwrap_or_default());
+                match new.get(key) {
+                    Some(new_value) => diff_node(&child, old_value, new_value, out),
+                    None => out.push(Change {
+                        path: child,
+                        value: None,
+                    }),
+                }
+            }
+            for (key, new_value) in new.iter() {
+                if !old.contains_key(key) {
+                    out.push(Change {
+                        path: path.child_key(key.as_str().unwrap_or_default()),
+                        value: Some(new_value.clone()),
+                    });
+                }
+            }
+        }
+        (Yaml::Array(old), Yaml::Array(new)) if old.len() == new.len() => {
+            for (idx, (o, n)) in old.iter().zip(new).enumerate() {
+                diff_node(&path.child_index(idx), o, n, out);
+            }
+        }
+        _ => {
+            if base != other {
+                out.push(Change {
+                    path: path.clone(),
+                    value: Some(other.clone()),
+                });
+            }
+        }
+    }
+}
+
+/// Whether one path is the other or lies under it.
+fn overlaps(a: &NodePath, b: &NodePath) -> bool {
+    a.0.starts_with(&b.0) || b.0.starts_with(&a.0)
+}
+
+/// Merge the changes `baseline → disk` into `buffer`, which was edited from `baseline`.
+/// Identical changes on both sides are not conflicts. Overlapping ones are reported once, at
+/// the outermost path involved.
+pub fn merge(baseline: &Yaml, disk: &Yaml, buffer: &Yaml) -> Merge {
+    let ours = changes(baseline, buffer);
+    let theirs = changes(baseline, disk);
+    let mut doc = buffer.clone();
+    let mut applied = 0;
+    let mut conflict_paths: Vec<NodePath> = Vec::new();
+    for change in &theirs {
+        let clashing: Vec<&Change> = ours
+            .iter()
+            .filter(|o| overlaps(&o.path, &change.path))
+            .collect();
+        if clashing.is_empty() {
+            // The parent is untouched in the buffer, so the change always applies.
+            if resolve(&mut doc, &change.path, change.value.clone()).is_ok() {
+                applied += 1;
+            }
+            continue;
+        }
+        if clashing.iter().all(|o| *o == change) {
+            continue;
+        }
+        let outermost = clashing
+            .iter()
+            .map(|o| &o.path)
+            .chain([&change.path])
+            .min_by_key(|p| p.0.len())
+            .cloned()
+            .unwrap_or_else(|| change.path.clone());
+        if !conflict_paths.contains(&outermost) {
+            conflict_paths.push(outermost);
+        }
+    }
+    let conflicts = conflict_paths
+        .iter()
+        .filter(|path| {
+            !conflict_paths
+                .iter()
+                .any(|other| other != *path && path.0.starts_with(&other.0))
+        })
+        .map(|path| Conflict {
+            path: path.clone(),
+            baseline: lookup(baseline, path),
+            disk: lookup(disk, path),
+            buffer: lookup(buffer, path),
+        })
+        .collect();
+    Merge {
+        doc,
+        applied,
+        conflicts,
+    }
+}
+
+fn lookup(doc: &Yaml, path: &NodePath) -> Option<Yaml> {
+    get_node(doc, path).ok().cloned()
+}
+
+/// Set the node at `path` to `value`, adding it if missing, or remove it when `value` is `None`.
+/// The parent must exist.
+pub fn resolve(doc: &mut Yaml, path: &NodePath, value: Option<Yaml>) -> Result<()> {
+    let Some((last, parent)) = path.0.split_last() else {
+        *doc = value.unwrap_or(Yaml::Null);
+        return Ok(());
+    };
+    let parent = get_node_mut(doc, &NodePath(parent.to_vec()))?;
+    match (parent, last) {
+        (Yaml::Hash(map), PathSegment::Key(key)) => {
+            let key = map_key(map, key);
+            match (map.get_mut(&key), value) {
+                (Some(slot), Some(value)) => *slot = value,
+                (None, Some(value)) => {
+                    map.insert(key, value);
+                }
+                (_, None) => {
+                    map.remove(&key);
+                }
+            }
+        }
+        (Yaml::Array(seq), PathSegment::Index(index)) => match value {
+            Some(value) if *index < seq.len() => seq[*index] = value,
+            Some(value) if *index == seq.len() => seq.push(value),
+            Some(_) => return Err(anyhow!("Index out of bounds")),
+            None => {
+                if *index < seq.len() {
+                    seq.remove(*index);
+                }
+            }
+        },
+        _ => return Err(anyhow!("{}: parent has changed shape", path.dot_path())),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use yaml_rust2::YamlLoader;
+
+    fn doc(src: &str) -> Yaml {
+        YamlLoader::load_from_str(src).unwrap().remove(0)
+    }
+
+    fn path(dot: &str) -> NodePath {
+        dot.split('.').fold(NodePath(Vec::new()), |path, segment| {
+            match segment.parse() {
+                Ok(index) => path.child_index(index),
+                Err(_) => path.child_key(segment),
+            }
+        })
+    }
+
+    #[test]
+    fn changes_cover_edits_additions_and_removals() {
+        let base = doc("a: 1\nb: {c: 2, d: 3}\nlist: [x, y]\n");
+        let other = doc("a: 1\nb: {c: 5}\nlist: [x, z]\ne: new\n");
+        assert_eq!(
+            changes(&base, &other),
+            vec![
+                Change {
+                    path: path("b.c"),
+                    value: Some(Yaml::Integer(5))
+                },
+                Change {
+                    path: path("b.d"),
+                    value: None
+                },
+                Change {
+                    path: path("list.1"),
+                    value: Some(Yaml::String("z".into()))
+                },
+                Change {
+                    path: path("e"),
+                    value: Some(Yaml::String("new".into()))
+                },
+            ]
+        );
+        let grown = doc("a: 1\nb: {c: 2, d: 3}\nlist: [x, y, w]\n");
+        assert_eq!(changes(&base, &grown).len(), 1);
+        assert_eq!(changes(&base, &grown)[0].path, path("list"));
+    }
+
+    #[test]
+    fn non_overlapping_changes_combine() {
+        let base = doc("name: api\nport: 80\nlist: [a, b]\nold: 1\n");
+        let disk = doc("name: web\nport: 80\nlist: [a, b]\nold: 1\nadded: true\n");
+        let buffer = doc("name: api\nport: 8080\nlist: [a, c]\n");
+        let merged = merge(&base, &disk, &buffer);
+        assert_eq!(merged.conflicts, Vec::new());
+        assert_eq!(merged.applied, 2);
+        assert_eq!(
+            merged.doc,
+            doc("name: web\nport: 8080\nlist: [a, c]\nadded: true\n")
+        );
+    }
+
+    #[test]
+    fn identical_changes_on_both_sides_are_not_conflicts() {
+        let base = doc("a: 1\nb: 2\n");
+        let disk = doc("a: 5\nc: 3\n");
+        let buffer = doc("a: 5\nb: 2\nc: 3\n");
+        let merged = merge(&base, &disk, &buffer);
+        assert_eq!(merged.conflicts, Vec::new());
+        assert_eq!(merged.doc, doc("a: 5\nc: 3\n"));
+    }
+
+    #[test]
+    fn same_path_different_values_conflict() {
+        let base = doc("a: 1\nb: 2\n");
+        let disk = doc("a: 2\nb: 3\n");
+        let buffer = doc("a: 3\nb: 2\n");
+        let merged = merge(&base, &disk, &buffer);
+        assert_eq!(
+            merged.conflicts,
+            vec![Conflict {
+                path: path("a"),
+                baseline: Some(Yaml::Integer(1)),
+                disk: Some(Yaml::Integer(2)),
+                buffer: Some(Yaml::Integer(3)),
+            }]
+        );
+        // The conflict keeps the buffer's value until resolved; b still merges.
+        assert_eq!(merged.doc, doc("a: 3\nb: 3\n"));
+    }
+
+    #[test]
+    fn both_sides_adding_the_same_key_differently_conflict() {
+        let base = doc("a: 1\n");
+        let merged = merge(&base, &doc("a: 1\nb: disk\n"), &doc("a: 1\nb: mine\n"));
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(merged.conflicts[0].baseline, None);
+        assert_eq!(merged.conflicts[0].disk, Some(Yaml::String("disk".into())));
+    }
+
+    #[test]
+    fn delete_against_edit_conflicts_at_the_removed_node() {
+        let base = doc("server:\n  host: a\n  port: 80\nkeep: 1\n");
+        let disk = doc("server:\n  host: b\n  port: 81\nkeep: 1\n");
+        let buffer = doc("keep: 2\n");
+        let merged = merge(&base, &disk, &buffer);
+        assert_eq!(merged.conflicts.len(), 1);
+        let conflict = &merged.conflicts[0];
+        assert_eq!(conflict.path, path("server"));
+        assert_eq!(conflict.buffer, None);
+        assert_eq!(conflict.disk, Some(doc("host: b\nport: 81\n")));
+        assert_eq!(conflict.baseline, Some(doc("host: a\nport: 80\n")));
+    }
+
+    #[test]
+    fn list_growth_on_both_sides_conflicts_on_the_whole_list() {
+        let base = doc("list: [a]\n");
+        let merged = merge(&base, &doc("list: [a, b]\n"), &doc("list: [a, c]\n"));
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(merged.conflicts[0].path, path("list"));
+        // An item edit on one side still conflicts with a resize on the other.
+        let merged = merge(&base, &doc("list: [z]\n"), &doc("list: [a, c]\n"));
+        assert_eq!(merged.conflicts[0].path, path("list"));
+    }
+
+    #[test]
+    fn resolve_sets_adds_and_removes() {
+        let mut target = doc("a: 1\nlist: [x, y]\n");
+        resolve(&mut target, &path("a"), Some(Yaml::Integer(2))).unwrap();
+        resolve(&mut target, &path("b"), Some(Yaml::Boolean(true))).unwrap();
+        resolve(&mut target, &path("list.0"), None).unwrap();
+        resolve(&mut target, &path("list.1"), Some(Yaml::String("z".into()))).unwrap();
+        assert_eq!(target, doc("a: 2\nlist: [y, z]\nb: true\n"));
+        resolve(&mut target, &path("a"), None).unwrap();
+        assert_eq!(target, doc("list: [y, z]\nb: true\n"));
+        assert!(resolve(&mut target, &path("missing.x"), Some(Yaml::Null)).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+// Node selected when each file was last left, so reopening it lands there again.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::settings;
+
+/// Files remembered in the state file.
+const MAX_FILES: usize = 100;
+
+/// `positions` next to the user config file (`~/.config/yed/positions`).
+pub fn state_path() -> Option<PathBuf> {
+    Some(settings::user_config_path()?.with_file_name("positions"))
+}
+
+/// Dot path last selected in `file`, if remembered in `state`.
+pub fn lookup(state: &Path, file: &Path) -> Option<String> {
+    let file = fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+    let text = fs::read_to_string(state).ok()?;
+    parse(&text)
+        .into_iter()
+        .find(|(path, _)| *path == file)
+        .map(|(_, dot)| dot)
+}
+
+/// Remember `dot` for `file`, most recent first, forgetting the oldest beyond the cap.
+pub fn record(state: &Path, file: &Path, dot: &str) -> Result<()> {
+    // One entry per line: a dot path across lines would not read back.
+    if dot.contains(['\n', '\r']) {
+        return Ok(());
+    }
+    let file = fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+    let mut entries = parse(&fs::read_to_string(state).unwrap_or_default());
+    entries.retain(|(path, _)| *path != file);
+    entries.insert(0, (file, dot.to_string()));
+    entries.truncate(MAX_FILES);
+    if let Some(dir) = state.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut text = String::new();
+    for (path, dot) in &entries {
+        text.push_str(&path.to_string_lossy());
+        text.push('\t');
+        text.push_str(dot);
+        text.push('\n');
+    }
+    fs::write(state, text)?;
+    Ok(())
+}
+
+/// `file<TAB>dot path` per line; lines without a tab are skipped.
+fn parse(text: &str) -> Vec<(PathBuf, String)> {
+    text.lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(path, dot)| (PathBuf::from(path), dot.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn record_replaces_the_entry_and_caps_the_list() {
+        let dir = std::env::temp_dir().join(format!("yed_positions_{}", std::process::id()));
+        let state = dir.join("state").join("positions");
+        let file = dir.join("values.yaml");
+        record(&state, &file, "spec.replicas").unwrap();
+        record(&state, &file, "spec.containers.0.image").unwrap();
+        assert_eq!(
+            lookup(&state, &file).as_deref(),
+            Some("spec.containers.0.image")
+        );
+        for i in 0..MAX_FILES {
+            record(&state, &dir.join(format!("f{i}.yaml")), "a").unwrap();
+        }
+        let text = fs::read_to_string(&state).unwrap();
+        fs::remove_dir_all(&dir).ok();
+        assert_eq!(parse(&text).len(), MAX_FILES);
+        assert!(!text.contains("values.yaml"));
+        assert_eq!(lookup(&state, &file), None);
+    }
+}
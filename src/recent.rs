@@ -0,0 +1,96 @@
+// Recently opened files, remembered between runs and listed at the top of the file picker.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::settings;
+
+/// Entries kept in the state file.
+const MAX_RECENT: usize = 10;
+
+/// `recent` next to the user config file (`~/.config/yed/recent`).
+pub fn state_path() -> Option<PathBuf> {
+    Some(settings::user_config_path()?.with_file_name("recent"))
+}
+
+/// Remembered files that still exist, most recently opened first. A missing or unreadable
+/// state file is an empty list.
+pub fn load(state: &Path) -> Vec<PathBuf> {
+    let text = fs::read_to_string(state).unwrap_or_default();
+    parse(&text)
+        .into_iter()
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Move `file` to the front of the list in `state`, dropping files that no longer exist.
+pub fn record(state: &Path, file: &Path) -> Result<()> {
+    let file = fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+    let list = remember(load(state), file);
+    if let Some(dir) = state.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut text = String::new();
+    for path in &list {
+        text.push_str(&path.to_string_lossy());
+        text.push('\n');
+    }
+    fs::write(state, text)?;
+    Ok(())
+}
+
+/// One path per line; blank lines are skipped.
+fn parse(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+fn remember(mut list: Vec<PathBuf>, file: PathBuf) -> Vec<PathBuf> {
+    list.retain(|path| *path != file);
+    list.insert(0, file);
+    list.truncate(MAX_RECENT);
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn remember_moves_to_front_and_caps() {
+        let list: Vec<PathBuf> = (0..MAX_RECENT)
+            .map(|i| PathBuf::from(format!("/f{i}")))
+            .collect();
+        let list = remember(list, PathBuf::from("/f3"));
+        assert_eq!(list[0], PathBuf::from("/f3"));
+        assert_eq!(list.len(), MAX_RECENT);
+        let list = remember(list, PathBuf::from("/new"));
+        assert_eq!(list.len(), MAX_RECENT);
+        assert_eq!(list[..2], [PathBuf::from("/new"), PathBuf::from("/f3")]);
+        assert!(!list.contains(&PathBuf::from(format!("/f{}", MAX_RECENT - 1))));
+    }
+
+    #[test]
+    fn record_drops_files_that_are_gone() {
+        let dir = std::env::temp_dir().join(format!("yed_recent_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let state = dir.join("state").join("recent");
+        let a = dir.join("a.yaml");
+        let b = dir.join("b.yaml");
+        fs::write(&a, "a: 1\n").unwrap();
+        fs::write(&b, "b: 1\n").unwrap();
+        record(&state, &a).unwrap();
+        record(&state, &b).unwrap();
+        let canonical = |path: &Path| fs::canonicalize(path).unwrap();
+        assert_eq!(load(&state), vec![canonical(&b), canonical(&a)]);
+
+        fs::remove_file(&b).unwrap();
+        assert_eq!(load(&state), vec![canonical(&a)]);
+        fs::remove_dir_all(&dir).ok();
+    }
+}
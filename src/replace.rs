@@ -0,0 +1,121 @@
+// Parsing of the `:%s/old/new/flags` find-and-replace command.
+
+use anyhow::{anyhow, bail, Result};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplaceCommand {
+    pub find: String,
+    pub with: String,
+    /// `c`: ask before each replacement.
+    pub confirm: bool,
+    /// `t`: also replace in numbers and booleans, reading the result back as a typed value.
+    pub coerce: bool,
+    /// `k`: replace in mapping keys instead of values.
+    pub keys: bool,
+}
+
+impl ReplaceCommand {
+    /// Parse `%s/old/new/flags` (the `%` is optional, the last delimiter too). Any punctuation
+    /// right after `s` is the delimiter, and a backslash escapes it. `g` is accepted and
+    /// ignored: every occurrence in a value is always replaced.
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let rest = text.strip_prefix('%').unwrap_or(text);
+        let Some(rest) = rest.strip_prefix('s') else {
+            bail!("Expected %s/old/new/");
+        };
+        let mut chars = rest.chars();
+        let delimiter = chars
+            .next()
+            .filter(|ch| ch.is_ascii_punctuation() && *ch != '\\')
+            .ok_or_else(|| anyhow!("Expected %s/old/new/"))?;
+        let mut parts = vec![String::new()];
+        let mut escaped = false;
+        for ch in chars {
+            let fields = parts.len();
+            let part = parts.last_mut().expect("parts is never empty");
+            if escaped {
+                if ch != delimiter {
+                    part.push('\\');
+                }
+                part.push(ch);
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == delimiter && fields < 3 {
+                parts.push(String::new());
+            } else {
+                part.push(ch);
+            }
+        }
+        if escaped {
+            parts.last_mut().expect("parts is never empty").push('\\');
+        }
+        let mut parts = parts.into_iter();
+        let find = parts.next().unwrap_or_default();
+        let with = parts
+            .next()
+            .ok_or_else(|| anyhow!("Expected %s/old/new/"))?;
+        let flags = parts.next().unwrap_or_default();
+        if find.is_empty() {
+            bail!("Nothing to find");
+        }
+        let mut command = Self {
+            find,
+            with,
+            confirm: false,
+            coerce: false,
+            keys: false,
+        };
+        for flag in flags.chars() {
+            match flag {
+                'c' => command.confirm = true,
+                't' => command.coerce = true,
+                'k' => command.keys = true,
+                'g' => {}
+                other => bail!("Unknown flag '{other}' (c: confirm each, t: numbers and booleans too, k: keys)"),
+            }
+        }
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_delimiters_escapes_and_flags() {
+        assert_eq!(
+            ReplaceCommand::parse("%s/staging/prod/").unwrap(),
+            ReplaceCommand {
+                find: "staging".into(),
+                with: "prod".into(),
+                confirm: false,
+                coerce: false,
+                keys: false,
+            }
+        );
+        let command = ReplaceCommand::parse(r"s#a/b#c\#d#gct").unwrap();
+        assert_eq!(
+            (command.find.as_str(), command.with.as_str()),
+            ("a/b", "c#d")
+        );
+        assert!(command.confirm && command.coerce && !command.keys);
+        assert!(ReplaceCommand::parse("%s/-/_/kc").unwrap().keys);
+        assert_eq!(ReplaceCommand::parse(r"%s/x\n/").unwrap().find, r"x\n");
+        assert_eq!(ReplaceCommand::parse("%s/old/").unwrap().with, "");
+
+        assert_eq!(
+            ReplaceCommand::parse("%s//new/").unwrap_err().to_string(),
+            "Nothing to find"
+        );
+        assert!(ReplaceCommand::parse("%s/old").is_err());
+        assert!(ReplaceCommand::parse("%x/a/b/").is_err());
+        assert!(ReplaceCommand::parse("%s/a/b/z")
+            .unwrap_err()
+            .to_string()
+            .starts_with("Unknown flag 'z'"));
+    }
+}
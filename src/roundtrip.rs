@@ -0,0 +1,507 @@
+// `yed roundtrip`: load each YAML file, emit it the way save would, reload the emission and
+// report what changed. Semantic differences fail the run; textual ones are only counted.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use yaml_rust2::{Yaml, YamlLoader};
+
+use crate::diff::{self, DiffMark};
+use crate::emitter::{self, EmitOptions};
+use crate::settings::{self, CliOverrides, Settings};
+use crate::source_map::{self, SourceMap};
+use crate::yaml_model::{get_node, node_preview, yaml_node_type, NodePath};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+/// How a path differs between the loaded document and its reloaded emission.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffKind {
+    /// Same type, different value.
+    Value,
+    /// Scalar of another type (e.g. number became string).
+    Type,
+    /// Container replaced by a scalar or the other kind of container.
+    Structure,
+    /// Only present after the round trip.
+    Added,
+    /// Lost in the round trip.
+    Removed,
+}
+
+impl DiffKind {
+    fn label(self) -> &'static str {
+        match self {
+            DiffKind::Value => "value",
+            DiffKind::Type => "type",
+            DiffKind::Structure => "structure",
+            DiffKind::Added => "added",
+            DiffKind::Removed => "removed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Difference {
+    pub path: String,
+    pub kind: DiffKind,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocumentReport {
+    pub index: usize,
+    pub semantic: Vec<Difference>,
+    /// Lines added or removed between the document's source text and its emission.
+    pub changed_lines: usize,
+    /// The emission could not be produced or parsed back.
+    pub error: Option<String>,
+}
+
+impl DocumentReport {
+    pub fn failed(&self) -> bool {
+        !self.semantic.is_empty() || self.error.is_some()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub documents: Vec<DocumentReport>,
+    /// The file itself could not be read or parsed.
+    pub error: Option<String>,
+}
+
+impl FileReport {
+    fn failed(&self) -> bool {
+        self.error.is_some() || self.documents.iter().any(DocumentReport::failed)
+    }
+}
+
+/// Check `target` (a file, or a directory searched recursively) and print the report.
+/// Returns whether every document round-tripped without semantic differences.
+pub fn run(target: &Path, format: Format) -> Result<bool> {
+    let reports = check_path(target)?;
+    let out = match format {
+        Format::Text => render_text(&reports),
+        Format::Json => render_json(&reports),
+    };
+    print!("{out}");
+    Ok(!reports.iter().any(FileReport::failed))
+}
+
+pub fn check_path(target: &Path) -> Result<Vec<FileReport>> {
+    let mut reports = Vec::new();
+    if target.is_dir() {
+        walk(target, &mut reports)?;
+    } else if target.is_file() {
+        let dir = target.parent().unwrap_or(Path::new("."));
+        reports.push(check_file(target, &emit_options(&settings_for(dir))));
+    } else {
+        return Err(anyhow!("{}: no such file or directory", target.display()));
+    }
+    Ok(reports)
+}
+
+fn walk(dir: &Path, reports: &mut Vec<FileReport>) -> Result<()> {
+    let settings = settings_for(dir);
+    let options = emit_options(&settings);
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .filter(|e| !settings.is_ignored(&e.file_name().to_string_lossy()))
+        .map(|e| e.path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            walk(&path, reports)?;
+        } else if is_yaml_file(&path) {
+            reports.push(check_file(&path, &options));
+        }
+    }
+    Ok(())
+}
+
+fn settings_for(dir: &Path) -> Settings {
+    let user_config = settings::user_config_path();
+    settings::load(dir, user_config.as_deref(), &CliOverrides::default())
+        .unwrap_or_else(|_| Settings::defaults(dir))
+}
+
+fn emit_options(settings: &Settings) -> EmitOptions {
+    EmitOptions {
+        indent: settings.indent_width.value,
+        document_start: settings.document_start.value,
+        flow_max_items: settings.flow_max_items.value,
+    }
+}
+
+fn is_yaml_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("yaml") || e.eq_ignore_ascii_case("yml"))
+}
+
+fn check_file(path: &Path, options: &EmitOptions) -> FileReport {
+    let result = fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|text| check_text(&text, options));
+    match result {
+        Ok(documents) => FileReport {
+            path: path.to_path_buf(),
+            documents,
+            error: None,
+        },
+        Err(err) => FileReport {
+            path: path.to_path_buf(),
+            documents: Vec::new(),
+            error: Some(err.to_string()),
+        },
+    }
+}
+
+/// Round-trip every document of `text` separately.
+pub fn check_text(text: &str, options: &EmitOptions) -> Result<Vec<DocumentReport>> {
+    let docs = YamlLoader::load_from_str(text)?;
+    let mut bounds = source_map::document_offsets(text);
+    bounds.push(text.len());
+    let reports = docs
+        .iter()
+        .enumerate()
+        .map(|(index, doc)| {
+            let source = match (bounds.get(index), bounds.get(index + 1)) {
+                (Some(&start), Some(&end)) => &text[start..end],
+                _ => "",
+            };
+            check_document(index, doc, source, options)
+        })
+        .collect();
+    Ok(reports)
+}
+
+fn check_document(index: usize, doc: &Yaml, source: &str, options: &EmitOptions) -> DocumentReport {
+    let mut report = DocumentReport {
+        index,
+        ..DocumentReport::default()
+    };
+    let emitted = match emitter::emit(doc, &SourceMap::scan(source), options) {
+        Ok(out) => out,
+        Err(err) => {
+            report.error = Some(format!("emit failed: {err}"));
+            return report;
+        }
+    };
+    report.changed_lines = changed_lines(source, &emitted);
+    match YamlLoader::load_from_str(&emitted) {
+        Ok(reloaded) => {
+            let reloaded = reloaded.into_iter().next().unwrap_or(Yaml::Null);
+            report.semantic = semantic_differences(doc, &reloaded);
+        }
+        Err(err) => report.error = Some(format!("emitted YAML does not parse: {err}")),
+    }
+    report
+}
+
+/// Path-keyed differences between a document and its reloaded emission, via the baseline diff.
+pub fn semantic_differences(before: &Yaml, after: &Yaml) -> Vec<Difference> {
+    let root = NodePath(Vec::new());
+    let annotations = diff::annotate(after, before);
+    let mut out: Vec<Difference> = annotations
+        .marks
+        .iter()
+        .map(|(path, mark)| {
+            let after_node = get_node(after, path).ok();
+            let before_node = get_node(before, path).ok();
+            match (mark, before_node, after_node) {
+                (DiffMark::Changed(_), Some(b), Some(a)) => {
+                    let kind = classify(b, a);
+                    Difference {
+                        path: label(path),
+                        kind,
+                        before: describe(b, kind),
+                        after: describe(a, kind),
+                    }
+                }
+                _ => Difference {
+                    path: label(path),
+                    kind: DiffKind::Added,
+                    before: String::new(),
+                    after: after_node.map(node_preview).unwrap_or_default(),
+                },
+            }
+        })
+        .collect();
+    for (parent, missing) in &annotations.missing {
+        for (key, value) in missing {
+            // Missing sequence items are listed as `[index]`.
+            let child = match key.strip_prefix('[').and_then(|k| k.strip_suffix(']')) {
+                Some(index) => parent.child_index(index.parse().unwrap_or_default()),
+                None => parent.child_key(key),
+            };
+            out.push(Difference {
+                path: label(&child),
+                kind: DiffKind::Removed,
+                before: value.clone(),
+                after: String::new(),
+            });
+        }
+    }
+    // The baseline diff only compares inside containers; a bare scalar document is the root.
+    if before != after && (!is_container(before) || !is_container(after)) {
+        let kind = classify(before, after);
+        out.push(Difference {
+            path: label(&root),
+            kind,
+            before: describe(before, kind),
+            after: describe(after, kind),
+        });
+    }
+    out.sort_by(|a, b| a.path.cmp(&b.path));
+    out
+}
+
+fn classify(before: &Yaml, after: &Yaml) -> DiffKind {
+    if scalar_kind(before) == scalar_kind(after) {
+        DiffKind::Value
+    } else if is_container(before) || is_container(after) {
+        DiffKind::Structure
+    } else {
+        DiffKind::Type
+    }
+}
+
+/// Finer than `NodeType`: an integer turning into a float counts as a type change.
+fn scalar_kind(node: &Yaml) -> String {
+    match node {
+        Yaml::Integer(_) => "integer".to_string(),
+        Yaml::Real(_) => "float".to_string(),
+        _ => yaml_node_type(node).to_string(),
+    }
+}
+
+/// Preview used on either side of a difference; type changes name both types.
+fn describe(node: &Yaml, kind: DiffKind) -> String {
+    if kind == DiffKind::Type {
+        format!("{} ({})", node_preview(node), scalar_kind(node))
+    } else {
+        node_preview(node)
+    }
+}
+
+fn is_container(node: &Yaml) -> bool {
+    matches!(node, Yaml::Hash(_) | Yaml::Array(_))
+}
+
+fn label(path: &NodePath) -> String {
+    if path.0.is_empty() {
+        "(root)".to_string()
+    } else {
+        path.dot_path()
+    }
+}
+
+/// Number of lines added plus removed by a line diff of `a` and `b`.
+fn changed_lines(a: &str, b: &str) -> usize {
+    let a: Vec<&str> = a.lines().collect();
+    let b: Vec<&str> = b.lines().collect();
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a = &a[prefix..a.len() - suffix];
+    let b = &b[prefix..b.len() - suffix];
+    // Longest common subsequence; past this size an exact count is not worth the memory.
+    if a.len().saturating_mul(b.len()) > 4_000_000 {
+        return a.len() + b.len();
+    }
+    let mut row = vec![0usize; b.len() + 1];
+    for x in a {
+        let mut diag = 0;
+        for (j, y) in b.iter().enumerate() {
+            let up = row[j + 1];
+            row[j + 1] = if x == y { diag + 1 } else { up.max(row[j]) };
+            diag = up;
+        }
+    }
+    a.len() + b.len() - 2 * row[b.len()]
+}
+
+fn render_text(reports: &[FileReport]) -> String {
+    let mut out = String::new();
+    let mut failed = 0;
+    for report in reports {
+        let name = report.path.display();
+        if let Some(err) = &report.error {
+            failed += 1;
+            let _ = writeln!(out, "ERROR {name}: {err}");
+            continue;
+        }
+        if report.failed() {
+            failed += 1;
+        }
+        let multi = report.documents.len() > 1;
+        for doc in &report.documents {
+            let name = if multi {
+                format!("{name} [doc {}]", doc.index + 1)
+            } else {
+                name.to_string()
+            };
+            let status = if doc.failed() {
+                "FAIL "
+            } else if doc.changed_lines > 0 {
+                "TEXT "
+            } else {
+                "OK   "
+            };
+            let _ = write!(out, "{status} {name}");
+            if doc.changed_lines > 0 {
+                let _ = write!(out, " ({} lines differ)", doc.changed_lines);
+            }
+            out.push('\n');
+            if let Some(err) = &doc.error {
+                let _ = writeln!(out, "      {err}");
+            }
+            for d in &doc.semantic {
+                let _ = writeln!(
+                    out,
+                    "      {} {}: {} -> {}",
+                    d.kind.label(),
+                    d.path,
+                    d.before,
+                    d.after
+                );
+            }
+        }
+    }
+    let _ = writeln!(
+        out,
+        "{} files checked, {} with semantic differences",
+        reports.len(),
+        failed
+    );
+    out
+}
+
+fn render_json(reports: &[FileReport]) -> String {
+    let mut out = String::from("{\"files\":[");
+    for (i, report) in reports.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(
+            out,
+            "{{\"path\":{},\"error\":{},\"documents\":[",
+            json_string(&report.path.display().to_string()),
+            json_optional(report.error.as_deref())
+        );
+        for (j, doc) in report.documents.iter().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+            let _ = write!(
+                out,
+                "{{\"index\":{},\"changed_lines\":{},\"error\":{},\"semantic\":[",
+                doc.index,
+                doc.changed_lines,
+                json_optional(doc.error.as_deref())
+            );
+            for (k, d) in doc.semantic.iter().enumerate() {
+                if k > 0 {
+                    out.push(',');
+                }
+                let _ = write!(
+                    out,
+                    "{{\"path\":{},\"kind\":\"{}\",\"before\":{},\"after\":{}}}",
+                    json_string(&d.path),
+                    d.kind.label(),
+                    json_string(&d.before),
+                    json_string(&d.after)
+                );
+            }
+            out.push_str("]}");
+        }
+        out.push_str("]}");
+    }
+    let failed = reports.iter().filter(|r| r.failed()).count();
+    let _ = writeln!(out, "],\"failed\":{failed}}}");
+    out
+}
+
+fn json_optional(value: Option<&str>) -> String {
+    value.map_or_else(|| "null".to_string(), json_string)
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn load(src: &str) -> Yaml {
+        YamlLoader::load_from_str(src).unwrap().remove(0)
+    }
+
+    #[test]
+    fn classifies_semantic_differences() {
+        let before = load("port: 80\nname: api\nlist: [1, 2]\nmode: {a: 1}\nold: x\n");
+        let after = load("port: \"80\"\nname: web\nlist: [1]\nmode: plain\nnew: y\n");
+        let kinds: Vec<(String, DiffKind)> = semantic_differences(&before, &after)
+            .into_iter()
+            .map(|d| (d.path, d.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("list.1".to_string(), DiffKind::Removed),
+                ("mode".to_string(), DiffKind::Structure),
+                ("name".to_string(), DiffKind::Value),
+                ("new".to_string(), DiffKind::Added),
+                ("old".to_string(), DiffKind::Removed),
+                ("port".to_string(), DiffKind::Type),
+            ]
+        );
+        assert!(semantic_differences(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn documents_are_checked_separately() {
+        let text = "# first\na: 1\n---\nb: ['x', y]\n---\nc: !!float 1\n";
+        let reports = check_text(text, &EmitOptions::default()).unwrap();
+        assert_eq!(reports.len(), 3);
+        assert!(reports[0].semantic.is_empty());
+        // Flow style and quoting survive.
+        assert_eq!(reports[1].changed_lines, 0);
+        assert_eq!(reports[2].semantic[0].kind, DiffKind::Type);
+        assert_eq!(changed_lines("a\nb\nc", "a\nc\nd"), 2);
+    }
+}
@@ -0,0 +1,154 @@
+// JSON Schema (written as JSON or YAML) describing the edited document. Only the structural
+// keywords needed to find the entry for a path are interpreted.
+
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use yaml_rust2::{Yaml, YamlLoader};
+
+use crate::yaml_model::{NodePath, PathSegment};
+
+pub struct Schema {
+    root: Yaml,
+}
+
+impl Schema {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| anyhow!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let root = YamlLoader::load_from_str(text)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("empty schema"))?;
+        if !matches!(root, Yaml::Hash(_)) {
+            return Err(anyhow!("schema must be a mapping"));
+        }
+        Ok(Self { root })
+    }
+
+    /// Schema entry for the node at `path`, following `properties`, `additionalProperties`
+    /// and `items`.
+    pub fn entry_for(&self, path: &NodePath) -> Option<&Yaml> {
+        let mut node = &self.root;
+        for segment in &path.0 {
+            node = match segment {
+                PathSegment::Key(key) => {
+                    let property = &node["properties"][key.as_str()];
+                    if property.is_badvalue() {
+                        match &node["additionalProperties"] {
+                            extra @ Yaml::Hash(_) => extra,
+                            _ => return None,
+                        }
+                    } else {
+                        property
+                    }
+                }
+                PathSegment::Index(_) => match &node["items"] {
+                    items @ Yaml::Hash(_) => items,
+                    _ => return None,
+                },
+            };
+        }
+        Some(node)
+    }
+
+    /// Names under `properties` of the entry for the mapping at `path`, in schema order.
+    pub fn property_names(&self, path: &NodePath) -> Vec<String> {
+        self.entry_for(path)
+            .and_then(|entry| entry["properties"].as_hash())
+            .map(|properties| {
+                properties
+                    .keys()
+                    .filter_map(Yaml::as_str)
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// One-line summary of the entry for `path`: its type and description (or title).
+    pub fn describe(&self, path: &NodePath) -> Option<String> {
+        let entry = self.entry_for(path)?;
+        let kind = match &entry["type"] {
+            Yaml::String(kind) => Some(kind.clone()),
+            Yaml::Array(kinds) => Some(
+                kinds
+                    .iter()
+                    .filter_map(Yaml::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+            ),
+            _ => None,
+        };
+        let text = entry["description"]
+            .as_str()
+            .or_else(|| entry["title"].as_str());
+        match (kind, text) {
+            (Some(kind), Some(text)) => Some(format!("{kind} — {text}")),
+            (Some(kind), None) => Some(kind),
+            (None, Some(text)) => Some(text.to_string()),
+            (None, None) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn describes_nested_properties_and_items() {
+        let schema = Schema::parse(
+            r#"{"type": "object", "properties": {
+                "replicas": {"type": "integer", "description": "Pod count"},
+                "ports": {"type": "array", "items": {"type": "object",
+                    "properties": {"name": {"type": ["string", "null"], "title": "Port name"}}}},
+                "labels": {"type": "object", "additionalProperties": {"type": "string"}}}}"#,
+        )
+        .unwrap();
+        let path = |segments: Vec<PathSegment>| NodePath(segments);
+        assert_eq!(
+            schema.describe(&path(vec![PathSegment::Key("replicas".into())])),
+            Some("integer — Pod count".to_string())
+        );
+        assert_eq!(
+            schema.describe(&path(vec![
+                PathSegment::Key("ports".into()),
+                PathSegment::Index(0),
+                PathSegment::Key("name".into()),
+            ])),
+            Some("string | null — Port name".to_string())
+        );
+        assert_eq!(
+            schema.describe(&path(vec![
+                PathSegment::Key("labels".into()),
+                PathSegment::Key("team".into()),
+            ])),
+            Some("string".to_string())
+        );
+        assert_eq!(
+            schema.describe(&path(vec![PathSegment::Key("nope".into())])),
+            None
+        );
+        assert_eq!(
+            schema.property_names(&path(vec![])),
+            ["replicas", "ports", "labels"]
+        );
+        assert_eq!(
+            schema.property_names(&path(vec![
+                PathSegment::Key("ports".into()),
+                PathSegment::Index(3)
+            ])),
+            ["name"]
+        );
+        assert!(schema
+            .property_names(&path(vec![PathSegment::Key("labels".into())]))
+            .is_empty());
+        assert!(Schema::parse("- 1").is_err());
+    }
+}
@@ -1,9 +1,406 @@
+use std::fmt;
+use std::ops::Range;
+
+use anyhow::{anyhow, Result};
+use regex::{Regex, RegexBuilder};
+
 use crate::yaml_model::VisibleRow;
 
-pub fn matches_row(row: &VisibleRow, query: &str) -> bool {
-    let q = query.to_lowercase();
-    row.path.dot_path().to_lowercase().contains(&q)
-        || row.display_key.to_lowercase().contains(&q)
+/// Prefix that makes a query a regular expression (`/re:^spec\..*image$`).
+const REGEX_PREFIX: &str = "re:";
+/// Prefix that makes a query a fuzzy subsequence (`/fz:srvtlsen`).
+const FUZZY_PREFIX: &str = "fz:";
+
+/// Whether a search tells `Name` from `name`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CaseMode {
+    #[default]
+    Insensitive,
+    Sensitive,
+    /// Case-insensitive unless the query has an uppercase letter.
+    Smart,
+}
+
+impl CaseMode {
+    /// The mode after this one, for cycling with Alt+c while typing a query.
+    pub fn next(self) -> Self {
+        match self {
+            CaseMode::Insensitive => CaseMode::Sensitive,
+            CaseMode::Sensitive => CaseMode::Smart,
+            CaseMode::Smart => CaseMode::Insensitive,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CaseMode::Insensitive => "ignore case",
+            CaseMode::Sensitive => "match case",
+            CaseMode::Smart => "smart case",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "insensitive" => Some(CaseMode::Insensitive),
+            "sensitive" => Some(CaseMode::Sensitive),
+            "smart" => Some(CaseMode::Smart),
+            _ => None,
+        }
+    }
+
+    /// Whether `query` is matched ignoring case. Escaped characters (`\D`, `\W` in a regex)
+    /// do not count as uppercase for smart case.
+    pub fn ignores_case(self, query: &str) -> bool {
+        match self {
+            CaseMode::Insensitive => true,
+            CaseMode::Sensitive => false,
+            CaseMode::Smart => {
+                let mut escaped = false;
+                !query.chars().any(|ch| {
+                    let literal = !escaped && ch.is_uppercase();
+                    escaped = !escaped && ch == '\\';
+                    literal
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for CaseMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaseMode::Insensitive => write!(f, "insensitive"),
+            CaseMode::Sensitive => write!(f, "sensitive"),
+            CaseMode::Smart => write!(f, "smart"),
+        }
+    }
+}
+
+/// A committed search query: text matched as a substring, a regex compiled once when the
+/// query is applied, or a fuzzy subsequence.
+#[derive(Clone, Debug)]
+pub struct Query {
+    text: String,
+    regex: Option<Regex>,
+    fuzzy: bool,
+    ignore_case: bool,
+}
+
+impl Query {
+    /// `text` as typed after any `k:`/`v:` prefix; `re:` makes the rest a regex and `fz:` a
+    /// fuzzy pattern. Fails with the compile error for an invalid regex.
+    pub fn parse(text: &str, case: CaseMode) -> Result<Self> {
+        if let Some(pattern) = text.strip_prefix(FUZZY_PREFIX) {
+            return Ok(Self {
+                fuzzy: true,
+                ignore_case: case.ignores_case(pattern),
+                ..Self::plain(pattern)
+            });
+        }
+        let Some(pattern) = text.strip_prefix(REGEX_PREFIX) else {
+            return Ok(Self {
+                ignore_case: case.ignores_case(text),
+                ..Self::plain(text)
+            });
+        };
+        let ignore_case = case.ignores_case(pattern);
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|err| {
+                // The syntax error ends with its one-line reason, after the pattern and a caret.
+                let message = err.to_string();
+                anyhow!(
+                    "Invalid regex: {}",
+                    message
+                        .lines()
+                        .last()
+                        .unwrap_or_default()
+                        .trim_start_matches("error: ")
+                )
+            })?;
+        Ok(Self {
+            text: pattern.to_string(),
+            regex: Some(regex),
+            fuzzy: false,
+            ignore_case,
+        })
+    }
+
+    /// Case-insensitive substring query.
+    pub fn plain(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            regex: None,
+            fuzzy: false,
+            ignore_case: true,
+        }
+    }
+
+    /// The substring, or the pattern without its `re:` or `fz:` prefix.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_regex(&self) -> bool {
+        self.regex.is_some()
+    }
+
+    pub fn is_fuzzy(&self) -> bool {
+        self.fuzzy
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        if self.fuzzy {
+            return self.score(text).is_some();
+        }
+        match &self.regex {
+            Some(regex) => regex.is_match(text),
+            None if self.ignore_case => text.to_lowercase().contains(&self.text.to_lowercase()),
+            None => text.contains(&self.text),
+        }
+    }
+
+    /// Byte ranges of the non-empty, non-overlapping matches in `text`.
+    pub fn ranges(&self, text: &str) -> Vec<Range<usize>> {
+        if self.fuzzy {
+            return fuzzy_match(text, &self.text, self.ignore_case)
+                .map(|(_, ranges)| ranges)
+                .unwrap_or_default();
+        }
+        match &self.regex {
+            Some(regex) => regex
+                .find_iter(text)
+                .map(|m| m.range())
+                .filter(|r| !r.is_empty())
+                .collect(),
+            None if self.ignore_case => match_ranges(text, &self.text),
+            None if self.text.is_empty() => Vec::new(),
+            None => text
+                .match_indices(&self.text)
+                .map(|(start, m)| start..start + m.len())
+                .collect(),
+        }
+    }
+
+    /// How well a fuzzy query matches `text`, higher is better; `None` for no match. Substring
+    /// and regex queries don't rank, so every match scores 0.
+    pub fn score(&self, text: &str) -> Option<i64> {
+        if self.fuzzy {
+            fuzzy_match(text, &self.text, self.ignore_case).map(|(score, _)| score)
+        } else {
+            self.is_match(text).then_some(0)
+        }
+    }
+}
+
+const FUZZY_MATCH: i64 = 16;
+const FUZZY_CONSECUTIVE: i64 = 12;
+const FUZZY_SEGMENT_START: i64 = 10;
+const FUZZY_GAP: i64 = 1;
+
+/// Best placement of the characters of `pattern`, in order, in `text`: its score and the byte
+/// ranges of the matched characters. Each matched character scores, more when it follows the
+/// previous one directly or starts a path segment or word, less the further it is from the
+/// previous one.
+fn fuzzy_match(text: &str, pattern: &str, ignore_case: bool) -> Option<(i64, Vec<Range<usize>>)> {
+    let fold = |ch: char| {
+        if ignore_case {
+            ch.to_lowercase().next().unwrap_or(ch)
+        } else {
+            ch
+        }
+    };
+    let needle: Vec<char> = pattern.chars().map(fold).collect();
+    if needle.is_empty() {
+        return None;
+    }
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let n = chars.len();
+    if n < needle.len() {
+        return None;
+    }
+    let bonus: Vec<i64> = (0..n)
+        .map(|j| {
+            let ch = chars[j].1;
+            let starts = match j.checked_sub(1).map(|prev| chars[prev].1) {
+                None => true,
+                Some(prev) => !prev.is_alphanumeric() || (prev.is_lowercase() && ch.is_uppercase()),
+            };
+            if starts && ch.is_alphanumeric() {
+                FUZZY_SEGMENT_START
+            } else {
+                0
+            }
+        })
+        .collect();
+    // best[i][j]: top score with needle[i] matched at text char j; from[i][j]: where needle[i-1]
+    // was matched for it.
+    let mut best = vec![vec![None::<i64>; n]; needle.len()];
+    let mut from = vec![vec![0usize; n]; needle.len()];
+    for (i, &want) in needle.iter().enumerate() {
+        // Best of best[i-1][k] + k * FUZZY_GAP over k < j - 1, so gaps cost per skipped char.
+        let mut gapped: Option<(i64, usize)> = None;
+        for j in 0..n {
+            if i > 0 && j >= 2 {
+                if let Some(score) = best[i - 1][j - 2] {
+                    let candidate = score + (j - 2) as i64 * FUZZY_GAP;
+                    if gapped.map_or(true, |(top, _)| candidate > top) {
+                        gapped = Some((candidate, j - 2));
+                    }
+                }
+            }
+            if fold(chars[j].1) != want {
+                continue;
+            }
+            let here = FUZZY_MATCH + bonus[j];
+            if i == 0 {
+                best[i][j] = Some(here - j as i64 * FUZZY_GAP);
+                continue;
+            }
+            let adjacent = j
+                .checked_sub(1)
+                .and_then(|k| best[i - 1][k])
+                .map(|score| (score + FUZZY_CONSECUTIVE, j - 1));
+            let skipped = gapped.map(|(top, k)| (top - (j - 1) as i64 * FUZZY_GAP, k));
+            let pick = match (adjacent, skipped) {
+                (Some(a), Some(s)) => Some(if a.0 >= s.0 { a } else { s }),
+                (a, s) => a.or(s),
+            };
+            if let Some((score, k)) = pick {
+                best[i][j] = Some(score + here);
+                from[i][j] = k;
+            }
+        }
+    }
+    let last = needle.len() - 1;
+    let (mut j, score) = (0..n)
+        .filter_map(|j| best[last][j].map(|score| (j, score)))
+        .max_by_key(|&(j, score)| (score, std::cmp::Reverse(j)))?;
+    let mut ranges = Vec::with_capacity(needle.len());
+    for i in (0..needle.len()).rev() {
+        let (start, ch) = chars[j];
+        ranges.push(start..start + ch.len_utf8());
+        j = from[i][j];
+    }
+    ranges.reverse();
+    Some((score, ranges))
+}
+
+/// What a search looks at: key names (and the path to them), scalar values, or both.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SearchScope {
+    #[default]
+    Both,
+    Keys,
+    Values,
+}
+
+impl SearchScope {
+    /// The scope after this one, for cycling with Ctrl+k while typing a query.
+    pub fn next(self) -> Self {
+        match self {
+            SearchScope::Both => SearchScope::Keys,
+            SearchScope::Keys => SearchScope::Values,
+            SearchScope::Values => SearchScope::Both,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchScope::Both => "keys+values",
+            SearchScope::Keys => "keys",
+            SearchScope::Values => "values",
+        }
+    }
+
+    pub fn keys(self) -> bool {
+        self != SearchScope::Values
+    }
+
+    pub fn values(self) -> bool {
+        self != SearchScope::Keys
+    }
+}
+
+/// Split a `k:` or `v:` prefix off a typed query; without one the query keeps `scope`.
+pub fn parse_query(text: &str, scope: SearchScope) -> (String, SearchScope) {
+    let text = text.trim();
+    if let Some(rest) = text.strip_prefix("k:") {
+        (rest.trim().to_string(), SearchScope::Keys)
+    } else if let Some(rest) = text.strip_prefix("v:") {
+        (rest.trim().to_string(), SearchScope::Values)
+    } else {
+        (text.to_string(), scope)
+    }
+}
+
+pub fn matches_row(row: &VisibleRow, query: &Query, scope: SearchScope) -> bool {
+    matches_fields(
+        &row.path.dot_path(),
+        &row.display_key,
+        &row.display_value_preview,
+        query,
+        scope,
+    )
+}
+
+/// Match of `query` against a node's path and key and/or its value preview.
+pub fn matches_fields(
+    dot_path: &str,
+    key: &str,
+    value: &str,
+    query: &Query,
+    scope: SearchScope,
+) -> bool {
+    (scope.keys() && (query.is_match(dot_path) || query.is_match(key)))
+        || (scope.values() && query.is_match(value))
+}
+
+/// Best score of `query` over the fields of `row` that `scope` looks at.
+pub fn score_row(row: &VisibleRow, query: &Query, scope: SearchScope) -> Option<i64> {
+    let keys = [row.path.dot_path(), row.display_key.clone()];
+    let keys = keys.iter().filter(|_| scope.keys());
+    let value = Some(&row.display_value_preview).filter(|_| scope.values());
+    keys.chain(value).filter_map(|text| query.score(text)).max()
+}
+
+/// Byte ranges of the non-overlapping case-insensitive matches of `query` in `text`, in the
+/// original text so the matched substring can be highlighted as written.
+pub fn match_ranges(text: &str, query: &str) -> Vec<Range<usize>> {
+    let needle: Vec<char> = query.to_lowercase().chars().collect();
+    let mut ranges = Vec::new();
+    if needle.is_empty() {
+        return ranges;
+    }
+    let mut from = 0;
+    while let Some((offset, _)) = text[from..]
+        .char_indices()
+        .find(|&(offset, _)| match_len_at(&text[from + offset..], &needle).is_some())
+    {
+        let start = from + offset;
+        let end = start + match_len_at(&text[start..], &needle).unwrap_or_default();
+        ranges.push(start..end);
+        from = end;
+    }
+    ranges
+}
+
+/// Length in bytes of the prefix of `text` whose lowercase form is `needle`, if any.
+fn match_len_at(text: &str, needle: &[char]) -> Option<usize> {
+    let mut matched = 0;
+    for (offset, ch) in text.char_indices() {
+        for lower in ch.to_lowercase() {
+            if needle.get(matched) != Some(&lower) {
+                return None;
+            }
+            matched += 1;
+        }
+        if matched == needle.len() {
+            return Some(offset + ch.len_utf8());
+        }
+    }
+    None
 }
 
 pub fn next_match(matches: &[usize], current: usize) -> Option<usize> {
@@ -31,10 +428,10 @@ pub fn prev_match(matches: &[usize], current: usize) -> Option<usize> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::yaml_model::{NodePath, PathSegment, VisibleRow, NodeType};
+    use crate::yaml_model::{NodeOrigin, NodePath, NodeType, PathSegment, VisibleRow};
     use pretty_assertions::assert_eq;
 
-    fn row(path: &str, key: &str) -> VisibleRow {
+    fn row(path: &str, key: &str, value: &str) -> VisibleRow {
         let path = NodePath(
             path.split('.')
                 .filter(|s| !s.is_empty())
@@ -51,18 +448,159 @@ mod tests {
             path,
             depth: 0,
             display_key: key.to_string(),
-            display_value_preview: String::new(),
+            display_value_preview: value.to_string(),
+            value_size: 0,
             node_type: NodeType::String,
             is_container: false,
+            origin: NodeOrigin::Own,
         }
     }
 
     #[test]
     fn match_logic() {
-        let row = row("server.tls.enabled", "enabled");
-        assert!(matches_row(&row, "tls"));
-        assert!(matches_row(&row, "enabled"));
-        assert!(!matches_row(&row, "missing"));
+        let row = row("server.tls.enabled", "enabled", "true");
+        assert!(matches_row(&row, &Query::plain("TLS"), SearchScope::Both));
+        assert!(matches_row(
+            &row,
+            &Query::plain("enabled"),
+            SearchScope::Keys
+        ));
+        assert!(!matches_row(
+            &row,
+            &Query::plain("missing"),
+            SearchScope::Both
+        ));
+    }
+
+    #[test]
+    fn regex_queries_match_paths_and_report_errors() {
+        let query = Query::parse(r"re:^items\.\d+\.name$", CaseMode::Insensitive).unwrap();
+        assert!(query.is_regex());
+        assert_eq!(query.text(), r"^items\.\d+\.name$");
+        assert!(matches_row(
+            &row("items.3.name", "name", "web"),
+            &query,
+            SearchScope::Keys
+        ));
+        assert!(!matches_row(
+            &row("items.3.name.first", "first", "web"),
+            &query,
+            SearchScope::Keys
+        ));
+        // Case-insensitive like plain text.
+        let query = Query::parse("re:^image", CaseMode::Insensitive).unwrap();
+        assert!(matches_row(
+            &row("a", "a", "Image: nginx"),
+            &query,
+            SearchScope::Values
+        ));
+
+        let query = Query::parse("re:p[o0]rt", CaseMode::Insensitive).unwrap();
+        assert_eq!(query.ranges("port P0RT"), vec![0..4, 5..9]);
+        assert!(Query::parse("re:x*", CaseMode::Insensitive)
+            .unwrap()
+            .ranges("abc")
+            .is_empty());
+
+        assert_eq!(
+            Query::parse("re:(spec", CaseMode::Insensitive)
+                .unwrap_err()
+                .to_string(),
+            "Invalid regex: unclosed group"
+        );
+        // Without the prefix regex syntax is literal.
+        assert!(Query::parse("a.b", CaseMode::Insensitive)
+            .unwrap()
+            .is_match("A.B"));
+        assert!(!Query::parse("a.b", CaseMode::Insensitive)
+            .unwrap()
+            .is_match("axb"));
+    }
+
+    #[test]
+    fn scope_picks_keys_or_values() {
+        let row = row("server.port", "port", "8080");
+        assert!(matches_row(&row, &Query::plain("8080"), SearchScope::Both));
+        assert!(matches_row(
+            &row,
+            &Query::plain("8080"),
+            SearchScope::Values
+        ));
+        assert!(!matches_row(&row, &Query::plain("8080"), SearchScope::Keys));
+        assert!(!matches_row(
+            &row,
+            &Query::plain("port"),
+            SearchScope::Values
+        ));
+
+        assert_eq!(
+            parse_query("v: 8080", SearchScope::Both),
+            ("8080".to_string(), SearchScope::Values)
+        );
+        assert_eq!(
+            parse_query("k:port", SearchScope::Values),
+            ("port".to_string(), SearchScope::Keys)
+        );
+        assert_eq!(
+            parse_query(" port ", SearchScope::Keys),
+            ("port".to_string(), SearchScope::Keys)
+        );
+        assert_eq!(SearchScope::Values.next(), SearchScope::Both);
+    }
+
+    #[test]
+    fn match_ranges_keep_original_case() {
+        assert_eq!(match_ranges("Port", "port"), vec![0..4]);
+        assert_eq!(match_ranges("httpPort_PORT", "PoRt"), vec![4..8, 9..13]);
+        assert_eq!(match_ranges("Größe größe", "GRÖ"), vec![0..4, 8..12]);
+        assert_eq!(match_ranges("aaa", "aa"), vec![0..2]);
+        assert!(match_ranges("name", "").is_empty());
+        assert!(match_ranges("name", "names").is_empty());
+    }
+
+    #[test]
+    fn case_modes_and_smart_case() {
+        let name = |text: &str, case| Query::parse(text, case).unwrap().is_match("Name");
+        assert!(name("name", CaseMode::Insensitive));
+        assert!(!name("name", CaseMode::Sensitive));
+        assert!(name("Name", CaseMode::Sensitive));
+        assert!(name("name", CaseMode::Smart));
+        assert!(!Query::parse("Name", CaseMode::Smart)
+            .unwrap()
+            .is_match("name"));
+        assert!(!name("re:^name", CaseMode::Sensitive));
+        // Escapes such as \D are not uppercase letters.
+        assert!(name(r"re:^n\D", CaseMode::Smart));
+        assert!(!name(r"re:^nA", CaseMode::Smart));
+
+        let query = Query::parse("Port", CaseMode::Sensitive).unwrap();
+        assert_eq!(query.ranges("port Port PORT"), vec![5..9]);
+        assert_eq!(CaseMode::parse("smart"), Some(CaseMode::Smart));
+        assert_eq!(CaseMode::Smart.next(), CaseMode::Insensitive);
+    }
+
+    #[test]
+    fn fuzzy_ranks_runs_and_segment_starts() {
+        let query = Query::parse("fz:srvtlsen", CaseMode::Insensitive).unwrap();
+        assert!(query.is_fuzzy());
+        let best = query.score("server.tls.enabled").unwrap();
+        assert!(
+            best > query
+                .score("services.a.very.long.tls.list.environment")
+                .unwrap()
+        );
+        assert!(query.score("tls.server.enabled").is_none());
+        assert_eq!(
+            query.ranges("server.tls.enabled"),
+            vec![0..1, 2..3, 3..4, 7..8, 8..9, 9..10, 11..12, 12..13]
+        );
+        // A run scores above the same letters scattered.
+        let port = Query::parse("fz:port", CaseMode::Insensitive).unwrap();
+        assert!(port.score("ports").unwrap() > port.score("p_o_r_t").unwrap());
+        assert!(!Query::parse("fz:", CaseMode::Insensitive)
+            .unwrap()
+            .is_match("anything"));
+        assert_eq!(Query::plain("tls").score("server.tls"), Some(0));
     }
 
     #[test]
@@ -0,0 +1,48 @@
+// `yed set FILE PATH VALUE`: change one scalar from a script, without opening the TUI.
+
+use std::fs;
+use std::io::Write as _;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use yaml_rust2::Yaml;
+
+use crate::settings;
+use crate::yaml_model::{get_node, parse_scalar_input, resolve_dot_path, YamlModel};
+
+/// Set the value at dot path `dot` in `file` to `value` (read like a value typed in the editor)
+/// and save, or print the result instead with `dry_run`. Missing keys are added with `create`.
+pub fn run(file: &Path, dot: &str, value: &str, create: bool, dry_run: bool) -> Result<()> {
+    let canonical = fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+    // An invalid settings file is an error here, since nobody would see a warning.
+    let settings = settings::for_file(&canonical)?;
+    if !dry_run {
+        if let Some(glob) = settings.readonly_match(&canonical) {
+            bail!("{} matches readonly '{glob}'", file.display());
+        }
+    }
+    let mut model = YamlModel::load(file).map_err(|err| anyhow!("{}: {err}", file.display()))?;
+    model.configure(&settings);
+    let value = parse_scalar_input(value)?;
+    let path = match resolve_dot_path(model.root(), dot) {
+        Some(path) => path,
+        None if create => model.create_dot_path(dot)?,
+        None => bail!("No node at {dot} (use --create to add missing keys)"),
+    };
+    match get_node(model.root(), &path)? {
+        Yaml::Hash(_) => bail!("{dot} is a mapping; set only changes scalars"),
+        Yaml::Array(_) => bail!("{dot} is a sequence; set only changes scalars"),
+        _ => {}
+    }
+    model.edit_value(&path, value)?;
+    if dry_run {
+        let (text, _) = model.render()?;
+        std::io::stdout().write_all(text.as_bytes())?;
+        return Ok(());
+    }
+    let report = model.save()?;
+    if let Some(err) = report.backup_error {
+        eprintln!("Backup failed: {err}");
+    }
+    Ok(())
+}
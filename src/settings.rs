@@ -0,0 +1,802 @@
+// Editor settings merged from defaults, the user config, a per-project `.yed.toml`
+// and command-line flags, remembering where each effective value came from.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use glob::{MatchOptions, Pattern};
+use toml::{Table, Value};
+
+use crate::search::CaseMode;
+
+/// Settings file looked up from the opened file's directory towards the repository root.
+pub const PROJECT_FILE: &str = ".yed.toml";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SettingSource {
+    Default,
+    User(PathBuf),
+    Project(PathBuf),
+    Cli,
+}
+
+impl fmt::Display for SettingSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingSource::Default => write!(f, "default"),
+            SettingSource::User(path) => write!(f, "user {}", path.display()),
+            SettingSource::Project(path) => write!(f, "project {}", path.display()),
+            SettingSource::Cli => write!(f, "command line"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: SettingSource,
+}
+
+impl<T> Setting<T> {
+    fn default(value: T) -> Self {
+        Self {
+            value,
+            source: SettingSource::Default,
+        }
+    }
+
+    fn set(&mut self, value: T, source: &SettingSource) {
+        self.value = value;
+        self.source = source.clone();
+    }
+}
+
+/// How a save writes the document back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaveMode {
+    /// Re-emit the whole document.
+    Full,
+    /// Rewrite only the changed scalars in the loaded text, re-emitting when that is impossible.
+    Minimal,
+}
+
+impl fmt::Display for SaveMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveMode::Full => write!(f, "full"),
+            SaveMode::Minimal => write!(f, "minimal"),
+        }
+    }
+}
+
+/// Values given on the command line; they win over every settings file.
+#[derive(Clone, Debug, Default)]
+pub struct CliOverrides {
+    pub indent_width: Option<usize>,
+    pub flow_max_items: Option<usize>,
+    pub schema: Option<PathBuf>,
+    pub read_only: bool,
+    /// Plain tree rows (`--no-color` or a non-empty `NO_COLOR`).
+    pub no_color: bool,
+    /// Skip the check that a save would reproduce the loaded file.
+    pub no_integrity_check: bool,
+    /// Copy the file's contents aside before saving (once per session unless `backup.once = false`).
+    pub backup: bool,
+    /// State file listing recently opened files; `None` keeps no list.
+    pub recent: Option<PathBuf>,
+    /// State file keeping committed search queries; `None` keeps them for this session only.
+    pub search_history: Option<PathBuf>,
+    /// State file keeping the node selected when each file was left; `None` remembers none.
+    pub positions: Option<PathBuf>,
+    /// Levels of the tree shown when a file opens (`--depth`).
+    pub depth: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// Spaces per nesting level when saving.
+    pub indent_width: Setting<usize>,
+    /// Whether saved files start with `---`.
+    pub document_start: Setting<bool>,
+    /// Collections of at most this many scalars are saved in flow style (`[a, b]`); 0 never.
+    pub flow_max_items: Setting<usize>,
+    /// Whether saves re-emit the document or splice edits into the loaded text.
+    pub save_mode: Setting<SaveMode>,
+    /// Whether opening a file checks, in the background, that saving it would not alter it.
+    pub integrity_check: Setting<bool>,
+    /// Keys whose value labels a mapping inside a sequence (first present key wins).
+    pub identity_keys: Setting<Vec<String>>,
+    /// File-name globs hidden from the file picker.
+    pub ignore: Setting<Vec<String>>,
+    /// Schema document, already resolved against the file that named it.
+    pub schema: Setting<Option<PathBuf>>,
+    /// Globs (relative to `root`) of files that open read-only.
+    pub readonly: Setting<Vec<String>>,
+    /// Command run after each save (`hooks.post_save`); `{file}` is replaced by the saved path.
+    pub post_save_hook: Setting<Option<String>>,
+    /// Seconds before a hook is killed (`hooks.timeout_secs`).
+    pub hook_timeout_secs: Setting<u64>,
+    /// Whether saves first copy the file's previous contents aside (`backup.enabled`).
+    pub backup: Setting<bool>,
+    /// Appended to the file name to name the backup (`backup.suffix`).
+    pub backup_suffix: Setting<String>,
+    /// Directory for backups, already resolved against the file that named it (`backup.dir`).
+    pub backup_dir: Setting<Option<PathBuf>>,
+    /// Keep numbered backups rather than only the most recent (`backup.numbered`).
+    pub backup_numbered: Setting<bool>,
+    /// Back up only before the first save of a session, keeping the original (`backup.once`).
+    pub backup_once: Setting<bool>,
+    /// Lines wider than this are reported by the lint pass (`lint.max_line_length`).
+    pub lint_max_line_length: Setting<usize>,
+    /// How searches treat case (`search.case`): insensitive, sensitive or smart.
+    pub search_case: Setting<CaseMode>,
+    /// Whether sequence items start with their `[n]` index in the tree (`tree.show_indices`).
+    pub show_indices: Setting<bool>,
+    /// Directory of the project file, or of the opened file when there is none.
+    pub root: PathBuf,
+}
+
+impl Settings {
+    pub fn defaults(root: &Path) -> Self {
+        Self {
+            indent_width: Setting::default(2),
+            document_start: Setting::default(true),
+            flow_max_items: Setting::default(0),
+            save_mode: Setting::default(SaveMode::Full),
+            integrity_check: Setting::default(true),
+            identity_keys: Setting::default(Vec::new()),
+            ignore: Setting::default(Vec::new()),
+            schema: Setting::default(None),
+            readonly: Setting::default(Vec::new()),
+            post_save_hook: Setting::default(None),
+            hook_timeout_secs: Setting::default(30),
+            backup: Setting::default(false),
+            backup_suffix: Setting::default(".bak".to_string()),
+            backup_dir: Setting::default(None),
+            backup_numbered: Setting::default(false),
+            backup_once: Setting::default(true),
+            lint_max_line_length: Setting::default(120),
+            search_case: Setting::default(CaseMode::Insensitive),
+            show_indices: Setting::default(false),
+            root: root.to_path_buf(),
+        }
+    }
+
+    /// The first readonly glob matching `file`, if any.
+    pub fn readonly_match(&self, file: &Path) -> Option<&str> {
+        let relative = file.strip_prefix(&self.root).unwrap_or(file);
+        self.readonly
+            .value
+            .iter()
+            .find(|glob| glob_matches(glob, relative))
+            .map(String::as_str)
+    }
+
+    /// Whether the file picker should hide an entry with this file name.
+    pub fn is_ignored(&self, name: &str) -> bool {
+        self.ignore
+            .value
+            .iter()
+            .any(|glob| glob_matches(glob, Path::new(name)))
+    }
+
+    /// (name, effective value, source) for each setting, for the settings overlay.
+    pub fn describe(&self) -> Vec<(&'static str, String, &SettingSource)> {
+        let list = |items: &[String]| {
+            if items.is_empty() {
+                "(none)".to_string()
+            } else {
+                items.join(", ")
+            }
+        };
+        vec![
+            (
+                "indent_width",
+                self.indent_width.value.to_string(),
+                &self.indent_width.source,
+            ),
+            (
+                "document_start",
+                self.document_start.value.to_string(),
+                &self.document_start.source,
+            ),
+            (
+                "flow_max_items",
+                self.flow_max_items.value.to_string(),
+                &self.flow_max_items.source,
+            ),
+            (
+                "save_mode",
+                self.save_mode.value.to_string(),
+                &self.save_mode.source,
+            ),
+            (
+                "integrity_check",
+                self.integrity_check.value.to_string(),
+                &self.integrity_check.source,
+            ),
+            (
+                "identity_keys",
+                list(&self.identity_keys.value),
+                &self.identity_keys.source,
+            ),
+            ("ignore", list(&self.ignore.value), &self.ignore.source),
+            (
+                "schema",
+                self.schema
+                    .value
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|| "(none)".to_string()),
+                &self.schema.source,
+            ),
+            (
+                "readonly",
+                list(&self.readonly.value),
+                &self.readonly.source,
+            ),
+            (
+                "hooks.post_save",
+                self.post_save_hook
+                    .value
+                    .clone()
+                    .unwrap_or_else(|| "(none)".to_string()),
+                &self.post_save_hook.source,
+            ),
+            (
+                "hooks.timeout_secs",
+                self.hook_timeout_secs.value.to_string(),
+                &self.hook_timeout_secs.source,
+            ),
+            (
+                "backup.enabled",
+                self.backup.value.to_string(),
+                &self.backup.source,
+            ),
+            (
+                "backup.suffix",
+                self.backup_suffix.value.clone(),
+                &self.backup_suffix.source,
+            ),
+            (
+                "backup.dir",
+                self.backup_dir
+                    .value
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|| "(next to the file)".to_string()),
+                &self.backup_dir.source,
+            ),
+            (
+                "backup.numbered",
+                self.backup_numbered.value.to_string(),
+                &self.backup_numbered.source,
+            ),
+            (
+                "backup.once",
+                self.backup_once.value.to_string(),
+                &self.backup_once.source,
+            ),
+            (
+                "lint.max_line_length",
+                self.lint_max_line_length.value.to_string(),
+                &self.lint_max_line_length.source,
+            ),
+            (
+                "search.case",
+                self.search_case.value.to_string(),
+                &self.search_case.source,
+            ),
+            (
+                "tree.show_indices",
+                self.show_indices.value.to_string(),
+                &self.show_indices.source,
+            ),
+        ]
+    }
+}
+
+/// `$XDG_CONFIG_HOME/yed/config.toml`, falling back to `~/.config/yed/config.toml`.
+pub fn user_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("yed").join("config.toml"))
+}
+
+/// Nearest `.yed.toml` from `start_dir` upwards, not looking past the repository root
+/// (the first ancestor containing `.git`).
+pub fn discover_project_file(start_dir: &Path) -> Option<PathBuf> {
+    for dir in start_dir.ancestors() {
+        let candidate = dir.join(PROJECT_FILE);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if dir.join(".git").exists() {
+            return None;
+        }
+    }
+    None
+}
+
+/// Discover and read the settings that apply to files in `dir`.
+pub fn load(dir: &Path, user_config: Option<&Path>, cli: &CliOverrides) -> Result<Settings> {
+    let read = |path: &Path| -> Result<Option<(PathBuf, String)>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Ok(Some((path.to_path_buf(), text)))
+    };
+    let user = match user_config {
+        Some(path) => read(path)?,
+        None => None,
+    };
+    let project = match discover_project_file(dir) {
+        Some(path) => read(&path)?,
+        None => None,
+    };
+    resolve(dir, user.as_ref(), project.as_ref(), cli)
+}
+
+/// Settings for one file outside the editor (the `set` and `get` subcommands), with no
+/// command-line overrides.
+pub fn for_file(file: &Path) -> Result<Settings> {
+    let dir = file
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let user_config = user_config_path();
+    load(&dir, user_config.as_deref(), &CliOverrides::default())
+        .map_err(|err| anyhow!("Settings: {err}"))
+}
+
+/// Merge settings file contents (path, TOML text) over the defaults, then apply `cli`.
+/// Precedence: command line > project > user > defaults.
+pub fn resolve(
+    dir: &Path,
+    user: Option<&(PathBuf, String)>,
+    project: Option<&(PathBuf, String)>,
+    cli: &CliOverrides,
+) -> Result<Settings> {
+    let root = project.and_then(|(path, _)| path.parent()).unwrap_or(dir);
+    let mut settings = Settings::defaults(root);
+    if let Some((path, text)) = user {
+        apply_file(
+            &mut settings,
+            text,
+            &SettingSource::User(path.clone()),
+            path,
+        )?;
+    }
+    if let Some((path, text)) = project {
+        apply_file(
+            &mut settings,
+            text,
+            &SettingSource::Project(path.clone()),
+            path,
+        )?;
+    }
+    if let Some(indent) = cli.indent_width {
+        settings
+            .indent_width
+            .set(check_indent(indent)?, &SettingSource::Cli);
+    }
+    if let Some(items) = cli.flow_max_items {
+        settings.flow_max_items.set(items, &SettingSource::Cli);
+    }
+    if let Some(schema) = &cli.schema {
+        settings
+            .schema
+            .set(Some(schema.clone()), &SettingSource::Cli);
+    }
+    if cli.read_only {
+        settings
+            .readonly
+            .set(vec!["**".to_string()], &SettingSource::Cli);
+    }
+    if cli.no_integrity_check {
+        settings.integrity_check.set(false, &SettingSource::Cli);
+    }
+    if cli.backup {
+        settings.backup.set(true, &SettingSource::Cli);
+    }
+    Ok(settings)
+}
+
+fn apply_file(
+    settings: &mut Settings,
+    text: &str,
+    source: &SettingSource,
+    path: &Path,
+) -> Result<()> {
+    let table: Table = text
+        .parse()
+        .map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    let invalid = |key: &str| anyhow!("{}: invalid '{}'", path.display(), key);
+    for (key, value) in &table {
+        match key.as_str() {
+            "indent_width" => {
+                let indent = value
+                    .as_integer()
+                    .and_then(|n| usize::try_from(n).ok())
+                    .ok_or_else(|| invalid(key))?;
+                settings.indent_width.set(check_indent(indent)?, source);
+            }
+            "document_start" => {
+                let enabled = value.as_bool().ok_or_else(|| invalid(key))?;
+                settings.document_start.set(enabled, source);
+            }
+            "flow_max_items" => {
+                let items = value
+                    .as_integer()
+                    .and_then(|n| usize::try_from(n).ok())
+                    .ok_or_else(|| invalid(key))?;
+                settings.flow_max_items.set(items, source);
+            }
+            "save_mode" => {
+                let mode = match value.as_str() {
+                    Some("full") => SaveMode::Full,
+                    Some("minimal") => SaveMode::Minimal,
+                    _ => return Err(invalid(key)),
+                };
+                settings.save_mode.set(mode, source);
+            }
+            "integrity_check" => {
+                let enabled = value.as_bool().ok_or_else(|| invalid(key))?;
+                settings.integrity_check.set(enabled, source);
+            }
+            "identity_keys" => {
+                let keys = string_list(value).ok_or_else(|| invalid(key))?;
+                settings.identity_keys.set(keys, source);
+            }
+            "ignore" => {
+                let globs = string_list(value).ok_or_else(|| invalid(key))?;
+                settings.ignore.set(globs, source);
+            }
+            "readonly" => {
+                let globs = string_list(value).ok_or_else(|| invalid(key))?;
+                settings.readonly.set(globs, source);
+            }
+            "schema" => {
+                let schema = value.as_str().ok_or_else(|| invalid(key))?;
+                // Relative schema paths are resolved against the file that names them.
+                let base = path.parent().unwrap_or(Path::new("."));
+                settings.schema.set(Some(base.join(schema)), source);
+            }
+            "hooks" => {
+                let hooks = value.as_table().ok_or_else(|| invalid(key))?;
+                for (name, value) in hooks {
+                    match name.as_str() {
+                        "post_save" => {
+                            let command =
+                                value.as_str().ok_or_else(|| invalid("hooks.post_save"))?;
+                            settings
+                                .post_save_hook
+                                .set(Some(command.to_string()), source);
+                        }
+                        "timeout_secs" => {
+                            let secs = value
+                                .as_integer()
+                                .and_then(|n| u64::try_from(n).ok())
+                                .filter(|&n| n > 0)
+                                .ok_or_else(|| invalid("hooks.timeout_secs"))?;
+                            settings.hook_timeout_secs.set(secs, source);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "backup" => {
+                let backup = value.as_table().ok_or_else(|| invalid(key))?;
+                for (name, value) in backup {
+                    match name.as_str() {
+                        "enabled" => {
+                            let enabled =
+                                value.as_bool().ok_or_else(|| invalid("backup.enabled"))?;
+                            settings.backup.set(enabled, source);
+                        }
+                        "suffix" => {
+                            let suffix = value
+                                .as_str()
+                                .filter(|s| !s.is_empty() && !s.contains(['/', '\\']))
+                                .ok_or_else(|| invalid("backup.suffix"))?;
+                            settings.backup_suffix.set(suffix.to_string(), source);
+                        }
+                        "dir" => {
+                            let dir = value.as_str().ok_or_else(|| invalid("backup.dir"))?;
+                            let base = path.parent().unwrap_or(Path::new("."));
+                            settings.backup_dir.set(Some(base.join(dir)), source);
+                        }
+                        "numbered" => {
+                            let numbered =
+                                value.as_bool().ok_or_else(|| invalid("backup.numbered"))?;
+                            settings.backup_numbered.set(numbered, source);
+                        }
+                        "once" => {
+                            let once = value.as_bool().ok_or_else(|| invalid("backup.once"))?;
+                            settings.backup_once.set(once, source);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "lint" => {
+                let lint = value.as_table().ok_or_else(|| invalid(key))?;
+                if let Some(value) = lint.get("max_line_length") {
+                    let length = value
+                        .as_integer()
+                        .and_then(|n| usize::try_from(n).ok())
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| invalid("lint.max_line_length"))?;
+                    settings.lint_max_line_length.set(length, source);
+                }
+            }
+            "search" => {
+                let search = value.as_table().ok_or_else(|| invalid(key))?;
+                if let Some(value) = search.get("case") {
+                    let case = value
+                        .as_str()
+                        .and_then(CaseMode::parse)
+                        .ok_or_else(|| invalid("search.case"))?;
+                    settings.search_case.set(case, source);
+                }
+            }
+            "tree" => {
+                let tree = value.as_table().ok_or_else(|| invalid(key))?;
+                if let Some(value) = tree.get("show_indices") {
+                    let show = value
+                        .as_bool()
+                        .ok_or_else(|| invalid("tree.show_indices"))?;
+                    settings.show_indices.set(show, source);
+                }
+            }
+            // Unknown keys are left for other tools and newer versions.
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn check_indent(indent: usize) -> Result<usize> {
+    if (1..=8).contains(&indent) {
+        Ok(indent)
+    } else {
+        Err(anyhow!(
+            "indent_width must be between 1 and 8 (got {indent})"
+        ))
+    }
+}
+
+fn string_list(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::String(s) => Some(vec![s.clone()]),
+        Value::Array(items) => items.iter().map(|v| v.as_str().map(String::from)).collect(),
+        _ => None,
+    }
+}
+
+fn glob_matches(glob: &str, path: &Path) -> bool {
+    let options = MatchOptions {
+        require_literal_separator: true,
+        ..MatchOptions::new()
+    };
+    Pattern::new(glob).is_ok_and(|pattern| pattern.matches_path_with(path, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn temp_tree(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("yed_settings_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("repo/.git")).unwrap();
+        std::fs::create_dir_all(root.join("repo/deploy/secrets")).unwrap();
+        root
+    }
+
+    #[test]
+    fn discovery_walks_up_to_repo_root() {
+        let root = temp_tree("discover");
+        let nested = root.join("repo/deploy/secrets");
+        assert_eq!(discover_project_file(&nested), None);
+        // Files above the repository root are not considered.
+        std::fs::write(root.join(PROJECT_FILE), "indent_width = 4\n").unwrap();
+        assert_eq!(discover_project_file(&nested), None);
+        std::fs::write(root.join("repo").join(PROJECT_FILE), "").unwrap();
+        assert_eq!(
+            discover_project_file(&nested),
+            Some(root.join("repo").join(PROJECT_FILE))
+        );
+        std::fs::write(root.join("repo/deploy").join(PROJECT_FILE), "").unwrap();
+        assert_eq!(
+            discover_project_file(&nested),
+            Some(root.join("repo/deploy").join(PROJECT_FILE))
+        );
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn precedence_is_cli_then_project_then_user() {
+        let root = temp_tree("precedence");
+        let repo = root.join("repo");
+        let user = root.join("user.toml");
+        std::fs::write(&user, "indent_width = 3\ndocument_start = false\nidentity_keys = [\"id\"]\nsave_mode = \"minimal\"\n").unwrap();
+        std::fs::write(
+            repo.join(PROJECT_FILE),
+            "indent_width = 4\nschema = \"schemas/app.json\"\nreadonly = [\"deploy/secrets/**\"]\n",
+        )
+        .unwrap();
+        let dir = repo.join("deploy");
+        let settings = load(&dir, Some(&user), &CliOverrides::default()).unwrap();
+        let project = SettingSource::Project(repo.join(PROJECT_FILE));
+        assert_eq!(
+            settings.indent_width,
+            Setting {
+                value: 4,
+                source: project.clone()
+            }
+        );
+        assert_eq!(settings.document_start.value, false);
+        assert_eq!(
+            settings.document_start.source,
+            SettingSource::User(user.clone())
+        );
+        assert_eq!(settings.identity_keys.value, vec!["id".to_string()]);
+        assert_eq!(settings.save_mode.value, SaveMode::Minimal);
+        assert_eq!(settings.schema.value, Some(repo.join("schemas/app.json")));
+        assert_eq!(settings.ignore.source, SettingSource::Default);
+        assert_eq!(settings.root, repo);
+
+        let cli = CliOverrides {
+            indent_width: Some(8),
+            ..CliOverrides::default()
+        };
+        let settings = load(&dir, Some(&user), &cli).unwrap();
+        assert_eq!(
+            settings.indent_width,
+            Setting {
+                value: 8,
+                source: SettingSource::Cli
+            }
+        );
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn readonly_globs_match_relative_to_project() {
+        let root = temp_tree("readonly");
+        let repo = root.join("repo");
+        std::fs::write(
+            repo.join(PROJECT_FILE),
+            "readonly = \"deploy/secrets/**\"\nignore = [\"*.gen.yaml\"]\n",
+        )
+        .unwrap();
+        let settings = load(&repo.join("deploy"), None, &CliOverrides::default()).unwrap();
+        assert_eq!(
+            settings.readonly_match(&repo.join("deploy/secrets/db.yaml")),
+            Some("deploy/secrets/**")
+        );
+        assert_eq!(
+            settings.readonly_match(&repo.join("deploy/values.yaml")),
+            None
+        );
+        assert!(settings.is_ignored("api.gen.yaml"));
+        assert!(!settings.is_ignored("api.yaml"));
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn invalid_values_are_reported() {
+        let dir = Path::new("/");
+        let bad = (
+            PathBuf::from("/p/.yed.toml"),
+            "indent_width = 0\n".to_string(),
+        );
+        assert!(resolve(dir, None, Some(&bad), &CliOverrides::default()).is_err());
+        let bad = (
+            PathBuf::from("/p/.yed.toml"),
+            "save_mode = \"diff\"\n".to_string(),
+        );
+        assert!(resolve(dir, None, Some(&bad), &CliOverrides::default()).is_err());
+        let bad = (PathBuf::from("/p/.yed.toml"), "schema = 3\n".to_string());
+        assert!(resolve(dir, None, Some(&bad), &CliOverrides::default()).is_err());
+        let bad = (PathBuf::from("/p/.yed.toml"), "not toml".to_string());
+        assert!(resolve(dir, None, Some(&bad), &CliOverrides::default()).is_err());
+        let bad = (
+            PathBuf::from("/p/.yed.toml"),
+            "hooks.timeout_secs = 0\n".to_string(),
+        );
+        assert!(resolve(dir, None, Some(&bad), &CliOverrides::default()).is_err());
+        let bad = (
+            PathBuf::from("/p/.yed.toml"),
+            "search.case = \"upper\"\n".to_string(),
+        );
+        assert!(resolve(dir, None, Some(&bad), &CliOverrides::default()).is_err());
+        let good = (
+            PathBuf::from("/p/.yed.toml"),
+            "[search]\ncase = \"smart\"\n".to_string(),
+        );
+        let settings = resolve(dir, None, Some(&good), &CliOverrides::default()).unwrap();
+        assert_eq!(settings.search_case.value, CaseMode::Smart);
+        let bad = (
+            PathBuf::from("/p/.yed.toml"),
+            "tree.show_indices = \"yes\"\n".to_string(),
+        );
+        assert!(resolve(dir, None, Some(&bad), &CliOverrides::default()).is_err());
+        let bad = (
+            PathBuf::from("/p/.yed.toml"),
+            "flow_max_items = -1\n".to_string(),
+        );
+        assert!(resolve(dir, None, Some(&bad), &CliOverrides::default()).is_err());
+    }
+
+    #[test]
+    fn hooks_merge_per_key() {
+        let dir = Path::new("/p");
+        let user = (
+            PathBuf::from("/u/config.toml"),
+            "[hooks]\npost_save = \"yamllint -f parsable {file}\"\ntimeout_secs = 5\n".to_string(),
+        );
+        let project = (
+            PathBuf::from("/p/.yed.toml"),
+            "hooks.post_save = \"./check {file}\"\n".to_string(),
+        );
+        let settings = resolve(dir, Some(&user), Some(&project), &CliOverrides::default()).unwrap();
+        assert_eq!(
+            settings.post_save_hook.value.as_deref(),
+            Some("./check {file}")
+        );
+        assert_eq!(
+            settings.post_save_hook.source,
+            SettingSource::Project(project.0.clone())
+        );
+        assert_eq!(settings.hook_timeout_secs.value, 5);
+        assert_eq!(
+            settings.hook_timeout_secs.source,
+            SettingSource::User(user.0.clone())
+        );
+    }
+
+    #[test]
+    fn backup_table_and_flag() {
+        let dir = Path::new("/p");
+        let project = (
+            PathBuf::from("/p/.yed.toml"),
+            "[backup]\nsuffix = \"~\"\ndir = \".backups\"\nnumbered = true\nonce = false\n"
+                .to_string(),
+        );
+        let settings = resolve(dir, None, Some(&project), &CliOverrides::default()).unwrap();
+        assert!(!settings.backup.value);
+        assert_eq!(settings.backup_suffix.value, "~");
+        assert_eq!(
+            settings.backup_dir.value,
+            Some(PathBuf::from("/p/.backups"))
+        );
+        assert!(settings.backup_numbered.value);
+        assert!(!settings.backup_once.value);
+        let cli = CliOverrides {
+            backup: true,
+            ..CliOverrides::default()
+        };
+        let settings = resolve(dir, None, Some(&project), &cli).unwrap();
+        assert_eq!(
+            settings.backup,
+            Setting {
+                value: true,
+                source: SettingSource::Cli
+            }
+        );
+        let bad = (
+            PathBuf::from("/p/.yed.toml"),
+            "backup.suffix = \"a/b\"\n".to_string(),
+        );
+        assert!(resolve(dir, None, Some(&bad), &CliOverrides::default()).is_err());
+    }
+}
@@ -0,0 +1,584 @@
+// Metadata recovered from the parser event stream that `Yaml` values cannot carry
+// (anchor names, alias sites, duplicate keys, flow style), keyed by the node path it belongs to.
+
+use std::collections::{HashMap, HashSet};
+
+use yaml_rust2::parser::{MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::{Marker, Scanner, TScalarStyle, TokenType};
+use yaml_rust2::Event;
+
+use crate::yaml_model::{NodePath, PathSegment};
+
+/// Key under which YAML merges another mapping into the current one.
+pub const MERGE_KEY: &str = "<<";
+
+/// A key that appears more than once in the same mapping. The loader keeps only the last value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateKey {
+    pub path: NodePath,
+    /// 1-based source line of the repeated occurrence.
+    pub line: usize,
+}
+
+/// Where a scalar value was written in the scanned text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScalarSite {
+    /// Character offset of the scalar's first character (its quote or `|`/`>` indicator).
+    pub index: usize,
+    pub style: TScalarStyle,
+}
+
+/// Style a string value was written in, with the value itself: quotes only carry over while the
+/// value is the same.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Quoting {
+    pub style: TScalarStyle,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    /// Anchor name declared on the node at each path (`&name`).
+    pub anchors: HashMap<NodePath, String>,
+    /// Anchor name referenced by the alias at each path (`*name`).
+    pub aliases: HashMap<NodePath, String>,
+    /// Repeated keys, in document order.
+    pub duplicates: Vec<DuplicateKey>,
+    /// 1-based line where each node starts, in document order; mapping values start at their key.
+    pub lines: Vec<(usize, NodePath)>,
+    /// Collections written in flow style (`[a, b]`, `{k: v}`). Their contents are flow too.
+    pub flow: HashSet<NodePath>,
+    /// Scalar values (not keys) as scanned; edits do not move these.
+    pub scalars: HashMap<NodePath, ScalarSite>,
+    /// How each string value was written, so it is saved the same way while unchanged.
+    pub quoting: HashMap<NodePath, Quoting>,
+    /// How each mapping key was written, by the path of its entry.
+    pub key_quoting: HashMap<NodePath, Quoting>,
+}
+
+impl SourceMap {
+    /// Scan the first document of `input`. Returns an empty map if the input does not parse.
+    pub fn scan(input: &str) -> Self {
+        let mut anchor_names = Vec::new();
+        let mut flow_starts = HashSet::new();
+        for token in Scanner::new(input.chars()) {
+            match token.1 {
+                TokenType::Anchor(name) => anchor_names.push(name),
+                TokenType::FlowSequenceStart | TokenType::FlowMappingStart => {
+                    flow_starts.insert(token.0.index());
+                }
+                _ => {}
+            }
+        }
+        let mut walker = Walker {
+            frames: Vec::new(),
+            anchor_names,
+            flow_starts,
+            done: false,
+            map: SourceMap::default(),
+        };
+        let mut parser = Parser::new_from_str(input);
+        if parser.load(&mut walker, false).is_err() {
+            return SourceMap::default();
+        }
+        walker.map
+    }
+
+    pub fn anchor_at(&self, path: &NodePath) -> Option<&str> {
+        self.anchors.get(path).map(String::as_str)
+    }
+
+    /// Innermost node starting on or before `line` (1-based), e.g. for a linter's `file:line`.
+    pub fn path_at_line(&self, line: usize) -> Option<&NodePath> {
+        self.lines
+            .iter()
+            .take_while(|(start, _)| *start <= line)
+            .last()
+            .map(|(_, path)| path)
+    }
+
+    pub fn is_flow(&self, path: &NodePath) -> bool {
+        self.flow.contains(path)
+    }
+
+    /// Switch the collection at `path` between flow and block style; returns whether it is now flow.
+    pub fn toggle_flow(&mut self, path: &NodePath) -> bool {
+        if self.flow.remove(path) {
+            return false;
+        }
+        self.flow.insert(path.clone());
+        true
+    }
+
+    pub fn alias_at(&self, path: &NodePath) -> Option<&str> {
+        self.aliases.get(path).map(String::as_str)
+    }
+
+    /// Anchor names merged into the mapping at `map_path` via `<<`, in precedence order.
+    pub fn merge_sources(&self, map_path: &NodePath) -> Vec<String> {
+        let merge_path = map_path.child_key(MERGE_KEY);
+        if let Some(name) = self.alias_at(&merge_path) {
+            return vec![name.to_string()];
+        }
+        let mut indexed: Vec<(usize, &String)> = self
+            .aliases
+            .iter()
+            .filter_map(|(path, name)| match path.0.split_last() {
+                Some((PathSegment::Index(idx), parent)) if parent == merge_path.0.as_slice() => {
+                    Some((*idx, name))
+                }
+                _ => None,
+            })
+            .collect();
+        indexed.sort();
+        indexed.into_iter().map(|(_, name)| name.clone()).collect()
+    }
+
+    /// Move all metadata under `old` to `new` (after a key rename).
+    pub fn rename_prefix(&mut self, old: &NodePath, new: &NodePath) {
+        let rebased = |path: &NodePath| {
+            let mut next = new.0.clone();
+            next.extend_from_slice(&path.0[old.0.len()..]);
+            NodePath(next)
+        };
+        fn rebase<V>(
+            table: &mut HashMap<NodePath, V>,
+            old: &NodePath,
+            rebased: impl Fn(&NodePath) -> NodePath,
+        ) {
+            let moved: Vec<NodePath> = table
+                .keys()
+                .filter(|path| path.0.starts_with(&old.0))
+                .cloned()
+                .collect();
+            for path in moved {
+                if let Some(entry) = table.remove(&path) {
+                    table.insert(rebased(&path), entry);
+                }
+            }
+        }
+        rebase(&mut self.anchors, old, rebased);
+        rebase(&mut self.aliases, old, rebased);
+        rebase(&mut self.quoting, old, rebased);
+        rebase(&mut self.key_quoting, old, rebased);
+        let moved: Vec<NodePath> = self
+            .flow
+            .iter()
+            .filter(|path| path.0.starts_with(&old.0))
+            .cloned()
+            .collect();
+        for path in &moved {
+            self.flow.remove(path);
+        }
+        self.flow.extend(moved.iter().map(rebased));
+    }
+
+    /// Drop all metadata at or under `path`; if it was a sequence element, shift later siblings down.
+    pub fn remove_prefix(&mut self, path: &NodePath) {
+        // Later siblings of a removed sequence element move down one index.
+        let removed_index = match path.0.split_last() {
+            Some((PathSegment::Index(removed), parent)) => Some((*removed, parent)),
+            _ => None,
+        };
+        let is_shifted = |p: &NodePath| {
+            removed_index.is_some_and(|(removed, parent)| {
+                p.0.len() > parent.len()
+                    && p.0.starts_with(parent)
+                    && matches!(p.0[parent.len()], PathSegment::Index(i) if i > removed)
+            })
+        };
+        let shifted = |p: &NodePath| {
+            let mut next = p.0.clone();
+            if let Some((_, parent)) = removed_index {
+                if let PathSegment::Index(i) = next[parent.len()] {
+                    next[parent.len()] = PathSegment::Index(i - 1);
+                }
+            }
+            NodePath(next)
+        };
+        fn shift<V>(
+            table: &mut HashMap<NodePath, V>,
+            path: &NodePath,
+            is_shifted: impl Fn(&NodePath) -> bool,
+            shifted: impl Fn(&NodePath) -> NodePath,
+        ) {
+            table.retain(|p, _| !p.0.starts_with(&path.0));
+            let moved: Vec<NodePath> = table.keys().filter(|p| is_shifted(p)).cloned().collect();
+            // Remove all before inserting any: a shifted entry may land on one still to be moved.
+            let entries: Vec<(NodePath, V)> = moved
+                .into_iter()
+                .filter_map(|p| table.remove(&p).map(|entry| (p, entry)))
+                .collect();
+            for (p, entry) in entries {
+                table.insert(shifted(&p), entry);
+            }
+        }
+        shift(&mut self.anchors, path, is_shifted, shifted);
+        shift(&mut self.aliases, path, is_shifted, shifted);
+        shift(&mut self.quoting, path, is_shifted, shifted);
+        shift(&mut self.key_quoting, path, is_shifted, shifted);
+        self.flow.retain(|p| !p.0.starts_with(&path.0));
+        let moved: Vec<NodePath> = self
+            .flow
+            .iter()
+            .filter(|p| is_shifted(p))
+            .cloned()
+            .collect();
+        for p in &moved {
+            self.flow.remove(p);
+        }
+        self.flow.extend(moved.iter().map(shifted));
+    }
+
+    /// Follow the items of the sequence at `parent` to their new places; `order[new]` is the old
+    /// index of the item now at `new` (`usize::MAX` for an inserted item).
+    pub fn reorder_items(&mut self, parent: &NodePath, order: &[usize]) {
+        let depth = parent.0.len();
+        let moved = |p: &NodePath| -> Option<NodePath> {
+            if p.0.len() <= depth || !p.0.starts_with(&parent.0) {
+                return None;
+            }
+            let PathSegment::Index(old) = p.0[depth] else {
+                return None;
+            };
+            let new = order.iter().position(|&o| o == old)?;
+            let mut next = p.0.clone();
+            next[depth] = PathSegment::Index(new);
+            Some(NodePath(next))
+        };
+        fn remap<V>(
+            table: &mut HashMap<NodePath, V>,
+            moved: impl Fn(&NodePath) -> Option<NodePath>,
+        ) {
+            *table = std::mem::take(table)
+                .into_iter()
+                .map(|(p, entry)| (moved(&p).unwrap_or(p), entry))
+                .collect();
+        }
+        remap(&mut self.anchors, moved);
+        remap(&mut self.aliases, moved);
+        remap(&mut self.quoting, moved);
+        remap(&mut self.key_quoting, moved);
+        self.flow = std::mem::take(&mut self.flow)
+            .into_iter()
+            .map(|p| moved(&p).unwrap_or(p))
+            .collect();
+    }
+
+    /// Forget alias sites at or under `path` (the value there was replaced by an edit).
+    pub fn clear_aliases_under(&mut self, path: &NodePath) {
+        self.aliases.retain(|p, _| !p.0.starts_with(&path.0));
+    }
+}
+
+/// Byte offset where each document of `input` starts; the first always starts at 0 so leading
+/// comments belong to it. Returns nothing if the input does not parse.
+pub fn document_offsets(input: &str) -> Vec<usize> {
+    let mut parser = Parser::new_from_str(input);
+    let mut starts = Vec::new();
+    loop {
+        match parser.next_token() {
+            Ok((Event::DocumentStart, mark)) => starts.push(mark.index()),
+            Ok((Event::StreamEnd, _)) => break,
+            Ok(_) => {}
+            Err(_) => return Vec::new(),
+        }
+    }
+    let byte_at = |char_idx: usize| {
+        input
+            .char_indices()
+            .nth(char_idx)
+            .map_or(input.len(), |(byte, _)| byte)
+    };
+    starts
+        .iter()
+        .enumerate()
+        .map(|(idx, &start)| if idx == 0 { 0 } else { byte_at(start) })
+        .collect()
+}
+
+enum Role {
+    Root,
+    Key,
+    Value,
+}
+
+enum Frame {
+    Map {
+        path: Option<NodePath>,
+        role: Role,
+        key: Option<String>,
+        seen: HashSet<String>,
+    },
+    Seq {
+        path: Option<NodePath>,
+        role: Role,
+        next: usize,
+    },
+}
+
+struct Walker {
+    frames: Vec<Frame>,
+    anchor_names: Vec<String>,
+    /// Character offsets of `[` and `{` tokens that open flow collections.
+    flow_starts: HashSet<usize>,
+    /// Only the first document is tracked, matching `YamlModel`.
+    done: bool,
+    map: SourceMap,
+}
+
+impl Walker {
+    /// Role and path of the node that is about to start under the current frame.
+    fn next_node(&self) -> (Role, Option<NodePath>) {
+        match self.frames.last() {
+            None => (Role::Root, Some(NodePath(Vec::new()))),
+            Some(Frame::Map { path, key, .. }) => match key {
+                None => (Role::Key, None),
+                Some(key) => (Role::Value, path.as_ref().map(|p| p.child_key(key))),
+            },
+            Some(Frame::Seq { path, next, .. }) => {
+                (Role::Value, path.as_ref().map(|p| p.child_index(*next)))
+            }
+        }
+    }
+
+    /// Advance the current frame past a completed child node.
+    fn finish_node(&mut self, role: Role, key_text: Option<String>) {
+        match self.frames.last_mut() {
+            Some(Frame::Map { key, .. }) => match role {
+                Role::Key => *key = Some(key_text.unwrap_or_else(|| "<non-string>".to_string())),
+                _ => *key = None,
+            },
+            Some(Frame::Seq { next, .. }) => *next += 1,
+            None => self.done = true,
+        }
+    }
+
+    fn record_line(&mut self, mark: &Marker, path: &Option<NodePath>) {
+        let Some(path) = path else {
+            return;
+        };
+        if self.map.lines.last().map(|(_, last)| last) != Some(path) {
+            self.map.lines.push((mark.line(), path.clone()));
+        }
+    }
+
+    fn record_flow(&mut self, mark: &Marker, path: &Option<NodePath>) {
+        if let Some(path) = path {
+            if self.flow_starts.contains(&mark.index()) {
+                self.map.flow.insert(path.clone());
+            }
+        }
+    }
+
+    fn record_anchor(&mut self, anchor_id: usize, path: &Option<NodePath>) {
+        if anchor_id == 0 {
+            return;
+        }
+        if let (Some(path), Some(name)) = (path, self.anchor_names.get(anchor_id - 1)) {
+            self.map.anchors.insert(path.clone(), name.clone());
+        }
+    }
+}
+
+impl MarkedEventReceiver for Walker {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        if self.done {
+            return;
+        }
+        match ev {
+            Event::Scalar(value, style, anchor_id, _) => {
+                let (role, path) = self.next_node();
+                self.record_anchor(anchor_id, &path);
+                self.record_line(&mark, &path);
+                let inline = matches!(
+                    style,
+                    TScalarStyle::Plain | TScalarStyle::SingleQuoted | TScalarStyle::DoubleQuoted
+                );
+                if let (Role::Value | Role::Root, Some(path)) = (&role, &path) {
+                    let site = ScalarSite {
+                        index: mark.index(),
+                        style,
+                    };
+                    self.map.scalars.insert(path.clone(), site);
+                    if inline {
+                        self.map.quoting.insert(
+                            path.clone(),
+                            Quoting {
+                                style,
+                                value: value.clone(),
+                            },
+                        );
+                    }
+                }
+                if let (
+                    Role::Key,
+                    Some(Frame::Map {
+                        path: Some(map_path),
+                        ..
+                    }),
+                ) = (&role, self.frames.last())
+                {
+                    let entry = map_path.child_key(&value);
+                    if inline {
+                        self.map.key_quoting.insert(
+                            entry.clone(),
+                            Quoting {
+                                style,
+                                value: value.clone(),
+                            },
+                        );
+                    }
+                    self.record_line(&mark, &Some(entry));
+                }
+                if let (
+                    Role::Key,
+                    Some(Frame::Map {
+                        path: Some(map_path),
+                        seen,
+                        ..
+                    }),
+                ) = (&role, self.frames.last_mut())
+                {
+                    if !seen.insert(value.clone()) {
+                        self.map.duplicates.push(DuplicateKey {
+                            path: map_path.child_key(&value),
+                            line: mark.line(),
+                        });
+                    }
+                }
+                self.finish_node(role, Some(value));
+            }
+            Event::Alias(anchor_id) => {
+                let (role, path) = self.next_node();
+                self.record_line(&mark, &path);
+                if let (Some(path), Some(name)) =
+                    (path, self.anchor_names.get(anchor_id.wrapping_sub(1)))
+                {
+                    self.map.aliases.insert(path, name.clone());
+                }
+                self.finish_node(role, None);
+            }
+            Event::MappingStart(anchor_id, _) => {
+                let (role, path) = self.next_node();
+                self.record_anchor(anchor_id, &path);
+                self.record_line(&mark, &path);
+                self.record_flow(&mark, &path);
+                self.frames.push(Frame::Map {
+                    path,
+                    role,
+                    key: None,
+                    seen: HashSet::new(),
+                });
+            }
+            Event::SequenceStart(anchor_id, _) => {
+                let (role, path) = self.next_node();
+                self.record_anchor(anchor_id, &path);
+                self.record_line(&mark, &path);
+                self.record_flow(&mark, &path);
+                self.frames.push(Frame::Seq {
+                    path,
+                    role,
+                    next: 0,
+                });
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                let role = match self.frames.pop() {
+                    Some(Frame::Map { role, .. }) | Some(Frame::Seq { role, .. }) => role,
+                    None => return,
+                };
+                self.finish_node(role, None);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn path(dot: &str) -> NodePath {
+        NodePath(
+            dot.split('.')
+                .map(|seg| match seg.parse::<usize>() {
+                    Ok(idx) => PathSegment::Index(idx),
+                    Err(_) => PathSegment::Key(seg.to_string()),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn records_anchors_and_merge_aliases() {
+        let src = "defaults: &defaults\n  a: 1\nextra: &extra\n  b: 2\nsvc:\n  <<: *defaults\n  c: 3\nmulti:\n  <<: [*defaults, *extra]\n";
+        let map = SourceMap::scan(src);
+        assert_eq!(map.anchor_at(&path("defaults")), Some("defaults"));
+        assert_eq!(map.anchor_at(&path("extra")), Some("extra"));
+        assert_eq!(map.alias_at(&path("svc.<<")), Some("defaults"));
+        assert_eq!(
+            map.merge_sources(&path("svc")),
+            vec!["defaults".to_string()]
+        );
+        assert_eq!(
+            map.merge_sources(&path("multi")),
+            vec!["defaults".to_string(), "extra".to_string()]
+        );
+    }
+
+    #[test]
+    fn remove_prefix_shifts_sequence_siblings() {
+        let src = "items:\n  - &a 1\n  - &b 2\n  - *a\n";
+        let mut map = SourceMap::scan(src);
+        map.remove_prefix(&path("items.0"));
+        assert_eq!(map.anchor_at(&path("items.0")), Some("b"));
+        assert_eq!(map.alias_at(&path("items.1")), Some("a"));
+    }
+
+    #[test]
+    fn finds_duplicate_keys_per_mapping() {
+        let src = "port: 1\nserver:\n  port: 2\n  port: 3\nport: 4\n";
+        let map = SourceMap::scan(src);
+        assert_eq!(
+            map.duplicates,
+            vec![
+                DuplicateKey {
+                    path: path("server.port"),
+                    line: 4
+                },
+                DuplicateKey {
+                    path: path("port"),
+                    line: 5
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn maps_lines_to_the_innermost_node() {
+        let src = "name: api\nserver:\n  port: 80\n  hosts:\n    - a\n    - b\n";
+        let map = SourceMap::scan(src);
+        let at = |line| map.path_at_line(line).map(NodePath::dot_path);
+        assert_eq!(at(1).as_deref(), Some("name"));
+        assert_eq!(at(2).as_deref(), Some("server"));
+        assert_eq!(at(3).as_deref(), Some("server.port"));
+        assert_eq!(at(6).as_deref(), Some("server.hosts.1"));
+        assert_eq!(at(40).as_deref(), Some("server.hosts.1"));
+    }
+
+    #[test]
+    fn flow_collections_follow_renames_and_removals() {
+        let src = "a: [1]\nitems:\n  - x\n  - [2]\n  - {k: v}\nblock:\n  - y\n";
+        let mut map = SourceMap::scan(src);
+        let flow = |map: &SourceMap| {
+            let mut paths: Vec<String> = map.flow.iter().map(NodePath::dot_path).collect();
+            paths.sort();
+            paths
+        };
+        assert_eq!(flow(&map), vec!["a", "items.1", "items.2"]);
+        map.remove_prefix(&path("items.0"));
+        map.rename_prefix(&path("a"), &path("b"));
+        assert_eq!(flow(&map), vec!["b", "items.0", "items.1"]);
+    }
+}
@@ -0,0 +1,269 @@
+// Minimal-diff saving: rewrite only the source text of scalars whose values changed, leaving
+// comments, quoting and layout of everything else exactly as loaded.
+
+use anyhow::{anyhow, Result};
+use yaml_rust2::scanner::TScalarStyle;
+use yaml_rust2::{Yaml, YamlLoader};
+
+use crate::emitter::{self, EmitOptions};
+use crate::source_map::SourceMap;
+use crate::yaml_model::{self, get_node, NodePath};
+
+/// Rewrite `original`, which loaded as `before`, so that it loads as `after`. `current` is the
+/// edited document's source map; aliases in it stand for their anchor and are left alone.
+/// Fails, with the reason, when the edit changed structure or style rather than scalar values,
+/// or when the spliced text would not read back as `after`.
+pub fn splice(
+    original: &str,
+    before: &Yaml,
+    after: &Yaml,
+    current: &SourceMap,
+    options: &EmitOptions,
+) -> Result<String> {
+    let scanned = SourceMap::scan(original);
+    if scanned.flow != current.flow
+        || scanned.anchors != current.anchors
+        || scanned.aliases != current.aliases
+    {
+        return Err(anyhow!("styles, anchors or aliases changed"));
+    }
+    let mut changed = Vec::new();
+    if !changed_scalars(&NodePath(Vec::new()), before, after, &mut changed) {
+        return Err(anyhow!("keys or items were added, removed or reordered"));
+    }
+    let mut edits = Vec::new();
+    for path in changed {
+        let under_alias = (0..=path.0.len()).any(|len| {
+            current
+                .alias_at(&NodePath(path.0[..len].to_vec()))
+                .is_some()
+        });
+        if under_alias {
+            continue;
+        }
+        let site = scanned
+            .scalars
+            .get(&path)
+            .ok_or_else(|| anyhow!("no source position for {}", path.dot_path()))?;
+        let mut start = byte_offset(original, site.index);
+        if matches!(site.style, TScalarStyle::Literal | TScalarStyle::Folded) {
+            // Block scalars are marked at their content; the header is the last indicator before it.
+            start = original[..start]
+                .rfind(['|', '>'])
+                .ok_or_else(|| anyhow!("no block indicator for {}", path.dot_path()))?;
+        }
+        let in_flow =
+            (0..path.0.len()).any(|len| scanned.is_flow(&NodePath(path.0[..len].to_vec())));
+        let end = scalar_end(original, start, site.style, in_flow)
+            .ok_or_else(|| anyhow!("cannot find the end of {}", path.dot_path()))?;
+        let value = get_node(after, &path)?;
+        let column = content_column(original, start);
+        // Block scalars can only be laid out on whole indent levels; otherwise stay on one line.
+        let level = column / options.indent.max(1);
+        let one_line = in_flow || column % options.indent.max(1) != 0;
+        let text = emitter::emit_scalar(value, options, level, one_line)?;
+        edits.push((start, end, text));
+    }
+    edits.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+    let mut out = original.to_string();
+    for (start, end, text) in edits {
+        out.replace_range(start..end, &text);
+    }
+    let reloaded = YamlLoader::load_from_str(&out)
+        .map_err(|e| anyhow!("spliced text does not parse: {e}"))?
+        .into_iter()
+        .next()
+        .unwrap_or(Yaml::Null);
+    if &reloaded != after {
+        return Err(anyhow!("spliced text does not read back as edited"));
+    }
+    Ok(out)
+}
+
+/// Collect paths of scalars that differ between `before` and `after`. Returns false if the
+/// documents differ in shape (keys, lengths, or scalar vs collection).
+fn changed_scalars(path: &NodePath, before: &Yaml, after: &Yaml, out: &mut Vec<NodePath>) -> bool {
+    match (before, after) {
+        (Yaml::Hash(old), Yaml::Hash(new)) => {
+            if old.len() != new.len() || !old.keys().eq(new.keys()) {
+                return false;
+            }
+            old.iter()
+                .zip(new.values())
+                .all(|((key, old_value), new_value)| {
+                    changed_scalars(
+                        &path.child_key(&key_segment(key)),
+                        old_value,
+                        new_value,
+                        out,
+                    )
+                })
+        }
+        (Yaml::Array(old), Yaml::Array(new)) => {
+            old.len() == new.len()
+                && old
+                    .iter()
+                    .zip(new)
+                    .enumerate()
+                    .all(|(idx, (o, n))| changed_scalars(&path.child_index(idx), o, n, out))
+        }
+        (Yaml::Hash(_) | Yaml::Array(_), _) | (_, Yaml::Hash(_) | Yaml::Array(_)) => false,
+        (old, new) => {
+            if old != new {
+                out.push(path.clone());
+            }
+            true
+        }
+    }
+}
+
+fn key_segment(key: &Yaml) -> String {
+    yaml_model::yaml_key_to_string(key).unwrap_or_else(|| "<non-string>".to_string())
+}
+
+fn byte_offset(text: &str, char_index: usize) -> usize {
+    text.char_indices()
+        .nth(char_index)
+        .map_or(text.len(), |(byte, _)| byte)
+}
+
+/// Column of the first content on the line holding `pos`, past indentation and `- ` markers.
+fn content_column(text: &str, pos: usize) -> usize {
+    let line_start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let line = &text[line_start..pos];
+    let rest = line.trim_start_matches([' ', '-']);
+    line.len() - rest.len()
+}
+
+/// Byte offset just past the scalar starting at `start` (a block scalar's indicator).
+fn scalar_end(text: &str, start: usize, style: TScalarStyle, in_flow: bool) -> Option<usize> {
+    let rest = &text[start..];
+    match style {
+        TScalarStyle::SingleQuoted => {
+            let mut chars = rest.char_indices().skip(1).peekable();
+            while let Some((idx, ch)) = chars.next() {
+                if ch == '\'' {
+                    if chars.peek().is_some_and(|(_, next)| *next == '\'') {
+                        chars.next();
+                        continue;
+                    }
+                    return Some(start + idx + 1);
+                }
+            }
+            None
+        }
+        TScalarStyle::DoubleQuoted => {
+            let mut chars = rest.char_indices().skip(1);
+            while let Some((idx, ch)) = chars.next() {
+                match ch {
+                    '\\' => {
+                        chars.next();
+                    }
+                    '"' => return Some(start + idx + 1),
+                    _ => {}
+                }
+            }
+            None
+        }
+        TScalarStyle::Literal | TScalarStyle::Folded => {
+            let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+            let base = text[line_start..].len() - text[line_start..].trim_start_matches(' ').len();
+            let mut end = start + rest.find('\n').unwrap_or(rest.len());
+            let mut pos = end;
+            while pos < text.len() {
+                let line_end = text[pos + 1..]
+                    .find('\n')
+                    .map_or(text.len(), |i| pos + 1 + i);
+                let line = text[pos + 1..line_end].trim_end_matches('\r');
+                if !line.trim().is_empty() {
+                    if line.len() - line.trim_start_matches(' ').len() <= base {
+                        break;
+                    }
+                    end = pos + 1 + line.len();
+                }
+                pos = line_end;
+            }
+            Some(end)
+        }
+        _ => {
+            let mut end = rest.find(['\n', '\r']).unwrap_or(rest.len());
+            if let Some(comment) = rest[..end].find(" #").or_else(|| rest[..end].find("\t#")) {
+                end = comment;
+            }
+            if in_flow {
+                if let Some(stop) = rest[..end].find([',', ']', '}']) {
+                    end = stop;
+                }
+            }
+            Some(start + rest[..end].trim_end().len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn edit(src: &str, change: impl FnOnce(&mut Yaml)) -> Result<String> {
+        let before = YamlLoader::load_from_str(src).unwrap().remove(0);
+        let mut after = before.clone();
+        change(&mut after);
+        splice(
+            src,
+            &before,
+            &after,
+            &SourceMap::scan(src),
+            &EmitOptions::default(),
+        )
+    }
+
+    fn set(doc: &mut Yaml, key: &str, value: Yaml) {
+        if let Yaml::Hash(map) = doc {
+            match map.get_mut(&Yaml::String(key.to_string())) {
+                Some(slot) => *slot = value,
+                None => {
+                    map.insert(Yaml::String(key.to_string()), value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn one_scalar_edit_changes_one_line() {
+        let src =
+            "# service\nname: 'api'   # quoted\nport: 80 # http\ntags: [a, b]\n\n\nnote: \"x\"\n";
+        let out = edit(src, |doc| set(doc, "port", Yaml::Integer(8080))).unwrap();
+        assert_eq!(
+            out,
+            "# service\nname: 'api'   # quoted\nport: 8080 # http\ntags: [a, b]\n\n\nnote: \"x\"\n"
+        );
+        let out = edit(src, |doc| set(doc, "name", Yaml::String("web: 1".into()))).unwrap();
+        assert_eq!(out, "# service\nname: \"web: 1\"   # quoted\nport: 80 # http\ntags: [a, b]\n\n\nnote: \"x\"\n");
+    }
+
+    #[test]
+    fn rewrites_flow_items_and_block_scalars() {
+        let src = "tags: [a, b, c]\ntext: |\n  one\n  two\nafter: 1\n";
+        let out = edit(src, |doc| {
+            if let Yaml::Hash(map) = doc {
+                if let Some(Yaml::Array(tags)) = map.get_mut(&Yaml::String("tags".into())) {
+                    tags[1] = Yaml::String("z".into());
+                }
+            }
+            set(doc, "text", Yaml::String("three\nfour\nfive\n".into()));
+        })
+        .unwrap();
+        assert_eq!(
+            out,
+            "tags: [a, z, c]\ntext: |\n  three\n  four\n  five\nafter: 1\n"
+        );
+    }
+
+    #[test]
+    fn structural_changes_are_refused() {
+        let src = "a: 1\nb: 2\n";
+        assert!(edit(src, |doc| set(doc, "c", Yaml::Integer(3))).is_err());
+        assert!(edit(src, |doc| set(doc, "a", Yaml::Array(Vec::new()))).is_err());
+    }
+}
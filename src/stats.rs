@@ -0,0 +1,94 @@
+// Size of a document at a glance (`Ctrl+g`): nodes, leaves, depth and a count per type.
+
+use crate::yaml_model::{NodeOrigin, NodeType, TreeNode};
+
+/// Types in the order the overlay lists them.
+const TYPES: [NodeType; 8] = [
+    NodeType::Map,
+    NodeType::Seq,
+    NodeType::String,
+    NodeType::Number,
+    NodeType::Bool,
+    NodeType::Null,
+    NodeType::Timestamp,
+    NodeType::Unknown,
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TreeStats {
+    /// Every node below the document root.
+    pub nodes: usize,
+    /// Nodes without children: scalars and empty collections.
+    pub leaves: usize,
+    /// Levels below the root; `a.b.c` is 3 deep.
+    pub max_depth: usize,
+    /// Nodes of each type that occurs, in [`TYPES`] order.
+    pub by_type: Vec<(NodeType, usize)>,
+}
+
+/// Walk `root`. Keys inherited through `<<` are counted where they are written, not again
+/// under each mapping that merges them.
+pub fn tree_stats(root: &TreeNode) -> TreeStats {
+    let mut stats = TreeStats::default();
+    let mut counts = [0; TYPES.len()];
+    for child in &root.children {
+        walk(child, 1, &mut stats, &mut counts);
+    }
+    stats.by_type = TYPES
+        .into_iter()
+        .zip(counts)
+        .filter(|&(_, count)| count > 0)
+        .collect();
+    stats
+}
+
+fn walk(node: &TreeNode, depth: usize, stats: &mut TreeStats, counts: &mut [usize; TYPES.len()]) {
+    if matches!(node.origin, NodeOrigin::Inherited(_)) {
+        return;
+    }
+    stats.nodes += 1;
+    stats.max_depth = stats.max_depth.max(depth);
+    if node.children.is_empty() {
+        stats.leaves += 1;
+    }
+    if let Some(slot) = TYPES
+        .iter()
+        .position(|node_type| *node_type == node.node_type)
+    {
+        counts[slot] += 1;
+    }
+    for child in &node.children {
+        walk(child, depth + 1, stats, counts);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml_model::YamlModel;
+    use pretty_assertions::assert_eq;
+    use yaml_rust2::YamlLoader;
+
+    #[test]
+    fn counts_nodes_leaves_depth_and_types() {
+        let yaml =
+            "web:\n  port: 80\n  tags: [a, b]\n  empty: {}\n  tls:\n    on: true\n    cert: ~\n";
+        let mut model = YamlModel::empty();
+        *model.root_mut() = YamlLoader::load_from_str(yaml).unwrap().remove(0);
+        let stats = tree_stats(&model.build_tree());
+        assert_eq!(stats.nodes, 9);
+        assert_eq!(stats.leaves, 6);
+        assert_eq!(stats.max_depth, 3);
+        assert_eq!(
+            stats.by_type,
+            [
+                (NodeType::Map, 3),
+                (NodeType::Seq, 1),
+                (NodeType::String, 2),
+                (NodeType::Number, 1),
+                (NodeType::Bool, 1),
+                (NodeType::Null, 1),
+            ]
+        );
+    }
+}
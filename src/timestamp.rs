@@ -0,0 +1,128 @@
+// YAML 1.1 timestamps (`2024-01-01`, `2024-01-01T00:00:00Z`, `2001-12-14 21:59:43.10 -5`).
+// yaml-rust2 loads them as plain strings; the tree only uses this to label and check them.
+
+/// Whether `text` is a YAML timestamp naming a real date and time.
+pub fn is_timestamp(text: &str) -> bool {
+    parse(text).is_some()
+}
+
+fn parse(text: &str) -> Option<()> {
+    let mut cursor = Cursor { rest: text };
+    let year = cursor.digits(4, 4)?;
+    cursor.eat('-')?;
+    let month = cursor.digits(1, 2)?;
+    cursor.eat('-')?;
+    let day = cursor.digits(1, 2)?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    if cursor.rest.is_empty() {
+        // A bare date needs two-digit month and day.
+        return (text.len() == 10).then_some(());
+    }
+
+    if cursor.eat('T').or_else(|| cursor.eat('t')).is_none() && cursor.spaces() == 0 {
+        return None;
+    }
+    let hour = cursor.digits(1, 2)?;
+    cursor.eat(':')?;
+    let minute = cursor.digits(2, 2)?;
+    cursor.eat(':')?;
+    let second = cursor.digits(2, 2)?;
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    if cursor.eat('.').is_some() {
+        cursor.digits(0, usize::MAX)?;
+    }
+    cursor.spaces();
+    if cursor.rest.is_empty() || cursor.eat('Z').is_some() {
+        return cursor.rest.is_empty().then_some(());
+    }
+    cursor.eat('+').or_else(|| cursor.eat('-'))?;
+    let offset_hours = cursor.digits(1, 2)?;
+    let offset_minutes = if cursor.eat(':').is_some() {
+        cursor.digits(2, 2)?
+    } else {
+        0
+    };
+    (offset_hours <= 23 && offset_minutes <= 59 && cursor.rest.is_empty()).then_some(())
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+struct Cursor<'a> {
+    rest: &'a str,
+}
+
+impl Cursor<'_> {
+    fn eat(&mut self, c: char) -> Option<()> {
+        self.rest = self.rest.strip_prefix(c)?;
+        Some(())
+    }
+
+    /// Between `min` and `max` ASCII digits, as a number (saturating for long fractions).
+    fn digits(&mut self, min: usize, max: usize) -> Option<u32> {
+        let len = self
+            .rest
+            .bytes()
+            .take(max)
+            .take_while(u8::is_ascii_digit)
+            .count();
+        if len < min {
+            return None;
+        }
+        let (digits, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Some(digits.bytes().fold(0u32, |n, d| {
+            n.saturating_mul(10).saturating_add(u32::from(d - b'0'))
+        }))
+    }
+
+    fn spaces(&mut self) -> usize {
+        let trimmed = self.rest.trim_start_matches([' ', '\t']);
+        let count = self.rest.len() - trimmed.len();
+        self.rest = trimmed;
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_yaml_timestamps() {
+        for text in [
+            "2024-01-01",
+            "2024-02-29",
+            "2024-01-01T00:00:00Z",
+            "2001-12-14t21:59:43.10-05:00",
+            "2001-12-14 21:59:43.10 -5",
+            "2001-12-15 2:59:43.10",
+            "2024-1-1T00:00:00",
+        ] {
+            assert!(is_timestamp(text), "{text}");
+        }
+        for text in [
+            "2024-1-1",
+            "2023-02-29",
+            "2024-13-01",
+            "2024-01-01T24:00:00Z",
+            "2024-01-01T00:00Z",
+            "2024-01-01X",
+            "2024-01-01T00:00:00ZZ",
+            "20240101",
+            "v2024-01-01",
+        ] {
+            assert!(!is_timestamp(text), "{text}");
+        }
+    }
+}
@@ -0,0 +1,183 @@
+// Conversion of YAML documents to TOML, for configs kept in both formats.
+
+use std::fmt;
+
+use anyhow::Result;
+use toml::{Table, Value};
+use yaml_rust2::Yaml;
+
+use crate::yaml_model::NodePath;
+
+/// A node TOML has no way to write, and why.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unrepresentable {
+    pub path: NodePath,
+    pub reason: &'static str,
+}
+
+/// Everything in a document that stops it from being written as TOML.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TomlError(pub Vec<Unrepresentable>);
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TOML cannot represent ")?;
+        for (index, problem) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            let at = if problem.path.0.is_empty() {
+                "the document".to_string()
+            } else {
+                problem.path.dot_path()
+            };
+            write!(f, "{at} ({})", problem.reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TomlError {}
+
+/// The document as a TOML table. Mappings become tables and sequences of mappings arrays of
+/// tables. Fails listing every null, mixed-type sequence, collection key and other value TOML
+/// cannot hold; the document itself must be a mapping.
+pub fn yaml_to_toml(root: &Yaml) -> Result<Table, TomlError> {
+    let mut problems = Vec::new();
+    let root_path = NodePath(Vec::new());
+    let table = match root {
+        Yaml::Hash(_) => match convert(root, &root_path, &mut problems) {
+            Some(Value::Table(table)) => Some(table),
+            _ => None,
+        },
+        _ => {
+            fail(&mut problems, &root_path, "not a mapping");
+            None
+        }
+    };
+    match table {
+        Some(table) if problems.is_empty() => Ok(table),
+        _ => Err(TomlError(problems)),
+    }
+}
+
+/// The document as TOML text.
+pub fn to_toml_string(root: &Yaml) -> Result<String> {
+    let table = yaml_to_toml(root)?;
+    Ok(toml::to_string(&table)?)
+}
+
+/// Convert `node`, recording what cannot be converted. Returns `None` when `node` or something
+/// inside it failed.
+fn convert(node: &Yaml, at: &NodePath, problems: &mut Vec<Unrepresentable>) -> Option<Value> {
+    match node {
+        Yaml::String(value) => Some(Value::String(value.clone())),
+        Yaml::Integer(value) => Some(Value::Integer(*value)),
+        Yaml::Real(text) => match Yaml::Real(text.clone()).as_f64() {
+            Some(value) => Some(Value::Float(value)),
+            None => fail(problems, at, "unreadable number"),
+        },
+        Yaml::Boolean(value) => Some(Value::Boolean(*value)),
+        Yaml::Null => fail(problems, at, "null"),
+        Yaml::Alias(_) | Yaml::BadValue => fail(problems, at, "no TOML form"),
+        Yaml::Array(seq) => {
+            let items: Vec<Option<Value>> = seq
+                .iter()
+                .enumerate()
+                .map(|(index, item)| convert(item, &at.child_index(index), problems))
+                .collect();
+            let items: Vec<Value> = items.into_iter().collect::<Option<_>>()?;
+            if items
+                .windows(2)
+                .any(|pair| pair[0].type_str() != pair[1].type_str())
+            {
+                return fail(problems, at, "mixed-type array");
+            }
+            Some(Value::Array(items))
+        }
+        Yaml::Hash(map) => {
+            let mut table = Table::new();
+            let mut complete = true;
+            for (key, value) in map {
+                let key = match key {
+                    Yaml::String(key) | Yaml::Real(key) => key.clone(),
+                    Yaml::Integer(key) => key.to_string(),
+                    Yaml::Boolean(key) => key.to_string(),
+                    _ => {
+                        fail(problems, at, "key that is not a scalar");
+                        complete = false;
+                        continue;
+                    }
+                };
+                match convert(value, &at.child_key(&key), problems) {
+                    Some(value) => {
+                        table.insert(key, value);
+                    }
+                    None => complete = false,
+                }
+            }
+            complete.then_some(Value::Table(table))
+        }
+    }
+}
+
+fn fail(problems: &mut Vec<Unrepresentable>, at: &NodePath, reason: &'static str) -> Option<Value> {
+    problems.push(Unrepresentable {
+        path: at.clone(),
+        reason,
+    });
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use yaml_rust2::YamlLoader;
+
+    fn load(src: &str) -> Yaml {
+        YamlLoader::load_from_str(src).unwrap().remove(0)
+    }
+
+    #[test]
+    fn nested_tables_and_arrays_of_tables() {
+        let doc = load(
+            "title: demo\nport: 8080\nratio: 0.5\ndebug: false\ntags: [a, b]\n\
+             server:\n  host: localhost\n  tls:\n    enabled: true\n\
+             users:\n  - name: ann\n    admin: true\n  - name: bob\n",
+        );
+        assert_eq!(
+            to_toml_string(&doc).unwrap(),
+            "title = \"demo\"\nport = 8080\nratio = 0.5\ndebug = false\ntags = [\"a\", \"b\"]\n\n\
+             [server]\nhost = \"localhost\"\n\n[server.tls]\nenabled = true\n\n\
+             [[users]]\nname = \"ann\"\nadmin = true\n\n[[users]]\nname = \"bob\"\n"
+        );
+    }
+
+    #[test]
+    fn result_reads_back_as_the_same_values() {
+        let doc = load("a:\n  1: one\n  list: [[1, 2], [x]]\n");
+        let text = to_toml_string(&doc).unwrap();
+        let table: Table = text.parse().unwrap();
+        assert_eq!(table["a"]["1"].as_str(), Some("one"));
+        assert_eq!(table["a"]["list"][1][0].as_str(), Some("x"));
+    }
+
+    #[test]
+    fn every_unrepresentable_node_is_listed() {
+        let doc =
+            load("a: ~\nb:\n  mixed: [1, two]\n  ok: [1, 2]\n  deep:\n    - x: null\n? [k]\n: v\n");
+        let err = yaml_to_toml(&doc).unwrap_err();
+        let paths: Vec<String> = err.0.iter().map(|p| p.path.dot_path()).collect();
+        assert_eq!(paths, ["a", "b.mixed", "b.deep.0.x", ""]);
+        assert_eq!(
+            err.to_string(),
+            "TOML cannot represent a (null), b.mixed (mixed-type array), b.deep.0.x (null), \
+             the document (key that is not a scalar)"
+        );
+        assert_eq!(
+            yaml_to_toml(&load("- 1\n")).unwrap_err().to_string(),
+            "TOML cannot represent the document (not a mapping)"
+        );
+    }
+}
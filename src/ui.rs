@@ -1,3 +1,6 @@
+use std::ops::Range;
+use std::time::SystemTime;
+
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
@@ -5,7 +8,19 @@ use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 use ratatui::Frame;
 use unicode_width::UnicodeWidthStr;
 
-use crate::app::{App, Mode, PickerEntry, RowHit};
+use crate::app::{
+    side_preview, App, Mode, Pick, PickerEntry, RowHit, DETAILS_CHUNK_BYTES, MAX_SHOWN_CANDIDATES,
+};
+use crate::clock;
+use crate::diff::DiffMark;
+use crate::interpolate::{self, Piece};
+use crate::k8s;
+use crate::line_diff::DiffLine;
+use crate::merge::Side;
+use crate::search::{Query, SearchScope};
+use crate::stats;
+use crate::width;
+use crate::yaml_model::{NodeOrigin, NodePath, NodeType, PathSegment};
 
 pub fn draw(frame: &mut Frame<'_>, app: &mut App) -> Vec<RowHit> {
     let size = frame.size();
@@ -54,13 +69,8 @@ pub fn draw(frame: &mut Frame<'_>, app: &mut App) -> Vec<RowHit> {
 }
 
 fn draw_parse_error(frame: &mut Frame<'_>, app: &App, area: Rect) {
-    let msg = app
-        .parse_error
-        .as_deref()
-        .unwrap_or("")
-        .chars()
-        .take(area.width as usize)
-        .collect::<String>();
+    let msg = app.parse_error.as_ref().map_or("", |e| e.message.as_str());
+    let msg = &msg[..width::byte_at_column(msg, area.width as usize)];
     let line = Line::from(Span::styled(
         format!("PARSE ERROR: {}", msg),
         Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
@@ -87,7 +97,58 @@ fn draw_status(frame: &mut Frame<'_>, app: &App, area: Rect) {
         return;
     }
     let (path, depth, kind, preview) = app.status_fields();
-    let mut spans = vec![
+    let mut spans = Vec::new();
+    if app.read_only.is_some() {
+        spans.push(Span::styled(
+            "[RO] ",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    let findings = app.hook_finding_count();
+    if findings > 0 {
+        spans.push(Span::styled(
+            format!(" {findings} finding(s) "),
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::raw(" "));
+    } else if app.hook_running() {
+        spans.push(Span::styled("[hook…] ", Style::default().fg(Color::Gray)));
+    }
+    if !app.lint.is_empty() {
+        spans.push(Span::styled(
+            format!(" {} lint ", app.lint.len()),
+            Style::default().fg(Color::Black).bg(Color::LightYellow),
+        ));
+        spans.push(Span::raw(" "));
+    }
+    if let Some(label) = app.lossy_label() {
+        spans.push(Span::styled(
+            format!(" lossy save: {label} "),
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::raw(" "));
+    }
+    if app.env_preview {
+        spans.push(Span::styled(
+            " $ENV ",
+            Style::default().fg(Color::Black).bg(Color::Green),
+        ));
+        spans.push(Span::raw(" "));
+    }
+    if let Some(label) = k8s::label(app.model.root()) {
+        spans.push(Span::styled(
+            format!(" {label} "),
+            Style::default().fg(Color::White).bg(Color::Blue),
+        ));
+        spans.push(Span::raw(" "));
+    }
+    spans.extend([
         Span::styled("PATH ", Style::default().fg(Color::Yellow)),
         Span::raw(path),
         Span::raw("  "),
@@ -99,8 +160,8 @@ fn draw_status(frame: &mut Frame<'_>, app: &App, area: Rect) {
         Span::raw("  "),
         Span::styled("VALUE ", Style::default().fg(Color::Yellow)),
         Span::raw(preview),
-    ];
-    if let Some(_) = app.search_query.as_ref() {
+    ]);
+    if app.search_query.is_some() {
         let total = app.matches.len();
         let current = app
             .matches
@@ -113,6 +174,17 @@ fn draw_status(frame: &mut Frame<'_>, app: &App, area: Rect) {
             "Search ",
             Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
         ));
+        if app.search_backward {
+            spans.push(Span::raw("up "));
+        }
+        if app.search_scope != SearchScope::Both {
+            spans.push(Span::raw(format!("({}) ", app.search_scope.label())));
+        }
+        if app.search_query.as_ref().is_some_and(Query::is_regex) {
+            spans.push(Span::raw("re "));
+        } else if app.search_query.as_ref().is_some_and(Query::is_fuzzy) {
+            spans.push(Span::raw("fz "));
+        }
         if total == 0 {
             spans.push(Span::styled(
                 "0/0",
@@ -121,6 +193,10 @@ fn draw_status(frame: &mut Frame<'_>, app: &App, area: Rect) {
         } else {
             spans.push(Span::raw(format!("{}/{}", current, total)));
         }
+        spans.push(Span::styled(
+            format!(" {}", app.search_case.label()),
+            Style::default().fg(Color::Gray),
+        ));
     }
     let text = Line::from(spans);
     let paragraph = Paragraph::new(text).style(Style::default().fg(Color::White));
@@ -145,12 +221,14 @@ fn draw_file_picker(frame: &mut Frame<'_>, app: &mut App, area: Rect) -> Vec<Row
     }
     let start = (app.selection + 1)
         .saturating_sub(available_height)
-        .max(0)
         .min(len.saturating_sub(available_height));
     let end = (start + available_height).min(len);
     let mut lines = Vec::new();
+    let row_width = area.width.saturating_sub(2) as usize;
+    let now = SystemTime::now();
     for (idx, entry) in picker.entries.iter().enumerate().take(end).skip(start) {
         let (name, is_dir) = match entry {
+            PickerEntry::Recent(p) => (p.display().to_string(), false),
             PickerEntry::Parent => ("..".to_string(), true),
             PickerEntry::Dir(p) => (
                 p.file_name()
@@ -159,7 +237,7 @@ fn draw_file_picker(frame: &mut Frame<'_>, app: &mut App, area: Rect) -> Vec<Row
                     .unwrap_or_else(|| "?/".to_string()),
                 true,
             ),
-            PickerEntry::File(p) => (
+            PickerEntry::File(p, _) => (
                 p.file_name()
                     .and_then(|n| n.to_str())
                     .unwrap_or("?")
@@ -169,27 +247,56 @@ fn draw_file_picker(frame: &mut Frame<'_>, app: &mut App, area: Rect) -> Vec<Row
         };
         let mut style = Style::default();
         if idx == app.selection {
-            style = style
-                .fg(Color::Black)
-                .bg(Color::Cyan)
-                .add_modifier(Modifier::BOLD);
+            style = selected_style();
         } else if app.hover_row == Some(idx) {
             style = style.bg(Color::DarkGray);
         } else if is_dir {
             style = style.fg(Color::Yellow);
+        } else if matches!(entry, PickerEntry::Recent(_)) {
+            style = style.fg(Color::Magenta);
         }
-        lines.push(Line::from(Span::styled(name.clone(), style)));
         let row_y = area.y + 1 + (idx - start) as u16;
-        let key_end = name.width().saturating_add(2);
-        hits.push(RowHit {
-            row_index: idx,
-            y: row_y,
-            key_x_start: area.x + 1,
-            key_x_end: area.x + key_end as u16,
-        });
+        hits.push(row_hit(idx, row_y, area, &name, 0, name.len()));
+        let mut spans = vec![Span::styled(name.clone(), style)];
+        if let PickerEntry::File(_, meta) = entry {
+            // Right-aligned `2.3K  3m ago`, dropped when the row is too narrow for it.
+            let age = meta.modified.map(|modified| {
+                clock::ago_label(now.duration_since(modified).map_or(0, |d| d.as_secs()))
+            });
+            let info = match age {
+                Some(age) => format!("{:>6}  {age:>8}", compact_size(meta.size)),
+                None => format!("{:>6}", compact_size(meta.size)),
+            };
+            if name.width() + 1 + info.width() <= row_width {
+                let gap = row_width - name.width() - info.width();
+                spans.push(Span::styled(" ".repeat(gap), style));
+                let info_style = if idx == app.selection {
+                    style
+                } else {
+                    style.fg(Color::DarkGray)
+                };
+                spans.push(Span::styled(info, info_style));
+            }
+        }
+        if matches!(entry, PickerEntry::Recent(_)) && name.width() + 7 <= row_width {
+            spans.push(Span::styled(
+                " ".repeat(row_width - name.width() - 6),
+                style,
+            ));
+            let tag_style = if idx == app.selection {
+                style
+            } else {
+                style.fg(Color::DarkGray)
+            };
+            spans.push(Span::styled("recent", tag_style));
+        }
+        lines.push(Line::from(spans));
     }
     let block = Block::default()
-        .title("Select file (.. = parent, dir/ = enter, .yaml/.yml = open)")
+        .title(format!(
+            "Select file (.. = parent, dir/ = enter, .yaml/.yml = open, m = sort; by {})",
+            picker.sort.label()
+        ))
         .borders(Borders::ALL);
     let paragraph = Paragraph::new(lines).block(block).wrap(Wrap { trim: false });
     frame.render_widget(paragraph, area);
@@ -210,31 +317,31 @@ fn draw_tree(frame: &mut Frame<'_>, app: &mut App, area: Rect) -> Vec<RowHit> {
         }
         let start = app.scroll;
         let end = (start + available_height).min(len);
+        let error_line = app.parse_error_line();
         let mut lines = Vec::new();
         for (idx, line_str) in raw_lines.iter().enumerate().take(end).skip(start) {
             let line_num = format!("{:4} ", idx + 1);
             let mut style = Style::default();
             if idx == app.selection {
-                style = style
-                    .fg(Color::Black)
-                    .bg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD);
+                let bg = if error_line == Some(idx) {
+                    Color::Red
+                } else {
+                    Color::Cyan
+                };
+                style = style.fg(Color::Black).bg(bg).add_modifier(Modifier::BOLD);
             } else if app.hover_row == Some(idx) {
                 style = style.bg(Color::DarkGray);
             }
+            if error_line == Some(idx) && idx != app.selection {
+                style = style.fg(Color::Red);
+            }
             let display = format!("{}{}", line_num, line_str);
-            lines.push(Line::from(Span::styled(display.clone(), style)));
             let row_y = area.y + 1 + (idx - start) as u16;
-            let key_end = display.width().saturating_add(2);
-            hits.push(RowHit {
-                row_index: idx,
-                y: row_y,
-                key_x_start: area.x + 1,
-                key_x_end: area.x + key_end as u16,
-            });
+            hits.push(row_hit(idx, row_y, area, &display, 0, display.len()));
+            lines.push(Line::from(Span::styled(display, style)));
         }
         let block = Block::default()
-            .title("Raw (parse error - e: edit line, Ctrl+s: save & re-parse)")
+            .title("Raw (parse error - e: edit line, !: error line, Ctrl+s: save & re-parse)")
             .borders(Borders::ALL);
         let paragraph = Paragraph::new(lines).block(block).wrap(Wrap { trim: false });
         frame.render_widget(paragraph, area);
@@ -244,6 +351,7 @@ fn draw_tree(frame: &mut Frame<'_>, app: &mut App, area: Rect) -> Vec<RowHit> {
     let start = app.scroll;
     let end = (start + available_height).min(app.visible.len());
     let mut lines = Vec::new();
+    let mut widest = 0;
     for (idx, row) in app.visible.iter().enumerate().take(end).skip(start) {
         let indent = row.depth * 2;
         let expanded = app.expanded.contains(&row.path.dot_path());
@@ -252,46 +360,208 @@ fn draw_tree(frame: &mut Frame<'_>, app: &mut App, area: Rect) -> Vec<RowHit> {
         } else {
             " "
         };
+        let diff_mark = app
+            .baseline
+            .as_ref()
+            .and_then(|b| b.annotations.mark(&row.path));
         let mut line = String::new();
         line.push_str(&" ".repeat(indent));
         line.push_str(indicator);
         line.push(' ');
-        let key_start = indent + 2;
+        match diff_mark {
+            Some(DiffMark::Changed(_)) => line.push_str("≠ "),
+            Some(DiffMark::Added) => line.push_str("+ "),
+            None => {}
+        }
+        if app.show_indices {
+            if let Some(PathSegment::Index(index)) = row.path.0.last() {
+                line.push_str(&format!("[{index}] "));
+            }
+        }
+        let key_start = line.len();
         line.push_str(&row.display_key);
-        let key_end = key_start + row.display_key.width();
-        if !row.is_container {
-            if !row.display_value_preview.is_empty() {
-                line.push_str(" = ");
-                line.push_str(&row.display_value_preview);
+        let key_end = line.len();
+        let value_start = line.len();
+        if !row.is_container && !row.display_value_preview.is_empty() {
+            line.push_str(" = ");
+            line.push_str(&row.display_value_preview);
+        }
+        let value_end = line.len();
+        match &row.origin {
+            NodeOrigin::Inherited(_) => line.push_str("  (inherited)"),
+            NodeOrigin::Alias(label) => {
+                line.push_str("  → ");
+                line.push_str(label);
             }
+            _ => {}
         }
 
         let mut style = Style::default();
         if idx == app.selection {
-            style = style
-                .fg(Color::Black)
-                .bg(Color::Cyan)
-                .add_modifier(Modifier::BOLD);
+            style = selected_style();
         } else if app.hover_row == Some(idx) {
             style = style.bg(Color::DarkGray);
+        } else if matches!(row.origin, NodeOrigin::Inherited(_)) {
+            style = style.fg(Color::DarkGray);
+        } else if matches!(row.origin, NodeOrigin::Alias(_)) {
+            style = style.fg(Color::Magenta);
+        } else {
+            match diff_mark {
+                Some(DiffMark::Changed(_)) => style = style.fg(Color::Yellow),
+                Some(DiffMark::Added) => style = style.fg(Color::Green),
+                None => {}
+            }
         }
 
-        lines.push(Line::from(Span::styled(line.clone(), style)));
+        // Values are colored by type, except on the selection bar and on greyed inherited rows.
+        let value_style = match value_color(&row.node_type) {
+            Some(color)
+                if !app.cli.no_color
+                    && idx != app.selection
+                    && !matches!(row.origin, NodeOrigin::Inherited(_)) =>
+            {
+                style.fg(color)
+            }
+            _ => style,
+        };
+
+        // Keys a Kubernetes manifest does not expect where they are.
+        let key_style =
+            if idx != app.selection && k8s::misplaced(app.model.root(), &row.path).is_some() {
+                style.fg(Color::LightRed).add_modifier(Modifier::UNDERLINED)
+            } else {
+                style
+            };
+
         let row_y = area.y + 1 + (idx - start) as u16;
-        hits.push(RowHit {
-            row_index: idx,
-            y: row_y,
-            key_x_start: area.x + key_start as u16,
-            key_x_end: area.x + key_end.saturating_sub(1) as u16,
+        hits.push(row_hit(idx, row_y, area, &line, key_start, key_end));
+        let mut spans = vec![Span::styled(line[..key_start].to_string(), style)];
+        let key_query = app
+            .search_query
+            .as_ref()
+            .filter(|_| app.search_scope.keys());
+        let value_query = app
+            .search_query
+            .as_ref()
+            .filter(|_| app.search_scope.values());
+        let key_ranges = key_query.map_or_else(Vec::new, |query| {
+            let ranges = query.ranges(&row.display_key);
+            // Matched only through the path to it (`server.port`): the whole key is the match.
+            if ranges.is_empty() && query.is_match(&row.path.dot_path()) {
+                std::iter::once(0..row.display_key.len()).collect()
+            } else {
+                ranges
+            }
         });
+        spans.extend(highlight_ranges(&row.display_key, key_ranges, key_style));
+        if value_end > value_start {
+            spans.push(Span::styled(" = ", style));
+            let value = &line[value_start + 3..value_end];
+            let value_ranges = value_query
+                .map(|query| query.ranges(value))
+                .unwrap_or_default();
+            spans.extend(highlight_ranges(value, value_ranges, value_style));
+        }
+        if line.len() > value_end {
+            spans.push(Span::styled(line[value_end..].to_string(), style));
+        }
+        widest = widest.max(width::display_width(&line));
+        lines.push(Line::from(spans));
     }
 
-    let block = Block::default().title("Tree").borders(Borders::ALL);
-    let paragraph = Paragraph::new(lines).block(block).wrap(Wrap { trim: false });
+    // Rows are cut at the pane edge rather than wrapped; `zl`/`zh` scroll them sideways, no
+    // further than the widest row on screen needs.
+    app.h_scroll = app
+        .h_scroll
+        .min(widest.saturating_sub(area.width.saturating_sub(2) as usize));
+    let offset = app.h_scroll;
+    if offset > 0 {
+        lines = lines
+            .into_iter()
+            .map(|line| Line::from(skip_columns(line.spans, offset)))
+            .collect();
+        let x0 = area.x + 1;
+        for hit in &mut hits {
+            hit.key_x_start = hit.key_x_start.saturating_sub(offset as u16).max(x0);
+            hit.key_x_end = hit.key_x_end.saturating_sub(offset as u16).max(x0);
+        }
+    }
+    let title = if offset > 0 {
+        format!("Tree (scrolled {offset} columns, 0: back)")
+    } else {
+        "Tree".to_string()
+    };
+    let block = Block::default().title(title).borders(Borders::ALL);
+    let paragraph = Paragraph::new(lines).block(block);
     frame.render_widget(paragraph, area);
     hits
 }
 
+/// `spans` with their first `columns` terminal columns cut off. A wide character cut in half
+/// leaves blanks for its visible half, so the rest of the row stays in its columns.
+fn skip_columns(spans: Vec<Span<'static>>, columns: usize) -> Vec<Span<'static>> {
+    let mut left = columns;
+    let mut out = Vec::new();
+    for span in spans {
+        if left == 0 {
+            out.push(span);
+            continue;
+        }
+        let span_width = width::display_width(&span.content);
+        if span_width <= left {
+            left -= span_width;
+            continue;
+        }
+        let (start, blanks) = width::skip_columns(&span.content, left);
+        left = 0;
+        out.push(Span::styled(
+            format!("{}{}", " ".repeat(blanks), &span.content[start..]),
+            span.style,
+        ));
+    }
+    out
+}
+
+/// `text` split into spans with the search matches (byte `ranges`) on a yellow background; on
+/// the selection bar they stay yellow so the current match stands out from the others.
+fn highlight_ranges(text: &str, ranges: Vec<Range<usize>>, style: Style) -> Vec<Span<'static>> {
+    let highlight = style.fg(Color::Black).bg(Color::Yellow);
+    let mut spans = Vec::new();
+    let mut at = 0;
+    for range in ranges {
+        if range.start > at {
+            spans.push(Span::styled(text[at..range.start].to_string(), style));
+        }
+        spans.push(Span::styled(text[range.clone()].to_string(), highlight));
+        at = range.end;
+    }
+    if at < text.len() || spans.is_empty() {
+        spans.push(Span::styled(text[at..].to_string(), style));
+    }
+    spans
+}
+
+/// The path to the selection as one chip per segment, `[n]` for sequence items, separated by `›`.
+fn breadcrumb(path: &NodePath, no_color: bool) -> Line<'static> {
+    let chip = |text: String, color: Color| {
+        let style = if no_color {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().fg(Color::White).bg(color)
+        };
+        Span::styled(format!(" {text} "), style)
+    };
+    let mut spans = vec![chip("(root)".to_string(), Color::DarkGray)];
+    for segment in &path.0 {
+        spans.push(Span::styled(" › ", Style::default().fg(Color::Gray)));
+        spans.push(match segment {
+            PathSegment::Key(key) => chip(key.clone(), Color::Blue),
+            PathSegment::Index(index) => chip(format!("[{index}]"), Color::Magenta),
+        });
+    }
+    Line::from(spans)
+}
+
 fn draw_details(frame: &mut Frame<'_>, app: &App, area: Rect) {
     let block = Block::default().title("Details").borders(Borders::ALL);
     let mut lines = Vec::new();
@@ -300,9 +570,10 @@ fn draw_details(frame: &mut Frame<'_>, app: &App, area: Rect) {
             lines.push(Line::from(format!("Dir: {}", picker.current_dir.display())));
             if app.selection < picker.entries.len() {
                 let hint = match &picker.entries[app.selection] {
+                    PickerEntry::Recent(_) => "Enter = open recent file",
                     PickerEntry::Parent => "Enter = go up",
                     PickerEntry::Dir(_) => "Enter = open folder",
-                    PickerEntry::File(_) => "Enter = open file",
+                    PickerEntry::File(..) => "Enter = open file",
                 };
                 lines.push(Line::from(""));
                 lines.push(Line::from(Span::styled(
@@ -311,47 +582,273 @@ fn draw_details(frame: &mut Frame<'_>, app: &App, area: Rect) {
                 )));
             }
         }
-        let paragraph = Paragraph::new(lines).block(block).wrap(Wrap { trim: false });
+        if app.mode == Mode::NewFileName {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "New File:",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )));
+            let mut input_line = app.input.text.clone();
+            let cursor = width::floor_grapheme_boundary(&input_line, app.input.cursor);
+            input_line.insert(cursor, '▌');
+            lines.push(Line::from(input_line));
+        }
+        let paragraph = Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false });
         frame.render_widget(paragraph, area);
         return;
     }
     if let Some(row) = app.current_row() {
+        lines.push(breadcrumb(&row.path, app.cli.no_color));
         lines.push(Line::from(format!("Path: {}", row.path.dot_path())));
         lines.push(Line::from(format!("Depth: {}", row.path.depth())));
         lines.push(Line::from(format!("Type: {}", row.node_type)));
-        lines.push(Line::from(format!("Value: {}", row.display_value_preview)));
+        // The value wrapped by the pane, where the tree row only shows a truncated preview. Long
+        // strings are formatted a chunk at a time.
+        let limit = app.details_limit(&row.path);
+        let loaded = match &row.origin {
+            NodeOrigin::MergeKey(_) => None,
+            _ => app.model.value_prefix(&row.path, limit),
+        };
+        let (full, cut) = loaded.unwrap_or_else(|| (row.display_value_preview.clone(), false));
+        let value = match (&row.node_type, full.as_str()) {
+            (NodeType::String, "\"\"") => "\"\" (empty string)".to_string(),
+            (NodeType::Null, _) => "null (no value)".to_string(),
+            _ => full,
+        };
+        if row.value_size > DETAILS_CHUNK_BYTES {
+            let mut size = vec![Span::raw(format!("Size: {}", format_size(row.value_size)))];
+            if cut {
+                size.push(Span::styled(
+                    format!(" ({} shown; Enter: load more)", format_size(limit)),
+                    Style::default().fg(Color::Gray),
+                ));
+            }
+            lines.push(Line::from(size));
+        }
+        lines.push(Line::from(format!("Value: {}", value)));
+        if app.env_preview {
+            let text = match &row.origin {
+                NodeOrigin::MergeKey(_) => None,
+                _ => app
+                    .model
+                    .node_at(&row.path)
+                    .ok()
+                    .and_then(|node| node.as_str()),
+            };
+            if let Some(text) = text.filter(|text| interpolate::has_references(text)) {
+                lines.push(resolved_line(text));
+            }
+        }
+        match &row.origin {
+            NodeOrigin::MergeKey(label) | NodeOrigin::Inherited(label) => {
+                lines.push(Line::from(Span::styled(
+                    format!("Merged from: {}", label),
+                    Style::default().fg(Color::Gray),
+                )));
+            }
+            NodeOrigin::Alias(label) => {
+                lines.push(Line::from(Span::styled(
+                    format!("Alias of: {}", label),
+                    Style::default().fg(Color::Magenta),
+                )));
+            }
+            NodeOrigin::Own => {}
+        }
+        if let Some(anchor) = app.model.anchor_at(&row.path) {
+            lines.push(Line::from(Span::styled(
+                format!("Anchor: &{}", anchor),
+                Style::default().fg(Color::Magenta),
+            )));
+        }
+        if let Some(hint) = k8s::misplaced(app.model.root(), &row.path) {
+            lines.push(Line::from(Span::styled(
+                format!("Kubernetes: {hint}"),
+                Style::default().fg(Color::LightRed),
+            )));
+        }
+        if let Some(summary) = app.schema.as_ref().and_then(|s| s.describe(&row.path)) {
+            lines.push(Line::from(Span::styled(
+                format!("Schema: {}", summary),
+                Style::default().fg(Color::Cyan),
+            )));
+        }
+        if let Some(baseline) = &app.baseline {
+            draw_baseline_details(&mut lines, baseline, row);
+        }
     }
 
     if matches!(
         app.mode,
-        Mode::EditValue | Mode::RenameKey | Mode::AddKey | Mode::AddValue | Mode::SearchInput | Mode::RawEditLine
+        Mode::EditValue
+            | Mode::RenameKey
+            | Mode::AddKey
+            | Mode::AddValue
+            | Mode::SearchInput
+            | Mode::RawEditLine
+            | Mode::PasteKey
+            | Mode::SaveAsPath
+            | Mode::MergeValue
+            | Mode::ExportPath
+            | Mode::RekeyField
+            | Mode::RenameKeyEverywhere
+            | Mode::ReplaceCommand
+            | Mode::PipeCommand
+            | Mode::GotoPath
     ) {
         lines.push(Line::from(""));
-        let input_label = match app.mode {
+        let search_label;
+        let input_label: &str = match app.mode {
             Mode::EditValue => "Edit Value:",
             Mode::RenameKey => "Rename Key:",
             Mode::AddKey => "New Key:",
             Mode::AddValue => "New Value:",
-            Mode::SearchInput => "Search:",
+            Mode::SearchInput => {
+                search_label = format!(
+                    "Search {}{} (Ctrl+k), {} (Alt+c):",
+                    if app.search_backward { "Up " } else { "" },
+                    app.search_scope.label(),
+                    app.search_case.label()
+                );
+                &search_label
+            }
             Mode::RawEditLine => "Edit Line:",
+            Mode::PasteKey => "Paste As Key:",
+            Mode::SaveAsPath => "Save As:",
+            Mode::MergeValue => "Merged Value:",
+            Mode::ExportPath => "Export To:",
+            Mode::RenameKeyEverywhere => "Rename Everywhere To:",
+            Mode::ReplaceCommand => {
+                "Replace (%s/old/new/ c: confirm each, t: numbers too, k: keys):"
+            }
+            Mode::PipeCommand => "Pipe Through (stdin: YAML of the node, stdout replaces it):",
+            Mode::GotoPath => "Go To Path (Tab: complete):",
+            Mode::RekeyField => match app.current_row().map(|row| &row.node_type) {
+                Some(NodeType::Seq) => "Key By Field:",
+                _ => "Put Keys Under Field:",
+            },
             _ => "Input:",
         };
         lines.push(Line::from(Span::styled(
-            input_label,
-            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            input_label.to_string(),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
         )));
-        let cursor = app.input.cursor;
         let mut input_line = app.input.text.clone();
-        if cursor <= input_line.len() {
-            input_line.insert(cursor, '▌');
-        }
+        let cursor = width::floor_grapheme_boundary(&input_line, app.input.cursor);
+        input_line.insert(cursor, '▌');
         lines.push(Line::from(input_line));
+        let completions = app.key_completions();
+        if !completions.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "Schema keys (↑/↓: choose, Tab: accept):",
+                Style::default().fg(Color::Gray),
+            )));
+            let selected = app.completion_index(&completions);
+            for (idx, name) in completions.iter().enumerate() {
+                let style = if idx == selected {
+                    Style::default().fg(Color::Black).bg(Color::Cyan)
+                } else {
+                    Style::default().fg(Color::Cyan)
+                };
+                lines.push(Line::from(Span::styled(format!(" {name} "), style)));
+            }
+        }
+        let paths = app.path_completions();
+        if paths.len() > 1 {
+            let dim = Style::default().fg(Color::Cyan);
+            for name in paths.iter().take(MAX_SHOWN_CANDIDATES) {
+                lines.push(Line::from(Span::styled(format!(" {name}"), dim)));
+            }
+            if paths.len() > MAX_SHOWN_CANDIDATES {
+                let more = paths.len() - MAX_SHOWN_CANDIDATES;
+                lines.push(Line::from(Span::styled(
+                    format!(" … {more} more"),
+                    Style::default().fg(Color::Gray),
+                )));
+            }
+        }
     }
 
-    let paragraph = Paragraph::new(lines).block(block).wrap(Wrap { trim: false });
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
     frame.render_widget(paragraph, area);
 }
 
+/// `512B`, `2.3K`, `20.0M`, `1.5G`: file sizes in the picker.
+fn compact_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["K", "M", "G"];
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1}{}", UNITS[unit])
+}
+
+/// `512 B`, `4.0 KB`, `20.0 MB`.
+fn format_size(bytes: usize) -> String {
+    const KB: f64 = 1024.0;
+    let size = bytes as f64;
+    if size < KB {
+        format!("{bytes} B")
+    } else if size < KB * KB {
+        format!("{:.1} KB", size / KB)
+    } else {
+        format!("{:.1} MB", size / (KB * KB))
+    }
+}
+
+fn draw_baseline_details(
+    lines: &mut Vec<Line<'static>>,
+    baseline: &crate::app::Baseline,
+    row: &crate::yaml_model::VisibleRow,
+) {
+    let dim = Style::default().fg(Color::DarkGray);
+    match baseline.annotations.mark(&row.path) {
+        Some(DiffMark::Changed(previous)) => {
+            lines.push(Line::from(Span::styled(
+                format!("Baseline: {}", previous),
+                dim,
+            )));
+        }
+        Some(DiffMark::Added) => {
+            lines.push(Line::from(Span::styled("Baseline: (not present)", dim)));
+        }
+        None => {}
+    }
+    let missing = baseline.annotations.missing_under(&row.path);
+    if !missing.is_empty() {
+        lines.push(Line::from(""));
+        let arrow = if baseline.show_missing { "▾" } else { "▸" };
+        lines.push(Line::from(Span::styled(
+            format!("{} Missing vs baseline ({}) [b]", arrow, missing.len()),
+            Style::default().fg(Color::Red),
+        )));
+        if baseline.show_missing {
+            for (key, preview) in missing {
+                lines.push(Line::from(Span::styled(
+                    format!("  - {}: {}", key, preview),
+                    dim,
+                )));
+            }
+        }
+    }
+    lines.push(Line::from(Span::styled(
+        format!("Compared with {}", baseline.path.display()),
+        dim,
+    )));
+}
+
 fn draw_help(frame: &mut Frame<'_>, app: &App, area: Rect) {
     if app.is_file_picker() {
         let mode_span = Span::styled(
@@ -361,7 +858,7 @@ fn draw_help(frame: &mut Frame<'_>, app: &App, area: Rect) {
                 .bg(Color::Magenta)
                 .add_modifier(Modifier::BOLD),
         );
-        let help_text = " j/k:move Enter:open q:quit";
+        let help_text = " j/k:move Ctrl+b/f:page Enter:open N:new file q:quit";
         let line = Line::from(vec![
             mode_span,
             Span::raw(" "),
@@ -381,8 +878,40 @@ fn draw_help(frame: &mut Frame<'_>, app: &App, area: Rect) {
         Mode::ConfirmQuit => ("CONFIRM", Color::Red),
         Mode::ConfirmOpenAnother => ("CONFIRM", Color::Red),
         Mode::ConfirmRawDeleteLine => ("CONFIRM", Color::Red),
+        Mode::ConfirmRenameMerge => ("CONFIRM", Color::Red),
         Mode::SearchInput => ("SEARCH", Color::Cyan),
         Mode::RawEditLine => ("EDIT LINE", Color::LightCyan),
+        Mode::PasteKey => ("PASTE", Color::Green),
+        Mode::ConfirmSaveDuplicates => ("CONFIRM", Color::Red),
+        Mode::DuplicateList => ("DUPLICATES", Color::Yellow),
+        Mode::EditMultiline => ("EDIT TEXT", Color::Blue),
+        Mode::SettingsOverlay => ("SETTINGS", Color::Cyan),
+        Mode::StatsOverlay => ("STATS", Color::Cyan),
+        Mode::HelpOverlay => ("HELP", Color::Cyan),
+        Mode::ConfirmLocked => ("LOCKED", Color::Red),
+        Mode::ConfirmAlias => ("SHARED", Color::Magenta),
+        Mode::JournalOverlay => ("JOURNAL", Color::Yellow),
+        Mode::HookFindings => ("FINDINGS", Color::Red),
+        Mode::ConfirmLossy => ("CONFIRM", Color::Red),
+        Mode::SaveAsPath => ("SAVE AS", Color::Green),
+        Mode::ConfirmOverwrite => ("CONFIRM", Color::Red),
+        Mode::MergeConflicts => ("MERGE", Color::Red),
+        Mode::MergeValue => ("MERGE VALUE", Color::Blue),
+        Mode::NewFileName => ("NEW FILE", Color::Green),
+        Mode::ExportPath => ("EXPORT", Color::Green),
+        Mode::ConfirmExportOverwrite => ("CONFIRM", Color::Red),
+        Mode::RekeyField => ("REKEY", Color::Blue),
+        Mode::RenameKeyEverywhere => ("RENAME ALL", Color::Blue),
+        Mode::ConfirmDiskChange => ("CHANGED ON DISK", Color::Red),
+        Mode::DiskDiff => ("DISK DIFF", Color::Yellow),
+        Mode::BufferDiff => ("DIFF", Color::Yellow),
+        Mode::LintFindings => ("LINT", Color::Yellow),
+        Mode::Placeholders => ("PLACEHOLDERS", Color::Green),
+        Mode::MarkList => ("MARKS", Color::Green),
+        Mode::ReplaceCommand => ("REPLACE", Color::Blue),
+        Mode::ConfirmReplace => ("REPLACE?", Color::Red),
+        Mode::PipeCommand => ("PIPE", Color::Blue),
+        Mode::GotoPath => ("GO TO", Color::Cyan),
     };
     let mode_span = Span::styled(
         format!(" {} ", mode_label),
@@ -391,7 +920,8 @@ fn draw_help(frame: &mut Frame<'_>, app: &App, area: Rect) {
             .bg(mode_bg)
             .add_modifier(Modifier::BOLD),
     );
-    let help_text = " j/k:move h/l:fold Enter:toggle e:edit r:rename a:add Shift+A:add object d:del Shift+Del:del line y:copy /:search Ctrl+s:save Ctrl+o:open another q:quit";
+    let help_text =
+        " j/k:move h/l:fold e:edit a:add d:del u:undo /:search Ctrl+s:save q:quit F1:help";
     let line = Line::from(vec![
         mode_span,
         Span::raw(" "),
@@ -404,7 +934,13 @@ fn draw_help(frame: &mut Frame<'_>, app: &App, area: Rect) {
 fn draw_overlay(frame: &mut Frame<'_>, app: &App, area: Rect) {
     // Draw confirm dialogs
     let confirm_message: Option<String> = match app.mode {
-        Mode::ConfirmDelete => Some("Delete node? (y/n)".to_string()),
+        Mode::ConfirmDelete => match app.current_row().map(|r| &r.origin) {
+            Some(NodeOrigin::MergeKey(label)) => Some(format!(
+                "Remove merge of {}? Inherited keys will disappear. (y/n)",
+                label
+            )),
+            _ => Some("Delete node? (y/n, p: copy JSON Patch remove op instead)".to_string()),
+        },
         Mode::ConfirmQuit => {
             if app.dirty {
                 Some("Unsaved changes. Quit? (y/n)".to_string())
@@ -416,6 +952,41 @@ fn draw_overlay(frame: &mut Frame<'_>, app: &App, area: Rect) {
             Some("Open another file? Unsaved changes will be lost. (y/n)".to_string())
         }
         Mode::ConfirmRawDeleteLine => Some("Delete this line? (y/n)".to_string()),
+        Mode::ConfirmRenameMerge => {
+            Some("Rename the << merge key? It will stop merging. (y/n)".to_string())
+        }
+        Mode::ConfirmLocked => app.lock_holder.as_ref().map(|holder| {
+            format!(
+                "Also being edited by pid {} on {} since {} — r: read-only / o: open anyway / q: quit",
+                holder.pid,
+                holder.host,
+                holder.since_label()
+            )
+        }),
+        Mode::ConfirmAlias => app.pending_shared_edit.as_ref().map(|(_, shared)| {
+            format!(
+                "&{} is shared by {} alias(es) — s: edit shared value / b: break alias / Esc: cancel",
+                shared.anchor, shared.aliases
+            )
+        }),
+        Mode::ConfirmLossy => app.lossy_prompt(),
+        Mode::ConfirmReplace => app.replace_prompt(),
+        Mode::ConfirmDiskChange => Some(
+            "File changed on disk — m: merge / r: reload (lose your edits) / k: keep yours / d: diff / =: line diff"
+                .to_string(),
+        ),
+        Mode::ConfirmOverwrite => app
+            .pending_save_as
+            .as_ref()
+            .map(|path| format!("{} exists. Overwrite? (y/n)", path.display())),
+        Mode::ConfirmExportOverwrite => app
+            .pending_export
+            .as_ref()
+            .map(|path| format!("{} exists. Overwrite? (y/n)", path.display())),
+        Mode::ConfirmSaveDuplicates => Some(format!(
+            "{} duplicate key(s) were collapsed; only the last values will be saved. Save? (y/n)",
+            app.model.duplicates().len()
+        )),
         _ => None,
     };
     if let Some(message) = confirm_message {
@@ -426,6 +997,45 @@ fn draw_overlay(frame: &mut Frame<'_>, app: &App, area: Rect) {
         let paragraph = Paragraph::new(message.as_str()).block(block);
         frame.render_widget(paragraph, rect);
     }
+    if app.mode == Mode::DuplicateList {
+        draw_duplicate_list(frame, app, area);
+    }
+    if app.mode == Mode::EditMultiline {
+        draw_multiline_editor(frame, app, area);
+    }
+    if app.mode == Mode::SettingsOverlay {
+        draw_settings(frame, app, area);
+    }
+    if app.mode == Mode::StatsOverlay {
+        draw_stats(frame, app, area);
+    }
+    if app.mode == Mode::HelpOverlay {
+        draw_key_help(frame, app, area);
+    }
+    if app.mode == Mode::JournalOverlay {
+        draw_journal(frame, app, area);
+    }
+    if app.mode == Mode::HookFindings {
+        draw_findings(frame, app, area);
+    }
+    if app.mode == Mode::MergeConflicts {
+        draw_merge(frame, app, area);
+    }
+    if app.mode == Mode::DiskDiff {
+        draw_disk_diff(frame, app, area);
+    }
+    if app.mode == Mode::BufferDiff {
+        draw_buffer_diff(frame, app, area);
+    }
+    if app.mode == Mode::LintFindings {
+        draw_lint(frame, app, area);
+    }
+    if app.mode == Mode::Placeholders {
+        draw_placeholders(frame, app, area);
+    }
+    if app.mode == Mode::MarkList {
+        draw_marks(frame, app, area);
+    }
     // Draw toast message in center
     if let Some(toast) = &app.toast {
         let block = Block::default()
@@ -442,6 +1052,560 @@ fn draw_overlay(frame: &mut Frame<'_>, app: &App, area: Rect) {
     }
 }
 
+fn draw_duplicate_list(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let lines: Vec<Line> = app
+        .model
+        .duplicates()
+        .iter()
+        .enumerate()
+        .map(|(idx, dup)| {
+            let text = format!("line {:>4}  {}", dup.line, dup.path.dot_path());
+            Line::from(Span::styled(
+                text,
+                list_style(idx == app.list_selection, Style::default()),
+            ))
+        })
+        .collect();
+    let title = "Duplicate keys (Enter: jump, Esc: close)";
+    draw_list_overlay(frame, area, title, Color::Yellow, lines, app.list_selection);
+}
+
+fn draw_journal(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let lines: Vec<Line> = app
+        .journal
+        .newest_first()
+        .enumerate()
+        .map(|(idx, entry)| {
+            Line::from(Span::styled(
+                entry.summary(),
+                list_style(idx == app.list_selection, Style::default()),
+            ))
+        })
+        .collect();
+    let title = "Edits, newest first (Enter: jump, u: undo to here, y: copy, Esc: close)";
+    draw_list_overlay(frame, area, title, Color::Yellow, lines, app.list_selection);
+}
+
+fn draw_buffer_diff(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let lines: Vec<Line> = app
+        .buffer_diff
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| {
+            let color = match line {
+                DiffLine::Hunk(_) => Color::Cyan,
+                DiffLine::Added(_) => Color::Green,
+                DiffLine::Removed(_) => Color::Red,
+                DiffLine::Context(_) => Color::Gray,
+            };
+            Line::from(Span::styled(
+                line.text(),
+                list_style(idx == app.list_selection, Style::default().fg(color)),
+            ))
+        })
+        .collect();
+    let title = "Disk → buffer (j/k: scroll, Esc: close)";
+    draw_list_overlay(frame, area, title, Color::Yellow, lines, app.list_selection);
+}
+
+fn draw_disk_diff(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let mut lines: Vec<Line> = app
+        .disk_diff_lines()
+        .into_iter()
+        .enumerate()
+        .map(|(idx, text)| {
+            let color = match text.chars().next() {
+                Some('+') => Color::Green,
+                Some('-') => Color::Red,
+                _ => Color::Yellow,
+            };
+            Line::from(Span::styled(
+                text,
+                list_style(idx == app.list_selection, Style::default().fg(color)),
+            ))
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "(same content)",
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    draw_list_overlay(
+        frame,
+        area,
+        "Buffer → disk (Esc: back)",
+        Color::Yellow,
+        lines,
+        app.list_selection,
+    );
+}
+
+fn draw_findings(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let Some(result) = &app.hook_result else {
+        return;
+    };
+    let mut lines: Vec<Line> = result
+        .findings
+        .iter()
+        .enumerate()
+        .map(|(idx, finding)| {
+            Line::from(Span::styled(
+                finding.summary(),
+                list_style(idx == app.list_selection, Style::default()),
+            ))
+        })
+        .collect();
+    // Nothing parsed as a finding: show what the command printed instead.
+    if lines.is_empty() {
+        lines = result
+            .output
+            .lines()
+            .map(|line| {
+                Line::from(Span::styled(
+                    line.to_string(),
+                    Style::default().fg(Color::Gray),
+                ))
+            })
+            .collect();
+    }
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "(no output)",
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    let title = format!("{} (Enter: jump, Esc: close)", result.summary());
+    let border = if result.has_problems() {
+        Color::Red
+    } else {
+        Color::Green
+    };
+    draw_list_overlay(frame, area, &title, border, lines, app.list_selection);
+}
+
+/// `Resolved: ...` for a value with `${VAR}` references: set variables substituted, unset ones
+/// left as written and shown in red.
+fn resolved_line(text: &str) -> Line<'static> {
+    let mut spans = vec![Span::raw("Resolved: ")];
+    for piece in interpolate::pieces(text) {
+        spans.push(match piece {
+            Piece::Text(text) => Span::raw(text.to_string()),
+            Piece::Var { reference, name } => match std::env::var(name) {
+                Ok(value) => Span::styled(value, Style::default().fg(Color::Green)),
+                Err(_) => Span::styled(
+                    reference.to_string(),
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+            },
+        });
+    }
+    Line::from(spans)
+}
+
+fn draw_placeholders(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let name_width = app
+        .placeholders
+        .iter()
+        .map(|p| p.name.width())
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<Line> = app
+        .placeholders
+        .iter()
+        .enumerate()
+        .map(|(idx, placeholder)| {
+            let set = std::env::var_os(&placeholder.name).is_some();
+            let uses = if placeholder.uses == 1 {
+                "1 use".to_string()
+            } else {
+                format!("{} uses", placeholder.uses)
+            };
+            let text = format!(
+                "{:<name_width$}  {:<5}  {uses}, first at {}",
+                placeholder.name,
+                if set { "set" } else { "unset" },
+                placeholder.path.dot_path(),
+            );
+            let normal = if set {
+                Style::default()
+            } else {
+                Style::default().fg(Color::Red)
+            };
+            Line::from(Span::styled(
+                text,
+                list_style(idx == app.list_selection, normal),
+            ))
+        })
+        .collect();
+    let unset = app
+        .placeholders
+        .iter()
+        .filter(|p| std::env::var_os(&p.name).is_none())
+        .count();
+    let title = format!(
+        "Placeholders: {} variable(s), {unset} unset (Enter: jump, Esc: close)",
+        app.placeholders.len()
+    );
+    draw_list_overlay(frame, area, &title, Color::Green, lines, app.list_selection);
+}
+
+fn draw_marks(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let lines: Vec<Line> = app
+        .mark_list()
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (letter, path, exists))| {
+            let label = if path.0.is_empty() {
+                "(root)".to_string()
+            } else {
+                path.dot_path()
+            };
+            let text = if exists {
+                format!("{letter}  {label}")
+            } else {
+                format!("{letter}  {label}  (deleted)")
+            };
+            let normal = if exists {
+                Style::default()
+            } else {
+                Style::default().fg(Color::Red)
+            };
+            Line::from(Span::styled(
+                text,
+                list_style(idx == app.list_selection, normal),
+            ))
+        })
+        .collect();
+    let title = format!("Marks: {} (Enter: jump, Esc: close)", lines.len());
+    draw_list_overlay(frame, area, &title, Color::Green, lines, app.list_selection);
+}
+
+fn draw_lint(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let lines: Vec<Line> = app
+        .lint
+        .iter()
+        .enumerate()
+        .map(|(idx, finding)| {
+            Line::from(Span::styled(
+                finding.summary(),
+                list_style(idx == app.list_selection, Style::default()),
+            ))
+        })
+        .collect();
+    let title = format!(
+        "Lint: {} finding(s) (Enter: jump, Esc: close)",
+        app.lint.len()
+    );
+    draw_list_overlay(
+        frame,
+        area,
+        &title,
+        Color::Yellow,
+        lines,
+        app.list_selection,
+    );
+}
+
+fn draw_merge(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let Some(state) = &app.merge else {
+        return;
+    };
+    let mut lines: Vec<Line> = state
+        .conflicts
+        .iter()
+        .zip(&state.picks)
+        .enumerate()
+        .map(|(idx, (conflict, pick))| {
+            let choice = match pick {
+                None => "?",
+                Some(Pick::Take(Side::Disk)) => "disk",
+                Some(Pick::Take(Side::Buffer)) => "buffer",
+                Some(Pick::Take(Side::Baseline)) => "baseline",
+                Some(Pick::Custom(_)) => "custom",
+            };
+            let normal = if pick.is_none() {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            let path = if conflict.path.0.is_empty() {
+                "(root)".to_string()
+            } else {
+                conflict.path.dot_path()
+            };
+            Line::from(Span::styled(
+                format!("[{choice:^8}] {path}"),
+                list_style(idx == app.list_selection, normal),
+            ))
+        })
+        .collect();
+    // The three versions of the selected conflict, below the list.
+    if let Some(conflict) = state.conflicts.get(app.list_selection) {
+        lines.push(Line::from(""));
+        for (label, side) in [
+            ("o baseline", Side::Baseline),
+            ("d disk    ", Side::Disk),
+            ("b buffer  ", Side::Buffer),
+        ] {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{label}  "), Style::default().fg(Color::Yellow)),
+                Span::raw(side_preview(conflict.value(side))),
+            ]));
+        }
+    }
+    let title = format!(
+        "{} conflict(s) with disk (d/b/o: take, e: edit, Enter: finish, Esc: cancel)",
+        state.conflicts.len()
+    );
+    draw_list_overlay(frame, area, &title, Color::Red, lines, app.list_selection);
+}
+
+/// A bordered list centred over `area`, at least 40 columns and as wide as its widest line or
+/// title, scrolled so row `selected` stays in view.
+fn draw_list_overlay(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    title: &str,
+    border: Color,
+    lines: Vec<Line>,
+    selected: usize,
+) {
+    let width = lines
+        .iter()
+        .map(|l| l.width())
+        .chain([title.width()])
+        .max()
+        .unwrap_or(0)
+        .max(40)
+        .saturating_add(4)
+        .min(area.width as usize) as u16;
+    let height = (lines.len() as u16).saturating_add(2).min(area.height);
+    let rect = centered_rect(width, height, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border))
+        .title(title);
+    let scroll = selected.saturating_sub(height.saturating_sub(3) as usize) as u16;
+    frame.render_widget(ratatui::widgets::Clear, rect);
+    frame.render_widget(Paragraph::new(lines).block(block).scroll((scroll, 0)), rect);
+}
+
+/// The selected row of a list, in the tree, the file picker and every list overlay.
+fn selected_style() -> Style {
+    Style::default()
+        .fg(Color::Black)
+        .bg(Color::Cyan)
+        .add_modifier(Modifier::BOLD)
+}
+
+/// `normal`, or [`selected_style`] for the selected row.
+fn list_style(selected: bool, normal: Style) -> Style {
+    if selected {
+        selected_style()
+    } else {
+        normal
+    }
+}
+
+fn draw_settings(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let entries = app.settings.describe();
+    let name_width = entries
+        .iter()
+        .map(|(name, _, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<Line> = entries
+        .iter()
+        .map(|(name, value, source)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<width$}  ", name, width = name_width),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::raw(value.clone()),
+                Span::styled(format!("  ({})", source), Style::default().fg(Color::Gray)),
+            ])
+        })
+        .collect();
+    let width = lines
+        .iter()
+        .map(|l| l.width())
+        .max()
+        .unwrap_or(0)
+        .max(40)
+        .saturating_add(4)
+        .min(area.width as usize) as u16;
+    let height = (lines.len() as u16).saturating_add(2).min(area.height);
+    let rect = centered_rect(width, height, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title("Settings (Esc: close)");
+    frame.render_widget(ratatui::widgets::Clear, rect);
+    frame.render_widget(Paragraph::new(lines).block(block), rect);
+}
+
+fn draw_stats(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let stats = stats::tree_stats(&app.tree_root);
+    let label = Style::default().fg(Color::Yellow);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Nodes      ", label),
+            Span::raw(stats.nodes.to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled("Leaves     ", label),
+            Span::raw(stats.leaves.to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled("Max depth  ", label),
+            Span::raw(stats.max_depth.to_string()),
+        ]),
+        Line::from(""),
+    ];
+    for (node_type, count) in &stats.by_type {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:<11}", node_type.to_string()),
+                Style::default().fg(Color::Gray),
+            ),
+            Span::raw(count.to_string()),
+        ]));
+    }
+    let width = 30.min(area.width);
+    let height = (lines.len() as u16).saturating_add(2).min(area.height);
+    let rect = centered_rect(width, height, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title("Stats (Esc: close)");
+    frame.render_widget(ratatui::widgets::Clear, rect);
+    frame.render_widget(Paragraph::new(lines).block(block), rect);
+}
+
+/// Every binding by category over the whole body, scrolled by `list_selection`.
+fn draw_key_help(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let help = app.vim.keymap().help();
+    let keys_width = help
+        .iter()
+        .flat_map(|(_, rows)| rows)
+        .map(|(keys, _)| keys.width())
+        .max()
+        .unwrap_or(0);
+    let mut lines = Vec::new();
+    for (section, rows) in help {
+        lines.push(Line::from(Span::styled(
+            section,
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )));
+        for (keys, about) in rows {
+            let keys = if keys.is_empty() {
+                "(unbound)".to_string()
+            } else {
+                keys
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {keys:<keys_width$}  "),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::raw(about),
+            ]));
+        }
+        lines.push(Line::from(""));
+    }
+    let visible = area.height.saturating_sub(2) as usize;
+    let scroll = app.list_selection.min(lines.len().saturating_sub(visible));
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title("Keys (j/k, Ctrl+d/u: scroll, Esc or F1: close)");
+    frame.render_widget(ratatui::widgets::Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .scroll((scroll as u16, 0)),
+        area,
+    );
+}
+
+fn draw_multiline_editor(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let area_state = &app.textarea;
+    let width = (area.width as usize * 7 / 10)
+        .max(30)
+        .min(area.width as usize) as u16;
+    let height = (area.height as usize * 6 / 10)
+        .max(5)
+        .min(area.height as usize) as u16;
+    let rect = centered_rect(width, height, area);
+    let inner_width = width.saturating_sub(2) as usize;
+    let inner_height = height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = area_state
+        .lines
+        .iter()
+        .enumerate()
+        .map(|(idx, text)| {
+            let mut text = text.clone();
+            if idx == area_state.row {
+                let cursor = width::floor_grapheme_boundary(&text, area_state.col);
+                text.insert(cursor, '▌');
+            }
+            Line::from(text)
+        })
+        .collect();
+    let cursor_col = width::column_span(&area_state.lines[area_state.row], 0, area_state.col).1;
+    let scroll_y = area_state
+        .row
+        .saturating_sub(inner_height.saturating_sub(1)) as u16;
+    let scroll_x = cursor_col.saturating_sub(inner_width.saturating_sub(1)) as u16;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Blue))
+        .title("Edit text (Ctrl+s: save, Esc: cancel)");
+    frame.render_widget(ratatui::widgets::Clear, rect);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .scroll((scroll_y, scroll_x)),
+        rect,
+    );
+}
+
+/// Foreground for a scalar value in the tree, by type.
+fn value_color(node_type: &NodeType) -> Option<Color> {
+    match node_type {
+        NodeType::String => Some(Color::Green),
+        NodeType::Number => Some(Color::Cyan),
+        NodeType::Bool => Some(Color::Magenta),
+        NodeType::Null => Some(Color::Gray),
+        NodeType::Timestamp => Some(Color::LightBlue),
+        _ => None,
+    }
+}
+
+/// Hit target for a row rendered inside a bordered block: `key_start..key_end` is the
+/// byte range of the key within `line`, converted to screen columns.
+fn row_hit(
+    row_index: usize,
+    y: u16,
+    area: Rect,
+    line: &str,
+    key_start: usize,
+    key_end: usize,
+) -> RowHit {
+    let (col_start, col_end) = width::column_span(line, key_start, key_end);
+    let x0 = area.x + 1;
+    RowHit {
+        row_index,
+        y,
+        key_x_start: x0 + col_start as u16,
+        key_x_end: x0 + col_end.saturating_sub(1).max(col_start) as u16,
+    }
+}
+
 fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
     let x = area.x + area.width.saturating_sub(width) / 2;
     let y = area.y + area.height.saturating_sub(height) / 2;
@@ -452,3 +1616,1130 @@ fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
         height,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::app_with;
+    use crate::input::{DiskChoice, InputAction};
+    use crate::search::CaseMode;
+    use crate::settings::CliOverrides;
+    use crate::yaml_model::{NodePath, PathSegment};
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use std::path::PathBuf;
+
+    /// Render and return, for each hit, the text found in the buffer between its key columns.
+    fn rendered_keys(app: &mut App) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        let mut hits = Vec::new();
+        terminal
+            .draw(|frame| {
+                hits = draw(frame, app);
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer().clone();
+        hits.iter()
+            .map(|hit| {
+                let mut text = String::new();
+                let mut x = hit.key_x_start;
+                while x <= hit.key_x_end {
+                    let symbol = buffer.get(x, hit.y).symbol();
+                    text.push_str(symbol);
+                    // Wide glyphs occupy trailing cells that hold no text of their own.
+                    x += symbol.width().max(1) as u16;
+                }
+                text
+            })
+            .collect()
+    }
+
+    /// Render and return the glyph right after the input cursor mark, wherever the prompt put it.
+    fn glyph_after_cursor(app: &mut App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|frame| {
+                draw(frame, app);
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let mut screen = Vec::new();
+        for y in 0..buffer.area.height {
+            let mut x = 0;
+            while x < buffer.area.width {
+                let symbol = buffer.get(x, y).symbol();
+                screen.push(symbol.to_string());
+                x += symbol.width().max(1) as u16;
+            }
+        }
+        let mark = screen.iter().position(|symbol| symbol == "▌").unwrap();
+        screen[mark + 1].clone()
+    }
+
+    #[test]
+    fn tree_hits_cover_wide_keys() {
+        let mut app = app_with(
+            "wide",
+            "서버:\n  포트: 8080\nemoji: \"🎉🎉\"\ncafe\u{301}: 1\n",
+        );
+        app.expanded.insert("서버".to_string());
+        app.rebuild_visible();
+        let keys = rendered_keys(&mut app);
+        assert_eq!(keys, vec!["(root)", "서버", "포트", "emoji", "cafe\u{301}"]);
+    }
+
+    #[test]
+    fn clicking_a_key_folds_and_clicking_its_row_selects() {
+        use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+        let mut app = app_with("click", "서버:\n  포트: 8080\nother: 1\n");
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        let mut click = |app: &mut App, dot: &str, x: fn(&RowHit) -> u16| {
+            let mut hits = Vec::new();
+            terminal.draw(|frame| hits = draw(frame, app)).unwrap();
+            let index = app
+                .visible
+                .iter()
+                .position(|row| row.path.dot_path() == dot)
+                .unwrap();
+            let hit = hits
+                .iter()
+                .find(|hit| hit.row_index == index)
+                .unwrap()
+                .clone();
+            app.update_hit_map(hits);
+            let mouse = MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: x(&hit),
+                row: hit.y,
+                modifiers: KeyModifiers::NONE,
+            };
+            app.handle_mouse(mouse, 10).unwrap();
+        };
+        // Past the key: selected, still folded.
+        click(&mut app, "서버", |hit| hit.key_x_end + 1);
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "서버");
+        assert!(!app.expanded.contains("서버"));
+        // The second cell of the wide `버` is still the key.
+        click(&mut app, "서버", |hit| hit.key_x_end);
+        assert!(app.expanded.contains("서버"));
+        click(&mut app, "서버", |hit| hit.key_x_start);
+        assert!(!app.expanded.contains("서버"));
+    }
+
+    #[test]
+    fn tree_values_are_colored_by_type() {
+        let yaml = "name: api\nport: 80\nflag: true\nnone: null\n";
+        // Foreground of the first key cell and the first value cell of each row.
+        let colors = |app: &mut App| -> Vec<(Color, Color)> {
+            let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+            let mut hits = Vec::new();
+            terminal.draw(|frame| hits = draw(frame, app)).unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            hits.iter()
+                .skip(1)
+                .map(|hit| {
+                    let key = buffer.get(hit.key_x_start, hit.y).fg;
+                    let value = buffer.get(hit.key_x_end + 4, hit.y).fg;
+                    (key, value)
+                })
+                .collect()
+        };
+
+        let mut app = app_with("colors", yaml);
+        assert_eq!(
+            colors(&mut app),
+            vec![
+                (Color::Reset, Color::Green),
+                (Color::Reset, Color::Cyan),
+                (Color::Reset, Color::Magenta),
+                (Color::Reset, Color::Gray),
+            ]
+        );
+        // The selection bar keeps its own foreground so values stay readable on cyan.
+        app.selection = 2;
+        assert_eq!(colors(&mut app)[1], (Color::Black, Color::Black));
+
+        app.cli.no_color = true;
+        app.selection = 0;
+        assert!(colors(&mut app)
+            .iter()
+            .all(|&colors| colors == (Color::Reset, Color::Reset)));
+    }
+
+    #[test]
+    fn search_matches_are_highlighted_in_tree_rows() {
+        let mut app = app_with("highlight", "httpPort: 80\nname: api\n");
+        app.search_query = Some(Query::plain("port"));
+        // Background of each key cell of the `httpPort` row.
+        let backgrounds = |app: &mut App| -> Vec<Color> {
+            let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+            let mut hits = Vec::new();
+            terminal.draw(|frame| hits = draw(frame, app)).unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            let hit = &hits[1];
+            (hit.key_x_start..=hit.key_x_end)
+                .map(|x| buffer.get(x, hit.y).bg)
+                .collect()
+        };
+
+        let mut expected = vec![Color::Reset; 4];
+        expected.extend([Color::Yellow; 4]);
+        assert_eq!(backgrounds(&mut app), expected);
+        // On the selection bar the match keeps its highlight against the cyan background.
+        app.selection = 1;
+        let mut expected = vec![Color::Cyan; 4];
+        expected.extend([Color::Yellow; 4]);
+        assert_eq!(backgrounds(&mut app), expected);
+
+        app.search_query = None;
+        assert!(backgrounds(&mut app).iter().all(|&bg| bg == Color::Cyan));
+
+        // A match in the path to a key, but not in the key itself, marks the whole key.
+        let mut app = app_with("highlight_path", "server:\n  port: 80\n");
+        app.search_query = Some(Query::plain("server.po"));
+        app.rebuild_visible();
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        let mut hits = Vec::new();
+        terminal.draw(|frame| hits = draw(frame, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer().clone();
+        let port = app
+            .visible
+            .iter()
+            .position(|row| row.path.dot_path() == "server.port")
+            .unwrap();
+        let hit = hits.iter().find(|hit| hit.row_index == port).unwrap();
+        let key: Vec<_> = (hit.key_x_start..=hit.key_x_end)
+            .map(|x| buffer.get(x, hit.y).bg)
+            .collect();
+        assert_eq!(key, vec![Color::Yellow; 4]);
+    }
+
+    #[test]
+    fn search_case_cycles_and_applies_to_filter_and_matches() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = app_with("search_case", "Name: a\nname: b\nnested:\n  NAME: c\n");
+        let search = |app: &mut App, query: &str| {
+            app.apply_action(InputAction::StartSearch, 10).unwrap();
+            app.input.set(query.to_string());
+            app.apply_action(InputAction::InputCommit, 10).unwrap();
+            app.matches
+                .iter()
+                .map(|&idx| app.visible[idx].path.dot_path())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(app.search_case, CaseMode::Insensitive);
+        assert_eq!(
+            search(&mut app, "k:name"),
+            vec!["Name", "name", "nested.NAME"]
+        );
+
+        app.apply_action(InputAction::StartSearch, 10).unwrap();
+        let alt_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::ALT);
+        app.handle_key(alt_c, 10).unwrap();
+        assert_eq!(app.search_case, CaseMode::Sensitive);
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+        assert_eq!(search(&mut app, "name"), vec!["name"]);
+        // The filter hides what the case-sensitive query does not match.
+        assert!(app
+            .visible
+            .iter()
+            .all(|row| row.path.dot_path() != "nested"));
+
+        app.search_case = CaseMode::Smart;
+        assert_eq!(
+            search(&mut app, "name"),
+            vec!["Name", "name", "nested.NAME"]
+        );
+        assert_eq!(search(&mut app, "NAME"), vec!["nested.NAME"]);
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal
+            .draw(|frame| {
+                draw(frame, &mut app);
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer().clone();
+        let status: String = (0..100).map(|x| buffer.get(x, 0).symbol()).collect();
+        assert!(status.contains("1/1 smart case"), "{status}");
+    }
+
+    #[test]
+    fn marks_jump_back_by_path() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = app_with(
+            "marks",
+            "server:\n  tls:\n    cert: a.pem\nname: api\nport: 80\n",
+        );
+        let press = |app: &mut App, ch: char| {
+            app.handle_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE), 10)
+                .unwrap();
+        };
+        let cert = NodePath(vec![
+            PathSegment::Key("server".into()),
+            PathSegment::Key("tls".into()),
+            PathSegment::Key("cert".into()),
+        ]);
+        assert!(app.reveal_path(&cert));
+        press(&mut app, 'm');
+        press(&mut app, 'a');
+        assert_eq!(app.marks.get(&'a'), Some(&cert));
+
+        // Folded away and moved off, the mark still finds the node by path.
+        app.expanded.clear();
+        app.rebuild_visible();
+        app.selection = 0;
+        press(&mut app, '\'');
+        press(&mut app, 'a');
+        assert_eq!(app.current_row().unwrap().path, cert);
+
+        press(&mut app, '\'');
+        press(&mut app, 'b');
+        assert_eq!(app.toast.as_ref().unwrap().message, "Mark 'b' is not set");
+
+        app.apply_action(InputAction::DeleteNode, 10).unwrap();
+        app.apply_action(InputAction::ConfirmYes, 10).unwrap();
+        app.selection = 0;
+        press(&mut app, '\'');
+        press(&mut app, 'a');
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Mark 'a': target server.tls.cert no longer exists"
+        );
+        assert_eq!(app.selection, 0);
+
+        // `'?` lists the marks, the deleted target in red, and Enter jumps to one.
+        press(&mut app, 'm');
+        press(&mut app, 'z');
+        app.selection = app
+            .visible
+            .iter()
+            .position(|row| row.path.dot_path() == "port")
+            .unwrap();
+        press(&mut app, 'm');
+        press(&mut app, 'b');
+        press(&mut app, '\'');
+        press(&mut app, '?');
+        assert_eq!(app.mode, Mode::MarkList);
+        app.toast = None;
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|frame| {
+                draw(frame, &mut app);
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen = (0..20)
+            .map(|y| {
+                (0..80)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(
+            screen.contains("Marks: 3 (Enter: jump, Esc: close)"),
+            "{screen}"
+        );
+        assert!(screen.contains("a  server.tls.cert  (deleted)"), "{screen}");
+        assert!(screen.contains("z  (root)"), "{screen}");
+        press(&mut app, 'j');
+        app.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "port");
+    }
+
+    #[test]
+    fn kubernetes_manifests_show_kind_and_flag_misplaced_keys() {
+        let mut app = app_with(
+            "k8s",
+            "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: api\nspec:\n  containers: []\n  template: {}\n",
+        );
+        let screen = |app: &mut App| {
+            let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+            terminal
+                .draw(|frame| {
+                    draw(frame, app);
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            (0..20)
+                .map(|y| {
+                    (0..120)
+                        .map(|x| buffer.get(x, y).symbol())
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        assert!(screen(&mut app)
+            .lines()
+            .next()
+            .unwrap()
+            .starts_with(" Deployment apps/v1 "));
+        assert!(app.reveal_path(&NodePath(vec![
+            PathSegment::Key("spec".into()),
+            PathSegment::Key("containers".into())
+        ])));
+        let text = screen(&mut app);
+        assert!(
+            text.contains("Kubernetes: containers is not a Deployment field"),
+            "{text}"
+        );
+
+        // Without a schema, new keys are suggested from the kind's fields.
+        assert!(app.reveal_path(&NodePath(vec![PathSegment::Key("spec".into())])));
+        app.apply_action(InputAction::AddChild, 10).unwrap();
+        app.input.set("re".to_string());
+        assert_eq!(app.key_completions(), ["replicas", "revisionHistoryLimit"]);
+    }
+
+    #[test]
+    fn env_preview_resolves_without_touching_the_value() {
+        std::env::set_var("YED_PREVIEW_HOST", "db.local");
+        std::env::remove_var("YED_PREVIEW_NOPE");
+        let mut app = app_with(
+            "envpreview",
+            "url: ${YED_PREVIEW_HOST}/$YED_PREVIEW_NOPE\nport: 80\n",
+        );
+        let screen = |app: &mut App| {
+            let mut terminal = Terminal::new(TestBackend::new(140, 20)).unwrap();
+            terminal
+                .draw(|frame| {
+                    draw(frame, app);
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            (0..20)
+                .map(|y| {
+                    (0..140)
+                        .map(|x| buffer.get(x, y).symbol())
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        assert!(app.reveal_path(&NodePath(vec![PathSegment::Key("url".into())])));
+        assert!(!screen(&mut app).contains("Resolved:"));
+        app.apply_action(InputAction::ToggleEnvPreview, 10).unwrap();
+        let text = screen(&mut app);
+        assert!(
+            text.contains("Resolved: db.local/$YED_PREVIEW_NOPE"),
+            "{text}"
+        );
+        assert!(text.lines().next().unwrap().contains(" $ENV "));
+
+        app.toast = None;
+        app.apply_action(InputAction::ShowPlaceholders, 10).unwrap();
+        assert_eq!(app.mode, Mode::Placeholders);
+        let text = screen(&mut app);
+        assert!(text.contains("2 variable(s), 1 unset"), "{text}");
+        assert!(
+            text.contains("YED_PREVIEW_NOPE  unset  1 use, first at url"),
+            "{text}"
+        );
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+
+        // Saving writes the placeholders, never the resolved text.
+        app.dirty = true;
+        app.save().unwrap();
+        let saved = std::fs::read_to_string(app.model.file_path()).unwrap();
+        assert!(
+            saved.contains("${YED_PREVIEW_HOST}/$YED_PREVIEW_NOPE") && !saved.contains("db.local"),
+            "{saved}"
+        );
+
+        app.selection = 2;
+        app.apply_action(InputAction::ToggleEnvPreview, 10).unwrap();
+        assert!(!app.env_preview);
+    }
+
+    #[test]
+    fn breadcrumb_shows_one_chip_per_segment() {
+        let path = NodePath(vec![
+            PathSegment::Key("spec".into()),
+            PathSegment::Key("containers".into()),
+            PathSegment::Index(0),
+            PathSegment::Key("image".into()),
+        ]);
+        let line = breadcrumb(&path, false);
+        let text: String = line
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(text, " (root)  ›  spec  ›  containers  ›  [0]  ›  image ");
+        assert_eq!(line.spans[2].style.bg, Some(Color::Blue));
+        assert_eq!(line.spans[6].style.bg, Some(Color::Magenta));
+        assert!(breadcrumb(&path, true)
+            .spans
+            .iter()
+            .all(|span| span.style.bg.is_none()));
+        assert_eq!(breadcrumb(&NodePath(Vec::new()), false).spans.len(), 1);
+    }
+
+    #[test]
+    fn details_show_the_whole_value_the_tree_truncates() {
+        let url =
+            "https://registry.example.com/v2/platform/images/api/manifests/sha256 digest ENDMARK";
+        let mut app = app_with("long_value", &format!("url: {url}\n"));
+        let preview = &app.visible[1].display_value_preview;
+        assert!(
+            preview.ends_with('…') && width::display_width(preview) <= 40,
+            "{preview}"
+        );
+
+        app.selection = 1;
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal
+            .draw(|frame| {
+                draw(frame, &mut app);
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer().clone();
+        let screen: String = (0..20)
+            .map(|y| {
+                (0..100)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect::<String>()
+            })
+            .collect();
+        assert_eq!(screen.matches("ENDMARK").count(), 1, "{screen}");
+
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        assert_eq!(app.input.text, format!("\"{url}\""));
+    }
+
+    #[test]
+    fn broken_files_open_on_the_error_line() {
+        let lines: Vec<String> = (1..=12)
+            .map(|n| {
+                if n == 6 {
+                    "f: @x".to_string()
+                } else {
+                    format!("k{n}: {n}")
+                }
+            })
+            .collect();
+        let mut app = app_with("parse_error", &lines.join("\n"));
+        assert_eq!(
+            app.parse_error.as_ref().map(|e| (e.line, e.column)),
+            Some((6, 4))
+        );
+        assert_eq!((app.selection, app.scroll), (5, 2));
+
+        // The error line is red once the selection moves off it.
+        app.apply_action(InputAction::JumpTop, 5).unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        let mut hits = Vec::new();
+        terminal.draw(|frame| hits = draw(frame, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer().clone();
+        let fg = |row: usize| {
+            let hit = hits.iter().find(|hit| hit.row_index == row).unwrap();
+            buffer.get(hit.key_x_start, hit.y).fg
+        };
+        assert_eq!((fg(5), fg(4)), (Color::Red, Color::Reset));
+        assert_eq!(app.selection, 0);
+
+        app.apply_action(InputAction::JumpToParseError, 5).unwrap();
+        assert_eq!(app.selection, 5);
+        assert!(app.scroll <= 5 && 5 < app.scroll + 5);
+
+        let mut fine = app_with("no_parse_error", "a: 1\n");
+        fine.apply_action(InputAction::JumpToParseError, 5).unwrap();
+        assert_eq!(
+            fine.toast.as_ref().map(|t| t.message.as_str()),
+            Some("No parse error")
+        );
+    }
+
+    #[test]
+    fn huge_values_scroll_past_quickly_and_page_into_details() {
+        const SIZE: usize = 20 * 1024 * 1024;
+        let mut app = app_with(
+            "huge_value",
+            &format!("a: 1\nblob: {}\nz: 2\n", "x".repeat(SIZE)),
+        );
+        assert_eq!(app.visible[2].value_size, SIZE);
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        let mut screen = |app: &mut App| {
+            terminal
+                .draw(|frame| {
+                    draw(frame, app);
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            (0..20)
+                .map(|y| {
+                    (0..100)
+                        .map(|x| buffer.get(x, y).symbol())
+                        .collect::<String>()
+                })
+                .collect::<String>()
+        };
+
+        let started = std::time::Instant::now();
+        for action in [
+            InputAction::MoveDown,
+            InputAction::MoveDown,
+            InputAction::MoveDown,
+            InputAction::MoveUp,
+        ] {
+            app.apply_action(action, 10).unwrap();
+            screen(&mut app);
+        }
+        let elapsed = started.elapsed();
+        assert!(
+            elapsed < std::time::Duration::from_millis(500),
+            "{elapsed:?}"
+        );
+
+        assert_eq!(app.selection, 2);
+        let text = screen(&mut app);
+        assert!(
+            text.contains("Size: 20.0 MB (4.0 KB shown; Enter: load"),
+            "{text}"
+        );
+        app.apply_action(InputAction::ToggleExpand, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(screen(&mut app).contains("Size: 20.0 MB (8.0 KB shown;"));
+        // The whole value is still there to edit and save.
+        assert_eq!(
+            app.model.full_value(&app.visible[2].path).map(|v| v.len()),
+            Some(SIZE + 2)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn post_save_hook_findings_show_badge_and_jump() {
+        use crate::settings::{Setting, SettingSource};
+        let path = std::env::temp_dir().join(format!("yed_ui_hook_{}.yaml", std::process::id()));
+        std::fs::write(&path, "name: api\nserver:\n  port: 80\n").unwrap();
+        let mut app = App::new(&path, CliOverrides::default()).unwrap();
+        let script = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/hooks/lint.sh");
+        app.settings.post_save_hook = Setting {
+            value: Some(format!("sh {} {{file}}", script.display())),
+            source: SettingSource::Cli,
+        };
+        app.apply_action(InputAction::Save, 10).unwrap();
+        assert!(app.hook_running());
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while app.hook_result.is_none() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            app.poll_tasks();
+        }
+        std::fs::remove_file(&path).ok();
+        assert_eq!(app.hook_finding_count(), 2);
+
+        let screen = |app: &mut App| {
+            let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+            terminal
+                .draw(|frame| {
+                    draw(frame, app);
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            (0..20)
+                .map(|y| {
+                    (0..100)
+                        .map(|x| buffer.get(x, y).symbol())
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert!(screen(&mut app)[0].starts_with(" 2 finding(s) "));
+
+        app.toast = None;
+        app.apply_action(InputAction::ShowFindings, 10).unwrap();
+        assert_eq!(app.mode, Mode::HookFindings);
+        let rows = screen(&mut app);
+        assert!(rows
+            .iter()
+            .any(|row| row.contains("4:9  [error] port must be above 1024 (policy)")));
+        app.apply_action(InputAction::MoveDown, 10).unwrap();
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.current_row().unwrap().path.dot_path(), "server.port");
+    }
+
+    #[test]
+    fn lossy_files_show_a_badge_and_gate_the_first_edit() {
+        let finish = |app: &mut App| {
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+            while app.integrity_task.is_some() && std::time::Instant::now() < deadline {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                app.poll_tasks();
+            }
+        };
+        let status = |app: &mut App| {
+            let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+            terminal
+                .draw(|frame| {
+                    draw(frame, app);
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            (0..100)
+                .map(|x| buffer.get(x, 0).symbol())
+                .collect::<String>()
+        };
+
+        // The explicit `!!float` tag is dropped on save, so `ratio` would become an integer.
+        let mut app = app_with(
+            "lossy",
+            "base: &b 1
+use: *b
+ratio: !!float 1
+",
+        );
+        finish(&mut app);
+        assert!(
+            status(&mut app).starts_with(" lossy save: ratio "),
+            "{}",
+            status(&mut app)
+        );
+        app.toast = None;
+        app.selection = 1;
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        assert_eq!(app.mode, Mode::ConfirmLossy);
+        assert_eq!(
+            app.lossy_prompt().as_deref(),
+            Some("Saving this file will alter 1 node(s) unrelated to your edits — continue? (y/n)")
+        );
+        app.apply_action(InputAction::ConfirmYes, 10).unwrap();
+        assert_eq!(app.mode, Mode::ConfirmAlias);
+        app.apply_action(InputAction::Cancel, 10).unwrap();
+        // Asked only once.
+        app.selection = 3;
+        app.apply_action(InputAction::ToggleBool, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+
+        // Anchors and aliases are written back, so this file saves faithfully.
+        let mut clean = app_with(
+            "not_lossy",
+            "base: &b 1
+use: *b
+name: api
+",
+        );
+        finish(&mut clean);
+        assert!(clean.lossy.is_none());
+        assert!(!status(&mut clean).contains("lossy"));
+        clean.selection = 3;
+        clean.apply_action(InputAction::EditValue, 10).unwrap();
+        assert_eq!(clean.mode, Mode::EditValue);
+    }
+
+    #[test]
+    fn raw_hits_cover_whole_line() {
+        let mut app = app_with("raw", "키: [\n");
+        assert!(app.raw_content.is_some());
+        let keys = rendered_keys(&mut app);
+        assert_eq!(keys, vec!["   1 키: ["]);
+    }
+
+    #[test]
+    fn cursor_glyph_lands_on_grapheme_boundary() {
+        let mut app = app_with("cursor", "a: 1\n");
+        app.mode = Mode::EditValue;
+        app.input.set("한e\u{301}".to_string());
+        app.input.move_left();
+        assert_eq!(app.input.cursor, "한".len());
+        app.input.move_left();
+        assert_eq!(app.input.cursor, 0);
+        // Inside `한` and inside `é`: the mark goes before the whole character.
+        app.input.cursor = 1;
+        assert_eq!(glyph_after_cursor(&mut app), "한");
+        app.input.cursor = "한e".len();
+        assert_eq!(glyph_after_cursor(&mut app), "e\u{301}");
+    }
+
+    #[test]
+    fn stats_overlay_summarizes_the_document() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = app_with(
+            "stats",
+            "name: api\nports:\n  - 80\n  - 443\ntls:\n  enabled: true\n",
+        );
+        app.handle_key(KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::StatsOverlay);
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|frame| {
+                draw(frame, &mut app);
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let text = (0..20)
+            .map(|y| {
+                (0..80)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        for line in [
+            "Nodes      6",
+            "Leaves     4",
+            "Max depth  2",
+            "map        1",
+            "seq        1",
+            "number     2",
+        ] {
+            assert!(text.contains(line), "{line}\n{text}");
+        }
+        app.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn help_overlay_lists_the_bindings_in_effect() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = app_with("help", "name: api\n");
+        app.vim.set_keymap(crate::keymap::parse("j = \"quit\"\n").0);
+        app.handle_key(KeyEvent::new(KeyCode::F(1), KeyModifiers::NONE), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::HelpOverlay);
+        let screen = |app: &mut App| {
+            let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+            terminal
+                .draw(|frame| {
+                    draw(frame, app);
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..20)
+                .map(|y| {
+                    (0..80)
+                        .map(|x| buffer.get(x, y).symbol())
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let has_row = |text: &str, keys: &str, about: &str| {
+            text.lines().any(|line| {
+                let line = line.trim_matches(['│', ' ']);
+                line.strip_prefix(keys)
+                    .is_some_and(|rest| rest.starts_with("  ") && rest.trim() == about)
+            })
+        };
+        let text = screen(&mut app);
+        assert!(text.contains("Navigation"), "{text}");
+        assert!(has_row(&text, "down", "Move down"), "{text}");
+        app.handle_key(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL), 10)
+            .unwrap();
+        let text = screen(&mut app);
+        assert!(!text.contains("Navigation"), "{text}");
+        for _ in 0..200 {
+            app.handle_key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE), 10)
+                .unwrap();
+        }
+        let text = screen(&mut app);
+        assert!(has_row(&text, "q j", "Quit"), "{text}");
+        app.handle_key(KeyEvent::new(KeyCode::F(1), KeyModifiers::NONE), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn index_column_prefixes_sequence_items() {
+        let mut app = app_with(
+            "indices",
+            "items:\n  - name: web\n    port: 80\n  - plain\n",
+        );
+        app.expanded.insert("items".to_string());
+        app.rebuild_visible();
+        // The row from four columns before the key: where the `[n] ` prefix goes.
+        let row_text = |app: &mut App, dot: &str| {
+            let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+            let mut hits = Vec::new();
+            terminal.draw(|frame| hits = draw(frame, app)).unwrap();
+            let index = app
+                .visible
+                .iter()
+                .position(|row| row.path.dot_path() == dot)
+                .unwrap();
+            let hit = hits
+                .iter()
+                .find(|hit| hit.row_index == index)
+                .unwrap()
+                .clone();
+            let buffer = terminal.backend().buffer();
+            (hit.key_x_start - 4..hit.key_x_start + 5)
+                .map(|x| buffer.get(x, hit.y).symbol())
+                .collect::<String>()
+        };
+        assert_eq!(row_text(&mut app, "items.0"), "  ▸ name ");
+        app.apply_action(InputAction::ToggleIndices, 10).unwrap();
+        assert!(app.show_indices);
+        assert_eq!(row_text(&mut app, "items.0"), "[0] name ");
+        assert!(row_text(&mut app, "items.1").starts_with("[1] "));
+        // Only list items get one; paths and labels are unchanged.
+        assert!(row_text(&mut app, "items").ends_with("items"));
+        let item = app
+            .visible
+            .iter()
+            .find(|row| row.path.dot_path() == "items.0")
+            .unwrap();
+        assert_eq!(item.display_key, "name");
+    }
+
+    #[test]
+    fn tree_rows_scroll_sideways_by_columns() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let long_key = format!("{}x한국어{}", "k".repeat(20), "b".repeat(60));
+        let yaml = format!("{long_key}: 1\nname: api\n");
+        let mut app = app_with("h_scroll", &yaml);
+        let key = |app: &mut App, ch: char| {
+            app.handle_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE), 10)
+                .unwrap();
+        };
+        let render = |app: &mut App| {
+            let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+            let mut hits = Vec::new();
+            terminal.draw(|frame| hits = draw(frame, app)).unwrap();
+            (terminal.backend().buffer().clone(), hits)
+        };
+        let long = app
+            .visible
+            .iter()
+            .position(|row| row.path.dot_path() == long_key)
+            .unwrap();
+        let (_, hits) = render(&mut app);
+        let hit = hits
+            .iter()
+            .find(|hit| hit.row_index == long)
+            .unwrap()
+            .clone();
+        let line = format!("    {long_key} = 1");
+        let wide = line.find('국').unwrap();
+        // Scroll until `국` is cut in half at the left edge.
+        let cut = width::display_width(&line[..wide]) + 1;
+        for _ in 0..cut.div_ceil(8) {
+            key(&mut app, 'z');
+            key(&mut app, 'l');
+        }
+        assert_eq!(app.h_scroll, cut.div_ceil(8) * 8);
+        app.h_scroll = cut;
+        let (buffer, hits) = render(&mut app);
+        assert_eq!(app.h_scroll, cut);
+        let x0 = hit.key_x_start - 4;
+        assert_eq!(buffer.get(x0, hit.y).symbol(), " ");
+        assert_eq!(buffer.get(x0 + 1, hit.y).symbol(), "어");
+        // The hit target follows the part of the key still in view.
+        let scrolled = hits.iter().find(|h| h.row_index == long).unwrap();
+        assert_eq!(scrolled.key_x_start, x0);
+        assert_eq!(scrolled.key_x_end, hit.key_x_end - cut as u16);
+
+        // No further than the widest row needs.
+        app.h_scroll = 1000;
+        render(&mut app);
+        assert!(app.h_scroll < width::display_width(&line));
+        key(&mut app, 'z');
+        key(&mut app, 'h');
+        key(&mut app, '0');
+        assert_eq!(app.h_scroll, 0);
+        let (buffer, _) = render(&mut app);
+        assert_eq!(buffer.get(hit.key_x_start, hit.y).symbol(), "k");
+    }
+
+    #[test]
+    fn typing_and_moving_across_multibyte_characters() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = app_with("cursor_cafe", "a: 1\n");
+        app.mode = Mode::EditValue;
+        app.input.set(String::new());
+        let key = |app: &mut App, code: KeyCode| {
+            app.handle_key(KeyEvent::new(code, KeyModifiers::NONE), 10)
+                .unwrap();
+        };
+        for ch in "café".chars() {
+            key(&mut app, KeyCode::Char(ch));
+        }
+        for (code, cursor, after) in [
+            (KeyCode::Left, "caf".len(), "é"),
+            (KeyCode::Right, "café".len(), " "),
+            (KeyCode::Left, "caf".len(), "é"),
+            (KeyCode::Left, "ca".len(), "f"),
+            (KeyCode::Right, "caf".len(), "é"),
+        ] {
+            key(&mut app, code);
+            assert_eq!(app.input.cursor, cursor);
+            assert_eq!(glyph_after_cursor(&mut app), after);
+        }
+        key(&mut app, KeyCode::Delete);
+        assert_eq!(app.input.text, "caf");
+        key(&mut app, KeyCode::Char('é'));
+        key(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.input.text, "cafés");
+
+        // A cursor left inside a character edits from the character's start.
+        app.input.set("é".to_string());
+        app.input.cursor = 1;
+        app.input.insert_char('x');
+        assert_eq!(app.input.text, "xé");
+        app.input.cursor = 2;
+        app.input.backspace();
+        assert_eq!(app.input.text, "é");
+        app.input.cursor = 1;
+        app.input.delete();
+        assert_eq!(app.input.text, "");
+    }
+
+    #[test]
+    fn disk_changes_merge_into_unsaved_edits() {
+        let path = std::env::temp_dir().join(format!("yed_ui_merge_{}.yaml", std::process::id()));
+        std::fs::write(&path, "name: api\nport: 80\nmode: dev\n").unwrap();
+        let mut app = App::new(&path, CliOverrides::default()).unwrap();
+        let edit = |app: &mut App, key: &str, value: &str| {
+            assert!(app.reveal_path(&NodePath(vec![PathSegment::Key(key.to_string())])));
+            app.apply_action(InputAction::EditValue, 10).unwrap();
+            app.input.set(value.to_string());
+            app.apply_action(InputAction::InputCommit, 10).unwrap();
+        };
+        let changed_on_disk = |app: &mut App, text: &str| {
+            std::fs::write(&path, text).unwrap();
+            app.last_modified = Some(std::time::UNIX_EPOCH);
+            app.last_file_check = None;
+            app.check_and_reload_if_changed().unwrap();
+        };
+        let value = |app: &App, key: &str| {
+            app.model
+                .full_value(&NodePath(vec![PathSegment::Key(key.to_string())]))
+        };
+
+        edit(&mut app, "port", "8080");
+        changed_on_disk(&mut app, "name: web\nport: 81\nmode: dev\n");
+        assert_eq!(app.mode, Mode::ConfirmDiskChange);
+        app.apply_action(InputAction::DiskChange(DiskChoice::Merge), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::MergeConflicts);
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal
+            .draw(|frame| {
+                draw(frame, &mut app);
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer().clone();
+        let screen: String = (0..20)
+            .map(|y| {
+                (0..100)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect();
+        assert!(screen.contains("d disk      81"), "{screen}");
+        assert!(screen.contains("b buffer    8080"), "{screen}");
+
+        // Enter refuses to finish while the conflict is open; a custom value resolves it.
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.mode, Mode::MergeConflicts);
+        app.apply_action(InputAction::EditValue, 10).unwrap();
+        assert_eq!(app.mode, Mode::MergeValue);
+        app.input.set("9090".to_string());
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.mode, Mode::MergeConflicts);
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.dirty);
+        assert_eq!(value(&app, "name").as_deref(), Some("\"web\""));
+        assert_eq!(value(&app, "port").as_deref(), Some("9090"));
+
+        // The disk text is the new baseline: a later change to another key merges silently,
+        // and undo goes back to the buffer from before the merge.
+        changed_on_disk(&mut app, "name: web\nport: 81\nmode: prod\n");
+        app.apply_action(InputAction::DiskChange(DiskChoice::Merge), 10)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(value(&app, "mode").as_deref(), Some("\"prod\""));
+        assert_eq!(value(&app, "port").as_deref(), Some("9090"));
+        app.apply_action(InputAction::Undo, 10).unwrap();
+        assert_eq!(value(&app, "mode").as_deref(), Some("\"dev\""));
+
+        // Picking a side with d takes the disk value.
+        changed_on_disk(&mut app, "name: web\nport: 82\nmode: prod\n");
+        app.apply_action(InputAction::DiskChange(DiskChoice::Merge), 10)
+            .unwrap();
+        app.apply_action(InputAction::MergeTake(crate::merge::Side::Disk), 10)
+            .unwrap();
+        app.apply_action(InputAction::InputCommit, 10).unwrap();
+        assert_eq!(value(&app, "port").as_deref(), Some("82"));
+        drop(app);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn picker_shows_size_and_age_and_sorts_by_mtime() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        use std::time::{Duration, SystemTime};
+        let dir = std::env::temp_dir().join(format!("yed_ui_picker_meta_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        let now = SystemTime::now();
+        for (name, size, age) in [("a.yaml", 2355, 180), ("b.yaml", 10, 7200)] {
+            std::fs::write(dir.join(name), "x".repeat(size)).unwrap();
+            let file = std::fs::File::options()
+                .write(true)
+                .open(dir.join(name))
+                .unwrap();
+            file.set_modified(now - Duration::from_secs(age)).unwrap();
+        }
+        let mut app = App::new_for_picker(CliOverrides::default()).unwrap();
+        app.file_picker.as_mut().unwrap().current_dir = dir.clone();
+        app.picker_refresh().unwrap();
+        let rows = |app: &mut App| {
+            let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+            terminal
+                .draw(|frame| {
+                    draw(frame, app);
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            // The picker pane's rows, between its left and right borders.
+            (2..6)
+                .map(|y| {
+                    (1..80)
+                        .map(|x| buffer.get(x, y).symbol().to_string())
+                        .collect::<String>()
+                })
+                .map(|row| {
+                    row.split('│')
+                        .next()
+                        .unwrap()
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            rows(&mut app),
+            ["..", "sub/", "a.yaml 2.3K 3m ago", "b.yaml 10B 2h ago"]
+        );
+
+        std::fs::File::options()
+            .write(true)
+            .open(dir.join("b.yaml"))
+            .unwrap()
+            .set_modified(now)
+            .unwrap();
+        app.selection = 3;
+        app.handle_key(KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE), 10)
+            .unwrap();
+        assert_eq!(
+            app.file_picker.as_ref().unwrap().sort,
+            crate::app::PickerSort::Modified
+        );
+        assert_eq!(
+            rows(&mut app),
+            ["..", "sub/", "b.yaml 10B just now", "a.yaml 2.3K 3m ago"]
+        );
+        assert_eq!(app.selection, 2);
+        drop(app);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1 +1,137 @@
-// Helper widgets placeholder for future extensions.
+// Helper widgets shared by the editor views.
+
+use crate::width;
+
+/// Multi-line text buffer with a cursor, used by the popup editor for long strings.
+#[derive(Clone, Debug)]
+pub struct TextArea {
+    pub lines: Vec<String>,
+    pub row: usize,
+    /// Byte offset of the cursor within `lines[row]`, always on a grapheme boundary.
+    pub col: usize,
+}
+
+impl TextArea {
+    pub fn new(text: &str) -> Self {
+        let lines: Vec<String> = text.split('\n').map(String::from).collect();
+        let row = lines.len() - 1;
+        let col = lines[row].len();
+        Self { lines, row, col }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn insert_char(&mut self, ch: char) {
+        self.lines[self.row].insert(self.col, ch);
+        self.col += ch.len_utf8();
+    }
+
+    pub fn newline(&mut self) {
+        let rest = self.lines[self.row].split_off(self.col);
+        self.row += 1;
+        self.lines.insert(self.row, rest);
+        self.col = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            let line = &mut self.lines[self.row];
+            let prev = width::prev_grapheme_boundary(line, self.col);
+            line.replace_range(prev..self.col, "");
+            self.col = prev;
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.lines[self.row].len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        let len = self.lines[self.row].len();
+        if self.col < len {
+            let line = &mut self.lines[self.row];
+            let next = width::next_grapheme_boundary(line, self.col);
+            line.replace_range(self.col..next, "");
+        } else if self.row + 1 < self.lines.len() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        if self.col > 0 {
+            self.col = width::prev_grapheme_boundary(&self.lines[self.row], self.col);
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.lines[self.row].len();
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if self.col < self.lines[self.row].len() {
+            self.col = width::next_grapheme_boundary(&self.lines[self.row], self.col);
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    pub fn move_up(&mut self) {
+        if self.row > 0 {
+            self.move_to_row(self.row - 1);
+        }
+    }
+
+    pub fn move_down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.move_to_row(self.row + 1);
+        }
+    }
+
+    pub fn move_home(&mut self) {
+        self.col = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.col = self.lines[self.row].len();
+    }
+
+    /// Change row keeping the cursor in the same screen column where possible.
+    fn move_to_row(&mut self, row: usize) {
+        let column = width::column_span(&self.lines[self.row], 0, self.col).1;
+        self.row = row;
+        self.col = width::byte_at_column(&self.lines[row], column);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn newline_and_backspace_join_lines() {
+        let mut area = TextArea::new("ab");
+        area.move_left();
+        area.newline();
+        assert_eq!(area.lines, vec!["a", "b"]);
+        area.backspace();
+        assert_eq!(area.text(), "ab");
+        assert_eq!((area.row, area.col), (0, 1));
+    }
+
+    #[test]
+    fn vertical_moves_keep_screen_column() {
+        let mut area = TextArea::new("한글\nabcd");
+        area.move_end();
+        area.move_left();
+        area.move_up();
+        // Column 3 falls inside "글"; snap to its start.
+        assert_eq!((area.row, area.col), (0, "한".len()));
+        area.move_down();
+        assert_eq!((area.row, area.col), (1, 2));
+    }
+}
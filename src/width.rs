@@ -0,0 +1,131 @@
+// Width-aware conversions between string byte offsets and terminal columns.
+//
+// Everything that maps text to screen cells (hit targets, cursor glyph, raw view
+// prefixes) goes through these helpers so CJK, emoji, and combining marks line up
+// with what ratatui renders.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Display width of a string in terminal columns.
+pub fn display_width(s: &str) -> usize {
+    s.width()
+}
+
+/// Column span `[start, end)` covered by the byte range `start..end` of `s`.
+/// Byte offsets that fall inside a grapheme are snapped to its start.
+pub fn column_span(s: &str, start: usize, end: usize) -> (usize, usize) {
+    let start = floor_grapheme_boundary(s, start);
+    let end = floor_grapheme_boundary(s, end.max(start));
+    let col_start = s[..start].width();
+    let col_end = col_start + s[start..end].width();
+    (col_start, col_end)
+}
+
+/// Byte offset of the grapheme boundary nearest to `column` (a column inside a
+/// wide grapheme resolves to that grapheme's start). Past the end yields `s.len()`.
+pub fn byte_at_column(s: &str, column: usize) -> usize {
+    let mut col = 0;
+    for (idx, g) in s.grapheme_indices(true) {
+        let w = g.width();
+        if column < col + w.max(1) {
+            return idx;
+        }
+        col += w;
+    }
+    s.len()
+}
+
+/// Byte offset where `s` goes on once its first `columns` columns are scrolled out of view,
+/// and how many columns of a wide grapheme cut in half are still visible.
+pub fn skip_columns(s: &str, columns: usize) -> (usize, usize) {
+    let mut col = 0;
+    for (idx, g) in s.grapheme_indices(true) {
+        if col >= columns {
+            return (idx, 0);
+        }
+        col += g.width();
+        if col > columns {
+            return (idx + g.len(), col - columns);
+        }
+    }
+    (s.len(), 0)
+}
+
+/// Largest grapheme boundary at or before `byte`.
+pub fn floor_grapheme_boundary(s: &str, byte: usize) -> usize {
+    if byte >= s.len() {
+        return s.len();
+    }
+    let mut last = 0;
+    for (idx, _) in s.grapheme_indices(true) {
+        if idx > byte {
+            break;
+        }
+        last = idx;
+    }
+    last
+}
+
+/// Grapheme boundary immediately before `byte` (0 if already at the start).
+pub fn prev_grapheme_boundary(s: &str, byte: usize) -> usize {
+    let byte = floor_grapheme_boundary(s, byte);
+    s[..byte]
+        .grapheme_indices(true)
+        .next_back()
+        .map(|(idx, _)| idx)
+        .unwrap_or(0)
+}
+
+/// Grapheme boundary immediately after `byte` (`s.len()` if at the end).
+pub fn next_grapheme_boundary(s: &str, byte: usize) -> usize {
+    let byte = floor_grapheme_boundary(s, byte);
+    s[byte..]
+        .graphemes(true)
+        .next()
+        .map(|g| byte + g.len())
+        .unwrap_or(s.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn column_span_counts_wide_chars() {
+        let s = "  ▸ 서버 = 1";
+        let key_start = s.find('서').unwrap();
+        let key_end = key_start + "서버".len();
+        assert_eq!(column_span(s, key_start, key_end), (4, 8));
+    }
+
+    #[test]
+    fn byte_at_column_snaps_to_grapheme_start() {
+        let s = "a한b";
+        assert_eq!(byte_at_column(s, 0), 0);
+        assert_eq!(byte_at_column(s, 1), 1);
+        assert_eq!(byte_at_column(s, 2), 1);
+        assert_eq!(byte_at_column(s, 3), 4);
+        assert_eq!(byte_at_column(s, 10), s.len());
+    }
+
+    #[test]
+    fn skip_columns_leaves_blanks_for_a_cut_wide_char() {
+        let s = "a한b";
+        assert_eq!(skip_columns(s, 0), (0, 0));
+        assert_eq!(skip_columns(s, 1), (1, 0));
+        assert_eq!(skip_columns(s, 2), (4, 1));
+        assert_eq!(skip_columns(s, 3), (4, 0));
+        assert_eq!(skip_columns(s, 9), (s.len(), 0));
+    }
+
+    #[test]
+    fn combining_marks_stay_with_base() {
+        let s = "e\u{301}x";
+        assert_eq!(next_grapheme_boundary(s, 0), 3);
+        assert_eq!(prev_grapheme_boundary(s, 3), 0);
+        assert_eq!(floor_grapheme_boundary(s, 2), 0);
+        assert_eq!(display_width(s), 2);
+    }
+}
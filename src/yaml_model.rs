@@ -3,7 +3,19 @@ use std::fmt;
 use std::path::Path;
 
 use anyhow::{anyhow, Result};
-use yaml_rust2::{Yaml, YamlEmitter, YamlLoader};
+use yaml_rust2::yaml::Hash;
+use yaml_rust2::{Yaml, YamlLoader};
+
+use crate::atomic_write;
+use crate::backup::{self, BackupOptions};
+use crate::emitter::{self, EmitOptions};
+use crate::json_patch;
+use crate::search::{self, Query, SearchScope};
+use crate::settings::{SaveMode, Settings};
+use crate::source_map::{DuplicateKey, SourceMap, MERGE_KEY};
+use crate::splice;
+use crate::timestamp;
+use crate::width;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum PathSegment {
@@ -54,6 +66,8 @@ pub enum NodeType {
     Number,
     Bool,
     Null,
+    /// A string that reads as a YAML timestamp; stored and saved as the string.
+    Timestamp,
     Unknown,
 }
 
@@ -66,20 +80,59 @@ impl fmt::Display for NodeType {
             NodeType::Number => "number",
             NodeType::Bool => "bool",
             NodeType::Null => "null",
+            NodeType::Timestamp => "timestamp",
             NodeType::Unknown => "unknown",
         };
         write!(f, "{label}")
     }
 }
 
+/// Where a node's value comes from with respect to YAML merge keys (`<<`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeOrigin {
+    Own,
+    /// The `<<` entry itself; holds the merge source label (e.g. `*defaults`).
+    MergeKey(String),
+    /// A key inherited through `<<`; holds the merge source label.
+    Inherited(String),
+    /// An alias (`*name`) standing for an anchored value; holds the label, e.g. `*defaults`.
+    Alias(String),
+}
+
+/// How an edit to a value shared through an anchor applies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AliasEdit {
+    /// Change the anchored value, and with it every alias.
+    Shared,
+    /// Change only the selected spot; the aliases involved become independent copies.
+    Break,
+}
+
+/// A node that is, or lies inside, an anchored value that aliases refer to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SharedNode {
+    pub anchor: String,
+    anchor_path: NodePath,
+    /// Alias the node was reached through; `None` when it lies under the anchor itself.
+    alias_path: Option<NodePath>,
+    /// Location of the node below the anchor (or alias).
+    suffix: Vec<PathSegment>,
+    /// Number of aliases referring to the anchor.
+    pub aliases: usize,
+}
+
 #[derive(Clone, Debug)]
 pub struct VisibleRow {
     pub path: NodePath,
     pub depth: usize,
     pub display_key: String,
     pub display_value_preview: String,
+    /// Bytes in the scalar's text (0 for collections), so huge values are known without
+    /// formatting them.
+    pub value_size: usize,
     pub node_type: NodeType,
     pub is_container: bool,
+    pub origin: NodeOrigin,
 }
 
 #[derive(Clone, Debug)]
@@ -88,12 +141,109 @@ pub struct TreeNode {
     pub key: String,
     pub node_type: NodeType,
     pub value_preview: String,
+    pub value_size: usize,
     pub children: Vec<TreeNode>,
+    pub origin: NodeOrigin,
+}
+
+impl TreeNode {
+    pub fn is_container(&self) -> bool {
+        matches!(self.node_type, NodeType::Map | NodeType::Seq)
+            && !matches!(self.origin, NodeOrigin::MergeKey(_))
+    }
+}
+
+/// Document state captured before an edit; restoring it undoes the edit.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    doc: Yaml,
+    source: SourceMap,
+}
+
+/// Why a file did not load, with the position `yaml_rust2` reports.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    /// 1-based line.
+    pub line: usize,
+    /// 1-based column.
+    pub column: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Newline conventions of a file as loaded, reapplied to what is saved over it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineEndings {
+    pub crlf: bool,
+    pub final_newline: bool,
+}
+
+impl Default for LineEndings {
+    fn default() -> Self {
+        Self {
+            crlf: false,
+            final_newline: true,
+        }
+    }
+}
+
+impl LineEndings {
+    /// CRLF if the first line break is `\r\n`.
+    pub fn detect(text: &str) -> Self {
+        Self {
+            crlf: text
+                .find('\n')
+                .is_some_and(|idx| text[..idx].ends_with('\r')),
+            final_newline: text.ends_with('\n'),
+        }
+    }
+
+    /// Convert `text`'s line breaks and add a final one if the file had it. A missing final
+    /// newline is not removed: the emitter only ends with one when a kept block scalar needs it.
+    pub fn apply(&self, text: String) -> String {
+        let mut out = if self.crlf {
+            text.replace("\r\n", "\n").replace('\n', "\r\n")
+        } else {
+            text
+        };
+        if self.final_newline && !out.ends_with('\n') {
+            out.push_str(if self.crlf { "\r\n" } else { "\n" });
+        }
+        out
+    }
 }
 
 pub struct YamlModel {
     doc: Yaml,
     path: String,
+    /// Anchors and aliases seen at load time, so save can write them back.
+    source: SourceMap,
+    emit_options: EmitOptions,
+    /// Keys whose value labels mappings inside sequences.
+    identity_keys: Vec<String>,
+    /// Text on disk as last loaded or saved, with the document it parses to; minimal-diff
+    /// saves splice edits into it.
+    loaded: Option<(String, Yaml)>,
+    save_mode: SaveMode,
+    line_endings: LineEndings,
+    /// Where to copy the previous contents before a save; `None` when backups are off.
+    backup: Option<BackupOptions>,
+    /// Whether this session already wrote the file, so a once-only backup is not taken again.
+    saved_in_session: bool,
+}
+
+/// What a save did besides writing the file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SaveReport {
+    /// Why the edits could not be spliced into the loaded text, in minimal save mode.
+    pub fallback: Option<String>,
+    /// Why the previous contents could not be backed up; the file was saved regardless.
+    pub backup_error: Option<String>,
 }
 
 impl YamlModel {
@@ -106,27 +256,48 @@ impl YamlModel {
     }
 
     /// Load YAML; on parse error returns empty doc, error message, and raw content so the file can be edited.
-    pub fn load_with_error(path: &Path) -> Result<(Self, Option<String>, Option<String>)> {
+    pub fn load_with_error(path: &Path) -> Result<(Self, Option<ParseError>, Option<String>)> {
         let input = std::fs::read_to_string(path)?;
         let path_str = path.display().to_string();
+        let line_endings = LineEndings::detect(&input);
         match YamlLoader::load_from_str(&input) {
             Ok(docs) => {
                 let doc = docs.into_iter().next().unwrap_or(Yaml::Null);
                 Ok((
                     Self {
+                        loaded: Some((input.clone(), doc.clone())),
                         doc,
                         path: path_str,
+                        source: SourceMap::scan(&input),
+                        emit_options: EmitOptions::default(),
+                        identity_keys: Vec::new(),
+                        save_mode: SaveMode::Full,
+                        line_endings,
+                        backup: None,
+                        saved_in_session: false,
                     },
                     None,
                     None,
                 ))
             }
             Err(e) => {
-                let err_msg = e.to_string();
+                let err_msg = ParseError {
+                    message: e.to_string(),
+                    line: e.marker().line(),
+                    column: e.marker().col() + 1,
+                };
                 Ok((
                     Self {
                         doc: Yaml::Null,
                         path: path_str.clone(),
+                        source: SourceMap::default(),
+                        emit_options: EmitOptions::default(),
+                        identity_keys: Vec::new(),
+                        loaded: None,
+                        save_mode: SaveMode::Full,
+                        line_endings,
+                        backup: None,
+                        saved_in_session: false,
                     },
                     Some(err_msg),
                     Some(input),
@@ -140,20 +311,107 @@ impl YamlModel {
         Self {
             doc: Yaml::Null,
             path: String::new(),
+            source: SourceMap::default(),
+            emit_options: EmitOptions::default(),
+            identity_keys: Vec::new(),
+            loaded: None,
+            save_mode: SaveMode::Full,
+            line_endings: LineEndings::default(),
+            backup: None,
+            saved_in_session: false,
         }
     }
 
+    /// Apply the layout and labelling settings that affect this file.
+    pub fn configure(&mut self, settings: &Settings) {
+        self.emit_options = EmitOptions {
+            indent: settings.indent_width.value,
+            document_start: settings.document_start.value,
+            flow_max_items: settings.flow_max_items.value,
+        };
+        self.identity_keys = settings.identity_keys.value.clone();
+        self.save_mode = settings.save_mode.value;
+        self.backup = BackupOptions::from_settings(settings);
+    }
+
+    /// Text of the file as last loaded or saved.
+    pub fn loaded_text(&self) -> Option<&str> {
+        self.loaded.as_ref().map(|(text, _)| text.as_str())
+    }
+
+    pub fn emit_options(&self) -> &EmitOptions {
+        &self.emit_options
+    }
+
+    /// Whether the file was saved since it was opened; reloading it keeps this.
+    pub fn saved_in_session(&self) -> bool {
+        self.saved_in_session
+    }
+
+    /// Carry the saved state over to a model reloaded from the same file, or reset it when
+    /// the model starts writing a different one.
+    pub fn set_saved_in_session(&mut self, saved: bool) {
+        self.saved_in_session = saved;
+    }
+
     /// Path of the currently loaded file (for "open another file").
     pub fn file_path(&self) -> &str {
         &self.path
     }
 
-    pub fn save(&self) -> Result<()> {
-        let mut out = String::new();
-        let mut emitter = YamlEmitter::new(&mut out);
-        emitter.dump(&self.doc)?;
-        std::fs::write(&self.path, out)?;
-        Ok(())
+    /// Write the document to its file, first backing up the previous contents if enabled (with
+    /// `backup.once`, only on the first save since the file was opened).
+    pub fn save(&mut self) -> Result<SaveReport> {
+        let path = std::path::PathBuf::from(&self.path);
+        self.save_to(&path)
+    }
+
+    /// Like `save`, but write to `path`, which becomes the model's file.
+    pub fn save_to(&mut self, path: &Path) -> Result<SaveReport> {
+        let mut report = SaveReport::default();
+        let (out, fallback) = self.render()?;
+        report.fallback = fallback;
+        if let Some(options) = self
+            .backup
+            .as_ref()
+            .filter(|options| !(options.once && self.saved_in_session))
+        {
+            report.backup_error = backup::backup(path, options)
+                .err()
+                .map(|err| format!("{err:#}"));
+        }
+        atomic_write::write(path, out.as_bytes())?;
+        self.saved_in_session = true;
+        self.path = path.display().to_string();
+        self.loaded = Some((out, self.doc.clone()));
+        Ok(report)
+    }
+
+    /// The text saving would write, and why minimal splicing fell back to a full emit if it did.
+    pub fn render(&self) -> Result<(String, Option<String>)> {
+        let mut fallback = None;
+        let spliced = match (&self.loaded, self.save_mode) {
+            (Some((text, before)), SaveMode::Minimal) => {
+                match splice::splice(text, before, &self.doc, &self.source, &self.emit_options) {
+                    Ok(out) => Some(out),
+                    Err(err) => {
+                        fallback = Some(err.to_string());
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        let out = match spliced {
+            Some(out) => out,
+            None => emitter::emit(&self.doc, &self.source, &self.emit_options)?,
+        };
+        Ok((self.line_endings.apply(out), fallback))
+    }
+
+    /// Keys repeated within one mapping in the loaded file (only the last value survived).
+    pub fn duplicates(&self) -> &[DuplicateKey] {
+        &self.source.duplicates
     }
 
     pub fn root(&self) -> &Yaml {
@@ -166,12 +424,161 @@ impl YamlModel {
 
     pub fn build_tree(&self) -> TreeNode {
         let root_path = NodePath(Vec::new());
-        build_tree_node(&root_path, "".to_string(), self.root())
+        build_tree_node(
+            &root_path,
+            "".to_string(),
+            self.root(),
+            &self.source,
+            &self.identity_keys,
+            None,
+        )
     }
 
     pub fn edit_value(&mut self, path: &NodePath, value: ScalarValue) -> Result<()> {
         let node = get_node_mut(self.root_mut(), path)?;
         *node = scalar_to_yaml(value);
+        self.source.clear_aliases_under(path);
+        Ok(())
+    }
+
+    /// Scalars whose text contains `find`, in document order: strings, plus numbers and
+    /// booleans when `coerce` is set. Values shared through an anchor are left out; the second
+    /// number counts them.
+    pub fn replace_targets(&self, find: &str, coerce: bool) -> (Vec<NodePath>, usize) {
+        let mut found = Vec::new();
+        collect_scalars(&self.doc, &NodePath(Vec::new()), &mut found);
+        let mut skipped = 0;
+        let targets = found
+            .into_iter()
+            .filter(|(_, node)| {
+                replaceable_text(node, coerce).is_some_and(|text| text.contains(find))
+            })
+            .filter_map(|(path, _)| {
+                if self.shared_node(&path).is_some() {
+                    skipped += 1;
+                    None
+                } else {
+                    Some(path)
+                }
+            })
+            .collect();
+        (targets, skipped)
+    }
+
+    /// The scalar at `path` before and after [`Self::replace_in_scalar`], cut to 40 columns
+    /// each, to ask about one match.
+    pub fn replace_preview(
+        &self,
+        path: &NodePath,
+        find: &str,
+        with: &str,
+    ) -> Option<(String, String)> {
+        let text = replaceable_text(self.node_at(path).ok()?, true)?;
+        let replaced = text.replace(find, with);
+        Some((truncate_label(text), truncate_label(replaced)))
+    }
+
+    /// Replace every `find` in the scalar at `path` with `with`. Strings stay strings; a number
+    /// or boolean is read back from the new text, so `8080` → `9090` stays a number.
+    pub fn replace_in_scalar(&mut self, path: &NodePath, find: &str, with: &str) -> Result<()> {
+        let node = get_node(&self.doc, path)?;
+        let text = replaceable_text(node, true)
+            .ok_or_else(|| anyhow!("Not a string, number or boolean"))?;
+        let replaced = text.replace(find, with);
+        let value = match node {
+            Yaml::String(_) => ScalarValue::String(replaced),
+            _ => parse_scalar_input(&replaced)?,
+        };
+        self.edit_value(path, value)
+    }
+
+    /// Mapping keys containing `find`, in document order. Merge keys are left out, and so are
+    /// keys shared through an anchor; the second number counts those.
+    pub fn key_replace_targets(&self, find: &str) -> (Vec<NodePath>, usize) {
+        let mut found = Vec::new();
+        collect_keys(&self.doc, &NodePath(Vec::new()), &mut found);
+        let mut skipped = 0;
+        let targets = found
+            .into_iter()
+            .filter(|path| match path.0.last() {
+                Some(PathSegment::Key(key)) => key.contains(find) && key != MERGE_KEY,
+                _ => false,
+            })
+            .filter(|path| {
+                let shared = self.shared_node(path).is_some();
+                skipped += usize::from(shared);
+                !shared
+            })
+            .collect();
+        (targets, skipped)
+    }
+
+    /// The key at `path` before and after [`Self::replace_in_key`], cut to 40 columns each.
+    pub fn key_replace_preview(
+        &self,
+        path: &NodePath,
+        find: &str,
+        with: &str,
+    ) -> Option<(String, String)> {
+        let Some(PathSegment::Key(key)) = path.0.last() else {
+            return None;
+        };
+        Some((
+            truncate_label(key.clone()),
+            truncate_label(key.replace(find, with)),
+        ))
+    }
+
+    /// Replace every `find` in the key at `path` with `with`, keeping the key where it is in
+    /// its mapping. Returns the key's new path, or `None` (and changes nothing) if the mapping
+    /// already has the new key.
+    pub fn replace_in_key(
+        &mut self,
+        path: &NodePath,
+        find: &str,
+        with: &str,
+    ) -> Result<Option<NodePath>> {
+        let (parent, old) = split_parent_key(path)?;
+        let new = old.replace(find, with);
+        if new.is_empty() {
+            return Err(anyhow!("Key cannot be empty"));
+        }
+        let Yaml::Hash(map) = get_node_mut(self.root_mut(), &parent)? else {
+            return Err(anyhow!("Parent is not a mapping"));
+        };
+        if new != old
+            && map
+                .keys()
+                .any(|key| yaml_key_to_string(key).as_deref() == Some(new.as_str()))
+        {
+            return Ok(None);
+        }
+        let old_key = map_key(map, &old);
+        let new_key = match old_key {
+            Yaml::String(_) => Yaml::String(new.clone()),
+            _ => Yaml::from_str(&new),
+        };
+        *map = std::mem::take(map)
+            .into_iter()
+            .map(|(k, v)| {
+                if k == old_key {
+                    (new_key.clone(), v)
+                } else {
+                    (k, v)
+                }
+            })
+            .collect();
+        let renamed = parent.child_key(&new);
+        self.source.rename_prefix(path, &renamed);
+        Ok(Some(renamed))
+    }
+
+    /// Convert the scalar at `path` to `target`, keeping its textual value where possible.
+    pub fn cast_scalar(&mut self, path: &NodePath, target: NodeType) -> Result<()> {
+        let node = get_node_mut(self.root_mut(), path)?;
+        let cast = cast_yaml(node, &target)?;
+        *node = cast;
+        self.source.clear_aliases_under(path);
         Ok(())
     }
 
@@ -189,16 +596,15 @@ impl YamlModel {
                 if existing_keys.contains(new_key) {
                     return Err(anyhow!("Key already exists"));
                 }
-                let mut removed = None;
-                for (k, v) in map.iter() {
-                    if yaml_key_to_string(k).as_deref() == Some(&old_key) {
-                        removed = Some((k.clone(), v.clone()));
-                        break;
-                    }
-                }
-                if let Some((old_key_node, value)) = removed {
-                    map.remove(&old_key_node);
-                    map.insert(Yaml::String(new_key.to_string()), value);
+                let old_key_node = map_key(map, &old_key);
+                if let Some(value) = map.remove(&old_key_node) {
+                    // A non-string key stays a scalar of whatever type the new text reads as.
+                    let new_key_node = match old_key_node {
+                        Yaml::String(_) => Yaml::String(new_key.to_string()),
+                        _ => Yaml::from_str(new_key),
+                    };
+                    map.insert(new_key_node, value);
+                    self.source.rename_prefix(path, &parent.child_key(new_key));
                     Ok(())
                 } else {
                     Err(anyhow!("Key not found"))
@@ -208,6 +614,90 @@ impl YamlModel {
         }
     }
 
+    /// Rename every mapping key `old` in the document to `new`, keeping each key's position.
+    /// Mappings that already have `new` are left alone. Returns how many keys were renamed and
+    /// how many mappings were skipped; copies behind aliases follow their anchor uncounted.
+    pub fn rename_key_everywhere(&mut self, old: &str, new: &str) -> Result<(usize, usize)> {
+        if old.is_empty() || new.is_empty() {
+            return Err(anyhow!("Key cannot be empty"));
+        }
+        if old == new {
+            return Err(anyhow!("New key is the same as the old one"));
+        }
+        if old == MERGE_KEY || new == MERGE_KEY {
+            return Err(anyhow!("Merge keys cannot be renamed"));
+        }
+        let mut renamed = Vec::new();
+        let mut skipped = 0;
+        let root = NodePath(Vec::new());
+        rename_keys_in(
+            &mut self.doc,
+            &root,
+            old,
+            new,
+            &self.source,
+            false,
+            &mut renamed,
+            &mut skipped,
+        );
+        // Deepest first, so each prefix still names the node when it is moved.
+        for path in renamed.iter().rev() {
+            let mut parent = path.clone();
+            parent.0.pop();
+            self.source.rename_prefix(path, &parent.child_key(new));
+        }
+        Ok((renamed.len(), skipped))
+    }
+
+    /// Path named by `dot`, adding the keys that are missing along it as mappings and the last
+    /// one as null. Numeric segments index existing sequence items; items are never created.
+    pub fn create_dot_path(&mut self, dot: &str) -> Result<NodePath> {
+        if let Some(path) = resolve_dot_path(&self.doc, dot) {
+            return Ok(path);
+        }
+        if self.doc.is_null() {
+            self.doc = Yaml::Hash(Hash::new());
+        }
+        let segments: Vec<&str> = dot.split('.').collect();
+        let mut path = NodePath(Vec::new());
+        let mut node = &mut self.doc;
+        for (idx, segment) in segments.iter().enumerate() {
+            let last = idx + 1 == segments.len();
+            node = match node {
+                Yaml::Hash(map) => {
+                    if segment.is_empty() {
+                        return Err(anyhow!("Empty key in {dot}"));
+                    }
+                    let key = Yaml::String(segment.to_string());
+                    path = path.child_key(segment);
+                    map.entry(key).or_insert_with(|| {
+                        if last {
+                            Yaml::Null
+                        } else {
+                            Yaml::Hash(Hash::new())
+                        }
+                    })
+                }
+                Yaml::Array(seq) => {
+                    let index: usize = segment.parse().map_err(|_| {
+                        anyhow!(
+                            "{} is a sequence; expected an index, got {segment}",
+                            path.dot_path()
+                        )
+                    })?;
+                    let len = seq.len();
+                    let item = seq.get_mut(index).ok_or_else(|| {
+                        anyhow!("{} has {len} items; items are not created", path.dot_path())
+                    })?;
+                    path = path.child_index(index);
+                    item
+                }
+                _ => return Err(anyhow!("{} is a scalar, not a mapping", path.dot_path())),
+            };
+        }
+        Ok(path)
+    }
+
     pub fn add_mapping_child(
         &mut self,
         path: &NodePath,
@@ -217,17 +707,75 @@ impl YamlModel {
         let node = get_node_mut(self.root_mut(), path)?;
         match node {
             Yaml::Hash(map) => {
-                let new_key = Yaml::String(key.to_string());
-                if map.contains_key(&new_key) {
+                if map.contains_key(&map_key(map, key)) {
                     return Err(anyhow!("Key already exists"));
                 }
-                map.insert(new_key, scalar_to_yaml(value));
+                map.insert(Yaml::String(key.to_string()), scalar_to_yaml(value));
                 Ok(())
             }
             _ => Err(anyhow!("Node is not a mapping")),
         }
     }
 
+    /// Add `key` to the mapping at `parent` right after `anchor_key`.
+    pub fn add_mapping_child_after(
+        &mut self,
+        parent: &NodePath,
+        anchor_key: &str,
+        key: &str,
+        value: ScalarValue,
+    ) -> Result<()> {
+        let node = get_node_mut(self.root_mut(), parent)?;
+        let Yaml::Hash(map) = node else {
+            return Err(anyhow!("Node is not a mapping"));
+        };
+        if map.contains_key(&map_key(map, key)) {
+            return Err(anyhow!("Key already exists"));
+        }
+        let new_key = Yaml::String(key.to_string());
+        let anchor = map_key(map, anchor_key);
+        if !map.contains_key(&anchor) {
+            return Err(anyhow!("Key not found"));
+        }
+        let mut new_entry = Some((new_key, scalar_to_yaml(value)));
+        for (k, v) in std::mem::take(map) {
+            let after_anchor = k == anchor;
+            map.insert(k, v);
+            if after_anchor {
+                if let Some((k, v)) = new_entry.take() {
+                    map.insert(k, v);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert `value` into the sequence at `parent` right after item `index`. Returns the path
+    /// of the new item.
+    pub fn insert_sequence_value_after(
+        &mut self,
+        parent: &NodePath,
+        index: usize,
+        value: ScalarValue,
+    ) -> Result<NodePath> {
+        let node = get_node_mut(self.root_mut(), parent)?;
+        let Yaml::Array(seq) = node else {
+            return Err(anyhow!("Node is not a sequence"));
+        };
+        if index >= seq.len() {
+            return Err(anyhow!("Index out of bounds"));
+        }
+        let len = seq.len();
+        seq.insert(index + 1, scalar_to_yaml(value));
+        // Later items move up one place; the new item has no metadata.
+        let order: Vec<usize> = (0..=index)
+            .chain([usize::MAX])
+            .chain(index + 1..len)
+            .collect();
+        self.source.reorder_items(parent, &order);
+        Ok(parent.child_index(index + 1))
+    }
+
     pub fn add_sequence_value(&mut self, path: &NodePath, value: ScalarValue) -> Result<()> {
         let node = get_node_mut(self.root_mut(), path)?;
         match node {
@@ -265,160 +813,849 @@ impl YamlModel {
             .next()
             .unwrap_or(Yaml::Null);
         *node = empty;
+        self.source.clear_aliases_under(path);
         Ok(())
     }
 
-    pub fn delete_node(&mut self, path: &NodePath) -> Result<()> {
-        if path.0.is_empty() {
-            return Err(anyhow!("Cannot delete root"));
-        }
-        let (parent, last) = split_parent(path);
-        let parent_node = get_node_mut(self.root_mut(), &parent)?;
-        match (parent_node, last) {
-            (Yaml::Hash(map), PathSegment::Key(key)) => {
-                let key_node = Yaml::String(key);
-                map.remove(&key_node);
-                Ok(())
-            }
-            (Yaml::Array(seq), PathSegment::Index(index)) => {
-                if index < seq.len() {
-                    seq.remove(index);
-                    Ok(())
-                } else {
-                    Err(anyhow!("Index out of bounds"))
-                }
-            }
-            _ => Err(anyhow!("Invalid delete target")),
+    /// Replace the scalar at `path` with a one-item flow sequence holding it (`host: [a]`).
+    pub fn wrap_in_sequence(&mut self, path: &NodePath) -> Result<()> {
+        let node = get_node_mut(self.root_mut(), path)?;
+        if matches!(node, Yaml::Array(_) | Yaml::Hash(_)) {
+            return Err(anyhow!("Only a scalar can be wrapped in a sequence"));
         }
+        let value = std::mem::replace(node, Yaml::Null);
+        *node = Yaml::Array(vec![value]);
+        self.source.rename_prefix(path, &path.child_index(0));
+        self.source.flow.insert(path.clone());
+        Ok(())
     }
-}
 
-fn build_tree_node(path: &NodePath, key: String, node: &Yaml) -> TreeNode {
-    match node {
-        Yaml::Hash(map) => {
-            let mut children = Vec::new();
-            for (k, v) in map.iter() {
-                let key_str = yaml_key_to_string(k).unwrap_or_else(|| "<non-string>".to_string());
-                let child_path = path.child_key(&key_str);
-                children.push(build_tree_node(&child_path, key_str, v));
-            }
-            TreeNode {
-                path: path.clone(),
-                key,
-                node_type: NodeType::Map,
-                value_preview: String::new(),
-                children,
+    /// Replace the one-item sequence at `path` with its only item, a scalar.
+    pub fn unwrap_sequence(&mut self, path: &NodePath) -> Result<()> {
+        if self.source.alias_at(path).is_some() {
+            return Err(anyhow!("Alias; unwrap the anchored value instead"));
+        }
+        if self.source.anchor_at(path).is_some() {
+            return Err(anyhow!("Anchored; aliases refer to the whole sequence"));
+        }
+        let node = get_node_mut(self.root_mut(), path)?;
+        let value = match node {
+            Yaml::Array(seq)
+                if seq.len() == 1 && !matches!(seq[0], Yaml::Array(_) | Yaml::Hash(_)) =>
+            {
+                seq.remove(0)
             }
+            _ => return Err(anyhow!("Only a sequence of one scalar can be unwrapped")),
+        };
+        *node = value;
+        self.source.flow.remove(path);
+        self.source.rename_prefix(&path.child_index(0), path);
+        Ok(())
+    }
+
+    /// Turn the sequence of mappings at `path` into a mapping keyed by each item's `field`
+    /// (`[{name: a, port: 1}]` becomes `{a: {port: 1}}`). Fails, leaving the document as it was,
+    /// when an item is not a mapping, lacks `field` or shares its value with an earlier item.
+    pub fn sequence_to_map(&mut self, path: &NodePath, field: &str) -> Result<()> {
+        if self.shared_node(path).is_some() {
+            return Err(anyhow!(
+                "Shared through an anchor; convert the anchored value instead"
+            ));
         }
-        Yaml::Array(seq) => {
-            let mut children = Vec::new();
-            for (idx, item) in seq.iter().enumerate() {
-                let child_path = path.child_index(idx);
-                let display_key = display_key_for_yaml(item);
-                children.push(build_tree_node(&child_path, display_key, item));
+        let field_key = Yaml::String(field.to_string());
+        let Yaml::Array(seq) = get_node(&self.doc, path)? else {
+            return Err(anyhow!("Node is not a sequence"));
+        };
+        let mut keys: Vec<String> = Vec::with_capacity(seq.len());
+        for (index, item) in seq.iter().enumerate() {
+            let item_path = path.child_index(index);
+            if self.source.alias_at(&item_path).is_some()
+                || self.source.anchor_at(&item_path).is_some()
+            {
+                return Err(anyhow!("Item {index} is shared through an anchor"));
             }
-            TreeNode {
-                path: path.clone(),
-                key,
-                node_type: NodeType::Seq,
-                value_preview: String::new(),
-                children,
+            let Yaml::Hash(map) = item else {
+                return Err(anyhow!("Item {index} is not a mapping"));
+            };
+            let key = match map.get(&field_key) {
+                Some(Yaml::String(text) | Yaml::Real(text)) => text.clone(),
+                Some(Yaml::Integer(value)) => value.to_string(),
+                Some(Yaml::Boolean(value)) => value.to_string(),
+                Some(_) => return Err(anyhow!("Item {index}: {field} is not a scalar")),
+                None => return Err(anyhow!("Item {index} has no {field}")),
+            };
+            if let Some(first) = keys.iter().position(|k| *k == key) {
+                return Err(anyhow!(
+                    "Items {first} and {index} both have {field}: {key}"
+                ));
             }
+            keys.push(key);
         }
-        _ => TreeNode {
-            path: path.clone(),
-            key,
-            node_type: yaml_node_type(node),
-            value_preview: scalar_preview(node),
-            children: Vec::new(),
-        },
-    }
-}
 
-fn yaml_key_to_string(key: &Yaml) -> Option<String> {
-    match key {
-        Yaml::String(value) => Some(value.clone()),
-        _ => None,
+        let node = get_node_mut(self.root_mut(), path)?;
+        let Yaml::Array(seq) = std::mem::replace(node, Yaml::Null) else {
+            unreachable!("checked above");
+        };
+        let mut map = Hash::new();
+        for (key, item) in keys.iter().zip(seq) {
+            let Yaml::Hash(mut fields) = item else {
+                unreachable!("checked above");
+            };
+            fields.remove(&field_key);
+            map.insert(Yaml::String(key.clone()), Yaml::Hash(fields));
+        }
+        *node = Yaml::Hash(map);
+        for (index, key) in keys.iter().enumerate() {
+            let item_path = path.child_index(index);
+            self.source.remove_prefix(&item_path.child_key(field));
+            self.source.rename_prefix(&item_path, &path.child_key(key));
+        }
+        Ok(())
     }
-}
 
-/// Display label for an array element: first key if object, else value preview. No index (0, 1, ...).
-fn display_key_for_yaml(node: &Yaml) -> String {
-    match node {
-        Yaml::Hash(map) => map
-            .iter()
-            .next()
-            .and_then(|(k, _)| yaml_key_to_string(k))
-            .unwrap_or_else(|| "{}".to_string()),
-        Yaml::Array(seq) => seq
-            .first()
-            .map(|first| display_key_for_yaml(first))
-            .unwrap_or_else(|| "[]".to_string()),
-        _ => {
-            let preview = scalar_preview(node);
-            if preview.len() > 40 {
-                format!("{}…", preview.chars().take(39).collect::<String>())
-            } else {
-                preview
+    /// Turn the mapping of mappings at `path` into a sequence, putting each key back into its
+    /// value as `field` (`{a: {port: 1}}` becomes `[{name: a, port: 1}]`). The inverse of
+    /// [`YamlModel::sequence_to_map`].
+    pub fn map_to_sequence(&mut self, path: &NodePath, field: &str) -> Result<()> {
+        if self.shared_node(path).is_some() {
+            return Err(anyhow!(
+                "Shared through an anchor; convert the anchored value instead"
+            ));
+        }
+        let field_key = Yaml::String(field.to_string());
+        let Yaml::Hash(map) = get_node(&self.doc, path)? else {
+            return Err(anyhow!("Node is not a mapping"));
+        };
+        let mut keys: Vec<String> = Vec::with_capacity(map.len());
+        for (key, value) in map {
+            let Some(key) = key.as_str() else {
+                return Err(anyhow!("Keys must be strings"));
+            };
+            let value_path = path.child_key(key);
+            if self.source.alias_at(&value_path).is_some()
+                || self.source.anchor_at(&value_path).is_some()
+            {
+                return Err(anyhow!("{key} is shared through an anchor"));
+            }
+            match value {
+                Yaml::Hash(fields) if fields.contains_key(&field_key) => {
+                    return Err(anyhow!("{key} already has {field}"));
+                }
+                Yaml::Hash(_) => keys.push(key.to_string()),
+                _ => return Err(anyhow!("{key} is not a mapping")),
             }
         }
-    }
-}
 
-pub fn yaml_node_type(node: &Yaml) -> NodeType {
-    match node {
-        Yaml::Hash(_) => NodeType::Map,
-        Yaml::Array(_) => NodeType::Seq,
-        Yaml::String(_) => NodeType::String,
-        Yaml::Integer(_) | Yaml::Real(_) => NodeType::Number,
-        Yaml::Boolean(_) => NodeType::Bool,
-        Yaml::Null => NodeType::Null,
-        _ => NodeType::Unknown,
+        let node = get_node_mut(self.root_mut(), path)?;
+        let Yaml::Hash(map) = std::mem::replace(node, Yaml::Null) else {
+            unreachable!("checked above");
+        };
+        let items = map
+            .into_iter()
+            .map(|(key, value)| {
+                let Yaml::Hash(rest) = value else {
+                    unreachable!("checked above");
+                };
+                let mut fields = Hash::new();
+                fields.insert(field_key.clone(), key);
+                fields.extend(rest);
+                Yaml::Hash(fields)
+            })
+            .collect();
+        *node = Yaml::Array(items);
+        for (index, key) in keys.iter().enumerate() {
+            self.source
+                .rename_prefix(&path.child_key(key), &path.child_index(index));
+        }
+        Ok(())
     }
-}
 
-pub fn scalar_preview(node: &Yaml) -> String {
-    match node {
-        Yaml::String(value) => format!("\"{}\"", escape_yaml_string(value)),
-        Yaml::Integer(value) => value.to_string(),
-        Yaml::Real(value) => value.clone(),
-        Yaml::Boolean(value) => value.to_string(),
-        Yaml::Null => "null".to_string(),
-        _ => String::new(),
+    pub fn is_flow(&self, path: &NodePath) -> bool {
+        self.source.is_flow(path)
     }
-}
 
-pub fn escape_yaml_string(value: &str) -> String {
-    value
-        .replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\t', "\\t")
-}
+    /// Switch the collection at `path` between flow (`[a, b]`, `{k: v}`) and block style for
+    /// saving. Returns whether it is now flow.
+    pub fn toggle_flow_style(&mut self, path: &NodePath) -> Result<bool> {
+        if !matches!(get_node(&self.doc, path)?, Yaml::Array(_) | Yaml::Hash(_)) {
+            return Err(anyhow!("Only maps and sequences have a flow style"));
+        }
+        if self.source.alias_at(path).is_some() {
+            return Err(anyhow!(
+                "Alias; change the style of the anchored value instead"
+            ));
+        }
+        if (0..path.0.len()).any(|len| self.source.is_flow(&NodePath(path.0[..len].to_vec()))) {
+            return Err(anyhow!(
+                "Inside a flow collection; toggle the enclosing one"
+            ));
+        }
+        Ok(self.source.toggle_flow(path))
+    }
 
-pub fn unescape_yaml_string(value: &str) -> String {
-    let mut out = String::new();
-    let mut chars = value.chars();
-    while let Some(ch) = chars.next() {
-        if ch == '\\' {
-            if let Some(next) = chars.next() {
-                match next {
-                    'n' => out.push('\n'),
-                    't' => out.push('\t'),
-                    '"' => out.push('"'),
-                    '\\' => out.push('\\'),
-                    other => {
-                        out.push('\\');
-                        out.push(other);
-                    }
-                }
+    /// Sort the keys of the mapping at `path` (case-insensitively; non-string keys go last) or
+    /// the items of a sequence of scalars. Nested collections keep their own order. Returns false
+    /// if the order was already right.
+    pub fn sort_children(&mut self, path: &NodePath, descending: bool) -> Result<bool> {
+        if self.source.alias_at(path).is_some() {
+            return Err(anyhow!("Alias; sort the anchored value instead"));
+        }
+        let node = get_node_mut(self.root_mut(), path)?;
+        let order: Vec<usize> = match node {
+            Yaml::Hash(map) => {
+                let mut order: Vec<usize> = (0..map.len()).collect();
+                let keys: Vec<Option<&str>> = map.keys().map(Yaml::as_str).collect();
+                order.sort_by(|&a, &b| match (keys[a], keys[b]) {
+                    (Some(a), Some(b)) => {
+                        let ordering = a
+                            .to_lowercase()
+                            .cmp(&b.to_lowercase())
+                            .then_with(|| a.cmp(b));
+                        if descending {
+                            ordering.reverse()
+                        } else {
+                            ordering
+                        }
+                    }
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                });
+                order
+            }
+            Yaml::Array(seq) => {
+                if seq
+                    .iter()
+                    .any(|item| matches!(item, Yaml::Array(_) | Yaml::Hash(_)))
+                {
+                    return Err(anyhow!("Only sequences of scalars can be sorted"));
+                }
+                let mut order: Vec<usize> = (0..seq.len()).collect();
+                order.sort_by(|&a, &b| {
+                    let ordering = compare_scalars(&seq[a], &seq[b]);
+                    if descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                });
+                order
+            }
+            _ => return Err(anyhow!("Only maps and sequences can be sorted")),
+        };
+        if order.iter().enumerate().all(|(new, &old)| new == old) {
+            return Ok(false);
+        }
+        match node {
+            Yaml::Hash(map) => {
+                let entries: Vec<(Yaml, Yaml)> = std::mem::take(map).into_iter().collect();
+                let mut entries: Vec<Option<(Yaml, Yaml)>> =
+                    entries.into_iter().map(Some).collect();
+                for old in &order {
+                    if let Some((key, value)) = entries[*old].take() {
+                        map.insert(key, value);
+                    }
+                }
+            }
+            Yaml::Array(seq) => {
+                let items = std::mem::take(seq);
+                let mut items: Vec<Option<Yaml>> = items.into_iter().map(Some).collect();
+                seq.extend(order.iter().filter_map(|old| items[*old].take()));
+                self.source.reorder_items(path, &order);
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            doc: self.doc.clone(),
+            source: self.source.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.doc = snapshot.doc;
+        self.source = snapshot.source;
+    }
+
+    /// Document as last loaded or saved; the common ancestor when merging changes on disk.
+    pub fn baseline(&self) -> Option<&Yaml> {
+        self.loaded.as_ref().map(|(_, doc)| doc)
+    }
+
+    /// Take `merged` as the document after merging in the file now on disk, which becomes the
+    /// new baseline. Aliases whose value no longer matches their anchor are written out in full.
+    pub fn adopt_merge(&mut self, disk_text: String, disk: Yaml, merged: Yaml) {
+        self.doc = merged;
+        self.loaded = Some((disk_text, disk));
+        let anchors = &self.source.anchors;
+        let doc = &self.doc;
+        self.source.aliases.retain(|site, name| {
+            anchors
+                .iter()
+                .find(|(_, anchor)| *anchor == name)
+                .and_then(|(anchor_path, _)| get_node(doc, anchor_path).ok())
+                .is_some_and(|value| get_node(doc, site).ok() == Some(value))
+        });
+    }
+
+    /// Anchor name declared on the node at `path`.
+    pub fn anchor_at(&self, path: &NodePath) -> Option<&str> {
+        self.source.anchor_at(path)
+    }
+
+    /// The anchored value `path` belongs to, if any alias refers to it. The innermost anchor or
+    /// alias wins.
+    pub fn shared_node(&self, path: &NodePath) -> Option<SharedNode> {
+        for len in (0..=path.0.len()).rev() {
+            let prefix = NodePath(path.0[..len].to_vec());
+            let (name, alias_path) = if let Some(name) = self.source.alias_at(&prefix) {
+                (name, Some(prefix.clone()))
+            } else if let Some(name) = self.source.anchor_at(&prefix) {
+                (name, None)
             } else {
-                out.push('\\');
+                continue;
+            };
+            let anchor_path = self
+                .source
+                .anchors
+                .iter()
+                .find(|(_, anchor)| anchor.as_str() == name)
+                .map(|(p, _)| p.clone())?;
+            let aliases = self
+                .source
+                .aliases
+                .values()
+                .filter(|a| a.as_str() == name)
+                .count();
+            if aliases == 0 {
+                return None;
             }
-        } else {
+            return Some(SharedNode {
+                anchor: name.to_string(),
+                anchor_path,
+                alias_path,
+                suffix: path.0[len..].to_vec(),
+                aliases,
+            });
+        }
+        None
+    }
+
+    /// Path an edit of the node at `path` should change for `choice`.
+    pub fn shared_edit_path(
+        &self,
+        path: &NodePath,
+        shared: &SharedNode,
+        choice: AliasEdit,
+    ) -> NodePath {
+        match choice {
+            AliasEdit::Shared => {
+                let mut target = shared.anchor_path.0.clone();
+                target.extend_from_slice(&shared.suffix);
+                NodePath(target)
+            }
+            AliasEdit::Break => path.clone(),
+        }
+    }
+
+    /// Complete an edit made at `shared_edit_path`: refresh every alias copy of a shared edit,
+    /// or turn the aliases a broken edit touched into plain copies.
+    pub fn finish_shared_edit(&mut self, shared: &SharedNode, choice: AliasEdit) {
+        let alias_sites: Vec<NodePath> = self
+            .source
+            .aliases
+            .iter()
+            .filter(|(_, name)| **name == shared.anchor)
+            .map(|(p, _)| p.clone())
+            .collect();
+        match choice {
+            AliasEdit::Shared => {
+                let Ok(value) = get_node(&self.doc, &shared.anchor_path).cloned() else {
+                    return;
+                };
+                for site in alias_sites {
+                    if let Ok(node) = get_node_mut(&mut self.doc, &site) {
+                        *node = value.clone();
+                    }
+                }
+            }
+            AliasEdit::Break => match &shared.alias_path {
+                Some(alias_path) => {
+                    self.source.aliases.remove(alias_path);
+                }
+                None => {
+                    for site in alias_sites {
+                        self.source.aliases.remove(&site);
+                    }
+                }
+            },
+        }
+    }
+
+    /// Whole scalar at `path` as the tree previews it (strings quoted and escaped), without the
+    /// tree's truncation. `None` for collections.
+    pub fn full_value(&self, path: &NodePath) -> Option<String> {
+        match get_node(self.root(), path).ok()? {
+            Yaml::Hash(_) | Yaml::Array(_) => None,
+            node => Some(scalar_preview(node)),
+        }
+    }
+
+    /// Like `full_value`, but formats at most `max_bytes` of a string. Returns the text and whether
+    /// it was cut short; a cut string has no closing quote.
+    pub fn value_prefix(&self, path: &NodePath, max_bytes: usize) -> Option<(String, bool)> {
+        match get_node(self.root(), path).ok()? {
+            Yaml::Hash(_) | Yaml::Array(_) => None,
+            Yaml::String(value) if value.len() > max_bytes => {
+                let mut end = max_bytes;
+                while !value.is_char_boundary(end) {
+                    end -= 1;
+                }
+                Some((format!("\"{}", escape_yaml_string(&value[..end])), true))
+            }
+            node => Some((scalar_preview(node), false)),
+        }
+    }
+
+    /// The document, or the node at `path`, as pretty-printed JSON.
+    pub fn to_json(&self, path: Option<&NodePath>) -> Result<String> {
+        match path {
+            Some(path) => json_patch::json_pretty(self.node_at(path)?, path),
+            None => json_patch::json_pretty(self.root(), &NodePath(Vec::new())),
+        }
+    }
+
+    /// Node at path (read-only), e.g. to copy it into the yank register.
+    pub fn node_at(&self, path: &NodePath) -> Result<&Yaml> {
+        get_node(self.root(), path)
+    }
+
+    /// Paste a copy of `value` into the container at path: appended to a sequence, or inserted
+    /// into a mapping under `key`. Returns the path of the pasted node.
+    pub fn paste_node(
+        &mut self,
+        path: &NodePath,
+        key: Option<&str>,
+        value: &Yaml,
+    ) -> Result<NodePath> {
+        let node = get_node_mut(self.root_mut(), path)?;
+        match node {
+            Yaml::Array(seq) => {
+                seq.push(value.clone());
+                Ok(path.child_index(seq.len() - 1))
+            }
+            Yaml::Hash(map) => {
+                let key = key.ok_or_else(|| anyhow!("Key required to paste into a mapping"))?;
+                let new_key = Yaml::String(key.to_string());
+                if map.contains_key(&new_key) {
+                    return Err(anyhow!("Key already exists"));
+                }
+                map.insert(new_key, value.clone());
+                Ok(path.child_key(key))
+            }
+            _ => Err(anyhow!("Cannot paste into a scalar")),
+        }
+    }
+
+    /// Replace the node at `path` with `value`, e.g. what a command it was piped through printed.
+    /// Aliases and flow styles recorded under it go with it; a subtree holding an anchor is
+    /// refused, since aliases elsewhere would lose what they refer to.
+    pub fn replace_subtree(&mut self, path: &NodePath, value: Yaml) -> Result<()> {
+        let under = |p: &NodePath| p.0.starts_with(&path.0);
+        if let Some((_, name)) = self.source.anchors.iter().find(|(p, _)| under(p)) {
+            return Err(anyhow!("Holds anchor &{name}; aliases refer to it"));
+        }
+        *get_node_mut(self.root_mut(), path)? = value;
+        self.source.clear_aliases_under(path);
+        self.source.flow.retain(|p| !under(p));
+        Ok(())
+    }
+
+    pub fn delete_node(&mut self, path: &NodePath) -> Result<()> {
+        if path.0.is_empty() {
+            return Err(anyhow!("Cannot delete root"));
+        }
+        let (parent, last) = split_parent(path);
+        let parent_node = get_node_mut(self.root_mut(), &parent)?;
+        match (parent_node, last) {
+            (Yaml::Hash(map), PathSegment::Key(key)) => {
+                let key_node = map_key(map, &key);
+                map.remove(&key_node);
+            }
+            (Yaml::Array(seq), PathSegment::Index(index)) => {
+                if index < seq.len() {
+                    seq.remove(index);
+                } else {
+                    return Err(anyhow!("Index out of bounds"));
+                }
+            }
+            _ => return Err(anyhow!("Invalid delete target")),
+        }
+        self.source.remove_prefix(path);
+        Ok(())
+    }
+}
+
+/// `inherited` carries the merge source label when building nodes reached through `<<`.
+fn build_tree_node(
+    path: &NodePath,
+    key: String,
+    node: &Yaml,
+    source: &SourceMap,
+    identity_keys: &[String],
+    inherited: Option<&str>,
+) -> TreeNode {
+    let origin = match (inherited, source.alias_at(path)) {
+        (Some(label), _) => NodeOrigin::Inherited(label.to_string()),
+        (None, Some(name)) => NodeOrigin::Alias(format!("*{name}")),
+        (None, None) => NodeOrigin::Own,
+    };
+    match node {
+        Yaml::Hash(map) => {
+            let mut children = Vec::new();
+            let mut merge_value = None;
+            for (k, v) in map.iter() {
+                let key_str = yaml_key_to_string(k).unwrap_or_else(|| "<non-string>".to_string());
+                let child_path = path.child_key(&key_str);
+                if key_str == MERGE_KEY && inherited.is_none() {
+                    merge_value = Some(v);
+                    let label = merge_label(source, path);
+                    let mut merge_node =
+                        build_tree_node(&child_path, key_str, v, source, identity_keys, None);
+                    merge_node.value_preview = label.clone();
+                    merge_node.children = Vec::new();
+                    merge_node.origin = NodeOrigin::MergeKey(label);
+                    children.push(merge_node);
+                    continue;
+                }
+                children.push(build_tree_node(
+                    &child_path,
+                    key_str,
+                    v,
+                    source,
+                    identity_keys,
+                    inherited,
+                ));
+            }
+            if let Some(merge_value) = merge_value {
+                let label = merge_label(source, path);
+                let own: HashSet<String> = children.iter().map(|c| c.key.clone()).collect();
+                children.extend(inherited_children(
+                    path,
+                    merge_value,
+                    &own,
+                    source,
+                    identity_keys,
+                    &label,
+                ));
+            }
+            TreeNode {
+                path: path.clone(),
+                key,
+                node_type: NodeType::Map,
+                value_preview: String::new(),
+                value_size: 0,
+                children,
+                origin,
+            }
+        }
+        Yaml::Array(seq) => {
+            let mut children = Vec::new();
+            for (idx, item) in seq.iter().enumerate() {
+                let child_path = path.child_index(idx);
+                let display_key = display_key_for_yaml(item, identity_keys);
+                children.push(build_tree_node(
+                    &child_path,
+                    display_key,
+                    item,
+                    source,
+                    identity_keys,
+                    inherited,
+                ));
+            }
+            TreeNode {
+                path: path.clone(),
+                key,
+                node_type: NodeType::Seq,
+                value_preview: String::new(),
+                value_size: 0,
+                children,
+                origin,
+            }
+        }
+        _ => TreeNode {
+            path: path.clone(),
+            key,
+            node_type: yaml_node_type(node),
+            value_preview: short_preview(node),
+            value_size: scalar_size(node),
+            children: Vec::new(),
+            origin,
+        },
+    }
+}
+
+/// Label for the merge sources of the map at `map_path`, e.g. `*defaults` or `*a, *b`.
+fn merge_label(source: &SourceMap, map_path: &NodePath) -> String {
+    let sources = source.merge_sources(map_path);
+    if sources.is_empty() {
+        "(inline)".to_string()
+    } else {
+        sources
+            .iter()
+            .map(|name| format!("*{name}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Keys merged in through `<<` that the map does not override. Earlier sources win.
+/// Their paths point into the `<<` value so the Details pane can show the real value.
+fn inherited_children(
+    map_path: &NodePath,
+    merge_value: &Yaml,
+    own: &HashSet<String>,
+    source: &SourceMap,
+    identity_keys: &[String],
+    label: &str,
+) -> Vec<TreeNode> {
+    let merge_path = map_path.child_key(MERGE_KEY);
+    let sources: Vec<(NodePath, &Yaml)> = match merge_value {
+        Yaml::Hash(_) => vec![(merge_path, merge_value)],
+        Yaml::Array(seq) => seq
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| (merge_path.child_index(idx), item))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let mut seen = own.clone();
+    let mut out = Vec::new();
+    for (src_path, src) in sources {
+        if let Yaml::Hash(map) = src {
+            for (k, v) in map.iter() {
+                let Some(key_str) = yaml_key_to_string(k) else {
+                    continue;
+                };
+                if key_str == MERGE_KEY || !seen.insert(key_str.clone()) {
+                    continue;
+                }
+                let child_path = src_path.child_key(&key_str);
+                out.push(build_tree_node(
+                    &child_path,
+                    key_str,
+                    v,
+                    source,
+                    identity_keys,
+                    Some(label),
+                ));
+            }
+        }
+    }
+    out
+}
+
+/// Text a mapping key is addressed by in paths: strings as-is, other scalars as written
+/// (`42`, `true`, `null`). Collection keys have no text and can't be addressed.
+pub(crate) fn yaml_key_to_string(key: &Yaml) -> Option<String> {
+    match key {
+        Yaml::String(value) | Yaml::Real(value) => Some(value.clone()),
+        Yaml::Integer(value) => Some(value.to_string()),
+        Yaml::Boolean(value) => Some(value.to_string()),
+        Yaml::Null => Some("null".to_string()),
+        _ => None,
+    }
+}
+
+/// The key node in `map` that path text `key` names. A string key wins over a scalar key that
+/// renders the same (`'42'` over `42`); a key not in `map` is a new string key.
+pub(crate) fn map_key(map: &Hash, key: &str) -> Yaml {
+    let string_key = Yaml::String(key.to_string());
+    if map.contains_key(&string_key) {
+        return string_key;
+    }
+    map.keys()
+        .find(|k| !matches!(k, Yaml::String(_)) && yaml_key_to_string(k).as_deref() == Some(key))
+        .cloned()
+        .unwrap_or(string_key)
+}
+
+/// Display label for an array element: the value of the first identity key present, else the
+/// first key if object, else value preview. No index (0, 1, ...).
+fn display_key_for_yaml(node: &Yaml, identity_keys: &[String]) -> String {
+    match node {
+        Yaml::Hash(map) => {
+            let identity =
+                identity_keys
+                    .iter()
+                    .find_map(|key| match map.get(&Yaml::String(key.clone()))? {
+                        Yaml::String(value) => Some(truncate_label(
+                            value[..preview_source_end(value)].to_string(),
+                        )),
+                        value @ (Yaml::Integer(_) | Yaml::Real(_) | Yaml::Boolean(_)) => {
+                            Some(scalar_preview(value))
+                        }
+                        _ => None,
+                    });
+            identity.unwrap_or_else(|| {
+                map.iter()
+                    .next()
+                    .and_then(|(k, _)| yaml_key_to_string(k))
+                    .unwrap_or_else(|| "{}".to_string())
+            })
+        }
+        Yaml::Array(seq) => seq
+            .first()
+            .map(|item| display_key_for_yaml(item, identity_keys))
+            .unwrap_or_else(|| "[]".to_string()),
+        _ => short_preview(node),
+    }
+}
+
+/// Bytes of a string formatted for a tree preview; plenty for 40 columns.
+const PREVIEW_SOURCE_BYTES: usize = 256;
+
+/// End of the prefix of `value` worth formatting for a preview, on a char boundary.
+fn preview_source_end(value: &str) -> usize {
+    let mut end = value.len().min(PREVIEW_SOURCE_BYTES);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    end
+}
+
+/// `scalar_preview` truncated for a tree row. Only the start of a long string is formatted, so
+/// a multi-megabyte value costs no more than a short one.
+pub fn short_preview(node: &Yaml) -> String {
+    let Yaml::String(value) = node else {
+        return truncate_label(scalar_preview(node));
+    };
+    let end = preview_source_end(value);
+    let label = truncate_label(scalar_preview(&Yaml::String(value[..end].to_string())));
+    if end < value.len() && !label.ends_with('…') {
+        format!("{}…", label.trim_end_matches('"'))
+    } else {
+        label
+    }
+}
+
+/// Bytes in a scalar's text: the string itself, or its preview for other scalars.
+pub fn scalar_size(node: &Yaml) -> usize {
+    match node {
+        Yaml::String(value) => value.len(),
+        Yaml::Hash(_) | Yaml::Array(_) => 0,
+        _ => scalar_preview(node).len(),
+    }
+}
+
+fn truncate_label(label: String) -> String {
+    if width::display_width(&label) > 40 {
+        format!("{}…", &label[..width::byte_at_column(&label, 39)])
+    } else {
+        label
+    }
+}
+
+pub fn yaml_node_type(node: &Yaml) -> NodeType {
+    match node {
+        Yaml::Hash(_) => NodeType::Map,
+        Yaml::Array(_) => NodeType::Seq,
+        Yaml::String(value) if timestamp::is_timestamp(value) => NodeType::Timestamp,
+        Yaml::String(_) => NodeType::String,
+        Yaml::Integer(_) | Yaml::Real(_) => NodeType::Number,
+        Yaml::Boolean(_) => NodeType::Bool,
+        Yaml::Null => NodeType::Null,
+        _ => NodeType::Unknown,
+    }
+}
+
+pub fn scalar_preview(node: &Yaml) -> String {
+    match node {
+        Yaml::String(value) => format!("\"{}\"", escape_yaml_string(value)),
+        Yaml::Integer(value) => value.to_string(),
+        Yaml::Real(value) => value.clone(),
+        Yaml::Boolean(value) => value.to_string(),
+        Yaml::Null => "null".to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Short summary of any node: the scalar preview, or the size of a container.
+pub fn node_preview(node: &Yaml) -> String {
+    match node {
+        Yaml::Hash(map) => format!("{{{} keys}}", map.len()),
+        Yaml::Array(seq) => format!("[{} items]", seq.len()),
+        _ => scalar_preview(node),
+    }
+}
+
+/// Double-quoted YAML form of `value` without the quotes. Control characters are escaped so
+/// the text stays on one line; other characters, non-ASCII included, are kept as they are.
+pub fn escape_yaml_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\0' => out.push_str("\\0"),
+            ch if ch.is_control() => out.push_str(&format!("\\u{:04X}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Resolve the escapes of a double-quoted YAML string: `\n`, `\t`, `\r`, `\0`, `\"`, `\\`,
+/// `\xXX`, `\uXXXX` and `\UXXXXXXXX`. Anything else, such as a bad hex code, is kept literally.
+pub fn unescape_yaml_string(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('\\') {
+        out.push_str(&rest[..start]);
+        let escape = &rest[start + 1..];
+        let simple = match escape.chars().next() {
+            Some('n') => Some('\n'),
+            Some('t') => Some('\t'),
+            Some('r') => Some('\r'),
+            Some('0') => Some('\0'),
+            Some('"') => Some('"'),
+            Some('\\') => Some('\\'),
+            _ => None,
+        };
+        let hex_len = match escape.chars().next() {
+            Some('x') => 2,
+            Some('u') => 4,
+            Some('U') => 8,
+            _ => 0,
+        };
+        let code_point = escape
+            .get(1..1 + hex_len)
+            .filter(|digits| hex_len > 0 && digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .and_then(char::from_u32);
+        if let Some(ch) = simple {
             out.push(ch);
+            rest = &escape[1..];
+        } else if let Some(ch) = code_point {
+            out.push(ch);
+            rest = &escape[1 + hex_len..];
+        } else {
+            out.push('\\');
+            rest = escape;
+            // An unknown escape such as `\q` stays as written.
+            if let Some(next) = rest.chars().next() {
+                out.push(next);
+                rest = &rest[next.len_utf8()..];
+            }
         }
     }
+    out.push_str(rest);
     out
 }
 
@@ -433,6 +1670,8 @@ pub enum ScalarValue {
 #[derive(Clone, Debug, PartialEq)]
 pub enum ScalarNumber {
     Integer(i64),
+    /// Integer literal outside the `i64` range, kept as its exact digits.
+    BigInt(String),
     Float(f64),
 }
 
@@ -445,6 +1684,11 @@ pub fn parse_scalar_input(input: &str) -> Result<ScalarValue> {
         let inner = &trimmed[1..trimmed.len() - 1];
         return Ok(ScalarValue::String(unescape_yaml_string(inner)));
     }
+    if trimmed.starts_with('\'') && trimmed.ends_with('\'') && trimmed.len() >= 2 {
+        // Single quotes have no backslash escapes; `''` stands for one quote.
+        let inner = &trimmed[1..trimmed.len() - 1];
+        return Ok(ScalarValue::String(inner.replace("''", "'")));
+    }
     let lower = trimmed.to_lowercase();
     match lower.as_str() {
         "true" => return Ok(ScalarValue::Bool(true)),
@@ -452,33 +1696,346 @@ pub fn parse_scalar_input(input: &str) -> Result<ScalarValue> {
         "null" => return Ok(ScalarValue::Null),
         _ => {}
     }
-    if let Ok(value) = trimmed.parse::<i64>() {
+    if let Some(value) = parse_radix_integer(trimmed) {
+        return value.map(|value| ScalarValue::Number(ScalarNumber::Integer(value)));
+    }
+    // `1_000` and `1_000.5`: underscores between digits only group them.
+    let number = without_separators(trimmed, 10).unwrap_or_else(|| trimmed.to_string());
+    if let Ok(value) = number.parse::<i64>() {
         return Ok(ScalarValue::Number(ScalarNumber::Integer(value)));
     }
-    if let Ok(value) = trimmed.parse::<f64>() {
+    if is_integer_literal(&number) {
+        return Ok(ScalarValue::Number(ScalarNumber::BigInt(number)));
+    }
+    if let Some(value) = parse_yaml_float(&number) {
         return Ok(ScalarValue::Number(ScalarNumber::Float(value)));
     }
     // YAML allows unquoted strings; treat remaining input as string
     Ok(ScalarValue::String(trimmed.to_string()))
 }
 
-fn scalar_to_yaml(value: ScalarValue) -> Yaml {
+/// YAML 1.2 float forms: decimal and scientific notation, `.inf`, `-.inf` and `.nan`.
+fn parse_yaml_float(text: &str) -> Option<f64> {
+    let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+    match unsigned {
+        ".inf" | ".Inf" | ".INF" if text.starts_with('-') => return Some(f64::NEG_INFINITY),
+        ".inf" | ".Inf" | ".INF" => return Some(f64::INFINITY),
+        ".nan" | ".NaN" | ".NAN" if unsigned.len() == text.len() => return Some(f64::NAN),
+        _ => {}
+    }
+    // Rust also parses `inf`, `infinity` and `nan`, which YAML reads as strings.
+    let numeric = unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && unsigned
+            .bytes()
+            .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'-' | b'+'));
+    if numeric {
+        unsigned
+            .parse::<f64>()
+            .ok()
+            .map(|v| if text.starts_with('-') { -v } else { v })
+    } else {
+        None
+    }
+}
+
+/// Canonical YAML text for a float; whole numbers keep a `.0` so they reload as floats.
+fn yaml_float_text(value: f64) -> String {
+    if value.is_nan() {
+        ".nan".to_string()
+    } else if value.is_infinite() {
+        if value < 0.0 { "-.inf" } else { ".inf" }.to_string()
+    } else {
+        format!("{value:?}")
+    }
+}
+
+/// `0x1F`, `0o755` and `0b1010`, optionally signed and grouped with underscores. `None` when
+/// `text` isn't one (`0xG` stays a string); an error when it doesn't fit in 64 bits.
+fn parse_radix_integer(text: &str) -> Option<Result<i64>> {
+    let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+    let (radix, digits) = [("0x", 16), ("0o", 8), ("0b", 2)]
+        .into_iter()
+        .find_map(|(prefix, radix)| unsigned.strip_prefix(prefix).map(|digits| (radix, digits)))?;
+    let digits = without_separators(digits, radix).unwrap_or_else(|| digits.to_string());
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    let signed = if text.starts_with('-') {
+        format!("-{digits}")
+    } else {
+        digits
+    };
+    Some(
+        i64::from_str_radix(&signed, radix)
+            .map_err(|_| anyhow!("{text} does not fit in a 64-bit integer")),
+    )
+}
+
+/// `text` without the underscores that group its digits, when it has any and each sits
+/// between two digits of `radix`.
+fn without_separators(text: &str, radix: u32) -> Option<String> {
+    if !text.contains('_') {
+        return None;
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let grouped = chars.iter().enumerate().all(|(i, &c)| {
+        c != '_'
+            || (i > 0
+                && chars[i - 1].is_digit(radix)
+                && chars.get(i + 1).is_some_and(|next| next.is_digit(radix)))
+    });
+    grouped.then(|| text.replace('_', ""))
+}
+
+fn is_integer_literal(text: &str) -> bool {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Parse editor input that replaces `previous`. Clearing a string leaves an empty string rather
+/// than turning it into null; other cleared values become null.
+pub fn parse_edit_input(input: &str, previous: &Yaml) -> Result<ScalarValue> {
+    if input.trim().is_empty() && matches!(previous, Yaml::String(_)) {
+        return Ok(ScalarValue::String(String::new()));
+    }
+    parse_scalar_input(input)
+}
+
+pub fn scalar_to_yaml(value: ScalarValue) -> Yaml {
     match value {
         ScalarValue::String(value) => Yaml::String(value),
         ScalarValue::Bool(value) => Yaml::Boolean(value),
         ScalarValue::Null => Yaml::Null,
         ScalarValue::Number(ScalarNumber::Integer(value)) => Yaml::Integer(value),
-        ScalarValue::Number(ScalarNumber::Float(value)) => Yaml::Real(value.to_string()),
+        // yaml-rust2 has no wider integer type; a Real keeps the literal text for display and save.
+        ScalarValue::Number(ScalarNumber::BigInt(digits)) => Yaml::Real(digits),
+        ScalarValue::Number(ScalarNumber::Float(value)) => Yaml::Real(yaml_float_text(value)),
+    }
+}
+
+fn cast_yaml(node: &Yaml, target: &NodeType) -> Result<Yaml> {
+    if matches!(node, Yaml::Hash(_) | Yaml::Array(_)) {
+        return Err(anyhow!("Cannot cast a {}", yaml_node_type(node)));
+    }
+    if yaml_node_type(node) == *target {
+        return Ok(node.clone());
+    }
+    let text = match node {
+        Yaml::String(value) => value.trim().to_string(),
+        Yaml::Null => String::new(),
+        _ => scalar_preview(node),
+    };
+    let cast = match target {
+        NodeType::String => Yaml::String(match node {
+            Yaml::String(value) => value.clone(),
+            _ => text,
+        }),
+        NodeType::Number => {
+            if let Ok(value) = text.parse::<i64>() {
+                Yaml::Integer(value)
+            } else if parse_yaml_float(&text).is_some() {
+                Yaml::Real(text)
+            } else {
+                return Err(anyhow!("'{}' is not a number", text));
+            }
+        }
+        NodeType::Bool => match text.to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Yaml::Boolean(true),
+            "false" | "no" | "off" | "0" => Yaml::Boolean(false),
+            _ => return Err(anyhow!("'{}' is not a boolean", text)),
+        },
+        NodeType::Null => Yaml::Null,
+        NodeType::Map | NodeType::Seq | NodeType::Timestamp | NodeType::Unknown => {
+            return Err(anyhow!("Cannot cast to {}", target));
+        }
+    };
+    Ok(cast)
+}
+
+/// Order for sorting sequence items: nulls, booleans, numbers (by value), then strings
+/// (case-insensitively).
+fn compare_scalars(a: &Yaml, b: &Yaml) -> std::cmp::Ordering {
+    let rank = |node: &Yaml| match node {
+        Yaml::Null => 0,
+        Yaml::Boolean(_) => 1,
+        Yaml::Integer(_) | Yaml::Real(_) => 2,
+        _ => 3,
+    };
+    match (a, b) {
+        (Yaml::Boolean(a), Yaml::Boolean(b)) => a.cmp(b),
+        (Yaml::Integer(_) | Yaml::Real(_), Yaml::Integer(_) | Yaml::Real(_)) => {
+            let number = |node: &Yaml| node.as_f64().or_else(|| node.as_i64().map(|n| n as f64));
+            number(a)
+                .partial_cmp(&number(b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        }
+        (Yaml::String(a), Yaml::String(b)) => a
+            .to_lowercase()
+            .cmp(&b.to_lowercase())
+            .then_with(|| a.cmp(b)),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Every scalar under `node` with its path, in document order.
+fn collect_scalars<'a>(node: &'a Yaml, at: &NodePath, out: &mut Vec<(NodePath, &'a Yaml)>) {
+    match node {
+        Yaml::Hash(map) => {
+            for (key, value) in map {
+                if let Some(key) = key.as_str() {
+                    collect_scalars(value, &at.child_key(key), out);
+                }
+            }
+        }
+        Yaml::Array(seq) => {
+            for (index, item) in seq.iter().enumerate() {
+                collect_scalars(item, &at.child_index(index), out);
+            }
+        }
+        Yaml::Alias(_) | Yaml::BadValue => {}
+        scalar => out.push((at.clone(), scalar)),
+    }
+}
+
+/// Paths of every mapping entry below `node`, parents before their children.
+fn collect_keys(node: &Yaml, at: &NodePath, out: &mut Vec<NodePath>) {
+    match node {
+        Yaml::Hash(map) => {
+            for (key, value) in map {
+                if let Some(key) = yaml_key_to_string(key) {
+                    let path = at.child_key(&key);
+                    out.push(path.clone());
+                    collect_keys(value, &path, out);
+                }
+            }
+        }
+        Yaml::Array(seq) => {
+            for (index, item) in seq.iter().enumerate() {
+                collect_keys(item, &at.child_index(index), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Text find-and-replace works on: a string's value, or with `coerce` a number's or boolean's.
+fn replaceable_text(node: &Yaml, coerce: bool) -> Option<String> {
+    match node {
+        Yaml::String(text) => Some(text.clone()),
+        Yaml::Integer(value) if coerce => Some(value.to_string()),
+        Yaml::Real(text) if coerce => Some(text.clone()),
+        Yaml::Boolean(value) if coerce => Some(value.to_string()),
+        _ => None,
     }
 }
 
-fn get_node_mut<'a>(root: &'a mut Yaml, path: &NodePath) -> Result<&'a mut Yaml> {
+/// Walk `node` for [`YamlModel::rename_key_everywhere`]. Paths stay those of the document
+/// before renaming; `shared` is set below an alias, whose copy mirrors its anchor.
+#[allow(clippy::too_many_arguments)]
+fn rename_keys_in(
+    node: &mut Yaml,
+    at: &NodePath,
+    old: &str,
+    new: &str,
+    source: &SourceMap,
+    shared: bool,
+    renamed: &mut Vec<NodePath>,
+    skipped: &mut usize,
+) {
+    let shared = shared || source.alias_at(at).is_some();
+    match node {
+        Yaml::Hash(map) => {
+            let old_key = Yaml::String(old.to_string());
+            let new_key = Yaml::String(new.to_string());
+            let mut renamed_here = false;
+            if map.contains_key(&old_key) {
+                if map.contains_key(&new_key) {
+                    if !shared {
+                        *skipped += 1;
+                    }
+                } else {
+                    *map = std::mem::take(map)
+                        .into_iter()
+                        .map(|(k, v)| {
+                            if k == old_key {
+                                (new_key.clone(), v)
+                            } else {
+                                (k, v)
+                            }
+                        })
+                        .collect();
+                    renamed_here = true;
+                    if !shared {
+                        renamed.push(at.child_key(old));
+                    }
+                }
+            }
+            for (key, value) in map.iter_mut() {
+                let Some(key) = key.as_str() else {
+                    continue;
+                };
+                let key = if renamed_here && key == new { old } else { key };
+                rename_keys_in(
+                    value,
+                    &at.child_key(key),
+                    old,
+                    new,
+                    source,
+                    shared,
+                    renamed,
+                    skipped,
+                );
+            }
+        }
+        Yaml::Array(seq) => {
+            for (index, item) in seq.iter_mut().enumerate() {
+                rename_keys_in(
+                    item,
+                    &at.child_index(index),
+                    old,
+                    new,
+                    source,
+                    shared,
+                    renamed,
+                    skipped,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn get_node<'a>(root: &'a Yaml, path: &NodePath) -> Result<&'a Yaml> {
     let mut node = root;
     for segment in &path.0 {
         match segment {
             PathSegment::Key(key) => match node {
                 Yaml::Hash(map) => {
-                    let key_node = Yaml::String(key.clone());
+                    let key_node = map_key(map, key);
+                    node = map.get(&key_node).ok_or_else(|| anyhow!("Key not found"))?;
+                }
+                _ => return Err(anyhow!("Expected mapping")),
+            },
+            PathSegment::Index(index) => match node {
+                Yaml::Array(seq) => {
+                    node = seq
+                        .get(*index)
+                        .ok_or_else(|| anyhow!("Index out of bounds"))?;
+                }
+                _ => return Err(anyhow!("Expected sequence")),
+            },
+        }
+    }
+    Ok(node)
+}
+
+pub fn get_node_mut<'a>(root: &'a mut Yaml, path: &NodePath) -> Result<&'a mut Yaml> {
+    let mut node = root;
+    for segment in &path.0 {
+        match segment {
+            PathSegment::Key(key) => match node {
+                Yaml::Hash(map) => {
+                    let key_node = map_key(map, key);
                     node = map.get_mut(&key_node).ok_or_else(|| anyhow!("Key not found"))?;
                 }
                 _ => return Err(anyhow!("Expected mapping")),
@@ -487,11 +2044,124 @@ fn get_node_mut<'a>(root: &'a mut Yaml, path: &NodePath) -> Result<&'a mut Yaml>
                 Yaml::Array(seq) => {
                     node = seq.get_mut(*index).ok_or_else(|| anyhow!("Index out of bounds"))?;
                 }
-                _ => return Err(anyhow!("Expected sequence")),
-            },
+                _ => return Err(anyhow!("Expected sequence")),
+            },
+        }
+    }
+    Ok(node)
+}
+
+/// Path of the node a dot path such as `spec.containers.0.image` names, the inverse of
+/// [`NodePath::dot_path`]. Keys may themselves contain dots; the longest matching key wins.
+/// The empty string is the root.
+pub fn resolve_dot_path(root: &Yaml, dot: &str) -> Option<NodePath> {
+    resolve(root, dot, false)
+}
+
+/// Like [`resolve_dot_path`], but a key a mapping inherits through `<<` is found too, at the
+/// path into the `<<` value the tree shows it under. For reading only: writing there would
+/// change the merge source.
+pub fn resolve_merged_dot_path(root: &Yaml, dot: &str) -> Option<NodePath> {
+    resolve(root, dot, true)
+}
+
+fn resolve(root: &Yaml, dot: &str, merges: bool) -> Option<NodePath> {
+    if dot.is_empty() {
+        return Some(NodePath(Vec::new()));
+    }
+    resolve_segments(root, dot, merges).map(|mut segments| {
+        segments.reverse();
+        NodePath(segments)
+    })
+}
+
+/// Segments of `rest` below `node`, innermost first.
+fn resolve_segments(node: &Yaml, rest: &str, merges: bool) -> Option<Vec<PathSegment>> {
+    let mut ends: Vec<usize> = rest.match_indices('.').map(|(idx, _)| idx).collect();
+    ends.push(rest.len());
+    for &end in ends.iter().rev() {
+        let (head, tail) = (&rest[..end], rest.get(end + 1..));
+        let (mut segments, child) = match node {
+            Yaml::Hash(map) if merges => match merged_lookup(map, head) {
+                Some((mut outer_first, child)) => {
+                    outer_first.reverse();
+                    (outer_first, Some(child))
+                }
+                None => continue,
+            },
+            Yaml::Hash(map) => (
+                vec![PathSegment::Key(head.to_string())],
+                map.get(&map_key(map, head)),
+            ),
+            Yaml::Array(seq) => match head.parse::<usize>() {
+                Ok(index) => (vec![PathSegment::Index(index)], seq.get(index)),
+                Err(_) => continue,
+            },
+            _ => return None,
+        };
+        let Some(child) = child else {
+            continue;
+        };
+        match tail {
+            None => return Some(segments),
+            Some(tail) => {
+                if let Some(mut below) = resolve_segments(child, tail, merges) {
+                    below.append(&mut segments);
+                    return Some(below);
+                }
+            }
         }
     }
-    Ok(node)
+    None
+}
+
+/// `key` in `map`, or else in the maps merged into it through `<<` (earlier sources first), with
+/// the segments leading to it, outermost first.
+fn merged_lookup<'a>(map: &'a Hash, key: &str) -> Option<(Vec<PathSegment>, &'a Yaml)> {
+    if let Some(value) = map.get(&map_key(map, key)) {
+        return Some((vec![PathSegment::Key(key.to_string())], value));
+    }
+    merge_sources(map).into_iter().find_map(|(index, source)| {
+        let (segments, value) = merged_lookup(source, key)?;
+        let mut path = vec![PathSegment::Key(MERGE_KEY.to_string())];
+        path.extend(index.map(PathSegment::Index));
+        path.extend(segments);
+        Some((path, value))
+    })
+}
+
+/// The mappings merged into `map` through `<<`, in precedence order, each with its index when
+/// the `<<` value is a sequence.
+fn merge_sources(map: &Hash) -> Vec<(Option<usize>, &Hash)> {
+    match map.get(&Yaml::String(MERGE_KEY.to_string())) {
+        Some(Yaml::Hash(source)) => vec![(None, source)],
+        Some(Yaml::Array(seq)) => seq
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| item.as_hash().map(|source| (Some(index), source)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The entries of `map` with its merge keys applied: its own keys first, then those inherited
+/// through `<<` that it does not override, in source order. A `<<` whose value is not a mapping
+/// or sequence stays an ordinary key.
+pub(crate) fn merged_entries(map: &Hash) -> Vec<(&Yaml, &Yaml)> {
+    let merge_key = Yaml::String(MERGE_KEY.to_string());
+    let merges = matches!(map.get(&merge_key), Some(Yaml::Hash(_) | Yaml::Array(_)));
+    let mut entries: Vec<(&Yaml, &Yaml)> = map
+        .iter()
+        .filter(|(key, _)| !merges || **key != merge_key)
+        .collect();
+    for (_, source) in merge_sources(map) {
+        for (key, value) in merged_entries(source) {
+            if !entries.iter().any(|(seen, _)| *seen == key) {
+                entries.push((key, value));
+            }
+        }
+    }
+    entries
 }
 
 fn split_parent(path: &NodePath) -> (NodePath, PathSegment) {
@@ -511,19 +2181,22 @@ fn split_parent_key(path: &NodePath) -> Result<(NodePath, String)> {
 pub fn flatten_visible(
     node: &TreeNode,
     expanded: &HashSet<String>,
-    filter: Option<&str>,
+    filter: Option<(&Query, SearchScope)>,
 ) -> Vec<VisibleRow> {
     let mut rows = Vec::new();
-    let query = filter.map(|q| q.to_lowercase());
     let mut ancestors = HashSet::new();
-    if let Some(q) = &query {
+    if let Some(q) = filter {
         collect_matching_ancestors(node, q, &mut ancestors);
     }
-    walk_visible(node, expanded, query.as_deref(), &ancestors, 0, &mut rows);
+    walk_visible(node, expanded, filter, &ancestors, 0, &mut rows);
     rows
 }
 
-fn collect_matching_ancestors(node: &TreeNode, query: &str, ancestors: &mut HashSet<String>) -> bool {
+fn collect_matching_ancestors(
+    node: &TreeNode,
+    query: (&Query, SearchScope),
+    ancestors: &mut HashSet<String>,
+) -> bool {
     let mut matched = node_matches(node, query);
     for child in &node.children {
         if collect_matching_ancestors(child, query, ancestors) {
@@ -539,7 +2212,7 @@ fn collect_matching_ancestors(node: &TreeNode, query: &str, ancestors: &mut Hash
 fn walk_visible(
     node: &TreeNode,
     expanded: &HashSet<String>,
-    query: Option<&str>,
+    query: Option<(&Query, SearchScope)>,
     ancestors: &HashSet<String>,
     depth: usize,
     rows: &mut Vec<VisibleRow>,
@@ -553,8 +2226,10 @@ fn walk_visible(
             depth: 0,
             display_key: "(root)".to_string(),
             display_value_preview: String::new(),
+            value_size: 0,
             node_type: node.node_type.clone(),
             is_container: true,
+            origin: NodeOrigin::Own,
         });
     }
     if !node.path.0.is_empty() {
@@ -569,8 +2244,10 @@ fn walk_visible(
             depth,
             display_key: node.key.clone(),
             display_value_preview: node.value_preview.clone(),
+            value_size: node.value_size,
             node_type: node.node_type.clone(),
-            is_container: matches!(node.node_type, NodeType::Map | NodeType::Seq),
+            is_container: node.is_container(),
+            origin: node.origin.clone(),
         });
     }
 
@@ -591,10 +2268,41 @@ fn walk_visible(
     }
 }
 
-fn node_matches(node: &TreeNode, query: &str) -> bool {
-    let query = query.to_lowercase();
-    let dot = node.path.dot_path().to_lowercase();
-    dot.contains(&query) || node.key.to_lowercase().contains(&query)
+fn node_matches(node: &TreeNode, (query, scope): (&Query, SearchScope)) -> bool {
+    !node.path.0.is_empty()
+        && search::matches_fields(
+            &node.path.dot_path(),
+            &node.key,
+            &node.value_preview,
+            query,
+            scope,
+        )
+}
+
+/// Node at `path` below `node`, following the children whose paths lead there.
+pub fn find_tree_node<'a>(node: &'a TreeNode, path: &NodePath) -> Option<&'a TreeNode> {
+    let mut node = node;
+    for depth in node.path.0.len()..path.0.len() {
+        node = node
+            .children
+            .iter()
+            .find(|child| child.path.0[..] == path.0[..=depth])?;
+    }
+    Some(node)
+}
+
+/// Dot paths of `node` and every mapping and list below it fewer than `max_depth` levels
+/// below the root, in one walk.
+pub fn container_dot_paths(node: &TreeNode, max_depth: usize, out: &mut Vec<String>) {
+    if node.path.0.len() >= max_depth {
+        return;
+    }
+    if node.is_container() {
+        out.push(node.path.dot_path());
+    }
+    for child in &node.children {
+        container_dot_paths(child, max_depth, out);
+    }
 }
 
 pub fn visible_row_by_path(rows: &[VisibleRow], path: &NodePath) -> Option<usize> {
@@ -602,6 +2310,29 @@ pub fn visible_row_by_path(rows: &[VisibleRow], path: &NodePath) -> Option<usize
         .position(|row| row.path == *path)
 }
 
+/// Next (or previous) row after `index` with the same parent, skipping the rows below the
+/// siblings in between. `None` at the last (or first) child and for the root.
+pub fn sibling_row(rows: &[VisibleRow], index: usize, forward: bool) -> Option<usize> {
+    let path = &rows.get(index)?.path.0;
+    let (_, parent) = path.split_last()?;
+    let same_parent =
+        |row: &VisibleRow| row.path.0.len() == path.len() && row.path.0.starts_with(parent);
+    // A row shallower than the selection means the parent's children ended.
+    let left_parent = |row: &VisibleRow| row.path.0.len() < path.len();
+    if forward {
+        let rest = rows.iter().enumerate().skip(index + 1);
+        rest.take_while(|(_, row)| !left_parent(row))
+            .find(|(_, row)| same_parent(row))
+            .map(|(idx, _)| idx)
+    } else {
+        let before = rows[..index].iter().enumerate().rev();
+        before
+            .take_while(|(_, row)| !left_parent(row))
+            .find(|(_, row)| same_parent(row))
+            .map(|(idx, _)| idx)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -617,6 +2348,42 @@ mod tests {
         assert_eq!(path.dot_path(), "items.0.name");
     }
 
+    #[test]
+    fn dot_paths_resolve_back_to_node_paths() {
+        let root = &YamlLoader::load_from_str("a:\n  b.c:\n    - x: 1\n  b: 2\n").unwrap()[0];
+        let path = resolve_dot_path(root, "a.b.c.0.x").unwrap();
+        assert_eq!(
+            path,
+            NodePath(vec![
+                PathSegment::Key("a".into()),
+                PathSegment::Key("b.c".into()),
+                PathSegment::Index(0),
+                PathSegment::Key("x".into()),
+            ])
+        );
+        assert_eq!(resolve_dot_path(root, &path.dot_path()), Some(path));
+        assert_eq!(resolve_dot_path(root, "a.b").unwrap().dot_path(), "a.b");
+        assert_eq!(resolve_dot_path(root, ""), Some(NodePath(Vec::new())));
+        assert_eq!(resolve_dot_path(root, "a.b.c.1"), None);
+        assert_eq!(resolve_dot_path(root, "a.missing"), None);
+    }
+
+    #[test]
+    fn merged_dot_paths_find_inherited_keys() {
+        let src = "one: &one {a: 1}\ntwo: &two {b: {c: 2}}\nsvc:\n  <<: [*one, *two]\n  a: 0\n";
+        let root = &YamlLoader::load_from_str(src).unwrap()[0];
+        assert_eq!(resolve_dot_path(root, "svc.b.c"), None);
+        assert_eq!(
+            resolve_merged_dot_path(root, "svc.b.c").unwrap().dot_path(),
+            "svc.<<.1.b.c"
+        );
+        assert_eq!(
+            resolve_merged_dot_path(root, "svc.a").unwrap().dot_path(),
+            "svc.a"
+        );
+        assert_eq!(resolve_merged_dot_path(root, "svc.z"), None);
+    }
+
     #[test]
     fn depth_computation() {
         let path = NodePath(vec![
@@ -628,6 +2395,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn scalar_parsing_rules() {
         assert_eq!(
             parse_scalar_input("\"hello\"").unwrap(),
@@ -647,7 +2415,812 @@ mod tests {
             parse_scalar_input("hello").unwrap(),
             ScalarValue::String("hello".into())
         );
+        assert_eq!(
+            parse_scalar_input("18446744073709551615").unwrap(),
+            ScalarValue::Number(ScalarNumber::BigInt("18446744073709551615".into()))
+        );
         assert_eq!(parse_scalar_input("").unwrap(), ScalarValue::Null);
         assert_eq!(parse_scalar_input("   ").unwrap(), ScalarValue::Null);
+        assert_eq!(
+            parse_scalar_input("'hello world'").unwrap(),
+            ScalarValue::String("hello world".into())
+        );
+        assert_eq!(
+            parse_scalar_input("'it''s'").unwrap(),
+            ScalarValue::String("it's".into())
+        );
+        assert_eq!(
+            parse_scalar_input("''").unwrap(),
+            ScalarValue::String(String::new())
+        );
+        assert_eq!(
+            parse_scalar_input(r"'C:\temp'").unwrap(),
+            ScalarValue::String(r"C:\temp".into())
+        );
+        assert_eq!(
+            parse_scalar_input("'true'").unwrap(),
+            ScalarValue::String("true".into())
+        );
+        assert_eq!(
+            parse_scalar_input("'").unwrap(),
+            ScalarValue::String("'".into())
+        );
+    }
+
+    #[test]
+    fn scalar_parsing_special_floats() {
+        let float = |input: &str| match parse_scalar_input(input).unwrap() {
+            ScalarValue::Number(ScalarNumber::Float(value)) => Some(value),
+            _ => None,
+        };
+        assert_eq!(float("1e-5"), Some(1e-5));
+        assert_eq!(float("-2.5E+3"), Some(-2500.0));
+        assert_eq!(float(".5"), Some(0.5));
+        assert_eq!(float(".inf"), Some(f64::INFINITY));
+        assert_eq!(float("+.INF"), Some(f64::INFINITY));
+        assert_eq!(float("-.Inf"), Some(f64::NEG_INFINITY));
+        assert!(float(".NaN").is_some_and(f64::is_nan));
+        for text in ["inf", "nan", "infinity", "-.nan", "1e", "."] {
+            assert_eq!(float(text), None, "{text}");
+        }
+
+        let emitted = |input: &str| scalar_to_yaml(parse_scalar_input(input).unwrap());
+        assert_eq!(emitted("-.inf"), Yaml::Real("-.inf".into()));
+        assert_eq!(emitted(".nan"), Yaml::Real(".nan".into()));
+        assert_eq!(emitted("1e-5"), Yaml::Real("1e-5".into()));
+        assert_eq!(emitted("2.0"), Yaml::Real("2.0".into()));
+        assert_eq!(scalar_preview(&emitted("1E300")), "1e300");
+    }
+
+    #[test]
+    fn string_escapes_round_trip() {
+        let text = "em\u{2014}dash \u{1F600} tab\tcr\r nul\0 bell\u{7} \"q\" \\ caf\u{e9}";
+        let escaped = escape_yaml_string(text);
+        assert_eq!(
+            escaped,
+            "em\u{2014}dash \u{1F600} tab\\tcr\\r nul\\0 bell\\u0007 \\\"q\\\" \\\\ caf\u{e9}"
+        );
+        assert_eq!(unescape_yaml_string(&escaped), text);
+        assert_eq!(
+            unescape_yaml_string(r"\u2014 \U0001F600 \x41\xe9"),
+            "\u{2014} \u{1F600} A\u{e9}"
+        );
+        // Invalid escapes are kept as typed.
+        for text in [r"\uZZZZ", r"\u12", r"\UFFFFFFFF", r"\uD800", r"\q", r"end\"] {
+            assert_eq!(unescape_yaml_string(text), text);
+        }
+        assert_eq!(
+            parse_scalar_input(r#""em\u2014dash""#).unwrap(),
+            ScalarValue::String("em\u{2014}dash".into())
+        );
+    }
+
+    #[test]
+    fn scalar_parsing_number_forms() {
+        let int = |input: &str| match parse_scalar_input(input).unwrap() {
+            ScalarValue::Number(ScalarNumber::Integer(value)) => Some(value),
+            _ => None,
+        };
+        assert_eq!(int("1_000"), Some(1000));
+        assert_eq!(int("-1_000_000"), Some(-1_000_000));
+        assert_eq!(int("0x1F"), Some(31));
+        assert_eq!(int("0x_ff"), None);
+        assert_eq!(int("0xdead_beef"), Some(0xdead_beef));
+        assert_eq!(int("0o755"), Some(0o755));
+        assert_eq!(int("-0o17"), Some(-15));
+        assert_eq!(int("0b1010"), Some(10));
+        assert_eq!(int("+0b1"), Some(1));
+        assert_eq!(int("-0x8000000000000000"), Some(i64::MIN));
+        assert!(parse_scalar_input("0x10000000000000000").is_err());
+        assert_eq!(
+            parse_scalar_input("1_000.5").unwrap(),
+            ScalarValue::Number(ScalarNumber::Float(1000.5))
+        );
+        assert_eq!(
+            parse_scalar_input("1e6").unwrap(),
+            ScalarValue::Number(ScalarNumber::Float(1e6))
+        );
+        assert_eq!(
+            parse_scalar_input("18_446_744_073_709_551_616").unwrap(),
+            ScalarValue::Number(ScalarNumber::BigInt("18446744073709551616".into()))
+        );
+        for text in [
+            "0xG", "0x", "0o8", "0b2", "1.2.3", "1__000", "_1", "1_", "v1_0", "1_e5",
+        ] {
+            assert_eq!(
+                parse_scalar_input(text).unwrap(),
+                ScalarValue::String(text.into()),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn cleared_input_keeps_string_type() {
+        let text = Yaml::String("abc".into());
+        assert_eq!(
+            parse_edit_input("", &text).unwrap(),
+            ScalarValue::String(String::new())
+        );
+        assert_eq!(
+            parse_edit_input("\"\"", &Yaml::Null).unwrap(),
+            ScalarValue::String(String::new())
+        );
+        assert_eq!(
+            parse_edit_input("  ", &Yaml::Integer(1)).unwrap(),
+            ScalarValue::Null
+        );
+        assert_eq!(
+            parse_edit_input("", &Yaml::Null).unwrap(),
+            ScalarValue::Null
+        );
+    }
+
+    #[test]
+    fn siblings_are_inserted_after_their_anchor() {
+        let file = std::env::temp_dir().join(format!("yed_sibling_{}.yaml", std::process::id()));
+        std::fs::write(&file, "a: 1\nb: 2\nc: 3\nlist: [x, &y y, z]\n").unwrap();
+        let mut model = YamlModel::load(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        let root = NodePath(Vec::new());
+        model
+            .add_mapping_child_after(
+                &root,
+                "a",
+                "a2",
+                ScalarValue::Number(ScalarNumber::Integer(5)),
+            )
+            .unwrap();
+        let keys: Vec<&str> = model
+            .root()
+            .as_hash()
+            .unwrap()
+            .keys()
+            .filter_map(Yaml::as_str)
+            .collect();
+        assert_eq!(keys, ["a", "a2", "b", "c", "list"]);
+        assert!(model
+            .add_mapping_child_after(&root, "c", "b", ScalarValue::Null)
+            .is_err());
+        assert!(model
+            .add_mapping_child_after(&root, "nope", "d", ScalarValue::Null)
+            .is_err());
+
+        let list = root.child_key("list");
+        let added = model
+            .insert_sequence_value_after(&list, 0, ScalarValue::String("w".into()))
+            .unwrap();
+        assert_eq!(added, list.child_index(1));
+        assert_eq!(
+            json_patch::json_value(&model.root()["list"]).unwrap(),
+            "[\"x\",\"w\",\"y\",\"z\"]"
+        );
+        assert_eq!(model.anchor_at(&list.child_index(2)), Some("y"));
+        assert_eq!(model.anchor_at(&list.child_index(1)), None);
+        assert!(model
+            .insert_sequence_value_after(&list, 4, ScalarValue::Null)
+            .is_err());
+    }
+
+    #[test]
+    fn sort_children_orders_keys_and_scalar_items() {
+        let file = std::env::temp_dir().join(format!("yed_sort_{}.yaml", std::process::id()));
+        std::fs::write(
+            &file,
+            "b: 1\nA: {z: 1, y: 2}\na: 3\n1: x\nlist: [10, b, 2.5, A, ~, true]\nrefs: [&p zed, *p, alpha]\n",
+        )
+        .unwrap();
+        let mut model = YamlModel::load(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        let root = NodePath(Vec::new());
+        let keys = |model: &YamlModel| -> Vec<String> {
+            model
+                .root()
+                .as_hash()
+                .unwrap()
+                .keys()
+                .map(|k| json_patch::json_value(k).unwrap())
+                .collect()
+        };
+
+        assert!(model.sort_children(&root, false).unwrap());
+        assert_eq!(
+            keys(&model),
+            ["\"A\"", "\"a\"", "\"b\"", "\"list\"", "\"refs\"", "1"]
+        );
+        assert!(!model.sort_children(&root, false).unwrap());
+        // Nested maps keep their order.
+        assert_eq!(
+            json_patch::json_value(&model.root()["A"]).unwrap(),
+            "{\"z\":1,\"y\":2}"
+        );
+        assert!(model.sort_children(&root, true).unwrap());
+        assert_eq!(keys(&model)[0], "\"refs\"");
+
+        let list = NodePath(vec![PathSegment::Key("list".into())]);
+        model.sort_children(&list, false).unwrap();
+        assert_eq!(
+            json_patch::json_value(&model.root()["list"]).unwrap(),
+            "[null,true,2.5,10,\"A\",\"b\"]"
+        );
+
+        let refs = NodePath(vec![PathSegment::Key("refs".into())]);
+        model.sort_children(&refs, false).unwrap();
+        assert_eq!(model.anchor_at(&refs.child_index(1)), Some("p"));
+        assert_eq!(
+            model.shared_node(&refs.child_index(2)).map(|s| s.anchor),
+            Some("p".to_string())
+        );
+
+        assert!(model.sort_children(&list.child_index(0), false).is_err());
+        assert!(model.sort_children(&root.child_key("A"), false).unwrap());
+        let nested = NodePath(vec![PathSegment::Key("nested".into())]);
+        model.root_mut().as_mut_hash().unwrap().insert(
+            Yaml::String("nested".into()),
+            Yaml::Array(vec![Yaml::Array(vec![])]),
+        );
+        assert!(model.sort_children(&nested, false).is_err());
+    }
+
+    #[test]
+    fn wrap_and_unwrap_round_trip() {
+        let file = std::env::temp_dir().join(format!("yed_wrap_{}.yaml", std::process::id()));
+        std::fs::write(
+            &file,
+            "host: &h web
+other: *h
+ports: [80, 443]
+",
+        )
+        .unwrap();
+        let mut model = YamlModel::load(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        let host = NodePath(vec![PathSegment::Key("host".into())]);
+
+        model.wrap_in_sequence(&host).unwrap();
+        assert_eq!(
+            json_patch::json_value(&model.root()["host"]).unwrap(),
+            "[\"web\"]"
+        );
+        assert!(model.is_flow(&host));
+        assert_eq!(model.anchor_at(&host.child_index(0)), Some("h"));
+        assert_eq!(model.anchor_at(&host), None);
+        assert!(model.wrap_in_sequence(&host).is_err());
+
+        model.unwrap_sequence(&host).unwrap();
+        assert_eq!(model.root()["host"], Yaml::String("web".into()));
+        assert!(!model.is_flow(&host));
+        assert_eq!(model.anchor_at(&host), Some("h"));
+
+        let ports = NodePath(vec![PathSegment::Key("ports".into())]);
+        assert!(model.unwrap_sequence(&ports).is_err());
+        assert!(model
+            .unwrap_sequence(&NodePath(vec![PathSegment::Key("other".into())]))
+            .is_err());
+    }
+
+    #[test]
+    fn sequence_of_maps_rekeys_and_back() {
+        let file = std::env::temp_dir().join(format!("yed_rekey_{}.yaml", std::process::id()));
+        std::fs::write(
+            &file,
+            "users:\n  - name: ann\n    role: &r admin\n  - id: 2\n    name: bob\nrole: *r\n",
+        )
+        .unwrap();
+        let mut model = YamlModel::load(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        let users = NodePath(vec![PathSegment::Key("users".into())]);
+
+        model.sequence_to_map(&users, "name").unwrap();
+        assert_eq!(
+            json_patch::json_value(&model.root()["users"]).unwrap(),
+            "{\"ann\":{\"role\":\"admin\"},\"bob\":{\"id\":2}}"
+        );
+        assert_eq!(
+            model.anchor_at(&users.child_key("ann").child_key("role")),
+            Some("r")
+        );
+
+        model.map_to_sequence(&users, "user").unwrap();
+        assert_eq!(
+            json_patch::json_value(&model.root()["users"]).unwrap(),
+            "[{\"user\":\"ann\",\"role\":\"admin\"},{\"user\":\"bob\",\"id\":2}]"
+        );
+        assert_eq!(
+            model.anchor_at(&users.child_index(0).child_key("role")),
+            Some("r")
+        );
+        assert_eq!(
+            model
+                .map_to_sequence(&NodePath(Vec::new()), "x")
+                .unwrap_err()
+                .to_string(),
+            "users is not a mapping"
+        );
+
+        let err = |model: &mut YamlModel, field: &str| {
+            model
+                .sequence_to_map(&users, field)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(err(&mut model, "id"), "Item 0 has no id");
+        model.root_mut()["users"][1]["user"] = Yaml::String("ann".into());
+        assert_eq!(err(&mut model, "user"), "Items 0 and 1 both have user: ann");
+        assert_eq!(model.root()["users"][0]["user"], Yaml::String("ann".into()));
+    }
+
+    #[test]
+    fn rename_key_everywhere_keeps_positions_and_skips_clashes() {
+        let file = std::env::temp_dir().join(format!("yed_rename_all_{}.yaml", std::process::id()));
+        std::fs::write(
+            &file,
+            "hosts:\n  - hostname: &h a\n    port: 1\n  - port: 2\n    hostname: b\n    host: c\n\
+             base: &b {hostname: {hostname: x}}\ncopy: *b\nref: *h\n",
+        )
+        .unwrap();
+        let mut model = YamlModel::load(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+
+        assert_eq!(
+            model.rename_key_everywhere("hostname", "host").unwrap(),
+            (3, 1)
+        );
+        let hosts = json_patch::json_value(&model.root()["hosts"]).unwrap();
+        assert_eq!(
+            hosts,
+            "[{\"host\":\"a\",\"port\":1},{\"port\":2,\"hostname\":\"b\",\"host\":\"c\"}]"
+        );
+        assert_eq!(
+            json_patch::json_value(&model.root()["base"]).unwrap(),
+            "{\"host\":{\"host\":\"x\"}}"
+        );
+        // The alias copy follows its anchor.
+        assert_eq!(model.root()["copy"], model.root()["base"]);
+        let first = NodePath(vec![
+            PathSegment::Key("hosts".into()),
+            PathSegment::Index(0),
+        ]);
+        assert_eq!(model.anchor_at(&first.child_key("host")), Some("h"));
+
+        assert_eq!(model.rename_key_everywhere("missing", "x").unwrap(), (0, 0));
+        assert!(model.rename_key_everywhere("host", "host").is_err());
+        assert!(model.rename_key_everywhere("host", "").is_err());
+    }
+
+    #[test]
+    fn replace_touches_strings_unless_coerced() {
+        let file = std::env::temp_dir().join(format!("yed_replace_{}.yaml", std::process::id()));
+        std::fs::write(
+            &file,
+            "url: http://staging:8080\nport: 8080\nratio: 8080.5\nshared: &s staging\nref: *s\nlist: [staging-1, true]\n",
+        )
+        .unwrap();
+        let mut model = YamlModel::load(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        let dots =
+            |targets: Vec<NodePath>| targets.iter().map(NodePath::dot_path).collect::<Vec<_>>();
+
+        let (targets, shared) = model.replace_targets("staging", false);
+        assert_eq!(dots(targets), ["url", "list.0"]);
+        assert_eq!(shared, 2);
+        let (targets, _) = model.replace_targets("8080", false);
+        assert_eq!(dots(targets), ["url"]);
+        let (targets, _) = model.replace_targets("8080", true);
+        assert_eq!(dots(targets.clone()), ["url", "port", "ratio"]);
+
+        for path in &targets {
+            model.replace_in_scalar(path, "8080", "9090").unwrap();
+        }
+        assert_eq!(
+            model.root()["url"],
+            Yaml::String("http://staging:9090".into())
+        );
+        assert_eq!(model.root()["port"], Yaml::Integer(9090));
+        assert_eq!(model.root()["ratio"], Yaml::Real("9090.5".into()));
+        let flag = NodePath(vec![PathSegment::Key("list".into()), PathSegment::Index(1)]);
+        model.replace_in_scalar(&flag, "true", "yes").unwrap();
+        assert_eq!(model.root()["list"][1], Yaml::String("yes".into()));
+    }
+
+    #[test]
+    fn document_and_subtree_export_to_json() {
+        let file = std::env::temp_dir().join(format!("yed_to_json_{}.yaml", std::process::id()));
+        std::fs::write(&file, "spec:\n  replicas: 2\n  on: yes\n  tags: [a]\n").unwrap();
+        let model = YamlModel::load(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        assert_eq!(
+            model.to_json(None).unwrap(),
+            "{\n  \"spec\": {\n    \"replicas\": 2,\n    \"on\": \"yes\",\n    \"tags\": [\n      \"a\"\n    ]\n  }\n}"
+        );
+        let tags = NodePath(vec![
+            PathSegment::Key("spec".into()),
+            PathSegment::Key("tags".into()),
+        ]);
+        assert_eq!(model.to_json(Some(&tags)).unwrap(), "[\n  \"a\"\n]");
+        assert!(model.to_json(Some(&tags.child_index(3))).is_err());
+    }
+
+    #[test]
+    fn empty_string_survives_edit_and_save() {
+        let file = std::env::temp_dir().join(format!("yed_empty_{}.yaml", std::process::id()));
+        std::fs::write(&file, "name: api\nnote: ~\n").unwrap();
+        let mut model = YamlModel::load(&file).unwrap();
+        let name = NodePath(vec![PathSegment::Key("name".into())]);
+        let value = parse_edit_input("", model.node_at(&name).unwrap()).unwrap();
+        model.edit_value(&name, value).unwrap();
+        model.save().unwrap();
+        let saved = std::fs::read_to_string(&file).unwrap();
+        let reloaded = YamlModel::load(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        assert_eq!(saved, "---\nname: \"\"\nnote: ~\n");
+        assert_eq!(
+            reloaded.node_at(&name).unwrap(),
+            &Yaml::String(String::new())
+        );
+        let note = NodePath(vec![PathSegment::Key("note".into())]);
+        assert_eq!(reloaded.node_at(&note).unwrap(), &Yaml::Null);
+    }
+
+    #[test]
+    fn integers_beyond_i64_keep_their_digits() {
+        let file = std::env::temp_dir().join(format!("yed_bigint_{}.yaml", std::process::id()));
+        std::fs::write(
+            &file,
+            "id: 18446744073709551615\nlow: -9223372036854775809\n",
+        )
+        .unwrap();
+        let mut model = YamlModel::load(&file).unwrap();
+        let id = NodePath(vec![PathSegment::Key("id".into())]);
+        let node = model.node_at(&id).unwrap();
+        assert_eq!(yaml_node_type(node), NodeType::Number);
+        assert_eq!(scalar_preview(node), "18446744073709551615");
+        let value = parse_edit_input("18446744073709551614", node).unwrap();
+        model.edit_value(&id, value).unwrap();
+        model.save().unwrap();
+        let saved = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        assert_eq!(
+            saved,
+            "---\nid: 18446744073709551614\nlow: -9223372036854775809\n"
+        );
+    }
+
+    #[test]
+    fn shared_and_broken_alias_edits_save_correctly() {
+        let file = std::env::temp_dir().join(format!("yed_alias_{}.yaml", std::process::id()));
+        std::fs::write(&file, "base: &b 1\nuse: *b\nother: *b\n").unwrap();
+        let mut model = YamlModel::load(&file).unwrap();
+        let key = |k: &str| NodePath(vec![PathSegment::Key(k.into())]);
+
+        let shared = model.shared_node(&key("use")).unwrap();
+        assert_eq!((shared.anchor.as_str(), shared.aliases), ("b", 2));
+        let target = model.shared_edit_path(&key("use"), &shared, AliasEdit::Shared);
+        assert_eq!(target, key("base"));
+        model
+            .edit_value(&target, ScalarValue::Number(ScalarNumber::Integer(5)))
+            .unwrap();
+        model.finish_shared_edit(&shared, AliasEdit::Shared);
+        assert_eq!(model.node_at(&key("other")).unwrap(), &Yaml::Integer(5));
+
+        let shared = model.shared_node(&key("other")).unwrap();
+        let target = model.shared_edit_path(&key("other"), &shared, AliasEdit::Break);
+        model
+            .edit_value(&target, ScalarValue::Number(ScalarNumber::Integer(9)))
+            .unwrap();
+        model.finish_shared_edit(&shared, AliasEdit::Break);
+        model.save().unwrap();
+        let saved = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        assert_eq!(saved, "---\nbase: &b 5\nuse: *b\nother: 9\n");
+        assert_eq!(model.shared_node(&key("other")), None);
+    }
+
+    #[test]
+    fn multiline_edit_saves_literal_block() {
+        let file = std::env::temp_dir().join(format!("yed_block_{}.yaml", std::process::id()));
+        std::fs::write(&file, "description: |\n  one\n  two\nname: api\n").unwrap();
+        let mut model = YamlModel::load(&file).unwrap();
+        let description = NodePath(vec![PathSegment::Key("description".into())]);
+        model
+            .edit_value(&description, ScalarValue::String("one\ntwo\nthree".into()))
+            .unwrap();
+        model.save().unwrap();
+        let saved = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        assert_eq!(
+            saved,
+            "---\ndescription: |-\n  one\n  two\n  three\nname: api\n"
+        );
+    }
+
+    #[test]
+    fn save_keeps_line_endings_and_final_newline() {
+        let file = std::env::temp_dir().join(format!("yed_newlines_{}.yaml", std::process::id()));
+        let key = |k: &str| NodePath(vec![PathSegment::Key(k.into())]);
+        for src in [
+            "---\r\nname: api\r\ntext: |\r\n  one\r\n  two\r\nport: 80\r\n",
+            "---\nname: api\nport: 80\n",
+            "---\nname: api\nport: 80",
+        ] {
+            std::fs::write(&file, src).unwrap();
+            let mut model = YamlModel::load(&file).unwrap();
+            model.save().unwrap();
+            assert_eq!(std::fs::read_to_string(&file).unwrap(), src);
+            model
+                .edit_value(&key("port"), ScalarValue::Number(ScalarNumber::Integer(81)))
+                .unwrap();
+            model.save().unwrap();
+            assert_eq!(
+                std::fs::read_to_string(&file).unwrap(),
+                src.replace("80", "81")
+            );
+        }
+        std::fs::remove_file(&file).ok();
+    }
+
+    #[test]
+    fn backup_is_taken_once_and_never_blocks_the_save() {
+        let dir = std::env::temp_dir().join(format!("yed_model_backup_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("values.yaml");
+        std::fs::write(&file, "a: 1\n").unwrap();
+        let mut settings = Settings::defaults(&dir);
+        settings.backup.value = true;
+        let mut model = YamlModel::load(&file).unwrap();
+        model.configure(&settings);
+        model
+            .edit_value(
+                &NodePath(vec![PathSegment::Key("a".into())]),
+                ScalarValue::Number(ScalarNumber::Integer(2)),
+            )
+            .unwrap();
+        assert_eq!(model.save().unwrap(), SaveReport::default());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("values.yaml.bak")).unwrap(),
+            "a: 1\n"
+        );
+
+        // Later saves keep the backup of the file as opened.
+        model
+            .edit_value(
+                &NodePath(vec![PathSegment::Key("a".into())]),
+                ScalarValue::Number(ScalarNumber::Integer(3)),
+            )
+            .unwrap();
+        model.save().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("values.yaml.bak")).unwrap(),
+            "a: 1\n"
+        );
+
+        // A backup dir that cannot be created only produces a warning.
+        std::fs::write(dir.join("blocked"), "").unwrap();
+        settings.backup_dir.value = Some(dir.join("blocked"));
+        settings.backup_once.value = false;
+        model.configure(&settings);
+        model
+            .edit_value(
+                &NodePath(vec![PathSegment::Key("a".into())]),
+                ScalarValue::Number(ScalarNumber::Integer(4)),
+            )
+            .unwrap();
+        assert!(model.save().unwrap().backup_error.is_some());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "---\na: 4\n");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn minimal_save_changes_only_the_edited_line() {
+        let file = std::env::temp_dir().join(format!("yed_minimal_{}.yaml", std::process::id()));
+        let src = "# deploy\nname: 'api'\n\nreplicas: 2  # scaled\nitems: [a, b]\n";
+        std::fs::write(&file, src).unwrap();
+        let mut model = YamlModel::load(&file).unwrap();
+        model.save_mode = SaveMode::Minimal;
+        let key = |k: &str| NodePath(vec![PathSegment::Key(k.into())]);
+        model
+            .edit_value(
+                &key("replicas"),
+                ScalarValue::Number(ScalarNumber::Integer(3)),
+            )
+            .unwrap();
+        assert_eq!(model.save().unwrap().fallback, None);
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "# deploy\nname: 'api'\n\nreplicas: 3  # scaled\nitems: [a, b]\n"
+        );
+        model
+            .add_mapping_child(&NodePath(Vec::new()), "extra", ScalarValue::Bool(true))
+            .unwrap();
+        assert!(model.save().unwrap().fallback.is_some());
+        let saved = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        assert_eq!(
+            saved,
+            "---\nname: 'api'\nreplicas: 3\nitems: [a, b]\nextra: true\n"
+        );
+    }
+
+    #[test]
+    fn merge_keys_show_inherited_children() {
+        let src = "base: &base\n  a: 1\n  b: 2\nsvc:\n  <<: *base\n  b: 3\n";
+        let model = YamlModel {
+            doc: YamlLoader::load_from_str(src).unwrap().remove(0),
+            path: String::new(),
+            source: SourceMap::scan(src),
+            emit_options: EmitOptions::default(),
+            identity_keys: Vec::new(),
+            loaded: None,
+            save_mode: SaveMode::Full,
+            line_endings: LineEndings::default(),
+            backup: None,
+            saved_in_session: false,
+        };
+        let tree = model.build_tree();
+        let svc = &tree.children[1];
+        let summary: Vec<(String, NodeOrigin)> = svc
+            .children
+            .iter()
+            .map(|c| (c.key.clone(), c.origin.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("<<".to_string(), NodeOrigin::MergeKey("*base".into())),
+                ("b".to_string(), NodeOrigin::Own),
+                ("a".to_string(), NodeOrigin::Inherited("*base".into())),
+            ]
+        );
+        assert!(!svc.children[0].is_container());
+        assert_eq!(svc.children[2].path.dot_path(), "svc.<<.a");
+    }
+
+    #[test]
+    fn paste_node_into_containers() {
+        let src = "list:\n  - 1\nmap:\n  a: 1\nscalar: 2\n";
+        let mut model = YamlModel {
+            doc: YamlLoader::load_from_str(src).unwrap().remove(0),
+            path: String::new(),
+            source: SourceMap::default(),
+            emit_options: EmitOptions::default(),
+            identity_keys: Vec::new(),
+            loaded: None,
+            save_mode: SaveMode::Full,
+            line_endings: LineEndings::default(),
+            backup: None,
+            saved_in_session: false,
+        };
+        let list = NodePath(vec![PathSegment::Key("list".into())]);
+        let map = NodePath(vec![PathSegment::Key("map".into())]);
+        let scalar = NodePath(vec![PathSegment::Key("scalar".into())]);
+        let value = model.node_at(&map).unwrap().clone();
+        assert_eq!(
+            model.paste_node(&list, None, &value).unwrap(),
+            list.child_index(1)
+        );
+        assert_eq!(
+            model.paste_node(&map, Some("b"), &value).unwrap(),
+            map.child_key("b")
+        );
+        assert!(model.paste_node(&map, Some("a"), &value).is_err());
+        assert!(model.paste_node(&map, None, &value).is_err());
+        assert!(model.paste_node(&scalar, None, &value).is_err());
+        // The pasted copy is independent of the original.
+        model
+            .edit_value(&map.child_key("a"), ScalarValue::Bool(true))
+            .unwrap();
+        assert_eq!(
+            model.node_at(&list.child_index(1)).unwrap()["a"],
+            Yaml::Integer(1)
+        );
+    }
+
+    #[test]
+    fn replace_subtree_drops_styles_and_refuses_anchors() {
+        let file = std::env::temp_dir().join(format!("yed_replace_{}.yaml", std::process::id()));
+        std::fs::write(&file, "base: &b {x: 1}\nuse: *b\nports: [80, 443]\n").unwrap();
+        let mut model = YamlModel::load(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        let ports = NodePath(vec![PathSegment::Key("ports".into())]);
+        model
+            .replace_subtree(
+                &ports,
+                YamlLoader::load_from_str("- 8080").unwrap().remove(0),
+            )
+            .unwrap();
+        assert_eq!(
+            model.root()["ports"],
+            Yaml::Array(vec![Yaml::Integer(8080)])
+        );
+        assert!(!model.is_flow(&ports));
+        let base = NodePath(vec![PathSegment::Key("base".into())]);
+        assert!(model.replace_subtree(&base, Yaml::Null).is_err());
+        assert_eq!(model.root()["base"]["x"], Yaml::Integer(1));
+    }
+
+    #[test]
+    fn integer_and_boolean_keys_are_addressable() {
+        let file =
+            std::env::temp_dir().join(format!("yed_scalar_keys_{}.yaml", std::process::id()));
+        std::fs::write(&file, "42: a\ntrue: b\n'7': c\n7: d\nnull: e\n").unwrap();
+        let mut model = YamlModel::load(&file).unwrap();
+        let keys: Vec<_> = model
+            .build_tree()
+            .children
+            .iter()
+            .map(|c| c.key.clone())
+            .collect();
+        assert_eq!(keys, ["42", "true", "7", "7", "null"]);
+        let key = |k: &str| NodePath(vec![PathSegment::Key(k.into())]);
+        assert_eq!(
+            model.node_at(&key("42")).unwrap(),
+            &Yaml::String("a".into())
+        );
+        // The quoted string key wins over the integer key that reads the same.
+        assert_eq!(model.node_at(&key("7")).unwrap(), &Yaml::String("c".into()));
+        model
+            .edit_value(&key("true"), ScalarValue::String("B".into()))
+            .unwrap();
+        assert_eq!(
+            model.root().as_hash().unwrap()[&Yaml::Boolean(true)],
+            Yaml::String("B".into())
+        );
+        assert!(model
+            .add_mapping_child(&NodePath(Vec::new()), "42", ScalarValue::Null)
+            .is_err());
+        model.rename_key(&key("42"), "43").unwrap();
+        assert_eq!(
+            model.root().as_hash().unwrap()[&Yaml::Integer(43)],
+            Yaml::String("a".into())
+        );
+        model.delete_node(&key("null")).unwrap();
+        model.delete_node(&key("true")).unwrap();
+        model.save().unwrap();
+        let saved = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).ok();
+        assert!(
+            !saved.contains("true") && !saved.contains("null"),
+            "{saved}"
+        );
+        assert!(saved.contains("43: a"), "{saved}");
+    }
+
+    #[test]
+    fn cast_scalar_between_types() {
+        let src = "flag: \"true\"\nport: 8080\nratio: \"2.5\"\nname: api\nmap: {a: 1}\n";
+        let mut model = YamlModel {
+            doc: YamlLoader::load_from_str(src).unwrap().remove(0),
+            path: String::new(),
+            source: SourceMap::default(),
+            emit_options: EmitOptions::default(),
+            identity_keys: Vec::new(),
+            loaded: None,
+            save_mode: SaveMode::Full,
+            line_endings: LineEndings::default(),
+            backup: None,
+            saved_in_session: false,
+        };
+        let key = |k: &str| NodePath(vec![PathSegment::Key(k.into())]);
+        model.cast_scalar(&key("flag"), NodeType::Bool).unwrap();
+        assert_eq!(model.node_at(&key("flag")).unwrap(), &Yaml::Boolean(true));
+        model.cast_scalar(&key("port"), NodeType::String).unwrap();
+        assert_eq!(
+            model.node_at(&key("port")).unwrap(),
+            &Yaml::String("8080".into())
+        );
+        model.cast_scalar(&key("ratio"), NodeType::Number).unwrap();
+        assert_eq!(
+            model.node_at(&key("ratio")).unwrap(),
+            &Yaml::Real("2.5".into())
+        );
+        assert!(model.cast_scalar(&key("name"), NodeType::Number).is_err());
+        assert!(model.cast_scalar(&key("map"), NodeType::String).is_err());
+        model.cast_scalar(&key("name"), NodeType::Null).unwrap();
+        model.cast_scalar(&key("name"), NodeType::String).unwrap();
+        assert_eq!(
+            model.node_at(&key("name")).unwrap(),
+            &Yaml::String(String::new())
+        );
     }
 }
@@ -0,0 +1,8 @@
+#!/bin/sh
+# Stub linter for the post-save hook tests: reports two findings against the file it is given.
+file="$1"
+port_line=$(grep -n 'port:' "$file" | head -n 1 | cut -d: -f1)
+echo "$file:1:1: [warning] missing document start \"---\" (document-start)"
+echo "$file:$port_line:9: [error] port must be above 1024 (policy)" >&2
+echo "2 problem(s)"
+exit 1
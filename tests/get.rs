@@ -0,0 +1,71 @@
+// Runs `yed get` against a fixture file: raw scalars, subtrees as YAML or JSON, merge keys and
+// failures.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn get(args: &[&str]) -> Output {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/get/config.yaml");
+    Command::new(env!("CARGO_BIN_EXE_yaml_master"))
+        .arg("get")
+        .arg(fixture)
+        .args(args)
+        // Keep the user's own config out of the emit settings.
+        .env("XDG_CONFIG_HOME", "/nonexistent")
+        .output()
+        .expect("run yaml_master")
+}
+
+fn stdout(args: &[&str]) -> String {
+    let output = get(args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn scalars_print_raw() {
+    assert_eq!(stdout(&["server.host"]), "api.internal\n");
+    assert_eq!(stdout(&["server.port"]), "8080\n");
+    assert_eq!(stdout(&["server.tls.enabled"]), "true\n");
+    assert_eq!(stdout(&["server.tls.ratio"]), "0.5\n");
+    assert_eq!(stdout(&["server.tls.cert"]), "null\n");
+    assert_eq!(stdout(&["server.hosts.1"]), "b\n");
+    assert_eq!(stdout(&["server.host", "--json"]), "\"api.internal\"\n");
+}
+
+#[test]
+fn subtrees_print_as_yaml_or_json() {
+    assert_eq!(
+        stdout(&["server.tls"]),
+        "enabled: true\nratio: 0.5\ncert: ~\n"
+    );
+    assert_eq!(
+        stdout(&["server.hosts", "--json"]),
+        "[\n  \"a\",\n  \"b\"\n]\n"
+    );
+}
+
+#[test]
+fn missing_paths_fail() {
+    let output = get(&["server.tls.key"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "No node at server.tls.key\n"
+    );
+}
+
+#[test]
+fn merged_keys_are_found_and_applied() {
+    assert_eq!(stdout(&["dev.host"]), "db.internal\n");
+    assert_eq!(stdout(&["dev.pool"]), "1\n");
+    assert_eq!(
+        stdout(&["dev", "--json"]),
+        "{\n  \"pool\": 1,\n  \"host\": \"db.internal\"\n}\n"
+    );
+}
@@ -0,0 +1,79 @@
+// Runs `yed roundtrip` over fixtures that either survive a save unchanged or are known to change.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn fixtures(dir: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/roundtrip")
+        .join(dir)
+}
+
+fn roundtrip(dir: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_yaml_master"))
+        .args(["roundtrip", "--format", "json"])
+        .arg(fixtures(dir))
+        // Keep the user's own config out of the emit settings.
+        .env("XDG_CONFIG_HOME", fixtures("no-user-config"))
+        .output()
+        .expect("run yaml_master")
+}
+
+/// JSON object for the fixture file `name`, up to the next file entry.
+fn file_entry<'a>(json: &'a str, name: &str) -> &'a str {
+    let start = json
+        .find(&format!("{name}\""))
+        .unwrap_or_else(|| panic!("{name} missing from report: {json}"));
+    let rest = &json[start..];
+    let body = rest.find("\"documents\"").unwrap_or(0) + 1;
+    let end = rest[body..]
+        .find("\"documents\"")
+        .map_or(rest.len(), |i| body + i);
+    &rest[..end]
+}
+
+#[test]
+fn clean_fixtures_round_trip_without_semantic_changes() {
+    let output = roundtrip("clean");
+    let json = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success(), "{json}");
+    assert!(json.contains("\"failed\":0"), "{json}");
+    let service = file_entry(&json, "service.yaml");
+    assert!(
+        service.contains("\"changed_lines\":0,\"error\":null,\"semantic\":[]"),
+        "{service}"
+    );
+    // Multi-document files are reported per document.
+    let multi = file_entry(&json, "multi.yaml");
+    assert!(
+        multi.contains("\"index\":0") && multi.contains("\"index\":1"),
+        "{multi}"
+    );
+    // Ignored by the fixture's .yed.toml; it would fail to parse.
+    assert!(!json.contains("broken.skip.yaml"), "{json}");
+}
+
+#[test]
+fn changing_fixtures_are_classified() {
+    let output = roundtrip("changes");
+    let json = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{json}");
+    assert!(json.contains("\"failed\":1"), "{json}");
+
+    // Comments are dropped but the values keep their meaning.
+    let anchors = file_entry(&json, "anchors.yaml");
+    assert!(anchors.contains("\"semantic\":[]"), "{anchors}");
+    assert!(!anchors.contains("\"changed_lines\":0"), "{anchors}");
+    // Plain YAML 1.1 booleans are strings here, and stay plain as they were written.
+    let booleans = file_entry(&json, "yaml11_booleans.yaml");
+    assert!(
+        booleans.contains("\"changed_lines\":0,\"error\":null,\"semantic\":[]"),
+        "{booleans}"
+    );
+
+    let float = file_entry(&json, "float_tag.yaml");
+    assert!(
+        float.contains("{\"path\":\"ratio\",\"kind\":\"type\",\"before\":\"1 (float)\",\"after\":\"1 (integer)\"}"),
+        "{float}"
+    );
+}
@@ -0,0 +1,83 @@
+// Runs `yed set` against scratch files: edits in place, dry runs, created keys and failures.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn scratch(name: &str, yaml: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("yed_set_{name}_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.yaml");
+    std::fs::write(&path, yaml).unwrap();
+    path
+}
+
+fn set(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_yaml_master"))
+        .arg("set")
+        .args(args)
+        // Keep the user's own config out of the emit settings.
+        .env("XDG_CONFIG_HOME", "/nonexistent")
+        .output()
+        .expect("run yaml_master")
+}
+
+#[test]
+fn sets_values_in_place_or_prints_a_dry_run() {
+    let path = scratch("edit", "server:\n  port: 80\n  hosts:\n    - a\n    - b\n");
+    let file = path.to_str().unwrap();
+
+    let output = set(&[file, "server.port", "8080"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let output = set(&[file, "server.hosts.1", "\"c\""]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "---\nserver:\n  port: 8080\n  hosts:\n    - a\n    - c\n"
+    );
+
+    let output = set(&["--dry-run", "--create", file, "server.tls.enabled", "true"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.ends_with("    - c\n  tls:\n    enabled: true\n"),
+        "{stdout}"
+    );
+    assert!(!std::fs::read_to_string(&path).unwrap().contains("tls"));
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn failures_exit_non_zero_with_a_message() {
+    let path = scratch("fail", "server:\n  port: 80\nlist: [1]\n");
+    let file = path.to_str().unwrap();
+    let failure = |args: &[&str]| {
+        let output = set(args);
+        assert!(!output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    assert!(failure(&[file, "server.tls", "1"]).contains("No node at server.tls (use --create"));
+    assert!(failure(&[file, "server", "1"]).contains("server is a mapping"));
+    assert!(failure(&["--create", file, "server.port.x", "1"]).contains("server.port is a scalar"));
+    assert!(failure(&["--create", file, "list.3", "1"]).contains("list has 1 items"));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "server:\n  port: 80\nlist: [1]\n"
+    );
+
+    std::fs::write(&path, "server: [\n").unwrap();
+    assert!(failure(&[file, "server", "1"]).contains("config.yaml: "));
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}